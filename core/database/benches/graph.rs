@@ -0,0 +1,164 @@
+//! Benchmarks for knowledge graph traversal.
+//!
+//! Seeds in-memory graphs and compares a client-side breadth-first walk (one
+//! relation query plus one entity lookup per edge, as the MCP server used to
+//! do) with [`Database::traverse`] (one query per level) and a single
+//! recursive SurrealQL idiom.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use std::collections::{HashSet, VecDeque};
+use tokio::runtime::Runtime;
+use whytcard_database::{CreateEntity, CreateRelation, Database};
+
+/// Fixture: a layered graph where every node links to `FANOUT` children.
+const FANOUT: usize = 4;
+const LAYERS: usize = 3;
+
+/// Large fixture: 10 + 100 + 1,000 + 10,000 = 11,110 edges.
+const WIDE_FANOUT: usize = 10;
+const WIDE_LAYERS: usize = 4;
+
+async fn seed_graph() -> (Database, String) {
+    let db = Database::new_memory().await.expect("database");
+
+    let root = db
+        .create_entity(CreateEntity::new("node-0", "bench"))
+        .await
+        .expect("root");
+    let root_key = root.id.as_ref().expect("id").key().to_string();
+
+    let mut frontier = vec![root];
+    let mut next_name = 1;
+    for _ in 0..LAYERS {
+        let mut next = Vec::new();
+        for parent in &frontier {
+            for _ in 0..FANOUT {
+                let child = db
+                    .create_entity(CreateEntity::new(format!("node-{}", next_name), "bench"))
+                    .await
+                    .expect("child");
+                next_name += 1;
+                db.create_relation(CreateRelation::new(
+                    parent.id.clone().expect("id"),
+                    child.id.clone().expect("id"),
+                    "links",
+                ))
+                .await
+                .expect("relation");
+                next.push(child);
+            }
+        }
+        frontier = next;
+    }
+
+    (db, root_key)
+}
+
+/// Seed a `fanout`-ary graph of `layers` levels in one transaction per level.
+async fn seed_wide_graph(fanout: usize, layers: usize) -> (Database, String) {
+    let db = Database::new_memory().await.expect("database");
+
+    let root = db
+        .create_entity(CreateEntity::new("node-0", "bench"))
+        .await
+        .expect("root")
+        .id
+        .expect("id");
+    let root_key = root.key().to_string();
+
+    let mut frontier = vec![root];
+    let mut next_name = 1;
+    for _ in 0..layers {
+        let mut tx = db.transaction();
+        let mut next = Vec::with_capacity(frontier.len() * fanout);
+        for parent in &frontier {
+            for _ in 0..fanout {
+                let child = tx.create_entity(CreateEntity::new(format!("node-{}", next_name), "bench"));
+                next_name += 1;
+                tx.create_relation(CreateRelation::new(parent.clone(), child.clone(), "links"));
+                next.push(child);
+            }
+        }
+        tx.commit().await.expect("level");
+        frontier = next;
+    }
+
+    (db, root_key)
+}
+
+/// Entities reachable in up to `max_depth` outgoing hops, in one recursive query.
+///
+/// The fixtures are trees; on graphs with cycles the recursion revisits
+/// entities at every step, which is why `traverse` walks level by level.
+async fn recursive(db: &Database, start: &str, max_depth: usize) -> usize {
+    let query = format!(
+        "RETURN type::thing('entity', $id).{{1..{}+collect}}(->relates_to->entity)",
+        max_depth
+    );
+    let mut result = db
+        .inner()
+        .query(query)
+        .bind(("id", start.to_string()))
+        .await
+        .expect("recursive query");
+    let ids: Vec<surrealdb::RecordId> = result.take(0).expect("recursive ids");
+    ids.len() + 1
+}
+
+/// Breadth-first walk up to `max_depth` hops, returning visited entity count.
+async fn bfs(db: &Database, start: &str, max_depth: usize) -> usize {
+    let mut visited = HashSet::new();
+    visited.insert(start.to_string());
+    let mut queue = VecDeque::from([(start.to_string(), 0usize)]);
+
+    while let Some((current, depth)) = queue.pop_front() {
+        if depth >= max_depth {
+            continue;
+        }
+        for rel in db.get_outgoing_relations(&current).await.expect("relations") {
+            let to = rel.to.key().to_string();
+            if visited.insert(to.clone()) {
+                db.get_entity(&to).await.expect("entity");
+                queue.push_back((to, depth + 1));
+            }
+        }
+    }
+
+    visited.len()
+}
+
+fn bench_bfs(c: &mut Criterion) {
+    let rt = Runtime::new().expect("runtime");
+    let (db, root) = rt.block_on(seed_graph());
+
+    let mut group = c.benchmark_group("graph");
+    for depth in 1..=LAYERS {
+        group.bench_with_input(BenchmarkId::new("bfs_neighbors", depth), &depth, |b, &depth| {
+            b.iter(|| rt.block_on(bfs(&db, &root, depth)))
+        });
+    }
+    group.finish();
+}
+
+fn bench_traverse(c: &mut Criterion) {
+    let rt = Runtime::new().expect("runtime");
+    let (db, root) = rt.block_on(seed_wide_graph(WIDE_FANOUT, WIDE_LAYERS));
+
+    let mut group = c.benchmark_group("graph_10k_edges");
+    group.sample_size(10);
+    for depth in 1..=WIDE_LAYERS {
+        group.bench_with_input(BenchmarkId::new("bfs_neighbors", depth), &depth, |b, &depth| {
+            b.iter(|| rt.block_on(bfs(&db, &root, depth)))
+        });
+        group.bench_with_input(BenchmarkId::new("traverse", depth), &depth, |b, &depth| {
+            b.iter(|| rt.block_on(db.traverse(&root, depth, &[])).expect("traverse").len())
+        });
+        group.bench_with_input(BenchmarkId::new("recursive", depth), &depth, |b, &depth| {
+            b.iter(|| rt.block_on(recursive(&db, &root, depth)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_bfs, bench_traverse);
+criterion_main!(benches);
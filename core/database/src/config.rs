@@ -19,13 +19,41 @@ pub struct Config {
 }
 
 /// Storage mode
-#[derive(Debug, Clone)]
+#[derive(Clone)]
 pub enum StorageMode {
     /// In-memory storage (data lost on restart)
     Memory,
 
     /// Persistent storage using RocksDB
     Persistent(PathBuf),
+
+    /// Remote SurrealDB server, shared by several processes
+    Remote {
+        /// Server address (`ws://host:8000` or `wss://host`)
+        url: String,
+
+        /// Root user to sign in as (no sign-in when `None`)
+        username: Option<String>,
+
+        /// Password of `username`
+        password: Option<String>,
+    },
+}
+
+impl std::fmt::Debug for StorageMode {
+    // Keeps the password out of logs
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Memory => f.write_str("Memory"),
+            Self::Persistent(path) => f.debug_tuple("Persistent").field(path).finish(),
+            Self::Remote { url, username, password } => f
+                .debug_struct("Remote")
+                .field("url", url)
+                .field("username", username)
+                .field("password", &password.as_ref().map(|_| "***"))
+                .finish(),
+        }
+    }
 }
 
 /// Vector index configuration
@@ -36,6 +64,27 @@ pub struct VectorConfig {
 
     /// Distance metric
     pub distance: DistanceMetric,
+
+    /// Whether stored embeddings are unit length
+    ///
+    /// Lets searches rank by another metric than the index's (see
+    /// [`VectorConfig::serves`]).
+    pub normalized: bool,
+
+    /// HNSW links per node (default 12)
+    ///
+    /// More links raise recall at the cost of memory and insert time;
+    /// 8 - 16 suits most embedding sizes, 32 - 48 very high recall targets.
+    /// Only applies when the index is created: change it on an existing
+    /// database with [`crate::Database::redefine_vector_index`].
+    pub m: usize,
+
+    /// HNSW candidate list size while building the graph (default 150)
+    ///
+    /// Larger values build a better connected graph (higher recall at a
+    /// given search `ef`) but slow inserts down. Like `m`, only applies when
+    /// the index is created.
+    pub ef_construction: usize,
 }
 
 /// Distance metric for vector similarity
@@ -63,6 +112,65 @@ impl DistanceMetric {
     }
 }
 
+/// Ranking function of a single vector search
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchMetric {
+    /// Cosine distance
+    Cosine,
+
+    /// Dot product, reported as distance `1 - dot`
+    Dot,
+
+    /// Euclidean distance
+    Euclidean,
+
+    /// Manhattan distance
+    Manhattan,
+}
+
+impl SearchMetric {
+    /// Name used in error messages
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Cosine => "cosine",
+            Self::Dot => "dot",
+            Self::Euclidean => "euclidean",
+            Self::Manhattan => "manhattan",
+        }
+    }
+
+    /// Similarity in which higher is closer, from this metric's distance
+    ///
+    /// `1 - distance` for cosine and dot product; `1 / (1 + distance)` for
+    /// the unbounded euclidean and manhattan distances.
+    pub fn similarity(&self, distance: f32) -> f32 {
+        match self {
+            Self::Cosine | Self::Dot => 1.0 - distance,
+            Self::Euclidean | Self::Manhattan => 1.0 / (1.0 + distance),
+        }
+    }
+
+    /// Distance function of the KNN operator, `None` for the dot product
+    pub(crate) fn knn_operator(&self) -> Option<&'static str> {
+        match self {
+            Self::Cosine => Some("COSINE"),
+            Self::Dot => None,
+            Self::Euclidean => Some("EUCLIDEAN"),
+            Self::Manhattan => Some("MANHATTAN"),
+        }
+    }
+}
+
+impl From<DistanceMetric> for SearchMetric {
+    fn from(metric: DistanceMetric) -> Self {
+        match metric {
+            DistanceMetric::Cosine => Self::Cosine,
+            DistanceMetric::Euclidean => Self::Euclidean,
+            DistanceMetric::Manhattan => Self::Manhattan,
+        }
+    }
+}
+
 impl Default for Config {
     fn default() -> Self {
         Self {
@@ -79,10 +187,31 @@ impl Default for VectorConfig {
         Self {
             dimension: 384, // all-MiniLM-L6-v2
             distance: DistanceMetric::Cosine,
+            normalized: false,
+            m: 12,
+            ef_construction: 150,
         }
     }
 }
 
+impl VectorConfig {
+    /// Whether searches ranked by `metric` are valid on this index
+    ///
+    /// The index metric always is. On unit-length vectors cosine, dot product
+    /// and euclidean distance rank identically, so a cosine or euclidean
+    /// index of normalized vectors also serves the other two.
+    pub fn serves(&self, metric: SearchMetric) -> bool {
+        let index_metric = SearchMetric::from(self.distance);
+        if metric == index_metric {
+            return true;
+        }
+        let interchangeable = |m: SearchMetric| {
+            matches!(m, SearchMetric::Cosine | SearchMetric::Dot | SearchMetric::Euclidean)
+        };
+        self.normalized && interchangeable(index_metric) && interchangeable(metric)
+    }
+}
+
 impl Config {
     /// Create a new in-memory configuration
     pub fn memory() -> Self {
@@ -97,6 +226,29 @@ impl Config {
         }
     }
 
+    /// Create a configuration connecting to a remote SurrealDB server
+    pub fn remote(url: impl Into<String>) -> Self {
+        Self {
+            storage: StorageMode::Remote {
+                url: url.into(),
+                username: None,
+                password: None,
+            },
+            ..Default::default()
+        }
+    }
+
+    /// Sign in to a remote server as this root user
+    ///
+    /// Has no effect on embedded storage.
+    pub fn with_credentials(mut self, username: impl Into<String>, password: impl Into<String>) -> Self {
+        if let StorageMode::Remote { username: u, password: p, .. } = &mut self.storage {
+            *u = Some(username.into());
+            *p = Some(password.into());
+        }
+        self
+    }
+
     /// Set the namespace
     pub fn with_namespace(mut self, namespace: impl Into<String>) -> Self {
         self.namespace = namespace.into();
@@ -120,4 +272,11 @@ impl Config {
         self.vector_config.distance = distance;
         self
     }
+
+    /// Set the HNSW graph parameters (links per node, build candidate list)
+    pub fn with_hnsw(mut self, m: usize, ef_construction: usize) -> Self {
+        self.vector_config.m = m;
+        self.vector_config.ef_construction = ef_construction;
+        self
+    }
 }
@@ -1,16 +1,25 @@
 //! Main database connection and operations
 
 use crate::config::StorageMode;
-use crate::{Config, Result, Schema};
-use surrealdb::engine::local::Db;
+use crate::{Config, DatabaseError, Result, Schema};
+use surrealdb::engine::any::{self, Any};
+use surrealdb::opt::auth::Root;
 use surrealdb::Surreal;
+use std::path::Path;
 use std::sync::Arc;
+use std::time::Duration;
+
+/// How long `close` waits for a persistent store to release its files
+const CLOSE_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Delay between checks while waiting for the store to be released
+const CLOSE_POLL_INTERVAL: Duration = Duration::from_millis(10);
 
 /// WhytCard database instance
 #[derive(Clone)]
 pub struct Database {
     /// Inner SurrealDB connection
-    inner: Arc<Surreal<Db>>,
+    inner: Arc<Surreal<Any>>,
 
     /// Configuration
     config: Arc<Config>,
@@ -30,9 +39,9 @@ impl Database {
     /// Create a new database with custom configuration
     pub async fn new(config: Config) -> Result<Self> {
         // Connect based on storage mode
-        let db: Surreal<Db> = match &config.storage {
+        let db: Surreal<Any> = match &config.storage {
             StorageMode::Memory => {
-                let db = Surreal::new::<surrealdb::engine::local::Mem>(()).await?;
+                let db = any::connect("mem://").await?;
                 tracing::info!("Connected to in-memory database");
                 db
             }
@@ -41,14 +50,28 @@ impl Database {
                 if let Some(parent) = path.parent() {
                     std::fs::create_dir_all(parent).ok();
                 }
-                let db = Surreal::new::<surrealdb::engine::local::RocksDb>(path.to_string_lossy().as_ref()).await?;
+                let db = any::connect(format!("rocksdb://{}", path.display())).await?;
                 tracing::info!("Connected to persistent database at {:?}", path);
                 db
             }
+            StorageMode::Remote { url, username, password } => {
+                let db = Self::connect_remote(url, username.as_deref(), password.as_deref()).await?;
+                tracing::info!("Connected to remote database at {}", url);
+                db
+            }
         };
 
         // Select namespace and database
-        db.use_ns(&config.namespace).use_db(&config.database).await?;
+        db.use_ns(&config.namespace)
+            .use_db(&config.database)
+            .await
+            .map_err(|e| match &config.storage {
+                StorageMode::Remote { url, .. } => DatabaseError::Connection(format!(
+                    "Failed to select {}/{} on {url}: {e}",
+                    config.namespace, config.database
+                )),
+                _ => e.into(),
+            })?;
 
         // Initialize schema before wrapping in Arc
         Schema::init(&db, &config).await?;
@@ -58,11 +81,48 @@ impl Database {
             config: Arc::new(config),
         };
 
+        // DEFINE INDEX keeps an existing index as it was built
+        let vector_config = &database.config.vector_config;
+        if let Some((m, ef_construction)) = database.vector_index_params().await? {
+            if (m, ef_construction) != (vector_config.m, vector_config.ef_construction) {
+                tracing::warn!(
+                    "Vector index was built with m={} efc={}, configured m={} efc={}; \
+                     call redefine_vector_index to rebuild it with the new parameters",
+                    m,
+                    ef_construction,
+                    vector_config.m,
+                    vector_config.ef_construction
+                );
+            }
+        }
+
         Ok(database)
     }
 
+    /// Connect to a remote server, signing in as root when credentials are given
+    async fn connect_remote(url: &str, username: Option<&str>, password: Option<&str>) -> Result<Surreal<Any>> {
+        if !(url.starts_with("ws://") || url.starts_with("wss://")) {
+            return Err(DatabaseError::Connection(format!(
+                "Unsupported remote address {url} (expected ws:// or wss://)"
+            )));
+        }
+        let db = any::connect(url)
+            .await
+            .map_err(|e| DatabaseError::Connection(format!("Failed to connect to {url}: {e}")))?;
+
+        if let Some(username) = username {
+            db.signin(Root {
+                username,
+                password: password.unwrap_or_default(),
+            })
+            .await
+            .map_err(|e| DatabaseError::Connection(format!("Failed to sign in to {url} as {username}: {e}")))?;
+        }
+        Ok(db)
+    }
+
     /// Get the inner SurrealDB connection
-    pub fn inner(&self) -> &Surreal<Db> {
+    pub fn inner(&self) -> &Surreal<Any> {
         &self.inner
     }
 
@@ -81,6 +141,80 @@ impl Database {
         let result: Option<i32> = self.inner.query("RETURN 1").await?.take(0)?;
         Ok(result == Some(1))
     }
+
+    /// Close the connection and wait until a persistent store has released its files
+    ///
+    /// SurrealDB shuts its embedded engine down in the background once the last
+    /// handle is dropped, so the store directory is polled until no file in it
+    /// is held open. Fails without closing anything when other clones of this
+    /// handle are still alive.
+    pub async fn close(self) -> Result<()> {
+        let handles = Arc::strong_count(&self.inner);
+        if handles > 1 {
+            return Err(DatabaseError::Connection(format!(
+                "Cannot close database: {} other handles are still open",
+                handles - 1
+            )));
+        }
+
+        let path = match &self.config.storage {
+            StorageMode::Persistent(path) => Some(path.canonicalize()?),
+            _ => None,
+        };
+        drop(self);
+
+        let Some(path) = path else {
+            return Ok(());
+        };
+        let deadline = tokio::time::Instant::now() + CLOSE_TIMEOUT;
+        while !storage_released(&path)? {
+            if tokio::time::Instant::now() >= deadline {
+                return Err(DatabaseError::Connection(format!(
+                    "Database at {:?} was not released within {:?}",
+                    path, CLOSE_TIMEOUT
+                )));
+            }
+            tokio::time::sleep(CLOSE_POLL_INTERVAL).await;
+        }
+        tracing::info!("Closed persistent database at {:?}", path);
+        Ok(())
+    }
+}
+
+/// Whether this process no longer holds any file of the store at `path` open
+#[cfg(target_os = "linux")]
+fn storage_released(path: &Path) -> Result<bool> {
+    for entry in std::fs::read_dir("/proc/self/fd")? {
+        // Descriptors closed while iterating have no link left to read
+        if let Ok(target) = std::fs::read_link(entry?.path()) {
+            if target.starts_with(path) {
+                return Ok(false);
+            }
+        }
+    }
+    Ok(true)
+}
+
+/// Whether this process no longer holds any file of the store at `path` open
+#[cfg(windows)]
+fn storage_released(path: &Path) -> Result<bool> {
+    // RocksDB opens its LOCK file without sharing until the store is closed
+    const ERROR_SHARING_VIOLATION: i32 = 32;
+    match std::fs::OpenOptions::new().write(true).open(path.join("LOCK")) {
+        Ok(_) => Ok(true),
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(true),
+        Err(e) if e.raw_os_error() == Some(ERROR_SHARING_VIOLATION) => Ok(false),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Whether this process no longer holds any file of the store at `path` open
+#[cfg(not(any(target_os = "linux", windows)))]
+fn storage_released(path: &Path) -> Result<bool> {
+    Err(DatabaseError::Io(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        format!("Cannot observe when {:?} is released on this platform", path),
+    )))
 }
 
 #[cfg(test)]
@@ -106,4 +240,45 @@ mod tests {
         assert_eq!(db.config().database, "testdb");
         assert_eq!(db.config().vector_config.dimension, 768);
     }
+
+    #[tokio::test]
+    async fn test_remote_connection_errors() {
+        // A port nothing listens on
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        drop(listener);
+
+        let config = Config::remote(format!("ws://127.0.0.1:{port}")).with_credentials("root", "secret");
+        let err = Database::new(config).await.err().unwrap();
+        assert!(matches!(err, DatabaseError::Connection(_)), "{err}");
+
+        let err = Database::new(Config::remote("http://127.0.0.1:8000")).await.err().unwrap();
+        assert!(err.to_string().contains("expected ws:// or wss://"), "{err}");
+    }
+
+    #[tokio::test]
+    async fn test_close_releases_persistent_store() {
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("store");
+        let db = Database::new_persistent(&path).await.unwrap();
+        let other = db.clone();
+        assert!(matches!(db.close().await, Err(DatabaseError::Connection(_))));
+
+        other.close().await.unwrap();
+        // The store can be moved and reopened at once
+        let moved = temp.path().join("moved");
+        std::fs::rename(&path, &moved).unwrap();
+        let db = Database::new_persistent(&moved).await.unwrap();
+        assert!(db.health().await.unwrap());
+
+        Database::new_memory().await.unwrap().close().await.unwrap();
+    }
+
+    #[test]
+    fn test_remote_debug_hides_password() {
+        let config = Config::remote("wss://db.example.com").with_credentials("root", "secret");
+        let debug = format!("{:?}", config.storage);
+        assert!(debug.contains("wss://db.example.com"));
+        assert!(!debug.contains("secret"));
+    }
 }
@@ -4,6 +4,7 @@ use crate::{Database, DatabaseError, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use surrealdb::RecordId;
+use tokio::io::{AsyncBufRead, AsyncBufReadExt, AsyncWrite, AsyncWriteExt};
 
 /// Document record
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -38,6 +39,22 @@ pub struct Document {
     /// Last update timestamp
     #[serde(skip_serializing_if = "Option::is_none")]
     pub updated_at: Option<DateTime<Utc>>,
+
+    /// Soft-deletion timestamp (set while the document is in the trash)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub deleted_at: Option<DateTime<Utc>>,
+
+    /// BM25 relevance, set by [`Database::search_documents_text`]
+    /// (unbounded, higher is better)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f32>,
+}
+
+impl Document {
+    /// Whether the document has been soft-deleted
+    pub fn is_deleted(&self) -> bool {
+        self.deleted_at.is_some()
+    }
 }
 
 /// Input for creating a document
@@ -106,10 +123,142 @@ impl CreateDocument {
     }
 }
 
+/// Partial update of a document
+///
+/// Only the fields that are set are written; `None` leaves a field unchanged.
+#[derive(Debug, Clone, Default)]
+pub struct UpdateDocument {
+    /// New content
+    pub content: Option<String>,
+
+    /// New title
+    pub title: Option<String>,
+
+    /// New tags, replacing the current ones
+    pub tags: Option<Vec<String>>,
+
+    /// New metadata, replacing the current object
+    pub metadata: Option<serde_json::Value>,
+}
+
+impl UpdateDocument {
+    /// Create an update that changes nothing
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the content
+    pub fn with_content(mut self, content: impl Into<String>) -> Self {
+        self.content = Some(content.into());
+        self
+    }
+
+    /// Set the title
+    pub fn with_title(mut self, title: impl Into<String>) -> Self {
+        self.title = Some(title.into());
+        self
+    }
+
+    /// Set the tags
+    pub fn with_tags(mut self, tags: Vec<String>) -> Self {
+        self.tags = Some(tags);
+        self
+    }
+
+    /// Set the metadata
+    pub fn with_metadata(mut self, metadata: serde_json::Value) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+}
+
+/// Portable document representation used for JSONL export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DocumentRecord {
+    /// Optional unique key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub key: Option<String>,
+
+    /// Document content
+    pub content: String,
+
+    /// Optional title
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+
+    /// Tags for filtering
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    /// Additional metadata
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<serde_json::Value>,
+
+    /// Creation timestamp
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub created_at: Option<DateTime<Utc>>,
+
+    /// Last update timestamp
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl From<Document> for DocumentRecord {
+    fn from(doc: Document) -> Self {
+        Self {
+            key: doc.key,
+            content: doc.content,
+            title: doc.title,
+            tags: doc.tags,
+            metadata: doc.metadata,
+            created_at: doc.created_at,
+            updated_at: doc.updated_at,
+        }
+    }
+}
+
+/// Outcome of a JSONL import
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportSummary {
+    /// Documents created
+    pub created: usize,
+
+    /// Existing documents updated (matched by key)
+    pub updated: usize,
+
+    /// Keys of all imported keyed documents, in file order
+    pub keys: Vec<String>,
+
+    /// Per-line errors (`line N: reason`)
+    pub errors: Vec<String>,
+}
+
+/// Number of documents fetched per page when exporting
+const EXPORT_PAGE_SIZE: usize = 500;
+
+/// Consecutive read failures after which an import gives up on its reader
+const MAX_IMPORT_READ_ERRORS: usize = 3;
+
+/// Drops the soft-deleted document holding `$key`, which would otherwise
+/// keep the key taken in the unique index
+const PURGE_TRASHED_KEY_QUERY: &str =
+    "DELETE FROM document WHERE key = $key AND deleted_at != NONE";
+
 /// Document operations
 impl Database {
     /// Create a new document
+    ///
+    /// A soft-deleted document with the same key is purged first, so a deleted
+    /// key can be stored again.
     pub async fn create_document(&self, input: CreateDocument) -> Result<Document> {
+        if let Some(key) = &input.key {
+            self.inner()
+                .query(PURGE_TRASHED_KEY_QUERY)
+                .bind(("key", key.clone()))
+                .await?
+                .check()?;
+        }
+
         let doc: Option<Document> = self.inner().create("document").content(input).await?;
         doc.ok_or_else(|| DatabaseError::Schema("Failed to create document".into()))
     }
@@ -133,8 +282,8 @@ impl Database {
         Ok(docs.into_iter().next())
     }
 
-    /// Update a document
-    pub async fn update_document(&self, id: &str, input: CreateDocument) -> Result<Document> {
+    /// Replace every field of a document by ID
+    pub async fn replace_document(&self, id: &str, input: CreateDocument) -> Result<Document> {
         let doc: Option<Document> = self
             .inner()
             .update(("document", id))
@@ -153,6 +302,53 @@ impl Database {
         })
     }
 
+    /// Update the fields set in `update` on the live document with this key
+    ///
+    /// Other fields keep their values; `updated_at` is bumped. Fails with
+    /// [`DatabaseError::NotFound`] if no live document has the key.
+    pub async fn update_document(&self, key: &str, update: UpdateDocument) -> Result<Document> {
+        let mut assignments = vec!["updated_at = time::now()"];
+        if update.content.is_some() {
+            assignments.push("content = $content");
+        }
+        if update.title.is_some() {
+            assignments.push("title = $title");
+        }
+        if update.tags.is_some() {
+            assignments.push("tags = $tags");
+        }
+        if update.metadata.is_some() {
+            assignments.push("metadata = $metadata");
+        }
+
+        let mut query = self
+            .inner()
+            .query(format!(
+                "UPDATE document SET {} WHERE key = $key AND deleted_at = NONE RETURN AFTER",
+                assignments.join(", ")
+            ))
+            .bind(("key", key.to_string()));
+        if let Some(content) = update.content {
+            query = query.bind(("content", content));
+        }
+        if let Some(title) = update.title {
+            query = query.bind(("title", title));
+        }
+        if let Some(tags) = update.tags {
+            query = query.bind(("tags", tags));
+        }
+        if let Some(metadata) = update.metadata {
+            query = query.bind(("metadata", metadata));
+        }
+
+        let mut result = query.await?.check()?;
+        let docs: Vec<Document> = result.take(0)?;
+        docs.into_iter().next().ok_or_else(|| DatabaseError::NotFound {
+            table: "document".into(),
+            id: key.into(),
+        })
+    }
+
     /// Delete a document
     pub async fn delete_document(&self, id: &str) -> Result<bool> {
         let doc: Option<Document> = self.inner().delete(("document", id)).await?;
@@ -172,7 +368,51 @@ impl Database {
         Ok(!docs.is_empty())
     }
 
-    /// List documents with optional tag filter
+    /// Soft-delete a document by key
+    ///
+    /// The document is kept but excluded from listings and counts until it is
+    /// restored, purged, or replaced by a new document with the same key.
+    /// Returns `false` if no live document has this key.
+    pub async fn soft_delete_document_by_key(&self, key: &str) -> Result<bool> {
+        let key_owned = key.to_string();
+        let mut result = self
+            .inner()
+            .query("UPDATE document SET deleted_at = time::now() WHERE key = $key AND deleted_at = NONE RETURN AFTER")
+            .bind(("key", key_owned))
+            .await?;
+
+        let docs: Vec<Document> = result.take(0)?;
+        Ok(!docs.is_empty())
+    }
+
+    /// Restore a soft-deleted document by key
+    ///
+    /// Returns the restored document, or `None` if no soft-deleted document has this key.
+    pub async fn restore_document_by_key(&self, key: &str) -> Result<Option<Document>> {
+        let key_owned = key.to_string();
+        let mut result = self
+            .inner()
+            .query("UPDATE document SET deleted_at = NONE, updated_at = time::now() WHERE key = $key AND deleted_at != NONE RETURN AFTER")
+            .bind(("key", key_owned))
+            .await?;
+
+        let docs: Vec<Document> = result.take(0)?;
+        Ok(docs.into_iter().next())
+    }
+
+    /// Permanently remove documents soft-deleted more than `retention_days` ago
+    pub async fn purge_deleted_documents(&self, retention_days: i64) -> Result<usize> {
+        let query = format!(
+            "DELETE FROM document WHERE deleted_at != NONE AND deleted_at < time::now() - {}d RETURN BEFORE",
+            retention_days.max(0)
+        );
+
+        let mut result = self.inner().query(&query).await?;
+        let docs: Vec<Document> = result.take(0)?;
+        Ok(docs.len())
+    }
+
+    /// List documents with optional tag filter (soft-deleted documents are excluded)
     pub async fn list_documents(
         &self,
         tags: Option<&[String]>,
@@ -183,12 +423,12 @@ impl Database {
             Some(tags) if !tags.is_empty() => {
                 let tags_json = serde_json::to_string(tags)?;
                 format!(
-                    "SELECT * FROM document WHERE tags CONTAINSANY {} ORDER BY created_at DESC LIMIT {} START {}",
+                    "SELECT * FROM document WHERE deleted_at = NONE AND tags CONTAINSANY {} ORDER BY created_at DESC LIMIT {} START {}",
                     tags_json, limit, offset
                 )
             }
             _ => format!(
-                "SELECT * FROM document ORDER BY created_at DESC LIMIT {} START {}",
+                "SELECT * FROM document WHERE deleted_at = NONE ORDER BY created_at DESC LIMIT {} START {}",
                 limit, offset
             ),
         };
@@ -198,13 +438,252 @@ impl Database {
         Ok(docs)
     }
 
-    /// Count documents
-    pub async fn count_documents(&self) -> Result<usize> {
+    /// List live documents whose key starts with `prefix`, ordered by key
+    pub async fn list_documents_by_key_prefix(&self, prefix: &str) -> Result<Vec<Document>> {
+        let prefix_owned = prefix.to_string();
         let mut result = self
             .inner()
-            .query("SELECT count() FROM document GROUP ALL")
+            .query("SELECT * FROM document WHERE deleted_at = NONE AND key != NONE AND string::starts_with(key, $prefix) ORDER BY key")
+            .bind(("prefix", prefix_owned))
             .await?;
 
+        let docs: Vec<Document> = result.take(0)?;
+        Ok(docs)
+    }
+
+    /// Full-text search over live documents' content
+    ///
+    /// Ranks documents containing the query's terms by BM25 relevance, best
+    /// first, with the relevance in [`Document::score`]. Only documents
+    /// carrying every tag in `tags` are ranked, so `limit` applies after the
+    /// tag filter.
+    pub async fn search_documents_text(
+        &self,
+        query: &str,
+        tags: &[String],
+        limit: usize,
+    ) -> Result<Vec<Document>> {
+        let mut result = self
+            .inner()
+            .query(
+                r#"
+                SELECT *, search::score(1) AS score
+                FROM document
+                WHERE content @1@ $query AND deleted_at = NONE AND tags CONTAINSALL $tags
+                ORDER BY score DESC
+                LIMIT $limit
+                "#,
+            )
+            .bind(("query", query.to_string()))
+            .bind(("tags", tags.to_vec()))
+            .bind(("limit", limit))
+            .await?
+            .check()?;
+
+        let docs: Vec<Document> = result.take(0)?;
+        Ok(docs)
+    }
+
+    /// Export documents as JSONL, one [`DocumentRecord`] per line
+    ///
+    /// Documents are fetched page by page, newest first, and written as they
+    /// arrive, so the full set is never held in memory. Each page resumes after
+    /// the last document written (by creation time, then ID), so documents
+    /// created during the export neither shift nor repeat earlier pages.
+    /// Returns the number of documents written.
+    pub async fn export_documents<W: AsyncWrite + Unpin>(
+        &self,
+        writer: &mut W,
+        tags: Option<&[String]>,
+    ) -> Result<usize> {
+        self.export_documents_paged(writer, tags, EXPORT_PAGE_SIZE).await
+    }
+
+    async fn export_documents_paged<W: AsyncWrite + Unpin>(
+        &self,
+        writer: &mut W,
+        tags: Option<&[String]>,
+        page_size: usize,
+    ) -> Result<usize> {
+        let tags = tags.unwrap_or_default().to_vec();
+        let tag_filter = if tags.is_empty() { "" } else { " AND tags CONTAINSANY $tags" };
+        let mut exported = 0;
+        // Creation time and ID of the last document written
+        let mut cursor: Option<(DateTime<Utc>, RecordId)> = None;
+
+        loop {
+            let after = if cursor.is_some() {
+                " AND (created_at < <datetime> $created_at OR (created_at = <datetime> $created_at AND id < $id))"
+            } else {
+                ""
+            };
+            let query = format!(
+                "SELECT * FROM document WHERE deleted_at = NONE{}{} ORDER BY created_at DESC, id DESC LIMIT $limit",
+                tag_filter, after
+            );
+            let mut request = self
+                .inner()
+                .query(query)
+                .bind(("tags", tags.clone()))
+                .bind(("limit", page_size));
+            if let Some((created_at, id)) = &cursor {
+                request = request
+                    .bind(("created_at", created_at.to_rfc3339()))
+                    .bind(("id", id.clone()));
+            }
+            let page: Vec<Document> = request.await?.take(0)?;
+            let page_len = page.len();
+            cursor = page.last().and_then(|doc| Some((doc.created_at?, doc.id.clone()?)));
+
+            for doc in page {
+                let mut line = serde_json::to_vec(&DocumentRecord::from(doc))?;
+                line.push(b'\n');
+                writer.write_all(&line).await?;
+                exported += 1;
+            }
+
+            if page_len < page_size || cursor.is_none() {
+                break;
+            }
+        }
+
+        writer.flush().await?;
+        Ok(exported)
+    }
+
+    /// Import documents from JSONL, one [`DocumentRecord`] per line
+    ///
+    /// Records with a key are upserted: an existing document with the same key
+    /// is updated in place, otherwise a new one is created. A soft-deleted
+    /// document with that key is restored, as the imported record is live.
+    /// Records without a key are always created. Malformed or non-UTF-8 lines,
+    /// read failures and failed writes are reported in the summary rather than
+    /// aborting the import; the reader is given up on after
+    /// `MAX_IMPORT_READ_ERRORS` consecutive read failures.
+    pub async fn import_documents<R: AsyncBufRead + Unpin>(&self, mut reader: R) -> Result<ImportSummary> {
+        let mut summary = ImportSummary::default();
+        let mut buf = Vec::new();
+        let mut line_number = 0;
+        let mut read_errors = 0;
+
+        loop {
+            buf.clear();
+            line_number += 1;
+            match reader.read_until(b'\n', &mut buf).await {
+                Ok(0) => break,
+                Ok(_) => read_errors = 0,
+                Err(e) => {
+                    summary.errors.push(format!("line {}: {}", line_number, e));
+                    read_errors += 1;
+                    if read_errors >= MAX_IMPORT_READ_ERRORS {
+                        summary
+                            .errors
+                            .push(format!("stopped after {} consecutive read errors", read_errors));
+                        break;
+                    }
+                    continue;
+                }
+            }
+
+            let line = match std::str::from_utf8(&buf) {
+                Ok(line) => line,
+                Err(e) => {
+                    summary.errors.push(format!("line {}: {}", line_number, e));
+                    continue;
+                }
+            };
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let record: DocumentRecord = match serde_json::from_str(line) {
+                Ok(record) => record,
+                Err(e) => {
+                    summary.errors.push(format!("line {}: {}", line_number, e));
+                    continue;
+                }
+            };
+
+            let key = record.key.clone();
+            match self.upsert_document_record(record).await {
+                Ok(created) => {
+                    if created {
+                        summary.created += 1;
+                    } else {
+                        summary.updated += 1;
+                    }
+                    if let Some(key) = key {
+                        summary.keys.push(key);
+                    }
+                }
+                Err(e) => summary.errors.push(format!("line {}: {}", line_number, e)),
+            }
+        }
+
+        Ok(summary)
+    }
+
+    /// Upsert a single record by key, returning `true` if it was created
+    async fn upsert_document_record(&self, record: DocumentRecord) -> Result<bool> {
+        if let Some(key) = &record.key {
+            let mut result = self
+                .inner()
+                .query(
+                    "UPDATE document SET content = $content, title = $title, tags = $tags, \
+                     metadata = $metadata, deleted_at = NONE, updated_at = time::now() \
+                     WHERE key = $key RETURN AFTER",
+                )
+                .bind(("key", key.clone()))
+                .bind(("content", record.content.clone()))
+                .bind(("title", record.title.clone()))
+                .bind(("tags", record.tags.clone()))
+                .bind(("metadata", record.metadata.clone()))
+                .await?;
+
+            let updated: Vec<Document> = result.take(0)?;
+            if !updated.is_empty() {
+                return Ok(false);
+            }
+        }
+
+        let mut input = CreateDocument::new(record.content).with_tags(record.tags);
+        input.key = record.key;
+        input.title = record.title;
+        input.metadata = record.metadata;
+
+        let doc = self.create_document(input).await?;
+
+        // Preserve the original creation time when the export carried one
+        if let (Some(id), Some(created_at)) = (doc.id, record.created_at) {
+            self.inner()
+                .query("UPDATE $id SET created_at = <datetime> $created_at")
+                .bind(("id", id))
+                .bind(("created_at", created_at.to_rfc3339()))
+                .await?;
+        }
+
+        Ok(true)
+    }
+
+    /// Count documents with optional tag filter (soft-deleted documents are excluded)
+    ///
+    /// Uses the same filter as [`Database::list_documents`], so it gives the
+    /// total to paginate over.
+    pub async fn count_documents(&self, tags: Option<&[String]>) -> Result<usize> {
+        let mut result = match tags {
+            Some(tags) if !tags.is_empty() => {
+                self.inner()
+                    .query("SELECT count() FROM document WHERE deleted_at = NONE AND tags CONTAINSANY $tags GROUP ALL")
+                    .bind(("tags", tags.to_vec()))
+                    .await?
+            }
+            _ => {
+                self.inner()
+                    .query("SELECT count() FROM document WHERE deleted_at = NONE GROUP ALL")
+                    .await?
+            }
+        };
+
         #[derive(Deserialize)]
         struct CountResult {
             count: usize,
@@ -265,6 +744,100 @@ mod tests {
             .await
             .unwrap();
         assert_eq!(even.len(), 3);
+
+        assert_eq!(db.count_documents(None).await.unwrap(), 5);
+        assert_eq!(db.count_documents(Some(&["even".to_string()])).await.unwrap(), 3);
+        assert_eq!(db.count_documents(Some(&[])).await.unwrap(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_list_documents_by_key_prefix() {
+        let db = Database::new_memory().await.unwrap();
+
+        for key in ["pref:bob:tone", "pref:alice:tone", "pref:alice:language", "other"] {
+            db.create_document(CreateDocument::new(key).with_key(key)).await.unwrap();
+        }
+        db.create_document(CreateDocument::new("no key")).await.unwrap();
+        db.soft_delete_document_by_key("pref:bob:tone").await.unwrap();
+
+        let alice = db.list_documents_by_key_prefix("pref:alice:").await.unwrap();
+        let keys: Vec<_> = alice.iter().filter_map(|d| d.key.as_deref()).collect();
+        assert_eq!(keys, vec!["pref:alice:language", "pref:alice:tone"]);
+
+        assert!(db.list_documents_by_key_prefix("pref:bob:").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_search_documents_text() {
+        let db = Database::new_memory().await.unwrap();
+
+        db.create_document(CreateDocument::new("Rust ownership and borrowing rules").with_key("a"))
+            .await
+            .unwrap();
+        db.create_document(CreateDocument::new("Ownership: every value has an owner. Ownership moves.").with_key("b"))
+            .await
+            .unwrap();
+        db.create_document(CreateDocument::new("Ownership of tagged notes").with_key("e").with_tags(vec!["rust".into()]))
+            .await
+            .unwrap();
+        db.create_document(CreateDocument::new("Python uses garbage collection").with_key("c"))
+            .await
+            .unwrap();
+        db.create_document(CreateDocument::new("Ownership in the trash").with_key("d"))
+            .await
+            .unwrap();
+        db.soft_delete_document_by_key("d").await.unwrap();
+
+        let docs = db.search_documents_text("ownership", &[], 10).await.unwrap();
+        let keys: Vec<_> = docs.iter().filter_map(|d| d.key.as_deref()).collect();
+        assert_eq!(keys.len(), 3);
+        assert!(keys.contains(&"a") && keys.contains(&"b") && keys.contains(&"e"));
+        assert!(docs.iter().all(|d| d.score.is_some_and(|s| s > 0.0)));
+        assert!(docs[0].score >= docs[1].score);
+
+        assert_eq!(db.search_documents_text("ownership", &[], 1).await.unwrap().len(), 1);
+        assert!(db.search_documents_text("haskell", &[], 10).await.unwrap().is_empty());
+
+        // The tag filter applies before the limit
+        let tagged = db.search_documents_text("ownership", &["rust".into()], 1).await.unwrap();
+        assert_eq!(tagged.len(), 1);
+        assert_eq!(tagged[0].key.as_deref(), Some("e"));
+    }
+
+    #[tokio::test]
+    async fn test_update_document_partial() {
+        let db = Database::new_memory().await.unwrap();
+        let created = db
+            .create_document(
+                CreateDocument::new("Original")
+                    .with_key("doc")
+                    .with_title("Title")
+                    .with_tags(vec!["a".to_string()])
+                    .with_metadata(serde_json::json!({ "lang": "rust" })),
+            )
+            .await
+            .unwrap();
+
+        let updated = db
+            .update_document("doc", UpdateDocument::new().with_tags(vec!["a".to_string(), "b".to_string()]))
+            .await
+            .unwrap();
+        assert_eq!(updated.tags, vec!["a", "b"]);
+        assert_eq!(updated.content, "Original");
+        assert_eq!(updated.title.as_deref(), Some("Title"));
+        assert_eq!(updated.metadata, Some(serde_json::json!({ "lang": "rust" })));
+        assert!(updated.updated_at >= created.updated_at);
+
+        let updated = db
+            .update_document("doc", UpdateDocument::new().with_content("Edited").with_title("New"))
+            .await
+            .unwrap();
+        assert_eq!(updated.content, "Edited");
+        assert_eq!(updated.title.as_deref(), Some("New"));
+        assert_eq!(updated.tags, vec!["a", "b"]);
+
+        let missing = db.update_document("missing", UpdateDocument::new().with_content("x")).await;
+        assert!(matches!(missing, Err(DatabaseError::NotFound { .. })));
     }
 
     #[tokio::test]
@@ -281,4 +854,183 @@ mod tests {
         let gone = db.get_document(&id).await.unwrap();
         assert!(gone.is_none());
     }
+
+    #[tokio::test]
+    async fn test_soft_delete_and_restore() {
+        let db = Database::new_memory().await.unwrap();
+
+        db.create_document(CreateDocument::new("Keep me").with_key("keep"))
+            .await
+            .unwrap();
+        db.create_document(CreateDocument::new("Trash me").with_key("trash"))
+            .await
+            .unwrap();
+
+        assert!(db.soft_delete_document_by_key("trash").await.unwrap());
+        assert!(!db.soft_delete_document_by_key("trash").await.unwrap());
+
+        let listed = db.list_documents(None, 10, 0).await.unwrap();
+        assert_eq!(listed.len(), 1);
+        assert_eq!(db.count_documents(None).await.unwrap(), 1);
+
+        let trashed = db.get_document_by_key("trash").await.unwrap().unwrap();
+        assert!(trashed.is_deleted());
+
+        let restored = db.restore_document_by_key("trash").await.unwrap();
+        assert!(restored.is_some_and(|d| !d.is_deleted()));
+        assert_eq!(db.count_documents(None).await.unwrap(), 2);
+
+        assert!(db.restore_document_by_key("keep").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_store_deleted_key_again() {
+        let db = Database::new_memory().await.unwrap();
+
+        db.create_document(CreateDocument::new("First").with_key("reused"))
+            .await
+            .unwrap();
+        assert!(db.soft_delete_document_by_key("reused").await.unwrap());
+
+        let doc = db
+            .create_document(CreateDocument::new("Second").with_key("reused"))
+            .await
+            .unwrap();
+        assert!(!doc.is_deleted());
+
+        let stored = db.get_document_by_key("reused").await.unwrap().unwrap();
+        assert_eq!(stored.content, "Second");
+        assert_eq!(db.count_documents(None).await.unwrap(), 1);
+
+        // The replaced trash entry is gone, a live key is still unique
+        assert!(db.restore_document_by_key("reused").await.unwrap().is_none());
+        assert!(db
+            .create_document(CreateDocument::new("Third").with_key("reused"))
+            .await
+            .is_err());
+    }
+
+    #[tokio::test]
+    async fn test_purge_deleted_documents() {
+        let db = Database::new_memory().await.unwrap();
+
+        db.create_document(CreateDocument::new("Old").with_key("old"))
+            .await
+            .unwrap();
+        db.soft_delete_document_by_key("old").await.unwrap();
+
+        // Still inside the retention window
+        assert_eq!(db.purge_deleted_documents(7).await.unwrap(), 0);
+
+        // Zero-day retention purges everything in the trash
+        assert_eq!(db.purge_deleted_documents(0).await.unwrap(), 1);
+        assert!(db.get_document_by_key("old").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_export_documents_jsonl() {
+        let db = Database::new_memory().await.unwrap();
+
+        for i in 0..3 {
+            let input = CreateDocument::new(format!("Content {}", i))
+                .with_key(format!("key-{}", i))
+                .with_tag(if i == 0 { "first" } else { "rest" });
+            db.create_document(input).await.unwrap();
+        }
+
+        let mut buf = Vec::new();
+        let exported = db.export_documents(&mut buf, None).await.unwrap();
+        assert_eq!(exported, 3);
+
+        let lines: Vec<DocumentRecord> = String::from_utf8(buf)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str(l).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert!(lines.iter().all(|r| r.key.is_some() && r.created_at.is_some()));
+
+        let mut filtered = Vec::new();
+        let exported = db
+            .export_documents(&mut filtered, Some(&["first".to_string()]))
+            .await
+            .unwrap();
+        assert_eq!(exported, 1);
+    }
+
+    #[tokio::test]
+    async fn test_export_documents_pages_past_equal_timestamps() {
+        let db = Database::new_memory().await.unwrap();
+
+        // Five documents sharing one creation time, exported two per page
+        let jsonl: String = (0..5)
+            .map(|i| format!("{{\"key\":\"same-{}\",\"content\":\"Same time\",\"created_at\":\"2024-01-01T00:00:00Z\"}}\n", i))
+            .collect();
+        assert_eq!(db.import_documents(jsonl.as_bytes()).await.unwrap().created, 5);
+
+        let mut buf = Vec::new();
+        assert_eq!(db.export_documents_paged(&mut buf, None, 2).await.unwrap(), 5);
+        let keys: std::collections::HashSet<String> = String::from_utf8(buf)
+            .unwrap()
+            .lines()
+            .map(|l| serde_json::from_str::<DocumentRecord>(l).unwrap().key.unwrap())
+            .collect();
+        assert_eq!(keys.len(), 5);
+    }
+
+    #[tokio::test]
+    async fn test_import_documents_upserts_by_key() {
+        let db = Database::new_memory().await.unwrap();
+
+        db.create_document(CreateDocument::new("Old content").with_key("existing"))
+            .await
+            .unwrap();
+
+        let jsonl = concat!(
+            r#"{"key":"existing","content":"New content","tags":["imported"]}"#, "\n",
+            r#"{"key":"fresh","content":"Fresh content","created_at":"2024-01-01T00:00:00Z"}"#, "\n",
+            "\n",
+            "not json\n",
+        );
+
+        let summary = db.import_documents(jsonl.as_bytes()).await.unwrap();
+        assert_eq!(summary.created, 1);
+        assert_eq!(summary.updated, 1);
+        assert_eq!(summary.keys, vec!["existing", "fresh"]);
+        assert_eq!(summary.errors.len(), 1);
+        assert!(summary.errors[0].starts_with("line 4"));
+
+        let existing = db.get_document_by_key("existing").await.unwrap().unwrap();
+        assert_eq!(existing.content, "New content");
+        assert_eq!(existing.tags, vec!["imported"]);
+
+        let fresh = db.get_document_by_key("fresh").await.unwrap().unwrap();
+        assert_eq!(fresh.created_at.unwrap().timestamp(), 1704067200);
+        assert_eq!(db.count_documents(None).await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_import_documents_continues_past_bad_lines() {
+        let db = Database::new_memory().await.unwrap();
+
+        db.create_document(CreateDocument::new("Trashed").with_key("trashed"))
+            .await
+            .unwrap();
+        db.soft_delete_document_by_key("trashed").await.unwrap();
+
+        let mut jsonl = b"\xff\xfe not utf-8\n".to_vec();
+        jsonl.extend_from_slice(br#"{"key":"trashed","content":"Restored"}"#);
+        jsonl.push(b'\n');
+        jsonl.extend_from_slice(br#"{"key":"after","content":"After the bad line"}"#);
+
+        let summary = db.import_documents(jsonl.as_slice()).await.unwrap();
+        assert_eq!(summary.errors.len(), 1);
+        assert!(summary.errors[0].starts_with("line 1"));
+        assert_eq!(summary.keys, vec!["trashed", "after"]);
+
+        // Importing a key restores its soft-deleted document
+        let restored = db.get_document_by_key("trashed").await.unwrap().unwrap();
+        assert_eq!(restored.content, "Restored");
+        assert!(!restored.is_deleted());
+    }
 }
@@ -9,6 +9,10 @@ pub enum DatabaseError {
     #[error("SurrealDB error: {0}")]
     Surreal(#[from] surrealdb::Error),
 
+    /// Could not connect or sign in to a remote server
+    #[error("Connection error: {0}")]
+    Connection(String),
+
     /// Serialization error
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
@@ -32,6 +36,26 @@ pub enum DatabaseError {
     /// Relation error
     #[error("Relation error: {0}")]
     Relation(String),
+
+    /// Vector search metric the index cannot serve
+    #[error("Unsupported search metric: {0}")]
+    UnsupportedMetric(String),
+
+    /// Invalid metadata filter
+    #[error("Invalid filter: {0}")]
+    InvalidFilter(String),
+
+    /// A transaction was rolled back because one of its operations failed
+    #[error("Transaction rolled back: operation {index} ({operation}) failed: {message}")]
+    Transaction {
+        index: usize,
+        operation: String,
+        message: String,
+    },
+
+    /// I/O error (import/export)
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
 }
 
 /// Result type alias
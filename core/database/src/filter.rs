@@ -0,0 +1,276 @@
+//! Metadata filters compiled to SurrealQL conditions
+//!
+//! A filter is a JSON object whose keys are metadata fields (dotted paths
+//! reach nested objects) and whose values are either a literal to match
+//! exactly or an object of operators. Every top-level entry must hold.
+//!
+//! | Operator    | Matches when the field...                   |
+//! |-------------|---------------------------------------------|
+//! | `"value"`   | equals the value (shorthand for `$eq`)      |
+//! | `$eq`       | equals the value                            |
+//! | `$ne`       | differs from the value                      |
+//! | `$in`       | equals one of the values in the array       |
+//! | `$nin`      | equals none of the values in the array      |
+//! | `$contains` | is an array containing the value            |
+//! | `$gt`, `$gte`, `$lt`, `$lte` | compares to the value      |
+//! | `$exists`   | is present (`true`) or absent (`false`)     |
+//!
+//! `$and` and `$or` take an array of filters and combine them:
+//!
+//! ```json
+//! {"project": "whytcard", "$or": [{"lang": {"$in": ["rust", "go"]}}, {"stars": {"$gte": 100}}]}
+//! ```
+//!
+//! `$tags` matches the document's tags (the `tags` field next to the
+//! metadata) instead of a metadata field: a tag or an array of tags, all of
+//! which must be present, e.g. `{"$tags": ["session-42", "notes"]}`.
+//!
+//! Values are bound as query parameters, never interpolated; field names are
+//! restricted to letters, digits and `_`.
+
+use crate::{DatabaseError, Result};
+use serde_json::Value;
+
+/// A compiled filter: a SurrealQL condition and the parameters it binds
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct CompiledFilter {
+    /// Condition usable in a `WHERE` clause
+    pub condition: String,
+
+    /// Parameters referenced by `condition`, as (name, value)
+    pub bindings: Vec<(String, Value)>,
+}
+
+/// Compile `filter` against the object field at `field` (e.g. `metadata`)
+pub(crate) fn compile(filter: &Value, field: &str) -> Result<CompiledFilter> {
+    let (mut conditions, bindings) = compile_each(filter, &[field])?;
+    Ok(CompiledFilter {
+        condition: conditions.remove(0),
+        bindings,
+    })
+}
+
+/// Compile `filter` against each of `fields`, in order, with one set of bindings
+///
+/// Every condition binds its own parameters, so all of them can run in the
+/// same query once the shared bindings are bound.
+pub(crate) fn compile_each(filter: &Value, fields: &[&str]) -> Result<(Vec<String>, Vec<(String, Value)>)> {
+    let mut bindings = Vec::new();
+    let mut conditions = Vec::with_capacity(fields.len());
+    for field in fields {
+        let mut compiler = Compiler { field, bindings };
+        conditions.push(compiler.filter(filter)?);
+        bindings = compiler.bindings;
+    }
+    Ok((conditions, bindings))
+}
+
+struct Compiler<'a> {
+    field: &'a str,
+    bindings: Vec<(String, Value)>,
+}
+
+impl Compiler<'_> {
+    fn filter(&mut self, filter: &Value) -> Result<String> {
+        let Value::Object(entries) = filter else {
+            return Err(invalid("filter must be an object"));
+        };
+        if entries.is_empty() {
+            return Ok("true".to_string());
+        }
+
+        let conditions = entries
+            .iter()
+            .map(|(key, value)| match key.as_str() {
+                "$and" => self.combine(value, "AND"),
+                "$or" => self.combine(value, "OR"),
+                "$tags" => self.tags(value),
+                op if op.starts_with('$') => Err(invalid(format!("unknown top-level operator {op}"))),
+                path => self.predicates(path, value),
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(join(conditions, "AND"))
+    }
+
+    fn combine(&mut self, filters: &Value, joiner: &str) -> Result<String> {
+        let Value::Array(filters) = filters else {
+            return Err(invalid("$and/$or expect an array of filters"));
+        };
+        if filters.is_empty() {
+            return Err(invalid("$and/$or need at least one filter"));
+        }
+        let conditions = filters
+            .iter()
+            .map(|f| self.filter(f))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(join(conditions, joiner))
+    }
+
+    fn tags(&mut self, value: &Value) -> Result<String> {
+        let tags = match value {
+            Value::String(tag) => vec![Value::String(tag.clone())],
+            Value::Array(tags) if !tags.is_empty() && tags.iter().all(Value::is_string) => tags.clone(),
+            _ => return Err(invalid("$tags expects a tag or a non-empty array of tags")),
+        };
+        let field = match self.field.rsplit_once('.') {
+            Some((parent, _)) => format!("{parent}.tags"),
+            None => "tags".to_string(),
+        };
+        Ok(format!("{field} CONTAINSALL {}", self.bind(Value::Array(tags))))
+    }
+
+    fn predicates(&mut self, path: &str, value: &Value) -> Result<String> {
+        let field = self.field_path(path)?;
+
+        let operators = match value {
+            Value::Object(ops) if ops.keys().any(|k| k.starts_with('$')) => ops,
+            literal => return Ok(format!("{field} = {}", self.bind(literal.clone()))),
+        };
+
+        let conditions = operators
+            .iter()
+            .map(|(op, operand)| {
+                let condition = match op.as_str() {
+                    "$eq" => format!("{field} = {}", self.bind(operand.clone())),
+                    "$ne" => format!("{field} != {}", self.bind(operand.clone())),
+                    "$gt" => format!("{field} > {}", self.bind(operand.clone())),
+                    "$gte" => format!("{field} >= {}", self.bind(operand.clone())),
+                    "$lt" => format!("{field} < {}", self.bind(operand.clone())),
+                    "$lte" => format!("{field} <= {}", self.bind(operand.clone())),
+                    "$contains" => format!("{field} CONTAINS {}", self.bind(operand.clone())),
+                    "$in" | "$nin" => {
+                        if !operand.is_array() {
+                            return Err(invalid(format!("{op} on {path} expects an array")));
+                        }
+                        let keyword = if op == "$in" { "IN" } else { "NOT IN" };
+                        format!("{field} {keyword} {}", self.bind(operand.clone()))
+                    }
+                    "$exists" => match operand {
+                        Value::Bool(true) => format!("{field} != NONE"),
+                        Value::Bool(false) => format!("{field} = NONE"),
+                        _ => return Err(invalid(format!("$exists on {path} expects a boolean"))),
+                    },
+                    other => return Err(invalid(format!("unknown operator {other} on {path}"))),
+                };
+                Ok(condition)
+            })
+            .collect::<Result<Vec<_>>>()?;
+        Ok(join(conditions, "AND"))
+    }
+
+    fn field_path(&self, path: &str) -> Result<String> {
+        let valid = path.split('.').all(|segment| {
+            !segment.is_empty() && segment.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+        });
+        if !valid {
+            return Err(invalid(format!("invalid metadata field name: {path:?}")));
+        }
+        Ok(format!("{}.{}", self.field, path))
+    }
+
+    fn bind(&mut self, value: Value) -> String {
+        let name = format!("filter_{}", self.bindings.len());
+        let placeholder = format!("${name}");
+        self.bindings.push((name, value));
+        placeholder
+    }
+}
+
+fn join(conditions: Vec<String>, joiner: &str) -> String {
+    if conditions.len() == 1 {
+        return conditions.into_iter().next().unwrap_or_default();
+    }
+    let joined = conditions.join(&format!(" {joiner} "));
+    format!("({joined})")
+}
+
+fn invalid(message: impl Into<String>) -> DatabaseError {
+    DatabaseError::InvalidFilter(message.into())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_compile_equality_and_operators() {
+        let compiled = compile(
+            &json!({ "project": "whytcard", "stars": { "$gte": 10, "$lt": 100 } }),
+            "metadata",
+        )
+        .unwrap();
+        assert_eq!(
+            compiled.condition,
+            "(metadata.project = $filter_0 AND (metadata.stars >= $filter_1 AND metadata.stars < $filter_2))"
+        );
+        assert_eq!(compiled.bindings[0], ("filter_0".to_string(), json!("whytcard")));
+        assert_eq!(compiled.bindings[2].1, json!(100));
+    }
+
+    #[test]
+    fn test_compile_in_or_and_exists() {
+        let compiled = compile(
+            &json!({ "$or": [{ "lang": { "$in": ["rust", "go"] } }, { "archived": { "$exists": false } }] }),
+            "document_id.metadata",
+        )
+        .unwrap();
+        assert_eq!(
+            compiled.condition,
+            "(document_id.metadata.lang IN $filter_0 OR document_id.metadata.archived = NONE)"
+        );
+        assert_eq!(compiled.bindings, vec![("filter_0".to_string(), json!(["rust", "go"]))]);
+
+        // Nested objects without operators are matched literally
+        let nested = compile(&json!({ "owner": { "name": "b" }, "source.repo": "a" }), "metadata").unwrap();
+        assert_eq!(nested.condition, "(metadata.owner = $filter_0 AND metadata.source.repo = $filter_1)");
+    }
+
+    #[test]
+    fn test_compile_tags() {
+        let compiled = compile(&json!({ "$tags": "session-42" }), "document_id.metadata").unwrap();
+        assert_eq!(compiled.condition, "document_id.tags CONTAINSALL $filter_0");
+        assert_eq!(compiled.bindings[0].1, json!(["session-42"]));
+
+        let compiled = compile(&json!({ "$tags": ["a", "b"], "lang": "rust" }), "metadata").unwrap();
+        assert_eq!(compiled.condition, "(tags CONTAINSALL $filter_0 AND metadata.lang = $filter_1)");
+    }
+
+    #[test]
+    fn test_compile_each_shares_bindings() {
+        let (conditions, bindings) = compile_each(
+            &json!({ "$tags": "notes", "lang": { "$in": ["rust"] } }),
+            &["document_id.metadata", "metadata"],
+        )
+        .unwrap();
+        assert_eq!(
+            conditions,
+            vec![
+                "(document_id.tags CONTAINSALL $filter_0 AND document_id.metadata.lang IN $filter_1)",
+                "(tags CONTAINSALL $filter_2 AND metadata.lang IN $filter_3)",
+            ]
+        );
+        let names: Vec<&str> = bindings.iter().map(|(name, _)| name.as_str()).collect();
+        assert_eq!(names, vec!["filter_0", "filter_1", "filter_2", "filter_3"]);
+    }
+
+    #[test]
+    fn test_compile_rejects_invalid_filters() {
+        for filter in [
+            json!("project"),
+            json!({ "lang": { "$in": "rust" } }),
+            json!({ "lang": { "$regex": "r.*" } }),
+            json!({ "$not": {} }),
+            json!({ "a; DELETE chunk": 1 }),
+            json!({ "a..b": 1 }),
+            json!({ "$or": [] }),
+            json!({ "$tags": [] }),
+            json!({ "$tags": [1] }),
+        ] {
+            assert!(
+                matches!(compile(&filter, "metadata"), Err(DatabaseError::InvalidFilter(_))),
+                "{filter} should be rejected"
+            );
+        }
+    }
+}
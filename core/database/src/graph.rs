@@ -1,8 +1,9 @@
 //! Knowledge graph operations with entities and relations
 
-use crate::{Database, DatabaseError, Result};
+use crate::{Database, DatabaseError, Result, SearchMetric};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
 use surrealdb::RecordId;
 
 /// Entity in the knowledge graph
@@ -12,9 +13,13 @@ pub struct Entity {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub id: Option<RecordId>,
 
-    /// Entity name
+    /// Entity name (the lookup key, normalized when normalization is enabled)
     pub name: String,
 
+    /// Name as originally written, when it differs from the lookup key
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+
     /// Entity type (person, concept, tool, etc.)
     pub entity_type: String,
 
@@ -35,12 +40,23 @@ pub struct Entity {
     pub updated_at: Option<DateTime<Utc>>,
 }
 
+impl Entity {
+    /// Name to show to users: the display name if recorded, else the lookup name
+    pub fn label(&self) -> &str {
+        self.display_name.as_deref().unwrap_or(&self.name)
+    }
+}
+
 /// Input for creating an entity
 #[derive(Debug, Clone, Serialize)]
 pub struct CreateEntity {
     /// Entity name
     pub name: String,
 
+    /// Original display name, if different from the lookup name
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+
     /// Entity type
     pub entity_type: String,
 
@@ -58,12 +74,22 @@ impl CreateEntity {
     pub fn new(name: impl Into<String>, entity_type: impl Into<String>) -> Self {
         Self {
             name: name.into(),
+            display_name: None,
             entity_type: entity_type.into(),
             observations: Vec::new(),
             metadata: None,
         }
     }
 
+    /// Keep the original name for display when it differs from the lookup name
+    pub fn with_display_name(mut self, display_name: impl Into<String>) -> Self {
+        let display_name = display_name.into();
+        if display_name != self.name {
+            self.display_name = Some(display_name);
+        }
+        self
+    }
+
     /// Add initial observations
     pub fn with_observations(mut self, observations: Vec<String>) -> Self {
         self.observations = observations;
@@ -77,6 +103,55 @@ impl CreateEntity {
     }
 }
 
+/// Partial update of an entity
+///
+/// Only the fields that are set are written; `None` leaves a field unchanged.
+#[derive(Debug, Clone, Default)]
+pub struct UpdateEntity {
+    /// New display name
+    pub display_name: Option<String>,
+
+    /// New entity type
+    pub entity_type: Option<String>,
+
+    /// New observations, replacing the current ones
+    pub observations: Option<Vec<String>>,
+
+    /// New metadata, replacing the current object
+    pub metadata: Option<serde_json::Value>,
+}
+
+impl UpdateEntity {
+    /// Create an update that changes nothing
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the display name
+    pub fn with_display_name(mut self, display_name: impl Into<String>) -> Self {
+        self.display_name = Some(display_name.into());
+        self
+    }
+
+    /// Set the entity type
+    pub fn with_entity_type(mut self, entity_type: impl Into<String>) -> Self {
+        self.entity_type = Some(entity_type.into());
+        self
+    }
+
+    /// Set the observations
+    pub fn with_observations(mut self, observations: Vec<String>) -> Self {
+        self.observations = Some(observations);
+        self
+    }
+
+    /// Set the metadata
+    pub fn with_metadata(mut self, metadata: serde_json::Value) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+}
+
 /// Relation between two entities
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Relation {
@@ -99,6 +174,10 @@ pub struct Relation {
     #[serde(default = "default_weight")]
     pub weight: f32,
 
+    /// Whether traversal treats this edge as bidirectional
+    #[serde(default)]
+    pub symmetric: bool,
+
     /// Additional metadata
     #[serde(skip_serializing_if = "Option::is_none")]
     pub metadata: Option<serde_json::Value>,
@@ -127,6 +206,9 @@ pub struct CreateRelation {
     /// Relation weight
     pub weight: f32,
 
+    /// Whether the relation is symmetric (e.g. "related_to", "synonym_of")
+    pub symmetric: bool,
+
     /// Additional metadata
     pub metadata: Option<serde_json::Value>,
 }
@@ -139,6 +221,7 @@ impl CreateRelation {
             to,
             relation_type: relation_type.into(),
             weight: 1.0,
+            symmetric: false,
             metadata: None,
         }
     }
@@ -149,6 +232,12 @@ impl CreateRelation {
         self
     }
 
+    /// Mark the relation as symmetric
+    pub fn with_symmetric(mut self, symmetric: bool) -> Self {
+        self.symmetric = symmetric;
+        self
+    }
+
     /// Set metadata
     pub fn with_metadata(mut self, metadata: serde_json::Value) -> Self {
         self.metadata = Some(metadata);
@@ -183,8 +272,76 @@ pub struct RelatedEntity {
 
     /// Relation weight
     pub weight: f32,
+
+    /// Hops from the start entity (1 for direct relations)
+    #[serde(default = "default_distance")]
+    pub distance: usize,
+
+    /// Relations walked from the start entity, filled by [`Database::traverse`]
+    #[serde(skip)]
+    pub path: Vec<PathStep>,
+}
+
+fn default_distance() -> usize {
+    1
+}
+
+/// One hop of a traversal path
+#[derive(Debug, Clone)]
+pub struct PathStep {
+    /// Relation crossed
+    pub relation: Relation,
+
+    /// `Outgoing` when crossed from `in` to `out`, `Incoming` when crossed backwards
+    pub direction: RelationDirection,
+}
+
+/// Result of [`Database::traverse_bounded`]
+#[derive(Debug, Clone)]
+pub struct Traversal {
+    /// Reached entities, nearest first
+    pub entities: Vec<RelatedEntity>,
+
+    /// Whether the walk stopped at the node limit
+    pub truncated: bool,
 }
 
+/// Entity found by [`Database::search_entity_embeddings`]
+#[derive(Debug, Clone, Deserialize)]
+pub struct EntityMatch {
+    /// The matching entity
+    pub entity: Entity,
+
+    /// Distance between the query and the entity's embedding
+    pub distance: f32,
+
+    /// Similarity to the query under the index metric (higher is closer),
+    /// see [`SearchMetric::similarity`]
+    #[serde(default)]
+    pub similarity: f32,
+}
+
+/// HNSW candidate list size for entity embedding searches
+const ENTITY_SEARCH_EF: usize = 64;
+
+/// One level of a traversal: the relations touching `$ids` and the entities
+/// at their ends
+///
+/// Edges are reached through the graph pointers of the `$ids` records
+/// (`->relates_to`, `<-relates_to`), so the cost follows the frontier's
+/// degree rather than the size of the relation table.
+const TRAVERSE_LEVEL_QUERY: &str = r#"
+    LET $edges = (
+        SELECT * FROM array::distinct(array::flatten(
+            (SELECT VALUE array::concat(->relates_to, <-relates_to) FROM $ids)
+        ))
+        WHERE array::len($types) = 0 OR relation_type IN $types
+    );
+    LET $ends = array::distinct(array::concat($edges.in, $edges.out));
+    SELECT * FROM $ends;
+    RETURN $edges;
+"#;
+
 /// Direction for relation queries
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum RelationDirection {
@@ -228,10 +385,64 @@ impl Database {
         Ok(entities.into_iter().next())
     }
 
-    /// Update entity
-    pub async fn update_entity(&self, id: &str, updates: serde_json::Value) -> Result<Entity> {
-        let entity: Option<Entity> = self.inner().update(("entity", id)).merge(updates).await?;
-        entity.ok_or_else(|| DatabaseError::NotFound {
+    /// Get the entity with this name and type
+    pub async fn get_entity_by_name_and_type(
+        &self,
+        name: &str,
+        entity_type: &str,
+    ) -> Result<Option<Entity>> {
+        let mut result = self
+            .inner()
+            .query("SELECT * FROM entity WHERE name = $name AND entity_type = $entity_type LIMIT 1")
+            .bind(("name", name.to_string()))
+            .bind(("entity_type", entity_type.to_string()))
+            .await?;
+
+        let entities: Vec<Entity> = result.take(0)?;
+        Ok(entities.into_iter().next())
+    }
+
+    /// Update the fields set in `update` on an entity
+    ///
+    /// Other fields keep their values; `updated_at` is bumped.
+    pub async fn update_entity(&self, id: &str, update: UpdateEntity) -> Result<Entity> {
+        let mut assignments = vec!["updated_at = time::now()"];
+        if update.display_name.is_some() {
+            assignments.push("display_name = $display_name");
+        }
+        if update.entity_type.is_some() {
+            assignments.push("entity_type = $entity_type");
+        }
+        if update.observations.is_some() {
+            assignments.push("observations = $observations");
+        }
+        if update.metadata.is_some() {
+            assignments.push("metadata = $metadata");
+        }
+
+        let mut query = self
+            .inner()
+            .query(format!(
+                "UPDATE type::thing('entity', $id) SET {} RETURN AFTER",
+                assignments.join(", ")
+            ))
+            .bind(("id", id.to_string()));
+        if let Some(display_name) = update.display_name {
+            query = query.bind(("display_name", display_name));
+        }
+        if let Some(entity_type) = update.entity_type {
+            query = query.bind(("entity_type", entity_type));
+        }
+        if let Some(observations) = update.observations {
+            query = query.bind(("observations", observations));
+        }
+        if let Some(metadata) = update.metadata {
+            query = query.bind(("metadata", metadata));
+        }
+
+        let mut result = query.await?.check()?;
+        let entities: Vec<Entity> = result.take(0)?;
+        entities.into_iter().next().ok_or_else(|| DatabaseError::NotFound {
             table: "entity".into(),
             id: id.into(),
         })
@@ -254,6 +465,31 @@ impl Database {
         })
     }
 
+    /// Remove every observation equal to `observation` from an entity
+    ///
+    /// Returns how many were removed (0 when the entity has no such
+    /// observation).
+    pub async fn remove_observation(&self, id: &str, observation: &str) -> Result<usize> {
+        let mut result = self
+            .inner()
+            .query(
+                "UPDATE type::thing('entity', $id) \
+                 SET observations = array::complement(observations, [$obs]), updated_at = time::now() \
+                 RETURN BEFORE",
+            )
+            .bind(("id", id.to_string()))
+            .bind(("obs", observation.to_string()))
+            .await?
+            .check()?;
+
+        let entities: Vec<Entity> = result.take(0)?;
+        let before = entities.into_iter().next().ok_or_else(|| DatabaseError::NotFound {
+            table: "entity".into(),
+            id: id.into(),
+        })?;
+        Ok(before.observations.iter().filter(|o| *o == observation).count())
+    }
+
     /// Delete entity and its relations
     pub async fn delete_entity(&self, id: &str) -> Result<()> {
         // Delete all relations involving this entity
@@ -280,6 +516,18 @@ impl Database {
         Ok(entities)
     }
 
+    /// List entities ordered by name with pagination
+    pub async fn list_entities(&self, limit: usize, offset: usize) -> Result<Vec<Entity>> {
+        let query = format!(
+            "SELECT * FROM entity ORDER BY name LIMIT {} START {}",
+            limit, offset
+        );
+
+        let mut result = self.inner().query(&query).await?;
+        let entities: Vec<Entity> = result.take(0)?;
+        Ok(entities)
+    }
+
     /// Search entities by name pattern
     pub async fn search_entities(&self, pattern: &str) -> Result<Vec<Entity>> {
         let pattern_owned = pattern.to_string();
@@ -293,6 +541,45 @@ impl Database {
         Ok(entities)
     }
 
+    /// Search entities by name pattern with pagination
+    pub async fn search_entities_page(
+        &self,
+        pattern: &str,
+        limit: usize,
+        offset: usize,
+    ) -> Result<Vec<Entity>> {
+        let query = format!(
+            "SELECT * FROM entity WHERE name CONTAINS $pattern ORDER BY name LIMIT {} START {}",
+            limit, offset
+        );
+
+        let mut result = self
+            .inner()
+            .query(&query)
+            .bind(("pattern", pattern.to_string()))
+            .await?;
+
+        let entities: Vec<Entity> = result.take(0)?;
+        Ok(entities)
+    }
+
+    /// Count entities matching a name pattern
+    pub async fn count_search_entities(&self, pattern: &str) -> Result<usize> {
+        let mut result = self
+            .inner()
+            .query("SELECT count() FROM entity WHERE name CONTAINS $pattern GROUP ALL")
+            .bind(("pattern", pattern.to_string()))
+            .await?;
+
+        #[derive(Deserialize)]
+        struct CountResult {
+            count: usize,
+        }
+
+        let counts: Vec<CountResult> = result.take(0)?;
+        Ok(counts.first().map(|c| c.count).unwrap_or(0))
+    }
+
     // ============ Relation Operations ============
 
     /// Create a relation between entities
@@ -301,6 +588,7 @@ impl Database {
             RELATE $from->relates_to->$to SET
                 relation_type = $rel_type,
                 weight = $weight,
+                symmetric = $symmetric,
                 metadata = $metadata,
                 created_at = time::now()
         "#;
@@ -312,6 +600,7 @@ impl Database {
             .bind(("to", input.to))
             .bind(("rel_type", input.relation_type))
             .bind(("weight", input.weight))
+            .bind(("symmetric", input.symmetric))
             .bind(("metadata", input.metadata))
             .await?;
 
@@ -322,6 +611,25 @@ impl Database {
             .ok_or_else(|| DatabaseError::Relation("Failed to create relation".into()))
     }
 
+    /// Find an existing relation of the given type between two entities
+    pub async fn find_relation(
+        &self,
+        from: &RecordId,
+        to: &RecordId,
+        relation_type: &str,
+    ) -> Result<Option<Relation>> {
+        let mut result = self
+            .inner()
+            .query("SELECT * FROM relates_to WHERE in = $from AND out = $to AND relation_type = $rel_type LIMIT 1")
+            .bind(("from", from.clone()))
+            .bind(("to", to.clone()))
+            .bind(("rel_type", relation_type.to_string()))
+            .await?;
+
+        let relations: Vec<Relation> = result.take(0)?;
+        Ok(relations.into_iter().next())
+    }
+
     /// Get all relations from an entity
     pub async fn get_outgoing_relations(&self, entity_id: &str) -> Result<Vec<Relation>> {
         let mut result = self
@@ -334,6 +642,22 @@ impl Database {
         Ok(relations)
     }
 
+    /// Get all relations leaving any of the given entities
+    pub async fn get_outgoing_relations_for(&self, entity_ids: &[RecordId]) -> Result<Vec<Relation>> {
+        if entity_ids.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let mut result = self
+            .inner()
+            .query("SELECT * FROM relates_to WHERE in IN $ids")
+            .bind(("ids", entity_ids.to_vec()))
+            .await?;
+
+        let relations: Vec<Relation> = result.take(0)?;
+        Ok(relations)
+    }
+
     /// Get all relations to an entity
     pub async fn get_incoming_relations(&self, entity_id: &str) -> Result<Vec<Relation>> {
         let mut result = self
@@ -457,6 +781,110 @@ impl Database {
         Ok(paths.into_iter().next().unwrap_or_default())
     }
 
+    /// Walk the graph breadth-first from an entity, up to `max_depth` hops
+    ///
+    /// Relations are followed in both directions, restricted to
+    /// `relation_types` unless empty. Each reachable entity is returned once,
+    /// at its shortest distance, with the path that reached it. Costs one
+    /// query per level rather than per entity visited.
+    pub async fn traverse(
+        &self,
+        start_id: &str,
+        max_depth: usize,
+        relation_types: &[String],
+    ) -> Result<Vec<RelatedEntity>> {
+        Ok(self
+            .traverse_bounded(start_id, max_depth, relation_types, None)
+            .await?
+            .entities)
+    }
+
+    /// Like [`Self::traverse`], stopping once `max_nodes` entities (the
+    /// start included) have been visited
+    ///
+    /// Runs one query per level rather than a single recursive idiom
+    /// (`.{1..N+collect}`): SurrealDB does not deduplicate the frontier between
+    /// recursion steps, so every cycle multiplies the work at each deeper
+    /// level, and the idiom returns entities without the relation and
+    /// direction that make up a [`PathStep`]. Deduplicating here also lets
+    /// `max_nodes` stop the walk before the next level is fetched.
+    pub async fn traverse_bounded(
+        &self,
+        start_id: &str,
+        max_depth: usize,
+        relation_types: &[String],
+        max_nodes: Option<usize>,
+    ) -> Result<Traversal> {
+        let start = RecordId::from(("entity", start_id));
+        // Path to every visited entity, keyed by record ID
+        let mut paths: HashMap<String, Vec<PathStep>> = HashMap::from([(start.to_string(), Vec::new())]);
+        let mut frontier = vec![start];
+        let mut entities = Vec::new();
+        let mut truncated = false;
+
+        for distance in 1..=max_depth {
+            if frontier.is_empty() || truncated {
+                break;
+            }
+
+            let mut result = self
+                .inner()
+                .query(TRAVERSE_LEVEL_QUERY)
+                .bind(("ids", frontier.clone()))
+                .bind(("types", relation_types.to_vec()))
+                .await?;
+            let ends: Vec<Entity> = result.take(2)?;
+            let relations: Vec<Relation> = result.take(3)?;
+
+            let ends: HashMap<String, Entity> = ends
+                .into_iter()
+                .filter_map(|entity| Some((entity.id.as_ref()?.to_string(), entity)))
+                .collect();
+            let current: HashSet<String> = frontier.iter().map(RecordId::to_string).collect();
+            let mut next = Vec::new();
+
+            'level: for relation in &relations {
+                let hops = [
+                    (&relation.from, &relation.to, RelationDirection::Outgoing),
+                    (&relation.to, &relation.from, RelationDirection::Incoming),
+                ];
+                for (near, far, direction) in hops {
+                    let (near_key, far_key) = (near.to_string(), far.to_string());
+                    if !current.contains(&near_key) || paths.contains_key(&far_key) {
+                        continue;
+                    }
+                    // Relations to deleted entities lead nowhere
+                    let Some(entity) = ends.get(&far_key) else {
+                        continue;
+                    };
+                    if max_nodes.is_some_and(|max| paths.len() >= max) {
+                        truncated = true;
+                        break 'level;
+                    }
+
+                    let mut path = paths[&near_key].clone();
+                    path.push(PathStep {
+                        relation: relation.clone(),
+                        direction,
+                    });
+                    paths.insert(far_key, path.clone());
+                    next.push(far.clone());
+                    entities.push(RelatedEntity {
+                        entity: entity.clone(),
+                        relation_type: relation.relation_type.clone(),
+                        weight: relation.weight,
+                        distance,
+                        path,
+                    });
+                }
+            }
+
+            frontier = next;
+        }
+
+        Ok(Traversal { entities, truncated })
+    }
+
     /// Get related entities by relation type
     pub async fn get_related(
         &self,
@@ -493,6 +921,94 @@ impl Database {
         Ok(entities)
     }
 
+    // ============ Entity Embeddings ============
+
+    /// Store the embedding of an entity's name and observations
+    ///
+    /// The embedding is dropped again when the entity is deleted or its
+    /// name or observations change, so the entity shows up in
+    /// [`Self::entities_without_embedding`] until it is re-embedded.
+    pub async fn set_entity_embedding(&self, entity: &RecordId, embedding: Vec<f32>) -> Result<()> {
+        let expected_dim = self.config().vector_config.dimension;
+        if embedding.len() != expected_dim {
+            return Err(DatabaseError::DimensionMismatch {
+                expected: expected_dim,
+                got: embedding.len(),
+            });
+        }
+
+        self.inner()
+            .query(
+                "UPSERT type::thing('entity_embedding', record::id($entity)) \
+                 SET entity = $entity, embedding = $embedding, updated_at = time::now()",
+            )
+            .bind(("entity", entity.clone()))
+            .bind(("embedding", embedding))
+            .await?
+            .check()?;
+        Ok(())
+    }
+
+    /// Entities with no stored embedding: new, changed, or from before
+    /// embeddings were stored
+    pub async fn entities_without_embedding(&self, limit: usize) -> Result<Vec<Entity>> {
+        let mut result = self
+            .inner()
+            .query(
+                "SELECT * FROM entity \
+                 WHERE !record::exists(type::thing('entity_embedding', record::id(id))) \
+                 LIMIT $limit",
+            )
+            .bind(("limit", limit))
+            .await?
+            .check()?;
+
+        let entities: Vec<Entity> = result.take(0)?;
+        Ok(entities)
+    }
+
+    /// Entities whose embedding is nearest to `query_embedding`, nearest first
+    ///
+    /// Walks the HNSW index, so only embedded entities are found (see
+    /// [`Self::set_entity_embedding`]). `min_similarity` drops matches
+    /// further than `1 - min_similarity`.
+    pub async fn search_entity_embeddings(
+        &self,
+        query_embedding: &[f32],
+        limit: usize,
+        min_similarity: Option<f32>,
+    ) -> Result<Vec<EntityMatch>> {
+        let expected_dim = self.config().vector_config.dimension;
+        if query_embedding.len() != expected_dim {
+            return Err(DatabaseError::DimensionMismatch {
+                expected: expected_dim,
+                got: query_embedding.len(),
+            });
+        }
+
+        let query = format!(
+            "SELECT entity, vector::distance::knn() AS distance FROM entity_embedding \
+             WHERE embedding <|{limit},{}|> $embedding ORDER BY distance FETCH entity",
+            ENTITY_SEARCH_EF.max(limit)
+        );
+        let mut result = self
+            .inner()
+            .query(query)
+            .bind(("embedding", query_embedding.to_vec()))
+            .await?
+            .check()?;
+
+        let metric = SearchMetric::from(self.config().vector_config.distance);
+        let mut matches: Vec<EntityMatch> = result.take(0)?;
+        for m in &mut matches {
+            m.similarity = metric.similarity(m.distance);
+        }
+        if let Some(min) = min_similarity {
+            matches.retain(|m| m.similarity >= min);
+        }
+        Ok(matches)
+    }
+
     /// Count entities
     pub async fn count_entities(&self) -> Result<usize> {
         let mut result = self
@@ -530,6 +1046,61 @@ impl Database {
 mod tests {
     use super::*;
 
+    #[tokio::test]
+    async fn test_entity_embeddings() {
+        let db = Database::new_memory().await.unwrap();
+        let embedding = |seed: f32| -> Vec<f32> { (0..384).map(|i| (seed + i as f32 * 0.001).sin()).collect() };
+
+        let rust = db.create_entity(CreateEntity::new("rust", "language")).await.unwrap();
+        let python = db.create_entity(CreateEntity::new("python", "language")).await.unwrap();
+        let rust_id = rust.id.unwrap();
+        let python_id = python.id.unwrap();
+        assert_eq!(db.entities_without_embedding(10).await.unwrap().len(), 2);
+
+        db.set_entity_embedding(&rust_id, embedding(1.0)).await.unwrap();
+        db.set_entity_embedding(&python_id, embedding(2.0)).await.unwrap();
+        assert!(db.entities_without_embedding(10).await.unwrap().is_empty());
+        assert!(matches!(
+            db.set_entity_embedding(&rust_id, vec![0.0; 3]).await,
+            Err(DatabaseError::DimensionMismatch { .. })
+        ));
+
+        let matches = db.search_entity_embeddings(&embedding(1.05), 2, None).await.unwrap();
+        assert_eq!(matches.len(), 2);
+        assert_eq!(matches[0].entity.name, "rust");
+        assert!(matches[0].similarity > matches[1].similarity);
+        assert_eq!(matches[0].similarity, 1.0 - matches[0].distance);
+        let close = db.search_entity_embeddings(&embedding(1.05), 2, Some(0.999)).await.unwrap();
+        assert_eq!(close.len(), 1);
+
+        // New observations make the embedding stale; deletion removes it
+        db.add_observation(&rust_id.key().to_string(), "memory safe").await.unwrap();
+        let stale = db.entities_without_embedding(10).await.unwrap();
+        assert_eq!(stale.len(), 1);
+        assert_eq!(stale[0].name, "rust");
+
+        db.delete_entity(&python_id.key().to_string()).await.unwrap();
+        assert!(db.search_entity_embeddings(&embedding(2.0), 2, None).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_entity_embedding_similarity_follows_metric() {
+        let mut config = crate::Config::memory();
+        config.vector_config.distance = crate::DistanceMetric::Euclidean;
+        let db = Database::new(config).await.unwrap();
+
+        let rust = db.create_entity(CreateEntity::new("rust", "language")).await.unwrap();
+        db.set_entity_embedding(rust.id.as_ref().unwrap(), vec![0.0; 384]).await.unwrap();
+
+        // A euclidean distance of 2 is far, not a negative similarity
+        let mut query = vec![0.0; 384];
+        query[0] = 2.0;
+        let matches = db.search_entity_embeddings(&query, 1, None).await.unwrap();
+        assert!((matches[0].distance - 2.0).abs() < 1e-4);
+        assert!((matches[0].similarity - 1.0 / 3.0).abs() < 1e-4);
+        assert!(db.search_entity_embeddings(&query, 1, Some(0.5)).await.unwrap().is_empty());
+    }
+
     #[tokio::test]
     async fn test_create_and_get_entity() {
         let db = Database::new_memory().await.unwrap();
@@ -546,6 +1117,27 @@ mod tests {
         let found = db.get_entity_by_name("Rust").await.unwrap();
         assert!(found.is_some());
         assert_eq!(found.unwrap().name, "Rust");
+
+        // Get by name and type
+        let found = db.get_entity_by_name_and_type("Rust", "programming_language").await.unwrap();
+        assert_eq!(found.unwrap().id, entity.id);
+        assert!(db.get_entity_by_name_and_type("Rust", "game").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_entity_display_name() {
+        let db = Database::new_memory().await.unwrap();
+
+        let entity = db
+            .create_entity(CreateEntity::new("rust", "language").with_display_name("Rust"))
+            .await
+            .unwrap();
+        assert_eq!(entity.name, "rust");
+        assert_eq!(entity.label(), "Rust");
+
+        // Identical display names are not stored twice
+        let plain = CreateEntity::new("go", "language").with_display_name("go");
+        assert!(plain.display_name.is_none());
     }
 
     #[tokio::test]
@@ -566,6 +1158,45 @@ mod tests {
         assert_eq!(updated.observations.len(), 2);
     }
 
+    #[tokio::test]
+    async fn test_remove_observation() {
+        let db = Database::new_memory().await.unwrap();
+        let entity = db.create_entity(CreateEntity::new("Go", "language")).await.unwrap();
+        let id = entity.id.unwrap().key().to_string();
+
+        for observation in ["Garbage collected", "Has goroutines", "Compiled"] {
+            db.add_observation(&id, observation).await.unwrap();
+        }
+
+        assert_eq!(db.remove_observation(&id, "Has goroutines").await.unwrap(), 1);
+        assert_eq!(db.remove_observation(&id, "has goroutines").await.unwrap(), 0);
+        let entity = db.get_entity(&id).await.unwrap();
+        assert_eq!(entity.observations, vec!["Garbage collected", "Compiled"]);
+
+        assert!(matches!(
+            db.remove_observation("missing", "Compiled").await,
+            Err(DatabaseError::NotFound { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_update_entity_partial() {
+        let db = Database::new_memory().await.unwrap();
+        let entity = db
+            .create_entity(CreateEntity::new("rust", "language").with_observations(vec!["Fast".to_string()]))
+            .await
+            .unwrap();
+        let id = entity.id.unwrap().key().to_string();
+
+        let updated = db
+            .update_entity(&id, UpdateEntity::new().with_display_name("Rust").with_entity_type("tool"))
+            .await
+            .unwrap();
+        assert_eq!(updated.label(), "Rust");
+        assert_eq!(updated.entity_type, "tool");
+        assert_eq!(updated.observations, vec!["Fast"]);
+    }
+
     #[tokio::test]
     async fn test_create_relation() {
         let db = Database::new_memory().await.unwrap();
@@ -592,6 +1223,32 @@ mod tests {
         assert_eq!(relation.weight, 1.0);
     }
 
+    #[tokio::test]
+    async fn test_find_relation() {
+        let db = Database::new_memory().await.unwrap();
+
+        let rust = db
+            .create_entity(CreateEntity::new("Rust", "language"))
+            .await
+            .unwrap();
+        let cargo = db
+            .create_entity(CreateEntity::new("Cargo", "tool"))
+            .await
+            .unwrap();
+        let rust_id = rust.id.unwrap();
+        let cargo_id = cargo.id.unwrap();
+
+        assert!(db.find_relation(&rust_id, &cargo_id, "uses").await.unwrap().is_none());
+
+        db.create_relation(CreateRelation::new(rust_id.clone(), cargo_id.clone(), "uses"))
+            .await
+            .unwrap();
+
+        assert!(db.find_relation(&rust_id, &cargo_id, "uses").await.unwrap().is_some());
+        assert!(db.find_relation(&rust_id, &cargo_id, "builds").await.unwrap().is_none());
+        assert!(db.find_relation(&cargo_id, &rust_id, "uses").await.unwrap().is_none());
+    }
+
     #[tokio::test]
     async fn test_get_relations() {
         let db = Database::new_memory().await.unwrap();
@@ -633,6 +1290,50 @@ mod tests {
         assert!(incoming.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_traverse() {
+        let db = Database::new_memory().await.unwrap();
+
+        // rust -uses-> cargo -uses-> crates_io, clippy -checks-> rust
+        let mut ids = HashMap::new();
+        for name in ["rust", "cargo", "crates_io", "clippy"] {
+            let entity = db.create_entity(CreateEntity::new(name, "tool")).await.unwrap();
+            ids.insert(name, entity.id.unwrap());
+        }
+        for (from, to, relation_type) in [
+            ("rust", "cargo", "uses"),
+            ("cargo", "crates_io", "uses"),
+            ("clippy", "rust", "checks"),
+        ] {
+            db.create_relation(CreateRelation::new(ids[from].clone(), ids[to].clone(), relation_type))
+                .await
+                .unwrap();
+        }
+        let rust = ids["rust"].key().to_string();
+
+        let reached = db.traverse(&rust, 2, &[]).await.unwrap();
+        let distance = |name: &str| reached.iter().find(|r| r.entity.name == name).map(|r| r.distance);
+        assert_eq!(reached.len(), 3);
+        assert_eq!(distance("cargo"), Some(1));
+        assert_eq!(distance("clippy"), Some(1));
+        assert_eq!(distance("crates_io"), Some(2));
+
+        let crates_io = reached.iter().find(|r| r.entity.name == "crates_io").unwrap();
+        assert_eq!(crates_io.path.len(), 2);
+        assert!(crates_io.path.iter().all(|step| step.direction == RelationDirection::Outgoing));
+        let clippy = reached.iter().find(|r| r.entity.name == "clippy").unwrap();
+        assert_eq!(clippy.path[0].direction, RelationDirection::Incoming);
+        assert_eq!(clippy.relation_type, "checks");
+
+        let uses = db.traverse(&rust, 3, &["uses".to_string()]).await.unwrap();
+        assert_eq!(uses.len(), 2);
+        assert_eq!(db.traverse(&rust, 1, &[]).await.unwrap().len(), 2);
+
+        let bounded = db.traverse_bounded(&rust, 3, &[], Some(2)).await.unwrap();
+        assert_eq!(bounded.entities.len(), 1);
+        assert!(bounded.truncated);
+    }
+
     #[tokio::test]
     async fn test_delete_entity_cascades() {
         let db = Database::new_memory().await.unwrap();
@@ -681,6 +1382,14 @@ mod tests {
 
         let results = db.search_entities("Rust").await.unwrap();
         assert_eq!(results.len(), 2);
+
+        assert_eq!(db.count_search_entities("Rust").await.unwrap(), 2);
+        let first = db.search_entities_page("Rust", 1, 0).await.unwrap();
+        let second = db.search_entities_page("Rust", 1, 1).await.unwrap();
+        assert_eq!(first.len(), 1);
+        assert_eq!(second.len(), 1);
+        assert_ne!(first[0].name, second[0].name);
+        assert!(db.search_entities_page("Rust", 1, 2).await.unwrap().is_empty());
     }
 
     #[tokio::test]
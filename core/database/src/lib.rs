@@ -26,25 +26,30 @@ mod error;
 mod schema;
 
 pub mod documents;
+pub mod filter;
 pub mod graph;
+pub mod transaction;
 pub mod vectors;
 
-pub use config::{Config, DistanceMetric, StorageMode, VectorConfig};
+pub use config::{Config, DistanceMetric, SearchMetric, StorageMode, VectorConfig};
 pub use database::Database;
 pub use error::{DatabaseError, Result};
 pub use schema::Schema;
 
 // Re-export document types
-pub use documents::{CreateDocument, Document};
+pub use documents::{CreateDocument, Document, DocumentRecord, ImportSummary, UpdateDocument};
 
 // Re-export vector types
-pub use vectors::{Chunk, CreateChunk, SearchResult as VectorSearchResult};
+pub use vectors::{Chunk, CreateChunk, SearchResult as VectorSearchResult, TextSearchResult};
 
 // Re-export graph types
 pub use graph::{
-    CreateEntity, CreateRelation, Entity, EntityWithRelations, RelatedEntity, Relation,
-    RelationDirection,
+    CreateEntity, CreateRelation, Entity, EntityMatch, EntityWithRelations, PathStep, RelatedEntity,
+    Relation, RelationDirection, Traversal, UpdateEntity,
 };
 
+// Re-export transaction types
+pub use transaction::Transaction;
+
 /// Re-export SurrealDB types for convenience
 pub use surrealdb::RecordId;
@@ -1,7 +1,7 @@
 //! Database schema definitions
 
 use crate::{Config, Result};
-use surrealdb::engine::local::Db;
+use surrealdb::engine::any::Any;
 use surrealdb::Surreal;
 
 /// Database schema manager
@@ -9,15 +9,15 @@ pub struct Schema;
 
 impl Schema {
     /// Initialize all schemas
-    pub async fn init(db: &Surreal<Db>, config: &Config) -> Result<()> {
+    pub async fn init(db: &Surreal<Any>, config: &Config) -> Result<()> {
         Self::init_documents(db).await?;
         Self::init_vectors(db, config).await?;
-        Self::init_graph(db).await?;
+        Self::init_graph(db, config).await?;
         Ok(())
     }
 
     /// Initialize document tables
-    async fn init_documents(db: &Surreal<Db>) -> Result<()> {
+    async fn init_documents(db: &Surreal<Any>) -> Result<()> {
         db.query(
             r#"
             -- Document table for storing any content
@@ -26,15 +26,21 @@ impl Schema {
             DEFINE FIELD content ON document TYPE string;
             DEFINE FIELD title ON document TYPE option<string>;
             DEFINE FIELD tags ON document TYPE array<string> DEFAULT [];
-            DEFINE FIELD metadata ON document TYPE option<object>;
+            DEFINE FIELD OVERWRITE metadata ON document FLEXIBLE TYPE option<object>;
             DEFINE FIELD created_at ON document TYPE datetime DEFAULT time::now();
             DEFINE FIELD updated_at ON document TYPE datetime DEFAULT time::now();
+            DEFINE FIELD deleted_at ON document TYPE option<datetime>;
 
             -- Unique index on key if provided
             DEFINE INDEX idx_document_key ON document FIELDS key UNIQUE;
 
             -- Index for tag filtering
             DEFINE INDEX idx_document_tags ON document FIELDS tags;
+
+            -- BM25 full-text index on content, tokenized like chunk content
+            DEFINE ANALYZER document_text TOKENIZERS blank, punct FILTERS lowercase, ascii;
+            DEFINE INDEX idx_document_content ON document FIELDS content
+                SEARCH ANALYZER document_text BM25;
             "#,
         )
         .await?;
@@ -44,9 +50,10 @@ impl Schema {
     }
 
     /// Initialize vector tables with HNSW index
-    async fn init_vectors(db: &Surreal<Db>, config: &Config) -> Result<()> {
+    async fn init_vectors(db: &Surreal<Any>, config: &Config) -> Result<()> {
         let dimension = config.vector_config.dimension;
         let distance = config.vector_config.distance.as_surreal_str();
+        let embedding_index = Self::vector_index(config);
 
         db.query(format!(
             r#"
@@ -56,34 +63,65 @@ impl Schema {
             DEFINE FIELD content ON chunk TYPE string;
             DEFINE FIELD embedding ON chunk TYPE array<float>;
             DEFINE FIELD chunk_index ON chunk TYPE int;
-            DEFINE FIELD metadata ON chunk TYPE option<object>;
+            DEFINE FIELD OVERWRITE metadata ON chunk FLEXIBLE TYPE option<object>;
             DEFINE FIELD created_at ON chunk TYPE datetime DEFAULT time::now();
 
             -- HNSW vector index for semantic search
-            DEFINE INDEX idx_chunk_embedding ON chunk FIELDS embedding
-                HNSW DIMENSION {dimension} DIST {distance};
+            DEFINE INDEX {embedding_index};
 
             -- Index for document lookup
             DEFINE INDEX idx_chunk_document ON chunk FIELDS document_id;
+
+            -- BM25 full-text index for lexical search; splitting on blanks
+            -- and punctuation keeps tokens like E0425 or snake_case whole
+            DEFINE ANALYZER chunk_text TOKENIZERS blank, punct FILTERS lowercase, ascii;
+            DEFINE INDEX idx_chunk_content ON chunk FIELDS content
+                SEARCH ANALYZER chunk_text BM25;
             "#
         ))
         .await?;
 
         tracing::info!(
-            "Vector schema initialized (dimension={}, distance={})",
+            "Vector schema initialized (dimension={}, distance={}, m={}, efc={})",
             dimension,
-            distance
+            distance,
+            config.vector_config.m,
+            config.vector_config.ef_construction
         );
         Ok(())
     }
 
+    /// Definition of the HNSW index on chunk embeddings, after `DEFINE INDEX`
+    pub(crate) fn vector_index(config: &Config) -> String {
+        Self::hnsw_index("idx_chunk_embedding", "chunk", config)
+    }
+
+    /// Definition of the HNSW index on entity embeddings, after `DEFINE INDEX`
+    pub(crate) fn entity_vector_index(config: &Config) -> String {
+        Self::hnsw_index("idx_entity_embedding", "entity_embedding", config)
+    }
+
+    fn hnsw_index(name: &str, table: &str, config: &Config) -> String {
+        let vector_config = &config.vector_config;
+        format!(
+            "{name} ON {table} FIELDS embedding HNSW DIMENSION {} DIST {} EFC {} M {}",
+            vector_config.dimension,
+            vector_config.distance.as_surreal_str(),
+            vector_config.ef_construction,
+            vector_config.m
+        )
+    }
+
     /// Initialize knowledge graph tables
-    async fn init_graph(db: &Surreal<Db>) -> Result<()> {
-        db.query(
+    async fn init_graph(db: &Surreal<Any>, config: &Config) -> Result<()> {
+        let embedding_index = Self::entity_vector_index(config);
+
+        db.query(format!(
             r#"
             -- Entity table for knowledge graph nodes
             DEFINE TABLE entity SCHEMAFULL;
             DEFINE FIELD name ON entity TYPE string;
+            DEFINE FIELD display_name ON entity TYPE option<string>;
             DEFINE FIELD entity_type ON entity TYPE string;
             DEFINE FIELD observations ON entity TYPE array<string> DEFAULT [];
             DEFINE FIELD metadata ON entity TYPE option<object>;
@@ -100,13 +138,35 @@ impl Schema {
             DEFINE TABLE relates_to SCHEMAFULL TYPE RELATION IN entity OUT entity;
             DEFINE FIELD relation_type ON relates_to TYPE string;
             DEFINE FIELD weight ON relates_to TYPE option<float>;
+            DEFINE FIELD symmetric ON relates_to TYPE bool DEFAULT false;
             DEFINE FIELD metadata ON relates_to TYPE option<object>;
             DEFINE FIELD created_at ON relates_to TYPE datetime DEFAULT time::now();
 
             -- Index for relation type
             DEFINE INDEX idx_relation_type ON relates_to FIELDS relation_type;
-            "#,
-        )
+
+            -- Indexes for relation lookups by endpoint
+            DEFINE INDEX idx_relation_in ON relates_to FIELDS in;
+            DEFINE INDEX idx_relation_out ON relates_to FIELDS out;
+
+            -- Embeddings of entity names and observations, keyed like their entity
+            DEFINE TABLE entity_embedding SCHEMAFULL;
+            DEFINE FIELD entity ON entity_embedding TYPE record<entity>;
+            DEFINE FIELD embedding ON entity_embedding TYPE array<float>;
+            DEFINE FIELD updated_at ON entity_embedding TYPE datetime DEFAULT time::now();
+            DEFINE INDEX {embedding_index};
+
+            -- An embedding is dropped with its entity or when the text it
+            -- was computed from changes
+            DEFINE EVENT entity_embedding_stale ON entity
+                WHEN $event = "DELETE" OR ($event = "UPDATE" AND (
+                    $before.name != $after.name
+                    OR $before.display_name != $after.display_name
+                    OR $before.observations != $after.observations
+                ))
+                THEN (DELETE type::thing('entity_embedding', record::id($value.id)));
+            "#
+        ))
         .await?;
 
         tracing::info!("Knowledge graph schema initialized");
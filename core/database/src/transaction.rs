@@ -0,0 +1,327 @@
+//! Atomic batches of writes
+//!
+//! [`Database::transaction`] returns a [`Transaction`] that buffers creates
+//! (and observation appends) and sends them as one `BEGIN TRANSACTION ... COMMIT TRANSACTION` query:
+//! either every write lands or none does.
+
+use crate::documents::CreateDocument;
+use crate::graph::{CreateEntity, CreateRelation};
+use crate::{Database, DatabaseError, Result};
+use surrealdb::RecordId;
+
+/// A buffered write
+#[derive(Debug, Clone)]
+enum Operation {
+    CreateDocument(RecordId, CreateDocument),
+    CreateEntity(RecordId, CreateEntity),
+    CreateRelation(CreateRelation),
+    AddObservation(RecordId, String),
+}
+
+impl Operation {
+    /// Short description used in errors
+    fn describe(&self) -> String {
+        match self {
+            Self::CreateDocument(id, _) => format!("create_document {id}"),
+            Self::CreateEntity(_, input) => format!("create_entity '{}'", input.name),
+            Self::CreateRelation(input) => format!(
+                "create_relation {} -{}-> {}",
+                input.from, input.relation_type, input.to
+            ),
+            Self::AddObservation(id, _) => format!("add_observation {id}"),
+        }
+    }
+}
+
+/// Writes committed together or not at all
+///
+/// Records get their IDs when buffered, so relations can link entities
+/// created in the same transaction. Nothing is written before
+/// [`Transaction::commit`]; dropping the transaction discards it.
+#[must_use = "a transaction does nothing until committed"]
+pub struct Transaction<'a> {
+    db: &'a Database,
+    operations: Vec<Operation>,
+}
+
+impl Database {
+    /// Start a transaction
+    pub fn transaction(&self) -> Transaction<'_> {
+        Transaction {
+            db: self,
+            operations: Vec::new(),
+        }
+    }
+}
+
+impl Transaction<'_> {
+    /// Buffer a document creation, returning the document's ID
+    pub fn create_document(&mut self, input: CreateDocument) -> RecordId {
+        let id = RecordId::from(("document", nanoid::nanoid!().as_str()));
+        self.operations.push(Operation::CreateDocument(id.clone(), input));
+        id
+    }
+
+    /// Buffer an entity creation, returning the entity's ID
+    pub fn create_entity(&mut self, input: CreateEntity) -> RecordId {
+        let id = RecordId::from(("entity", nanoid::nanoid!().as_str()));
+        self.operations.push(Operation::CreateEntity(id.clone(), input));
+        id
+    }
+
+    /// Buffer a relation creation
+    ///
+    /// Skipped at commit when the graph (this transaction's writes included)
+    /// already holds the same relation, or its symmetric reverse for a
+    /// symmetric relation, as `knowledge_add_relation` does.
+    pub fn create_relation(&mut self, input: CreateRelation) {
+        self.operations.push(Operation::CreateRelation(input));
+    }
+
+    /// Buffer an observation appended to an existing entity
+    ///
+    /// An observation the entity already has is not added twice; a missing
+    /// entity fails the commit.
+    pub fn add_observation(&mut self, entity: RecordId, observation: impl Into<String>) {
+        self.operations.push(Operation::AddObservation(entity, observation.into()));
+    }
+
+    /// Number of buffered writes
+    pub fn len(&self) -> usize {
+        self.operations.len()
+    }
+
+    /// Whether no write is buffered
+    pub fn is_empty(&self) -> bool {
+        self.operations.is_empty()
+    }
+
+    /// Run every buffered write atomically
+    ///
+    /// On failure nothing is written and the error names the first
+    /// operation that failed.
+    pub async fn commit(self) -> Result<()> {
+        if self.operations.is_empty() {
+            return Ok(());
+        }
+
+        let mut statements = vec!["BEGIN TRANSACTION;".to_string()];
+        // Operation each statement belongs to; a keyed document takes two.
+        // BEGIN and COMMIT get no result slot, so statement results line up
+        // with this list.
+        let mut owners = Vec::new();
+        for (i, operation) in self.operations.iter().enumerate() {
+            if let Operation::CreateDocument(_, CreateDocument { key: Some(_), .. }) = operation {
+                // A soft-deleted document would keep the key taken
+                statements.push(format!("DELETE FROM document WHERE key = $key{i} AND deleted_at != NONE;"));
+                owners.push(i);
+            }
+            owners.push(i);
+            statements.push(match operation {
+                Operation::CreateDocument(..) | Operation::CreateEntity(..) => {
+                    format!("CREATE $id{i} CONTENT $content{i};")
+                }
+                Operation::CreateRelation(_) => format!(
+                    "IF (SELECT VALUE id FROM relates_to WHERE relation_type = $rel_type{i} \
+                     AND ((in = $from{i} AND out = $to{i}) \
+                     OR ($symmetric{i} AND symmetric = true AND in = $to{i} AND out = $from{i})) LIMIT 1) = [] {{ \
+                     RELATE $from{i}->relates_to->$to{i} SET relation_type = $rel_type{i}, \
+                     weight = $weight{i}, symmetric = $symmetric{i}, metadata = $metadata{i}, \
+                     created_at = time::now() }};"
+                ),
+                // Fails the transaction when the entity doesn't exist
+                Operation::AddObservation(..) => format!(
+                    "IF (UPDATE $id{i} SET observations = array::union(observations, [$observation{i}]), \
+                     updated_at = time::now() RETURN AFTER) = [] {{ THROW 'entity not found' }};"
+                ),
+            });
+        }
+        statements.push("COMMIT TRANSACTION;".to_string());
+
+        let mut query = self.db.inner().query(statements.join("\n"));
+        for (i, operation) in self.operations.iter().enumerate() {
+            query = match operation.clone() {
+                Operation::CreateDocument(id, input) => {
+                    if let Some(key) = input.key.clone() {
+                        query = query.bind((format!("key{i}"), key));
+                    }
+                    query.bind((format!("id{i}"), id)).bind((format!("content{i}"), input))
+                }
+                Operation::CreateEntity(id, input) => {
+                    query.bind((format!("id{i}"), id)).bind((format!("content{i}"), input))
+                }
+                Operation::CreateRelation(input) => query
+                    .bind((format!("from{i}"), input.from))
+                    .bind((format!("to{i}"), input.to))
+                    .bind((format!("rel_type{i}"), input.relation_type))
+                    .bind((format!("weight{i}"), input.weight))
+                    .bind((format!("symmetric{i}"), input.symmetric))
+                    .bind((format!("metadata{i}"), input.metadata)),
+                Operation::AddObservation(id, observation) => query
+                    .bind((format!("id{i}"), id))
+                    .bind((format!("observation{i}"), observation)),
+            };
+        }
+
+        let mut response = query.await?;
+        let mut errors: Vec<(usize, surrealdb::Error)> = response.take_errors().into_iter().collect();
+        if errors.is_empty() {
+            return Ok(());
+        }
+        errors.sort_by_key(|(index, _)| *index);
+
+        // Statements after or before the failing one report that they were
+        // not executed; the failing one carries the actual error
+        let (statement, error) = errors
+            .iter()
+            .find(|(_, e)| !e.to_string().contains("not executed"))
+            .unwrap_or(&errors[0]);
+        let index = owners.get(*statement).copied().unwrap_or(*statement);
+        let operation = self
+            .operations
+            .get(index)
+            .map(Operation::describe)
+            .unwrap_or_else(|| "commit".to_string());
+        Err(DatabaseError::Transaction {
+            index,
+            operation,
+            message: error.to_string(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_transaction_commits_all() {
+        let db = Database::new_memory().await.unwrap();
+
+        let mut tx = db.transaction();
+        tx.create_document(CreateDocument::new("Design notes").with_key("notes"));
+        let rust = tx.create_entity(CreateEntity::new("rust", "language"));
+        let cargo = tx.create_entity(CreateEntity::new("cargo", "tool"));
+        tx.create_relation(CreateRelation::new(rust.clone(), cargo.clone(), "uses"));
+        assert_eq!(tx.len(), 4);
+        tx.commit().await.unwrap();
+
+        assert!(db.get_document_by_key("notes").await.unwrap().is_some());
+
+        // A deleted key can be written again
+        assert!(db.soft_delete_document_by_key("notes").await.unwrap());
+        let mut tx = db.transaction();
+        tx.create_document(CreateDocument::new("Design notes v2").with_key("notes"));
+        tx.commit().await.unwrap();
+        let notes = db.get_document_by_key("notes").await.unwrap().unwrap();
+        assert_eq!(notes.content, "Design notes v2");
+
+        let entity = db.get_entity(&rust.key().to_string()).await.unwrap();
+        assert_eq!(entity.name, "rust");
+        assert!(db.find_relation(&rust, &cargo, "uses").await.unwrap().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_transaction_adds_observations() {
+        let db = Database::new_memory().await.unwrap();
+        let rust = db
+            .create_entity(CreateEntity::new("rust", "language").with_observations(vec!["fast".into()]))
+            .await
+            .unwrap()
+            .id
+            .unwrap();
+
+        let mut tx = db.transaction();
+        tx.add_observation(rust.clone(), "fast");
+        tx.add_observation(rust.clone(), "memory safe");
+        tx.commit().await.unwrap();
+        let entity = db.get_entity(&rust.key().to_string()).await.unwrap();
+        assert_eq!(entity.observations, vec!["fast", "memory safe"]);
+
+        let mut tx = db.transaction();
+        tx.create_document(CreateDocument::new("Design notes").with_key("notes"));
+        tx.add_observation(RecordId::from(("entity", "missing")), "orphan");
+        let err = tx.commit().await.unwrap_err();
+        assert!(matches!(err, DatabaseError::Transaction { index: 1, .. }));
+        assert!(db.get_document_by_key("notes").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_transaction_skips_duplicate_relations() {
+        let db = Database::new_memory().await.unwrap();
+        let rust = db.create_entity(CreateEntity::new("rust", "language")).await.unwrap().id.unwrap();
+        let cargo = db.create_entity(CreateEntity::new("cargo", "tool")).await.unwrap().id.unwrap();
+        db.create_relation(CreateRelation::new(rust.clone(), cargo.clone(), "uses"))
+            .await
+            .unwrap();
+
+        let mut tx = db.transaction();
+        // Already in the graph
+        tx.create_relation(CreateRelation::new(rust.clone(), cargo.clone(), "uses"));
+        // Buffered twice in the same transaction
+        tx.create_relation(CreateRelation::new(cargo.clone(), rust.clone(), "peer").with_symmetric(true));
+        tx.create_relation(CreateRelation::new(cargo.clone(), rust.clone(), "peer").with_symmetric(true));
+        // The symmetric edge already covers the reverse direction
+        tx.create_relation(CreateRelation::new(rust.clone(), cargo.clone(), "peer").with_symmetric(true));
+        // A different type is a different relation
+        tx.create_relation(CreateRelation::new(rust.clone(), cargo.clone(), "builds"));
+        tx.commit().await.unwrap();
+
+        assert_eq!(db.count_relations().await.unwrap(), 3);
+        assert!(db.find_relation(&rust, &cargo, "builds").await.unwrap().is_some());
+        assert!(db.find_relation(&rust, &cargo, "peer").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_transaction_reports_failing_operation_index() {
+        let db = Database::new_memory().await.unwrap();
+        db.create_entity(CreateEntity::new("rust", "language")).await.unwrap();
+
+        // Keyed documents take two statements; the reported index is the
+        // operation's position among the buffered writes
+        for position in 0..3 {
+            let mut tx = db.transaction();
+            for i in 0..position {
+                tx.create_document(CreateDocument::new("Notes").with_key(format!("notes-{position}-{i}")));
+            }
+            tx.create_entity(CreateEntity::new("rust", "language"));
+            tx.create_entity(CreateEntity::new("cargo", "tool"));
+
+            match tx.commit().await.unwrap_err() {
+                DatabaseError::Transaction { index, operation, .. } => {
+                    assert_eq!(index, position);
+                    assert_eq!(operation, "create_entity 'rust'");
+                }
+                other => panic!("unexpected error: {other}"),
+            }
+        }
+        assert_eq!(db.count_entities().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_transaction_rolls_back_on_error() {
+        let db = Database::new_memory().await.unwrap();
+        db.create_entity(CreateEntity::new("rust", "language")).await.unwrap();
+
+        let mut tx = db.transaction();
+        tx.create_document(CreateDocument::new("Design notes").with_key("notes"));
+        let cargo = tx.create_entity(CreateEntity::new("cargo", "tool"));
+        // Violates the unique (name, entity_type) index
+        let rust = tx.create_entity(CreateEntity::new("rust", "language"));
+        tx.create_relation(CreateRelation::new(rust, cargo, "uses"));
+
+        let err = tx.commit().await.unwrap_err();
+        match err {
+            DatabaseError::Transaction { index, operation, .. } => {
+                assert_eq!(index, 2);
+                assert_eq!(operation, "create_entity 'rust'");
+            }
+            other => panic!("unexpected error: {other}"),
+        }
+
+        assert!(db.get_document_by_key("notes").await.unwrap().is_none());
+        assert!(db.get_entity_by_name("cargo").await.unwrap().is_none());
+        assert_eq!(db.count_entities().await.unwrap(), 1);
+        assert_eq!(db.count_relations().await.unwrap(), 0);
+    }
+}
@@ -1,6 +1,6 @@
 //! Vector operations for semantic search
 
-use crate::{Database, DatabaseError, Result};
+use crate::{Database, DatabaseError, Result, SearchMetric};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use surrealdb::RecordId;
@@ -86,6 +86,10 @@ pub struct SearchResult {
     /// Document ID
     pub document_id: RecordId,
 
+    /// Key of the document, if it has one
+    #[serde(default)]
+    pub document_key: Option<String>,
+
     /// Chunk content
     pub content: String,
 
@@ -97,6 +101,36 @@ pub struct SearchResult {
 
     /// Additional metadata
     pub metadata: Option<serde_json::Value>,
+
+    /// Chunk embedding, only selected by [`Database::search_vectors_with_embeddings`]
+    #[serde(default)]
+    pub embedding: Option<Vec<f32>>,
+}
+
+/// Full-text (BM25) search result
+#[derive(Debug, Clone, Deserialize)]
+pub struct TextSearchResult {
+    /// Chunk ID
+    pub id: RecordId,
+
+    /// Document ID
+    pub document_id: RecordId,
+
+    /// Key of the document, if it has one
+    #[serde(default)]
+    pub document_key: Option<String>,
+
+    /// Chunk content
+    pub content: String,
+
+    /// Chunk index
+    pub chunk_index: i32,
+
+    /// BM25 relevance (unbounded, higher is better)
+    pub score: f32,
+
+    /// Additional metadata
+    pub metadata: Option<serde_json::Value>,
 }
 
 /// Vector operations
@@ -116,13 +150,62 @@ impl Database {
         chunk.ok_or_else(|| DatabaseError::Schema("Failed to create chunk".into()))
     }
 
-    /// Create multiple chunks for a document
+    /// Create multiple chunks in a single INSERT statement
     pub async fn create_chunks(&self, inputs: Vec<CreateChunk>) -> Result<Vec<Chunk>> {
-        let mut chunks = Vec::with_capacity(inputs.len());
-        for input in inputs {
-            chunks.push(self.create_chunk(input).await?);
+        if inputs.is_empty() {
+            return Ok(Vec::new());
+        }
+        self.check_embedding_dimensions(&inputs)?;
+
+        let mut result = self
+            .inner()
+            .query("INSERT INTO chunk $chunks")
+            .bind(("chunks", inputs))
+            .await?
+            .check()?;
+        Ok(result.take(0)?)
+    }
+
+    /// Replace every chunk of a document with `inputs`, atomically
+    ///
+    /// Old chunks are deleted and new ones inserted in one transaction, so
+    /// searches never see both versions or neither. Returns
+    /// (chunks removed, chunks added).
+    pub async fn replace_chunks(
+        &self,
+        document_id: &RecordId,
+        inputs: Vec<CreateChunk>,
+    ) -> Result<(usize, usize)> {
+        self.check_embedding_dimensions(&inputs)?;
+
+        let added = inputs.len();
+        let mut result = self
+            .inner()
+            .query(
+                "BEGIN TRANSACTION; \
+                 DELETE chunk WHERE document_id = $document RETURN BEFORE; \
+                 IF array::len($chunks) > 0 { INSERT INTO chunk $chunks RETURN NONE; }; \
+                 COMMIT TRANSACTION;",
+            )
+            .bind(("document", document_id.clone()))
+            .bind(("chunks", inputs))
+            .await?
+            .check()?;
+
+        let removed: Vec<Chunk> = result.take(0)?;
+        Ok((removed.len(), added))
+    }
+
+    /// Error unless every embedding has the configured dimension
+    fn check_embedding_dimensions(&self, inputs: &[CreateChunk]) -> Result<()> {
+        let expected_dim = self.config().vector_config.dimension;
+        match inputs.iter().find(|i| i.embedding.len() != expected_dim) {
+            Some(input) => Err(DatabaseError::DimensionMismatch {
+                expected: expected_dim,
+                got: input.embedding.len(),
+            }),
+            None => Ok(()),
         }
-        Ok(chunks)
     }
 
     /// Search for similar chunks using vector similarity
@@ -131,6 +214,92 @@ impl Database {
         query_embedding: &[f32],
         limit: usize,
         min_score: Option<f32>,
+    ) -> Result<Vec<SearchResult>> {
+        self.search_vectors_filtered(query_embedding, limit, min_score, None)
+            .await
+    }
+
+    /// Search for similar chunks whose document metadata matches `filter`
+    ///
+    /// The filter (see [`crate::filter`] for operators) is evaluated inside
+    /// the KNN query against the parent document's `metadata` and `tags`, so
+    /// `limit` counts matching chunks only. When fewer than `limit` chunks
+    /// match, all of them are returned; results are never padded with
+    /// non-matching chunks. `min_score` is applied afterwards and can drop
+    /// more.
+    ///
+    /// With an HNSW walk (see [`Self::search_vectors_with_metric`]), a
+    /// filter matching few chunks can exhaust the candidate list first; the
+    /// search then scores the matching chunks exactly, so a selective filter
+    /// costs speed, not recall.
+    pub async fn search_vectors_filtered(
+        &self,
+        query_embedding: &[f32],
+        limit: usize,
+        min_score: Option<f32>,
+        filter: Option<&serde_json::Value>,
+    ) -> Result<Vec<SearchResult>> {
+        let metric = self.config().vector_config.distance.into();
+        self.knn_search(query_embedding, limit, min_score, filter, metric, KnnOptions::default())
+            .await
+    }
+
+    /// [`Self::search_vectors_filtered`] ranked by `metric` instead of the
+    /// index metric
+    ///
+    /// `distance` is the metric's distance (`1 - dot` for [`SearchMetric::Dot`]).
+    /// Fails with [`DatabaseError::UnsupportedMetric`] unless the index
+    /// serves the metric (see [`crate::VectorConfig::serves`]).
+    ///
+    /// With `ef_search`, a search ranked by the index metric walks the HNSW
+    /// graph with that candidate list size instead of scoring every chunk:
+    /// much faster on large tables, at the cost of recall when `ef_search`
+    /// is small. Values of 40 - 100 usually keep recall above 95%; keep it
+    /// at least `limit`. Other metrics always score every chunk.
+    pub async fn search_vectors_with_metric(
+        &self,
+        query_embedding: &[f32],
+        limit: usize,
+        filter: Option<&serde_json::Value>,
+        metric: SearchMetric,
+        ef_search: Option<usize>,
+    ) -> Result<Vec<SearchResult>> {
+        let options = KnnOptions {
+            ef_search,
+            with_embeddings: false,
+        };
+        self.knn_search(query_embedding, limit, None, filter, metric, options)
+            .await
+    }
+
+    /// [`Self::search_vectors_with_metric`], also returning each chunk's embedding
+    ///
+    /// For callers that compare results with each other (e.g. diversity
+    /// re-ranking) without embedding the chunks again.
+    pub async fn search_vectors_with_embeddings(
+        &self,
+        query_embedding: &[f32],
+        limit: usize,
+        filter: Option<&serde_json::Value>,
+        metric: SearchMetric,
+        ef_search: Option<usize>,
+    ) -> Result<Vec<SearchResult>> {
+        let options = KnnOptions {
+            ef_search,
+            with_embeddings: true,
+        };
+        self.knn_search(query_embedding, limit, None, filter, metric, options)
+            .await
+    }
+
+    async fn knn_search(
+        &self,
+        query_embedding: &[f32],
+        limit: usize,
+        min_score: Option<f32>,
+        filter: Option<&serde_json::Value>,
+        metric: SearchMetric,
+        options: KnnOptions,
     ) -> Result<Vec<SearchResult>> {
         // Validate embedding dimension
         let expected_dim = self.config().vector_config.dimension;
@@ -141,42 +310,195 @@ impl Database {
             });
         }
 
-        let distance = self.config().vector_config.distance.as_surreal_str();
+        let vector_config = &self.config().vector_config;
+        if !vector_config.serves(metric) {
+            return Err(DatabaseError::UnsupportedMetric(format!(
+                "the index ranks by {}{} and cannot serve {}",
+                SearchMetric::from(vector_config.distance).name(),
+                if vector_config.normalized { "" } else { " over unnormalized vectors" },
+                metric.name()
+            )));
+        }
+
+        let filter = filter
+            .map(|f| crate::filter::compile(f, "document_id.metadata"))
+            .transpose()?;
+
+        // A numeric second KNN parameter walks the HNSW graph, which only
+        // ranks by the index metric; a metric name scores chunks exactly
+        let ef_search = options
+            .ef_search
+            .filter(|_| metric == SearchMetric::from(vector_config.distance));
+
+        let mut results = self
+            .knn_select(query_embedding, limit, filter.as_ref(), metric, ef_search, options.with_embeddings)
+            .await?;
+
+        // A selective filter can leave the graph walk without enough
+        // matching candidates; score the matching chunks exactly instead of
+        // silently returning fewer
+        if ef_search.is_some() && filter.is_some() && results.len() < limit {
+            tracing::debug!(
+                found = results.len(),
+                limit,
+                "Filtered HNSW search came up short, rescoring matching chunks exactly"
+            );
+            results = self
+                .knn_select(query_embedding, limit, filter.as_ref(), metric, None, options.with_embeddings)
+                .await?;
+        }
+
+        // Filter by minimum score if specified
+        if let Some(min) = min_score {
+            results.retain(|r| {
+                // For cosine, distance is 1 - similarity, so lower is better
+                // Convert to similarity score
+                let similarity = 1.0 - r.distance;
+                similarity >= min
+            });
+        }
+
+        Ok(results)
+    }
+
+    /// Run one KNN query: an HNSW walk with `ef_search`, else an exact scan
+    async fn knn_select(
+        &self,
+        query_embedding: &[f32],
+        limit: usize,
+        filter: Option<&crate::filter::CompiledFilter>,
+        metric: SearchMetric,
+        ef_search: Option<usize>,
+        with_embeddings: bool,
+    ) -> Result<Vec<SearchResult>> {
+        let condition = filter
+            .map(|f| format!("AND {}", f.condition))
+            .unwrap_or_default();
+        let embedding = if with_embeddings { "embedding," } else { "" };
+
+        // The KNN operator has no dot product; score every chunk instead
+        let (distance, selection) = match (ef_search, metric.knn_operator()) {
+            (Some(ef), _) => (
+                "vector::distance::knn()",
+                format!("embedding <|{limit},{}|> $embedding {condition} ORDER BY distance", ef.max(limit)),
+            ),
+            (None, Some(operator)) => (
+                "vector::distance::knn()",
+                format!("embedding <|{limit},{operator}|> $embedding {condition} ORDER BY distance"),
+            ),
+            (None, None) => (
+                "1 - vector::dot(embedding, $embedding)",
+                format!("embedding != NONE {condition} ORDER BY distance LIMIT {limit}"),
+            ),
+        };
 
-        // Build query with KNN operator
         let query = format!(
             r#"
             SELECT
                 id,
                 document_id,
+                document_id.key AS document_key,
                 content,
                 chunk_index,
                 metadata,
-                vector::distance::knn() AS distance
+                {embedding}
+                {distance} AS distance
             FROM chunk
-            WHERE embedding <|{limit},{distance}|> $embedding
-            ORDER BY distance
+            WHERE {selection}
             "#
         );
 
-        let mut result = self
+        let mut query = self
             .inner()
             .query(&query)
-            .bind(("embedding", query_embedding.to_vec()))
-            .await?;
+            .bind(("embedding", query_embedding.to_vec()));
+        for binding in filter.map(|f| f.bindings.clone()).unwrap_or_default() {
+            query = query.bind(binding);
+        }
+        let mut result = query.await?;
 
-        let mut results: Vec<SearchResult> = result.take(0)?;
+        Ok(result.take(0)?)
+    }
 
-        // Filter by minimum score if specified
-        if let Some(min) = min_score {
-            results.retain(|r| {
-                // For cosine, distance is 1 - similarity, so lower is better
-                // Convert to similarity score
-                let similarity = 1.0 - r.distance;
-                similarity >= min
-            });
+    /// Count the chunks a vector search with `filter` would examine
+    ///
+    /// Without `ef_search` the KNN query names its distance metric, so
+    /// SurrealDB scores every chunk that passes the filter exactly instead
+    /// of walking the HNSW graph; this is that candidate count.
+    pub async fn count_chunks_filtered(&self, filter: Option<&serde_json::Value>) -> Result<usize> {
+        let filter = filter
+            .map(|f| crate::filter::compile(f, "document_id.metadata"))
+            .transpose()?;
+        let condition = filter
+            .as_ref()
+            .map(|f| format!("WHERE {}", f.condition))
+            .unwrap_or_default();
+
+        let mut query = self
+            .inner()
+            .query(format!("SELECT count() FROM chunk {condition} GROUP ALL"));
+        for binding in filter.map(|f| f.bindings).unwrap_or_default() {
+            query = query.bind(binding);
+        }
+        let mut result = query.await?;
+
+        #[derive(Deserialize)]
+        struct CountResult {
+            count: usize,
+        }
+
+        let counts: Vec<CountResult> = result.take(0)?;
+        Ok(counts.first().map(|c| c.count).unwrap_or(0))
+    }
+
+    /// Search chunk text with the BM25 full-text index
+    ///
+    /// Ranks chunks containing the query's terms, best first. Like
+    /// [`Database::search_vectors_filtered`], `filter` applies to the parent
+    /// document's `metadata`. The index is maintained by SurrealDB, so it
+    /// follows chunk inserts and deletes.
+    pub async fn search_chunks_text(
+        &self,
+        query: &str,
+        limit: usize,
+        filter: Option<&serde_json::Value>,
+    ) -> Result<Vec<TextSearchResult>> {
+        let filter = filter
+            .map(|f| crate::filter::compile(f, "document_id.metadata"))
+            .transpose()?;
+        let condition = filter
+            .as_ref()
+            .map(|f| format!("AND {}", f.condition))
+            .unwrap_or_default();
+
+        let query_text = format!(
+            r#"
+            SELECT
+                id,
+                document_id,
+                document_id.key AS document_key,
+                content,
+                chunk_index,
+                metadata,
+                search::score(1) AS score
+            FROM chunk
+            WHERE content @1@ $query {condition}
+            ORDER BY score DESC
+            LIMIT $limit
+            "#
+        );
+
+        let mut db_query = self
+            .inner()
+            .query(&query_text)
+            .bind(("query", query.to_string()))
+            .bind(("limit", limit));
+        for binding in filter.map(|f| f.bindings).unwrap_or_default() {
+            db_query = db_query.bind(binding);
         }
+        let mut result = db_query.await?.check()?;
 
+        let results: Vec<TextSearchResult> = result.take(0)?;
         Ok(results)
     }
 
@@ -206,6 +528,88 @@ impl Database {
         Ok(deleted.len())
     }
 
+    /// Delete documents whose metadata matches `filter`, with their chunks
+    ///
+    /// Runs as one query (chunks first, while their document links still
+    /// resolve). Returns the number of chunks removed, so re-running the same
+    /// filter returns 0.
+    pub async fn delete_documents_by_filter(&self, filter: &serde_json::Value) -> Result<usize> {
+        let (conditions, bindings) = crate::filter::compile_each(filter, &["document_id.metadata", "metadata"])?;
+
+        let mut query = self.inner().query(format!(
+            "DELETE chunk WHERE {} RETURN BEFORE; DELETE document WHERE {};",
+            conditions[0], conditions[1]
+        ));
+        for binding in bindings {
+            query = query.bind(binding);
+        }
+        let mut result = query.await?.check()?;
+
+        let deleted: Vec<Chunk> = result.take(0)?;
+        Ok(deleted.len())
+    }
+
+    /// Rebuild the HNSW index from the stored embeddings
+    ///
+    /// Heavy insert/delete churn degrades the graph and search recall drops;
+    /// rebuilding restores it without re-embedding anything. Returns the
+    /// number of chunks in the rebuilt index.
+    pub async fn rebuild_vector_index(&self) -> Result<usize> {
+        self.inner()
+            .query("REBUILD INDEX idx_chunk_embedding ON chunk")
+            .await?
+            .check()?;
+        self.count_chunks().await
+    }
+
+    /// Recreate the HNSW index with the configured `m` and `ef_construction`
+    ///
+    /// The index keeps the parameters it was created with, so changing them
+    /// on an existing database needs this explicit, full reindex of the
+    /// stored embeddings (no re-embedding). Returns the number of chunks in
+    /// the new index.
+    pub async fn redefine_vector_index(&self) -> Result<usize> {
+        self.inner()
+            .query(format!("DEFINE INDEX OVERWRITE {}", crate::Schema::vector_index(self.config())))
+            .await?
+            .check()?;
+        self.count_chunks().await
+    }
+
+    /// `(m, ef_construction)` the HNSW index was built with
+    ///
+    /// `None` when the index is missing or its definition can't be read.
+    pub async fn vector_index_params(&self) -> Result<Option<(usize, usize)>> {
+        #[derive(Deserialize)]
+        struct TableInfo {
+            indexes: std::collections::HashMap<String, String>,
+        }
+
+        let mut result = self.inner().query("INFO FOR TABLE chunk").await?.check()?;
+        let info: Option<TableInfo> = result.take(0)?;
+        Ok(info
+            .and_then(|info| info.indexes.get("idx_chunk_embedding").cloned())
+            .and_then(|definition| hnsw_params(&definition)))
+    }
+
+    /// Count the chunks of a document
+    pub async fn count_chunks_by_document(&self, document_id: &RecordId) -> Result<usize> {
+        let doc_key = document_id.key().to_string();
+        let mut result = self
+            .inner()
+            .query("SELECT count() FROM chunk WHERE document_id = type::thing('document', $doc_key) GROUP ALL")
+            .bind(("doc_key", doc_key))
+            .await?;
+
+        #[derive(Deserialize)]
+        struct CountResult {
+            count: usize,
+        }
+
+        let counts: Vec<CountResult> = result.take(0)?;
+        Ok(counts.first().map(|c| c.count).unwrap_or(0))
+    }
+
     /// Count chunks
     pub async fn count_chunks(&self) -> Result<usize> {
         let mut result = self
@@ -223,6 +627,30 @@ impl Database {
     }
 }
 
+/// How [`Database::knn_search`] selects and returns chunks
+#[derive(Debug, Clone, Copy, Default)]
+struct KnnOptions {
+    /// HNSW candidate list size, `None` to score every chunk
+    ef_search: Option<usize>,
+
+    /// Select each chunk's embedding
+    with_embeddings: bool,
+}
+
+/// `(m, ef_construction)` of an HNSW index definition
+///
+/// Reads the `M` and `EFC` clauses of `DEFINE INDEX ... HNSW ...`.
+fn hnsw_params(definition: &str) -> Option<(usize, usize)> {
+    let tokens: Vec<&str> = definition.split_whitespace().collect();
+    let value = |clause: &str| {
+        tokens
+            .windows(2)
+            .find(|pair| pair[0].eq_ignore_ascii_case(clause))
+            .and_then(|pair| pair[1].parse().ok())
+    };
+    Some((value("M")?, value("EFC")?))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -265,6 +693,96 @@ mod tests {
         assert!(results[0].content.contains("Rust"));
     }
 
+    #[tokio::test]
+    async fn test_replace_chunks() {
+        let db = Database::new_memory().await.unwrap();
+        let doc_id = db.create_document(CreateDocument::new("Doc")).await.unwrap().id.unwrap();
+        let other = db.create_document(CreateDocument::new("Other")).await.unwrap().id.unwrap();
+
+        let chunks = |prefix: &str, count: i32, doc: &RecordId| -> Vec<CreateChunk> {
+            (0..count)
+                .map(|i| CreateChunk::new(doc.clone(), format!("{prefix} {i}"), make_embedding(i as f32), i))
+                .collect()
+        };
+        db.create_chunks(chunks("old", 3, &doc_id)).await.unwrap();
+        db.create_chunks(chunks("other", 1, &other)).await.unwrap();
+
+        assert_eq!(db.replace_chunks(&doc_id, chunks("new", 2, &doc_id)).await.unwrap(), (3, 2));
+        let current = db.get_chunks_by_document(&doc_id).await.unwrap();
+        assert_eq!(current.len(), 2);
+        assert!(current.iter().all(|c| c.content.starts_with("new")));
+        assert_eq!(db.count_chunks().await.unwrap(), 3);
+
+        // A rejected replacement leaves the old chunks in place
+        let wrong = vec![CreateChunk::new(doc_id.clone(), "bad", vec![0.1; 3], 0)];
+        assert!(matches!(
+            db.replace_chunks(&doc_id, wrong).await,
+            Err(DatabaseError::DimensionMismatch { .. })
+        ));
+        assert_eq!(db.get_chunks_by_document(&doc_id).await.unwrap().len(), 2);
+
+        assert_eq!(db.replace_chunks(&doc_id, vec![]).await.unwrap(), (2, 0));
+        assert_eq!(db.count_chunks().await.unwrap(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_rebuild_vector_index() {
+        let db = Database::new_memory().await.unwrap();
+        let keep = db.create_document(CreateDocument::new("Kept")).await.unwrap().id.unwrap();
+        let churn = db.create_document(CreateDocument::new("Churned")).await.unwrap().id.unwrap();
+
+        for i in 0..20 {
+            let doc_id = if i % 4 == 0 { keep.clone() } else { churn.clone() };
+            let input = CreateChunk::new(doc_id, format!("chunk {i}"), make_embedding(i as f32), i);
+            db.create_chunk(input).await.unwrap();
+        }
+        db.delete_chunks_by_document(&churn).await.unwrap();
+
+        assert_eq!(db.rebuild_vector_index().await.unwrap(), 5);
+
+        // Searches still find the surviving chunks
+        let results = db.search_vectors(&make_embedding(8.0), 1, None).await.unwrap();
+        assert_eq!(results[0].content, "chunk 8");
+    }
+
+    #[tokio::test]
+    async fn test_hnsw_parameters() {
+        let db = Database::new(crate::Config::memory().with_hnsw(8, 64)).await.unwrap();
+        assert_eq!(db.vector_index_params().await.unwrap(), Some((8, 64)));
+
+        let doc_id = db.create_document(CreateDocument::new("Doc")).await.unwrap().id.unwrap();
+        for i in 0..10 {
+            let input = CreateChunk::new(doc_id.clone(), format!("chunk {i}"), make_embedding(i as f32), i);
+            db.create_chunk(input).await.unwrap();
+        }
+
+        // Walking the graph finds what the exact scan finds
+        let query = make_embedding(4.0);
+        let exact = db.search_vectors_with_metric(&query, 3, None, SearchMetric::Cosine, None).await.unwrap();
+        let hnsw = db.search_vectors_with_metric(&query, 3, None, SearchMetric::Cosine, Some(40)).await.unwrap();
+        assert_eq!(hnsw[0].content, "chunk 4");
+        assert_eq!(hnsw[0].content, exact[0].content);
+
+        // An index built with other parameters is only replaced on request
+        db.query(
+            "DEFINE INDEX OVERWRITE idx_chunk_embedding ON chunk FIELDS embedding \
+             HNSW DIMENSION 384 DIST COSINE EFC 100 M 4",
+        )
+        .await
+        .unwrap();
+        assert_eq!(db.vector_index_params().await.unwrap(), Some((4, 100)));
+        assert_eq!(db.redefine_vector_index().await.unwrap(), 10);
+        assert_eq!(db.vector_index_params().await.unwrap(), Some((8, 64)));
+    }
+
+    #[test]
+    fn test_hnsw_params() {
+        let definition = "DEFINE INDEX idx_chunk_embedding ON chunk FIELDS embedding \
+                          HNSW DIMENSION 384 DIST COSINE TYPE F64 EFC 150 M 12 M0 24 LM 0.40242960438184466f";
+        assert_eq!(hnsw_params(definition), Some((12, 150)));
+        assert_eq!(hnsw_params("DEFINE INDEX idx ON chunk FIELDS document_id"), None);
+    }
+
     #[tokio::test]
     async fn test_dimension_validation() {
         let db = Database::new_memory().await.unwrap();
@@ -331,10 +849,216 @@ mod tests {
             db.create_chunk(input).await.unwrap();
         }
 
+        assert_eq!(db.count_chunks_by_document(&doc_id).await.unwrap(), 3);
         let deleted = db.delete_chunks_by_document(&doc_id).await.unwrap();
         assert_eq!(deleted, 3);
+        assert_eq!(db.count_chunks_by_document(&doc_id).await.unwrap(), 0);
 
         let remaining = db.get_chunks_by_document(&doc_id).await.unwrap();
         assert!(remaining.is_empty());
     }
+
+    #[tokio::test]
+    async fn test_delete_documents_by_filter() {
+        let db = Database::new_memory().await.unwrap();
+
+        for (project, chunks) in [("old", 2), ("old", 1), ("new", 2)] {
+            let doc = db
+                .create_document(
+                    CreateDocument::new(project).with_metadata(serde_json::json!({ "project": project })),
+                )
+                .await
+                .unwrap();
+            let doc_id = doc.id.unwrap();
+            for i in 0..chunks {
+                let input = CreateChunk::new(doc_id.clone(), project, make_embedding(i as f32), i);
+                db.create_chunk(input).await.unwrap();
+            }
+        }
+
+        let filter = serde_json::json!({ "project": "old" });
+        assert_eq!(db.delete_documents_by_filter(&filter).await.unwrap(), 3);
+        assert_eq!(db.count_chunks().await.unwrap(), 2);
+        assert_eq!(db.count_documents(None).await.unwrap(), 1);
+
+        // Idempotent
+        assert_eq!(db.delete_documents_by_filter(&filter).await.unwrap(), 0);
+        assert_eq!(db.count_chunks().await.unwrap(), 2);
+
+        // A document without chunks is only reached through its own condition
+        db.create_document(
+            CreateDocument::new("draft")
+                .with_tag("draft")
+                .with_metadata(serde_json::json!({ "project": "draft" })),
+        )
+        .await
+        .unwrap();
+        let filter = serde_json::json!({ "$tags": "draft", "$or": [{ "project": "draft" }, { "project": "gone" }] });
+        assert_eq!(db.delete_documents_by_filter(&filter).await.unwrap(), 0);
+        assert_eq!(db.count_documents(None).await.unwrap(), 1);
+        assert_eq!(db.count_chunks().await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_search_vectors_filtered() {
+        let db = Database::new_memory().await.unwrap();
+
+        for (project, lang, seed) in [("alpha", "rust", 1.0), ("beta", "go", 1.01), ("gamma", "rust", 1.02)] {
+            let doc = db
+                .create_document(
+                    CreateDocument::new(project)
+                        .with_key(project)
+                        .with_metadata(serde_json::json!({ "project": project, "lang": lang })),
+                )
+                .await
+                .unwrap();
+            let input = CreateChunk::new(doc.id.unwrap(), project, make_embedding(seed), 0);
+            db.create_chunk(input).await.unwrap();
+        }
+
+        let query = make_embedding(1.0);
+        let filter = serde_json::json!({ "project": "beta" });
+        let results = db.search_vectors_filtered(&query, 1, None, Some(&filter)).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].content, "beta");
+        assert_eq!(results[0].document_key.as_deref(), Some("beta"));
+
+        // The limit applies after filtering
+        let filter = serde_json::json!({ "lang": { "$in": ["rust"] } });
+        let results = db.search_vectors_filtered(&query, 2, None, Some(&filter)).await.unwrap();
+        let contents: Vec<&str> = results.iter().map(|r| r.content.as_str()).collect();
+        assert_eq!(contents, vec!["alpha", "gamma"]);
+
+        let invalid = serde_json::json!({ "lang": { "$regex": "r" } });
+        let result = db.search_vectors_filtered(&query, 2, None, Some(&invalid)).await;
+        assert!(matches!(result, Err(DatabaseError::InvalidFilter(_))));
+
+        assert_eq!(db.count_chunks_filtered(None).await.unwrap(), 3);
+        let filter = serde_json::json!({ "lang": "rust" });
+        assert_eq!(db.count_chunks_filtered(Some(&filter)).await.unwrap(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_search_vectors_selective_filter() {
+        let db = Database::new_memory().await.unwrap();
+
+        // 40 chunks near the query in one session, 2 far from it in another
+        let busy = db
+            .create_document(CreateDocument::new("Busy").with_tags(vec!["session-1".into()]))
+            .await
+            .unwrap()
+            .id
+            .unwrap();
+        let quiet = db
+            .create_document(CreateDocument::new("Quiet").with_tags(vec!["session-2".into(), "notes".into()]))
+            .await
+            .unwrap()
+            .id
+            .unwrap();
+        for i in 0..40 {
+            let input = CreateChunk::new(busy.clone(), format!("busy {i}"), make_embedding(1.0 + i as f32 * 0.01), i);
+            db.create_chunk(input).await.unwrap();
+        }
+        for i in 0..2 {
+            let input = CreateChunk::new(quiet.clone(), format!("quiet {i}"), make_embedding(50.0 + i as f32), i);
+            db.create_chunk(input).await.unwrap();
+        }
+
+        let query = make_embedding(1.0);
+        let filter = serde_json::json!({ "$tags": "session-2" });
+        let exact = db
+            .search_vectors_with_metric(&query, 1, Some(&filter), SearchMetric::Cosine, None)
+            .await
+            .unwrap();
+        // A narrow graph walk would only meet session-1 chunks
+        let hnsw = db
+            .search_vectors_with_metric(&query, 1, Some(&filter), SearchMetric::Cosine, Some(1))
+            .await
+            .unwrap();
+        assert_eq!(hnsw.len(), 1);
+        assert_eq!(hnsw[0].content, exact[0].content);
+
+        // Fewer matches than the limit: every match, nothing else
+        let results = db
+            .search_vectors_with_metric(&query, 10, Some(&filter), SearchMetric::Cosine, Some(10))
+            .await
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.content.starts_with("quiet")));
+
+        let filter = serde_json::json!({ "$tags": ["session-2", "missing"] });
+        assert!(db.search_vectors_filtered(&query, 5, None, Some(&filter)).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_search_vectors_with_metric() {
+        let unit = |seed: f32| {
+            let v = make_embedding(seed);
+            let norm = v.iter().map(|x| x * x).sum::<f32>().sqrt();
+            v.into_iter().map(|x| x / norm).collect::<Vec<f32>>()
+        };
+        let query = unit(1.0);
+
+        // Unnormalized index: only its own metric
+        let db = Database::new_memory().await.unwrap();
+        let result = db.search_vectors_with_metric(&query, 2, None, SearchMetric::Dot, None).await;
+        assert!(matches!(result, Err(DatabaseError::UnsupportedMetric(_))));
+        assert!(db.search_vectors_with_metric(&query, 2, None, SearchMetric::Cosine, None).await.is_ok());
+
+        let mut config = crate::Config::memory();
+        config.vector_config.normalized = true;
+        let db = Database::new(config).await.unwrap();
+        let doc_id = db.create_document(CreateDocument::new("Doc")).await.unwrap().id.unwrap();
+        for (i, (content, seed)) in [("near", 1.01), ("far", 2.5), ("mid", 1.3)].into_iter().enumerate() {
+            db.create_chunk(CreateChunk::new(doc_id.clone(), content, unit(seed), i as i32)).await.unwrap();
+        }
+
+        let cosine = db.search_vectors_with_metric(&query, 3, None, SearchMetric::Cosine, None).await.unwrap();
+        let dot = db.search_vectors_with_metric(&query, 3, None, SearchMetric::Dot, None).await.unwrap();
+        let contents = |results: &[SearchResult]| results.iter().map(|r| r.content.clone()).collect::<Vec<_>>();
+        assert_eq!(contents(&dot), ["near", "mid", "far"]);
+        assert_eq!(contents(&dot), contents(&cosine));
+        // On unit vectors, 1 - dot is the cosine distance
+        assert!((dot[1].distance - cosine[1].distance).abs() < 1e-4);
+
+        let result = db.search_vectors_with_metric(&query, 3, None, SearchMetric::Manhattan, None).await;
+        assert!(matches!(result, Err(DatabaseError::UnsupportedMetric(_))));
+    }
+
+    #[tokio::test]
+    async fn test_search_chunks_text() {
+        let db = Database::new_memory().await.unwrap();
+
+        for (project, content) in [
+            ("compiler", "error E0425: cannot find value in this scope"),
+            ("compiler", "error E0308: mismatched types, expected value"),
+            ("web", "the handler returns a value after E0425 was fixed"),
+        ] {
+            let doc = db
+                .create_document(
+                    CreateDocument::new(content).with_metadata(serde_json::json!({ "project": project })),
+                )
+                .await
+                .unwrap();
+            let input = CreateChunk::new(doc.id.unwrap(), content, make_embedding(1.0), 0);
+            db.create_chunk(input).await.unwrap();
+        }
+
+        let results = db.search_chunks_text("E0425", 10, None).await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert!(results.iter().all(|r| r.content.contains("E0425")));
+        assert!(results[0].score >= results[1].score && results[1].score > 0.0);
+
+        let filter = serde_json::json!({ "project": "web" });
+        let results = db.search_chunks_text("e0425", 10, Some(&filter)).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].content.starts_with("the handler"));
+
+        assert!(db.search_chunks_text("nonexistent", 10, None).await.unwrap().is_empty());
+
+        // The index follows deletes
+        let doc_id = results[0].document_id.clone();
+        db.delete_chunks_by_document(&doc_id).await.unwrap();
+        assert_eq!(db.search_chunks_text("E0425", 10, None).await.unwrap().len(), 1);
+    }
 }
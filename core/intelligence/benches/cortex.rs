@@ -0,0 +1,33 @@
+//! Benchmarks for CORTEX perception.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use whytcard_intelligence::Perceiver;
+
+/// Fixture queries covering short, technical and multi-intent inputs.
+const QUERIES: &[(&str, &str)] = &[
+    ("short", "fix the login bug"),
+    (
+        "technical",
+        "How do I configure tokio with axum to serve SSE streams and handle graceful shutdown?",
+    ),
+    (
+        "multi_intent",
+        "Create a React component with hooks for the dashboard, then write tests, refactor the API \
+         client to use async/await and document the changes in the README",
+    ),
+];
+
+fn bench_analyze(c: &mut Criterion) {
+    let perceiver = Perceiver::default();
+
+    let mut group = c.benchmark_group("perceiver");
+    for (name, query) in QUERIES {
+        group.bench_with_input(BenchmarkId::new("analyze", name), query, |b, query| {
+            b.iter(|| perceiver.analyze(query, None))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_analyze);
+criterion_main!(benches);
@@ -1,26 +1,42 @@
 //! Configuration for WhytCard Intelligence
 
+use crate::cortex::CortexConfig;
 use crate::paths::DataPaths;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::path::Path;
-use whytcard_rag::{ChunkingConfig, EmbeddingModel, SearchConfig};
+use whytcard_database::StorageMode;
+use whytcard_rag::{ChunkingConfig, EmbeddingCacheConfig, EmbeddingModel, SearchConfig};
+
+/// Embedding cache file, in the data directory
+const EMBEDDING_CACHE_FILE: &str = "embedding_cache.jsonl";
 
 /// Main configuration for Intelligence server
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct IntelligenceConfig {
-    /// Server name for MCP
+    /// Server name reported in the MCP handshake
     #[serde(default = "default_server_name")]
     pub server_name: String,
 
-    /// Server version
+    /// Server version reported in the MCP handshake
     #[serde(default = "default_version")]
     pub version: String,
 
+    /// Usage instructions sent to clients in the MCP handshake
+    /// (None = the built-in description of the tools)
+    #[serde(default)]
+    pub instructions: Option<String>,
+
     /// Namespace for data isolation (allows multiple instances)
     /// Each namespace gets its own subdirectory for data storage
     #[serde(default)]
     pub namespace: Option<String>,
 
+    /// Maximum concurrent SSE clients (0 = unlimited)
+    /// Connections beyond the limit are rejected with 503
+    #[serde(default)]
+    pub max_clients: usize,
+
     /// Data paths (optional, resolved at runtime if not set)
     #[serde(skip)]
     pub paths: Option<DataPaths>,
@@ -36,6 +52,53 @@ pub struct IntelligenceConfig {
     /// Knowledge graph settings
     #[serde(default)]
     pub knowledge: KnowledgeSettings,
+
+    /// External integration settings
+    #[serde(default)]
+    pub external: ExternalSettings,
+
+    /// Main database location
+    #[serde(default)]
+    pub database: DatabaseSettings,
+}
+
+/// Main database location
+#[derive(Clone, Default, Serialize, Deserialize)]
+pub struct DatabaseSettings {
+    /// Remote SurrealDB server (`ws://` or `wss://`) shared by several
+    /// instances (None = embedded store in the data directory)
+    #[serde(default)]
+    pub url: Option<String>,
+
+    /// Root user to sign in as on the remote server
+    #[serde(default)]
+    pub username: Option<String>,
+
+    /// Password of `username`
+    #[serde(default)]
+    pub password: Option<String>,
+}
+
+impl DatabaseSettings {
+    /// Storage mode for the remote server, if one is configured
+    pub fn remote_storage(&self) -> Option<StorageMode> {
+        self.url.as_ref().map(|url| StorageMode::Remote {
+            url: url.clone(),
+            username: self.username.clone(),
+            password: self.password.clone(),
+        })
+    }
+}
+
+impl std::fmt::Debug for DatabaseSettings {
+    // Keeps the password out of logs
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("DatabaseSettings")
+            .field("url", &self.url)
+            .field("username", &self.username)
+            .field("password", &self.password.as_ref().map(|_| "***"))
+            .finish()
+    }
 }
 
 /// RAG-specific settings
@@ -56,6 +119,48 @@ pub struct RagSettings {
     /// Auto-index new memories
     #[serde(default = "default_true")]
     pub auto_index: bool,
+
+    /// Minimum relevance (0.0 - 1.0) of search results when the caller
+    /// doesn't give one (memory_search, hybrid_search, get_context, analyze)
+    #[serde(default = "default_min_relevance")]
+    pub min_relevance: f32,
+
+    /// Fall back to full-text search of memories when semantic search fails
+    /// in memory_search (e.g. the embedder is unavailable)
+    #[serde(default = "default_true")]
+    pub search_fallback: bool,
+
+    /// Embedding cache
+    #[serde(default)]
+    pub embedding_cache: EmbeddingCacheSettings,
+}
+
+/// Embedding cache settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingCacheSettings {
+    /// Cache embeddings of indexed chunks and queries
+    #[serde(default)]
+    pub enabled: bool,
+
+    /// Maximum cached embeddings (least recently used are evicted)
+    #[serde(default = "default_embedding_cache_capacity")]
+    pub capacity: usize,
+
+    /// Keep the cache in the data directory so it survives restarts
+    #[serde(default = "default_true")]
+    pub persist: bool,
+}
+
+impl EmbeddingCacheSettings {
+    /// RAG cache config, persisted under `root` when enabled (None = disabled)
+    pub fn cache_config(&self, root: &Path) -> Option<EmbeddingCacheConfig> {
+        self.enabled.then(|| EmbeddingCacheConfig {
+            capacity: self.capacity,
+            path: self
+                .persist
+                .then(|| root.join(EMBEDDING_CACHE_FILE).to_string_lossy().to_string()),
+        })
+    }
 }
 
 /// Memory storage settings
@@ -72,10 +177,26 @@ pub struct MemorySettings {
     /// Auto-generate titles if not provided
     #[serde(default = "default_true")]
     pub auto_title: bool,
+
+    /// Soft-delete memories by default (restorable until purged)
+    #[serde(default = "default_true")]
+    pub soft_delete: bool,
+
+    /// Days a soft-deleted memory is kept before cleanup purges it
+    #[serde(default = "default_deleted_retention_days")]
+    pub deleted_retention_days: i64,
+
+    /// Hours between automatic cleanups (0 = only run cleanup on request)
+    #[serde(default)]
+    pub auto_cleanup_hours: u64,
+
+    /// Largest memory content accepted, in bytes (0 = unlimited)
+    #[serde(default = "default_max_content_bytes")]
+    pub max_content_bytes: usize,
 }
 
 /// Knowledge graph settings
-#[derive(Debug, Clone, Serialize, Deserialize, Default)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KnowledgeSettings {
     /// Maximum entities (0 = unlimited)
     #[serde(default)]
@@ -92,6 +213,103 @@ pub struct KnowledgeSettings {
     /// Allowed relation types (empty = all allowed)
     #[serde(default)]
     pub allowed_relation_types: Vec<String>,
+
+    /// Maximum entities visited by a single graph traversal (0 = unlimited)
+    #[serde(default = "default_max_nodes_visited")]
+    pub max_nodes_visited: usize,
+
+    /// Entity name normalization applied at create and lookup time
+    #[serde(default)]
+    pub name_normalization: NameNormalization,
+}
+
+/// Entity name normalization rules (all off by default, so names stay verbatim)
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct NameNormalization {
+    /// Strip leading and trailing whitespace
+    #[serde(default)]
+    pub trim: bool,
+
+    /// Lowercase names so lookups ignore case
+    #[serde(default)]
+    pub case_fold: bool,
+
+    /// Collapse runs of internal whitespace to a single space
+    #[serde(default)]
+    pub collapse_whitespace: bool,
+}
+
+impl NameNormalization {
+    /// Enable every rule
+    pub fn all() -> Self {
+        Self {
+            trim: true,
+            case_fold: true,
+            collapse_whitespace: true,
+        }
+    }
+
+    /// Whether any rule is enabled
+    pub fn is_enabled(&self) -> bool {
+        self.trim || self.case_fold || self.collapse_whitespace
+    }
+
+    /// Normalize a name into its lookup key
+    pub fn apply(&self, name: &str) -> String {
+        let mut key = if self.collapse_whitespace {
+            let mut collapsed = String::with_capacity(name.len());
+            let mut in_space = false;
+            for c in name.chars() {
+                if c.is_whitespace() {
+                    if !in_space {
+                        collapsed.push(' ');
+                    }
+                    in_space = true;
+                } else {
+                    collapsed.push(c);
+                    in_space = false;
+                }
+            }
+            collapsed
+        } else {
+            name.to_string()
+        };
+
+        if self.trim {
+            key = key.trim().to_string();
+        }
+        if self.case_fold {
+            key = key.to_lowercase();
+        }
+        key
+    }
+}
+
+/// How `external_docs` combines its providers
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum DocsFallbackMode {
+    /// Try providers in order and return the first non-empty result
+    #[default]
+    FirstNonEmpty,
+    /// Try every provider and merge all non-empty results
+    Merge,
+}
+
+/// External integration settings
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExternalSettings {
+    /// Documentation provider fallback order
+    #[serde(default = "default_docs_providers")]
+    pub docs_providers: Vec<String>,
+
+    /// Whether docs lookups stop at the first hit or merge every provider
+    #[serde(default)]
+    pub docs_mode: DocsFallbackMode,
+
+    /// Hours a resolved Context7 library ID is reused before re-resolving
+    #[serde(default = "default_context7_cache_ttl_hours")]
+    pub context7_cache_ttl_hours: u64,
 }
 
 // Default value helpers
@@ -107,16 +325,48 @@ fn default_true() -> bool {
     true
 }
 
+fn default_embedding_cache_capacity() -> usize {
+    10_000
+}
+
+fn default_min_relevance() -> f32 {
+    0.3
+}
+
+fn default_deleted_retention_days() -> i64 {
+    7
+}
+
+fn default_max_content_bytes() -> usize {
+    1024 * 1024
+}
+
+fn default_max_nodes_visited() -> usize {
+    1000
+}
+
+fn default_docs_providers() -> Vec<String> {
+    vec!["context7".to_string(), "mslearn".to_string()]
+}
+
+fn default_context7_cache_ttl_hours() -> u64 {
+    24 * 7
+}
+
 impl Default for IntelligenceConfig {
     fn default() -> Self {
         Self {
             server_name: default_server_name(),
             version: default_version(),
+            instructions: None,
             namespace: None,
+            max_clients: 0,
             paths: None,
             rag: RagSettings::default(),
             memory: MemorySettings::default(),
             knowledge: KnowledgeSettings::default(),
+            external: ExternalSettings::default(),
+            database: DatabaseSettings::default(),
         }
     }
 }
@@ -128,6 +378,19 @@ impl Default for RagSettings {
             chunking: ChunkingConfig::default(),
             search: SearchConfig::default(),
             auto_index: true,
+            min_relevance: default_min_relevance(),
+            search_fallback: true,
+            embedding_cache: EmbeddingCacheSettings::default(),
+        }
+    }
+}
+
+impl Default for EmbeddingCacheSettings {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            capacity: default_embedding_cache_capacity(),
+            persist: true,
         }
     }
 }
@@ -138,6 +401,46 @@ impl Default for MemorySettings {
             max_entries: 0, // unlimited
             semantic_search: true,
             auto_title: true,
+            soft_delete: true,
+            deleted_retention_days: default_deleted_retention_days(),
+            auto_cleanup_hours: 0,
+            max_content_bytes: default_max_content_bytes(),
+        }
+    }
+}
+
+impl MemorySettings {
+    /// Reject content larger than `max_content_bytes`
+    pub fn check_content_size(&self, content: &str) -> crate::Result<()> {
+        if self.max_content_bytes > 0 && content.len() > self.max_content_bytes {
+            return Err(crate::IntelligenceError::ContentTooLarge {
+                size: content.len(),
+                limit: self.max_content_bytes,
+            });
+        }
+        Ok(())
+    }
+}
+
+impl Default for ExternalSettings {
+    fn default() -> Self {
+        Self {
+            docs_providers: default_docs_providers(),
+            docs_mode: DocsFallbackMode::default(),
+            context7_cache_ttl_hours: default_context7_cache_ttl_hours(),
+        }
+    }
+}
+
+impl Default for KnowledgeSettings {
+    fn default() -> Self {
+        Self {
+            max_entities: 0,             // unlimited
+            max_relations_per_entity: 0, // unlimited
+            strict_relation_types: false,
+            allowed_relation_types: Vec::new(),
+            max_nodes_visited: default_max_nodes_visited(),
+            name_normalization: NameNormalization::default(),
         }
     }
 }
@@ -148,12 +451,64 @@ impl IntelligenceConfig {
         Self::default()
     }
 
+    /// Set the server name reported to MCP clients
+    pub fn with_server_name(mut self, name: impl Into<String>) -> Self {
+        self.server_name = name.into();
+        self
+    }
+
+    /// Set the server version reported to MCP clients
+    pub fn with_version(mut self, version: impl Into<String>) -> Self {
+        self.version = version.into();
+        self
+    }
+
+    /// Set the instructions sent to MCP clients
+    pub fn with_instructions(mut self, instructions: impl Into<String>) -> Self {
+        self.instructions = Some(instructions.into());
+        self
+    }
+
     /// Set namespace for data isolation
     pub fn with_namespace(mut self, namespace: impl Into<String>) -> Self {
         self.namespace = Some(namespace.into());
         self
     }
 
+    /// Set the maximum number of concurrent SSE clients (0 = unlimited)
+    pub fn with_max_clients(mut self, max_clients: usize) -> Self {
+        self.max_clients = max_clients;
+        self
+    }
+
+    /// Set the default minimum relevance of search results
+    pub fn with_min_relevance(mut self, min_relevance: f32) -> Self {
+        self.rag.min_relevance = min_relevance;
+        self
+    }
+
+    /// Enable or disable the full-text fallback of memory_search
+    pub fn with_search_fallback(mut self, enabled: bool) -> Self {
+        self.rag.search_fallback = enabled;
+        self
+    }
+
+    /// Run cleanup automatically every `hours` hours (0 = disabled)
+    pub fn with_auto_cleanup_hours(mut self, hours: u64) -> Self {
+        self.memory.auto_cleanup_hours = hours;
+        self
+    }
+
+    /// CORTEX settings derived from this config
+    pub fn cortex_config(&self) -> CortexConfig {
+        let hours = self.memory.auto_cleanup_hours;
+        CortexConfig {
+            auto_cleanup: hours > 0,
+            cleanup_interval: std::time::Duration::from_secs(hours.saturating_mul(3600)),
+            ..CortexConfig::default()
+        }
+    }
+
     /// Load config from file or create default
     pub fn load_or_default(paths: &DataPaths) -> Self {
         Self::load(&paths.config).unwrap_or_default()
@@ -217,7 +572,46 @@ mod tests {
         let config = IntelligenceConfig::default();
         assert_eq!(config.server_name, "whytcard-intelligence");
         assert!(config.rag.auto_index);
+        assert_eq!(config.rag.min_relevance, 0.3);
+        assert!(config.rag.search_fallback);
+        assert!(config.rag.embedding_cache.cache_config(Path::new("/data")).is_none());
         assert!(config.memory.semantic_search);
+        assert!(config.memory.soft_delete);
+        assert_eq!(config.memory.deleted_retention_days, 7);
+        assert_eq!(config.memory.max_content_bytes, 1024 * 1024);
+        assert_eq!(config.knowledge.max_nodes_visited, 1000);
+        assert!(!config.knowledge.name_normalization.is_enabled());
+        assert_eq!(config.external.docs_providers, vec!["context7", "mslearn"]);
+        assert_eq!(config.external.docs_mode, DocsFallbackMode::FirstNonEmpty);
+    }
+
+    #[test]
+    fn test_check_content_size() {
+        let mut memory = MemorySettings {
+            max_content_bytes: 8,
+            ..MemorySettings::default()
+        };
+        assert!(memory.check_content_size("12345678").is_ok());
+        let err = memory.check_content_size("123456789").unwrap_err();
+        assert_eq!(err.to_string(), "Content too large: 9 bytes exceeds the limit of 8 bytes");
+
+        memory.max_content_bytes = 0;
+        assert!(memory.check_content_size(&"x".repeat(10_000)).is_ok());
+    }
+
+    #[test]
+    fn test_name_normalization() {
+        let all = NameNormalization::all();
+        assert_eq!(all.apply("  Rust \t Lang "), "rust lang");
+        assert_eq!(all.apply("Rust"), all.apply("rust "));
+
+        let trim_only = NameNormalization {
+            trim: true,
+            ..Default::default()
+        };
+        assert_eq!(trim_only.apply(" Rust  Lang "), "Rust  Lang");
+
+        assert_eq!(NameNormalization::default().apply("Rust "), "Rust ");
     }
 
     #[test]
@@ -240,4 +634,69 @@ mod tests {
         assert!(toml_str.contains("server_name"));
         assert!(toml_str.contains("whytcard-intelligence"));
     }
+
+    #[test]
+    fn test_embedding_cache_from_toml() {
+        let config: IntelligenceConfig = toml::from_str(
+            r#"
+            [rag.embedding_cache]
+            enabled = true
+            capacity = 500
+            "#,
+        )
+        .unwrap();
+        let root = Path::new("/data");
+        let cache = config.rag.embedding_cache.cache_config(root).unwrap();
+        assert_eq!(cache.capacity, 500);
+        assert_eq!(cache.path, Some(root.join(EMBEDDING_CACHE_FILE).to_string_lossy().to_string()));
+
+        let memory_only = EmbeddingCacheSettings {
+            persist: false,
+            ..config.rag.embedding_cache
+        };
+        assert_eq!(memory_only.cache_config(root).unwrap().path, None);
+    }
+
+    #[test]
+    fn test_remote_database_from_toml() {
+        assert!(IntelligenceConfig::default().database.remote_storage().is_none());
+
+        let config: IntelligenceConfig = toml::from_str(
+            r#"
+            [database]
+            url = "ws://db:8000"
+            username = "root"
+            password = "secret"
+            "#,
+        )
+        .unwrap();
+        match config.database.remote_storage() {
+            Some(StorageMode::Remote { url, username, password }) => {
+                assert_eq!(url, "ws://db:8000");
+                assert_eq!(username.as_deref(), Some("root"));
+                assert_eq!(password.as_deref(), Some("secret"));
+            }
+            other => panic!("unexpected storage: {other:?}"),
+        }
+        assert!(!format!("{:?}", config).contains("secret"));
+    }
+
+    #[test]
+    fn test_server_identity_from_toml() {
+        let config: IntelligenceConfig = toml::from_str(
+            r#"
+            server_name = "acme-assistant"
+            version = "2.1.0"
+            instructions = "Use memory_search before answering."
+            "#,
+        )
+        .unwrap();
+        assert_eq!(config.server_name, "acme-assistant");
+        assert_eq!(config.version, "2.1.0");
+        assert_eq!(config.instructions.as_deref(), Some("Use memory_search before answering."));
+
+        let config = IntelligenceConfig::default();
+        assert_eq!(config.version, env!("CARGO_PKG_VERSION"));
+        assert!(config.instructions.is_none());
+    }
 }
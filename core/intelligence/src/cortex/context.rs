@@ -574,7 +574,6 @@ impl ContextManager {
     }
 
     /// Get recent history
-    #[allow(dead_code)]
     pub fn get_recent_history(&self, count: usize) -> Vec<&HistoryItem> {
         self.history.iter().rev().take(count).collect()
     }
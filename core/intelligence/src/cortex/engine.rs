@@ -12,15 +12,76 @@ use crate::paths::DataPaths;
 use super::{
     CortexConfig,
     perceiver::{Perceiver, PerceptionResult},
-    executor::{Executor, ExecutionPlan},
-    learner::Learner,
+    executor::{Executor, ExecutionPlan, ExecutionResult, ExecutionStep},
+    learner::{Learner, LearningOutcome},
     context::{ContextManager, ActiveContext},
     instructions::InstructionsManager,
 };
 use serde::{Deserialize, Serialize};
+use std::future::Future;
 use std::path::Path;
-use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
 use tokio::sync::RwLock;
+use tokio_util::sync::CancellationToken;
+
+/// Why a CORTEX process call stopped
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, schemars::JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum StoppedReason {
+    /// Every phase ran to completion
+    #[default]
+    Completed,
+    /// The overall deadline passed; the result is partial
+    Timeout,
+    /// Stopped by [`CortexEngine::abort_current`]; the result is partial
+    Aborted,
+}
+
+/// One decision CORTEX made while processing a query
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, schemars::JsonSchema)]
+pub struct DecisionPoint {
+    /// Phase the decision belongs to (perception, cognition, execution, learning)
+    pub phase: String,
+
+    /// What was decided (intent, research, plan, step, adjustment, memorize, stop)
+    pub decision: String,
+
+    /// What the decision was based on
+    pub inputs: serde_json::Value,
+
+    /// The branch taken
+    pub chosen: String,
+}
+
+impl DecisionPoint {
+    fn new(phase: &str, decision: &str, inputs: serde_json::Value, chosen: impl Into<String>) -> Self {
+        Self {
+            phase: phase.to_string(),
+            decision: decision.to_string(),
+            inputs,
+            chosen: chosen.into(),
+        }
+    }
+}
+
+/// Records removed by [`CortexEngine::cleanup`], per memory type
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CleanupReport {
+    pub episodic: usize,
+    pub semantic: usize,
+    pub procedural: usize,
+    /// Bytes returned to disk by compacting the episodic store (0 when not compacted)
+    pub bytes_reclaimed: u64,
+}
+
+impl CleanupReport {
+    /// Records removed across all memory types
+    pub fn total(&self) -> usize {
+        self.episodic + self.semantic + self.procedural
+    }
+}
 
 /// Result of a CORTEX process call
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -45,6 +106,23 @@ pub struct CortexResult {
 
     /// Suggested next actions
     pub next_actions: Vec<String>,
+
+    /// Why processing stopped
+    #[serde(default)]
+    pub stopped_reason: StoppedReason,
+
+    /// Decisions taken along the way, in order
+    #[serde(default)]
+    pub decision_trace: Vec<DecisionPoint>,
+}
+
+impl CortexResult {
+    /// Prepend the decisions taken before this result was built
+    fn after(mut self, mut trace: Vec<DecisionPoint>) -> Self {
+        trace.append(&mut self.decision_trace);
+        self.decision_trace = trace;
+        self
+    }
 }
 
 /// Execution metrics summary
@@ -68,32 +146,73 @@ pub struct ExecutionMetrics {
 
 /// The main CORTEX Engine
 pub struct CortexEngine {
-    /// Configuration (reserved for future use)
-    #[allow(dead_code)]
+    /// Configuration
     config: CortexConfig,
 
     /// Triple memory system
     memory: Arc<RwLock<TripleMemory>>,
 
     /// Perceiver module
-    perceiver: Perceiver,
+    perceiver: Arc<Perceiver>,
 
     /// Executor module
-    executor: Executor,
+    executor: Arc<Executor>,
 
     /// Learner module
-    learner: Learner,
+    learner: Arc<Learner>,
 
-    /// Context manager
+    /// Context manager (per client, see [`CortexEngine::for_client`])
     context: RwLock<ContextManager>,
 
     /// Instructions manager for loading .instructions.md files
-    instructions: RwLock<InstructionsManager>,
+    instructions: Arc<RwLock<InstructionsManager>>,
+
+    /// Stops this client's process calls (see [`CortexEngine::abort_current`])
+    abort: AbortSwitch,
 
     /// Whether initialized
     initialized: bool,
 }
 
+/// Stops the process calls in progress on one client's engine
+#[derive(Debug, Default)]
+struct AbortSwitch {
+    /// Token shared by the calls in progress, replaced once cancelled
+    token: Mutex<CancellationToken>,
+
+    /// Number of calls in progress
+    running: AtomicUsize,
+}
+
+impl AbortSwitch {
+    /// Register a call; it stops once the returned token is cancelled
+    fn begin(&self) -> RunGuard<'_> {
+        self.running.fetch_add(1, Ordering::SeqCst);
+        let token = self.token.lock().unwrap_or_else(|e| e.into_inner()).clone();
+        RunGuard { switch: self, token }
+    }
+
+    /// Cancel the calls in progress; later calls get a fresh token
+    fn abort(&self) -> bool {
+        let mut token = self.token.lock().unwrap_or_else(|e| e.into_inner());
+        token.cancel();
+        *token = CancellationToken::new();
+        self.running.load(Ordering::SeqCst) > 0
+    }
+}
+
+/// A process call in progress, unregistered when dropped
+struct RunGuard<'a> {
+    switch: &'a AbortSwitch,
+    token: CancellationToken,
+}
+
+impl Drop for RunGuard<'_> {
+    fn drop(&mut self) {
+        self.switch.running.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
 impl CortexEngine {
     /// Create a new CORTEX engine
     pub async fn new(data_path: &Path, config: CortexConfig) -> Result<Self> {
@@ -102,6 +221,11 @@ impl CortexEngine {
 
         // Initialize triple memory
         let memory = TripleMemory::new(&paths).await?;
+        memory
+            .procedural
+            .write()
+            .await
+            .set_confidence_bounds(config.confidence_floor, config.confidence_ceiling);
         let memory = Arc::new(RwLock::new(memory));
 
         // Create modules
@@ -126,37 +250,143 @@ impl CortexEngine {
         Ok(Self {
             config,
             memory,
-            perceiver,
-            executor,
-            learner,
+            perceiver: Arc::new(perceiver),
+            executor: Arc::new(executor),
+            learner: Arc::new(learner),
             context,
-            instructions: RwLock::new(instructions_mgr),
+            instructions: Arc::new(RwLock::new(instructions_mgr)),
+            abort: AbortSwitch::default(),
             initialized: true,
         })
     }
 
+    /// Create a view of this engine for another client
+    ///
+    /// The view shares memory, modules and instructions with this engine but
+    /// has its own context (session, workspace, query history) and abort
+    /// switch, so sessions of concurrent clients don't leak into each other.
+    pub fn for_client(&self) -> Self {
+        Self {
+            config: self.config.clone(),
+            memory: Arc::clone(&self.memory),
+            perceiver: Arc::clone(&self.perceiver),
+            executor: Arc::clone(&self.executor),
+            learner: Arc::clone(&self.learner),
+            context: RwLock::new(ContextManager::new()),
+            instructions: Arc::clone(&self.instructions),
+            abort: AbortSwitch::default(),
+            initialized: self.initialized,
+        }
+    }
+
+    /// Stop the process calls in progress on this engine
+    ///
+    /// Each call stops at its next step boundary (between phases, or
+    /// between execution steps) and returns a partial result with
+    /// [`StoppedReason::Aborted`]. Calls started afterwards are unaffected.
+    /// Returns whether a call was in progress.
+    pub fn abort_current(&self) -> bool {
+        let aborted = self.abort.abort();
+        if aborted {
+            tracing::info!("CORTEX processing abort requested");
+        }
+        aborted
+    }
+
     /// Process a query through the full CORTEX pipeline
-    pub async fn process(&self, query: &str, _context: Option<serde_json::Value>) -> Result<CortexResult> {
-        let start_time = std::time::Instant::now();
+    pub async fn process(&self, query: &str, context: Option<serde_json::Value>) -> Result<CortexResult> {
+        self.process_with_timeout(query, context, None).await
+    }
+
+    /// Process a query under an overall deadline (`None` = no limit)
+    ///
+    /// When the deadline passes, the phase in progress is abandoned and a
+    /// partial result with [`StoppedReason::Timeout`] is returned. Perception
+    /// is always present; execution metrics only once execution finished.
+    /// [`Self::abort_current`] stops the call the same way, at the next step
+    /// boundary.
+    pub async fn process_with_timeout(
+        &self,
+        query: &str,
+        context: Option<serde_json::Value>,
+        timeout: Option<Duration>,
+    ) -> Result<CortexResult> {
+        let run = self.abort.begin();
+        self.process_until(query, context, timeout, &run.token).await
+    }
+
+    /// Process a query until the deadline passes or `abort` is cancelled
+    async fn process_until(
+        &self,
+        query: &str,
+        _context: Option<serde_json::Value>,
+        timeout: Option<Duration>,
+        abort: &CancellationToken,
+    ) -> Result<CortexResult> {
+        let start_time = Instant::now();
+        let deadline = timeout.map(|t| start_time + t);
 
         tracing::info!("CORTEX processing: {}", query);
 
         // 1. PERCEPTION - Analyze and understand (without aggregated context initially)
         let perception = self.perceiver.analyze_simple(query);
         tracing::debug!("Perception: intent={:?}, confidence={}", perception.intent, perception.confidence);
+        let mut trace = perception_decisions(&perception);
 
         // 2. COGNITION - Memory retrieval and planning
-        let plan = self.cognition(&perception).await?;
+        if abort.is_cancelled() {
+            return Ok(self
+                .stopped(query, perception, "cognition", None, start_time, StoppedReason::Aborted)
+                .await
+                .after(trace));
+        }
+        let plan = match Self::before_deadline(deadline, self.cognition(&perception)).await {
+            Some(plan) => plan?,
+            None => {
+                return Ok(self
+                    .stopped(query, perception, "cognition", None, start_time, StoppedReason::Timeout)
+                    .await
+                    .after(trace))
+            }
+        };
         let research_performed = perception.needs_research;
         tracing::debug!("Plan created: {} steps", plan.steps.len());
-
-        // 3. ACTION - Execute with OODA
-        let execution = self.executor.execute(plan).await?;
+        trace.push(plan_decision(&plan));
+        let planned_steps = plan.steps.clone();
+
+        // 3. ACTION - Execute with OODA, checking for an abort between steps
+        let execution = match Self::before_deadline(deadline, self.executor.execute_until(plan, abort)).await {
+            Some(execution) => execution?,
+            None => {
+                return Ok(self
+                    .stopped(query, perception, "execution", None, start_time, StoppedReason::Timeout)
+                    .await
+                    .after(trace))
+            }
+        };
         tracing::debug!("Execution: success={}, steps={}", execution.success, execution.successful_steps);
+        trace.extend(execution_decisions(&planned_steps, &execution));
+        if abort.is_cancelled() {
+            return Ok(self
+                .stopped(query, perception, "execution", Some(&execution), start_time, StoppedReason::Aborted)
+                .await
+                .after(trace));
+        }
 
-        // 4. REFLECTION - Learn and improve
-        let learning = self.learner.reflect(&execution, &perception).await?;
+        // 4. REFLECTION - Learn and improve, under this client's session
+        let session_id = self.context.read().await.get_context().session_id.clone();
+        let reflection = self.learner.reflect(&execution, &perception, session_id.as_deref());
+        let learning = match Self::before_deadline(deadline, reflection).await {
+            Some(learning) => learning?,
+            None => {
+                return Ok(self
+                    .stopped(query, perception, "learning", Some(&execution), start_time, StoppedReason::Timeout)
+                    .await
+                    .after(trace))
+            }
+        };
         tracing::debug!("Learning: {} insights, {} memory updates", learning.insights.len(), learning.memory_updates.len());
+        trace.push(learning_decision(&learning));
 
         // Record in context
         {
@@ -183,11 +413,82 @@ impl CortexEngine {
             insights: learning.insights.iter().map(|i| i.description.clone()).collect(),
             confidence: learning.success_rate,
             next_actions: learning.recommendations,
+            stopped_reason: StoppedReason::Completed,
+            decision_trace: trace,
         };
 
         Ok(result)
     }
 
+    /// Run a phase unless the deadline has passed, abandoning it at the deadline
+    async fn before_deadline<F: Future>(deadline: Option<Instant>, phase: F) -> Option<F::Output> {
+        match deadline {
+            None => Some(phase.await),
+            Some(deadline) if Instant::now() >= deadline => None,
+            Some(deadline) => tokio::time::timeout_at(deadline.into(), phase).await.ok(),
+        }
+    }
+
+    /// Partial result for a run that timed out or was aborted during `phase`
+    async fn stopped(
+        &self,
+        query: &str,
+        perception: PerceptionResult,
+        phase: &str,
+        execution: Option<&ExecutionResult>,
+        start_time: Instant,
+        reason: StoppedReason,
+    ) -> CortexResult {
+        let duration_ms = start_time.elapsed().as_millis() as u64;
+        let (message, next_action) = match reason {
+            StoppedReason::Aborted => (
+                format!("Aborted during {}", phase),
+                "Rephrase the query and process it again".to_string(),
+            ),
+            _ => (
+                format!("Timed out during {}", phase),
+                format!("Raise timeout_secs or simplify the query ({} did not finish)", phase),
+            ),
+        };
+        tracing::warn!("CORTEX processing stopped: {} after {}ms", message, duration_ms);
+
+        {
+            let mut ctx = self.context.write().await;
+            ctx.record_query(query, perception.intent.as_str(), false);
+        }
+
+        let research_performed = perception.needs_research;
+        CortexResult {
+            success: false,
+            result: serde_json::json!({
+                "message": message,
+                "phase": phase,
+                "output": execution.and_then(|e| e.output.clone()),
+            }),
+            perception,
+            execution: ExecutionMetrics {
+                duration_ms,
+                steps_executed: execution.map_or(0, |e| e.successful_steps + e.failed_steps),
+                success_rate: execution.map_or(0.0, ExecutionResult::success_rate),
+                research_performed,
+                adjustments: execution.map_or(0, |e| e.adjustments.len()),
+            },
+            insights: Vec::new(),
+            confidence: execution.map_or(0.0, ExecutionResult::success_rate),
+            next_actions: vec![next_action],
+            stopped_reason: reason,
+            decision_trace: vec![DecisionPoint::new(
+                phase,
+                "stop",
+                serde_json::json!({ "elapsed_ms": duration_ms }),
+                serde_json::to_value(reason)
+                    .ok()
+                    .and_then(|v| v.as_str().map(String::from))
+                    .unwrap_or_default(),
+            )],
+        }
+    }
+
     /// Cognition phase - retrieve memory and create plan
     async fn cognition(&self, perception: &PerceptionResult) -> Result<ExecutionPlan> {
         let memory = self.memory.read().await;
@@ -230,6 +531,11 @@ impl CortexEngine {
         Ok(plan)
     }
 
+    /// Get the configuration
+    pub fn config(&self) -> &CortexConfig {
+        &self.config
+    }
+
     /// Get the current context
     pub async fn get_context(&self) -> ActiveContext {
         self.context.read().await.get_context().clone()
@@ -244,10 +550,13 @@ impl CortexEngine {
             ctx.set_workspace(ws);
         }
 
-        // Also start episodic memory session
+        // Also record the session in episodic memory, under the same id.
+        // Episodes carry the id explicitly rather than relying on the shared
+        // "current session", which other clients would overwrite.
         let memory = self.memory.read().await;
-        let mut episodic = memory.episodic.write().await;
-        episodic.start_session(ctx.get_workspace().map(|p| p.to_string_lossy().to_string())).await?;
+        let episodic = memory.episodic.read().await;
+        let workspace = ctx.get_workspace().map(|p| p.to_string_lossy().to_string());
+        episodic.record_session(&session_id, workspace).await?;
 
         Ok(session_id)
     }
@@ -255,28 +564,38 @@ impl CortexEngine {
     /// End the current session
     pub async fn end_session(&self) -> Result<()> {
         let mut ctx = self.context.write().await;
+        if let Some(session_id) = &ctx.get_context().session_id {
+            tracing::info!("Ended session: {}", session_id);
+        }
         ctx.end_session();
 
-        let memory = self.memory.read().await;
-        let mut episodic = memory.episodic.write().await;
-        episodic.end_session().await?;
-
         Ok(())
     }
 
+    /// Most recent queries of this client's context, newest first
+    pub async fn recent_queries(&self, count: usize) -> Vec<String> {
+        let ctx = self.context.read().await;
+        ctx.get_recent_history(count).into_iter().map(|h| h.query.clone()).collect()
+    }
+
     /// Get memory statistics
     pub async fn get_stats(&self) -> serde_json::Value {
         let mem = self.memory.read().await;
         let stats = mem.get_stats().await;
+        // The session is per client, so it comes from this client's context
+        let current_session = self.context.read().await.get_context().session_id.clone();
 
         serde_json::json!({
             "semantic": {
                 "total_facts": stats.semantic.total_facts,
                 "initialized": stats.semantic.initialized,
+                "dimension": stats.semantic.dimension,
+                "index_size_bytes": stats.semantic.index_size_bytes,
+                "embedding_model": stats.semantic.embedding_model,
             },
             "episodic": {
                 "total_episodes": stats.episodic.total_episodes,
-                "current_session": stats.episodic.current_session,
+                "current_session": current_session,
                 "by_type": stats.episodic.episodes_by_type,
             },
             "procedural": {
@@ -294,11 +613,33 @@ impl CortexEngine {
         self.learner.provide_feedback(rule_id, success).await
     }
 
-    /// Cleanup old data
-    pub async fn cleanup(&self, retention_days: i64) -> Result<usize> {
+    /// Cleanup old data using the per-type retention windows from the config
+    ///
+    /// `episodic_days` overrides `episodic_retention_days` for this call.
+    /// Memory types without a retention window are left untouched. With
+    /// `compact`, the episodic store is compacted afterwards.
+    pub async fn cleanup(&self, episodic_days: Option<i64>, compact: bool) -> Result<CleanupReport> {
         let memory = self.memory.read().await;
-        let episodic = memory.episodic.read().await;
-        episodic.cleanup_old(retention_days).await
+        let mut report = CleanupReport::default();
+
+        if let Some(days) = episodic_days.or(self.config.episodic_retention_days) {
+            report.episodic = memory.episodic.read().await.cleanup_old(days).await?;
+        }
+        if let Some(days) = self.config.semantic_retention_days {
+            report.semantic = memory.semantic.write().await.cleanup_old(days).await?;
+        }
+        if let Some(days) = self.config.procedural_retention_days {
+            report.procedural = memory.procedural.write().await.prune_stale_rules(days)?;
+        }
+        if compact {
+            report.bytes_reclaimed = memory.episodic.write().await.compact().await?;
+        }
+
+        tracing::info!(
+            "CORTEX cleanup: {} episodic, {} semantic, {} procedural",
+            report.episodic, report.semantic, report.procedural
+        );
+        Ok(report)
     }
 
     /// Search episodic memory
@@ -355,6 +696,12 @@ impl CortexEngine {
         instructions.add_user_instruction(instruction);
     }
 
+    /// Remove a user instruction from the active set
+    pub async fn remove_user_instruction(&self, user_id: &str, key: &str) -> bool {
+        let mut instructions = self.instructions.write().await;
+        instructions.remove_user_instruction(user_id, key)
+    }
+
     /// Set current user ID for instruction filtering
     pub async fn set_user(&self, user_id: impl Into<String>) {
         let mut instructions = self.instructions.write().await;
@@ -389,9 +736,42 @@ impl CortexEngine {
     ///
     /// This generates a formatted string suitable for injection into LLM prompts.
     /// If a file_path is provided, only instructions that apply to that file are included.
+    /// The result respects `max_instructions_chars`.
     pub async fn get_instructions_prompt(&self, file_path: Option<&str>) -> String {
+        self.get_instructions_prompt_capped(file_path, None).await.text
+    }
+
+    /// Get instructions as prompt context under a character budget
+    ///
+    /// `max_chars` overrides the configured `max_instructions_chars` (0 = unlimited).
+    /// Lower-priority instructions are cut or dropped first and reported.
+    pub async fn get_instructions_prompt_capped(
+        &self,
+        file_path: Option<&str>,
+        max_chars: Option<usize>,
+    ) -> super::instructions::InstructionsPrompt {
         let instructions = self.instructions.read().await;
-        instructions.to_prompt_context(file_path)
+        instructions.to_prompt_context_capped(
+            file_path,
+            max_chars.unwrap_or(self.config.max_instructions_chars),
+        )
+    }
+
+    /// Get instructions as prompt context with only `user_id`'s preferences
+    ///
+    /// Same budget rules as [`Self::get_instructions_prompt_capped`].
+    pub async fn get_user_instructions_prompt(
+        &self,
+        file_path: Option<&str>,
+        user_id: &str,
+        max_chars: Option<usize>,
+    ) -> super::instructions::InstructionsPrompt {
+        let instructions = self.instructions.read().await;
+        instructions.to_prompt_context_for_user(
+            file_path,
+            user_id,
+            max_chars.unwrap_or(self.config.max_instructions_chars),
+        )
     }
 
     /// Get instruction content by name
@@ -415,6 +795,7 @@ impl CortexEngine {
                 "name": i.name,
                 "description": i.description,
                 "apply_to": i.apply_to,
+                "priority": i.priority,
                 "source": format!("{:?}", i.source),
             })).collect::<Vec<_>>(),
         })
@@ -431,6 +812,96 @@ pub struct ProceduralRuleResult {
     pub usage_count: i32,
 }
 
+/// Intent and research decisions of the perception phase
+fn perception_decisions(perception: &PerceptionResult) -> Vec<DecisionPoint> {
+    let labels: Vec<&str> = perception.labels.iter().map(|l| l.as_str()).collect();
+    vec![
+        DecisionPoint::new(
+            "perception",
+            "intent",
+            serde_json::json!({
+                "query": perception.query,
+                "keywords": perception.keywords,
+                "labels": labels,
+                "secondary_intents": perception.secondary_intents,
+                "confidence": perception.confidence,
+            }),
+            perception.intent.as_str(),
+        ),
+        DecisionPoint::new(
+            "perception",
+            "research",
+            serde_json::json!({
+                "confidence": perception.confidence,
+                "complexity": perception.complexity,
+                "external_sources": perception.external_sources,
+            }),
+            if perception.needs_research { "research" } else { "no_research" },
+        ),
+    ]
+}
+
+/// Plan chosen in the cognition phase, with the memory it drew on
+fn plan_decision(plan: &ExecutionPlan) -> DecisionPoint {
+    let metadata = |key: &str| plan.metadata.get(key).cloned().unwrap_or(serde_json::Value::Null);
+    let steps: Vec<&str> = plan.steps.iter().map(|s| s.name.as_str()).collect();
+    DecisionPoint::new(
+        "cognition",
+        "plan",
+        serde_json::json!({
+            "relevant_facts": metadata("relevant_facts"),
+            "rules_applied": metadata("rules_applied"),
+            "routing": metadata("routing"),
+        }),
+        steps.join(" -> "),
+    )
+}
+
+/// Outcome of each executed step, then the adjustments made
+fn execution_decisions(steps: &[ExecutionStep], execution: &ExecutionResult) -> Vec<DecisionPoint> {
+    let mut decisions: Vec<DecisionPoint> = execution
+        .step_results
+        .iter()
+        .map(|result| {
+            let step = steps.iter().find(|s| s.id == result.step_id);
+            let chosen = match (&result.error, result.success) {
+                (_, true) => "succeeded".to_string(),
+                (Some(error), false) => format!("failed: {}", error),
+                (None, false) => "failed".to_string(),
+            };
+            DecisionPoint::new(
+                "execution",
+                "step",
+                serde_json::json!({
+                    "step": step.map(|s| s.name.as_str()),
+                    "action": step.map(|s| &s.action),
+                    "critical": step.map(|s| s.critical),
+                    "retries_used": result.retries_used,
+                }),
+                chosen,
+            )
+        })
+        .collect();
+    decisions.extend(execution.adjustments.iter().map(|adjustment| {
+        DecisionPoint::new("execution", "adjustment", serde_json::Value::Null, adjustment.clone())
+    }));
+    decisions
+}
+
+/// What the learning phase kept from the run
+fn learning_decision(learning: &LearningOutcome) -> DecisionPoint {
+    let insights: Vec<&str> = learning.insights.iter().map(|i| i.description.as_str()).collect();
+    DecisionPoint::new(
+        "learning",
+        "memorize",
+        serde_json::json!({
+            "success_rate": learning.success_rate,
+            "insights": insights,
+        }),
+        format!("{} memory updates", learning.memory_updates.len()),
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -442,4 +913,113 @@ mod tests {
         let engine = CortexEngine::new(temp.path(), CortexConfig::default()).await;
         assert!(engine.is_ok());
     }
+
+    #[tokio::test]
+    async fn test_cleanup_honors_per_type_retention() {
+        let temp = tempdir().unwrap();
+        let engine = CortexEngine::new(temp.path(), CortexConfig::default()).await.unwrap();
+
+        // Procedural rules are kept forever by default
+        let report = engine.cleanup(None, false).await.unwrap();
+        assert_eq!(report.procedural, 0);
+        assert_eq!(report.semantic, 0);
+
+        let temp = tempdir().unwrap();
+        let config = CortexConfig {
+            procedural_retention_days: Some(0),
+            ..CortexConfig::default()
+        };
+        let engine = CortexEngine::new(temp.path(), config).await.unwrap();
+
+        let report = engine.cleanup(None, true).await.unwrap();
+        assert!(report.procedural > 0);
+        assert_eq!(report.total(), report.episodic + report.procedural);
+    }
+
+    #[tokio::test]
+    async fn test_process_timeout_returns_partial_result() {
+        let temp = tempdir().unwrap();
+        let engine = CortexEngine::new(temp.path(), CortexConfig::default()).await.unwrap();
+
+        let result = engine
+            .process_with_timeout("Write a Rust function", None, Some(Duration::ZERO))
+            .await
+            .unwrap();
+        assert_eq!(result.stopped_reason, StoppedReason::Timeout);
+        assert!(!result.success);
+        assert_eq!(result.result["phase"], "cognition");
+        assert_eq!(result.perception.query, "Write a Rust function");
+        let decisions: Vec<&str> = result.decision_trace.iter().map(|d| d.decision.as_str()).collect();
+        assert_eq!(decisions, ["intent", "research", "stop"]);
+        assert_eq!(result.decision_trace[2].chosen, "timeout");
+
+        let result = engine
+            .process_with_timeout("Write a Rust function", None, Some(Duration::from_secs(60)))
+            .await
+            .unwrap();
+        assert_eq!(result.stopped_reason, StoppedReason::Completed);
+    }
+
+    #[tokio::test]
+    async fn test_process_records_decision_trace() {
+        let temp = tempdir().unwrap();
+        let engine = CortexEngine::new(temp.path(), CortexConfig::default()).await.unwrap();
+
+        let result = engine.process("Write a Rust function", None).await.unwrap();
+        let trace = &result.decision_trace;
+        let phases: Vec<&str> = trace.iter().map(|d| d.phase.as_str()).collect();
+        assert_eq!(phases.first(), Some(&"perception"));
+        assert_eq!(phases.last(), Some(&"learning"));
+
+        assert_eq!(trace[0].decision, "intent");
+        assert_eq!(trace[0].chosen, result.perception.intent.as_str());
+        assert_eq!(trace[0].inputs["query"], "Write a Rust function");
+
+        let plan = trace.iter().find(|d| d.decision == "plan").unwrap();
+        assert!(plan.chosen.starts_with("Analyze requirements"));
+        let steps = trace.iter().filter(|d| d.decision == "step").count();
+        assert_eq!(steps, result.execution.steps_executed);
+    }
+
+    #[tokio::test]
+    async fn test_abort_current_stops_process() {
+        let temp = tempdir().unwrap();
+        let engine = CortexEngine::new(temp.path(), CortexConfig::default()).await.unwrap();
+
+        // Nothing to abort, and later calls are unaffected
+        assert!(!engine.abort_current());
+        let result = engine.process("Write a Rust function", None).await.unwrap();
+        assert_eq!(result.stopped_reason, StoppedReason::Completed);
+
+        let run = engine.abort.begin();
+        assert!(engine.abort_current());
+        let result = engine
+            .process_until("Write a Rust function", None, None, &run.token)
+            .await
+            .unwrap();
+        assert_eq!(result.stopped_reason, StoppedReason::Aborted);
+        assert!(!result.success);
+        assert_eq!(result.result["phase"], "cognition");
+        drop(run);
+
+        // Each client view aborts only its own calls
+        let other = engine.for_client();
+        let run = engine.abort.begin();
+        assert!(!other.abort_current());
+        assert!(!run.token.is_cancelled());
+    }
+
+    #[tokio::test]
+    async fn test_stats_report_client_session() {
+        let temp = tempdir().unwrap();
+        let engine = CortexEngine::new(temp.path(), CortexConfig::default()).await.unwrap();
+        let other = engine.for_client();
+
+        let session_id = engine.start_session(None).await.unwrap();
+        assert_eq!(engine.get_stats().await["episodic"]["current_session"], session_id.as_str());
+        assert!(other.get_stats().await["episodic"]["current_session"].is_null());
+
+        engine.end_session().await.unwrap();
+        assert!(engine.get_stats().await["episodic"]["current_session"].is_null());
+    }
 }
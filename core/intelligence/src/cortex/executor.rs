@@ -9,6 +9,7 @@
 use crate::error::Result;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use tokio_util::sync::CancellationToken;
 
 /// An execution plan with steps
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -311,6 +312,11 @@ impl Executor {
 
     /// Execute a plan
     pub async fn execute(&self, plan: ExecutionPlan) -> Result<ExecutionResult> {
+        self.execute_until(plan, &CancellationToken::new()).await
+    }
+
+    /// Execute a plan, stopping at the next step boundary once `abort` is cancelled
+    pub async fn execute_until(&self, plan: ExecutionPlan, abort: &CancellationToken) -> Result<ExecutionResult> {
         let mut result = ExecutionResult::new(plan.id.clone());
         let start_time = std::time::Instant::now();
 
@@ -319,6 +325,10 @@ impl Executor {
                 result.add_adjustment(format!("Stopped at step {} (max steps reached)", idx));
                 break;
             }
+            if abort.is_cancelled() {
+                result.add_adjustment(format!("Aborted before step {}", idx));
+                break;
+            }
 
             // Execute step with OODA
             let step_result = self.execute_step_ooda(step).await;
@@ -516,4 +526,21 @@ mod tests {
         assert_eq!(result.failed_steps, 1);
         assert!((result.success_rate() - 0.666).abs() < 0.01);
     }
+
+    #[tokio::test]
+    async fn test_execute_until_stops_when_aborted() {
+        let mut plan = ExecutionPlan::new("Test plan");
+        plan.add_step(ExecutionStep::new("Step 1", StepAction::Analyze));
+        plan.add_step(ExecutionStep::new("Step 2", StepAction::Generate));
+        let executor = Executor::new(20);
+
+        let abort = CancellationToken::new();
+        let result = executor.execute_until(plan.clone(), &abort).await.unwrap();
+        assert_eq!(result.successful_steps, 2);
+
+        abort.cancel();
+        let result = executor.execute_until(plan, &abort).await.unwrap();
+        assert_eq!(result.successful_steps, 0);
+        assert_eq!(result.adjustments, vec!["Aborted before step 0"]);
+    }
 }
@@ -4,7 +4,10 @@
 //! 1. File-based: .instructions.md files from .github/instructions/
 //! 2. User-defined: Stored in database, personalized per user/session
 //!
-//! User instructions take priority over file instructions.
+//! User instructions take priority over file instructions. Within each source,
+//! instructions are ordered by `priority` (higher first), then by how specific
+//! their `applyTo` pattern is, then by name, so injection order never depends
+//! on filesystem order.
 
 use crate::error::Result;
 use serde::{Deserialize, Serialize};
@@ -25,6 +28,10 @@ pub struct Instruction {
     /// Glob pattern for applyTo
     pub apply_to: String,
 
+    /// Priority from frontmatter (higher = injected first)
+    #[serde(default)]
+    pub priority: i32,
+
     /// Compiled glob pattern
     #[serde(skip)]
     pub pattern: Option<Pattern>,
@@ -151,6 +158,7 @@ impl UserInstruction {
             name: self.key.clone(),
             description: format!("User instruction: {}", self.category),
             apply_to: "**".to_string(),
+            priority: self.priority,
             pattern: Some(Pattern::new("**").unwrap()),
             content: self.value.clone(),
             source_path: None,
@@ -160,6 +168,22 @@ impl UserInstruction {
 }
 
 impl Instruction {
+    /// How specific the applyTo pattern is (literal characters, `**` = 0)
+    pub fn specificity(&self) -> usize {
+        self.apply_to
+            .chars()
+            .filter(|c| !matches!(c, '*' | '?' | '[' | ']' | '{' | '}'))
+            .count()
+    }
+
+    /// Injection order within a source: priority, then specificity, then name
+    fn injection_order(a: &Instruction, b: &Instruction) -> std::cmp::Ordering {
+        b.priority
+            .cmp(&a.priority)
+            .then_with(|| b.specificity().cmp(&a.specificity()))
+            .then_with(|| a.name.cmp(&b.name))
+    }
+
     /// Check if this instruction applies to a given file path
     pub fn applies_to(&self, file_path: &str) -> bool {
         if self.apply_to == "**" {
@@ -217,18 +241,41 @@ impl InstructionsManager {
             instructions.into_iter().filter(|i| i.active).collect()
         };
 
-        self.user_instructions.extend(filtered);
+        for instruction in filtered {
+            self.upsert_user_instruction(instruction);
+        }
         self.rebuild_combined();
     }
 
-    /// Add a single user instruction
+    /// Add a single user instruction, replacing the user's instruction with the same key
     pub fn add_user_instruction(&mut self, instruction: UserInstruction) {
         if instruction.active {
-            self.user_instructions.push(instruction);
+            self.upsert_user_instruction(instruction);
             self.rebuild_combined();
         }
     }
 
+    fn upsert_user_instruction(&mut self, instruction: UserInstruction) {
+        match self.user_instructions
+            .iter_mut()
+            .find(|i| i.user_id == instruction.user_id && i.key == instruction.key)
+        {
+            Some(existing) => *existing = instruction,
+            None => self.user_instructions.push(instruction),
+        }
+    }
+
+    /// Remove a user's instruction by key, returning whether one was removed
+    pub fn remove_user_instruction(&mut self, user_id: &str, key: &str) -> bool {
+        let before = self.user_instructions.len();
+        self.user_instructions.retain(|i| !(i.user_id == user_id && i.key == key));
+        let removed = self.user_instructions.len() != before;
+        if removed {
+            self.rebuild_combined();
+        }
+        removed
+    }
+
     /// Get user instructions for export/save to DB
     pub fn get_user_instructions(&self) -> &[UserInstruction] {
         &self.user_instructions
@@ -239,15 +286,17 @@ impl InstructionsManager {
         self.instructions.clear();
 
         // Add user instructions first (higher priority)
-        let mut user_sorted = self.user_instructions.clone();
-        user_sorted.sort_by(|a, b| b.priority.cmp(&a.priority));
-
-        for ui in user_sorted {
-            self.instructions.push(ui.to_instruction());
-        }
+        let mut user_sorted: Vec<Instruction> = self.user_instructions
+            .iter()
+            .map(UserInstruction::to_instruction)
+            .collect();
+        user_sorted.sort_by(Instruction::injection_order);
+        self.instructions.extend(user_sorted);
 
         // Add file instructions
-        self.instructions.extend(self.file_instructions.clone());
+        let mut file_sorted = self.file_instructions.clone();
+        file_sorted.sort_by(Instruction::injection_order);
+        self.instructions.extend(file_sorted);
     }
 
     /// Load instructions from a workspace directory
@@ -334,6 +383,14 @@ impl InstructionsManager {
             .cloned()
             .unwrap_or_else(|| "**".to_string());
 
+        let priority = match frontmatter.get("priority") {
+            Some(value) => value.parse().unwrap_or_else(|_| {
+                tracing::warn!("Invalid priority '{}' in {:?}, using 0", value, path);
+                0
+            }),
+            None => 0,
+        };
+
         // Compile glob pattern
         let pattern = Pattern::new(&apply_to).ok();
 
@@ -341,6 +398,7 @@ impl InstructionsManager {
             name,
             description,
             apply_to,
+            priority,
             pattern,
             content: body,
             source_path: Some(path.to_path_buf()),
@@ -403,52 +461,136 @@ impl InstructionsManager {
 
     /// Generate prompt context from instructions
     pub fn to_prompt_context(&self, file_path: Option<&str>) -> String {
-        let applicable: Vec<&Instruction> = if let Some(fp) = file_path {
-            self.for_file(fp)
-        } else {
-            self.global()
-        };
-
-        if applicable.is_empty() {
-            return String::new();
-        }
+        self.to_prompt_context_capped(file_path, 0).text
+    }
 
-        let mut parts = vec![];
+    /// Generate prompt context from instructions, capped at `max_chars` (0 = unlimited)
+    ///
+    /// Instructions are added in priority order (user instructions first). The
+    /// first one that doesn't fit is cut to the remaining budget and every
+    /// lower-priority instruction after it is dropped.
+    pub fn to_prompt_context_capped(&self, file_path: Option<&str>, max_chars: usize) -> InstructionsPrompt {
+        let applicable = self.applicable(file_path);
 
-        // User instructions first (highest priority)
-        let user_instr: Vec<_> = applicable.iter()
+        // User instructions first (highest priority), then file instructions
+        let ordered = applicable.iter()
             .filter(|i| i.source == InstructionSource::User)
+            .chain(applicable.iter().filter(|i| i.source == InstructionSource::File))
+            .copied();
+
+        Self::render_capped(ordered, max_chars)
+    }
+
+    /// Like [`Self::to_prompt_context_capped`], but with only `user_id`'s
+    /// instructions as user preferences (other users' are left out)
+    pub fn to_prompt_context_for_user(
+        &self,
+        file_path: Option<&str>,
+        user_id: &str,
+        max_chars: usize,
+    ) -> InstructionsPrompt {
+        let mut user: Vec<Instruction> = self.user_instructions
+            .iter()
+            .filter(|i| i.active && i.user_id == user_id)
+            .map(UserInstruction::to_instruction)
             .collect();
+        user.sort_by(Instruction::injection_order);
 
-        if !user_instr.is_empty() {
-            parts.push("## User Preferences\n".to_string());
-            for instruction in user_instr {
-                parts.push(format!("**{}**: {}\n", instruction.name, instruction.content));
-            }
-            parts.push("\n".to_string());
+        let applicable = self.applicable(file_path);
+        let ordered = user.iter()
+            .chain(applicable.into_iter().filter(|i| i.source == InstructionSource::File));
+
+        Self::render_capped(ordered, max_chars)
+    }
+
+    /// Instructions for a file, or the global ones without a file
+    fn applicable(&self, file_path: Option<&str>) -> Vec<&Instruction> {
+        if let Some(fp) = file_path {
+            self.for_file(fp)
+        } else {
+            self.global()
         }
+    }
 
-        // File instructions
-        let file_instr: Vec<_> = applicable.iter()
-            .filter(|i| i.source == InstructionSource::File)
-            .collect();
+    /// Render instructions in the given order under a character budget
+    fn render_capped<'a>(
+        ordered: impl Iterator<Item = &'a Instruction>,
+        max_chars: usize,
+    ) -> InstructionsPrompt {
+        let mut prompt = InstructionsPrompt::default();
+        let mut used = 0;
+        let mut current_section = None;
+
+        for instruction in ordered {
+            let heading = if current_section != Some(&instruction.source) {
+                current_section = Some(&instruction.source);
+                match (instruction.source == InstructionSource::User, prompt.text.is_empty()) {
+                    (true, _) => "## User Preferences\n\n",
+                    (false, true) => "## Instructions\n\n",
+                    (false, false) => "\n## Instructions\n\n",
+                }
+            } else {
+                ""
+            };
 
-        if !file_instr.is_empty() {
-            parts.push("## Instructions\n".to_string());
-            for instruction in file_instr {
-                parts.push(format!("### {} ({})\n", instruction.name, instruction.description));
+            let block = if instruction.source == InstructionSource::User {
+                format!("**{}**: {}\n", instruction.name, instruction.content)
+            } else {
                 // Truncate long instructions
-                let content = if instruction.content.len() > 2000 {
-                    format!("{}...\n[truncated]", &instruction.content[..2000])
+                let content = if instruction.content.len() > MAX_INSTRUCTION_BYTES {
+                    format!("{}...\n[truncated]", truncate_at_char(&instruction.content, MAX_INSTRUCTION_BYTES))
                 } else {
                     instruction.content.clone()
                 };
-                parts.push(content);
-                parts.push("\n".to_string());
+                format!("### {} ({})\n\n{}\n\n", instruction.name, instruction.description, content)
+            };
+
+            let cost = heading.chars().count() + block.chars().count();
+            let over_budget = max_chars > 0 && used + cost > max_chars;
+
+            if !over_budget {
+                prompt.text.push_str(heading);
+                prompt.text.push_str(&block);
+                prompt.included += 1;
+                if instruction.source == InstructionSource::User {
+                    prompt.user_keys.push(instruction.name.clone());
+                }
+                used += cost;
+                continue;
+            }
+
+            // Cut the first instruction that overflows, drop everything after it
+            let remaining = max_chars.saturating_sub(used);
+            let kept_budget = remaining.saturating_sub(heading.chars().count() + TRUNCATION_MARKER.len());
+            if prompt.truncated.is_none() && prompt.dropped.is_empty() && kept_budget >= MIN_TRUNCATED_CHARS {
+                let kept: String = block.chars().take(kept_budget).collect();
+                prompt.text.push_str(heading);
+                prompt.text.push_str(&kept);
+                prompt.text.push_str(TRUNCATION_MARKER);
+                prompt.included += 1;
+                if instruction.source == InstructionSource::User {
+                    prompt.user_keys.push(instruction.name.clone());
+                }
+                prompt.truncated = Some(instruction.name.clone());
+                prompt.dropped_chars += cost - heading.chars().count() - kept_budget;
+                used = max_chars;
+            } else {
+                prompt.dropped.push(instruction.name.clone());
+                prompt.dropped_chars += block.chars().count();
             }
         }
 
-        parts.join("\n")
+        if !prompt.dropped.is_empty() || prompt.truncated.is_some() {
+            tracing::debug!(
+                "Instructions capped at {} chars: {} dropped, truncated {:?}, {} chars omitted",
+                max_chars,
+                prompt.dropped.len(),
+                prompt.truncated,
+                prompt.dropped_chars
+            );
+        }
+
+        prompt
     }
 
     /// Get instruction content by name
@@ -489,6 +631,42 @@ impl InstructionsManager {
     }
 }
 
+/// Longest file instruction body injected before it is cut (bytes)
+const MAX_INSTRUCTION_BYTES: usize = 2000;
+
+/// Smallest remaining budget worth spending on a partial instruction
+const MIN_TRUNCATED_CHARS: usize = 80;
+
+/// Appended to an instruction cut to fit the budget
+const TRUNCATION_MARKER: &str = "\n[truncated to fit budget]\n";
+
+/// Cut `s` to at most `max_bytes` without splitting a character
+fn truncate_at_char(s: &str, max_bytes: usize) -> &str {
+    let mut end = max_bytes.min(s.len());
+    while !s.is_char_boundary(end) {
+        end -= 1;
+    }
+    &s[..end]
+}
+
+/// Instructions prompt with what was left out to respect the size cap
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct InstructionsPrompt {
+    /// Prompt text to inject
+    pub text: String,
+    /// Instructions injected (fully or truncated)
+    pub included: usize,
+    /// Keys of the user instructions injected, in priority order
+    #[serde(default)]
+    pub user_keys: Vec<String>,
+    /// Instruction that was cut to fit, if any
+    pub truncated: Option<String>,
+    /// Instructions left out entirely, in priority order
+    pub dropped: Vec<String>,
+    /// Characters left out (dropped instructions plus the cut tail)
+    pub dropped_chars: usize,
+}
+
 /// Statistics about loaded instructions
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct InstructionsStats {
@@ -507,6 +685,14 @@ mod tests {
     use super::*;
     use tempfile::TempDir;
 
+    fn create_prioritized_instruction(dir: &Path, name: &str, apply_to: &str, priority: &str) {
+        let content = format!(
+            "---\ndescription: \"{}\"\napplyTo: \"{}\"\npriority: {}\n---\n\n{} rules\n",
+            name, apply_to, priority, name
+        );
+        std::fs::write(dir.join(format!("{}.instructions.md", name)), content).unwrap();
+    }
+
     fn create_test_instruction(dir: &Path, name: &str, apply_to: &str, content: &str) {
         let path = dir.join(format!("{}.instructions.md", name));
         let full_content = format!(
@@ -574,6 +760,110 @@ applyTo: "{}"
         assert!(context.contains("Follow ACID workflow"));
     }
 
+    #[test]
+    fn test_prompt_context_cap_keeps_priority_order() {
+        let temp = TempDir::new().unwrap();
+        let instructions_dir = temp.path();
+
+        create_test_instruction(instructions_dir, "workflow", "**", &"Follow ACID workflow. ".repeat(20));
+
+        let mut manager = InstructionsManager::new();
+        manager.load_from_directory(instructions_dir).unwrap();
+        manager.add_user_instruction(UserInstruction::new("u", "language", "Answer in French").with_priority(10));
+        manager.add_user_instruction(UserInstruction::new("u", "tone", "Be concise").with_priority(1));
+
+        // Unlimited keeps everything
+        let full = manager.to_prompt_context_capped(None, 0);
+        assert_eq!(full.included, 3);
+        assert!(full.dropped.is_empty() && full.truncated.is_none());
+        assert_eq!(full.text, manager.to_prompt_context(None));
+
+        // Room for the user preferences and part of the file instruction
+        let capped = manager.to_prompt_context_capped(None, 200);
+        assert!(capped.text.chars().count() <= 200);
+        assert!(capped.text.contains("Answer in French"));
+        assert!(capped.text.contains("Be concise"));
+        assert_eq!(capped.truncated.as_deref(), Some("workflow"));
+        assert!(capped.dropped_chars > 0);
+
+        // Too small for anything but the top instruction
+        let tiny = manager.to_prompt_context_capped(None, 60);
+        assert!(tiny.text.contains("Answer in French"));
+        assert_eq!(tiny.included, 1);
+        assert_eq!(tiny.dropped, vec!["tone".to_string(), "workflow".to_string()]);
+        assert_eq!(
+            tiny.dropped_chars,
+            full.text.chars().count() - tiny.text.chars().count() - "\n## Instructions\n\n".len()
+        );
+    }
+
+    #[test]
+    fn test_priority_then_specificity_order() {
+        let temp = TempDir::new().unwrap();
+        let dir = temp.path();
+
+        create_prioritized_instruction(dir, "a-style", "**", "1");
+        create_prioritized_instruction(dir, "b-security", "**", "10");
+        create_prioritized_instruction(dir, "c-rust", "**/*.rs", "1");
+        create_prioritized_instruction(dir, "d-db", "src/db/**/*.rs", "1");
+        create_prioritized_instruction(dir, "e-broken", "**", "high");
+
+        let mut manager = InstructionsManager::new();
+        manager.load_from_directory(dir).unwrap();
+        manager.add_user_instruction(UserInstruction::new("u", "tone", "Be concise"));
+
+        let order: Vec<&str> = manager.all().iter().map(|i| i.name.as_str()).collect();
+        assert_eq!(order, vec!["tone", "b-security", "d-db", "c-rust", "a-style", "e-broken"]);
+        assert_eq!(manager.all()[1].priority, 10);
+        assert_eq!(manager.all()[5].priority, 0);
+
+        let context = manager.to_prompt_context(Some("src/db/pool.rs"));
+        let positions: Vec<usize> = ["b-security", "d-db", "c-rust", "a-style"]
+            .iter()
+            .map(|name| context.find(&format!("### {}", name)).unwrap())
+            .collect();
+        assert!(positions.windows(2).all(|w| w[0] < w[1]));
+    }
+
+    #[test]
+    fn test_remove_user_instruction() {
+        let mut manager = InstructionsManager::new();
+        manager.add_user_instruction(UserInstruction::new("alice", "tone", "Be concise"));
+        manager.add_user_instruction(UserInstruction::new("bob", "tone", "Be verbose"));
+
+        assert!(manager.remove_user_instruction("alice", "tone"));
+        assert!(!manager.remove_user_instruction("alice", "tone"));
+        assert_eq!(manager.get_user_instructions().len(), 1);
+        assert!(manager.to_prompt_context(None).contains("Be verbose"));
+        assert!(!manager.to_prompt_context(None).contains("Be concise"));
+    }
+
+    #[test]
+    fn test_prompt_context_for_user() {
+        let temp = TempDir::new().unwrap();
+        create_test_instruction(temp.path(), "workflow", "**", "Follow ACID workflow");
+
+        let mut manager = InstructionsManager::new();
+        manager.load_from_directory(temp.path()).unwrap();
+        manager.add_user_instruction(UserInstruction::new("alice", "tone", "Be concise"));
+        manager.add_user_instruction(UserInstruction::new("alice", "language", "Answer in French").with_priority(5));
+        manager.add_user_instruction(UserInstruction::new("bob", "tone", "Be verbose"));
+        // Saving the same key again replaces the value
+        manager.add_user_instruction(UserInstruction::new("alice", "tone", "Be terse"));
+        assert_eq!(manager.get_user_instructions().len(), 3);
+
+        let alice = manager.to_prompt_context_for_user(None, "alice", 0);
+        assert_eq!(alice.user_keys, vec!["language".to_string(), "tone".to_string()]);
+        assert!(alice.text.find("Answer in French").unwrap() < alice.text.find("Be terse").unwrap());
+        assert!(alice.text.contains("Follow ACID workflow"));
+        assert!(!alice.text.contains("Be verbose") && !alice.text.contains("Be concise"));
+        assert_eq!(alice.included, 3);
+
+        let nobody = manager.to_prompt_context_for_user(None, "carol", 0);
+        assert!(nobody.user_keys.is_empty());
+        assert!(!nobody.text.contains("User Preferences"));
+    }
+
     #[test]
     fn test_parse_frontmatter() {
         let manager = InstructionsManager::new();
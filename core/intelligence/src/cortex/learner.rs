@@ -476,6 +476,7 @@ impl Learner {
         &self,
         execution: &super::executor::ExecutionResult,
         perception: &super::perceiver::PerceptionResult,
+        session_id: Option<&str>,
     ) -> Result<LearningOutcome> {
         let mut insights = Vec::new();
         let mut memory_updates = Vec::new();
@@ -520,7 +521,9 @@ impl Learner {
         // Update memory if auto_learn is enabled
         if self.auto_learn {
             if let Some(memory) = &self.memory {
-                memory_updates = self.update_memory(memory, &insights, execution, perception).await?;
+                memory_updates = self
+                    .update_memory(memory, &insights, execution, perception, session_id)
+                    .await?;
             }
         }
 
@@ -591,6 +594,7 @@ impl Learner {
         insights: &[ReflectionInsight],
         execution: &super::executor::ExecutionResult,
         perception: &super::perceiver::PerceptionResult,
+        session_id: Option<&str>,
     ) -> Result<Vec<MemoryUpdate>> {
         let mut updates = Vec::new();
         let mem = memory.read().await;
@@ -605,7 +609,7 @@ impl Learner {
                 EpisodeType::Error
             };
 
-            let episode = Episode::new(episode_type, format!(
+            let mut episode = Episode::new(episode_type, format!(
                 "{:?} task: {} (success_rate: {:.1}%)",
                 perception.intent,
                 perception.query,
@@ -615,6 +619,9 @@ impl Learner {
                 "duration_ms": execution.total_duration_ms,
                 "steps": execution.successful_steps + execution.failed_steps,
             }));
+            if let Some(session_id) = session_id {
+                episode = episode.with_session(session_id);
+            }
 
             let episodic = mem.episodic.write().await;
             let episode_id = episodic.record(episode).await?;
@@ -16,7 +16,8 @@ mod executor;
 mod learner;
 mod context;
 
-pub use engine::{CortexEngine, CortexResult};
+pub use engine::{CleanupReport, CortexEngine, CortexResult, DecisionPoint, StoppedReason};
+pub use perceiver::Perceiver;
 // instructions module re-exports types used internally by CortexEngine
 
 /// Configuration for the CORTEX engine
@@ -33,6 +34,30 @@ pub struct CortexConfig {
 
     /// Enable research pipeline
     pub enable_research: bool,
+
+    /// Lowest confidence feedback can push a rule to
+    pub confidence_floor: f32,
+
+    /// Highest confidence feedback can push a rule to (below 1.0 so rules can still decay)
+    pub confidence_ceiling: f32,
+
+    /// Character budget for injected instructions (0 = unlimited)
+    pub max_instructions_chars: usize,
+
+    /// Days to keep episodic events during cleanup (None = keep forever)
+    pub episodic_retention_days: Option<i64>,
+
+    /// Days to keep semantic facts during cleanup (None = keep forever)
+    pub semantic_retention_days: Option<i64>,
+
+    /// Days to keep procedural rules without feedback (None = keep forever)
+    pub procedural_retention_days: Option<i64>,
+
+    /// Apply the retention policy in the background every `cleanup_interval`
+    pub auto_cleanup: bool,
+
+    /// Time between automatic cleanups
+    pub cleanup_interval: std::time::Duration,
 }
 
 impl Default for CortexConfig {
@@ -42,6 +67,14 @@ impl Default for CortexConfig {
             max_execution_steps: 20,
             auto_learn: true,
             enable_research: true,
+            confidence_floor: 0.05,
+            confidence_ceiling: 0.95,
+            max_instructions_chars: 16_000,
+            episodic_retention_days: Some(30),
+            semantic_retention_days: None,
+            procedural_retention_days: None,
+            auto_cleanup: false,
+            cleanup_interval: std::time::Duration::from_secs(24 * 60 * 60),
         }
     }
 }
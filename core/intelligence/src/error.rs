@@ -51,6 +51,14 @@ pub enum IntelligenceError {
     /// Invalid operation
     #[error("Invalid operation: {0}")]
     InvalidOperation(String),
+
+    /// A tool parameter failed validation
+    #[error("Invalid parameter `{field}`: {message}")]
+    InvalidParams { field: String, message: String },
+
+    /// Memory content exceeds the configured size limit
+    #[error("Content too large: {size} bytes exceeds the limit of {limit} bytes")]
+    ContentTooLarge { size: usize, limit: usize },
 }
 
 // Manual From implementations for boxed error types
@@ -81,6 +89,14 @@ impl IntelligenceError {
     pub fn invalid_operation(msg: impl Into<String>) -> Self {
         Self::InvalidOperation(msg.into())
     }
+
+    /// Create an invalid parameter error
+    pub fn invalid_param(field: impl Into<String>, msg: impl Into<String>) -> Self {
+        Self::InvalidParams {
+            field: field.into(),
+            message: msg.into(),
+        }
+    }
 }
 
 impl From<IntelligenceError> for rmcp::ErrorData {
@@ -97,6 +113,14 @@ impl From<IntelligenceError> for rmcp::ErrorData {
             IntelligenceError::InvalidOperation(_) => {
                 rmcp::ErrorData::new(ErrorCode(-32002), err.to_string(), None)
             }
+            IntelligenceError::InvalidParams { field, message } => rmcp::ErrorData::invalid_params(
+                err.to_string(),
+                Some(serde_json::json!({ "errors": [{ "field": field, "message": message }] })),
+            ),
+            IntelligenceError::ContentTooLarge { size, limit } => rmcp::ErrorData::invalid_params(
+                err.to_string(),
+                Some(serde_json::json!({ "size": size, "limit": limit })),
+            ),
             _ => rmcp::ErrorData::new(ErrorCode(-32603), err.to_string(), None),
         }
     }
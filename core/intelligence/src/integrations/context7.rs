@@ -3,15 +3,27 @@
 //! Provides access to official library documentation via Context7 API.
 //! Used for retrieving up-to-date documentation and code examples.
 
-use super::{DocResult, IntegrationClient, SearchResult};
+use super::{CodeSnippet, DocResult, IntegrationClient, RelevanceScoring, SearchResult};
 use crate::error::{IntelligenceError, Result};
 use async_trait::async_trait;
 use reqwest::Client;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::RwLock;
 
+/// Default lifetime of a resolved library ID (7 days)
+const DEFAULT_RESOLVE_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// A library ID resolved through the API
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ResolvedLibrary {
+    library_id: String,
+    resolved_at: i64,
+}
+
 /// Context7 API client for library documentation
 pub struct Context7Client {
     /// HTTP client
@@ -26,6 +38,18 @@ pub struct Context7Client {
     /// Library ID cache (name -> id)
     library_cache: Arc<RwLock<HashMap<String, String>>>,
 
+    /// IDs resolved through the API (name -> id), expiring after `resolve_ttl`
+    resolved_cache: Arc<RwLock<HashMap<String, ResolvedLibrary>>>,
+
+    /// File the resolved IDs are persisted to
+    cache_path: Option<PathBuf>,
+
+    /// How long a resolved ID stays valid
+    resolve_ttl: Duration,
+
+    /// Relevance scoring for search results
+    scoring: RelevanceScoring,
+
     /// Whether initialized
     initialized: bool,
 }
@@ -45,6 +69,8 @@ struct ResolveResponse {
 struct DocsResponse {
     content: String,
     url: Option<String>,
+    #[serde(default)]
+    version: Option<String>,
     #[serde(rename = "library_id")]
     _library_id: Option<String>,
 }
@@ -72,10 +98,41 @@ impl Context7Client {
             api_key,
             base_url: "https://context7.com/api".to_string(),
             library_cache: Arc::new(RwLock::new(Self::common_library_mappings())),
+            resolved_cache: Arc::new(RwLock::new(HashMap::new())),
+            cache_path: None,
+            resolve_ttl: DEFAULT_RESOLVE_TTL,
+            scoring: RelevanceScoring::default(),
             initialized: false,
         }
     }
 
+    /// Set the relevance scoring used for search results
+    pub fn with_scoring(mut self, scoring: RelevanceScoring) -> Self {
+        self.scoring = scoring;
+        self
+    }
+
+    /// Persist resolved library IDs to a JSON file, loading any existing entries
+    pub fn with_cache_file(mut self, path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        match std::fs::read_to_string(&path) {
+            Ok(content) => match serde_json::from_str(&content) {
+                Ok(entries) => self.resolved_cache = Arc::new(RwLock::new(entries)),
+                Err(e) => tracing::warn!("Ignoring unreadable Context7 cache {:?}: {}", path, e),
+            },
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => {}
+            Err(e) => tracing::warn!("Failed to read Context7 cache {:?}: {}", path, e),
+        }
+        self.cache_path = Some(path);
+        self
+    }
+
+    /// Set how long resolved library IDs are reused before re-resolving
+    pub fn with_cache_ttl(mut self, ttl: Duration) -> Self {
+        self.resolve_ttl = ttl;
+        self
+    }
+
     /// Create client from environment variable
     pub fn from_env() -> Self {
         let api_key = std::env::var("CONTEXT7_API_KEY").ok();
@@ -142,6 +199,11 @@ impl Context7Client {
             }
         }
 
+        // Then previously resolved IDs that haven't expired
+        if let Some(id) = self.cached_resolution(&name_lower).await {
+            return Ok(Some(id));
+        }
+
         // If API key is available, try to resolve via API
         if self.initialized {
             if let Some(ref client) = self.client {
@@ -156,8 +218,7 @@ impl Context7Client {
                         if let Ok(data) = resp.json::<ResolveResponse>().await {
                             if let Some(library_id) = data.library_id {
                                 // Cache the result
-                                let mut cache = self.library_cache.write().await;
-                                cache.insert(name_lower.clone(), library_id.clone());
+                                self.remember_resolution(&name_lower, &library_id).await;
                                 return Ok(Some(library_id));
                             }
                         }
@@ -170,6 +231,49 @@ impl Context7Client {
         Ok(None)
     }
 
+    /// Look up a resolved ID, evicting it if older than the TTL
+    async fn cached_resolution(&self, name: &str) -> Option<String> {
+        let ttl = i64::try_from(self.resolve_ttl.as_secs()).unwrap_or(i64::MAX);
+        let now = chrono::Utc::now().timestamp();
+
+        let mut cache = self.resolved_cache.write().await;
+        match cache.get(name) {
+            Some(entry) if now.saturating_sub(entry.resolved_at) < ttl => Some(entry.library_id.clone()),
+            Some(_) => {
+                cache.remove(name);
+                None
+            }
+            None => None,
+        }
+    }
+
+    /// Record a resolved ID and persist the cache
+    async fn remember_resolution(&self, name: &str, library_id: &str) {
+        let snapshot = {
+            let mut cache = self.resolved_cache.write().await;
+            cache.insert(
+                name.to_string(),
+                ResolvedLibrary {
+                    library_id: library_id.to_string(),
+                    resolved_at: chrono::Utc::now().timestamp(),
+                },
+            );
+            serde_json::to_string_pretty(&*cache)
+        };
+
+        let Some(path) = &self.cache_path else {
+            return;
+        };
+        match snapshot {
+            Ok(json) => {
+                if let Err(e) = tokio::fs::write(path, json).await {
+                    tracing::warn!("Failed to persist Context7 cache {:?}: {}", path, e);
+                }
+            }
+            Err(e) => tracing::warn!("Failed to serialize Context7 cache: {}", e),
+        }
+    }
+
     /// Get documentation for a library
     pub async fn get_library_docs(
         &self,
@@ -200,6 +304,7 @@ impl Context7Client {
                 code_snippets: vec![],
                 url: None,
                 provider: "context7".into(),
+                version: Self::version_from_id(&library_id),
             }));
         }
 
@@ -234,7 +339,8 @@ impl Context7Client {
                 .map_err(|e| IntelligenceError::Config(format!("Context7 parse failed: {}", e)))?;
 
             // Extract code snippets from content
-            let code_snippets = Self::extract_code_snippets(&data.content);
+            let code_snippets = Self::extract_code_snippets(&data.content, data.url.as_deref());
+            let version = data.version.or_else(|| Self::version_from_id(&library_id));
 
             return Ok(Some(DocResult {
                 source: library_id,
@@ -243,6 +349,7 @@ impl Context7Client {
                 code_snippets,
                 url: data.url,
                 provider: "context7".into(),
+                version,
             }));
         }
 
@@ -275,17 +382,20 @@ impl Context7Client {
         match response {
             Ok(resp) if resp.status().is_success() => {
                 if let Ok(data) = resp.json::<SearchResponse>().await {
+                    let total = data.results.len();
                     return Ok(data
                         .results
                         .into_iter()
-                        .map(|item| SearchResult {
+                        .enumerate()
+                        .map(|(rank, item)| SearchResult {
+                            score: self.scoring.score(query, &item.title, &item.snippet, rank, total),
                             title: item.title,
                             content: item.snippet,
                             url: item.url,
                             source: "context7".into(),
-                            score: item.score.unwrap_or(0.5),
                             metadata: serde_json::json!({
-                                "library": item.library_id
+                                "library": item.library_id,
+                                "provider_score": item.score
                             }),
                         })
                         .collect());
@@ -297,19 +407,39 @@ impl Context7Client {
         Ok(vec![])
     }
 
+    /// Version pinned in a library ID such as `/vercel/next.js/v14.3.0`
+    fn version_from_id(library_id: &str) -> Option<String> {
+        let parts: Vec<&str> = library_id.trim_matches('/').split('/').collect();
+        match parts.as_slice() {
+            [_, _, version] if !version.is_empty() => Some(version.to_string()),
+            _ => None,
+        }
+    }
+
     /// Extract code snippets from markdown content
-    fn extract_code_snippets(content: &str) -> Vec<String> {
+    fn extract_code_snippets(content: &str, source_url: Option<&str>) -> Vec<CodeSnippet> {
         let mut snippets = Vec::new();
         let mut in_code_block = false;
+        let mut language = None;
         let mut current_snippet = Vec::new();
 
         for line in content.lines() {
-            if line.starts_with("```") {
+            if let Some(info) = line.strip_prefix("```") {
                 if in_code_block {
                     if !current_snippet.is_empty() {
-                        snippets.push(current_snippet.join("\n"));
+                        snippets.push(CodeSnippet {
+                            code: current_snippet.join("\n"),
+                            language: language.take(),
+                            source_url: source_url.map(String::from),
+                        });
                     }
                     current_snippet.clear();
+                } else {
+                    // Info string: first word is the language (```rust title="x")
+                    language = info
+                        .split_whitespace()
+                        .next()
+                        .map(|l| l.to_lowercase());
                 }
                 in_code_block = !in_code_block;
             } else if in_code_block {
@@ -405,10 +535,22 @@ def hello():
 ```
 "#;
 
-        let snippets = Context7Client::extract_code_snippets(content);
+        let snippets = Context7Client::extract_code_snippets(content, Some("https://docs.example/page"));
         assert_eq!(snippets.len(), 2);
-        assert!(snippets[0].contains("fn main"));
-        assert!(snippets[1].contains("def hello"));
+        assert!(snippets[0].code.contains("fn main"));
+        assert_eq!(snippets[0].language.as_deref(), Some("rust"));
+        assert!(snippets[1].code.contains("def hello"));
+        assert_eq!(snippets[1].language.as_deref(), Some("python"));
+        assert_eq!(snippets[1].source_url.as_deref(), Some("https://docs.example/page"));
+    }
+
+    #[test]
+    fn test_version_from_library_id() {
+        assert_eq!(
+            Context7Client::version_from_id("/vercel/next.js/v14.3.0"),
+            Some("v14.3.0".to_string())
+        );
+        assert_eq!(Context7Client::version_from_id("/facebook/react"), None);
     }
 
     #[tokio::test]
@@ -417,4 +559,25 @@ def hello():
         let result = client.resolve_library_id("react").await.unwrap();
         assert_eq!(result, Some("/facebook/react".to_string()));
     }
+
+    #[tokio::test]
+    async fn test_resolved_ids_persist_across_clients() {
+        let temp = tempfile::TempDir::new().unwrap();
+        let path = temp.path().join("context7_libraries.json");
+
+        let client = Context7Client::new(None).with_cache_file(&path);
+        client.remember_resolution("my-lib", "/acme/my-lib").await;
+
+        let reloaded = Context7Client::new(None).with_cache_file(&path);
+        let result = reloaded.resolve_library_id("My-Lib").await.unwrap();
+        assert_eq!(result, Some("/acme/my-lib".to_string()));
+    }
+
+    #[tokio::test]
+    async fn test_resolved_ids_expire_after_ttl() {
+        let client = Context7Client::new(None).with_cache_ttl(Duration::ZERO);
+        client.remember_resolution("my-lib", "/acme/my-lib").await;
+
+        assert!(client.resolve_library_id("my-lib").await.unwrap().is_none());
+    }
 }
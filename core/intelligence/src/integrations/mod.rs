@@ -15,7 +15,9 @@ pub use tavily::TavilyClient;
 
 use crate::error::Result;
 use async_trait::async_trait;
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 
 /// Common result type for documentation retrieval
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -30,13 +32,32 @@ pub struct DocResult {
     pub content: String,
 
     /// Code snippets extracted
-    pub code_snippets: Vec<String>,
+    pub code_snippets: Vec<CodeSnippet>,
 
     /// Source URL
     pub url: Option<String>,
 
     /// Provider name
     pub provider: String,
+
+    /// Library version the content was taken from, if known
+    #[serde(default)]
+    pub version: Option<String>,
+}
+
+/// A code snippet with its attribution
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct CodeSnippet {
+    /// Snippet source code
+    pub code: String,
+
+    /// Language from the code fence (e.g. "rust"), if declared
+    #[serde(default)]
+    pub language: Option<String>,
+
+    /// Page the snippet was taken from
+    #[serde(default)]
+    pub source_url: Option<String>,
 }
 
 /// Common result type for search operations
@@ -55,12 +76,74 @@ pub struct SearchResult {
     pub source: String,
 
     /// Relevance score (0.0 - 1.0)
+    ///
+    /// Every provider computes this with [`RelevanceScoring`] so results from
+    /// different sources are comparable; the provider's own score (if any) is
+    /// kept in `metadata.provider_score`.
     pub score: f32,
 
     /// Additional metadata
     pub metadata: serde_json::Value,
 }
 
+/// Provider-independent relevance scoring for search results.
+///
+/// The score is a weighted blend, in 0.0 - 1.0, of:
+/// - term overlap: fraction of distinct query terms found as whole words in
+///   title or content
+/// - rank: `1 - rank / total`, so the provider's first result gets 1.0
+///
+/// Weights are normalized, so only their ratio matters.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RelevanceScoring {
+    /// Weight of query-term overlap
+    pub term_weight: f32,
+
+    /// Weight of the provider-reported rank
+    pub rank_weight: f32,
+}
+
+impl Default for RelevanceScoring {
+    fn default() -> Self {
+        Self {
+            term_weight: 0.6,
+            rank_weight: 0.4,
+        }
+    }
+}
+
+impl RelevanceScoring {
+    /// Score a result at position `rank` (0-based) out of `total` results
+    pub fn score(&self, query: &str, title: &str, content: &str, rank: usize, total: usize) -> f32 {
+        let vocabulary: HashSet<String> = words(title).chain(words(content)).collect();
+        let terms: HashSet<String> = words(query).filter(|t| t.len() >= 2).collect();
+
+        let overlap = if terms.is_empty() {
+            0.0
+        } else {
+            terms.iter().filter(|t| vocabulary.contains(t.as_str())).count() as f32 / terms.len() as f32
+        };
+        let rank_score = if total == 0 {
+            0.0
+        } else {
+            1.0 - rank.min(total) as f32 / total as f32
+        };
+
+        let weight_sum = self.term_weight + self.rank_weight;
+        if weight_sum <= 0.0 {
+            return 0.0;
+        }
+        ((self.term_weight * overlap + self.rank_weight * rank_score) / weight_sum).clamp(0.0, 1.0)
+    }
+}
+
+/// Lowercased alphanumeric words of a text
+fn words(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .map(|w| w.to_lowercase())
+}
+
 /// Trait for integration clients
 #[async_trait]
 pub trait IntegrationClient: Send + Sync {
@@ -220,3 +303,39 @@ impl Default for IntegrationHub {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relevance_scoring_term_overlap() {
+        let scoring = RelevanceScoring::default();
+        let full = scoring.score("azure functions", "Azure Functions overview", "", 0, 2);
+        let partial = scoring.score("azure functions", "Azure Storage", "", 0, 2);
+        let none = scoring.score("azure functions", "Unrelated", "", 0, 2);
+
+        assert!((full - 1.0).abs() < 1e-6);
+        assert!(full > partial && partial > none);
+    }
+
+    #[test]
+    fn test_relevance_scoring_rank() {
+        let scoring = RelevanceScoring::default();
+        let first = scoring.score("rust", "Rust", "", 0, 4);
+        let last = scoring.score("rust", "Rust", "", 3, 4);
+        assert!(first > last);
+        assert!((0.0..=1.0).contains(&last));
+
+        let rank_only = RelevanceScoring { term_weight: 0.0, rank_weight: 1.0 };
+        assert_eq!(rank_only.score("rust", "nothing", "", 0, 4), 1.0);
+    }
+
+    #[test]
+    fn test_relevance_scoring_whole_words() {
+        let scoring = RelevanceScoring { term_weight: 1.0, rank_weight: 0.0 };
+        assert_eq!(scoring.score("go", "Google search", "", 0, 1), 0.0);
+        assert_eq!(scoring.score("go", "Go modules", "", 0, 1), 1.0);
+        assert_eq!(scoring.score("go", "Intro", "Writing go-routines", 0, 1), 1.0);
+    }
+}
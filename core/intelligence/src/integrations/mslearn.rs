@@ -3,7 +3,7 @@
 //! Provides access to official Microsoft documentation via Learn API.
 //! Used for retrieving Azure, .NET, Microsoft 365, and other Microsoft technologies docs.
 
-use super::{DocResult, IntegrationClient, SearchResult};
+use super::{DocResult, IntegrationClient, RelevanceScoring, SearchResult};
 use crate::error::{IntelligenceError, Result};
 use async_trait::async_trait;
 use reqwest::Client;
@@ -17,6 +17,9 @@ pub struct MSLearnClient {
     /// Base search URL
     search_url: String,
 
+    /// Relevance scoring for search results
+    scoring: RelevanceScoring,
+
     /// Whether initialized
     initialized: bool,
 }
@@ -62,10 +65,17 @@ impl MSLearnClient {
         Self {
             client: None,
             search_url: "https://learn.microsoft.com/api/search".to_string(),
+            scoring: RelevanceScoring::default(),
             initialized: false,
         }
     }
 
+    /// Set the relevance scoring used for search results
+    pub fn with_scoring(mut self, scoring: RelevanceScoring) -> Self {
+        self.scoring = scoring;
+        self
+    }
+
     /// Search Microsoft Learn documentation
     pub async fn search(&self, query: &str, max_results: usize) -> Result<Vec<SearchResult>> {
         if !self.initialized || self.client.is_none() {
@@ -95,18 +105,21 @@ impl MSLearnClient {
             .await
             .map_err(|e| IntelligenceError::Config(format!("MS Learn parse failed: {}", e)))?;
 
+        let total = data.results.len();
         Ok(data
             .results
             .into_iter()
-            .map(|r| SearchResult {
+            .enumerate()
+            .map(|(rank, r)| SearchResult {
+                score: self.scoring.score(query, &r.title, &r.description, rank, total),
                 title: r.title,
                 content: r.description,
                 url: Some(r.url),
                 source: "microsoft_learn".into(),
-                score: r.score.unwrap_or(0.5),
                 metadata: serde_json::json!({
                     "product": r.product,
-                    "last_updated": r.last_updated
+                    "last_updated": r.last_updated,
+                    "provider_score": r.score
                 }),
             })
             .collect())
@@ -132,6 +145,7 @@ impl MSLearnClient {
             code_snippets: vec![],
             url: first.url.clone(),
             provider: "microsoft_learn".into(),
+            version: None,
         }))
     }
 
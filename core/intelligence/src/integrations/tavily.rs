@@ -6,7 +6,7 @@
 //! - Domain filtering
 //! - Topic-based search (general, news)
 
-use super::{IntegrationClient, SearchResult};
+use super::{IntegrationClient, RelevanceScoring, SearchResult};
 use crate::error::{IntelligenceError, Result};
 use async_trait::async_trait;
 use reqwest::Client;
@@ -23,6 +23,9 @@ pub struct TavilyClient {
     /// Base URL
     base_url: String,
 
+    /// Relevance scoring for search results
+    scoring: RelevanceScoring,
+
     /// Whether initialized
     initialized: bool,
 }
@@ -165,10 +168,17 @@ impl TavilyClient {
             client: None,
             api_key,
             base_url: "https://api.tavily.com".to_string(),
+            scoring: RelevanceScoring::default(),
             initialized: false,
         }
     }
 
+    /// Set the relevance scoring used for search results
+    pub fn with_scoring(mut self, scoring: RelevanceScoring) -> Self {
+        self.scoring = scoring;
+        self
+    }
+
     /// Create client from environment variable
     pub fn from_env() -> Self {
         let api_key = std::env::var("TAVILY_API_KEY").ok();
@@ -231,17 +241,20 @@ impl TavilyClient {
             .await
             .map_err(|e| IntelligenceError::Config(format!("Tavily parse failed: {}", e)))?;
 
+        let total = data.results.len();
         Ok(data
             .results
             .into_iter()
-            .map(|r| SearchResult {
+            .enumerate()
+            .map(|(rank, r)| SearchResult {
+                score: self.scoring.score(query, &r.title, &r.content, rank, total),
                 title: r.title,
                 content: r.content,
                 url: Some(r.url),
                 source: "tavily".into(),
-                score: r.score,
                 metadata: serde_json::json!({
-                    "has_raw_content": r.raw_content.is_some()
+                    "has_raw_content": r.raw_content.is_some(),
+                    "provider_score": r.score
                 }),
             })
             .collect())
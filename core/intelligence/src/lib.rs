@@ -53,9 +53,12 @@
 //!
 //! ## Memory Tools
 //! - `memory_store`: Store information with optional semantic indexing
+//! - `ingest`: Store a memory with the entities and relations extracted from it, atomically
 //! - `memory_search`: Semantic search across all stored information
+//! - `memory_search_explain`: Search with distances and retrieval stats, for debugging
 //! - `memory_get`: Retrieve by key
-//! - `memory_delete`: Delete by key
+//! - `memory_delete`: Delete by key (soft-delete by default)
+//! - `memory_restore`: Restore a soft-deleted memory
 //!
 //! ## Knowledge Tools
 //! - `knowledge_add_entity`: Add entity to knowledge graph
@@ -64,6 +67,7 @@
 //!
 //! ## CORTEX Tools
 //! - `cortex_process`: Main entry point - Perceive, Execute, Learn pipeline
+//! - `cortex_abort`: Stop the running `cortex_process` at the next step boundary
 
 mod config;
 mod cortex;
@@ -71,18 +75,22 @@ mod error;
 pub mod integrations;
 mod memory;
 pub mod mcp_client;
+pub mod metrics;
 mod paths;
+mod progress;
 mod server;
 pub mod session;
 pub mod tools;
+mod validation;
 
-pub use config::IntelligenceConfig;
-pub use cortex::{CortexEngine, CortexConfig, CortexResult};
+pub use config::{IntelligenceConfig, NameNormalization};
+pub use cortex::{CortexEngine, CortexConfig, CortexResult, Perceiver};
 pub use error::{IntelligenceError, Result};
-pub use integrations::{IntegrationHub, Context7Client, TavilyClient, MSLearnClient};
+pub use integrations::{IntegrationHub, Context7Client, TavilyClient, MSLearnClient, RelevanceScoring, CodeSnippet};
 pub use mcp_client::{McpClientManager, McpToolResult, McpServerConfig, SequentialThinkingClient};
 pub use memory::{TripleMemory, MemoryStats};
+pub use metrics::{PayloadSizeStats, ToolMetrics, ToolPayloadStats};
 pub use paths::DataPaths;
 pub use server::IntelligenceServer;
 pub use session::{MultiSessionManager, ClientInfo, ClientSession, SessionId, SessionStats};
-pub use tools::cortex::{init_cortex, cortex_process, cortex_feedback, cortex_stats, cortex_cleanup};
+pub use tools::cortex::{init_cortex, cortex_process, cortex_feedback, cortex_stats, cortex_abort, cortex_cleanup};
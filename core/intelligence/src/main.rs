@@ -20,6 +20,9 @@
 //! whytcard-intelligence --port 3000
 //! whytcard-intelligence -p 8080 --namespace shared
 //!
+//! # Cap concurrent clients (extra connections get 503, default unlimited)
+//! whytcard-intelligence --port 3000 --max-clients 4
+//!
 //! # Clients connect via SSE to http://localhost:3000/sse
 //! # and POST messages to http://localhost:3000/message
 //! ```
@@ -28,6 +31,14 @@
 //! ```bash
 //! WHYTCARD_NAMESPACE=copilot whytcard-intelligence
 //! WHYTCARD_PORT=3000 whytcard-intelligence
+//! WHYTCARD_MAX_CLIENTS=4 whytcard-intelligence --port 3000
+//! WHYTCARD_AUTO_CLEANUP_HOURS=24 whytcard-intelligence
+//! ```
+//!
+//! ## Scheduled Cleanup
+//! ```bash
+//! # Purge old episodes and expired trash every 24 hours (default: only on request)
+//! whytcard-intelligence --auto-cleanup-hours 24
 //! ```
 
 use tracing_subscriber::{layer::SubscriberExt, util::SubscriberInitExt};
@@ -37,6 +48,10 @@ use whytcard_intelligence::{IntelligenceConfig, IntelligenceServer};
 const NAMESPACE_ENV: &str = "WHYTCARD_NAMESPACE";
 /// Environment variable for port (SSE mode)
 const PORT_ENV: &str = "WHYTCARD_PORT";
+/// Environment variable for the SSE client limit
+const MAX_CLIENTS_ENV: &str = "WHYTCARD_MAX_CLIENTS";
+/// Environment variable for the automatic cleanup interval
+const AUTO_CLEANUP_HOURS_ENV: &str = "WHYTCARD_AUTO_CLEANUP_HOURS";
 
 #[tokio::main]
 async fn main() -> anyhow::Result<()> {
@@ -50,7 +65,12 @@ async fn main() -> anyhow::Result<()> {
         .init();
 
     // Parse command line arguments
-    let (namespace, port) = parse_args();
+    let Args {
+        namespace,
+        port,
+        max_clients,
+        auto_cleanup_hours,
+    } = parse_args()?;
 
     // Determine transport mode
     let transport_mode = if let Some(p) = port {
@@ -81,6 +101,14 @@ async fn main() -> anyhow::Result<()> {
         Some(ns) => IntelligenceConfig::default().with_namespace(ns),
         None => IntelligenceConfig::default(),
     };
+    let config = match max_clients {
+        Some(max) => config.with_max_clients(max),
+        None => config,
+    };
+    let config = match auto_cleanup_hours {
+        Some(hours) => config.with_auto_cleanup_hours(hours),
+        None => config,
+    };
 
     // Create server
     let server = IntelligenceServer::new(config).await?;
@@ -106,11 +134,25 @@ enum TransportMode {
     Sse(u16),
 }
 
-/// Parse namespace and port from CLI args or environment variables
-fn parse_args() -> (Option<String>, Option<u16>) {
+/// Command line options
+struct Args {
+    /// Data namespace
+    namespace: Option<String>,
+    /// SSE port (stdio when unset)
+    port: Option<u16>,
+    /// Maximum concurrent SSE clients
+    max_clients: Option<usize>,
+    /// Hours between automatic cleanups
+    auto_cleanup_hours: Option<u64>,
+}
+
+/// Parse namespace, port, client limit and cleanup schedule from CLI args or environment variables
+fn parse_args() -> anyhow::Result<Args> {
     let args: Vec<String> = std::env::args().collect();
     let mut namespace = None;
     let mut port = None;
+    let mut max_clients = None;
+    let mut auto_cleanup_hours = None;
 
     // Parse CLI args
     let mut i = 1;
@@ -140,6 +182,25 @@ fn parse_args() -> (Option<String>, Option<u16>) {
                 port = arg.trim_start_matches("-p=").parse().ok();
                 i += 1;
             }
+            "--max-clients" if i + 1 < args.len() => {
+                max_clients = Some(parse_max_clients(&args[i + 1], "--max-clients")?);
+                i += 2;
+            }
+            "--max-clients" => {
+                anyhow::bail!("--max-clients requires a value");
+            }
+            arg if arg.starts_with("--max-clients=") => {
+                max_clients = Some(parse_max_clients(arg.trim_start_matches("--max-clients="), "--max-clients")?);
+                i += 1;
+            }
+            "--auto-cleanup-hours" if i + 1 < args.len() => {
+                auto_cleanup_hours = args[i + 1].parse().ok();
+                i += 2;
+            }
+            arg if arg.starts_with("--auto-cleanup-hours=") => {
+                auto_cleanup_hours = arg.trim_start_matches("--auto-cleanup-hours=").parse().ok();
+                i += 1;
+            }
             _ => {
                 i += 1;
             }
@@ -153,6 +214,32 @@ fn parse_args() -> (Option<String>, Option<u16>) {
     if port.is_none() {
         port = std::env::var(PORT_ENV).ok().and_then(|p| p.parse().ok());
     }
+    if max_clients.is_none() {
+        if let Ok(value) = std::env::var(MAX_CLIENTS_ENV) {
+            max_clients = Some(parse_max_clients(&value, MAX_CLIENTS_ENV)?);
+        }
+    }
+    if auto_cleanup_hours.is_none() {
+        auto_cleanup_hours = std::env::var(AUTO_CLEANUP_HOURS_ENV).ok().and_then(|h| h.parse().ok());
+    }
 
-    (namespace, port)
+    Ok(Args {
+        namespace,
+        port,
+        max_clients,
+        auto_cleanup_hours,
+    })
+}
+
+/// Parse a client limit, failing on anything that isn't a count
+///
+/// A typo must not silently lift the limit, so bad values stop startup.
+fn parse_max_clients(value: &str, origin: &str) -> anyhow::Result<usize> {
+    value.trim().parse().map_err(|_| {
+        anyhow::anyhow!(
+            "Invalid {} value '{}': expected a number of clients (0 = unlimited)",
+            origin,
+            value
+        )
+    })
 }
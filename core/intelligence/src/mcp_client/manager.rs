@@ -5,6 +5,7 @@
 
 use super::types::*;
 use crate::error::{IntelligenceError, Result};
+use futures::stream::{self, StreamExt};
 use rmcp::{
     model::{CallToolRequestParam, ClientCapabilities, ClientInfo, Implementation},
     service::ServiceExt,
@@ -272,6 +273,24 @@ impl McpClientManager {
         }
     }
 
+    /// Call several tools concurrently, returning results in input order.
+    ///
+    /// At most `max_concurrency` calls are in flight at once so child
+    /// processes are not flooded with requests.
+    pub async fn call_tools_concurrent(
+        &self,
+        calls: Vec<(String, String, Option<serde_json::Value>)>,
+        max_concurrency: usize,
+    ) -> Vec<Result<McpToolResult>> {
+        stream::iter(calls)
+            .map(|(server_name, tool_name, arguments)| async move {
+                self.call_tool(&server_name, &tool_name, arguments).await
+            })
+            .buffered(max_concurrency.max(1))
+            .collect()
+            .await
+    }
+
     /// Get available tools from all connected servers
     pub async fn list_all_tools(&self) -> Vec<McpToolInfo> {
         let cache = self.tools_cache.read().await;
@@ -496,6 +515,27 @@ mod tests {
         assert!(result.is_err());
     }
 
+    #[tokio::test]
+    async fn test_call_tools_concurrent_preserves_order() {
+        let manager = McpClientManager::new();
+        let calls = vec![
+            ("first".to_string(), "a".to_string(), None),
+            ("second".to_string(), "b".to_string(), None),
+            ("third".to_string(), "c".to_string(), None),
+        ];
+
+        let results = manager.call_tools_concurrent(calls, 2).await;
+        assert_eq!(results.len(), 3);
+
+        let errors: Vec<String> = results
+            .into_iter()
+            .map(|r| r.unwrap_err().to_string())
+            .collect();
+        assert!(errors[0].contains("first"));
+        assert!(errors[1].contains("second"));
+        assert!(errors[2].contains("third"));
+    }
+
     #[tokio::test]
     async fn test_remove_config() {
         let manager = McpClientManager::new();
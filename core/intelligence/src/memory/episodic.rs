@@ -3,7 +3,8 @@
 //! Stores events, interactions, and history in chronological order.
 //! Allows finding "what happened" and "in what context".
 
-use crate::error::Result;
+use super::dir_size;
+use crate::error::{IntelligenceError, Result};
 use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use std::path::Path;
@@ -51,9 +52,6 @@ pub struct EpisodicMemory {
     /// Database connection
     db: Database,
 
-    /// Current session ID
-    current_session: Option<String>,
-
     /// Whether initialized
     initialized: bool,
 }
@@ -72,7 +70,6 @@ impl EpisodicMemory {
 
         Ok(Self {
             db,
-            current_session: None,
             initialized: true,
         })
     }
@@ -91,20 +88,20 @@ impl EpisodicMemory {
 
         Ok(Self {
             db,
-            current_session: None,
             initialized: true,
         })
     }
 
-    /// Start a new session
-    pub async fn start_session(&mut self, workspace: Option<String>) -> Result<String> {
-        let session_id = uuid::Uuid::new_v4().to_string();
-        let now = Utc::now();
-
+    /// Store a session started by a client
+    ///
+    /// Several clients share this memory, so there is no current session
+    /// here: each tags its episodes with its own session id (see
+    /// [`Episode::with_session`]).
+    pub async fn record_session(&self, session_id: &str, workspace: Option<String>) -> Result<()> {
         let session = Session {
-            id: session_id.clone(),
+            id: session_id.to_string(),
             workspace,
-            started_at: now,
+            started_at: Utc::now(),
             ended_at: None,
             metadata: None,
         };
@@ -115,23 +112,9 @@ impl EpisodicMemory {
             .with_tags(vec!["session".to_string()]);
 
         self.db.create_document(doc).await?;
-        self.current_session = Some(session_id.clone());
 
         tracing::info!("Started session: {}", session_id);
-        Ok(session_id)
-    }
-
-    /// End the current session
-    pub async fn end_session(&mut self) -> Result<bool> {
-        if let Some(session_id) = &self.current_session {
-            // Update session with end time
-            // Note: In a real implementation, we'd update the document
-            tracing::info!("Ended session: {}", session_id);
-            self.current_session = None;
-            Ok(true)
-        } else {
-            Ok(false)
-        }
+        Ok(())
     }
 
     /// Record an episode
@@ -139,7 +122,6 @@ impl EpisodicMemory {
         let id = uuid::Uuid::new_v4().to_string();
 
         let session_id = episode.session_id
-            .or_else(|| self.current_session.clone())
             .unwrap_or_else(|| "default".to_string());
 
         let stored_episode = StoredEpisode {
@@ -238,6 +220,131 @@ impl EpisodicMemory {
         Ok(deleted)
     }
 
+    /// Compact the on-disk store, returning the number of bytes reclaimed
+    ///
+    /// RocksDB has no VACUUM: deleted records stay on disk until its files are
+    /// rewritten. Live episodes are copied into a new store built beside the
+    /// live one (keys and creation times are kept), both stores are closed and
+    /// the directories swapped. On any failure the previous store is put back
+    /// and reopened. In-memory stores are left untouched.
+    pub async fn compact(&mut self) -> Result<u64> {
+        let config = self.db.config().clone();
+        let StorageMode::Persistent(path) = config.storage.clone() else {
+            return Ok(0);
+        };
+        let before = dir_size(&path);
+        let staging = path.with_extension("compacting");
+        let previous = path.with_extension("precompact");
+        for dir in [&staging, &previous] {
+            if dir.exists() {
+                std::fs::remove_dir_all(dir)?;
+            }
+        }
+
+        // The live store keeps serving while the copy is built
+        let exported = match self.build_compacted(&config, &staging).await {
+            Ok(exported) => exported,
+            Err(e) => {
+                if let Err(remove) = std::fs::remove_dir_all(&staging) {
+                    tracing::warn!("Failed to remove partial compacted store {:?}: {}", staging, remove);
+                }
+                return Err(e);
+            }
+        };
+
+        let live = std::mem::replace(&mut self.db, Database::new_memory().await?);
+        let swapped = match live.close().await {
+            Ok(()) => Self::open_compacted(&config, &path, &staging, &previous).await,
+            Err(e) => Err(e.into()),
+        };
+        match swapped {
+            Ok(db) => self.db = db,
+            Err(e) => return Err(self.restore(&config, &staging, &previous, e).await),
+        }
+
+        if let Err(e) = std::fs::remove_dir_all(&previous) {
+            tracing::warn!("Failed to remove pre-compaction store {:?}: {}", previous, e);
+        }
+
+        let reclaimed = before.saturating_sub(dir_size(&path));
+        tracing::info!("Compacted episodic store: {} episodes, {} bytes reclaimed", exported, reclaimed);
+        Ok(reclaimed)
+    }
+
+    /// Copy the live episodes into a new store at `staging`, returning how many were copied
+    async fn build_compacted(&self, config: &DbConfig, staging: &Path) -> Result<usize> {
+        let mut export = Vec::new();
+        let exported = self.db.export_documents(&mut export, None).await?;
+
+        let mut staging_config = config.clone();
+        staging_config.storage = StorageMode::Persistent(staging.to_path_buf());
+        let db = Database::new(staging_config).await?;
+        let imported = db.import_documents(export.as_slice()).await;
+        db.close().await?;
+
+        let summary = imported?;
+        if !summary.errors.is_empty() {
+            return Err(IntelligenceError::invalid_operation(format!(
+                "Episodic compaction copied {} of {} episodes: {}",
+                summary.created + summary.updated,
+                exported,
+                summary.errors.join("; ")
+            )));
+        }
+        Ok(exported)
+    }
+
+    /// Move the closed live store aside, put the compacted copy in its place and open it
+    async fn open_compacted(config: &DbConfig, path: &Path, staging: &Path, previous: &Path) -> Result<Database> {
+        std::fs::rename(path, previous)?;
+        std::fs::rename(staging, path)?;
+        Ok(Database::new(config.clone()).await?)
+    }
+
+    /// Put the pre-compaction store back and reopen it, returning the error to report
+    async fn restore(
+        &mut self,
+        config: &DbConfig,
+        staging: &Path,
+        previous: &Path,
+        error: IntelligenceError,
+    ) -> IntelligenceError {
+        let StorageMode::Persistent(path) = &config.storage else {
+            return error;
+        };
+        let moved_back = (|| -> std::io::Result<()> {
+            if previous.exists() {
+                if path.exists() {
+                    std::fs::rename(path, staging)?;
+                }
+                std::fs::rename(previous, path)?;
+            }
+            Ok(())
+        })();
+        if staging.exists() {
+            if let Err(e) = std::fs::remove_dir_all(staging) {
+                tracing::warn!("Failed to remove compacted store {:?}: {}", staging, e);
+            }
+        }
+        if let Err(e) = moved_back {
+            return IntelligenceError::invalid_operation(format!(
+                "Episodic compaction failed ({}) and the previous store could not be moved back from {:?}: {}",
+                error, previous, e
+            ));
+        }
+
+        match Database::new(config.clone()).await {
+            Ok(db) => {
+                self.db = db;
+                error
+            }
+            Err(e) => IntelligenceError::invalid_operation(format!(
+                "Episodic compaction failed ({}) and the store at {:?} could not be reopened: {}",
+                error, path, e
+            )),
+        }
+    }
+
     /// Get statistics
     pub async fn get_stats(&self) -> EpisodicStats {
         let episodes = self.get_recent(10000, None, None).await.unwrap_or_default();
@@ -249,7 +356,6 @@ impl EpisodicMemory {
 
         EpisodicStats {
             total_episodes: episodes.len(),
-            current_session: self.current_session.clone(),
             episodes_by_type: by_type,
             initialized: self.initialized,
         }
@@ -306,6 +412,12 @@ impl Episode {
         self.context = Some(context);
         self
     }
+
+    /// Record under a specific session instead of the current one
+    pub fn with_session(mut self, session_id: impl Into<String>) -> Self {
+        self.session_id = Some(session_id.into());
+        self
+    }
 }
 
 /// A stored episode with ID and timestamps
@@ -334,7 +446,6 @@ pub struct Session {
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct EpisodicStats {
     pub total_episodes: usize,
-    pub current_session: Option<String>,
     pub episodes_by_type: std::collections::HashMap<String, usize>,
     pub initialized: bool,
 }
@@ -358,4 +469,32 @@ mod tests {
         assert_eq!(ep.content, "What is Rust?");
         assert!(ep.context.is_some());
     }
+
+    #[tokio::test]
+    async fn test_compact_keeps_live_episodes() {
+        let mut mem = EpisodicMemory::in_memory().await.unwrap();
+        assert_eq!(mem.compact().await.unwrap(), 0);
+
+        let temp = tempfile::tempdir().unwrap();
+        let path = temp.path().join("episodic.db");
+        let mut mem = EpisodicMemory::new(&path).await.unwrap();
+        for i in 0..20 {
+            mem.record(Episode::query(format!("old question {}", i))).await.unwrap();
+        }
+        assert_eq!(mem.cleanup_old(-1).await.unwrap(), 20);
+        mem.record(Episode::query("kept question")).await.unwrap();
+
+        mem.compact().await.unwrap();
+
+        let episodes = mem.get_recent(10, None, None).await.unwrap();
+        assert_eq!(episodes.len(), 1);
+        assert_eq!(episodes[0].content, "kept question");
+        assert!(path.exists());
+        assert!(!path.with_extension("compacting").exists());
+        assert!(!path.with_extension("precompact").exists());
+
+        // The reopened store keeps accepting episodes
+        mem.record(Episode::query("after compaction")).await.unwrap();
+        assert_eq!(mem.get_recent(10, None, None).await.unwrap().len(), 2);
+    }
 }
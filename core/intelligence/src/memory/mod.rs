@@ -15,6 +15,7 @@ pub use procedural::*;
 
 use crate::error::Result;
 use crate::paths::DataPaths;
+use std::path::Path;
 use std::sync::Arc;
 use tokio::sync::RwLock;
 
@@ -42,7 +43,7 @@ impl TripleMemory {
         let semantic = SemanticMemory::new(&semantic_path).await?;
         tracing::debug!("Semantic memory initialized at {:?}", semantic_path);
 
-        // Initialize episodic memory (SQLite)
+        // Initialize episodic memory (RocksDB-backed store)
         let episodic_path = paths.cortex_memory.join("episodic.db");
         let episodic = EpisodicMemory::new(&episodic_path).await?;
         tracing::debug!("Episodic memory initialized at {:?}", episodic_path);
@@ -94,3 +95,18 @@ pub struct MemoryStats {
     pub episodic: EpisodicStats,
     pub procedural: ProceduralStats,
 }
+
+/// Total size of the files under `path`, in bytes (unreadable entries count as 0)
+pub(crate) fn dir_size(path: &Path) -> u64 {
+    let Ok(entries) = std::fs::read_dir(path) else {
+        return 0;
+    };
+    entries
+        .flatten()
+        .map(|entry| match entry.metadata() {
+            Ok(meta) if meta.is_dir() => dir_size(&entry.path()),
+            Ok(meta) => meta.len(),
+            Err(_) => 0,
+        })
+        .sum()
+}
@@ -16,6 +16,8 @@ const ROUTING_FILE: &str = "routing.yaml";
 const MIN_CONFIDENCE: f32 = 0.5;
 const CONFIDENCE_INCREMENT: f32 = 0.1;
 const CONFIDENCE_DECREMENT: f32 = 0.15;
+const DEFAULT_CONFIDENCE_FLOOR: f32 = 0.05;
+const DEFAULT_CONFIDENCE_CEILING: f32 = 0.95;
 
 /// Procedural memory for rules and learned procedures
 pub struct ProceduralMemory {
@@ -31,6 +33,10 @@ pub struct ProceduralMemory {
     /// Cached routing rules
     routing: HashMap<String, RoutingRule>,
 
+    /// Feedback keeps confidence within [floor, ceiling]
+    confidence_floor: f32,
+    confidence_ceiling: f32,
+
     /// Whether using in-memory mode
     in_memory: bool,
 
@@ -51,6 +57,8 @@ impl ProceduralMemory {
             rules: HashMap::new(),
             patterns: HashMap::new(),
             routing: HashMap::new(),
+            confidence_floor: DEFAULT_CONFIDENCE_FLOOR,
+            confidence_ceiling: DEFAULT_CONFIDENCE_CEILING,
             in_memory: false,
             initialized: false,
         };
@@ -69,6 +77,8 @@ impl ProceduralMemory {
             rules: HashMap::new(),
             patterns: HashMap::new(),
             routing: HashMap::new(),
+            confidence_floor: DEFAULT_CONFIDENCE_FLOOR,
+            confidence_ceiling: DEFAULT_CONFIDENCE_CEILING,
             in_memory: true,
             initialized: true,
         };
@@ -404,25 +414,60 @@ impl ProceduralMemory {
         Ok(id)
     }
 
-    /// Update confidence for a rule based on success/failure
-    pub fn update_confidence(&mut self, rule_id: &str, success: bool) -> Result<f32> {
-        let rule = self.rules.get_mut(rule_id)
-            .ok_or_else(|| IntelligenceError::KeyNotFound(format!("Rule not found: {}", rule_id)))?;
+    /// Set the range feedback keeps confidence in
+    ///
+    /// A ceiling below 1.0 leaves every rule some uncertainty so failures can
+    /// still decay it. Bounds are clamped to [0, 1] with floor <= ceiling.
+    pub fn set_confidence_bounds(&mut self, floor: f32, ceiling: f32) {
+        let floor = floor.clamp(0.0, 1.0);
+        self.confidence_floor = floor;
+        self.confidence_ceiling = ceiling.clamp(floor, 1.0);
+    }
+
+    /// Current (floor, ceiling) confidence bounds
+    pub fn confidence_bounds(&self) -> (f32, f32) {
+        (self.confidence_floor, self.confidence_ceiling)
+    }
 
-        if success {
-            rule.success_count += 1;
-            rule.confidence = (rule.confidence + CONFIDENCE_INCREMENT).min(1.0);
+    /// Apply one feedback step to a confidence value, within the bounds
+    fn adjust_confidence(&self, confidence: f32, success: bool) -> f32 {
+        let adjusted = if success {
+            confidence + CONFIDENCE_INCREMENT
         } else {
-            rule.failure_count += 1;
-            rule.confidence = (rule.confidence - CONFIDENCE_DECREMENT).max(0.0);
+            confidence - CONFIDENCE_DECREMENT
+        };
+        adjusted.clamp(self.confidence_floor, self.confidence_ceiling)
+    }
+
+    /// Update confidence for a rule (or routing rule) based on success/failure
+    pub fn update_confidence(&mut self, rule_id: &str, success: bool) -> Result<f32> {
+        if let Some(current) = self.rules.get(rule_id).map(|r| r.confidence) {
+            let confidence = self.adjust_confidence(current, success);
+            let rule = self.rules.get_mut(rule_id).expect("rule present");
+            if success {
+                rule.success_count += 1;
+            } else {
+                rule.failure_count += 1;
+            }
+            rule.confidence = confidence;
+            rule.updated_at = chrono::Utc::now().to_rfc3339();
+
+            self.save_rules()?;
+            return Ok(confidence);
         }
 
-        rule.updated_at = chrono::Utc::now().to_rfc3339();
-        let new_confidence = rule.confidence;
+        // Routing recommendations report their routing_id for feedback
+        if let Some(current) = self.routing.get(rule_id).map(|r| r.confidence) {
+            let confidence = self.adjust_confidence(current, success);
+            if let Some(routing) = self.routing.get_mut(rule_id) {
+                routing.confidence = confidence;
+            }
 
-        self.save_rules()?;
+            self.save_routing()?;
+            return Ok(confidence);
+        }
 
-        Ok(new_confidence)
+        Err(IntelligenceError::KeyNotFound(format!("Rule not found: {}", rule_id)))
     }
 
     /// Increment routing usage count
@@ -434,6 +479,28 @@ impl ProceduralMemory {
         Ok(())
     }
 
+    /// Remove rules not updated within `retention_days`
+    ///
+    /// Feedback bumps `updated_at`, so rules that are still in use survive.
+    pub fn prune_stale_rules(&mut self, retention_days: i64) -> Result<usize> {
+        let cutoff = chrono::Utc::now() - chrono::Duration::days(retention_days);
+        let before = self.rules.len();
+
+        self.rules.retain(|_, rule| {
+            chrono::DateTime::parse_from_rfc3339(&rule.updated_at)
+                .map(|updated| updated >= cutoff)
+                .unwrap_or(true)
+        });
+
+        let removed = before - self.rules.len();
+        if removed > 0 {
+            self.save_rules()?;
+        }
+
+        tracing::info!("Pruned {} stale rules", removed);
+        Ok(removed)
+    }
+
     /// Get statistics
     pub fn get_stats(&self) -> ProceduralStats {
         let avg_confidence = if self.rules.is_empty() {
@@ -560,6 +627,33 @@ mod tests {
         assert_eq!(matches[0].pattern_name, "code_generation");
     }
 
+    #[tokio::test]
+    async fn test_confidence_bounds() {
+        let mut mem = ProceduralMemory::in_memory().await.unwrap();
+        let id = mem
+            .add_rule("sticky".into(), "always".into(), "route".into(), 0.9)
+            .unwrap();
+
+        // Repeated success stops at the ceiling instead of reaching 1.0
+        for _ in 0..10 {
+            mem.update_confidence(&id, true).unwrap();
+        }
+        assert_eq!(mem.rules[&id].confidence, DEFAULT_CONFIDENCE_CEILING);
+
+        // ... so a single failure can still decay it
+        let decayed = mem.update_confidence(&id, false).unwrap();
+        assert!(decayed < DEFAULT_CONFIDENCE_CEILING);
+
+        // Repeated failure stops at the floor
+        for _ in 0..20 {
+            mem.update_confidence(&id, false).unwrap();
+        }
+        assert_eq!(mem.rules[&id].confidence, DEFAULT_CONFIDENCE_FLOOR);
+
+        mem.set_confidence_bounds(0.2, 0.1);
+        assert_eq!(mem.confidence_bounds(), (0.2, 0.2));
+    }
+
     #[tokio::test]
     async fn test_routing() {
         let mut mem = ProceduralMemory::in_memory().await.unwrap();
@@ -569,4 +663,22 @@ mod tests {
         assert!(routing.is_some());
         assert_eq!(routing.unwrap().target_agent, "code");
     }
+
+    #[tokio::test]
+    async fn test_prune_stale_rules() {
+        let mut mem = ProceduralMemory::in_memory().await.unwrap();
+        let fresh = mem
+            .add_rule("fresh".into(), "always".into(), "route".into(), 0.8)
+            .unwrap();
+        let stale = mem
+            .add_rule("stale".into(), "never".into(), "route".into(), 0.8)
+            .unwrap();
+        let old = (chrono::Utc::now() - chrono::Duration::days(90)).to_rfc3339();
+        mem.rules.get_mut(&stale).unwrap().updated_at = old;
+
+        assert_eq!(mem.prune_stale_rules(30).unwrap(), 1);
+        assert!(mem.rules.contains_key(&fresh));
+        assert!(!mem.rules.contains_key(&stale));
+        assert_eq!(mem.prune_stale_rules(30).unwrap(), 0);
+    }
 }
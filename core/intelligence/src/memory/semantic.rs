@@ -3,9 +3,10 @@
 //! Vector-based memory for semantic search and retrieval.
 //! Stores facts, knowledge, API patterns, and definitions.
 
+use super::dir_size;
 use crate::error::Result;
 use serde::{Deserialize, Serialize};
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use whytcard_database::{Config as DbConfig, Database, StorageMode, VectorConfig};
 use whytcard_rag::{Document, RagEngine, RagEngineBuilder};
 
@@ -19,6 +20,9 @@ pub struct SemanticMemory {
 
     /// Whether initialized
     initialized: bool,
+
+    /// On-disk location (None for in-memory)
+    base_path: Option<PathBuf>,
 }
 
 impl SemanticMemory {
@@ -38,6 +42,8 @@ impl SemanticMemory {
             vector_config: VectorConfig {
                 dimension: 384,
                 distance: whytcard_database::DistanceMetric::Cosine,
+                normalized: true,
+                ..Default::default()
             },
         };
 
@@ -52,6 +58,7 @@ impl SemanticMemory {
             db,
             rag,
             initialized: true,
+            base_path: Some(base_path.to_path_buf()),
         })
     }
 
@@ -77,6 +84,7 @@ impl SemanticMemory {
             db,
             rag,
             initialized: true,
+            base_path: None,
         })
     }
 
@@ -153,13 +161,36 @@ impl SemanticMemory {
         Ok(true)
     }
 
+    /// Delete facts stored more than `retention_days` ago
+    pub async fn cleanup_old(&mut self, retention_days: i64) -> Result<usize> {
+        let cutoff = chrono::Utc::now() - chrono::Duration::days(retention_days);
+
+        let docs = self.db.list_documents(None, 10000, 0).await?;
+        let mut deleted = 0;
+
+        for doc in docs {
+            let expired = doc.created_at.map(|created| created < cutoff).unwrap_or(false);
+            if let (true, Some(key)) = (expired, doc.key) {
+                self.delete(&key).await?;
+                deleted += 1;
+            }
+        }
+
+        tracing::info!("Cleaned up {} old semantic facts", deleted);
+        Ok(deleted)
+    }
+
     /// Get statistics
     pub async fn get_stats(&self) -> SemanticStats {
-        let count = self.db.count_documents().await.unwrap_or(0);
+        let count = self.db.count_documents(None).await.unwrap_or(0);
+        let model = &self.rag.config().embedding_model;
 
         SemanticStats {
             total_facts: count,
             initialized: self.initialized,
+            dimension: model.dimensions(),
+            index_size_bytes: self.base_path.as_deref().map(dir_size).unwrap_or(0),
+            embedding_model: model.fastembed_name().to_string(),
         }
     }
 }
@@ -224,6 +255,15 @@ pub struct SemanticSearchResult {
 pub struct SemanticStats {
     pub total_facts: usize,
     pub initialized: bool,
+    /// Vector dimension of the embedding model
+    #[serde(default)]
+    pub dimension: usize,
+    /// Size of the database and vector index on disk (0 in memory)
+    #[serde(default)]
+    pub index_size_bytes: u64,
+    /// Embedding model used to index facts
+    #[serde(default)]
+    pub embedding_model: String,
 }
 
 #[cfg(test)]
@@ -235,4 +275,20 @@ mod tests {
         let mem = SemanticMemory::in_memory().await.unwrap();
         assert!(mem.initialized);
     }
+
+    #[tokio::test]
+    async fn test_semantic_stats() {
+        let mem = SemanticMemory::in_memory().await.unwrap();
+        let stats = mem.get_stats().await;
+        assert_eq!(stats.dimension, 384);
+        assert_eq!(stats.embedding_model, "sentence-transformers/all-MiniLM-L6-v2");
+        assert_eq!(stats.index_size_bytes, 0);
+
+        let temp = tempfile::tempdir().unwrap();
+        std::fs::create_dir_all(temp.path().join("db")).unwrap();
+        std::fs::write(temp.path().join("db").join("a"), [0u8; 100]).unwrap();
+        std::fs::write(temp.path().join("b"), [0u8; 20]).unwrap();
+        assert_eq!(dir_size(temp.path()), 120);
+        assert_eq!(dir_size(&temp.path().join("missing")), 0);
+    }
 }
@@ -0,0 +1,190 @@
+//! Per-tool payload size metrics
+//!
+//! Every tool call records the serialized size of its arguments and of its
+//! result (or error). Totals are exact; percentiles are computed over the most
+//! recent [`SAMPLE_WINDOW`] calls of each tool so memory stays bounded on
+//! long-running servers.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+
+/// Number of recent samples kept per tool for percentiles
+pub const SAMPLE_WINDOW: usize = 1024;
+
+/// Aggregated sizes for one direction (request or response) of a tool
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct PayloadSizeStats {
+    /// Total bytes over every call since startup
+    pub total_bytes: u64,
+
+    /// Median size in bytes over the sample window
+    pub p50_bytes: u64,
+
+    /// 95th percentile size in bytes over the sample window
+    pub p95_bytes: u64,
+
+    /// Largest size in bytes since startup
+    pub max_bytes: u64,
+}
+
+/// Payload sizes handled by one tool
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize, JsonSchema)]
+pub struct ToolPayloadStats {
+    /// Tool name
+    pub tool: String,
+
+    /// Number of calls since startup
+    pub calls: u64,
+
+    /// Serialized argument sizes
+    pub request: PayloadSizeStats,
+
+    /// Serialized result sizes
+    pub response: PayloadSizeStats,
+}
+
+/// Raw samples for one direction of a tool
+#[derive(Debug, Default)]
+struct SizeSamples {
+    total: u64,
+    max: u64,
+    recent: VecDeque<u64>,
+}
+
+impl SizeSamples {
+    fn record(&mut self, bytes: u64) {
+        self.total += bytes;
+        self.max = self.max.max(bytes);
+        if self.recent.len() == SAMPLE_WINDOW {
+            self.recent.pop_front();
+        }
+        self.recent.push_back(bytes);
+    }
+
+    fn stats(&self) -> PayloadSizeStats {
+        let mut sorted: Vec<u64> = self.recent.iter().copied().collect();
+        sorted.sort_unstable();
+        PayloadSizeStats {
+            total_bytes: self.total,
+            p50_bytes: percentile(&sorted, 50),
+            p95_bytes: percentile(&sorted, 95),
+            max_bytes: self.max,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct ToolSamples {
+    calls: u64,
+    request: SizeSamples,
+    response: SizeSamples,
+}
+
+/// Payload size recorder shared by every session of a server
+#[derive(Debug, Default)]
+pub struct ToolMetrics {
+    tools: Mutex<HashMap<String, ToolSamples>>,
+}
+
+impl ToolMetrics {
+    /// Create an empty recorder
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Record one call of `tool`
+    pub fn record(&self, tool: &str, request_bytes: u64, response_bytes: u64) {
+        let mut tools = self.tools.lock().unwrap_or_else(|e| e.into_inner());
+        let samples = tools.entry(tool.to_string()).or_default();
+        samples.calls += 1;
+        samples.request.record(request_bytes);
+        samples.response.record(response_bytes);
+    }
+
+    /// Aggregates per tool, heaviest total response bandwidth first
+    pub fn snapshot(&self) -> Vec<ToolPayloadStats> {
+        let tools = self.tools.lock().unwrap_or_else(|e| e.into_inner());
+        let mut stats: Vec<ToolPayloadStats> = tools
+            .iter()
+            .map(|(tool, samples)| ToolPayloadStats {
+                tool: tool.clone(),
+                calls: samples.calls,
+                request: samples.request.stats(),
+                response: samples.response.stats(),
+            })
+            .collect();
+        stats.sort_by(|a, b| {
+            b.response
+                .total_bytes
+                .cmp(&a.response.total_bytes)
+                .then_with(|| a.tool.cmp(&b.tool))
+        });
+        stats
+    }
+}
+
+/// Serialized JSON size of `value`, 0 if it cannot be serialized
+pub fn json_size<T: Serialize + ?Sized>(value: &T) -> u64 {
+    serde_json::to_vec(value).map(|v| v.len() as u64).unwrap_or(0)
+}
+
+/// Nearest-rank percentile of sorted samples
+fn percentile(sorted: &[u64], p: usize) -> u64 {
+    if sorted.is_empty() {
+        return 0;
+    }
+    let rank = (p * sorted.len()).div_ceil(100).max(1);
+    sorted[rank - 1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_percentile_nearest_rank() {
+        let samples: Vec<u64> = (1..=100).collect();
+        assert_eq!(percentile(&samples, 50), 50);
+        assert_eq!(percentile(&samples, 95), 95);
+        assert_eq!(percentile(&[7], 95), 7);
+        assert_eq!(percentile(&[], 50), 0);
+    }
+
+    #[test]
+    fn test_tool_metrics_aggregates_per_tool() {
+        let metrics = ToolMetrics::new();
+        for size in [100, 200, 300, 400] {
+            metrics.record("memory_list", 20, size);
+        }
+        metrics.record("memory_get", 30, 50);
+
+        let stats = metrics.snapshot();
+        assert_eq!(stats.len(), 2);
+
+        // Heaviest response bandwidth comes first
+        assert_eq!(stats[0].tool, "memory_list");
+        assert_eq!(stats[0].calls, 4);
+        assert_eq!(stats[0].request.total_bytes, 80);
+        assert_eq!(stats[0].response.total_bytes, 1000);
+        assert_eq!(stats[0].response.p50_bytes, 200);
+        assert_eq!(stats[0].response.p95_bytes, 400);
+        assert_eq!(stats[0].response.max_bytes, 400);
+        assert_eq!(stats[1].tool, "memory_get");
+    }
+
+    #[test]
+    fn test_percentiles_use_recent_window() {
+        let metrics = ToolMetrics::new();
+        metrics.record("knowledge_read_graph", 0, 1_000_000);
+        for _ in 0..SAMPLE_WINDOW {
+            metrics.record("knowledge_read_graph", 0, 10);
+        }
+
+        let stats = &metrics.snapshot()[0];
+        assert_eq!(stats.response.p95_bytes, 10);
+        assert_eq!(stats.response.max_bytes, 1_000_000);
+        assert_eq!(stats.response.total_bytes, 1_000_000 + 10 * SAMPLE_WINDOW as u64);
+    }
+}
@@ -14,7 +14,7 @@
 
 use crate::error::{IntelligenceError, Result};
 use directories::ProjectDirs;
-use std::path::{Path, PathBuf};
+use std::path::{Component, Path, PathBuf};
 
 /// Application identifier constants
 const APP_QUALIFIER: &str = "com";
@@ -39,6 +39,8 @@ pub struct DataPaths {
     pub models: PathBuf,
     /// Logs directory
     pub logs: PathBuf,
+    /// Directory memory_export writes to and memory_import reads from
+    pub exports: PathBuf,
     /// Config file path
     pub config: PathBuf,
     /// Namespace (for multi-instance support)
@@ -66,6 +68,7 @@ impl DataPaths {
             cortex_memory: root.join("cortex"),
             models: root.join("models"),
             logs: root.join("logs"),
+            exports: root.join("exports"),
             config: root.join("config.toml"),
             namespace: None,
             root,
@@ -82,6 +85,7 @@ impl DataPaths {
             // Models are shared across namespaces (they're large)
             models: root.join("models"),
             logs: namespaced_root.join("logs"),
+            exports: namespaced_root.join("exports"),
             config: namespaced_root.join("config.toml"),
             namespace: Some(namespace.to_string()),
             root: namespaced_root,
@@ -113,6 +117,7 @@ impl DataPaths {
         std::fs::create_dir_all(&self.cortex_memory)?;
         std::fs::create_dir_all(&self.models)?;
         std::fs::create_dir_all(&self.logs)?;
+        std::fs::create_dir_all(&self.exports)?;
         Ok(())
     }
 
@@ -139,6 +144,35 @@ impl DataPaths {
         Ok(path)
     }
 
+    /// Resolve a file name given by a client inside the exports directory
+    ///
+    /// Only relative paths made of plain components are accepted, so a client
+    /// can never read or write outside `exports`.
+    pub fn export_file(&self, relative: &str) -> Result<PathBuf> {
+        Self::confine(&self.exports, relative)
+    }
+
+    /// Join a relative path onto `base`, rejecting anything that could escape it
+    fn confine(base: &Path, relative: &str) -> Result<PathBuf> {
+        let path = Path::new(relative);
+        if relative.trim().is_empty() {
+            return Err(IntelligenceError::invalid_param("path", "must not be empty"));
+        }
+        if path.is_absolute() {
+            return Err(IntelligenceError::invalid_param(
+                "path",
+                format!("'{}' must be relative to the exports directory", relative),
+            ));
+        }
+        if !path.components().all(|c| matches!(c, Component::Normal(_) | Component::CurDir)) {
+            return Err(IntelligenceError::invalid_param(
+                "path",
+                format!("'{}' must not contain '..' or a drive prefix", relative),
+            ));
+        }
+        Ok(base.join(path))
+    }
+
     /// Get the database connection string for SQLite
     pub fn database_url(&self) -> String {
         format!("sqlite:{}", self.database.display())
@@ -175,6 +209,7 @@ mod tests {
         assert_eq!(paths.cortex_memory, root.join("cortex"));
         assert_eq!(paths.models, root.join("models"));
         assert_eq!(paths.logs, root.join("logs"));
+        assert_eq!(paths.exports, root.join("exports"));
         assert_eq!(paths.config, root.join("config.toml"));
     }
 
@@ -190,6 +225,21 @@ mod tests {
         assert!(paths.cortex_memory.exists());
         assert!(paths.models.exists());
         assert!(paths.logs.exists());
+        assert!(paths.exports.exists());
+    }
+
+    #[test]
+    fn test_export_file_confined() {
+        let paths = DataPaths::from_root(PathBuf::from("/data"));
+
+        assert_eq!(
+            paths.export_file("backup/memories.jsonl").unwrap(),
+            PathBuf::from("/data/exports/backup/memories.jsonl")
+        );
+        assert!(paths.export_file("").is_err());
+        assert!(paths.export_file("../whytcard.db").is_err());
+        assert!(paths.export_file("backup/../../config.toml").is_err());
+        assert!(paths.export_file("/etc/passwd").is_err());
     }
 
     #[test]
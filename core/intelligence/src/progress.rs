@@ -0,0 +1,60 @@
+//! Incremental tool output via MCP progress notifications
+//!
+//! Long-running tools emit one notification per partial result, and per step
+//! start and finish, so clients (over stdio, SSE or HTTP) can render output as
+//! it arrives. Clients opt in by sending a `progressToken` in the request
+//! `_meta`; without one, reporting is a no-op.
+
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use rmcp::model::{Meta, ProgressNotificationParam, ProgressToken};
+use rmcp::service::Peer;
+use rmcp::RoleServer;
+
+/// Sends progress events for a single tool call
+///
+/// Each event advances `progress` by one, so `total` is the number of events
+/// the tool will send.
+pub(crate) struct ProgressReporter {
+    peer: Peer<RoleServer>,
+    token: Option<ProgressToken>,
+    total: Option<f64>,
+    sent: AtomicUsize,
+}
+
+impl ProgressReporter {
+    /// Create a reporter for the request described by `meta`
+    pub(crate) fn new(meta: &Meta, peer: Peer<RoleServer>, total: Option<usize>) -> Self {
+        Self {
+            peer,
+            token: meta.get_progress_token(),
+            total: total.map(|t| t as f64),
+            sent: AtomicUsize::new(0),
+        }
+    }
+
+    /// Whether the client asked for progress events
+    pub(crate) fn is_enabled(&self) -> bool {
+        self.token.is_some()
+    }
+
+    /// Emit the next progress event, with a JSON payload as its message
+    pub(crate) async fn report(&self, payload: serde_json::Value) {
+        let Some(token) = &self.token else {
+            return;
+        };
+        let progress = self.sent.fetch_add(1, Ordering::Relaxed) + 1;
+
+        let notification = ProgressNotificationParam {
+            progress_token: token.clone(),
+            progress: progress as f64,
+            total: self.total,
+            message: Some(payload.to_string()),
+        };
+
+        // Delivery is best effort; the final tool result still carries everything
+        if let Err(e) = self.peer.notify_progress(notification).await {
+            tracing::debug!("Failed to send progress notification: {}", e);
+        }
+    }
+}
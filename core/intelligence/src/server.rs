@@ -3,25 +3,42 @@
 //! Uses the official rmcp SDK for MCP protocol handling.
 
 use crate::config::IntelligenceConfig;
-use crate::cortex::{CortexConfig, CortexEngine};
+use crate::cortex::instructions::InstructionsPrompt;
+use crate::cortex::CortexEngine;
 use crate::error::IntelligenceError;
 use crate::integrations::{Context7Client, IntegrationClient, MSLearnClient, TavilyClient};
 use crate::mcp_client::{InstalledMcpServer, McpClientManager, McpConfigManager, PredefinedServers, SequentialThinkingClient};
+use crate::metrics::{json_size, ToolMetrics};
+use crate::paths::DataPaths;
+use crate::progress::ProgressReporter;
+use crate::session::{ClientInfo, MultiSessionManager};
+use crate::validation::{require_non_negative, require_one_of, require_positive, require_unit_range};
 use crate::tools::{
     // CORTEX tools
-    CortexCleanupParams, CortexCleanupResult, CortexExecuteParams, CortexExecuteResult,
+    CortexAbortParams, CortexAbortResult, CortexCleanupParams, CortexCleanupResult, CortexExecuteParams, CortexExecuteResult,
     CortexFeedbackParams, CortexFeedbackResult, CortexInstructionsParams, CortexInstructionsResult,
-    CortexProcessParams, CortexProcessResult, CortexStatsParams, CortexStatsResult,
-    InstructionInfo, InstructionsAction,
+    CortexPreviewContextResult, CortexProcessParams, CortexProcessResult, CortexStatsParams,
+    CortexStatsResult, RECENT_QUERIES_LIMIT,
+    InstructionInfo, InstructionsAction, remaining_timeout, response_language_instruction,
+    UserInstructionInfo, UserInstructionsDeleteParams, UserInstructionsDeleteResult,
+    UserInstructionsListParams, UserInstructionsListResult, user_instruction_key,
+    user_instruction_prefix,
     // External tools
-    ExternalDocsParams, ExternalDocsResult, ExternalMcpCallParams,
-    ExternalMcpCallResult, ExternalSearchParams, ExternalSearchResult, KeyRequiredServer,
+    DocsFallbackMode, ExternalDocsParams, ExternalDocsResult, ExternalMcpCallBatchParams,
+    ExternalMcpCallBatchResult, ExternalMcpCallParams, truncate_at_boundary, ExternalMcpCallResult, ExternalSearchParams, ExternalSearchResult, KeyRequiredServer,
     McpAvailableServersParams, McpAvailableServersResult, McpConfigureParams, McpConfigureResult,
     McpConnectParams, McpConnectResult, McpDisconnectParams, McpDisconnectResult,
     McpInstallParams, McpInstallResult, McpListInstalledParams, McpListInstalledResult,
     McpListToolsParams, McpListToolsResult, McpServerInfo, McpServerStatus, McpStatusParams,
     McpStatusResult, McpToolDetail, McpUninstallParams, McpUninstallResult, SearchResultItem,
     SequentialThinkingParams, SequentialThinkingResult, ServerDescription, ThinkingStep, ToolInfo,
+    // Health
+    HealthParams, HealthResult, SubsystemHealth,
+    // Capabilities
+    CapabilitiesParams, CapabilitiesResult, EmbeddingCapability, FeatureFlags,
+    IntegrationCapability, Limits, StorageCapability,
+    // Ingest
+    IngestParams, IngestResult, ENTITIES_METADATA_KEY, SOURCE_OBSERVATION_PREFIX,
     // Knowledge tools
     EntityInfo, ExportGraphParams, ExportGraphResult, KnowledgeAddEntityParams,
     KnowledgeAddEntityResult, KnowledgeAddObservationParams, KnowledgeAddObservationResult,
@@ -30,17 +47,22 @@ use crate::tools::{
     KnowledgeDeleteRelationParams, KnowledgeDeleteRelationResult, KnowledgeFindPathParams,
     KnowledgeFindPathResult, KnowledgeGetEntityParams, KnowledgeGetEntityResult,
     KnowledgeGetNeighborsParams, KnowledgeGetNeighborsResult, KnowledgeReadGraphParams,
-    KnowledgeReadGraphResult, KnowledgeSearchParams, KnowledgeSearchResult, NeighborInfo,
+    KnowledgeReadGraphResult, KnowledgeSearchMode, KnowledgeSearchParams, KnowledgeSearchResult,
+    NeighborInfo,
     // Memory tools
-    BatchStoreParams, BatchStoreResult, ContextScores, EpisodicItem,
-    GetContextParams, GetContextResult, HybridSearchParams, HybridSearchResult,
+    BatchStoreItem, BatchStoreParams, BatchStoreResult, ContextScores, EpisodicItem,
+    GetContextParams, GetContextResult, HybridItem, HybridSearchParams, HybridSearchResult,
     ManageTagsParams, ManageTagsResult, MemoryDeleteParams, MemoryDeleteResult, MemoryGetParams,
-    MemoryGetResult, MemoryListParams, MemoryListResult, MemorySearchParams, MemorySearchResult,
-    MemoryStoreParams, MemoryStoreResult, ProceduralItem, RelationInfo, SemanticItem,
+    MemoryExportParams, MemoryExportResult, MemoryGetResult, MemoryImportParams, MemoryImportResult,
+    MemoryListParams, MemoryListResult,
+    MemoryRestoreParams, MemoryRestoreResult,
+    MemorySearchExplainItem, MemorySearchExplainParams, MemorySearchExplainResult,
+    MemorySearchParams, MemorySearchResult, MemoryStoreParams, MemoryStoreResult, ProceduralItem, RelationInfo, SemanticItem,
+    reciprocal_rank_fusion,
     // Pipeline types (ACID workflow)
     pipelines::{
-        AnalyzeParams, AnalyzeResult, AnalyzeSource, PipelineResponse,
-        PrepareParams, PrepareResult,
+        AnalyzeParams, AnalyzeResult, AnalyzeSource, PipelineResponse, analyze_confidence, dedup_by_similarity,
+        PrepareParams, PrepareResult, UserInstructionDef,
         CodeParams, CodeResult,
         VerifyParams, VerifyResult, VerifyCheck,
         DocumentParams, DocumentResult,
@@ -48,25 +70,54 @@ use crate::tools::{
     },
 };
 use rmcp::{
-    handler::server::router::tool::ToolRouter,
+    handler::server::{router::tool::ToolRouter, tool::ToolCallContext},
     model::*,
-    tool, tool_handler, tool_router,
-    ErrorData as McpError, Json, ServiceExt,
+    service::RequestContext,
+    tool, tool_router,
+    ErrorData as McpError, Json, RoleServer, ServiceExt,
 };
 use std::sync::Arc;
 use tokio::sync::RwLock;
 use whytcard_database::{
-    Config as DbConfig, CreateEntity, CreateRelation, Database, StorageMode,
-    VectorConfig,
+    Config as DbConfig, CreateEntity, CreateRelation, Database, RelationDirection,
+    StorageMode, VectorConfig,
 };
 use whytcard_rag::RagEngine;
 
+/// Instructions sent to clients unless `IntelligenceConfig::instructions` is set
+const DEFAULT_INSTRUCTIONS: &str = "WhytCard Intelligence - A cognitive memory and knowledge system powered by CORTEX. \
+     Use cortex_* tools for intelligent query processing and learning. \
+     Use memory_* tools to store and search information. \
+     Use knowledge_* tools to manage entities and relations.";
+
+/// Entities embedded per batch when refreshing entity embeddings
+const ENTITY_EMBEDDING_BATCH: usize = 64;
+
+/// Semantic entity matches merged into a knowledge_search page at least
+const SEMANTIC_ENTITY_MATCHES: usize = 100;
+
+/// Documentation providers known to `fetch_docs_from`
+const DOCS_PROVIDERS: &[&str] = &["context7", "mslearn"];
+
+/// Runs entity embedding refreshes in the background, one at a time
+///
+/// Writes only raise `pending`; a refresh already queued picks up every write
+/// made before it starts, so bursts of writes cost one refresh.
+#[derive(Default)]
+struct EntityEmbeddingRefresh {
+    pending: std::sync::atomic::AtomicBool,
+    running: tokio::sync::Mutex<()>,
+}
+
 /// WhytCard Intelligence MCP Server
 #[derive(Clone)]
 pub struct IntelligenceServer {
     /// Configuration
     config: Arc<IntelligenceConfig>,
 
+    /// Resolved data paths
+    paths: Arc<DataPaths>,
+
     /// Database (SurrealDB)
     db: Arc<Database>,
 
@@ -94,6 +145,15 @@ pub struct IntelligenceServer {
     /// MCP configuration manager for persistence
     mcp_config: Arc<RwLock<McpConfigManager>>,
 
+    /// Payload sizes of handled tool calls
+    metrics: Arc<ToolMetrics>,
+
+    /// Connected SSE clients, capped at `config.max_clients`
+    sessions: Arc<MultiSessionManager>,
+
+    /// Background refresh of entity embeddings after graph writes
+    entity_embeddings: Arc<EntityEmbeddingRefresh>,
+
     /// Tool router
     tool_router: ToolRouter<Self>,
 }
@@ -109,7 +169,9 @@ impl IntelligenceServer {
         tracing::info!("Data directory: {:?}", paths.root);
 
         // Initialize SurrealDB database
-        let storage = if paths.database.to_str().is_some_and(|s| s.contains(":memory:")) {
+        let storage = if let Some(remote) = config.database.remote_storage() {
+            remote
+        } else if paths.database.to_str().is_some_and(|s| s.contains(":memory:")) {
             StorageMode::Memory
         } else {
             StorageMode::Persistent(paths.database.clone())
@@ -122,6 +184,8 @@ impl IntelligenceServer {
             vector_config: VectorConfig {
                 dimension: config.rag.model.dimensions(),
                 distance: whytcard_database::DistanceMetric::Cosine,
+                normalized: true,
+                ..Default::default()
             },
         };
 
@@ -130,22 +194,29 @@ impl IntelligenceServer {
 
         // Initialize RAG engine
         tracing::info!("Initializing RAG engine: {:?}", paths.vectors);
-        let rag = whytcard_rag::RagEngineBuilder::new()
+        let mut rag = whytcard_rag::RagEngineBuilder::new()
             .db_path(paths.vectors.to_str().unwrap_or("vectors"))
             .embedding_model(config.rag.model.clone())
             .chunking_config(config.rag.chunking.clone())
-            .search_config(config.rag.search.clone())
-            .build()
-            .await?;
+            .search_config(config.rag.search.clone());
+        if let Some(cache) = config.rag.embedding_cache.cache_config(&paths.root) {
+            tracing::info!("Embedding cache enabled: {:?}", cache.path);
+            rag = rag.embedding_cache(cache);
+        }
+        let rag = rag.build().await?;
 
         // Initialize CORTEX cognitive engine
         tracing::info!("Initializing CORTEX engine");
-        let cortex_config = CortexConfig::default();
-        let cortex = CortexEngine::new(&paths.root, cortex_config).await?;
+        let cortex = CortexEngine::new(&paths.root, config.cortex_config()).await?;
+        Self::load_user_instructions(&db, &cortex).await;
 
         // Initialize integration clients
         tracing::info!("Initializing external integration clients");
-        let mut context7 = Context7Client::from_env();
+        let mut context7 = Context7Client::from_env()
+            .with_cache_file(paths.root.join("context7_libraries.json"))
+            .with_cache_ttl(std::time::Duration::from_secs(
+                config.external.context7_cache_ttl_hours.saturating_mul(3600),
+            ));
         let mut tavily = TavilyClient::from_env();
         let mut mslearn = MSLearnClient::new();
 
@@ -201,8 +272,11 @@ impl IntelligenceServer {
             mcp_clients.add_config(PredefinedServers::tavily(&api_key)).await;
         }
 
+        let sessions = MultiSessionManager::new().with_max_sessions(config.max_clients);
+
         Ok(Self {
             config: Arc::new(config),
+            paths: Arc::new(paths),
             db: Arc::new(db),
             rag: Arc::new(RwLock::new(rag)),
             cortex: Arc::new(cortex),
@@ -212,6 +286,9 @@ impl IntelligenceServer {
             thinking: Arc::new(RwLock::new(thinking)),
             mcp_clients: Arc::new(mcp_clients),
             mcp_config: Arc::new(RwLock::new(mcp_config)),
+            metrics: Arc::new(ToolMetrics::new()),
+            sessions: Arc::new(sessions),
+            entity_embeddings: Arc::new(EntityEmbeddingRefresh::default()),
             tool_router: Self::tool_router(),
         })
     }
@@ -219,8 +296,15 @@ impl IntelligenceServer {
     /// Create server for testing with in-memory database
     #[cfg(test)]
     pub async fn for_testing(temp_dir: &std::path::Path) -> crate::Result<Self> {
-        use crate::paths::DataPaths;
-        
+        Self::for_testing_with_config(temp_dir, IntelligenceConfig::default()).await
+    }
+
+    /// Create server for testing with in-memory database and a custom config
+    #[cfg(test)]
+    pub async fn for_testing_with_config(
+        temp_dir: &std::path::Path,
+        config: IntelligenceConfig,
+    ) -> crate::Result<Self> {
         let paths = DataPaths::for_testing(temp_dir);
         paths.ensure_directories()?;
 
@@ -240,8 +324,8 @@ impl IntelligenceServer {
             .await?;
 
         // Initialize CORTEX for testing
-        let cortex_config = CortexConfig::default();
-        let cortex = CortexEngine::new(temp_dir, cortex_config).await?;
+        let cortex = CortexEngine::new(temp_dir, config.cortex_config()).await?;
+        Self::load_user_instructions(&db, &cortex).await;
 
         // Create non-initialized clients for testing
         let context7 = Context7Client::new(None);
@@ -252,8 +336,11 @@ impl IntelligenceServer {
         let mcp_config = McpConfigManager::new(temp_dir)
             .map_err(|e| IntelligenceError::Config(format!("MCP config error: {}", e)))?;
 
+        let sessions = MultiSessionManager::new().with_max_sessions(config.max_clients);
+
         Ok(Self {
-            config: Arc::new(IntelligenceConfig::default()),
+            config: Arc::new(config),
+            paths: Arc::new(paths),
             db: Arc::new(db),
             rag: Arc::new(RwLock::new(rag)),
             cortex: Arc::new(cortex),
@@ -263,6 +350,9 @@ impl IntelligenceServer {
             thinking: Arc::new(RwLock::new(thinking)),
             mcp_clients: Arc::new(mcp_clients),
             mcp_config: Arc::new(RwLock::new(mcp_config)),
+            metrics: Arc::new(ToolMetrics::new()),
+            sessions: Arc::new(sessions),
+            entity_embeddings: Arc::new(EntityEmbeddingRefresh::default()),
             tool_router: Self::tool_router(),
         })
     }
@@ -277,6 +367,7 @@ impl IntelligenceServer {
         params: rmcp::handler::server::wrapper::Parameters<MemoryStoreParams>,
     ) -> std::result::Result<Json<MemoryStoreResult>, McpError> {
         let params = params.0;
+        self.config.memory.check_content_size(&params.content)?;
         let key = params
             .key
             .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
@@ -325,22 +416,184 @@ impl IntelligenceServer {
         }))
     }
 
+    #[tool(description = "Store a text as a memory and add the entities and relations extracted from it, in one transaction. Entities record the memory key as an observation; nothing is written if any part fails")]
+    async fn ingest(
+        &self,
+        params: rmcp::handler::server::wrapper::Parameters<IngestParams>,
+    ) -> std::result::Result<Json<IngestResult>, McpError> {
+        use crate::tools::pipelines::{EntityResult, RelationResult};
+
+        let params = params.0;
+        self.config.memory.check_content_size(&params.content)?;
+        let mut metadata = match params.metadata {
+            Some(serde_json::Value::Object(map)) => map,
+            None => serde_json::Map::new(),
+            Some(_) => return Err(IntelligenceError::invalid_param("metadata", "must be a JSON object").into()),
+        };
+        let key = params
+            .key
+            .unwrap_or_else(|| uuid::Uuid::new_v4().to_string());
+        let now = chrono::Utc::now().timestamp();
+        let source = format!("{SOURCE_OBSERVATION_PREFIX}{key}");
+
+        let mut tx = self.db.transaction();
+
+        // Entities, reusing existing ones, all tagged with the memory key
+        let mut entity_ids: std::collections::HashMap<String, whytcard_database::RecordId> =
+            std::collections::HashMap::new();
+        let mut entities = Vec::new();
+        for entity in params.entities {
+            let name_key = self.entity_key(&entity.name);
+            if entity_ids.contains_key(&name_key) {
+                return Err(IntelligenceError::invalid_param(
+                    "entities",
+                    format!("'{}' is listed more than once", entity.name),
+                )
+                .into());
+            }
+
+            let mut observations = entity.observations.clone();
+            observations.push(source.clone());
+            let existing = self
+                .db
+                .get_entity_by_name_and_type(&name_key, &entity.entity_type)
+                .await
+                .map_err(IntelligenceError::from)?;
+
+            let (id, created) = match existing {
+                Some(existing) => {
+                    let id = existing
+                        .id
+                        .ok_or_else(|| IntelligenceError::EntityNotFound(entity.name.clone()))?;
+                    observations.retain(|o| !existing.observations.contains(o));
+                    for observation in &observations {
+                        tx.add_observation(id.clone(), observation.clone());
+                    }
+                    (id, false)
+                }
+                None => {
+                    let input = CreateEntity::new(&name_key, &entity.entity_type)
+                        .with_display_name(entity.name.trim())
+                        .with_observations(observations.clone());
+                    (tx.create_entity(input), true)
+                }
+            };
+
+            entities.push(EntityResult {
+                id: id.to_string(),
+                name: entity.name,
+                entity_type: entity.entity_type,
+                created,
+                observations_added: observations.len(),
+            });
+            entity_ids.insert(name_key, id);
+        }
+
+        // Relations, skipping those already in the graph
+        let mut relations = Vec::new();
+        let mut linked = std::collections::HashSet::new();
+        for rel in params.relations {
+            let from_id = self.resolve_entity_id(&entity_ids, &rel.from).await?.ok_or_else(|| {
+                IntelligenceError::invalid_param("relations", format!("Entity not found: {}", rel.from))
+            })?;
+            let to_id = self.resolve_entity_id(&entity_ids, &rel.to).await?.ok_or_else(|| {
+                IntelligenceError::invalid_param("relations", format!("Entity not found: {}", rel.to))
+            })?;
+            let exists = self
+                .db
+                .find_relation(&from_id, &to_id, &rel.relation_type)
+                .await
+                .map_err(IntelligenceError::from)?
+                .is_some();
+            let first = linked.insert((from_id.to_string(), to_id.to_string(), rel.relation_type.clone()));
+            if !exists && first {
+                tx.create_relation(CreateRelation::new(from_id, to_id, &rel.relation_type));
+            }
+            relations.push(RelationResult {
+                from: rel.from,
+                to: rel.to,
+                relation_type: rel.relation_type,
+                created: !exists && first,
+            });
+        }
+
+        // The memory lists its entities, closing the link both ways
+        let names: Vec<String> = entities.iter().map(|e| e.name.clone()).collect();
+        metadata.insert(ENTITIES_METADATA_KEY.to_string(), serde_json::json!(names));
+        let doc = whytcard_database::CreateDocument::new(&params.content)
+            .with_key(&key)
+            .with_metadata(serde_json::Value::Object(metadata))
+            .with_tags(params.tags);
+        let doc = if let Some(title) = params.title {
+            doc.with_title(title)
+        } else {
+            doc
+        };
+        tx.create_document(doc);
+
+        tx.commit().await.map_err(IntelligenceError::from)?;
+        self.schedule_entity_embedding_refresh();
+
+        // The vector index is not transactional; a failure only leaves the
+        // memory unindexed
+        let mut indexed = false;
+        if params.index && self.config.rag.auto_index {
+            let doc = whytcard_rag::Document::new(&params.content)
+                .with_id(&key)
+                .with_metadata_field("type", "memory")
+                .with_metadata_field("key", key.clone());
+
+            let mut rag = self.rag.write().await;
+            if let Err(e) = rag.index(&doc).await {
+                tracing::warn!("Failed to index ingested memory in RAG: {}", e);
+            } else {
+                indexed = true;
+            }
+        }
+
+        Ok(Json(IngestResult {
+            key,
+            indexed,
+            stored_at: now,
+            entities,
+            relations,
+        }))
+    }
+
+    /// Minimum relevance of search results: the caller's value if given,
+    /// else the configured `rag.min_relevance`
+    fn min_relevance(&self, field: &str, requested: Option<f32>) -> crate::Result<f32> {
+        match requested {
+            Some(min) => {
+                require_unit_range(field, min)?;
+                Ok(min)
+            }
+            None => Ok(self.config.rag.min_relevance),
+        }
+    }
+
     #[tool(description = "Search memories using semantic search")]
     async fn memory_search(
         &self,
         params: rmcp::handler::server::wrapper::Parameters<MemorySearchParams>,
     ) -> std::result::Result<Json<MemorySearchResult>, McpError> {
         let params = params.0;
-
-        let mut rag = self.rag.write().await;
-        let results = rag
-            .search(&params.query, Some(params.limit))
-            .await
-            .map_err(IntelligenceError::from)?;
+        require_positive("limit", params.limit)?;
+        let min_score = self.min_relevance("min_score", params.min_score)?;
+
+        let results = self.rag.write().await.search(&params.query, Some(params.limit)).await;
+        let results = match results {
+            Ok(results) => results,
+            Err(e) if self.config.rag.search_fallback => {
+                tracing::warn!("Semantic search failed, falling back to full-text search: {}", e);
+                return self.memory_search_fallback(params).await;
+            }
+            Err(e) => return Err(IntelligenceError::from(e).into()),
+        };
 
         let items = results
             .into_iter()
-            .filter(|r| params.min_score.is_none_or(|min| r.score >= min))
+            .filter(|r| r.score >= min_score)
             .map(|r| {
                 // Extract title from metadata if present
                 let title = r.chunk.metadata.as_ref().and_then(|m| {
@@ -364,6 +617,79 @@ impl IntelligenceServer {
             results: items,
             total,
             query: params.query,
+            fallback: false,
+        }))
+    }
+
+    /// Full-text search of memories standing in for a failed semantic search
+    async fn memory_search_fallback(
+        &self,
+        params: MemorySearchParams,
+    ) -> std::result::Result<Json<MemorySearchResult>, McpError> {
+        let documents = self
+            .db
+            .search_documents_text(&params.query, &params.tags, params.limit)
+            .await
+            .map_err(IntelligenceError::from)?;
+
+        let items = documents
+            .into_iter()
+            .map(|doc| crate::tools::MemorySearchResultItem {
+                key: doc.key.unwrap_or_default(),
+                content: doc.content,
+                title: doc.title,
+                score: doc.score.unwrap_or(0.0),
+                tags: doc.tags,
+                stored_at: doc.created_at.map(|d| d.timestamp()).unwrap_or(0),
+            })
+            .collect::<Vec<_>>();
+
+        let total = items.len();
+
+        Ok(Json(MemorySearchResult {
+            results: items,
+            total,
+            query: params.query,
+            fallback: true,
+        }))
+    }
+
+    #[tool(description = "Explain a semantic search for debugging: each hit's raw distance, scores and chunk metadata, plus the query embedding norm and the number of candidates examined")]
+    async fn memory_search_explain(
+        &self,
+        params: rmcp::handler::server::wrapper::Parameters<MemorySearchExplainParams>,
+    ) -> std::result::Result<Json<MemorySearchExplainResult>, McpError> {
+        let params = params.0;
+        require_positive("limit", params.limit)?;
+
+        let explain = self
+            .rag
+            .read()
+            .await
+            .search_explain(&params.query, Some(params.limit))
+            .await
+            .map_err(IntelligenceError::from)?;
+
+        let results = explain
+            .results
+            .into_iter()
+            .map(|r| MemorySearchExplainItem {
+                key: r.chunk.document_id,
+                chunk_index: r.chunk.index,
+                content: r.chunk.text,
+                distance: r.distance,
+                vector_score: r.vector_score,
+                score: r.score,
+                metadata: r.chunk.metadata,
+            })
+            .collect();
+
+        Ok(Json(MemorySearchExplainResult {
+            query: params.query,
+            query_norm: explain.query_norm,
+            candidates_examined: explain.candidates,
+            ef: explain.ef,
+            results,
         }))
     }
 
@@ -379,6 +705,7 @@ impl IntelligenceServer {
             .get_document_by_key(&params.key)
             .await
             .map_err(IntelligenceError::from)?
+            .filter(|doc| !doc.is_deleted())
             .ok_or_else(|| IntelligenceError::KeyNotFound(params.key.clone()))?;
 
         Ok(Json(MemoryGetResult {
@@ -392,21 +719,23 @@ impl IntelligenceServer {
         }))
     }
 
-    #[tool(description = "Delete a memory by key")]
+    #[tool(description = "Delete a memory by key. Memories are moved to the trash and can be restored with memory_restore until cortex_cleanup purges them, unless permanent is set")]
     async fn memory_delete(
         &self,
         params: rmcp::handler::server::wrapper::Parameters<MemoryDeleteParams>,
     ) -> std::result::Result<Json<MemoryDeleteResult>, McpError> {
         let params = params.0;
+        let soft = self.config.memory.soft_delete && !params.permanent;
 
-        // Delete from database
-        let deleted_from_db = self
-            .db
-            .delete_document_by_key(&params.key)
-            .await
-            .map_err(IntelligenceError::from)?;
+        // Delete from database (soft-deleted documents stay until purged)
+        let deleted_from_db = if soft {
+            self.db.soft_delete_document_by_key(&params.key).await
+        } else {
+            self.db.delete_document_by_key(&params.key).await
+        }
+        .map_err(IntelligenceError::from)?;
 
-        // Delete from RAG index (key is used as document_id)
+        // Delete from RAG index (key is used as document_id), re-indexed on restore
         let mut rag = self.rag.write().await;
         if let Err(e) = rag.delete_document(&params.key).await {
             tracing::warn!("Failed to delete memory from RAG: {}", e);
@@ -415,6 +744,51 @@ impl IntelligenceServer {
         Ok(Json(MemoryDeleteResult {
             key: params.key,
             deleted: deleted_from_db,
+            restorable: soft && deleted_from_db,
+        }))
+    }
+
+    #[tool(description = "Restore a soft-deleted memory by key and re-index it for semantic search")]
+    async fn memory_restore(
+        &self,
+        params: rmcp::handler::server::wrapper::Parameters<MemoryRestoreParams>,
+    ) -> std::result::Result<Json<MemoryRestoreResult>, McpError> {
+        let params = params.0;
+
+        let Some(doc) = self
+            .db
+            .restore_document_by_key(&params.key)
+            .await
+            .map_err(IntelligenceError::from)?
+        else {
+            return Ok(Json(MemoryRestoreResult {
+                key: params.key,
+                restored: false,
+                indexed: false,
+            }));
+        };
+
+        let mut indexed = false;
+
+        // Re-index in RAG since soft-delete removed the chunks
+        if self.config.rag.auto_index {
+            let rag_doc = whytcard_rag::Document::new(&doc.content)
+                .with_id(&params.key)
+                .with_metadata_field("type", "memory")
+                .with_metadata_field("key", params.key.clone());
+
+            let mut rag = self.rag.write().await;
+            if let Err(e) = rag.index(&rag_doc).await {
+                tracing::warn!("Failed to re-index restored memory in RAG: {}", e);
+            } else {
+                indexed = true;
+            }
+        }
+
+        Ok(Json(MemoryRestoreResult {
+            key: params.key,
+            restored: true,
+            indexed,
         }))
     }
 
@@ -424,21 +798,19 @@ impl IntelligenceServer {
         params: rmcp::handler::server::wrapper::Parameters<MemoryListParams>,
     ) -> std::result::Result<Json<MemoryListResult>, McpError> {
         let params = params.0;
+        require_positive("limit", params.limit)?;
+        let tags = if params.tags.is_empty() {
+            None
+        } else {
+            Some(params.tags.as_slice())
+        };
 
         // Fetch limit + 1 to check if there are more results
         let fetch_limit = params.limit + 1;
 
         let mut docs = self
             .db
-            .list_documents(
-                if params.tags.is_empty() {
-                    None
-                } else {
-                    Some(&params.tags)
-                },
-                fetch_limit,
-                params.offset,
-            )
+            .list_documents(tags, fetch_limit, params.offset)
             .await
             .map_err(IntelligenceError::from)?;
 
@@ -459,8 +831,11 @@ impl IntelligenceServer {
             })
             .collect();
 
-        // Total count would require a separate query, returning 0 for now as it's optional
-        let total = 0;
+        let total = self
+            .db
+            .count_documents(tags)
+            .await
+            .map_err(IntelligenceError::from)?;
 
         Ok(Json(MemoryListResult {
             memories,
@@ -469,18 +844,118 @@ impl IntelligenceServer {
         }))
     }
 
-    #[tool(description = "Store multiple memories in batch with optional semantic indexing")]
+    #[tool(description = "Export memories to a JSONL file (content, title, tags, metadata, timestamps) for external processing")]
+    async fn memory_export(
+        &self,
+        params: rmcp::handler::server::wrapper::Parameters<MemoryExportParams>,
+    ) -> std::result::Result<Json<MemoryExportResult>, McpError> {
+        let params = params.0;
+
+        let path = self.paths.export_file(&params.path)?;
+
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await.map_err(IntelligenceError::from)?;
+        }
+        let file = tokio::fs::File::create(&path).await.map_err(IntelligenceError::from)?;
+        let exported = self
+            .db
+            .export_documents(
+                &mut tokio::io::BufWriter::new(file),
+                if params.tags.is_empty() {
+                    None
+                } else {
+                    Some(&params.tags)
+                },
+            )
+            .await
+            .map_err(IntelligenceError::from)?;
+
+        tracing::info!(path = ?path, exported, "Exported memories");
+
+        Ok(Json(MemoryExportResult {
+            path: params.path,
+            exported,
+        }))
+    }
+
+    #[tool(description = "Import memories from a JSONL file (upsert by key) and optionally re-index them for semantic search")]
+    async fn memory_import(
+        &self,
+        params: rmcp::handler::server::wrapper::Parameters<MemoryImportParams>,
+    ) -> std::result::Result<Json<MemoryImportResult>, McpError> {
+        let params = params.0;
+
+        let path = self.paths.export_file(&params.path)?;
+        let file = tokio::fs::File::open(&path).await.map_err(IntelligenceError::from)?;
+        let summary = self
+            .db
+            .import_documents(tokio::io::BufReader::new(file))
+            .await
+            .map_err(IntelligenceError::from)?;
+
+        let mut errors = summary.errors;
+        let mut indexed = 0;
+
+        if params.reindex && self.config.rag.auto_index {
+            let mut rag = self.rag.write().await;
+            for key in &summary.keys {
+                let doc = match self.db.get_document_by_key(key).await {
+                    Ok(Some(doc)) => doc,
+                    Ok(None) => continue,
+                    Err(e) => {
+                        errors.push(format!("{}: {}", key, e));
+                        continue;
+                    }
+                };
+
+                let rag_doc = whytcard_rag::Document::new(&doc.content)
+                    .with_id(key)
+                    .with_metadata_field("type", "memory")
+                    .with_metadata_field("key", key.clone());
+
+                // reindex drops stale chunks of updated memories first
+                match rag.reindex(&rag_doc).await {
+                    Ok(_) => indexed += 1,
+                    Err(e) => errors.push(format!("{}: indexing failed: {}", key, e)),
+                }
+            }
+        }
+
+        tracing::info!(
+            path = ?path,
+            created = summary.created,
+            updated = summary.updated,
+            indexed,
+            "Imported memories"
+        );
+
+        Ok(Json(MemoryImportResult {
+            created: summary.created,
+            updated: summary.updated,
+            indexed,
+            errors,
+        }))
+    }
+
+    #[tool(description = "Store multiple memories in batch with optional semantic indexing; index_only indexes them for search without storing memory documents")]
     async fn batch_store(
         &self,
         params: rmcp::handler::server::wrapper::Parameters<BatchStoreParams>,
     ) -> std::result::Result<Json<BatchStoreResult>, McpError> {
         let params = params.0;
+        if params.index_only {
+            return Ok(Json(self.batch_index(params.items).await));
+        }
 
         let mut stored = 0;
         let mut keys = Vec::new();
         let mut errors = Vec::new();
 
         for item in params.items {
+            if let Err(e) = self.config.memory.check_content_size(&item.content) {
+                errors.push(format!("Failed to store item: {}", e));
+                continue;
+            }
             let key = uuid::Uuid::new_v4().to_string();
 
             // Store in database
@@ -521,14 +996,57 @@ impl IntelligenceServer {
         }))
     }
 
+    /// Index batch items in RAG only, embedding them together
+    async fn batch_index(&self, items: Vec<BatchStoreItem>) -> BatchStoreResult {
+        let mut errors = Vec::new();
+        let mut docs = Vec::with_capacity(items.len());
+
+        for item in items {
+            if let Err(e) = self.config.memory.check_content_size(&item.content) {
+                errors.push(format!("Failed to index item: {}", e));
+                continue;
+            }
+
+            let doc = whytcard_rag::Document::new(&item.content);
+            let doc = match item.metadata {
+                Some(metadata @ serde_json::Value::Object(_)) => doc.with_metadata(metadata),
+                Some(metadata) => doc.with_metadata_field("metadata", metadata),
+                None => doc,
+            };
+            docs.push(
+                doc.with_metadata_field("type", "indexed")
+                    .with_metadata_field("source", item.source)
+                    .with_metadata_field("category", item.category)
+                    .with_metadata_field("tags", item.tags),
+            );
+        }
+
+        let results = self.rag.read().await.index_batch(&docs).await;
+
+        let mut keys = Vec::new();
+        for (doc, result) in docs.into_iter().zip(results) {
+            match result {
+                Ok(_) => keys.push(doc.id),
+                Err(e) => errors.push(format!("Failed to index item: {}", e)),
+            }
+        }
+
+        BatchStoreResult {
+            stored: keys.len(),
+            keys,
+            errors,
+        }
+    }
+
     #[tool(description = "Perform hybrid search across semantic, episodic and procedural memories")]
     async fn hybrid_search(
         &self,
         params: rmcp::handler::server::wrapper::Parameters<HybridSearchParams>,
     ) -> std::result::Result<Json<HybridSearchResult>, McpError> {
         let params = params.0;
+        require_positive("top_k", params.top_k)?;
         let limit = params.top_k;
-        let min_score = params.min_relevance;
+        let min_score = self.min_relevance("min_relevance", params.min_relevance)?;
 
         // Semantic search via RAG
         let mut semantic = Vec::new();
@@ -588,28 +1106,106 @@ impl IntelligenceServer {
                 .collect();
         }
 
-        let summary = format!(
-            "Found {} semantic, {} episodic, {} procedural results",
-            semantic.len(),
-            episodic.len(),
-            procedural.len()
-        );
-
-        Ok(Json(HybridSearchResult {
-            semantic,
-            episodic,
-            procedural,
-            graph: Vec::new(),
-            summary,
-        }))
-    }
+        // Knowledge graph search by entity name
+        let mut graph_entities = match self.db.search_entities(&self.entity_key(&params.query)).await {
+            Ok(entities) => entities,
+            Err(e) => {
+                tracing::warn!("Knowledge graph search failed: {}", e);
+                Vec::new()
+            }
+        };
+        graph_entities.truncate(limit);
+        let graph: Vec<serde_json::Value> = graph_entities
+            .iter()
+            .map(|e| {
+                serde_json::json!({
+                    "name": e.label(),
+                    "entity_type": e.entity_type,
+                    "observations": e.observations,
+                })
+            })
+            .collect();
 
-    #[tool(description = "Manage tags on stored memories (add, remove, get, search)")]
+        // Fuse every source into a single ranking
+        let combined = reciprocal_rank_fusion(vec![
+            semantic
+                .iter()
+                .map(|item| HybridItem {
+                    id: item.id.clone(),
+                    content: item.content.clone(),
+                    source: "semantic".to_string(),
+                    score: 0.0,
+                    rank: 0,
+                    raw_score: Some(item.score),
+                })
+                .collect(),
+            episodic
+                .iter()
+                .map(|item| HybridItem {
+                    id: item.id.clone(),
+                    content: item.content.clone(),
+                    source: "episodic".to_string(),
+                    score: 0.0,
+                    rank: 0,
+                    raw_score: None,
+                })
+                .collect(),
+            procedural
+                .iter()
+                .map(|item| HybridItem {
+                    id: item.id.clone(),
+                    content: item.description.clone(),
+                    source: "procedural".to_string(),
+                    score: 0.0,
+                    rank: 0,
+                    raw_score: Some(item.confidence),
+                })
+                .collect(),
+            graph_entities
+                .iter()
+                .map(|e| HybridItem {
+                    id: e.label().to_string(),
+                    content: e.observations.join("; "),
+                    source: "graph".to_string(),
+                    score: 0.0,
+                    rank: 0,
+                    raw_score: None,
+                })
+                .collect(),
+        ]);
+
+        let summary = format!(
+            "Found {} semantic, {} episodic, {} procedural, {} graph results",
+            semantic.len(),
+            episodic.len(),
+            procedural.len(),
+            graph.len()
+        );
+
+        Ok(Json(HybridSearchResult {
+            semantic,
+            episodic,
+            procedural,
+            graph,
+            combined,
+            summary,
+        }))
+    }
+
+    #[tool(description = "Manage tags on stored memories (add, remove, get, search)")]
     async fn manage_tags(
         &self,
         params: rmcp::handler::server::wrapper::Parameters<ManageTagsParams>,
     ) -> std::result::Result<Json<ManageTagsResult>, McpError> {
         let params = params.0;
+        require_one_of("action", &params.action, &["add", "remove", "get", "search"])?;
+        if params.action != "search" && params.doc_id.is_none() {
+            return Err(IntelligenceError::invalid_param(
+                "doc_id",
+                format!("is required for action '{}'", params.action),
+            )
+            .into());
+        }
 
         match params.action.as_str() {
             "add" => {
@@ -621,11 +1217,16 @@ impl IntelligenceServer {
                                 new_tags.push(tag.clone());
                             }
                         }
-                        // Note: would need UpdateDocument support
+                        let update = whytcard_database::UpdateDocument::new().with_tags(new_tags);
+                        let doc = self
+                            .db
+                            .update_document(doc_id, update)
+                            .await
+                            .map_err(IntelligenceError::from)?;
                         return Ok(Json(ManageTagsResult {
                             success: true,
                             doc_id: Some(doc_id.clone()),
-                            tags: new_tags,
+                            tags: doc.tags,
                             results: Vec::new(),
                             message: format!("Added {} tags", params.tags.len()),
                         }));
@@ -646,10 +1247,16 @@ impl IntelligenceServer {
                             .filter(|t| !params.tags.contains(t))
                             .cloned()
                             .collect();
+                        let update = whytcard_database::UpdateDocument::new().with_tags(new_tags);
+                        let doc = self
+                            .db
+                            .update_document(doc_id, update)
+                            .await
+                            .map_err(IntelligenceError::from)?;
                         return Ok(Json(ManageTagsResult {
                             success: true,
                             doc_id: Some(doc_id.clone()),
-                            tags: new_tags,
+                            tags: doc.tags,
                             results: Vec::new(),
                             message: format!("Removed {} tags", params.tags.len()),
                         }));
@@ -683,7 +1290,8 @@ impl IntelligenceServer {
                     message: "Document not found".to_string(),
                 }))
             }
-            "search" => {
+            _ => {
+                // "search", the only action left after validation
                 // Search for documents with the given tags
                 let docs = self.db.list_documents(
                     if params.tags.is_empty() { None } else { Some(&params.tags) },
@@ -709,13 +1317,6 @@ impl IntelligenceServer {
                     message: "Search completed".to_string(),
                 }))
             }
-            _ => Ok(Json(ManageTagsResult {
-                success: false,
-                doc_id: None,
-                tags: Vec::new(),
-                results: Vec::new(),
-                message: format!("Unknown action: {}", params.action),
-            })),
         }
     }
 
@@ -725,8 +1326,9 @@ impl IntelligenceServer {
         params: rmcp::handler::server::wrapper::Parameters<GetContextParams>,
     ) -> std::result::Result<Json<GetContextResult>, McpError> {
         let params = params.0;
+        require_one_of("context_type", &params.context_type, &["query", "search", "session"])?;
         let limit = 5;
-        let min_relevance = 0.3;
+        let min_relevance = self.min_relevance("min_relevance", params.min_relevance)?;
 
         // Gather context from all sources
         let mut semantic_items = Vec::new();
@@ -825,11 +1427,11 @@ impl IntelligenceServer {
         // Check if entity already exists
         let existing = self
             .db
-            .get_entity_by_name(&params.name)
+            .get_entity_by_name(&self.entity_key(&params.name))
             .await
             .map_err(IntelligenceError::from)?;
 
-        let (created, observations_added) = if let Some(entity) = existing {
+        let (entity_id, created, observations_added) = if let Some(entity) = existing {
             // Entity exists, add observations
             let entity_id = entity
                 .id
@@ -842,21 +1444,28 @@ impl IntelligenceServer {
                     added += 1;
                 }
             }
-            (false, added)
+            (entity_id, false, added)
         } else {
             // Create new entity
-            let input = CreateEntity::new(&params.name, &params.entity_type)
+            let input = CreateEntity::new(self.entity_key(&params.name), &params.entity_type)
+                .with_display_name(params.name.trim())
                 .with_observations(params.observations.clone());
 
-            self.db
+            let entity = self
+                .db
                 .create_entity(input)
                 .await
                 .map_err(IntelligenceError::from)?;
+            let entity_id = entity
+                .id
+                .ok_or_else(|| IntelligenceError::EntityNotFound(params.name.clone()))?;
 
-            (true, params.observations.len())
+            (entity_id, true, params.observations.len())
         };
+        self.schedule_entity_embedding_refresh();
 
         Ok(Json(KnowledgeAddEntityResult {
+            id: entity_id.to_string(),
             name: params.name,
             entity_type: params.entity_type,
             created,
@@ -874,7 +1483,7 @@ impl IntelligenceServer {
         // Find entity by name
         let entity = self
             .db
-            .get_entity_by_name(&params.entity_name)
+            .get_entity_by_name(&self.entity_key(&params.entity_name))
             .await
             .map_err(IntelligenceError::from)?
             .ok_or_else(|| IntelligenceError::EntityNotFound(params.entity_name.clone()))?;
@@ -890,6 +1499,7 @@ impl IntelligenceServer {
                 added += 1;
             }
         }
+        self.schedule_entity_embedding_refresh();
 
         Ok(Json(KnowledgeAddObservationResult {
             entity_name: params.entity_name,
@@ -907,7 +1517,7 @@ impl IntelligenceServer {
         // Find source entity
         let from_entity = self
             .db
-            .get_entity_by_name(&params.from)
+            .get_entity_by_name(&self.entity_key(&params.from))
             .await
             .map_err(IntelligenceError::from)?
             .ok_or_else(|| IntelligenceError::EntityNotFound(params.from.clone()))?;
@@ -919,7 +1529,7 @@ impl IntelligenceServer {
         // Find target entity
         let to_entity = self
             .db
-            .get_entity_by_name(&params.to)
+            .get_entity_by_name(&self.entity_key(&params.to))
             .await
             .map_err(IntelligenceError::from)?
             .ok_or_else(|| IntelligenceError::EntityNotFound(params.to.clone()))?;
@@ -928,40 +1538,95 @@ impl IntelligenceServer {
             .id
             .ok_or_else(|| IntelligenceError::EntityNotFound(params.to.clone()))?;
 
-        // Create relation
-        let input = CreateRelation::new(from_id, to_id, &params.relation_type);
-        self.db
-            .create_relation(input)
+        // Skip identical relations so repeated calls stay idempotent
+        let mut existing = self
+            .db
+            .find_relation(&from_id, &to_id, &params.relation_type)
             .await
             .map_err(IntelligenceError::from)?;
 
+        // A symmetric edge already covers the reverse direction
+        if existing.is_none() && params.symmetric {
+            existing = self
+                .db
+                .find_relation(&to_id, &from_id, &params.relation_type)
+                .await
+                .map_err(IntelligenceError::from)?
+                .filter(|rel| rel.symmetric);
+        }
+
+        if existing.is_none() {
+            let input = CreateRelation::new(from_id, to_id, &params.relation_type)
+                .with_symmetric(params.symmetric);
+            self.db
+                .create_relation(input)
+                .await
+                .map_err(IntelligenceError::from)?;
+        }
+
         Ok(Json(KnowledgeAddRelationResult {
             from: params.from,
             to: params.to,
             relation_type: params.relation_type,
-            created: true,
+            created: existing.is_none(),
+            already_existed: existing.is_some(),
         }))
     }
 
-    #[tool(description = "Search the knowledge graph")]
+    #[tool(description = "Search the knowledge graph by entity name, optionally merged with semantic matches over names and observations. Page with limit/offset; total and has_more describe the full result")]
     async fn knowledge_search(
         &self,
         params: rmcp::handler::server::wrapper::Parameters<KnowledgeSearchParams>,
     ) -> std::result::Result<Json<KnowledgeSearchResult>, McpError> {
         let params = params.0;
+        require_positive("limit", params.limit)?;
+        require_unit_range("similarity_threshold", params.similarity_threshold)?;
+        let pattern = self.entity_key(&params.query);
 
-        // Search entities by name pattern
-        let entities = self
-            .db
-            .search_entities(&params.query)
-            .await
-            .map_err(IntelligenceError::from)?;
+        let (entities, total) = if params.mode == KnowledgeSearchMode::Semantic {
+            // Name and semantic matches are merged here, so page the merged list
+            let mut entities = self
+                .db
+                .search_entities(&pattern)
+                .await
+                .map_err(IntelligenceError::from)?;
+
+            // Merge in entities whose name + observations embed close to the query
+            let limit = SEMANTIC_ENTITY_MATCHES.max(params.offset + params.limit);
+            match self.semantic_entity_matches(&params.query, params.similarity_threshold, limit).await {
+                Ok(matches) => {
+                    for entity in matches {
+                        if !entities.iter().any(|e| e.name == entity.name && e.entity_type == entity.entity_type) {
+                            entities.push(entity);
+                        }
+                    }
+                }
+                Err(e) => tracing::warn!("Semantic entity matching failed: {}", e),
+            }
+
+            let total = entities.len();
+            let page: Vec<_> = entities.into_iter().skip(params.offset).take(params.limit).collect();
+            (page, total)
+        } else {
+            // Name matches are paged by the database
+            let page = self
+                .db
+                .search_entities_page(&pattern, params.limit, params.offset)
+                .await
+                .map_err(IntelligenceError::from)?;
+            let total = self
+                .db
+                .count_search_entities(&pattern)
+                .await
+                .map_err(IntelligenceError::from)?;
+            (page, total)
+        };
 
+        let has_more = params.offset + entities.len() < total;
         let entity_infos: Vec<EntityInfo> = entities
             .into_iter()
-            .take(params.limit)
             .map(|e| EntityInfo {
-                name: e.name,
+                name: e.label().to_string(),
                 entity_type: e.entity_type,
                 observations: e.observations,
             })
@@ -970,9 +1635,130 @@ impl IntelligenceServer {
         Ok(Json(KnowledgeSearchResult {
             entities: entity_infos,
             relations: Vec::new(), // Relations between found entities could be added
+            total,
+            has_more,
         }))
     }
 
+    /// Lookup key for an entity name, per the configured normalization
+    fn entity_key(&self, name: &str) -> String {
+        self.config.knowledge.name_normalization.apply(name)
+    }
+
+    /// Entity ID from `known` (keyed by lookup key), else looked up by name
+    ///
+    /// `None` if no entity has that name; lookup failures are returned as errors.
+    async fn resolve_entity_id(
+        &self,
+        known: &std::collections::HashMap<String, whytcard_database::RecordId>,
+        name: &str,
+    ) -> crate::Result<Option<whytcard_database::RecordId>> {
+        let key = self.entity_key(name);
+        if let Some(id) = known.get(&key) {
+            return Ok(Some(id.clone()));
+        }
+        match self.db.get_entity_by_name(&key).await? {
+            Some(entity) => entity
+                .id
+                .map(Some)
+                .ok_or_else(|| IntelligenceError::EntityNotFound(name.to_string())),
+            None => Ok(None),
+        }
+    }
+
+    /// Rank entities by embedding similarity of their name and observations to the query
+    ///
+    /// Served by the entity embedding index, which tool calls keep current
+    /// (see [`Self::refresh_entity_embeddings`]); returns the `limit` nearest.
+    async fn semantic_entity_matches(
+        &self,
+        query: &str,
+        threshold: f32,
+        limit: usize,
+    ) -> crate::Result<Vec<whytcard_database::Entity>> {
+        let query_embedding = self.rag.read().await.embed_query(query).await?;
+        let matches = self
+            .db
+            .search_entity_embeddings(&query_embedding, limit, Some(threshold))
+            .await?;
+
+        Ok(matches.into_iter().map(|m| m.entity).collect())
+    }
+
+    /// Embed one batch of entities the database holds no embedding for,
+    /// returning how many were embedded
+    async fn embed_pending_entities(&self) -> crate::Result<usize> {
+        let entities = self.db.entities_without_embedding(ENTITY_EMBEDDING_BATCH).await?;
+        if entities.is_empty() {
+            return Ok(0);
+        }
+
+        let texts = entities
+            .iter()
+            .map(|e| format!("{}: {}", e.label(), e.observations.join(". ")))
+            .collect();
+        let embeddings = self.rag.read().await.embed_texts(texts).await?;
+        for (entity, embedding) in entities.iter().zip(embeddings) {
+            if let Some(id) = &entity.id {
+                self.db.set_entity_embedding(id, embedding).await?;
+            }
+        }
+        Ok(entities.len())
+    }
+
+    /// Embed new and changed entities
+    ///
+    /// The database drops an entity's embedding when its name or
+    /// observations change, so only those entities are embedded. A failure
+    /// costs semantic recall, not the write, so it is only logged.
+    async fn refresh_entity_embeddings(&self) {
+        loop {
+            match self.embed_pending_entities().await {
+                Ok(embedded) if embedded == ENTITY_EMBEDDING_BATCH => continue,
+                Ok(_) => break,
+                Err(e) => {
+                    tracing::warn!("Failed to embed entities: {}", e);
+                    break;
+                }
+            }
+        }
+    }
+
+    /// Queue a background embedding refresh after a graph write
+    ///
+    /// Returns at once so writes never wait on the embedder. At startup this
+    /// also backfills entities stored before entity embeddings were.
+    fn schedule_entity_embedding_refresh(&self) {
+        use std::sync::atomic::Ordering;
+
+        // A queued refresh that hasn't started yet covers this write too
+        if self.entity_embeddings.pending.swap(true, Ordering::AcqRel) {
+            return;
+        }
+        let server = self.clone();
+        tokio::spawn(async move {
+            let _running = server.entity_embeddings.running.lock().await;
+            server.entity_embeddings.pending.store(false, Ordering::Release);
+            server.refresh_entity_embeddings().await;
+        });
+    }
+
+    /// Wait until every queued embedding refresh has run
+    #[cfg(test)]
+    async fn settle_entity_embeddings(&self) {
+        use std::sync::atomic::Ordering;
+
+        loop {
+            {
+                let _running = self.entity_embeddings.running.lock().await;
+                if !self.entity_embeddings.pending.load(Ordering::Acquire) {
+                    return;
+                }
+            }
+            tokio::task::yield_now().await;
+        }
+    }
+
     #[tool(description = "Get a specific entity and its relations")]
     async fn knowledge_get_entity(
         &self,
@@ -983,7 +1769,7 @@ impl IntelligenceServer {
         // Find entity by name
         let entity = self
             .db
-            .get_entity_by_name(&params.name)
+            .get_entity_by_name(&self.entity_key(&params.name))
             .await
             .map_err(IntelligenceError::from)?
             .ok_or_else(|| IntelligenceError::EntityNotFound(params.name.clone()))?;
@@ -994,8 +1780,9 @@ impl IntelligenceServer {
             .ok_or_else(|| IntelligenceError::EntityNotFound(params.name.clone()))?;
         let id_str = entity_id.key().to_string();
 
+        let label = entity.label().to_string();
         let entity_info = EntityInfo {
-            name: entity.name,
+            name: label.clone(),
             entity_type: entity.entity_type,
             observations: entity.observations,
         };
@@ -1013,8 +1800,8 @@ impl IntelligenceServer {
                 let to_id = rel.to.key().to_string();
                 if let Ok(to_entity) = self.db.get_entity(&to_id).await {
                     outgoing_infos.push(RelationInfo {
-                        from: params.name.clone(),
-                        to: to_entity.name,
+                        from: label.clone(),
+                        to: to_entity.label().to_string(),
                         relation_type: rel.relation_type,
                     });
                 }
@@ -1032,8 +1819,8 @@ impl IntelligenceServer {
                 let from_id = rel.from.key().to_string();
                 if let Ok(from_entity) = self.db.get_entity(&from_id).await {
                     incoming_infos.push(RelationInfo {
-                        from: from_entity.name,
-                        to: params.name.clone(),
+                        from: from_entity.label().to_string(),
+                        to: label.clone(),
                         relation_type: rel.relation_type,
                     });
                 }
@@ -1062,7 +1849,7 @@ impl IntelligenceServer {
         let mut relations_removed = 0;
 
         for name in params.names {
-            if let Ok(Some(entity)) = self.db.get_entity_by_name(&name).await {
+            if let Ok(Some(entity)) = self.db.get_entity_by_name(&self.entity_key(&name)).await {
                 if let Some(entity_id) = entity.id {
                     let id_str = entity_id.key().to_string();
 
@@ -1106,8 +1893,8 @@ impl IntelligenceServer {
         for rel_spec in params.relations {
             // Find entities by name
             if let (Ok(Some(from_entity)), Ok(Some(to_entity))) = (
-                self.db.get_entity_by_name(&rel_spec.from).await,
-                self.db.get_entity_by_name(&rel_spec.to).await,
+                self.db.get_entity_by_name(&self.entity_key(&rel_spec.from)).await,
+                self.db.get_entity_by_name(&self.entity_key(&rel_spec.to)).await,
             ) {
                 if let (Some(from_id), Some(to_id)) = (from_entity.id, to_entity.id) {
                     let from_str = from_id.key().to_string();
@@ -1128,69 +1915,77 @@ impl IntelligenceServer {
         }))
     }
 
-    #[tool(description = "Read the entire knowledge graph")]
+    #[tool(description = "Read the knowledge graph page by page (limit/offset), with entities ordered by name and the relations leaving them")]
     async fn knowledge_read_graph(
         &self,
         params: rmcp::handler::server::wrapper::Parameters<KnowledgeReadGraphParams>,
     ) -> std::result::Result<Json<KnowledgeReadGraphResult>, McpError> {
         let params = params.0;
+        if let Some(limit) = params.limit {
+            require_positive("limit", limit)?;
+        }
 
-        // Get all entities (limited if specified)
-        let all_entities: Vec<whytcard_database::Entity> = self
+        let total_entities = self.db.count_entities().await.unwrap_or(0);
+        let total_relations = self.db.count_relations().await.unwrap_or(0);
+
+        // Page entities in the database (no limit = everything after offset)
+        let limit = params.limit.unwrap_or(total_entities);
+        let page = self
             .db
-            .inner()
-            .select("entity")
+            .list_entities(limit, params.offset)
             .await
-            .map_err(|e| IntelligenceError::Database(Box::new(whytcard_database::DatabaseError::from(e))))?;
-
-        let entities: Vec<EntityInfo> = all_entities
-            .into_iter()
-            .take(if params.limit > 0 {
-                params.limit
-            } else {
-                usize::MAX
-            })
-            .map(|e| EntityInfo {
-                name: e.name,
-                entity_type: e.entity_type,
-                observations: e.observations,
-            })
-            .collect();
+            .map_err(IntelligenceError::from)?;
 
-        // Get all relations
-        let all_relations: Vec<whytcard_database::Relation> = self
+        // Relations leaving this page, so each relation appears on exactly one page
+        let ids: Vec<_> = page.iter().filter_map(|e| e.id.clone()).collect();
+        let page_relations = self
             .db
-            .inner()
-            .select("relates_to")
+            .get_outgoing_relations_for(&ids)
             .await
-            .map_err(|e| IntelligenceError::Database(Box::new(whytcard_database::DatabaseError::from(e))))?;
+            .map_err(IntelligenceError::from)?;
 
-        // Convert relations to RelationInfo (need to lookup entity names)
+        let mut names: std::collections::HashMap<String, String> = page
+            .iter()
+            .filter_map(|e| Some((e.id.as_ref()?.key().to_string(), e.label().to_string())))
+            .collect();
         let mut relations = Vec::new();
-        for rel in all_relations {
+        for rel in page_relations {
             let from_id = rel.from.key().to_string();
             let to_id = rel.to.key().to_string();
 
-            if let (Ok(from_entity), Ok(to_entity)) = (
-                self.db.get_entity(&from_id).await,
-                self.db.get_entity(&to_id).await,
-            ) {
+            if !names.contains_key(&to_id) {
+                match self.db.get_entity(&to_id).await {
+                    Ok(entity) => {
+                        names.insert(to_id.clone(), entity.label().to_string());
+                    }
+                    Err(_) => continue,
+                }
+            }
+            if let (Some(from), Some(to)) = (names.get(&from_id), names.get(&to_id)) {
                 relations.push(RelationInfo {
-                    from: from_entity.name,
-                    to: to_entity.name,
+                    from: from.clone(),
+                    to: to.clone(),
                     relation_type: rel.relation_type,
                 });
             }
         }
 
-        let total_entities = self.db.count_entities().await.unwrap_or(0);
-        let total_relations = self.db.count_relations().await.unwrap_or(0);
+        let has_more = params.offset + page.len() < total_entities;
+        let entities: Vec<EntityInfo> = page
+            .into_iter()
+            .map(|e| EntityInfo {
+                name: e.label().to_string(),
+                entity_type: e.entity_type,
+                observations: e.observations,
+            })
+            .collect();
 
         Ok(Json(KnowledgeReadGraphResult {
             entities,
             relations,
             total_entities,
             total_relations,
+            has_more,
         }))
     }
 
@@ -1204,7 +1999,7 @@ impl IntelligenceServer {
         // Find entity by name
         let entity = self
             .db
-            .get_entity_by_name(&params.entity_name)
+            .get_entity_by_name(&self.entity_key(&params.entity_name))
             .await
             .map_err(IntelligenceError::from)?
             .ok_or_else(|| IntelligenceError::EntityNotFound(params.entity_name.clone()))?;
@@ -1212,20 +2007,17 @@ impl IntelligenceServer {
         let entity_id = entity
             .id
             .ok_or_else(|| IntelligenceError::EntityNotFound(params.entity_name.clone()))?;
-        let _id_str = entity_id.key().to_string();
+        let id_str = entity_id.key().to_string();
 
-        // Get current observations and remove the specified ones
-        let mut remaining = entity.observations.clone();
         let mut removed = 0;
         for obs in &params.observations {
-            if let Some(pos) = remaining.iter().position(|o| o == obs) {
-                remaining.remove(pos);
-                removed += 1;
-            }
+            removed += self
+                .db
+                .remove_observation(&id_str, obs)
+                .await
+                .map_err(IntelligenceError::from)?;
         }
-
-        // Note: Would need UpdateEntity support in whytcard_database
-        // For now, just report what would be removed
+        self.schedule_entity_embedding_refresh();
 
         Ok(Json(KnowledgeDeleteObservationResult {
             entity_name: params.entity_name,
@@ -1270,7 +2062,7 @@ impl IntelligenceServer {
             // Build entity map for relation lookup
             let entity_map: std::collections::HashMap<String, String> = entities
                 .iter()
-                .filter_map(|e| e.id.as_ref().map(|id| (id.key().to_string(), e.name.clone())))
+                .filter_map(|e| e.id.as_ref().map(|id| (id.key().to_string(), e.label().to_string())))
                 .collect();
 
             all_relations
@@ -1299,7 +2091,7 @@ impl IntelligenceServer {
         let entity_infos: Vec<EntityInfo> = entities
             .into_iter()
             .map(|e| EntityInfo {
-                name: e.name,
+                name: e.label().to_string(),
                 entity_type: e.entity_type,
                 observations: e.observations,
             })
@@ -1324,12 +2116,16 @@ impl IntelligenceServer {
         params: rmcp::handler::server::wrapper::Parameters<KnowledgeGetNeighborsParams>,
     ) -> std::result::Result<Json<KnowledgeGetNeighborsResult>, McpError> {
         let params = params.0;
+        require_positive("max_depth", params.max_depth)?;
+        if let Some(max_nodes) = params.max_nodes_visited {
+            require_positive("max_nodes_visited", max_nodes)?;
+        }
         let max_depth = params.max_depth;
 
         // Find entity
         let entity = self
             .db
-            .get_entity_by_name(&params.entity_name)
+            .get_entity_by_name(&self.entity_key(&params.entity_name))
             .await
             .map_err(IntelligenceError::from)?
             .ok_or_else(|| IntelligenceError::EntityNotFound(params.entity_name.clone()))?;
@@ -1339,74 +2135,40 @@ impl IntelligenceServer {
             .ok_or_else(|| IntelligenceError::EntityNotFound(params.entity_name.clone()))?;
         let id_str = entity_id.key().to_string();
 
-        let mut neighbors = Vec::new();
-        let mut visited = std::collections::HashSet::new();
-        visited.insert(params.entity_name.clone());
-
-        // BFS to find neighbors up to depth
-        let mut queue = vec![(id_str.clone(), Vec::<String>::new(), 0usize)];
-        while let Some((current_id, path, current_depth)) = queue.pop() {
-            if current_depth >= max_depth {
-                continue;
-            }
-
-            // Get outgoing relations
-            if let Ok(rels) = self.db.get_outgoing_relations(&current_id).await {
-                for rel in rels {
-                    // Filter by relation types if specified
-                    if !params.relation_types.is_empty() && !params.relation_types.contains(&rel.relation_type) {
-                        continue;
-                    }
-
-                    let to_id = rel.to.key().to_string();
-                    if let Ok(to_entity) = self.db.get_entity(&to_id).await {
-                        if !visited.contains(&to_entity.name) {
-                            visited.insert(to_entity.name.clone());
-                            let mut new_path = path.clone();
-                            new_path.push(rel.relation_type.clone());
-                            neighbors.push(NeighborInfo {
-                                entity: EntityInfo {
-                                    name: to_entity.name.clone(),
-                                    entity_type: to_entity.entity_type,
-                                    observations: to_entity.observations,
-                                },
-                                distance: current_depth + 1,
-                                path: new_path.clone(),
-                            });
-                            queue.push((to_id, new_path, current_depth + 1));
-                        }
-                    }
-                }
-            }
+        let max_nodes = params
+            .max_nodes_visited
+            .unwrap_or(self.config.knowledge.max_nodes_visited);
 
-            // Get incoming relations
-            if let Ok(rels) = self.db.get_incoming_relations(&current_id).await {
-                for rel in rels {
-                    if !params.relation_types.is_empty() && !params.relation_types.contains(&rel.relation_type) {
-                        continue;
-                    }
+        let traversal = self
+            .db
+            .traverse_bounded(&id_str, max_depth, &params.relation_types, (max_nodes > 0).then_some(max_nodes))
+            .await
+            .map_err(IntelligenceError::from)?;
+        let truncated = traversal.truncated;
 
-                    let from_id = rel.from.key().to_string();
-                    if let Ok(from_entity) = self.db.get_entity(&from_id).await {
-                        if !visited.contains(&from_entity.name) {
-                            visited.insert(from_entity.name.clone());
-                            let mut new_path = path.clone();
-                            new_path.push(format!("~{}", rel.relation_type));
-                            neighbors.push(NeighborInfo {
-                                entity: EntityInfo {
-                                    name: from_entity.name.clone(),
-                                    entity_type: from_entity.entity_type,
-                                    observations: from_entity.observations,
-                                },
-                                distance: current_depth + 1,
-                                path: new_path.clone(),
-                            });
-                            queue.push((from_id, new_path, current_depth + 1));
+        let neighbors: Vec<NeighborInfo> = traversal
+            .entities
+            .into_iter()
+            .map(|related| NeighborInfo {
+                path: related
+                    .path
+                    .iter()
+                    .map(|step| match step.direction {
+                        // Symmetric edges read the same in both directions
+                        RelationDirection::Incoming if !step.relation.symmetric => {
+                            format!("~{}", step.relation.relation_type)
                         }
-                    }
-                }
-            }
-        }
+                        _ => step.relation.relation_type.clone(),
+                    })
+                    .collect(),
+                distance: related.distance,
+                entity: EntityInfo {
+                    name: related.entity.label().to_string(),
+                    entity_type: related.entity.entity_type,
+                    observations: related.entity.observations,
+                },
+            })
+            .collect();
 
         let total = neighbors.len();
 
@@ -1414,6 +2176,7 @@ impl IntelligenceServer {
             start_entity: params.entity_name,
             neighbors,
             total,
+            truncated,
         }))
     }
 
@@ -1423,112 +2186,93 @@ impl IntelligenceServer {
         params: rmcp::handler::server::wrapper::Parameters<KnowledgeFindPathParams>,
     ) -> std::result::Result<Json<KnowledgeFindPathResult>, McpError> {
         let params = params.0;
+        require_positive("max_depth", params.max_depth)?;
+        if let Some(max_nodes) = params.max_nodes_visited {
+            require_positive("max_nodes_visited", max_nodes)?;
+        }
         let max_depth = params.max_depth;
 
         // Find source entity
         let from_entity = self
             .db
-            .get_entity_by_name(&params.from)
+            .get_entity_by_name(&self.entity_key(&params.from))
             .await
             .map_err(IntelligenceError::from)?
             .ok_or_else(|| IntelligenceError::EntityNotFound(params.from.clone()))?;
 
         let from_id = from_entity
             .id
+            .clone()
             .ok_or_else(|| IntelligenceError::EntityNotFound(params.from.clone()))?;
 
         // Find target entity
-        let _to_entity = self
+        let to_entity = self
             .db
-            .get_entity_by_name(&params.to)
+            .get_entity_by_name(&self.entity_key(&params.to))
             .await
             .map_err(IntelligenceError::from)?
             .ok_or_else(|| IntelligenceError::EntityNotFound(params.to.clone()))?;
 
-        // BFS to find shortest path
-        let mut visited: std::collections::HashMap<String, (Option<String>, Option<RelationInfo>)> = std::collections::HashMap::new();
-        visited.insert(params.from.clone(), (None, None));
-
-        let mut queue = vec![(from_id.key().to_string(), params.from.clone(), 0usize)];
-        let mut found = false;
-
-        while let Some((current_id, current_name, depth)) = queue.pop() {
-            if current_name == params.to {
-                found = true;
-                break;
-            }
-
-            if depth >= max_depth {
-                continue;
-            }
-
-            // Get outgoing relations
-            if let Ok(rels) = self.db.get_outgoing_relations(&current_id).await {
-                for rel in rels {
-                    let to_id = rel.to.key().to_string();
-                    if let Ok(entity) = self.db.get_entity(&to_id).await {
-                        if !visited.contains_key(&entity.name) {
-                            visited.insert(
-                                entity.name.clone(),
-                                (Some(current_name.clone()), Some(RelationInfo {
-                                    from: current_name.clone(),
-                                    to: entity.name.clone(),
-                                    relation_type: rel.relation_type.clone(),
-                                })),
-                            );
-                            queue.push((to_id, entity.name.clone(), depth + 1));
-                        }
-                    }
-                }
-            }
+        let max_nodes = params
+            .max_nodes_visited
+            .unwrap_or(self.config.knowledge.max_nodes_visited);
 
-            // Get incoming relations
-            if let Ok(rels) = self.db.get_incoming_relations(&current_id).await {
-                for rel in rels {
-                    let other_id = rel.from.key().to_string();
-                    if let Ok(entity) = self.db.get_entity(&other_id).await {
-                        if !visited.contains_key(&entity.name) {
-                            visited.insert(
-                                entity.name.clone(),
-                                (Some(current_name.clone()), Some(RelationInfo {
-                                    from: entity.name.clone(),
-                                    to: current_name.clone(),
-                                    relation_type: rel.relation_type.clone(),
-                                })),
-                            );
-                            queue.push((other_id, entity.name.clone(), depth + 1));
-                        }
-                    }
+        // Breadth-first, so the first path reaching the target is a shortest one
+        let (found, path, truncated) = if from_entity.id == to_entity.id {
+            (true, Vec::new(), false)
+        } else {
+            let traversal = self
+                .db
+                .traverse_bounded(&from_id.key().to_string(), max_depth, &[], (max_nodes > 0).then_some(max_nodes))
+                .await
+                .map_err(IntelligenceError::from)?;
+            let mut labels: std::collections::HashMap<String, String> = std::collections::HashMap::new();
+            labels.insert(from_id.to_string(), from_entity.label().to_string());
+            for related in &traversal.entities {
+                if let Some(id) = &related.entity.id {
+                    labels.insert(id.to_string(), related.entity.label().to_string());
                 }
             }
-        }
 
-        // Reconstruct path
-        let path = if found {
-            let mut path_relations = Vec::new();
-            let mut current = params.to.clone();
-
-            while let Some((prev, rel_opt)) = visited.get(&current) {
-                if let (Some(_p), Some(rel)) = (prev, rel_opt) {
-                    path_relations.push(rel.clone());
-                    current = prev.clone().unwrap();
-                } else {
-                    break;
+            let target = traversal
+                .entities
+                .iter()
+                .find(|related| related.entity.id.is_some() && related.entity.id == to_entity.id);
+            match target {
+                Some(target) => {
+                    let label = |id: &whytcard_database::RecordId| {
+                        labels.get(&id.to_string()).cloned().unwrap_or_else(|| id.key().to_string())
+                    };
+                    let path = target
+                        .path
+                        .iter()
+                        .map(|step| {
+                            let relation = &step.relation;
+                            // Symmetric edges are walked forward, like outgoing ones
+                            let (from, to) = if step.direction == RelationDirection::Incoming && relation.symmetric {
+                                (&relation.to, &relation.from)
+                            } else {
+                                (&relation.from, &relation.to)
+                            };
+                            RelationInfo {
+                                from: label(from),
+                                to: label(to),
+                                relation_type: relation.relation_type.clone(),
+                            }
+                        })
+                        .collect();
+                    (true, path, false)
                 }
+                None => (false, Vec::new(), traversal.truncated),
             }
-
-            path_relations.reverse();
-            path_relations
-        } else {
-            Vec::new()
         };
-
         let length = path.len();
 
         Ok(Json(KnowledgeFindPathResult {
             found,
             path,
             length,
+            truncated,
         }))
     }
 
@@ -1536,14 +2280,13 @@ impl IntelligenceServer {
     // CORTEX TOOLS
     // ========================================================================
 
-    #[tool(description = "Process a query through CORTEX cognitive engine for intelligent analysis and execution")]
+    #[tool(description = "Process a query through CORTEX cognitive engine for intelligent analysis and execution. Set response_language to force the language of the response")]
     async fn cortex_process(
         &self,
         params: rmcp::handler::server::wrapper::Parameters<CortexProcessParams>,
     ) -> std::result::Result<Json<CortexProcessResult>, McpError> {
+        let started = std::time::Instant::now();
         let params = params.0;
-        let mut loaded_prompts: Vec<String> = Vec::new();
-        let mut instructions_count = 0;
 
         // Start session if requested
         let session_id = if params.session_id.is_some() {
@@ -1558,42 +2301,107 @@ impl IntelligenceServer {
             None
         };
 
-        // ====================================================================
-        // PROMPT INJECTION: Load prompts from memory
-        // ====================================================================
-        let mut prompt_context = String::new();
-
-        // 0. ALWAYS inject .instructions.md files if inject_instructions is true (default)
-        if params.inject_instructions {
-            let instructions_prompt = self.cortex.get_instructions_prompt(params.file_path.as_deref()).await;
-            if !instructions_prompt.is_empty() {
-                prompt_context.push_str("# System Instructions (from .instructions.md files)\n\n");
-                prompt_context.push_str(&instructions_prompt);
-                prompt_context.push_str("\n\n---\n\n");
+        let (prompt_context, loaded_prompts, instructions) =
+            self.assemble_prompt_context(&params).await;
+        let context = Self::enriched_context(&params, &prompt_context, &loaded_prompts);
 
-                // Count instructions
-                if let Some(ref file_path) = params.file_path {
-                    instructions_count = self.cortex.get_instructions_for_file(file_path).await.len();
-                } else {
-                    let stats = self.cortex.get_instructions_stats().await;
-                    instructions_count = stats.get("count").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
-                }
-                loaded_prompts.push(format!("instructions:{}", instructions_count));
-            }
-        }
+        // Process through CORTEX, within what is left of the overall timeout
+        let timeout = params.timeout_secs.map(|secs| remaining_timeout(secs, started));
+        let result = self
+            .cortex
+            .process_with_timeout(&params.query, context, timeout)
+            .await
+            .map_err(|e| {
+                McpError::internal_error(format!("CORTEX processing failed: {}", e), None)
+            })?;
 
-        // 1. Always load doubt prompt if inject_doubt is true (default)
-        if params.inject_doubt {
-            if let Ok(Some(doubt_doc)) = self.db.get_document_by_key("prompt:root:doubt").await {
-                prompt_context.push_str(&doubt_doc.content);
-                prompt_context.push_str("\n\n---\n\n");
-                loaded_prompts.push("prompt:root:doubt".to_string());
-            }
+        // End session if we started one
+        if session_id.is_some() {
+            let _ = self.cortex.end_session().await;
         }
 
-        // 2. Load language-specific prompt if language is provided
-        if let Some(ref lang) = params.language {
-            let lang_key = format!("prompt:code:{}", lang.to_lowercase());
+        // Convert result
+        let mut output = CortexProcessResult {
+            success: result.success,
+            output: result.result.to_string(),
+            intent: format!("{:?}", result.perception.intent),
+            labels: result
+                .perception
+                .labels
+                .iter()
+                .map(|l| l.as_str().to_string())
+                .collect(),
+            confidence: result.confidence,
+            research_needed: result.execution.research_performed,
+            steps_executed: result.execution.steps_executed,
+            duration_ms: result.execution.duration_ms as u128,
+            recommendations: result.next_actions,
+            session_id: None,
+            loaded_prompts,
+            instructions_count: instructions.included,
+            instructions_dropped: instructions.dropped,
+            instructions_dropped_chars: instructions.dropped_chars,
+            stopped_reason: result.stopped_reason,
+            decision_trace: result.decision_trace,
+        };
+        output.session_id = session_id;
+
+        Ok(Json(output))
+    }
+
+    /// Assemble the prompts cortex_process injects, in injection order
+    ///
+    /// Returns the prompt context, the keys of the loaded prompts and the
+    /// injected instructions with what the instruction budget left out.
+    async fn assemble_prompt_context(
+        &self,
+        params: &CortexProcessParams,
+    ) -> (String, Vec<String>, InstructionsPrompt) {
+        let mut prompt_context = String::new();
+        let mut loaded_prompts: Vec<String> = Vec::new();
+        let mut instructions = InstructionsPrompt::default();
+
+        // Response language goes first so later prompts can't override it
+        if let Some(instruction) = response_language_instruction(params.response_language.as_deref()) {
+            prompt_context.push_str(&instruction);
+            prompt_context.push_str("\n\n---\n\n");
+            if let Some(lang) = params.response_language.as_deref() {
+                loaded_prompts.push(format!("response_language:{}", lang.trim()));
+            }
+        }
+
+        // 0. ALWAYS inject .instructions.md files and the user's saved
+        //    instructions if inject_instructions is true (default)
+        if params.inject_instructions {
+            // Capped so many instruction files can't overflow the context budget
+            let user_id = params.user_id.as_deref().unwrap_or("default");
+            instructions = self
+                .cortex
+                .get_user_instructions_prompt(params.file_path.as_deref(), user_id, params.max_instructions_chars)
+                .await;
+            if !instructions.text.is_empty() {
+                prompt_context.push_str("# System Instructions (from .instructions.md files)\n\n");
+                prompt_context.push_str(&instructions.text);
+                prompt_context.push_str("\n\n---\n\n");
+                loaded_prompts.push(format!("instructions:{}", instructions.included));
+                loaded_prompts.extend(
+                    instructions.user_keys.iter().map(|key| user_instruction_key(user_id, key)),
+                );
+            }
+        }
+
+        // 1. Always load doubt prompt if inject_doubt is true (default)
+        if params.inject_doubt {
+            if let Ok(Some(doubt_doc)) = self.db.get_document_by_key("prompt:root:doubt").await {
+                prompt_context.push_str(&doubt_doc.content);
+                prompt_context.push_str("\n\n---\n\n");
+                loaded_prompts.push("prompt:root:doubt".to_string());
+            }
+        }
+
+        // 2. Load language-specific prompt if language is provided
+        if let Some(ref lang) = params.language {
+            let lang_key = format!("prompt:code:{}", lang.to_lowercase());
             if let Ok(Some(lang_doc)) = self.db.get_document_by_key(&lang_key).await {
                 prompt_context.push_str(&lang_doc.content);
                 prompt_context.push_str("\n\n---\n\n");
@@ -1611,8 +2419,16 @@ impl IntelligenceServer {
             }
         }
 
-        // Build enriched context
-        let context = if prompt_context.is_empty() {
+        (prompt_context, loaded_prompts, instructions)
+    }
+
+    /// Context object handed to the CORTEX engine
+    fn enriched_context(
+        params: &CortexProcessParams,
+        prompt_context: &str,
+        loaded_prompts: &[String],
+    ) -> Option<serde_json::Value> {
+        if prompt_context.is_empty() {
             params
                 .context
                 .as_ref()
@@ -1622,47 +2438,30 @@ impl IntelligenceServer {
             Some(serde_json::json!({
                 "system_prompts": prompt_context,
                 "user_context": user_ctx,
-                "loaded_prompts": &loaded_prompts
+                "loaded_prompts": loaded_prompts
             }))
-        };
+        }
+    }
 
-        // Process through CORTEX
-        let result = self
-            .cortex
-            .process(&params.query, context)
-            .await
-            .map_err(|e| {
-                McpError::internal_error(format!("CORTEX processing failed: {}", e), None)
-            })?;
+    #[tool(description = "Preview the prompt context cortex_process would inject (instructions, doubt, language, task and response-language prompts) for the same params, without running execution")]
+    async fn cortex_preview_context(
+        &self,
+        params: rmcp::handler::server::wrapper::Parameters<CortexProcessParams>,
+    ) -> std::result::Result<Json<CortexPreviewContextResult>, McpError> {
+        let params = params.0;
 
-        // End session if we started one
-        if session_id.is_some() {
-            let _ = self.cortex.end_session().await;
-        }
+        let (prompt_context, loaded_prompts, instructions) =
+            self.assemble_prompt_context(&params).await;
+        let context = Self::enriched_context(&params, &prompt_context, &loaded_prompts);
 
-        // Convert result
-        let mut output = CortexProcessResult {
-            success: result.success,
-            output: result.result.to_string(),
-            intent: format!("{:?}", result.perception.intent),
-            labels: result
-                .perception
-                .labels
-                .iter()
-                .map(|l| l.as_str().to_string())
-                .collect(),
-            confidence: result.confidence,
-            research_needed: result.execution.research_performed,
-            steps_executed: result.execution.steps_executed,
-            duration_ms: result.execution.duration_ms as u128,
-            recommendations: result.next_actions,
-            session_id: None,
+        Ok(Json(CortexPreviewContextResult {
+            prompt_context,
+            context,
             loaded_prompts,
-            instructions_count,
-        };
-        output.session_id = session_id;
-
-        Ok(Json(output))
+            instructions_count: instructions.included,
+            instructions_dropped: instructions.dropped,
+            instructions_dropped_chars: instructions.dropped_chars,
+        }))
     }
 
     #[tool(description = "Provide feedback to CORTEX for adaptive learning and rule improvement")]
@@ -1689,6 +2488,16 @@ impl IntelligenceServer {
         }))
     }
 
+    #[tool(description = "Abort the cortex_process calls in progress for this client. They stop at the next step boundary and return partial results with stopped_reason \"aborted\"")]
+    async fn cortex_abort(
+        &self,
+        _params: rmcp::handler::server::wrapper::Parameters<CortexAbortParams>,
+    ) -> std::result::Result<Json<CortexAbortResult>, McpError> {
+        Ok(Json(CortexAbortResult {
+            aborted: self.cortex.abort_current(),
+        }))
+    }
+
     #[tool(description = "Get CORTEX cognitive engine statistics including memory usage")]
     async fn cortex_stats(
         &self,
@@ -1696,36 +2505,170 @@ impl IntelligenceServer {
     ) -> std::result::Result<Json<CortexStatsResult>, McpError> {
         let stats = self.cortex.get_stats().await;
 
-        // Extract values from JSON
-        let semantic_facts = stats
-            .get("semantic")
-            .and_then(|s| s.get("total_facts"))
-            .and_then(|v| v.as_u64())
-            .unwrap_or(0) as usize;
-
-        let episodic_events = stats
-            .get("episodic")
-            .and_then(|s| s.get("total_episodes"))
-            .and_then(|v| v.as_u64())
-            .unwrap_or(0) as usize;
-
-        let procedural_rules = stats
-            .get("procedural")
-            .and_then(|s| s.get("total_rules"))
-            .and_then(|v| v.as_u64())
-            .unwrap_or(0) as usize;
-
         Ok(Json(CortexStatsResult {
-            memory: crate::tools::MemoryStatsDetail {
-                semantic_facts,
-                episodic_events,
-                procedural_rules,
-            },
+            memory: crate::tools::MemoryStatsDetail::from_stats(&stats),
             status: "running".to_string(),
             uptime_secs: 0,
+            recent_queries: self.cortex.recent_queries(RECENT_QUERIES_LIMIT).await,
+        }))
+    }
+
+    #[tool(description = "Report health of server subsystems (database, RAG, CORTEX, data directory, integrations, MCP clients). Works on every transport")]
+    async fn health(
+        &self,
+        _params: rmcp::handler::server::wrapper::Parameters<HealthParams>,
+    ) -> std::result::Result<Json<HealthResult>, McpError> {
+        Ok(Json(self.check_health().await))
+    }
+
+    #[tool(description = "Describe this server: enabled tools, configured integrations, embedding model and dimension, storage mode, namespace, version and limits")]
+    async fn capabilities(
+        &self,
+        _params: rmcp::handler::server::wrapper::Parameters<CapabilitiesParams>,
+    ) -> std::result::Result<Json<CapabilitiesResult>, McpError> {
+        let mut tools: Vec<String> = self
+            .tool_router
+            .list_all()
+            .into_iter()
+            .map(|tool| tool.name.to_string())
+            .collect();
+        tools.sort();
+
+        let integrations = vec![
+            IntegrationCapability {
+                name: "context7".to_string(),
+                ready: self.context7.read().await.is_ready(),
+            },
+            IntegrationCapability {
+                name: "tavily".to_string(),
+                ready: self.tavily.read().await.is_ready(),
+            },
+            IntegrationCapability {
+                name: "mslearn".to_string(),
+                ready: self.mslearn.read().await.is_ready(),
+            },
+        ];
+
+        let mut mcp_servers: Vec<String> =
+            self.mcp_config.read().await.list_all().map(|(name, _)| name.clone()).collect();
+        mcp_servers.sort();
+
+        let (mode, path) = match &self.db.config().storage {
+            StorageMode::Memory => ("memory", None),
+            StorageMode::Persistent(path) => ("persistent", Some(path.display().to_string())),
+            StorageMode::Remote { url, .. } => ("remote", Some(url.clone())),
+        };
+
+        let memory = &self.config.memory;
+        let knowledge = &self.config.knowledge;
+        Ok(Json(CapabilitiesResult {
+            server_name: self.config.server_name.clone(),
+            version: self.config.version.clone(),
+            tools,
+            integrations,
+            mcp_servers,
+            embedding: EmbeddingCapability {
+                model: self.config.rag.model.fastembed_name().to_string(),
+                dimension: self.config.rag.model.dimensions(),
+            },
+            storage: StorageCapability {
+                mode: mode.to_string(),
+                path,
+                namespace: self.config.namespace.clone(),
+            },
+            features: FeatureFlags {
+                semantic_search: memory.semantic_search,
+                auto_index: self.config.rag.auto_index,
+                soft_delete: memory.soft_delete,
+                auto_cleanup: memory.auto_cleanup_hours > 0,
+                strict_relation_types: knowledge.strict_relation_types,
+            },
+            limits: Limits {
+                max_content_bytes: memory.max_content_bytes,
+                max_entries: memory.max_entries,
+                max_entities: knowledge.max_entities,
+                max_relations_per_entity: knowledge.max_relations_per_entity,
+                max_nodes_visited: knowledge.max_nodes_visited,
+                max_search_results: self.config.rag.search.max_limit,
+                max_clients: self.config.max_clients,
+            },
         }))
     }
 
+    /// Check every subsystem; shared by the `health` tool and startup validation
+    pub async fn check_health(&self) -> HealthResult {
+        let mut subsystems = Vec::new();
+
+        subsystems.push(match self.db.health().await {
+            Ok(true) => SubsystemHealth::ok("database", true, "reachable"),
+            Ok(false) => SubsystemHealth::failed("database", true, "health query returned unexpected result"),
+            Err(e) => SubsystemHealth::failed("database", true, e.to_string()),
+        });
+
+        subsystems.push(match self.rag.read().await.count().await {
+            Ok(count) => SubsystemHealth::ok("rag", true, format!("{} chunks indexed", count)),
+            Err(e) => SubsystemHealth::failed("rag", true, e.to_string()),
+        });
+
+        let stats = self.cortex.get_stats().await;
+        subsystems.push(if stats.is_object() {
+            SubsystemHealth::ok("cortex", true, "running")
+        } else {
+            SubsystemHealth::failed("cortex", true, "stats unavailable")
+        });
+
+        subsystems.push(match self.config.get_paths() {
+            Ok(paths) => match std::fs::metadata(&paths.root) {
+                Ok(meta) if meta.permissions().readonly() => {
+                    SubsystemHealth::failed("data_dir", true, format!("{:?} is read-only", paths.root))
+                }
+                Ok(_) => SubsystemHealth::ok("data_dir", true, format!("{:?}", paths.root)),
+                Err(e) => SubsystemHealth::failed("data_dir", true, format!("{:?}: {}", paths.root, e)),
+            },
+            Err(e) => SubsystemHealth::failed("data_dir", true, e.to_string()),
+        });
+
+        // Integrations are optional: missing API keys only degrade the server
+        let mut ready = Vec::new();
+        if self.context7.read().await.is_ready() {
+            ready.push("context7");
+        }
+        if self.tavily.read().await.is_ready() {
+            ready.push("tavily");
+        }
+        if self.mslearn.read().await.is_ready() {
+            ready.push("mslearn");
+        }
+        subsystems.push(if ready.is_empty() {
+            SubsystemHealth::failed("integrations", false, "no external integration configured")
+        } else {
+            SubsystemHealth::ok("integrations", false, format!("ready: {}", ready.join(", ")))
+        });
+
+        let status = self.mcp_clients.get_status().await;
+        let connected = status.values().filter(|s| **s == crate::mcp_client::McpClientStatus::Connected).count();
+        let failed: Vec<&String> = status
+            .iter()
+            .filter(|(_, s)| **s == crate::mcp_client::McpClientStatus::Failed)
+            .map(|(name, _)| name)
+            .collect();
+        subsystems.push(if failed.is_empty() {
+            SubsystemHealth::ok("mcp_clients", false, format!("{} connected", connected))
+        } else {
+            SubsystemHealth::failed(
+                "mcp_clients",
+                false,
+                format!("{} connected, failed: {:?}", connected, failed),
+            )
+        });
+
+        let mut health = HealthResult::from_subsystems(subsystems);
+        health.tool_payloads = self.metrics.snapshot();
+        health.active_clients = self.sessions.session_count().await;
+        health.max_clients = self.sessions.max_sessions();
+        health
+    }
+
     #[tool(description = "Manage workspace instructions from .instructions.md files. Actions: list (show all), reload (refresh from disk), get (get content by name), for_file (filter by file path pattern)")]
     async fn cortex_instructions(
         &self,
@@ -1736,8 +2679,7 @@ impl IntelligenceServer {
         match params.action {
             InstructionsAction::List => {
                 let stats = self.cortex.get_instructions_stats().await;
-                let count = stats.get("count").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
-                let instructions = stats.get("instructions")
+                let instructions: Vec<InstructionInfo> = stats.get("instructions")
                     .and_then(|v| v.as_array())
                     .map(|arr| {
                         arr.iter().filter_map(|v| {
@@ -1745,10 +2687,12 @@ impl IntelligenceServer {
                                 name: v.get("name")?.as_str()?.to_string(),
                                 description: v.get("description").and_then(|d| d.as_str()).map(|s| s.to_string()),
                                 apply_to: v.get("apply_to").and_then(|a| a.as_str()).map(|s| s.to_string()),
+                                priority: v.get("priority").and_then(|p| p.as_i64()).unwrap_or(0) as i32,
                             })
                         }).collect()
                     })
                     .unwrap_or_default();
+                let count = instructions.len();
 
                 Ok(Json(CortexInstructionsResult {
                     success: true,
@@ -1777,7 +2721,7 @@ impl IntelligenceServer {
             }
             InstructionsAction::Get => {
                 let name = params.name.ok_or_else(|| {
-                    McpError::invalid_params("name parameter required for get action", None)
+                    IntelligenceError::invalid_param("name", "is required for action 'get'")
                 })?;
 
                 let content = self.cortex.get_instruction_content(&name).await;
@@ -1798,7 +2742,7 @@ impl IntelligenceServer {
             }
             InstructionsAction::ForFile => {
                 let file_path = params.file_path.ok_or_else(|| {
-                    McpError::invalid_params("file_path parameter required for for_file action", None)
+                    IntelligenceError::invalid_param("file_path", "is required for action 'for_file'")
                 })?;
 
                 let matching = self.cortex.get_instructions_for_file(&file_path).await;
@@ -1807,6 +2751,7 @@ impl IntelligenceServer {
                         name: i.name.clone(),
                         description: Some(i.description.clone()),
                         apply_to: Some(i.apply_to.clone()),
+                        priority: i.priority,
                     }
                 }).collect();
                 let count = instructions.len();
@@ -1823,25 +2768,136 @@ impl IntelligenceServer {
         }
     }
 
+    #[tool(description = "List the user instructions (preferences) saved for a user via prepare, ordered by key")]
+    async fn user_instructions_list(
+        &self,
+        params: rmcp::handler::server::wrapper::Parameters<UserInstructionsListParams>,
+    ) -> std::result::Result<Json<UserInstructionsListResult>, McpError> {
+        let params = params.0;
+
+        let docs = self
+            .db
+            .list_documents_by_key_prefix(&user_instruction_prefix(&params.user_id))
+            .await
+            .map_err(IntelligenceError::from)?;
+
+        let instructions: Vec<UserInstructionInfo> = docs
+            .into_iter()
+            .filter_map(|doc| {
+                let content: serde_json::Value = serde_json::from_str(&doc.content).ok()?;
+                Some(UserInstructionInfo {
+                    key: content.get("key")?.as_str()?.to_string(),
+                    value: content.get("value")?.as_str()?.to_string(),
+                    category: content
+                        .get("category")
+                        .and_then(|c| c.as_str())
+                        .unwrap_or("general")
+                        .to_string(),
+                    priority: content.get("priority").and_then(|p| p.as_i64()).unwrap_or(0) as i32,
+                    updated_at: doc.updated_at.or(doc.created_at).map(|d| d.to_rfc3339()),
+                })
+            })
+            .collect();
+
+        Ok(Json(UserInstructionsListResult {
+            user_id: params.user_id,
+            count: instructions.len(),
+            instructions,
+        }))
+    }
+
+    #[tool(description = "Delete a saved user instruction by key, removing it from storage and from the active CORTEX instructions")]
+    async fn user_instructions_delete(
+        &self,
+        params: rmcp::handler::server::wrapper::Parameters<UserInstructionsDeleteParams>,
+    ) -> std::result::Result<Json<UserInstructionsDeleteResult>, McpError> {
+        let params = params.0;
+
+        let deleted_from_db = self
+            .db
+            .delete_document_by_key(&user_instruction_key(&params.user_id, &params.key))
+            .await
+            .map_err(IntelligenceError::from)?;
+        let removed_from_cortex = self
+            .cortex
+            .remove_user_instruction(&params.user_id, &params.key)
+            .await;
+
+        let deleted = deleted_from_db || removed_from_cortex;
+        Ok(Json(UserInstructionsDeleteResult {
+            deleted,
+            message: if deleted {
+                format!("Deleted user instruction '{}' for {}", params.key, params.user_id)
+            } else {
+                format!("User instruction '{}' not found for {}", params.key, params.user_id)
+            },
+        }))
+    }
+
     #[tool(description = "Cleanup old CORTEX data based on retention policy")]
     async fn cortex_cleanup(
         &self,
         params: rmcp::handler::server::wrapper::Parameters<CortexCleanupParams>,
     ) -> std::result::Result<Json<CortexCleanupResult>, McpError> {
         let params = params.0;
+        if let Some(days) = params.retention_days {
+            require_non_negative("retention_days", days as f64)?;
+        }
 
-        let cleaned = self
-            .cortex
-            .cleanup(params.retention_days)
+        let result = self
+            .apply_retention(params.retention_days, params.compact)
             .await
             .map_err(|e| McpError::internal_error(format!("Cleanup failed: {}", e), None))?;
 
-        Ok(Json(CortexCleanupResult {
-            cleaned_count: cleaned,
-            message: format!(
-                "Cleaned {} old records (retention: {} days)",
-                cleaned, params.retention_days
-            ),
+        Ok(Json(result))
+    }
+
+    /// Apply the retention policy: CORTEX memories by retention window, then
+    /// soft-deleted memories whose grace period has expired
+    async fn apply_retention(&self, episodic_days: Option<i64>, compact: bool) -> crate::Result<CortexCleanupResult> {
+        let report = self.cortex.cleanup(episodic_days, compact).await?;
+        let purged_memories = self
+            .db
+            .purge_deleted_documents(self.config.memory.deleted_retention_days)
+            .await?;
+
+        Ok(CortexCleanupResult::from_report(report, purged_memories))
+    }
+
+    /// Apply the retention policy every `cleanup_interval` in the background
+    ///
+    /// Does nothing unless `CortexConfig::auto_cleanup` is set. The first run
+    /// happens one interval after startup.
+    fn spawn_auto_cleanup(&self) -> Option<tokio::task::JoinHandle<()>> {
+        let cortex_config = self.cortex.config();
+        if !cortex_config.auto_cleanup {
+            return None;
+        }
+        let interval = cortex_config.cleanup_interval;
+        if interval.is_zero() {
+            tracing::warn!("Automatic cleanup disabled: cleanup interval is zero");
+            return None;
+        }
+
+        tracing::info!("Automatic cleanup every {:?}", interval);
+        let server = self.clone();
+        Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval_at(tokio::time::Instant::now() + interval, interval);
+            ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Delay);
+            loop {
+                ticker.tick().await;
+                match server.apply_retention(None, false).await {
+                    Ok(result) => tracing::info!(
+                        episodic = result.episodic_cleaned,
+                        semantic = result.semantic_cleaned,
+                        procedural = result.procedural_cleaned,
+                        purged_memories = result.purged_memories,
+                        "Scheduled cleanup: {}",
+                        result.message
+                    ),
+                    Err(e) => tracing::warn!("Scheduled cleanup failed: {}", e),
+                }
+            }
         }))
     }
 
@@ -1854,6 +2910,7 @@ impl IntelligenceServer {
         use tokio::process::Command;
 
         let params = params.0;
+        require_positive("timeout_secs", params.timeout_secs as usize)?;
         let start = Instant::now();
 
         // Determine shell based on OS
@@ -1943,6 +3000,7 @@ impl IntelligenceServer {
         params: rmcp::handler::server::wrapper::Parameters<SequentialThinkingParams>,
     ) -> std::result::Result<Json<SequentialThinkingResult>, McpError> {
         let params = params.0;
+        require_positive("estimated_steps", params.estimated_steps as usize)?;
 
         let mut thinking = self.thinking.write().await;
         thinking.start_session();
@@ -1971,73 +3029,144 @@ impl IntelligenceServer {
         }))
     }
 
-    #[tool(description = "Get documentation from external sources (Context7, MS Learn) for libraries and frameworks")]
+    #[tool(description = "Get documentation from external sources (Context7, MS Learn) for libraries and frameworks. Content from every provider is cut to max_chars (default max_tokens * 4) at a heading or paragraph boundary")]
     async fn external_docs(
         &self,
         params: rmcp::handler::server::wrapper::Parameters<ExternalDocsParams>,
     ) -> std::result::Result<Json<ExternalDocsResult>, McpError> {
         let params = params.0;
+        require_positive("max_tokens", params.max_tokens as usize)?;
+        if let Some(max_chars) = params.max_chars {
+            require_positive("max_chars", max_chars)?;
+        }
+        require_one_of("source", &params.source, &["context7", "mslearn", "auto"])?;
+        for (i, provider) in params.providers.iter().enumerate() {
+            require_one_of(&format!("providers[{i}]"), provider, DOCS_PROVIDERS)?;
+        }
 
-        // Try Context7 first for library documentation
-        if params.source == "auto" || params.source == "context7" {
-            let context7 = self.context7.read().await;
-            if context7.is_ready() {
-                if let Ok(Some(doc)) = context7
-                    .get_library_docs(&params.library, params.topic.as_deref(), params.max_tokens)
-                    .await
-                {
-                    return Ok(Json(ExternalDocsResult {
-                        library: doc.source,
-                        topic: doc.topic,
-                        content: doc.content,
-                        code_snippets: doc.code_snippets,
-                        url: doc.url,
-                        provider: doc.provider,
-                    }));
+        // Explicit providers win, then a specific `source`, then the configured order
+        let providers: Vec<String> = if !params.providers.is_empty() {
+            params.providers.clone()
+        } else if params.source != "auto" {
+            vec![params.source.clone()]
+        } else {
+            self.config.external.docs_providers.clone()
+        };
+        let mode = params.mode.unwrap_or(self.config.external.docs_mode);
+
+        let budget = params.char_budget();
+        let mut providers_tried = Vec::new();
+        let mut found = Vec::new();
+        let mut truncated = false;
+        for provider in &providers {
+            providers_tried.push(provider.clone());
+            if let Some(mut doc) = self.fetch_docs_from(provider, &params).await {
+                // Same budget for every provider; not all of them honour max_tokens
+                if let Some(content) = truncate_at_boundary(&doc.content, budget) {
+                    doc.code_snippets.retain(|s| content.contains(s.code.as_str()));
+                    doc.content = content;
+                    truncated = true;
+                }
+                found.push(doc);
+                if mode == DocsFallbackMode::FirstNonEmpty {
+                    break;
                 }
             }
         }
 
-        // Try MS Learn for Microsoft/Azure libraries
-        if params.source == "auto" || params.source == "mslearn" {
-            let mslearn = self.mslearn.read().await;
-            if mslearn.is_ready() {
+        let mut docs = found.into_iter();
+        let Some(first) = docs.next() else {
+            // Return empty result if no docs found
+            return Ok(Json(ExternalDocsResult {
+                library: params.library,
+                topic: params.topic,
+                content: "Documentation not found. Check if the library name is correct or try a different source.".to_string(),
+                code_snippets: Vec::new(),
+                url: None,
+                provider: "none".to_string(),
+                version: None,
+                truncated: false,
+                providers_tried,
+            }));
+        };
+
+        let mut result = ExternalDocsResult {
+            library: first.source,
+            topic: first.topic,
+            content: first.content,
+            code_snippets: first.code_snippets,
+            url: first.url,
+            provider: first.provider,
+            version: first.version,
+            truncated,
+            providers_tried,
+        };
+
+        // Merge mode: append the remaining providers' docs
+        for doc in docs {
+            result.content.push_str(&format!("\n\n---\n\n## {}\n\n{}", doc.provider, doc.content));
+            result.code_snippets.extend(doc.code_snippets);
+            result.provider = format!("{}+{}", result.provider, doc.provider);
+            if result.url.is_none() {
+                result.url = doc.url;
+            }
+            if result.version.is_none() {
+                result.version = doc.version;
+            }
+        }
+
+        Ok(Json(result))
+    }
+
+    /// Fetch docs from a single provider, `None` if unavailable or empty
+    async fn fetch_docs_from(
+        &self,
+        provider: &str,
+        params: &ExternalDocsParams,
+    ) -> Option<crate::integrations::DocResult> {
+        let doc = match provider {
+            "context7" => {
+                let context7 = self.context7.read().await;
+                if !context7.is_ready() {
+                    return None;
+                }
+                context7
+                    .get_library_docs(&params.library, params.topic.as_deref(), params.max_tokens)
+                    .await
+                    .ok()
+                    .flatten()
+            }
+            // MS Learn for Microsoft/Azure libraries
+            "mslearn" => {
+                let mslearn = self.mslearn.read().await;
+                if !mslearn.is_ready() {
+                    return None;
+                }
                 let query = if let Some(t) = &params.topic {
                     format!("{} {}", params.library, t)
                 } else {
                     params.library.clone()
                 };
-
-                if let Ok(Some(doc)) = mslearn.fetch_docs(&query).await {
-                    return Ok(Json(ExternalDocsResult {
-                        library: doc.source,
-                        topic: doc.topic,
-                        content: doc.content,
-                        code_snippets: doc.code_snippets,
-                        url: doc.url,
-                        provider: doc.provider,
-                    }));
-                }
+                mslearn.fetch_docs(&query).await.ok().flatten()
             }
-        }
-
-        // Return empty result if no docs found
-        Ok(Json(ExternalDocsResult {
-            library: params.library,
-            topic: params.topic,
-            content: "Documentation not found. Check if the library name is correct or try a different source.".to_string(),
-            code_snippets: Vec::new(),
-            url: None,
-            provider: "none".to_string(),
-        }))
+            other => {
+                tracing::warn!("Unknown documentation provider: {}", other);
+                None
+            }
+        };
+        doc.filter(|doc| !doc.content.trim().is_empty())
     }
 
-    #[tool(description = "Search the web using external search provider (Tavily)")]
+    #[tool(description = "Search the web using external search provider (Tavily). Send a progressToken to receive each result as a progress notification")]
     async fn external_search(
         &self,
+        meta: Meta,
+        peer: rmcp::service::Peer<rmcp::RoleServer>,
         params: rmcp::handler::server::wrapper::Parameters<ExternalSearchParams>,
     ) -> std::result::Result<Json<ExternalSearchResult>, McpError> {
         let params = params.0;
+        require_positive("max_results", params.max_results as usize)?;
+        require_one_of("search_type", &params.search_type, &["general", "news"])?;
 
         let tavily = self.tavily.read().await;
         if !tavily.is_ready() {
@@ -2088,6 +3217,21 @@ impl IntelligenceServer {
 
         let total = items.len();
 
+        // Stream each parsed result to clients that asked for progress
+        let progress = ProgressReporter::new(&meta, peer, Some(total));
+        if progress.is_enabled() {
+            for (i, item) in items.iter().enumerate() {
+                progress
+                    .report(serde_json::json!({
+                        "event": "result",
+                        "provider": "tavily",
+                        "index": i,
+                        "result": item,
+                    }))
+                    .await;
+            }
+        }
+
         Ok(Json(ExternalSearchResult {
             query: params.query,
             results: items,
@@ -2126,6 +3270,7 @@ impl IntelligenceServer {
                                 data: Some(serde_json::json!({
                                     "library": doc.source,
                                     "url": doc.url,
+                                    "version": doc.version,
                                     "code_snippets": doc.code_snippets
                                 })),
                                 error: None,
@@ -2279,39 +3424,109 @@ impl IntelligenceServer {
         }
     }
 
-    #[tool(description = "Connect to an external MCP server by name (predefined) or custom config")]
-    async fn mcp_connect(
+    #[tool(description = "Call several external MCP server tools concurrently, returning results in order")]
+    async fn external_mcp_call_batch(
         &self,
-        params: rmcp::handler::server::wrapper::Parameters<McpConnectParams>,
-    ) -> std::result::Result<Json<McpConnectResult>, McpError> {
+        params: rmcp::handler::server::wrapper::Parameters<ExternalMcpCallBatchParams>,
+    ) -> std::result::Result<Json<ExternalMcpCallBatchResult>, McpError> {
         let params = params.0;
+        require_positive("max_concurrency", params.max_concurrency)?;
+
+        // Auto-connect each distinct server once before fanning out
+        let mut connect_errors: std::collections::HashMap<String, String> =
+            std::collections::HashMap::new();
+        for call in &params.calls {
+            if connect_errors.contains_key(&call.server)
+                || self.mcp_clients.is_connected(&call.server).await
+            {
+                continue;
+            }
+            if let Err(e) = self.mcp_clients.connect(&call.server).await {
+                connect_errors.insert(call.server.clone(), e.to_string());
+            }
+        }
 
-        // If custom config provided, add it first
-        if let Some(custom) = params.custom_config {
-            let transport = match custom.transport.as_str() {
-                "stdio" => {
-                    let command = custom.command.unwrap_or_else(|| "npx".to_string());
-                    crate::mcp_client::McpTransport::Stdio {
-                        command,
-                        args: custom.args,
-                    }
-                }
-                "sse" => crate::mcp_client::McpTransport::Sse {
-                    url: custom.url.unwrap_or_default(),
-                    auth_token: None,
+        let calls: Vec<_> = params
+            .calls
+            .iter()
+            .map(|c| (c.server.clone(), c.tool.clone(), c.arguments.clone()))
+            .collect();
+        let outcomes = self
+            .mcp_clients
+            .call_tools_concurrent(calls, params.max_concurrency)
+            .await;
+
+        let results: Vec<ExternalMcpCallResult> = params
+            .calls
+            .into_iter()
+            .zip(outcomes)
+            .map(|(call, outcome)| match outcome {
+                Ok(result) => ExternalMcpCallResult {
+                    server: call.server,
+                    tool: call.tool,
+                    success: result.success,
+                    content: result.content,
+                    data: result.data,
+                    error: result.error,
                 },
-                "http" => crate::mcp_client::McpTransport::Sse {
-                    url: custom.url.unwrap_or_default(),
+                Err(e) => {
+                    let error = match connect_errors.get(&call.server) {
+                        Some(connect_err) => format!(
+                            "Server '{}' not connected and auto-connect failed: {}. Use mcp_connect first.",
+                            call.server, connect_err
+                        ),
+                        None => format!("MCP call failed: {}", e),
+                    };
+                    ExternalMcpCallResult {
+                        server: call.server,
+                        tool: call.tool,
+                        success: false,
+                        content: String::new(),
+                        data: None,
+                        error: Some(error),
+                    }
+                }
+            })
+            .collect();
+
+        let succeeded = results.iter().filter(|r| r.success).count();
+        let failed = results.len() - succeeded;
+
+        Ok(Json(ExternalMcpCallBatchResult {
+            results,
+            succeeded,
+            failed,
+        }))
+    }
+
+    #[tool(description = "Connect to an external MCP server by name (predefined) or custom config")]
+    async fn mcp_connect(
+        &self,
+        params: rmcp::handler::server::wrapper::Parameters<McpConnectParams>,
+    ) -> std::result::Result<Json<McpConnectResult>, McpError> {
+        let params = params.0;
+
+        // If custom config provided, add it first
+        if let Some(custom) = params.custom_config {
+            require_one_of("custom_config.transport", &custom.transport, &["stdio", "sse", "http"])?;
+            let transport = match custom.transport.as_str() {
+                "stdio" => {
+                    let command = custom.command.unwrap_or_else(|| "npx".to_string());
+                    crate::mcp_client::McpTransport::Stdio {
+                        command,
+                        args: custom.args,
+                    }
+                }
+                // "sse" or "http"
+                _ => crate::mcp_client::McpTransport::Sse {
+                    url: custom.url.ok_or_else(|| {
+                        IntelligenceError::invalid_param(
+                            "custom_config.url",
+                            format!("is required for transport '{}'", custom.transport),
+                        )
+                    })?,
                     auth_token: None,
                 },
-                _ => {
-                    return Ok(Json(McpConnectResult {
-                        server: params.server,
-                        connected: false,
-                        tools: Vec::new(),
-                        error: Some("Invalid transport type. Use 'stdio', 'sse', or 'http'".to_string()),
-                    }));
-                }
             };
 
             let _config = crate::mcp_client::McpServerConfig {
@@ -2646,18 +3861,10 @@ impl IntelligenceServer {
         let params = params.0;
 
         // Create the InstalledMcpServer based on package_type
+        require_one_of("package_type", &params.package_type, &["npm", "pip"])?;
         let server = match params.package_type.as_str() {
             "npm" => InstalledMcpServer::npm(&params.name, &params.package),
-            "pip" => InstalledMcpServer::pip(&params.name, &params.package),
-            _ => {
-                return Ok(Json(McpInstallResult {
-                    name: params.name,
-                    installed: false,
-                    connected: false,
-                    tools: Vec::new(),
-                    error: Some(format!("Invalid package_type: {}. Use npm or pip", params.package_type)),
-                }));
-            }
+            _ => InstalledMcpServer::pip(&params.name, &params.package),
         };
 
         // Add environment variables and description
@@ -2882,14 +4089,32 @@ impl IntelligenceServer {
     // ACID PIPELINE TOOLS
     // ========================================================================
 
-    #[tool(description = "Phase A - ANALYZE: Research and understand before coding. Combines sequential_thinking + memory_search + knowledge_search + external_docs/search. Use this FIRST to gather context about any task.")]
+    #[tool(description = "Phase A - ANALYZE: Research and understand before coding. Combines sequential_thinking + memory_search + knowledge_search + external_docs/search. Use this FIRST to gather context about any task. Send a progressToken to receive a progress notification as each source completes.")]
     async fn analyze(
         &self,
+        meta: Meta,
+        peer: rmcp::service::Peer<rmcp::RoleServer>,
         params: rmcp::handler::server::wrapper::Parameters<AnalyzeParams>,
     ) -> std::result::Result<Json<PipelineResponse<AnalyzeResult>>, McpError> {
         use crate::tools::pipelines::{ThinkingStep, MemoryResult, KnowledgeResult, DocsResult, WebResult};
 
         let params = params.0;
+        let min_score = self.min_relevance("min_score", params.min_score)?;
+        if params.think {
+            require_positive("think_steps", params.think_steps as usize)?;
+        }
+        require_positive("max_per_source", params.max_per_source)?;
+        require_unit_range("dedup_threshold", params.dedup_threshold)?;
+        let weights = &params.source_weights;
+        for (field, weight) in [
+            ("source_weights.memory", weights.memory),
+            ("source_weights.knowledge", weights.knowledge),
+            ("source_weights.docs", weights.docs),
+            ("source_weights.web", weights.web),
+            ("source_weights.microsoft", weights.microsoft),
+        ] {
+            require_unit_range(field, weight)?;
+        }
         let start = std::time::Instant::now();
         let mut warnings: Vec<String> = Vec::new();
 
@@ -2919,16 +4144,35 @@ impl IntelligenceServer {
         let mut knowledge_results = Vec::new();
         let mut docs_results = Vec::new();
         let mut web_results = Vec::new();
+        let mut web_duplicates_removed = 0;
         let mut sources_searched = Vec::new();
 
+        // Report each provider as it starts and completes so clients can render incrementally
+        let progress = ProgressReporter::new(&meta, peer, Some(params.sources.len() * 2));
+
         for source in &params.sources {
+            let name = match source {
+                AnalyzeSource::Memory => "memory",
+                AnalyzeSource::Knowledge => "knowledge",
+                AnalyzeSource::Docs => "docs",
+                AnalyzeSource::Web => "web",
+                AnalyzeSource::Microsoft => "microsoft",
+            };
+            progress
+                .report(serde_json::json!({
+                    "event": "source_started",
+                    "source": name,
+                }))
+                .await;
+            let docs_before = docs_results.len();
+
             match source {
                 AnalyzeSource::Memory => {
                     sources_searched.push("memory".to_string());
                     let mut rag = self.rag.write().await;
                     if let Ok(results) = rag.search(&params.query, Some(params.max_per_source)).await {
                         memory_results = results.into_iter()
-                            .filter(|r| r.score >= params.min_score)
+                            .filter(|r| r.score >= min_score)
                             .map(|r| MemoryResult {
                                 key: r.chunk.document_id,
                                 content: r.chunk.text,
@@ -2944,11 +4188,11 @@ impl IntelligenceServer {
                 }
                 AnalyzeSource::Knowledge => {
                     sources_searched.push("knowledge".to_string());
-                    if let Ok(results) = self.db.search_entities(&params.query).await {
+                    if let Ok(results) = self.db.search_entities(&self.entity_key(&params.query)).await {
                         knowledge_results = results.into_iter()
                             .take(params.max_per_source)
                             .map(|e| KnowledgeResult {
-                                name: e.name,
+                                name: e.label().to_string(),
                                 entity_type: e.entity_type,
                                 observations: e.observations,
                                 related: Vec::new(),
@@ -2967,14 +4211,17 @@ impl IntelligenceServer {
                                 code_snippets: result.code_snippets,
                                 url: result.url,
                                 provider: "context7".to_string(),
+                                version: result.version,
                             });
                         }
                     }
                 }
                 AnalyzeSource::Web => {
                     sources_searched.push("web".to_string());
+                    // Over-fetch when deduplicating so rewrites don't eat the budget
+                    let fetch = if params.dedup_web { params.max_per_source * 2 } else { params.max_per_source };
                     let tavily = self.tavily.read().await;
-                    if let Ok(results) = tavily.search(&params.query, params.max_per_source).await {
+                    if let Ok(results) = tavily.search(&params.query, fetch).await {
                         web_results = results.into_iter()
                             .map(|r| WebResult {
                                 title: r.title,
@@ -2984,6 +4231,26 @@ impl IntelligenceServer {
                             })
                             .collect();
                     }
+                    drop(tavily);
+
+                    if params.dedup_web && web_results.len() > 1 {
+                        let texts: Vec<String> = web_results
+                            .iter()
+                            .map(|r| format!("{}\n{}", r.title, r.content))
+                            .collect();
+                        let embedded = self.rag.read().await.embed_texts(texts).await;
+                        match embedded {
+                            Ok(embeddings) => {
+                                let scores: Vec<f32> = web_results.iter().map(|r| r.score).collect();
+                                let kept = dedup_by_similarity(&embeddings, &scores, params.dedup_threshold);
+                                web_duplicates_removed = web_results.len() - kept.len();
+                                let mut slots: Vec<Option<WebResult>> = web_results.into_iter().map(Some).collect();
+                                web_results = kept.into_iter().filter_map(|i| slots[i].take()).collect();
+                            }
+                            Err(e) => warnings.push(format!("Web result dedup skipped: {}", e)),
+                        }
+                    }
+                    web_results.truncate(params.max_per_source);
                 }
                 AnalyzeSource::Microsoft => {
                     sources_searched.push("microsoft".to_string());
@@ -2996,20 +4263,39 @@ impl IntelligenceServer {
                                 code_snippets: Vec::new(),
                                 url: r.url,
                                 provider: "mslearn".to_string(),
+                                version: None,
                             });
                         }
                     }
                 }
             }
+
+            // Docs and Microsoft share a list, so count only what this step added
+            let count = match source {
+                AnalyzeSource::Memory => memory_results.len(),
+                AnalyzeSource::Knowledge => knowledge_results.len(),
+                AnalyzeSource::Docs | AnalyzeSource::Microsoft => docs_results.len() - docs_before,
+                AnalyzeSource::Web => web_results.len(),
+            };
+            progress
+                .report(serde_json::json!({
+                    "event": "source_complete",
+                    "source": name,
+                    "results": count,
+                }))
+                .await;
         }
 
-        // Calculate confidence
-        let total_results = memory_results.len() + knowledge_results.len() +
-                           docs_results.len() + web_results.len();
-        let confidence = if total_results == 0 { 0.2 }
-                        else if total_results < 3 { 0.5 }
-                        else if total_results < 10 { 0.7 }
-                        else { 0.9 };
+        // Weigh every result by its source and score
+        let mut evidence: Vec<(AnalyzeSource, f32)> = Vec::new();
+        evidence.extend(memory_results.iter().map(|r| (AnalyzeSource::Memory, r.score)));
+        evidence.extend(knowledge_results.iter().map(|_| (AnalyzeSource::Knowledge, 1.0)));
+        evidence.extend(docs_results.iter().map(|r| {
+            let source = if r.provider == "mslearn" { AnalyzeSource::Microsoft } else { AnalyzeSource::Docs };
+            (source, 1.0)
+        }));
+        evidence.extend(web_results.iter().map(|r| (AnalyzeSource::Web, r.score)));
+        let confidence = analyze_confidence(&evidence, &params.source_weights);
 
         let needs_more_research = confidence < 0.5;
         let suggested_query = if needs_more_research {
@@ -3051,6 +4337,7 @@ impl IntelligenceServer {
             knowledge_results,
             docs_results,
             web_results,
+            web_duplicates_removed,
             summary,
             sources_searched,
             confidence,
@@ -3082,12 +4369,14 @@ impl IntelligenceServer {
         let mut errors = Vec::new();
 
         let mut remembered = Vec::new();
-        let mut entities_created = Vec::new();
-        let mut relations_created = Vec::new();
         let mut observations_added = Vec::new();
 
         // 1. Store memories
         for item in params.remember {
+            if let Err(e) = self.config.memory.check_content_size(&item.content) {
+                errors.push(format!("Memory store failed: {}", e));
+                continue;
+            }
             let key = uuid::Uuid::new_v4().to_string();
             let now = chrono::Utc::now().timestamp();
 
@@ -3114,65 +4403,96 @@ impl IntelligenceServer {
             }
         }
 
-        // 2. Add entities
+        // 2-3. Entities and relations are written in one transaction so a
+        // failure leaves the knowledge graph untouched
+        let mut graph_tx = self.db.transaction();
+        let mut pending_entities = Vec::new();
+        let mut pending_relations = Vec::new();
+
+        // 2. Add entities, remembering their IDs for the relations below
+        let mut entity_ids: std::collections::HashMap<String, whytcard_database::RecordId> =
+            std::collections::HashMap::new();
         for entity in params.entities {
-            let input = CreateEntity::new(&entity.name, &entity.entity_type)
-                .with_observations(entity.observations.clone());
-            match self.db.create_entity(input).await {
-                Ok(_) => entities_created.push(EntityResult {
-                    name: entity.name,
-                    entity_type: entity.entity_type,
-                    created: true,
-                    observations_added: entity.observations.len(),
-                }),
-                Err(e) => errors.push(format!("Entity create failed: {}", e)),
+            let key = self.entity_key(&entity.name);
+            // An existing entity would abort the whole transaction
+            if let Ok(Some(existing)) = self.db.get_entity_by_name(&key).await {
+                if existing.entity_type == entity.entity_type {
+                    errors.push(format!("Entity create failed: {} already exists", entity.name));
+                    continue;
+                }
             }
+            let input = CreateEntity::new(&key, &entity.entity_type)
+                .with_display_name(entity.name.trim())
+                .with_observations(entity.observations.clone());
+            let id = graph_tx.create_entity(input);
+            pending_entities.push(EntityResult {
+                id: id.to_string(),
+                name: entity.name,
+                entity_type: entity.entity_type,
+                created: true,
+                observations_added: entity.observations.len(),
+            });
+            entity_ids.insert(key, id);
         }
 
-        // 3. Add relations (need to get entity IDs first)
+        // 3. Add relations, resolving by name only for entities not created above
         for rel in params.relations {
-            // Get from entity
-            let from_entity = match self.db.get_entity_by_name(&rel.from).await {
-                Ok(Some(e)) => e,
-                _ => {
+            let from_id = match self.resolve_entity_id(&entity_ids, &rel.from).await {
+                Ok(Some(id)) => id,
+                Ok(None) => {
                     errors.push(format!("Entity not found: {}", rel.from));
                     continue;
                 }
-            };
-            let from_id = match from_entity.id {
-                Some(id) => id,
-                None => {
-                    errors.push(format!("Entity has no ID: {}", rel.from));
+                Err(e) => {
+                    errors.push(format!("Entity lookup failed: {}", e));
                     continue;
                 }
             };
-            // Get to entity
-            let to_entity = match self.db.get_entity_by_name(&rel.to).await {
-                Ok(Some(e)) => e,
-                _ => {
+            let to_id = match self.resolve_entity_id(&entity_ids, &rel.to).await {
+                Ok(Some(id)) => id,
+                Ok(None) => {
                     errors.push(format!("Entity not found: {}", rel.to));
                     continue;
                 }
-            };
-            let to_id = match to_entity.id {
-                Some(id) => id,
-                None => {
-                    errors.push(format!("Entity has no ID: {}", rel.to));
+                Err(e) => {
+                    errors.push(format!("Entity lookup failed: {}", e));
                     continue;
                 }
             };
-            let input = CreateRelation::new(from_id, to_id, &rel.relation_type);
-            match self.db.create_relation(input).await {
-                Ok(_) => relations_created.push(RelationResult {
-                    from: rel.from,
-                    to: rel.to,
-                    relation_type: rel.relation_type,
-                    created: true,
-                }),
-                Err(e) => errors.push(format!("Relation create failed: {}", e)),
+            match self.db.find_relation(&from_id, &to_id, &rel.relation_type).await {
+                Ok(Some(_)) => {
+                    pending_relations.push(RelationResult {
+                        from: rel.from,
+                        to: rel.to,
+                        relation_type: rel.relation_type,
+                        created: false,
+                    });
+                    continue;
+                }
+                Ok(None) => {}
+                Err(e) => {
+                    errors.push(format!("Relation lookup failed: {}", e));
+                    continue;
+                }
             }
+            graph_tx.create_relation(CreateRelation::new(from_id, to_id, &rel.relation_type));
+            pending_relations.push(RelationResult {
+                from: rel.from,
+                to: rel.to,
+                relation_type: rel.relation_type,
+                created: true,
+            });
         }
 
+        let (entities_created, relations_created) = match graph_tx.commit().await {
+            Ok(()) => (pending_entities, pending_relations),
+            Err(e) => {
+                errors.push(format!("Knowledge graph write failed, no entity or relation was written: {}", e));
+                // Relations that already existed are still reported
+                (Vec::new(), pending_relations.into_iter().filter(|r| !r.created).collect())
+            }
+        };
+
         // 4. Add observations (one at a time)
         for obs in params.observations {
             let mut added_count = 0;
@@ -3187,6 +4507,7 @@ impl IntelligenceServer {
                 added: added_count,
             });
         }
+        self.schedule_entity_embedding_refresh();
 
         // 5. Save user instructions to DB (persisted for future sessions)
         let mut user_instructions_saved = Vec::new();
@@ -3196,7 +4517,7 @@ impl IntelligenceServer {
             let user_instruction = ui_def.to_user_instruction(&params.user_id);
 
             // Store in DB as a document with special category
-            let key = format!("user_instruction:{}:{}", params.user_id, ui_def.key);
+            let key = user_instruction_key(&params.user_id, &ui_def.key);
             let content = serde_json::json!({
                 "key": ui_def.key,
                 "value": ui_def.value,
@@ -3205,14 +4526,27 @@ impl IntelligenceServer {
                 "user_id": params.user_id,
             });
 
-            let doc = whytcard_database::CreateDocument::new(content.to_string())
-                .with_key(&key)
-                .with_title(format!("User Instruction: {}", ui_def.key))
-                .with_tags(vec!["user_instruction".to_string(), ui_def.category.clone()]);
+            let title = format!("User Instruction: {}", ui_def.key);
+            let tags = vec!["user_instruction".to_string(), ui_def.category.clone()];
 
-            let replaced = self.db.get_document(&key).await.map(|d| d.is_some()).unwrap_or(false);
+            // Keys are unique, so an existing instruction is updated in place
+            let replaced = matches!(self.db.get_document_by_key(&key).await, Ok(Some(_)));
 
-            match self.db.create_document(doc).await {
+            let saved = if replaced {
+                let update = whytcard_database::UpdateDocument::new()
+                    .with_content(content.to_string())
+                    .with_title(title)
+                    .with_tags(tags);
+                self.db.update_document(&key, update).await
+            } else {
+                let doc = whytcard_database::CreateDocument::new(content.to_string())
+                    .with_key(&key)
+                    .with_title(title)
+                    .with_tags(tags);
+                self.db.create_document(doc).await
+            };
+
+            match saved {
                 Ok(_) => {
                     // Also add to CORTEX instructions manager for immediate use
                     self.cortex.add_user_instruction(user_instruction).await;
@@ -3275,6 +4609,9 @@ impl IntelligenceServer {
         use tokio::process::Command;
 
         let params = params.0;
+        for (i, cmd) in params.commands.iter().enumerate() {
+            require_positive(&format!("commands[{i}].timeout_secs"), cmd.timeout_secs as usize)?;
+        }
         let start = std::time::Instant::now();
 
         let mut executions = Vec::new();
@@ -3434,6 +4771,9 @@ impl IntelligenceServer {
         use tokio::process::Command;
 
         let params = params.0;
+        for (i, cmd) in params.custom_commands.iter().enumerate() {
+            require_positive(&format!("custom_commands[{i}].timeout_secs"), cmd.timeout_secs as usize)?;
+        }
         let start = std::time::Instant::now();
 
         // Get commands based on language preset or custom
@@ -3447,9 +4787,11 @@ impl IntelligenceServer {
                 all.into_iter().filter(|c| params.checks.contains(&c.check_type)).collect()
             }
         } else {
-            return Err(McpError::invalid_params(
-                "No commands - specify language or custom_commands", None
-            ));
+            return Err(IntelligenceError::invalid_param(
+                "language",
+                "is required when custom_commands is empty",
+            )
+            .into());
         };
 
         let mut checks = Vec::new();
@@ -3777,6 +5119,7 @@ impl IntelligenceServer {
                 });
             }
         }
+        self.schedule_entity_embedding_refresh();
 
         // 6. Document error fixes
         for fix in &params.error_fixes {
@@ -3844,14 +5187,62 @@ impl IntelligenceServer {
         Ok(Json(PipelineResponse::ok(result, duration_ms)))
     }
 
+    /// Number of connected MCP servers
+    async fn connected_count(&self) -> usize {
+        let status_map = self.mcp_clients.get_status().await;
+        status_map
+            .values()
+            .filter(|s| **s == crate::mcp_client::McpClientStatus::Connected)
+            .count()
+    }
+
+    /// Connection status of one MCP server, with its installed config if any
+    async fn connection_info(&self, name: &str, tool_count: usize) -> crate::tools::pipelines::ServerInfo {
+        let status = self
+            .mcp_clients
+            .get_status()
+            .await
+            .get(name)
+            .map(|status| format!("{:?}", status))
+            .unwrap_or_else(|| "NotConfigured".to_string());
+        let config = self.mcp_config.read().await;
+        let installed = config.get(name);
+        crate::tools::pipelines::ServerInfo {
+            name: name.to_string(),
+            status,
+            tool_count,
+            package: installed.map(|server| server.package.clone()),
+            description: installed.map(|server| server.description.clone()),
+            enabled: installed.is_none_or(|server| server.enabled),
+            auto_connect: installed.is_some_and(|server| server.auto_connect),
+        }
+    }
+
     #[tool(description = "MANAGE: Administrative tool for MCP servers and CORTEX. Install/uninstall servers, get status, manage instructions. Use for system administration tasks.")]
     async fn manage(
         &self,
         params: rmcp::handler::server::wrapper::Parameters<ManageParams>,
     ) -> std::result::Result<Json<PipelineResponse<ManageResult>>, McpError> {
         use crate::tools::pipelines::{ServerInfo, ToolInfoItem, CortexStatsInfo, InstructionInfoItem};
+        use crate::tools::SetEnvParam;
+        use rmcp::handler::server::wrapper::Parameters;
+
+        /// Tools of `server` as manage result items
+        fn server_tools(server: &str, names: Vec<String>) -> Vec<ToolInfoItem> {
+            names
+                .into_iter()
+                .map(|name| ToolInfoItem {
+                    name,
+                    server: server.to_string(),
+                    description: None,
+                })
+                .collect()
+        }
 
         let params = params.0;
+        if let Some(days) = params.retention_days {
+            require_non_negative("retention_days", days as f64)?;
+        }
         let start = std::time::Instant::now();
 
         let result = match params.action {
@@ -3876,14 +5267,8 @@ impl IntelligenceServer {
                     success: true,
                     message: format!("{} servers connected", connected_count),
                     servers,
-                    tools: Vec::new(),
-                    cortex_stats: None,
-                    cleaned_count: None,
-                    tool_result: None,
-                    instructions: Vec::new(),
-                    instruction_content: None,
                     connected_count,
-                    error: None,
+                    ..Default::default()
                 }
             }
             ManageAction::ListAvailable => {
@@ -3915,14 +5300,8 @@ impl IntelligenceServer {
                     success: true,
                     message: format!("{} predefined servers", count),
                     servers,
-                    tools: Vec::new(),
-                    cortex_stats: None,
-                    cleaned_count: None,
-                    tool_result: None,
-                    instructions: Vec::new(),
-                    instruction_content: None,
                     connected_count,
-                    error: None,
+                    ..Default::default()
                 }
             }
             ManageAction::ListInstalled => {
@@ -3946,14 +5325,8 @@ impl IntelligenceServer {
                     success: true,
                     message: format!("{} servers installed", count),
                     servers,
-                    tools: Vec::new(),
-                    cortex_stats: None,
-                    cleaned_count: None,
-                    tool_result: None,
-                    instructions: Vec::new(),
-                    instruction_content: None,
                     connected_count,
-                    error: None,
+                    ..Default::default()
                 }
             }
             ManageAction::ListTools => {
@@ -3976,15 +5349,9 @@ impl IntelligenceServer {
                     action: "list_tools".to_string(),
                     success: true,
                     message: format!("{} tools available", count),
-                    servers: Vec::new(),
                     tools,
-                    cortex_stats: None,
-                    cleaned_count: None,
-                    tool_result: None,
-                    instructions: Vec::new(),
-                    instruction_content: None,
                     connected_count,
-                    error: None,
+                    ..Default::default()
                 }
             }
             ManageAction::CortexStats => {
@@ -4007,53 +5374,39 @@ impl IntelligenceServer {
                     action: "cortex_stats".to_string(),
                     success: true,
                     message: format!("CORTEX: {} facts, {} events, {} rules", semantic_facts, episodic_events, procedural_rules),
-                    servers: Vec::new(),
-                    tools: Vec::new(),
                     cortex_stats: Some(CortexStatsInfo {
                         semantic_facts,
                         episodic_events,
                         procedural_rules,
                         status: "running".to_string(),
                     }),
-                    cleaned_count: None,
-                    tool_result: None,
-                    instructions: Vec::new(),
-                    instruction_content: None,
                     connected_count,
-                    error: None,
+                    ..Default::default()
                 }
             }
             ManageAction::CortexCleanup => {
                 let status_map = self.mcp_clients.get_status().await;
                 let connected_count = status_map.values().filter(|s| **s == crate::mcp_client::McpClientStatus::Connected).count();
-                match self.cortex.cleanup(params.retention_days).await {
-                    Ok(cleaned) => ManageResult {
+                match self.apply_retention(params.retention_days, params.compact).await {
+                    Ok(cleanup) => ManageResult {
                         action: "cortex_cleanup".to_string(),
                         success: true,
-                        message: format!("Cleaned {} old records", cleaned),
-                        servers: Vec::new(),
-                        tools: Vec::new(),
-                        cortex_stats: None,
-                        cleaned_count: Some(cleaned),
-                        tool_result: None,
-                        instructions: Vec::new(),
-                        instruction_content: None,
+                        message: format!(
+                            "Cleaned {} old records, purged {} deleted memories, reclaimed {} bytes",
+                            cleanup.cleaned_count, cleanup.purged_memories, cleanup.bytes_reclaimed
+                        ),
+                        cleaned_count: Some(cleanup.cleaned_count),
+                        purged_count: Some(cleanup.purged_memories),
                         connected_count,
-                        error: None,
+                        ..Default::default()
                     },
                     Err(e) => ManageResult {
                         action: "cortex_cleanup".to_string(),
                         success: false,
                         message: "Cleanup failed".to_string(),
-                        servers: Vec::new(),
-                        tools: Vec::new(),
-                        cortex_stats: None,
-                        cleaned_count: None,
-                        tool_result: None,
-                        instructions: Vec::new(),
-                        instruction_content: None,
                         connected_count,
                         error: Some(e.to_string()),
+                        ..Default::default()
                     }
                 }
             }
@@ -4064,6 +5417,7 @@ impl IntelligenceServer {
                         name: i.name.clone(),
                         description: Some(i.description.clone()),
                         apply_to: Some(i.apply_to.clone()),
+                        priority: i.priority,
                     }
                 }).collect();
                 let count = instructions.len();
@@ -4073,15 +5427,9 @@ impl IntelligenceServer {
                     action: "instructions_list".to_string(),
                     success: true,
                     message: format!("{} instructions loaded", count),
-                    servers: Vec::new(),
-                    tools: Vec::new(),
-                    cortex_stats: None,
-                    cleaned_count: None,
-                    tool_result: None,
                     instructions,
-                    instruction_content: None,
                     connected_count,
-                    error: None,
+                    ..Default::default()
                 }
             }
             ManageAction::InstructionsReload => {
@@ -4094,49 +5442,243 @@ impl IntelligenceServer {
                             action: "instructions_reload".to_string(),
                             success: true,
                             message: format!("Reloaded {} instructions", count),
-                            servers: Vec::new(),
-                            tools: Vec::new(),
-                            cortex_stats: None,
-                            cleaned_count: None,
-                            tool_result: None,
-                            instructions: Vec::new(),
-                            instruction_content: None,
                             connected_count,
-                            error: None,
+                            ..Default::default()
                         }
                     }
                     Err(e) => ManageResult {
                         action: "instructions_reload".to_string(),
                         success: false,
                         message: "Reload failed".to_string(),
-                        servers: Vec::new(),
-                        tools: Vec::new(),
-                        cortex_stats: None,
-                        cleaned_count: None,
-                        tool_result: None,
-                        instructions: Vec::new(),
-                        instruction_content: None,
                         connected_count,
                         error: Some(e.to_string()),
+                        ..Default::default()
                     }
                 }
             }
-            _ => {
+            ManageAction::OptimizeIndex => {
                 let status_map = self.mcp_clients.get_status().await;
                 let connected_count = status_map.values().filter(|s| **s == crate::mcp_client::McpClientStatus::Connected).count();
+                let rebuild_start = std::time::Instant::now();
+                match self.rag.read().await.optimize_index().await {
+                    Ok(indexed) => {
+                        tracing::info!(
+                            "Rebuilt vector index over {} chunks in {} ms",
+                            indexed,
+                            rebuild_start.elapsed().as_millis()
+                        );
+                        ManageResult {
+                            action: "optimize_index".to_string(),
+                            success: true,
+                            message: format!("Rebuilt vector index over {} chunks", indexed),
+                            indexed_count: Some(indexed),
+                            connected_count,
+                            ..Default::default()
+                        }
+                    }
+                    Err(e) => ManageResult {
+                        action: "optimize_index".to_string(),
+                        success: false,
+                        message: "Index rebuild failed".to_string(),
+                        connected_count,
+                        error: Some(e.to_string()),
+                        ..Default::default()
+                    },
+                }
+            }
+            ManageAction::Install => {
+                let install = params.install.ok_or_else(|| {
+                    IntelligenceError::invalid_param("install", "is required for action 'install'")
+                })?;
+                require_one_of("install.package_type", &install.package_type, &["npm", "pip"])?;
+                let installed = self
+                    .mcp_install(Parameters(McpInstallParams {
+                        name: install.name,
+                        package: install.package,
+                        package_type: install.package_type,
+                        description: install.description,
+                        env: install.env,
+                        auto_connect: install.auto_connect,
+                        connect_now: install.connect_now,
+                    }))
+                    .await?
+                    .0;
+                let message = match (installed.installed, installed.connected) {
+                    (true, true) => format!("Installed and connected {} ({} tools)", installed.name, installed.tools.len()),
+                    (true, false) => format!("Installed {}", installed.name),
+                    (false, _) => format!("Failed to install {}", installed.name),
+                };
                 ManageResult {
-                    action: format!("{:?}", params.action).to_lowercase(),
-                    success: false,
-                    message: "Action not yet implemented in pipeline".to_string(),
-                    servers: Vec::new(),
-                    tools: Vec::new(),
-                    cortex_stats: None,
-                    cleaned_count: None,
-                    tool_result: None,
-                    instructions: Vec::new(),
-                    instruction_content: None,
-                    connected_count,
-                    error: Some("Use atomic tools for this action".to_string()),
+                    tools: server_tools(&installed.name, installed.tools),
+                    error: installed.error,
+                    ..ManageResult::new("install", installed.installed, message, self.connected_count().await)
+                }
+            }
+            ManageAction::Uninstall => {
+                let server = params.server.ok_or_else(|| {
+                    IntelligenceError::invalid_param("server", "is required for action 'uninstall'")
+                })?;
+                let uninstalled = self
+                    .mcp_uninstall(Parameters(McpUninstallParams {
+                        name: server,
+                        disconnect: true,
+                    }))
+                    .await?
+                    .0;
+                let message = if uninstalled.uninstalled {
+                    format!("Uninstalled {}", uninstalled.name)
+                } else {
+                    format!("Failed to uninstall {}", uninstalled.name)
+                };
+                ManageResult {
+                    error: uninstalled.error,
+                    ..ManageResult::new("uninstall", uninstalled.uninstalled, message, self.connected_count().await)
+                }
+            }
+            ManageAction::Configure => {
+                let configure = params.configure.ok_or_else(|| {
+                    IntelligenceError::invalid_param("configure", "is required for action 'configure'")
+                })?;
+                let configured = self
+                    .mcp_configure(Parameters(McpConfigureParams {
+                        name: configure.name,
+                        set_env: configure.set_env.map(|(key, value)| SetEnvParam { key, value }),
+                        remove_env: configure.remove_env,
+                        enable: configure.enable,
+                        auto_connect: configure.auto_connect,
+                        description: configure.description,
+                    }))
+                    .await?
+                    .0;
+                let message = if configured.configured {
+                    format!("Configured {}", configured.name)
+                } else {
+                    format!("Failed to configure {}", configured.name)
+                };
+                let servers = configured
+                    .current_config
+                    .into_iter()
+                    .map(|server| ServerInfo {
+                        name: server.name,
+                        status: if server.enabled { "installed" } else { "disabled" }.to_string(),
+                        tool_count: 0,
+                        package: Some(server.package),
+                        description: Some(server.description),
+                        enabled: server.enabled,
+                        auto_connect: server.auto_connect,
+                    })
+                    .collect();
+                ManageResult {
+                    servers,
+                    error: configured.error,
+                    ..ManageResult::new("configure", configured.configured, message, self.connected_count().await)
+                }
+            }
+            ManageAction::Connect => {
+                let server = params.server.ok_or_else(|| {
+                    IntelligenceError::invalid_param("server", "is required for action 'connect'")
+                })?;
+                let connected = self.mcp_clients.connect(&server).await;
+                let tools: Vec<ToolInfoItem> = self
+                    .mcp_clients
+                    .list_server_tools(&server)
+                    .await
+                    .into_iter()
+                    .map(|tool| ToolInfoItem {
+                        name: tool.name,
+                        server: server.clone(),
+                        description: tool.description,
+                    })
+                    .collect();
+                let message = match &connected {
+                    Ok(()) => format!("Connected to {} ({} tools)", server, tools.len()),
+                    Err(_) => format!("Failed to connect to {}", server),
+                };
+                ManageResult {
+                    servers: vec![self.connection_info(&server, tools.len()).await],
+                    tools,
+                    error: connected.as_ref().err().map(|e| format!("Connection failed: {}", e)),
+                    ..ManageResult::new("connect", connected.is_ok(), message, self.connected_count().await)
+                }
+            }
+            ManageAction::Disconnect => {
+                let server = params.server.ok_or_else(|| {
+                    IntelligenceError::invalid_param("server", "is required for action 'disconnect'")
+                })?;
+                let disconnected = self.mcp_clients.disconnect(&server).await;
+                let message = match &disconnected {
+                    Ok(()) => format!("Disconnected from {}", server),
+                    Err(_) => format!("Failed to disconnect from {}", server),
+                };
+                ManageResult {
+                    servers: vec![self.connection_info(&server, 0).await],
+                    error: disconnected.as_ref().err().map(|e| format!("Disconnection failed: {}", e)),
+                    ..ManageResult::new("disconnect", disconnected.is_ok(), message, self.connected_count().await)
+                }
+            }
+            ManageAction::CallTool => {
+                let call = params.tool_call.ok_or_else(|| {
+                    IntelligenceError::invalid_param("tool_call", "is required for action 'call_tool'")
+                })?;
+                let called = self
+                    .external_mcp_call(Parameters(ExternalMcpCallParams {
+                        server: call.server,
+                        tool: call.tool,
+                        arguments: call.arguments,
+                    }))
+                    .await?
+                    .0;
+                let message = if called.success {
+                    format!("Called {} on {}", called.tool, called.server)
+                } else {
+                    format!("Call to {} on {} failed", called.tool, called.server)
+                };
+                let tool_result = called.data.or_else(|| {
+                    (!called.content.is_empty()).then(|| serde_json::Value::String(called.content))
+                });
+                ManageResult {
+                    tool_result,
+                    error: called.error,
+                    ..ManageResult::new("call_tool", called.success, message, self.connected_count().await)
+                }
+            }
+            ManageAction::Instructions => {
+                let instructions = params.instructions.ok_or_else(|| {
+                    IntelligenceError::invalid_param("instructions", "is required for action 'instructions'")
+                })?;
+                let action = match instructions.action {
+                    crate::tools::pipelines::InstructionsAction::List => InstructionsAction::List,
+                    crate::tools::pipelines::InstructionsAction::Reload => InstructionsAction::Reload,
+                    crate::tools::pipelines::InstructionsAction::Get => InstructionsAction::Get,
+                    crate::tools::pipelines::InstructionsAction::ForFile => InstructionsAction::ForFile,
+                };
+                let found = self
+                    .cortex_instructions(Parameters(CortexInstructionsParams {
+                        action,
+                        name: instructions.name,
+                        file_path: instructions.file_path,
+                    }))
+                    .await?
+                    .0;
+                let instructions = found
+                    .instructions
+                    .into_iter()
+                    .map(|i| InstructionInfoItem {
+                        name: i.name,
+                        description: i.description,
+                        apply_to: i.apply_to,
+                        priority: i.priority,
+                    })
+                    .collect();
+                ManageResult {
+                    instructions,
+                    instruction_content: found.content,
+                    ..ManageResult::new(
+                        &format!("instructions_{}", found.action),
+                        found.success,
+                        found.message,
+                        self.connected_count().await,
+                    )
                 }
             }
         };
@@ -4147,8 +5689,39 @@ impl IntelligenceServer {
 }
 
 // Implement the server handler for MCP
-#[tool_handler]
 impl rmcp::ServerHandler for IntelligenceServer {
+    /// Dispatch through the tool router, recording payload sizes per tool
+    async fn call_tool(
+        &self,
+        request: CallToolRequestParam,
+        context: RequestContext<RoleServer>,
+    ) -> std::result::Result<CallToolResult, McpError> {
+        let tool = request.name.to_string();
+        let request_bytes = json_size(&request.arguments);
+        let arguments = request.arguments.clone();
+
+        let result = self
+            .tool_router
+            .call(ToolCallContext::new(self, request, context))
+            .await
+            .map_err(|error| self.explain_invalid_params(&tool, arguments, error));
+
+        let response_bytes = match &result {
+            Ok(result) => json_size(result),
+            Err(error) => json_size(error),
+        };
+        self.metrics.record(&tool, request_bytes, response_bytes);
+        result
+    }
+
+    async fn list_tools(
+        &self,
+        _request: Option<PaginatedRequestParam>,
+        _context: RequestContext<RoleServer>,
+    ) -> std::result::Result<ListToolsResult, McpError> {
+        Ok(ListToolsResult::with_all_items(self.tool_router.list_all()))
+    }
+
     fn get_info(&self) -> ServerInfo {
         ServerInfo {
             protocol_version: Default::default(),
@@ -4159,30 +5732,131 @@ impl rmcp::ServerHandler for IntelligenceServer {
                 ..Default::default()
             },
             instructions: Some(
-                "WhytCard Intelligence - A cognitive memory and knowledge system powered by CORTEX. \
-                 Use cortex_* tools for intelligent query processing and learning. \
-                 Use memory_* tools to store and search information. \
-                 Use knowledge_* tools to manage entities and relations."
-                    .to_string(),
+                self.config
+                    .instructions
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_INSTRUCTIONS.to_string()),
             ),
         }
     }
 }
 
 impl IntelligenceServer {
+    /// Load the user instructions saved by `prepare` into CORTEX
+    async fn load_user_instructions(db: &Database, cortex: &CortexEngine) {
+        let docs = match db.list_documents_by_key_prefix("user_instruction:").await {
+            Ok(docs) => docs,
+            Err(e) => {
+                tracing::warn!("Failed to load user instructions: {}", e);
+                return;
+            }
+        };
+
+        let instructions: Vec<_> = docs
+            .iter()
+            .filter_map(|doc| {
+                let content: serde_json::Value = serde_json::from_str(&doc.content).ok()?;
+                let user_id = content.get("user_id")?.as_str()?.to_string();
+                let def: UserInstructionDef = serde_json::from_value(content).ok()?;
+                Some(def.to_user_instruction(&user_id))
+            })
+            .collect();
+        if !instructions.is_empty() {
+            tracing::info!("Loaded {} user instructions", instructions.len());
+            cortex.load_user_instructions(instructions).await;
+        }
+    }
+
+    /// Validate subsystems before accepting clients, on every transport
+    ///
+    /// Fails if a critical subsystem is down; non-critical failures are logged.
+    pub async fn validate_startup(&self) -> crate::Result<()> {
+        let health = self.check_health().await;
+
+        for subsystem in health.subsystems.iter().filter(|s| !s.healthy && !s.critical) {
+            tracing::warn!("{} unavailable: {}", subsystem.name, subsystem.detail);
+        }
+
+        let failures: Vec<String> = health
+            .critical_failures()
+            .map(|s| format!("{}: {}", s.name, s.detail))
+            .collect();
+        if !failures.is_empty() {
+            return Err(IntelligenceError::config(format!(
+                "Startup validation failed: {}",
+                failures.join("; ")
+            )));
+        }
+
+        tracing::info!("Startup validation passed ({})", health.status);
+        Ok(())
+    }
+
+    /// Name the offending fields when a tool's arguments fail to deserialize
+    ///
+    /// rmcp reports only the serde message; the arguments are re-checked
+    /// against the tool's schema. Other errors pass through unchanged.
+    fn explain_invalid_params(&self, tool: &str, arguments: Option<JsonObject>, error: McpError) -> McpError {
+        if error.code != ErrorCode::INVALID_PARAMS {
+            return error;
+        }
+        let Some(schema) = self
+            .tool_router
+            .list_all()
+            .into_iter()
+            .find(|t| t.name == tool)
+            .map(|t| t.input_schema)
+        else {
+            return error;
+        };
+
+        let arguments = serde_json::Value::Object(arguments.unwrap_or_default());
+        let issues = crate::validation::check_arguments(&schema, &arguments);
+        if issues.is_empty() {
+            return error;
+        }
+
+        let details: Vec<String> = issues
+            .iter()
+            .map(|issue| format!("`{}` {}", issue.field, issue.message))
+            .collect();
+        McpError::invalid_params(
+            format!("Invalid parameters for {}: {}", tool, details.join("; ")),
+            Some(serde_json::json!({ "tool": tool, "errors": issues })),
+        )
+    }
+
+    /// Handle for one more client of this server
+    ///
+    /// Storage, RAG, memory stores, metrics and the session registry are
+    /// shared; the CORTEX context (session id, query history) is the
+    /// client's own.
+    pub fn for_client(&self) -> Self {
+        Self {
+            cortex: Arc::new(self.cortex.for_client()),
+            ..self.clone()
+        }
+    }
+
     /// Run the server with stdio transport
     pub async fn run_stdio(self) -> crate::Result<()> {
+        self.validate_startup().await?;
+        self.spawn_auto_cleanup();
+        self.schedule_entity_embedding_refresh();
+
         tracing::info!("Starting Intelligence MCP server on stdio");
 
+        let cortex = Arc::clone(&self.cortex);
         let service = self
             .serve(rmcp::transport::stdio())
             .await
             .map_err(|e| IntelligenceError::config(format!("Failed to start server: {}", e)))?;
 
-        service
-            .waiting()
-            .await
-            .map_err(|e| IntelligenceError::config(format!("Server error: {}", e)))?;
+        let waiting = service.waiting().await;
+
+        // Nobody is left to read the results of calls still running
+        cortex.abort_current();
+        waiting.map_err(|e| IntelligenceError::config(format!("Server error: {}", e)))?;
 
         Ok(())
     }
@@ -4206,20 +5880,40 @@ impl IntelligenceServer {
     /// # Clients connect via SSE to http://localhost:3000/sse
     /// ```
     pub async fn run_sse(self, port: u16) -> crate::Result<()> {
-        use rmcp::transport::sse_server::{SseServer, SseServerConfig};
         use std::net::SocketAddr;
+
+        self.validate_startup().await?;
+        self.spawn_auto_cleanup();
+        self.schedule_entity_embedding_refresh();
+
+        let addr = SocketAddr::from(([0, 0, 0, 0], port));
+        let listener = tokio::net::TcpListener::bind(addr)
+            .await
+            .map_err(|e| IntelligenceError::config(format!("Failed to start SSE server: {}", e)))?;
+
+        self.serve_sse(listener).await
+    }
+
+    /// Serve SSE clients on an already bound listener
+    ///
+    /// Each client gets its own CORTEX context (see [`Self::for_client`]).
+    async fn serve_sse(self, listener: tokio::net::TcpListener) -> crate::Result<()> {
+        use rmcp::transport::sse_server::{SseServer, SseServerConfig};
         use tokio_util::sync::CancellationToken;
         use futures::StreamExt;
 
-        let addr = SocketAddr::from(([0, 0, 0, 0], port));
+        let addr = listener.local_addr()?;
 
         tracing::info!(
             "Starting Intelligence MCP server on SSE transport at http://{}",
             addr
         );
         tracing::info!("Clients can connect via:");
-        tracing::info!("  - SSE: http://{}/sse", addr);
-        tracing::info!("  - Messages: POST http://{}/message", addr);
+        tracing::info!("  - SSE: http://{}{}", addr, SSE_PATH);
+        tracing::info!("  - Messages: POST http://{}{}", addr, MESSAGE_PATH);
+        if self.config.max_clients > 0 {
+            tracing::info!("Accepting at most {} concurrent clients", self.config.max_clients);
+        }
 
         // Create cancellation token
         let ct = CancellationToken::new();
@@ -4227,35 +5921,45 @@ impl IntelligenceServer {
         // Create SSE server config
         let config = SseServerConfig {
             bind: addr,
-            sse_path: "/sse".to_string(),
-            post_path: "/message".to_string(),
+            sse_path: SSE_PATH.to_string(),
+            post_path: MESSAGE_PATH.to_string(),
             ct: ct.clone(),
             sse_keep_alive: Some(std::time::Duration::from_secs(30)),
         };
 
-        // Create SSE server
-        let mut sse_server = SseServer::serve_with_config(config)
-            .await
-            .map_err(|e| IntelligenceError::config(format!("Failed to start SSE server: {}", e)))?;
-
-        // Clone self for session handling since we need to handle multiple connections
-        let session_manager = std::sync::Arc::new(crate::session::MultiSessionManager::new());
+        // Create SSE server, turning clients away before the stream opens
+        // once the limit is reached
+        let (mut sse_server, router) = SseServer::new(config);
+        let router = router.layer(axum::middleware::from_fn_with_state(
+            self.sessions.clone(),
+            reject_when_full,
+        ));
+        let shutdown = ct.clone();
+        tokio::spawn(async move {
+            let server = axum::serve(listener, router)
+                .with_graceful_shutdown(async move { shutdown.cancelled().await });
+            if let Err(e) = server.await {
+                tracing::error!("SSE server error: {}", e);
+            }
+        });
 
         tracing::info!("SSE server started, waiting for connections...");
 
         // Accept connections using Stream interface
         while let Some(transport) = sse_server.next().await {
-            let server_clone = self.clone();
-            let sessions = session_manager.clone();
+            let server_clone = self.for_client();
+            let sessions = self.sessions.clone();
 
             // Spawn a task for each client connection
             tokio::spawn(async move {
                 // Create session for this client
-                let client_info = crate::session::ClientInfo::new("sse-client");
+                // A burst can pass the 503 check together; the session limit
+                // is authoritative, so late arrivals are dropped here
+                let client_info = ClientInfo::new("sse-client");
                 let session_id = match sessions.create_session(client_info).await {
                     Ok(id) => id,
                     Err(e) => {
-                        tracing::error!("Failed to create session: {}", e);
+                        tracing::warn!("Dropping SSE client: {}", e);
                         return;
                     }
                 };
@@ -4263,6 +5967,7 @@ impl IntelligenceServer {
                 tracing::info!(session_id = %session_id, "New SSE client connected");
 
                 // Serve this client
+                let cortex = Arc::clone(&server_clone.cortex);
                 match server_clone.serve(transport).await {
                     Ok(service) => {
                         if let Err(e) = service.waiting().await {
@@ -4282,7 +5987,10 @@ impl IntelligenceServer {
                     }
                 }
 
-                // Cleanup session
+                // Stop this client's CORTEX calls still running, then cleanup session
+                if cortex.abort_current() {
+                    tracing::info!(session_id = %session_id, "Aborted CORTEX processing of disconnected client");
+                }
                 sessions.end_session(&session_id).await;
                 tracing::info!(session_id = %session_id, "SSE client disconnected");
             });
@@ -4293,6 +6001,31 @@ impl IntelligenceServer {
     }
 }
 
+/// Path clients open the SSE stream on
+const SSE_PATH: &str = "/sse";
+
+/// Path clients POST messages to
+const MESSAGE_PATH: &str = "/message";
+
+/// Reject new SSE streams with 503 while every client slot is taken
+async fn reject_when_full(
+    axum::extract::State(sessions): axum::extract::State<Arc<MultiSessionManager>>,
+    request: axum::extract::Request,
+    next: axum::middleware::Next,
+) -> axum::response::Response {
+    use axum::response::IntoResponse;
+
+    if request.uri().path() == SSE_PATH && sessions.is_full().await {
+        tracing::warn!(max_clients = sessions.max_sessions(), "Rejecting SSE client: limit reached");
+        return (
+            axum::http::StatusCode::SERVICE_UNAVAILABLE,
+            format!("Maximum of {} concurrent clients reached", sessions.max_sessions()),
+        )
+            .into_response();
+    }
+    next.run(request).await
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -4306,6 +6039,391 @@ mod tests {
         assert!(server.is_ok());
     }
 
+    #[tokio::test]
+    async fn test_startup_validation_passes() {
+        let temp = TempDir::new().unwrap();
+        let server = IntelligenceServer::for_testing(temp.path()).await.unwrap();
+
+        let health = server.check_health().await;
+        assert!(health.subsystems.iter().any(|s| s.name == "database" && s.healthy));
+        assert!(health.subsystems.iter().any(|s| s.name == "rag" && s.healthy));
+    }
+
+    #[tokio::test]
+    async fn test_health_reports_tool_payloads() {
+        let temp = TempDir::new().unwrap();
+        let server = IntelligenceServer::for_testing(temp.path()).await.unwrap();
+        assert!(server.check_health().await.tool_payloads.is_empty());
+
+        server.metrics.record("memory_get", 40, 120);
+        server.metrics.record("knowledge_read_graph", 10, 90_000);
+
+        let payloads = server.check_health().await.tool_payloads;
+        assert_eq!(payloads.len(), 2);
+        assert_eq!(payloads[0].tool, "knowledge_read_graph");
+        assert_eq!(payloads[0].response.p95_bytes, 90_000);
+        assert_eq!(payloads[1].request.total_bytes, 40);
+    }
+
+    #[tokio::test]
+    async fn test_sse_rejects_clients_over_limit() {
+        let temp = TempDir::new().unwrap();
+        let config = IntelligenceConfig::default().with_max_clients(1);
+        let server = IntelligenceServer::for_testing_with_config(temp.path(), config)
+            .await
+            .unwrap();
+
+        let router = axum::Router::new()
+            .route(SSE_PATH, axum::routing::get(|| async { "stream" }))
+            .route(MESSAGE_PATH, axum::routing::post(|| async { "accepted" }))
+            .layer(axum::middleware::from_fn_with_state(
+                server.sessions.clone(),
+                reject_when_full,
+            ));
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, router).await.unwrap() });
+
+        let sse_url = format!("{}{}", base, SSE_PATH);
+        assert_eq!(reqwest::get(&sse_url).await.unwrap().status(), 200);
+
+        server.sessions.create_session(ClientInfo::new("first")).await.unwrap();
+        assert_eq!(reqwest::get(&sse_url).await.unwrap().status(), 503);
+
+        // Connected clients keep posting messages
+        let posted = reqwest::Client::new()
+            .post(format!("{}{}", base, MESSAGE_PATH))
+            .send()
+            .await
+            .unwrap();
+        assert_eq!(posted.status(), 200);
+
+        let health = server.check_health().await;
+        assert_eq!(health.active_clients, 1);
+        assert_eq!(health.max_clients, 1);
+    }
+
+    /// Minimal MCP client speaking the SSE transport over raw HTTP
+    struct SseTestClient {
+        http: reqwest::Client,
+        stream: reqwest::Response,
+        buffer: String,
+        post_url: String,
+        next_id: u64,
+    }
+
+    impl SseTestClient {
+        async fn connect(base: &str) -> Self {
+            let http = reqwest::Client::new();
+            let stream = http.get(format!("{}{}", base, SSE_PATH)).send().await.unwrap();
+            let mut client = Self {
+                http,
+                stream,
+                buffer: String::new(),
+                post_url: String::new(),
+                next_id: 1,
+            };
+
+            let (event, endpoint) = client.next_event().await;
+            assert_eq!(event, "endpoint");
+            client.post_url = format!("{}{}", base, endpoint);
+
+            client
+                .request(
+                    "initialize",
+                    serde_json::json!({
+                        "protocolVersion": "2024-11-05",
+                        "capabilities": {},
+                        "clientInfo": { "name": "sse-test", "version": "0.0.0" },
+                    }),
+                )
+                .await;
+            client
+                .post(serde_json::json!({ "jsonrpc": "2.0", "method": "notifications/initialized" }))
+                .await;
+            client
+        }
+
+        /// Next SSE event as (event name, data)
+        async fn next_event(&mut self) -> (String, String) {
+            loop {
+                if let Some(end) = self.buffer.find("\n\n") {
+                    let raw: String = self.buffer.drain(..end + 2).collect();
+                    let mut event = "message".to_string();
+                    let mut data = Vec::new();
+                    for line in raw.lines() {
+                        if let Some(name) = line.strip_prefix("event:") {
+                            event = name.trim().to_string();
+                        } else if let Some(value) = line.strip_prefix("data:") {
+                            data.push(value.trim_start().to_string());
+                        }
+                    }
+                    if !data.is_empty() {
+                        return (event, data.join("\n"));
+                    }
+                    continue;
+                }
+                let chunk = self.stream.chunk().await.unwrap().expect("SSE stream closed");
+                self.buffer.push_str(&String::from_utf8_lossy(&chunk).replace("\r\n", "\n"));
+            }
+        }
+
+        async fn post(&self, message: serde_json::Value) {
+            let response = self.http.post(&self.post_url).json(&message).send().await.unwrap();
+            assert!(response.status().is_success(), "POST failed: {}", response.status());
+        }
+
+        /// Send a request and wait for its response message
+        async fn send(&mut self, method: &str, params: serde_json::Value) -> serde_json::Value {
+            let id = self.next_id;
+            self.next_id += 1;
+            self.post(serde_json::json!({ "jsonrpc": "2.0", "id": id, "method": method, "params": params }))
+                .await;
+
+            loop {
+                let (event, data) = self.next_event().await;
+                if event != "message" {
+                    continue;
+                }
+                let message: serde_json::Value = serde_json::from_str(&data).unwrap();
+                if message["id"] == id {
+                    return message;
+                }
+            }
+        }
+
+        async fn request(&mut self, method: &str, params: serde_json::Value) -> serde_json::Value {
+            let message = self.send(method, params).await;
+            assert!(message.get("error").is_none(), "{method} failed: {message}");
+            message["result"].clone()
+        }
+
+        async fn call_tool(&mut self, name: &str, arguments: serde_json::Value) -> serde_json::Value {
+            let result = self
+                .request("tools/call", serde_json::json!({ "name": name, "arguments": arguments }))
+                .await;
+            assert_ne!(result["isError"], true, "{name} failed: {result}");
+            result["structuredContent"].clone()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_sse_clients_have_isolated_sessions() {
+        let temp = TempDir::new().unwrap();
+        let server = IntelligenceServer::for_testing(temp.path()).await.unwrap();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(server.serve_sse(listener));
+
+        let mut alice = SseTestClient::connect(&base).await;
+        let mut bob = SseTestClient::connect(&base).await;
+
+        let process = |query: &str| serde_json::json!({ "query": query, "session_id": "mine" });
+        let from_alice = alice.call_tool("cortex_process", process("explain rust lifetimes")).await;
+        let from_bob = bob.call_tool("cortex_process", process("review python imports")).await;
+        let alice_session = from_alice["session_id"].as_str().unwrap();
+        let bob_session = from_bob["session_id"].as_str().unwrap();
+        assert_ne!(alice_session, bob_session);
+
+        // Each client only sees its own queries
+        let alice_stats = alice.call_tool("cortex_stats", serde_json::json!({})).await;
+        let bob_stats = bob.call_tool("cortex_stats", serde_json::json!({})).await;
+        assert_eq!(alice_stats["recent_queries"], serde_json::json!(["explain rust lifetimes"]));
+        assert_eq!(bob_stats["recent_queries"], serde_json::json!(["review python imports"]));
+
+        // Memory stays shared between clients
+        alice
+            .call_tool(
+                "memory_store",
+                serde_json::json!({ "key": "shared-note", "content": "visible to all", "index": false }),
+            )
+            .await;
+        let note = bob.call_tool("memory_get", serde_json::json!({ "key": "shared-note" })).await;
+        assert_eq!(note["content"], "visible to all");
+    }
+
+    #[tokio::test]
+    async fn test_malformed_params_name_the_field() {
+        let temp = TempDir::new().unwrap();
+        let server = IntelligenceServer::for_testing(temp.path()).await.unwrap();
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let base = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(server.serve_sse(listener));
+        let mut client = SseTestClient::connect(&base).await;
+
+        let call = |name: &str, arguments: serde_json::Value| {
+            serde_json::json!({ "name": name, "arguments": arguments })
+        };
+
+        // Deserialization failures list every offending field
+        let response = client
+            .send("tools/call", call("memory_search", serde_json::json!({ "limit": "ten" })))
+            .await;
+        let error = &response["error"];
+        assert_eq!(error["code"], -32602);
+        let message = error["message"].as_str().unwrap();
+        assert!(message.contains("`query` is required"), "{message}");
+        assert!(message.contains("`limit` expected integer, got string"), "{message}");
+        assert_eq!(error["data"]["errors"].as_array().unwrap().len(), 2);
+
+        let response = client
+            .send(
+                "tools/call",
+                call("cortex_instructions", serde_json::json!({ "action": "foo" })),
+            )
+            .await;
+        let message = response["error"]["message"].as_str().unwrap();
+        assert!(message.contains("`action` unknown value 'foo', expected one of:"), "{message}");
+
+        // Constraints the schema can't express are checked by the handlers
+        let response = client
+            .send("tools/call", call("memory_search", serde_json::json!({ "query": "q", "limit": 0 })))
+            .await;
+        assert_eq!(response["error"]["code"], -32602);
+        assert_eq!(response["error"]["message"], "Invalid parameter `limit`: must be > 0");
+        assert_eq!(response["error"]["data"]["errors"][0]["field"], "limit");
+
+        let response = client
+            .send("tools/call", call("manage_tags", serde_json::json!({ "action": "rename" })))
+            .await;
+        assert_eq!(
+            response["error"]["message"],
+            "Invalid parameter `action`: unknown action 'rename', expected one of: add, remove, get, search"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_handlers_reject_out_of_range_params() {
+        use rmcp::handler::server::wrapper::Parameters;
+
+        let temp = TempDir::new().unwrap();
+        let server = IntelligenceServer::for_testing(temp.path()).await.unwrap();
+        fn params<T: serde::de::DeserializeOwned>(value: serde_json::Value) -> Parameters<T> {
+            Parameters(serde_json::from_value(value).unwrap())
+        }
+
+        let err = server
+            .knowledge_read_graph(params(serde_json::json!({ "limit": 0 })))
+            .await
+            .unwrap_err();
+        assert_eq!(err.message, "Invalid parameter `limit`: must be > 0");
+        let graph = server.knowledge_read_graph(params(serde_json::json!({}))).await.unwrap().0;
+        assert!(graph.entities.is_empty());
+
+        let err = server
+            .knowledge_get_neighbors(params(serde_json::json!({ "entity_name": "a", "max_depth": 0 })))
+            .await
+            .unwrap_err();
+        assert_eq!(err.message, "Invalid parameter `max_depth`: must be > 0");
+        let err = server
+            .knowledge_find_path(params(serde_json::json!({ "from": "a", "to": "b", "max_nodes_visited": 0 })))
+            .await
+            .unwrap_err();
+        assert_eq!(err.message, "Invalid parameter `max_nodes_visited`: must be > 0");
+
+        let err = server
+            .cortex_cleanup(params(serde_json::json!({ "retention_days": -1 })))
+            .await
+            .unwrap_err();
+        assert_eq!(err.message, "Invalid parameter `retention_days`: must be >= 0, got -1");
+
+        let err = server
+            .knowledge_search(params(serde_json::json!({ "query": "a", "similarity_threshold": 2.0 })))
+            .await
+            .unwrap_err();
+        assert!(err.message.contains("`similarity_threshold`"), "{}", err.message);
+
+        let err = server
+            .mcp_install(params(serde_json::json!({ "name": "x", "package": "x", "package_type": "binary" })))
+            .await
+            .unwrap_err();
+        assert_eq!(
+            err.message,
+            "Invalid parameter `package_type`: unknown package_type 'binary', expected one of: npm, pip"
+        );
+    }
+
+    #[tokio::test]
+    async fn test_auto_cleanup_runs_on_schedule() {
+        use crate::cortex::CortexConfig;
+
+        let temp = TempDir::new().unwrap();
+        let mut config = IntelligenceConfig::default();
+        config.memory.deleted_retention_days = 0;
+        let mut server = IntelligenceServer::for_testing_with_config(temp.path(), config)
+            .await
+            .unwrap();
+        assert!(server.spawn_auto_cleanup().is_none(), "off by default");
+
+        let cortex_dir = TempDir::new().unwrap();
+        let cortex_config = CortexConfig {
+            auto_cleanup: true,
+            cleanup_interval: std::time::Duration::from_millis(50),
+            ..CortexConfig::default()
+        };
+        server.cortex = Arc::new(CortexEngine::new(cortex_dir.path(), cortex_config).await.unwrap());
+
+        server
+            .db
+            .create_document(whytcard_database::CreateDocument::new("stale").with_key("stale"))
+            .await
+            .unwrap();
+        assert!(server.db.soft_delete_document_by_key("stale").await.unwrap());
+
+        let task = server.spawn_auto_cleanup().unwrap();
+        tokio::time::sleep(std::time::Duration::from_millis(300)).await;
+        task.abort();
+
+        // Purged by the scheduled run, so it can no longer be restored
+        assert!(server.db.restore_document_by_key("stale").await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_entity_name_normalization() {
+        use rmcp::handler::server::wrapper::Parameters;
+
+        let temp = TempDir::new().unwrap();
+        let mut config = IntelligenceConfig::default();
+        config.knowledge.name_normalization = crate::config::NameNormalization::all();
+        let server = IntelligenceServer::for_testing_with_config(temp.path(), config)
+            .await
+            .unwrap();
+
+        let first = server
+            .knowledge_add_entity(Parameters(KnowledgeAddEntityParams {
+                name: "Rust".to_string(),
+                entity_type: "language".to_string(),
+                observations: vec!["Memory safe".to_string()],
+            }))
+            .await
+            .unwrap();
+        assert!(first.0.created);
+
+        // Variants resolve to the same entity instead of creating new ones
+        for variant in ["rust", "Rust ", "  RUST"] {
+            let result = server
+                .knowledge_add_entity(Parameters(KnowledgeAddEntityParams {
+                    name: variant.to_string(),
+                    entity_type: "language".to_string(),
+                    observations: Vec::new(),
+                }))
+                .await
+                .unwrap();
+            assert!(!result.0.created, "{:?} created a duplicate", variant);
+        }
+
+        // The original spelling is kept for display
+        let entity = server
+            .knowledge_get_entity(Parameters(KnowledgeGetEntityParams {
+                name: "rust".to_string(),
+                include_relations: false,
+            }))
+            .await
+            .unwrap();
+        assert_eq!(entity.0.entity.name, "Rust");
+        assert_eq!(server.db.count_entities().await.unwrap(), 1);
+    }
+
     #[tokio::test]
     async fn test_server_info() {
         let temp = TempDir::new().unwrap();
@@ -4315,4 +6433,728 @@ mod tests {
         assert_eq!(info.server_info.name, "whytcard-intelligence");
         assert!(info.instructions.is_some());
     }
+
+    #[tokio::test]
+    async fn test_user_instructions_list_and_delete() {
+        use rmcp::handler::server::wrapper::Parameters;
+
+        let temp = TempDir::new().unwrap();
+        let server = IntelligenceServer::for_testing(temp.path()).await.unwrap();
+
+        let prepare = |value: &str| -> PrepareParams {
+            serde_json::from_value(serde_json::json!({
+                "user_id": "alice",
+                "user_instructions": [
+                    { "key": "language", "value": value, "category": "language", "priority": 5 },
+                    { "key": "style", "value": "Be concise" },
+                ],
+            }))
+            .unwrap()
+        };
+        server.prepare(Parameters(prepare("Answer in French"))).await.unwrap();
+
+        // Saving the same key again replaces the stored value
+        let again = server.prepare(Parameters(prepare("Answer in German"))).await.unwrap();
+        assert!(again.0.data.user_instructions_saved.iter().all(|u| u.saved));
+        assert!(again.0.data.user_instructions_saved.iter().all(|u| u.replaced));
+
+        let list = |user_id: &str| UserInstructionsListParams { user_id: user_id.to_string() };
+        let listed = server.user_instructions_list(Parameters(list("alice"))).await.unwrap().0;
+        assert_eq!(listed.count, 2);
+        assert_eq!(listed.instructions[0].key, "language");
+        assert_eq!(listed.instructions[0].value, "Answer in German");
+        assert_eq!(listed.instructions[0].priority, 5);
+        assert_eq!(listed.instructions[1].key, "style");
+
+        // Other users' instructions are not listed
+        let other = server.user_instructions_list(Parameters(list("bob"))).await.unwrap().0;
+        assert_eq!(other.count, 0);
+
+        let deleted = server
+            .user_instructions_delete(Parameters(UserInstructionsDeleteParams {
+                user_id: "alice".to_string(),
+                key: "language".to_string(),
+            }))
+            .await
+            .unwrap()
+            .0;
+        assert!(deleted.deleted);
+
+        let listed = server.user_instructions_list(Parameters(list("alice"))).await.unwrap().0;
+        assert_eq!(listed.count, 1);
+        assert_eq!(listed.instructions[0].key, "style");
+    }
+
+    #[tokio::test]
+    async fn test_memory_store_rejects_oversized_content() {
+        use rmcp::handler::server::wrapper::Parameters;
+
+        let temp = TempDir::new().unwrap();
+        let mut config = IntelligenceConfig::default();
+        config.memory.max_content_bytes = 16;
+        let server = IntelligenceServer::for_testing_with_config(temp.path(), config)
+            .await
+            .unwrap();
+
+        let store = |content: &str| -> MemoryStoreParams {
+            serde_json::from_value(serde_json::json!({ "key": "log", "content": content, "index": false })).unwrap()
+        };
+        let err = server.memory_store(Parameters(store(&"x".repeat(17)))).await.unwrap_err();
+        assert_eq!(err.code, rmcp::model::ErrorCode::INVALID_PARAMS);
+        assert_eq!(err.data, Some(serde_json::json!({ "size": 17, "limit": 16 })));
+        assert!(server.db.get_document_by_key("log").await.unwrap().is_none());
+
+        assert!(server.memory_store(Parameters(store("short log"))).await.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_batch_store_index_only() {
+        use rmcp::handler::server::wrapper::Parameters;
+
+        let temp = TempDir::new().unwrap();
+        let server = IntelligenceServer::for_testing(temp.path()).await.unwrap();
+
+        let params: BatchStoreParams = serde_json::from_value(serde_json::json!({
+            "index_only": true,
+            "items": [
+                { "content": "Rust ownership rules prevent data races at compile time.", "source": "notes",
+                  "tags": ["rust"], "metadata": { "chapter": 4 } },
+                { "content": "Brew coffee at 94 degrees for a balanced extraction.", "source": "notes" }
+            ]
+        }))
+        .unwrap();
+        let result = server.batch_store(Parameters(params)).await.unwrap().0;
+        assert_eq!(result.stored, 2);
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+
+        // Searchable, but no memory documents were created
+        assert!(server.db.list_documents(None, 10, 0).await.unwrap().is_empty());
+        let params: MemorySearchParams =
+            serde_json::from_value(serde_json::json!({ "query": "Rust data races", "limit": 1 })).unwrap();
+        let found = server.memory_search(Parameters(params)).await.unwrap().0;
+        assert_eq!(found.results[0].key, result.keys[0]);
+
+        let hits = server.rag.write().await.search("Rust data races", Some(1)).await.unwrap();
+        let metadata = hits[0].chunk.metadata.as_ref().unwrap();
+        assert_eq!(metadata["type"], "indexed");
+        assert_eq!(metadata["chapter"], 4);
+        assert_eq!(metadata["tags"], serde_json::json!(["rust"]));
+    }
+
+    #[tokio::test]
+    async fn test_memory_search_explain() {
+        use rmcp::handler::server::wrapper::Parameters;
+
+        let temp = TempDir::new().unwrap();
+        let server = IntelligenceServer::for_testing(temp.path()).await.unwrap();
+
+        for (key, content) in [
+            ("rust-owner", "Rust ownership rules prevent data races at compile time."),
+            ("coffee", "Brew coffee at 94 degrees for a balanced extraction."),
+        ] {
+            let params: MemoryStoreParams =
+                serde_json::from_value(serde_json::json!({ "key": key, "content": content })).unwrap();
+            server.memory_store(Parameters(params)).await.unwrap();
+        }
+
+        let params: MemorySearchExplainParams =
+            serde_json::from_value(serde_json::json!({ "query": "Rust data races", "limit": 1 })).unwrap();
+        let explain = server.memory_search_explain(Parameters(params)).await.unwrap().0;
+        assert_eq!(explain.candidates_examined, 2);
+        assert_eq!(explain.ef, None);
+        // The default model returns normalized embeddings
+        assert!((explain.query_norm - 1.0).abs() < 1e-3, "{}", explain.query_norm);
+
+        assert_eq!(explain.results.len(), 1);
+        let hit = &explain.results[0];
+        assert_eq!(hit.key, "rust-owner");
+        assert!((hit.vector_score - (1.0 - hit.distance)).abs() < 1e-5);
+        assert!(hit.metadata.is_some());
+
+        let params: MemorySearchExplainParams =
+            serde_json::from_value(serde_json::json!({ "query": "q", "limit": 0 })).unwrap();
+        let err = server.memory_search_explain(Parameters(params)).await.unwrap_err();
+        assert_eq!(err.code, rmcp::model::ErrorCode::INVALID_PARAMS);
+    }
+
+    #[tokio::test]
+    async fn test_memory_list_total() {
+        use rmcp::handler::server::wrapper::Parameters;
+
+        let temp = TempDir::new().unwrap();
+        let server = IntelligenceServer::for_testing(temp.path()).await.unwrap();
+        for (content, tags) in [
+            ("Rust ownership", vec!["rust"]),
+            ("Rust lifetimes", vec!["rust"]),
+            ("Coffee extraction", vec![]),
+        ] {
+            let params: MemoryStoreParams =
+                serde_json::from_value(serde_json::json!({ "content": content, "tags": tags })).unwrap();
+            server.memory_store(Parameters(params)).await.unwrap();
+        }
+
+        let list = |value: serde_json::Value| {
+            let params: MemoryListParams = serde_json::from_value(value).unwrap();
+            server.memory_list(Parameters(params))
+        };
+        let page = list(serde_json::json!({ "limit": 1 })).await.unwrap().0;
+        assert_eq!(page.memories.len(), 1);
+        assert_eq!(page.total, 3);
+        assert!(page.has_more);
+
+        let page = list(serde_json::json!({ "limit": 1, "offset": 1, "tags": ["rust"] })).await.unwrap().0;
+        assert_eq!(page.memories.len(), 1);
+        assert_eq!(page.total, 2);
+        assert!(!page.has_more);
+    }
+
+    #[tokio::test]
+    async fn test_deleted_key_can_be_stored_again() {
+        use rmcp::handler::server::wrapper::Parameters;
+
+        let temp = TempDir::new().unwrap();
+        let mut config = IntelligenceConfig::default();
+        config.memory.deleted_retention_days = 0;
+        let server = IntelligenceServer::for_testing_with_config(temp.path(), config)
+            .await
+            .unwrap();
+
+        let store = |key: &str, content: &str| {
+            let params: MemoryStoreParams = serde_json::from_value(serde_json::json!({
+                "key": key, "content": content, "index": false
+            }))
+            .unwrap();
+            server.memory_store(Parameters(params))
+        };
+        let delete = |key: &str| {
+            let params: MemoryDeleteParams = serde_json::from_value(serde_json::json!({ "key": key })).unwrap();
+            server.memory_delete(Parameters(params))
+        };
+
+        store("notes", "First draft").await.unwrap();
+        delete("notes").await.unwrap();
+        store("notes", "Second draft").await.unwrap();
+        let doc = server.db.get_document_by_key("notes").await.unwrap().unwrap();
+        assert_eq!(doc.content, "Second draft");
+        assert!(!doc.is_deleted());
+
+        // Cleanup reports trash purges apart from CORTEX records
+        store("scratch", "Throwaway").await.unwrap();
+        delete("scratch").await.unwrap();
+        let params: ManageParams =
+            serde_json::from_value(serde_json::json!({ "action": "cortex_cleanup" })).unwrap();
+        let result = server.manage(Parameters(params)).await.unwrap().0.data;
+        assert!(result.success, "{:?}", result.error);
+        assert_eq!(result.purged_count, Some(1));
+        assert!(result.cleaned_count.is_some());
+
+        // Compaction is reachable through manage
+        let params: ManageParams =
+            serde_json::from_value(serde_json::json!({ "action": "cortex_cleanup", "compact": true })).unwrap();
+        let result = server.manage(Parameters(params)).await.unwrap().0.data;
+        assert!(result.success, "{:?}", result.error);
+        assert!(result.message.contains("reclaimed"));
+    }
+
+    #[tokio::test]
+    async fn test_entity_embeddings_follow_writes() {
+        use rmcp::handler::server::wrapper::Parameters;
+
+        let temp = TempDir::new().unwrap();
+        let server = IntelligenceServer::for_testing(temp.path()).await.unwrap();
+
+        // Stored before embeddings were: picked up by the backfill
+        server
+            .db
+            .create_entity(CreateEntity::new("legacy", "concept"))
+            .await
+            .unwrap();
+        server.schedule_entity_embedding_refresh();
+        server.settle_entity_embeddings().await;
+        assert!(server.db.entities_without_embedding(10).await.unwrap().is_empty());
+
+        let params: KnowledgeAddEntityParams = serde_json::from_value(serde_json::json!({
+            "name": "Tokio", "entity_type": "library", "observations": ["Asynchronous runtime for Rust"]
+        }))
+        .unwrap();
+        server.knowledge_add_entity(Parameters(params)).await.unwrap();
+        server.settle_entity_embeddings().await;
+        assert!(server.db.entities_without_embedding(10).await.unwrap().is_empty());
+
+        let params: KnowledgeAddObservationParams = serde_json::from_value(serde_json::json!({
+            "entity_name": "Tokio", "observations": ["Work-stealing scheduler"]
+        }))
+        .unwrap();
+        server.knowledge_add_observation(Parameters(params)).await.unwrap();
+        server.settle_entity_embeddings().await;
+        assert!(server.db.entities_without_embedding(10).await.unwrap().is_empty());
+
+        let matches = server.semantic_entity_matches("async runtime", 0.3, 10).await.unwrap();
+        assert_eq!(matches.first().map(|e| e.label()), Some("Tokio"));
+    }
+
+    #[tokio::test]
+    async fn test_prepare_writes_graph_atomically() {
+        use rmcp::handler::server::wrapper::Parameters;
+
+        let temp = TempDir::new().unwrap();
+        let server = IntelligenceServer::for_testing(temp.path()).await.unwrap();
+        let prepare = |value: serde_json::Value| {
+            let params: PrepareParams = serde_json::from_value(value).unwrap();
+            server.prepare(Parameters(params))
+        };
+
+        let result = prepare(serde_json::json!({
+            "entities": [
+                { "name": "Rust", "entity_type": "language" },
+                { "name": "Cargo", "entity_type": "tool" },
+            ],
+            "relations": [{ "from": "Rust", "to": "Cargo", "relation_type": "uses" }],
+        }))
+        .await
+        .unwrap()
+        .0
+        .data;
+        assert!(result.errors.is_empty(), "{:?}", result.errors);
+        assert_eq!(result.entities_created.len(), 2);
+        assert_eq!(result.relations_created.len(), 1);
+        assert_eq!(server.db.count_relations().await.unwrap(), 1);
+
+        // The duplicate fails inside the transaction: nothing of this call is written
+        let result = prepare(serde_json::json!({
+            "entities": [
+                { "name": "Clippy", "entity_type": "tool" },
+                { "name": "Clippy", "entity_type": "tool" },
+            ],
+            "relations": [{ "from": "Cargo", "to": "Clippy", "relation_type": "uses" }],
+        }))
+        .await
+        .unwrap()
+        .0
+        .data;
+        assert!(result.entities_created.is_empty());
+        assert!(result.relations_created.is_empty());
+        assert_eq!(result.errors.len(), 1);
+        assert!(result.errors[0].contains("create_entity"), "{}", result.errors[0]);
+        assert_eq!(server.db.count_entities().await.unwrap(), 2);
+        assert_eq!(server.db.count_relations().await.unwrap(), 1);
+
+        // Existing entities are reported without aborting the rest
+        let result = prepare(serde_json::json!({
+            "entities": [
+                { "name": "Rust", "entity_type": "language" },
+                { "name": "Clippy", "entity_type": "tool" },
+            ],
+        }))
+        .await
+        .unwrap()
+        .0
+        .data;
+        assert_eq!(result.entities_created.len(), 1);
+        assert_eq!(result.errors.len(), 1);
+        assert_eq!(server.db.count_entities().await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_search_uses_configured_min_relevance() {
+        use rmcp::handler::server::wrapper::Parameters;
+
+        let temp = TempDir::new().unwrap();
+        let config = IntelligenceConfig::default().with_min_relevance(0.99);
+        let server = IntelligenceServer::for_testing_with_config(temp.path(), config)
+            .await
+            .unwrap();
+
+        let params: MemoryStoreParams = serde_json::from_value(serde_json::json!({
+            "key": "coffee", "content": "Brew coffee at 94 degrees for a balanced extraction."
+        }))
+        .unwrap();
+        server.memory_store(Parameters(params)).await.unwrap();
+
+        let search = |value: serde_json::Value| {
+            let params: MemorySearchParams = serde_json::from_value(value).unwrap();
+            server.memory_search(Parameters(params))
+        };
+        let found = search(serde_json::json!({ "query": "coffee brewing temperature" })).await.unwrap().0;
+        assert!(found.results.is_empty());
+        let found = search(serde_json::json!({ "query": "coffee brewing temperature", "min_score": 0.0 }))
+            .await
+            .unwrap()
+            .0;
+        assert_eq!(found.results.len(), 1);
+        let err = search(serde_json::json!({ "query": "q", "min_score": 1.5 })).await.unwrap_err();
+        assert_eq!(err.code, rmcp::model::ErrorCode::INVALID_PARAMS);
+
+        let params: HybridSearchParams =
+            serde_json::from_value(serde_json::json!({ "query": "coffee brewing temperature" })).unwrap();
+        let hybrid = server.hybrid_search(Parameters(params)).await.unwrap().0;
+        assert!(hybrid.semantic.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_ingest_links_memory_and_graph() {
+        use rmcp::handler::server::wrapper::Parameters;
+
+        let temp = TempDir::new().unwrap();
+        let server = IntelligenceServer::for_testing(temp.path()).await.unwrap();
+        server
+            .db
+            .create_entity(CreateEntity::new("rust", "language").with_observations(vec!["fast".into()]))
+            .await
+            .unwrap();
+
+        let ingest = |value: serde_json::Value| {
+            let params: IngestParams = serde_json::from_value(value).unwrap();
+            server.ingest(Parameters(params))
+        };
+        let result = ingest(serde_json::json!({
+            "key": "tokio-notes",
+            "content": "Tokio is the async runtime most Rust services use.",
+            "entities": [
+                { "name": "tokio", "entity_type": "library", "observations": ["async runtime"] },
+                { "name": "rust", "entity_type": "language" }
+            ],
+            "relations": [{ "from": "tokio", "to": "rust", "relation_type": "written_in" }]
+        }))
+        .await
+        .unwrap()
+        .0;
+        assert_eq!(result.key, "tokio-notes");
+        assert!(result.entities[0].created);
+        assert!(!result.entities[1].created);
+        assert!(result.relations[0].created);
+
+        let source = format!("{SOURCE_OBSERVATION_PREFIX}tokio-notes");
+        let tokio = server.db.get_entity_by_name("tokio").await.unwrap().unwrap();
+        assert_eq!(tokio.observations, vec!["async runtime".to_string(), source.clone()]);
+        let rust = server.db.get_entity_by_name("rust").await.unwrap().unwrap();
+        assert_eq!(rust.observations, vec!["fast".to_string(), source]);
+        assert!(server
+            .db
+            .find_relation(tokio.id.as_ref().unwrap(), rust.id.as_ref().unwrap(), "written_in")
+            .await
+            .unwrap()
+            .is_some());
+        let memory = server.db.get_document_by_key("tokio-notes").await.unwrap().unwrap();
+        assert_eq!(memory.metadata.unwrap()[ENTITIES_METADATA_KEY], serde_json::json!(["tokio", "rust"]));
+
+        // An unknown entity in a relation writes nothing at all
+        let err = ingest(serde_json::json!({
+            "key": "serde-notes",
+            "content": "Serde serializes Rust data structures.",
+            "entities": [{ "name": "serde", "entity_type": "library" }],
+            "relations": [{ "from": "serde", "to": "missing", "relation_type": "uses" }]
+        }))
+        .await
+        .unwrap_err();
+        assert_eq!(err.code, rmcp::model::ErrorCode::INVALID_PARAMS);
+        assert!(server.db.get_document_by_key("serde-notes").await.unwrap().is_none());
+        assert!(server.db.get_entity_by_name("serde").await.unwrap().is_none());
+
+        // The same name with another type is a different entity
+        let result = ingest(serde_json::json!({
+            "key": "rust-game",
+            "content": "Rust is also a survival game.",
+            "entities": [{ "name": "rust", "entity_type": "game" }]
+        }))
+        .await
+        .unwrap()
+        .0;
+        assert!(result.entities[0].created);
+        let game = server.db.get_entity_by_name_and_type("rust", "game").await.unwrap().unwrap();
+        assert_ne!(game.id, rust.id);
+        let language = server.db.get_entity_by_name_and_type("rust", "language").await.unwrap().unwrap();
+        assert_eq!(language.observations.len(), 2);
+    }
+
+    /// Embedding backend standing in for an unavailable embedder
+    struct UnavailableBackend;
+
+    impl whytcard_rag::EmbeddingBackend for UnavailableBackend {
+        fn dimension(&self) -> usize {
+            8
+        }
+
+        fn embed_texts(&mut self, _texts: Vec<String>) -> whytcard_rag::Result<Vec<Vec<f32>>> {
+            Err(whytcard_rag::RagError::Embedding("embedder unavailable".to_string()))
+        }
+    }
+
+    #[tokio::test]
+    async fn test_memory_search_falls_back_to_full_text() {
+        use rmcp::handler::server::wrapper::Parameters;
+
+        let temp = TempDir::new().unwrap();
+        let server = IntelligenceServer::for_testing(temp.path()).await.unwrap();
+        for (key, content) in [
+            ("coffee", "Brew coffee at 94 degrees for a balanced extraction."),
+            ("tea", "Steep green tea at 80 degrees."),
+        ] {
+            let params: MemoryStoreParams =
+                serde_json::from_value(serde_json::json!({ "key": key, "content": content })).unwrap();
+            server.memory_store(Parameters(params)).await.unwrap();
+        }
+
+        *server.rag.write().await = whytcard_rag::RagEngineBuilder::new()
+            .db_path(":memory:")
+            .embedding_model(whytcard_rag::EmbeddingModel::custom("unavailable", 8))
+            .embedding_backend(Box::new(UnavailableBackend))
+            .build()
+            .await
+            .unwrap();
+
+        let params: MemorySearchParams = serde_json::from_value(serde_json::json!({ "query": "coffee" })).unwrap();
+        let found = server.memory_search(Parameters(params)).await.unwrap().0;
+        assert!(found.fallback);
+        assert_eq!(found.results.len(), 1);
+        assert_eq!(found.results[0].key, "coffee");
+
+        let temp = TempDir::new().unwrap();
+        let config = IntelligenceConfig::default().with_search_fallback(false);
+        let server = IntelligenceServer::for_testing_with_config(temp.path(), config).await.unwrap();
+        *server.rag.write().await = whytcard_rag::RagEngineBuilder::new()
+            .db_path(":memory:")
+            .embedding_model(whytcard_rag::EmbeddingModel::custom("unavailable", 8))
+            .embedding_backend(Box::new(UnavailableBackend))
+            .build()
+            .await
+            .unwrap();
+        let params: MemorySearchParams = serde_json::from_value(serde_json::json!({ "query": "coffee" })).unwrap();
+        assert!(server.memory_search(Parameters(params)).await.is_err());
+    }
+
+    #[tokio::test]
+    async fn test_manage_optimize_index() {
+        use rmcp::handler::server::wrapper::Parameters;
+
+        let temp = TempDir::new().unwrap();
+        let server = IntelligenceServer::for_testing(temp.path()).await.unwrap();
+
+        let params: ManageParams = serde_json::from_value(serde_json::json!({ "action": "optimize_index" })).unwrap();
+        let result = server.manage(Parameters(params)).await.unwrap().0.data;
+        assert!(result.success, "{:?}", result.error);
+        assert_eq!(result.action, "optimize_index");
+        assert_eq!(result.indexed_count, Some(0));
+    }
+
+    #[tokio::test]
+    async fn test_get_info_uses_configured_identity() {
+        let temp = TempDir::new().unwrap();
+        let config = IntelligenceConfig::default()
+            .with_server_name("acme-assistant")
+            .with_version("2.1.0")
+            .with_instructions("Use memory_search before answering.");
+        let server = IntelligenceServer::for_testing_with_config(temp.path(), config)
+            .await
+            .unwrap();
+
+        let info = rmcp::ServerHandler::get_info(&server);
+        assert_eq!(info.server_info.name, "acme-assistant");
+        assert_eq!(info.server_info.version, "2.1.0");
+        assert_eq!(info.instructions.as_deref(), Some("Use memory_search before answering."));
+
+        let temp = TempDir::new().unwrap();
+        let server = IntelligenceServer::for_testing(temp.path()).await.unwrap();
+        let info = rmcp::ServerHandler::get_info(&server);
+        assert_eq!(info.server_info.name, "whytcard-intelligence");
+        assert_eq!(info.instructions.as_deref(), Some(DEFAULT_INSTRUCTIONS));
+    }
+
+    #[tokio::test]
+    async fn test_capabilities() {
+        use rmcp::handler::server::wrapper::Parameters;
+
+        let temp = TempDir::new().unwrap();
+        let config = IntelligenceConfig::default().with_version("3.0.0");
+        let server = IntelligenceServer::for_testing_with_config(temp.path(), config)
+            .await
+            .unwrap();
+
+        let result = server.capabilities(Parameters(CapabilitiesParams {})).await.unwrap().0;
+        assert_eq!(result.version, "3.0.0");
+        assert!(result.tools.iter().any(|t| t == "capabilities"));
+        assert!(result.tools.iter().any(|t| t == "memory_store"));
+        assert!(result.tools.windows(2).all(|w| w[0] <= w[1]));
+        assert_eq!(result.integrations.len(), 3);
+        assert_eq!(result.embedding.dimension, server.config.rag.model.dimensions());
+        assert_eq!(result.storage.mode, "memory");
+        assert!(result.storage.path.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_manage_tags_persists() {
+        use rmcp::handler::server::wrapper::Parameters;
+
+        let temp = TempDir::new().unwrap();
+        let server = IntelligenceServer::for_testing(temp.path()).await.unwrap();
+        let params: MemoryStoreParams =
+            serde_json::from_value(serde_json::json!({ "key": "note", "content": "Tag me", "index": false })).unwrap();
+        server.memory_store(Parameters(params)).await.unwrap();
+
+        let tags = |action: &str, tags: &[&str]| -> ManageTagsParams {
+            serde_json::from_value(serde_json::json!({ "action": action, "doc_id": "note", "tags": tags })).unwrap()
+        };
+        let added = server.manage_tags(Parameters(tags("add", &["rust", "draft"]))).await.unwrap().0;
+        assert!(added.success);
+        server.manage_tags(Parameters(tags("remove", &["draft"]))).await.unwrap();
+
+        let stored = server.db.get_document_by_key("note").await.unwrap().unwrap();
+        assert!(stored.tags.contains(&"rust".to_string()));
+        assert!(!stored.tags.contains(&"draft".to_string()));
+        assert_eq!(stored.content, "Tag me");
+    }
+
+    #[tokio::test]
+    async fn test_manage_server_actions() {
+        use rmcp::handler::server::wrapper::Parameters;
+
+        let temp = TempDir::new().unwrap();
+        let server = &IntelligenceServer::for_testing(temp.path()).await.unwrap();
+        let manage = move |value: serde_json::Value| {
+            let params: ManageParams = serde_json::from_value(value).unwrap();
+            server.manage(Parameters(params))
+        };
+
+        let installed = manage(serde_json::json!({
+            "action": "install",
+            "install": { "name": "notes", "package": "@example/notes-mcp", "connect_now": false }
+        }))
+        .await
+        .unwrap()
+        .0
+        .data;
+        assert!(installed.success, "{:?}", installed.error);
+        assert_eq!(installed.action, "install");
+
+        let disconnected = manage(serde_json::json!({ "action": "disconnect", "server": "notes" }))
+            .await
+            .unwrap()
+            .0
+            .data;
+        assert!(disconnected.success, "{:?}", disconnected.error);
+        assert_eq!(disconnected.servers[0].status, "Disconnected");
+        assert_eq!(disconnected.servers[0].package.as_deref(), Some("@example/notes-mcp"));
+        assert_eq!(disconnected.connected_count, 0);
+
+        let configured = manage(serde_json::json!({
+            "action": "configure",
+            "configure": { "name": "notes", "enable": false, "set_env": ["API_KEY", "secret"] }
+        }))
+        .await
+        .unwrap()
+        .0
+        .data;
+        assert!(configured.success, "{:?}", configured.error);
+        assert_eq!(configured.servers.len(), 1);
+        assert!(!configured.servers[0].enabled);
+
+        let uninstalled = manage(serde_json::json!({ "action": "uninstall", "server": "notes" }))
+            .await
+            .unwrap()
+            .0
+            .data;
+        assert!(uninstalled.success, "{:?}", uninstalled.error);
+        let again = manage(serde_json::json!({ "action": "uninstall", "server": "notes" }))
+            .await
+            .unwrap()
+            .0
+            .data;
+        assert!(!again.success);
+        assert!(again.error.is_some());
+
+        // Actions naming a server require it
+        assert!(manage(serde_json::json!({ "action": "connect" })).await.is_err());
+
+        let instructions = manage(serde_json::json!({
+            "action": "instructions",
+            "instructions": { "action": "for_file", "file_path": "src/main.rs" }
+        }))
+        .await
+        .unwrap()
+        .0
+        .data;
+        assert!(instructions.success);
+        assert_eq!(instructions.action, "instructions_for_file");
+    }
+
+    #[tokio::test]
+    async fn test_cortex_process_timeout() {
+        use rmcp::handler::server::wrapper::Parameters;
+
+        let temp = TempDir::new().unwrap();
+        let server = IntelligenceServer::for_testing(temp.path()).await.unwrap();
+
+        let params: CortexProcessParams = serde_json::from_value(serde_json::json!({
+            "query": "Refactor the database layer",
+            "timeout_secs": 0,
+        }))
+        .unwrap();
+        let result = server.cortex_process(Parameters(params)).await.unwrap().0;
+        assert_eq!(result.stopped_reason, crate::cortex::StoppedReason::Timeout);
+        assert!(!result.success);
+        assert!(!result.intent.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_cortex_abort_without_running_process() {
+        use rmcp::handler::server::wrapper::Parameters;
+
+        let temp = TempDir::new().unwrap();
+        let server = IntelligenceServer::for_testing(temp.path()).await.unwrap();
+
+        let result = server.cortex_abort(Parameters(CortexAbortParams {})).await.unwrap().0;
+        assert!(!result.aborted);
+
+        // A later call runs to completion
+        let params: CortexProcessParams =
+            serde_json::from_value(serde_json::json!({ "query": "Refactor the database layer" })).unwrap();
+        let result = server.cortex_process(Parameters(params)).await.unwrap().0;
+        assert_eq!(result.stopped_reason, crate::cortex::StoppedReason::Completed);
+    }
+
+    #[tokio::test]
+    async fn test_cortex_process_injects_user_instructions() {
+        use rmcp::handler::server::wrapper::Parameters;
+
+        let temp = TempDir::new().unwrap();
+        let server = IntelligenceServer::for_testing(temp.path()).await.unwrap();
+
+        let prepare: PrepareParams = serde_json::from_value(serde_json::json!({
+            "user_id": "alice",
+            "user_instructions": [
+                { "key": "style", "value": "Use bullet points", "priority": 1 },
+                { "key": "language", "value": "Answer in French", "priority": 5 },
+            ],
+        }))
+        .unwrap();
+        server.prepare(Parameters(prepare)).await.unwrap();
+        let prepare: PrepareParams = serde_json::from_value(serde_json::json!({
+            "user_id": "bob",
+            "user_instructions": [{ "key": "language", "value": "Answer in German" }],
+        }))
+        .unwrap();
+        server.prepare(Parameters(prepare)).await.unwrap();
+
+        let process = |user_id: &str| -> CortexProcessParams {
+            serde_json::from_value(serde_json::json!({
+                "query": "Explain closures",
+                "inject_doubt": false,
+                "user_id": user_id,
+            }))
+            .unwrap()
+        };
+
+        let preview = server.cortex_preview_context(Parameters(process("alice"))).await.unwrap().0;
+        let context = &preview.prompt_context;
+        assert!(context.find("Answer in French").unwrap() < context.find("Use bullet points").unwrap());
+        assert!(!context.contains("Answer in German"));
+        assert!(preview.loaded_prompts.contains(&"user_instruction:alice:language".to_string()));
+        assert!(preview.loaded_prompts.contains(&"user_instruction:alice:style".to_string()));
+
+        let result = server.cortex_process(Parameters(process("bob"))).await.unwrap().0;
+        assert_eq!(
+            result.loaded_prompts.iter().filter(|p| p.starts_with("user_instruction:")).collect::<Vec<_>>(),
+            vec!["user_instruction:bob:language"]
+        );
+    }
 }
@@ -189,6 +189,16 @@ impl MultiSessionManager {
         Ok(session_id)
     }
 
+    /// Whether the session limit is reached (never true when unlimited)
+    pub async fn is_full(&self) -> bool {
+        self.max_sessions > 0 && self.sessions.read().await.len() >= self.max_sessions
+    }
+
+    /// Maximum allowed sessions (0 = unlimited)
+    pub fn max_sessions(&self) -> usize {
+        self.max_sessions
+    }
+
     /// Get a session by ID
     pub async fn get_session(&self, session_id: &str) -> Option<ClientSession> {
         let sessions = self.sessions.read().await;
@@ -348,10 +358,15 @@ mod tests {
         manager.create_session(client1).await.unwrap();
         manager.create_session(client2).await.unwrap();
 
+        assert!(manager.is_full().await);
+
         // Third should fail
         let client3 = ClientInfo::new("client3");
         let result = manager.create_session(client3).await;
         assert!(matches!(result, Err(SessionError::MaxSessionsReached(2))));
+
+        // Unlimited managers are never full
+        assert!(!MultiSessionManager::new().is_full().await);
     }
 
     #[tokio::test]
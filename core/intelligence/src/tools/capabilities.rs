@@ -0,0 +1,123 @@
+//! Capabilities Tool for WhytCard Intelligence
+//!
+//! Describes what this server instance supports (tools, integrations,
+//! embedding model, storage, limits) so clients can adapt to it instead of
+//! assuming a fixed feature set.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Parameters for capabilities (empty - no params needed)
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct CapabilitiesParams {}
+
+/// One external integration
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct IntegrationCapability {
+    /// Provider name (context7, tavily, mslearn)
+    pub name: String,
+
+    /// Whether it is configured and usable
+    pub ready: bool,
+}
+
+/// Embedding model used for semantic search
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct EmbeddingCapability {
+    /// Model name
+    pub model: String,
+
+    /// Vector dimension
+    pub dimension: usize,
+}
+
+/// Where data is stored
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct StorageCapability {
+    /// "memory", "persistent" or "remote"
+    pub mode: String,
+
+    /// Database path for persistent storage, server address for remote storage
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<String>,
+
+    /// Namespace isolating this instance's data
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub namespace: Option<String>,
+}
+
+/// Optional behaviours and whether they are on
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FeatureFlags {
+    /// Memories are searchable semantically
+    pub semantic_search: bool,
+
+    /// New memories are indexed automatically
+    pub auto_index: bool,
+
+    /// Deleted memories can be restored until purged
+    pub soft_delete: bool,
+
+    /// Old data is cleaned up periodically
+    pub auto_cleanup: bool,
+
+    /// Relation types are restricted to an allowed list
+    pub strict_relation_types: bool,
+}
+
+/// Limits enforced by the server (0 = unlimited)
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct Limits {
+    /// Maximum memory content size in bytes
+    pub max_content_bytes: usize,
+
+    /// Maximum number of memories
+    pub max_entries: usize,
+
+    /// Maximum number of knowledge graph entities
+    pub max_entities: usize,
+
+    /// Maximum relations per entity
+    pub max_relations_per_entity: usize,
+
+    /// Maximum nodes a graph traversal visits
+    pub max_nodes_visited: usize,
+
+    /// Maximum results of a search
+    pub max_search_results: usize,
+
+    /// Maximum concurrent SSE clients
+    pub max_clients: usize,
+}
+
+/// Result of capabilities
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CapabilitiesResult {
+    /// Server name reported in the MCP handshake
+    pub server_name: String,
+
+    /// Server version
+    pub version: String,
+
+    /// Names of the tools this server exposes, sorted
+    pub tools: Vec<String>,
+
+    /// External integrations
+    pub integrations: Vec<IntegrationCapability>,
+
+    /// Installed external MCP servers
+    #[serde(default)]
+    pub mcp_servers: Vec<String>,
+
+    /// Embedding model
+    pub embedding: EmbeddingCapability,
+
+    /// Storage
+    pub storage: StorageCapability,
+
+    /// Optional features
+    pub features: FeatureFlags,
+
+    /// Enforced limits
+    pub limits: Limits,
+}
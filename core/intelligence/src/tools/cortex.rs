@@ -4,10 +4,12 @@
 //! - cortex_process: Main cognitive processing entry point
 //! - cortex_feedback: Provide feedback for learning
 //! - cortex_stats: Get cognitive engine statistics
+//! - cortex_abort: Stop the processing in progress
 //! - cortex_cleanup: Cleanup old data
 
-use crate::cortex::{CortexEngine, CortexResult};
+use crate::cortex::{CleanupReport, CortexEngine, CortexResult, DecisionPoint, StoppedReason};
 use crate::cortex::CortexConfig;
+use crate::cortex::instructions::InstructionsPrompt;
 use crate::error::Result;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
@@ -115,12 +117,61 @@ pub struct CortexProcessParams {
     /// Whether to inject instructions from .instructions.md files (default: true)
     #[serde(default = "default_true")]
     pub inject_instructions: bool,
+
+    /// Character budget for injected instructions, overriding the server default (0 = unlimited)
+    #[serde(default)]
+    pub max_instructions_chars: Option<usize>,
+
+    /// User whose saved instructions (from prepare) are injected (default: "default")
+    #[serde(default)]
+    pub user_id: Option<String>,
+
+    /// Upper bound in seconds for the whole call; a partial result with
+    /// stopped_reason "timeout" is returned when exceeded (default: no limit).
+    /// cortex_abort stops the call early with stopped_reason "aborted".
+    #[serde(default)]
+    pub timeout_secs: Option<u64>,
+
+    /// Language the response must be written in (e.g. "fr", "German")
+    #[serde(default)]
+    pub response_language: Option<String>,
 }
 
 fn default_true() -> bool {
     true
 }
 
+impl Default for CortexProcessParams {
+    fn default() -> Self {
+        Self {
+            query: String::new(),
+            session_id: None,
+            context: None,
+            auto_learn: true,
+            task_type: None,
+            language: None,
+            inject_doubt: true,
+            file_path: None,
+            inject_instructions: true,
+            max_instructions_chars: None,
+            user_id: None,
+            timeout_secs: None,
+            response_language: None,
+        }
+    }
+}
+
+/// Build the instruction that pins the response language
+///
+/// Returns `None` for a missing or blank language.
+pub fn response_language_instruction(language: Option<&str>) -> Option<String> {
+    let language = language.map(str::trim).filter(|l| !l.is_empty())?;
+    Some(format!(
+        "# Response Language\n\nRespond only in {}, regardless of the language of the query or of the injected context. Keep code, identifiers and quoted text unchanged.",
+        language
+    ))
+}
+
 /// Output from cortex_process tool
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct CortexProcessResult {
@@ -159,6 +210,20 @@ pub struct CortexProcessResult {
 
     /// Number of instructions injected
     pub instructions_count: usize,
+
+    /// Instructions left out to stay within the instruction budget
+    pub instructions_dropped: Vec<String>,
+
+    /// Instruction characters left out (dropped or cut) to stay within the budget
+    pub instructions_dropped_chars: usize,
+
+    /// Why processing stopped ("completed", "timeout" or "aborted")
+    #[serde(default)]
+    pub stopped_reason: StoppedReason,
+
+    /// Decisions taken from intent to learning, in order
+    #[serde(default)]
+    pub decision_trace: Vec<DecisionPoint>,
 }
 
 impl From<CortexResult> for CortexProcessResult {
@@ -176,12 +241,17 @@ impl From<CortexResult> for CortexProcessResult {
             session_id: None,
             loaded_prompts: Vec::new(),
             instructions_count: 0,
+            instructions_dropped: Vec::new(),
+            instructions_dropped_chars: 0,
+            stopped_reason: result.stopped_reason,
+            decision_trace: result.decision_trace,
         }
     }
 }
 
 /// Process a query through CORTEX cognitive engine
 pub async fn cortex_process(params: CortexProcessParams) -> Result<CortexProcessResult> {
+    let started = std::time::Instant::now();
     let engine = get_cortex()?;
 
     // Start session if provided
@@ -200,27 +270,24 @@ pub async fn cortex_process(params: CortexProcessParams) -> Result<CortexProcess
         context_obj.insert("user_context".to_string(), serde_json::json!(user_ctx));
     }
 
-    // Inject instructions if enabled
-    let mut instructions_count = 0;
+    // Inject instructions if enabled, within the instruction budget
+    let mut instructions = InstructionsPrompt::default();
     if params.inject_instructions {
         // Get instructions prompt (filtered by file if provided)
-        let instructions_prompt = engine.get_instructions_prompt(params.file_path.as_deref()).await;
-
-        if !instructions_prompt.is_empty() {
-            // Count instructions
-            if let Some(file_path) = &params.file_path {
-                // If filtering by file, count matching instructions
-                instructions_count = engine.get_instructions_for_file(file_path).await.len();
-            } else {
-                // Count all loaded instructions from stats
-                let stats = engine.get_instructions_stats().await;
-                instructions_count = stats.get("count").and_then(|v| v.as_u64()).unwrap_or(0) as usize;
-            }
-
-            context_obj.insert("system_instructions".to_string(), serde_json::json!(instructions_prompt));
+        instructions = engine
+            .get_instructions_prompt_capped(params.file_path.as_deref(), params.max_instructions_chars)
+            .await;
+
+        if !instructions.text.is_empty() {
+            context_obj.insert("system_instructions".to_string(), serde_json::json!(instructions.text));
         }
     }
 
+    // Pin the response language
+    if let Some(instruction) = response_language_instruction(params.response_language.as_deref()) {
+        context_obj.insert("response_language".to_string(), serde_json::json!(instruction));
+    }
+
     // Build final context
     let context = if context_obj.is_empty() {
         None
@@ -229,7 +296,8 @@ pub async fn cortex_process(params: CortexProcessParams) -> Result<CortexProcess
     };
 
     // Process through CORTEX
-    let result = engine.process(&params.query, context).await?;
+    let timeout = params.timeout_secs.map(|secs| remaining_timeout(secs, started));
+    let result = engine.process_with_timeout(&params.query, context, timeout).await?;
 
     // End session if we started one
     if session_id.is_some() {
@@ -238,11 +306,44 @@ pub async fn cortex_process(params: CortexProcessParams) -> Result<CortexProcess
 
     let mut output: CortexProcessResult = result.into();
     output.session_id = session_id;
-    output.instructions_count = instructions_count;
+    output.instructions_count = instructions.included;
+    output.instructions_dropped = instructions.dropped;
+    output.instructions_dropped_chars = instructions.dropped_chars;
 
     Ok(output)
 }
 
+/// What is left of a `timeout_secs` budget that started at `started`
+pub fn remaining_timeout(timeout_secs: u64, started: std::time::Instant) -> std::time::Duration {
+    std::time::Duration::from_secs(timeout_secs).saturating_sub(started.elapsed())
+}
+
+// ============================================================================
+// cortex_preview_context - Inspect injected prompts
+// ============================================================================
+
+/// Output from cortex_preview_context tool (takes `CortexProcessParams`)
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CortexPreviewContextResult {
+    /// Fully assembled prompt context, exactly as cortex_process injects it
+    pub prompt_context: String,
+
+    /// Context object cortex_process would hand to the engine
+    pub context: Option<serde_json::Value>,
+
+    /// Prompts that would be loaded and injected
+    pub loaded_prompts: Vec<String>,
+
+    /// Number of instructions that would be injected
+    pub instructions_count: usize,
+
+    /// Instructions that would be left out to stay within the instruction budget
+    pub instructions_dropped: Vec<String>,
+
+    /// Instruction characters that would be left out (dropped or cut)
+    pub instructions_dropped_chars: usize,
+}
+
 // ============================================================================
 // cortex_feedback - Provide feedback for learning
 // ============================================================================
@@ -299,6 +400,9 @@ pub async fn cortex_feedback(params: CortexFeedbackParams) -> Result<CortexFeedb
 #[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct CortexStatsParams {}
 
+/// Number of recent queries reported by cortex_stats
+pub const RECENT_QUERIES_LIMIT: usize = 10;
+
 /// Output from cortex_stats tool
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct CortexStatsResult {
@@ -310,6 +414,10 @@ pub struct CortexStatsResult {
 
     /// Uptime in seconds
     pub uptime_secs: u64,
+
+    /// Queries recently processed in this client's session, newest first
+    #[serde(default)]
+    pub recent_queries: Vec<String>,
 }
 
 /// Detailed memory statistics
@@ -323,6 +431,41 @@ pub struct MemoryStatsDetail {
 
     /// Procedural memory rules count
     pub procedural_rules: usize,
+
+    /// Vector dimension of the semantic memory embedding model
+    #[serde(default)]
+    pub semantic_dimension: usize,
+
+    /// Semantic memory size on disk in bytes
+    #[serde(default)]
+    pub semantic_index_size_bytes: u64,
+
+    /// Embedding model of the semantic memory
+    #[serde(default)]
+    pub semantic_embedding_model: String,
+}
+
+impl MemoryStatsDetail {
+    /// Extract memory statistics from `CortexEngine::get_stats` output
+    pub fn from_stats(stats: &serde_json::Value) -> Self {
+        let count = |memory: &str, field: &str| {
+            stats.get(memory).and_then(|s| s.get(field)).and_then(|v| v.as_u64()).unwrap_or(0)
+        };
+        let semantic = stats.get("semantic");
+
+        Self {
+            semantic_facts: count("semantic", "total_facts") as usize,
+            episodic_events: count("episodic", "total_episodes") as usize,
+            procedural_rules: count("procedural", "total_rules") as usize,
+            semantic_dimension: count("semantic", "dimension") as usize,
+            semantic_index_size_bytes: count("semantic", "index_size_bytes"),
+            semantic_embedding_model: semantic
+                .and_then(|s| s.get("embedding_model"))
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string(),
+        }
+    }
 }
 
 /// Get CORTEX engine statistics
@@ -331,30 +474,38 @@ pub async fn cortex_stats(_params: CortexStatsParams) -> Result<CortexStatsResul
 
     let stats = engine.get_stats().await;
 
-    // Extract values from JSON
-    let semantic_facts = stats.get("semantic")
-        .and_then(|s| s.get("total_facts"))
-        .and_then(|v| v.as_u64())
-        .unwrap_or(0) as usize;
-
-    let episodic_events = stats.get("episodic")
-        .and_then(|s| s.get("total_episodes"))
-        .and_then(|v| v.as_u64())
-        .unwrap_or(0) as usize;
-
-    let procedural_rules = stats.get("procedural")
-        .and_then(|s| s.get("total_rules"))
-        .and_then(|v| v.as_u64())
-        .unwrap_or(0) as usize;
-
     Ok(CortexStatsResult {
-        memory: MemoryStatsDetail {
-            semantic_facts,
-            episodic_events,
-            procedural_rules,
-        },
+        memory: MemoryStatsDetail::from_stats(&stats),
         status: "running".to_string(),
         uptime_secs: 0, // TODO: Track actual uptime
+        recent_queries: engine.recent_queries(RECENT_QUERIES_LIMIT).await,
+    })
+}
+
+// ============================================================================
+// cortex_abort - Stop the processing in progress
+// ============================================================================
+
+/// Input parameters for cortex_abort (empty - no params needed)
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct CortexAbortParams {}
+
+/// Output from cortex_abort tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CortexAbortResult {
+    /// Whether a cortex_process call was in progress and is now stopping
+    pub aborted: bool,
+}
+
+/// Stop the cortex_process calls in progress
+///
+/// They stop at their next step boundary and return partial results with
+/// stopped_reason "aborted".
+pub async fn cortex_abort(_params: CortexAbortParams) -> Result<CortexAbortResult> {
+    let engine = get_cortex()?;
+
+    Ok(CortexAbortResult {
+        aborted: engine.abort_current(),
     })
 }
 
@@ -363,15 +514,17 @@ pub async fn cortex_stats(_params: CortexStatsParams) -> Result<CortexStatsResul
 // ============================================================================
 
 /// Input parameters for cortex_cleanup tool
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct CortexCleanupParams {
-    /// Number of days to retain data (default: 30)
-    #[serde(default = "default_retention_days")]
-    pub retention_days: i64,
-}
+    /// Days to retain episodic events, overriding the configured window
+    /// (default: CortexConfig::episodic_retention_days, 30 days).
+    /// Semantic and procedural memory follow their own configured windows.
+    #[serde(default)]
+    pub retention_days: Option<i64>,
 
-fn default_retention_days() -> i64 {
-    30
+    /// Compact the episodic store after cleanup to reclaim disk space (default: false)
+    #[serde(default)]
+    pub compact: bool,
 }
 
 /// Output from cortex_cleanup tool
@@ -380,6 +533,26 @@ pub struct CortexCleanupResult {
     /// Number of items cleaned up
     pub cleaned_count: usize,
 
+    /// Episodic events removed
+    #[serde(default)]
+    pub episodic_cleaned: usize,
+
+    /// Semantic facts removed
+    #[serde(default)]
+    pub semantic_cleaned: usize,
+
+    /// Procedural rules removed
+    #[serde(default)]
+    pub procedural_cleaned: usize,
+
+    /// Bytes reclaimed by compacting the episodic store
+    #[serde(default)]
+    pub bytes_reclaimed: u64,
+
+    /// Number of soft-deleted memories purged
+    #[serde(default)]
+    pub purged_memories: usize,
+
     /// Message
     pub message: String,
 }
@@ -388,15 +561,32 @@ pub struct CortexCleanupResult {
 pub async fn cortex_cleanup(params: CortexCleanupParams) -> Result<CortexCleanupResult> {
     let engine = get_cortex()?;
 
-    let cleaned = engine.cleanup(params.retention_days).await?;
+    let report = engine.cleanup(params.retention_days, params.compact).await?;
 
-    Ok(CortexCleanupResult {
-        cleaned_count: cleaned,
-        message: format!(
-            "Cleaned {} old records (retention: {} days)",
-            cleaned, params.retention_days
-        ),
-    })
+    Ok(CortexCleanupResult::from_report(report, 0))
+}
+
+impl CortexCleanupResult {
+    /// Build the tool result from a cleanup report and the purged memory count
+    pub fn from_report(report: CleanupReport, purged_memories: usize) -> Self {
+        Self {
+            cleaned_count: report.total(),
+            episodic_cleaned: report.episodic,
+            semantic_cleaned: report.semantic,
+            procedural_cleaned: report.procedural,
+            bytes_reclaimed: report.bytes_reclaimed,
+            purged_memories,
+            message: format!(
+                "Cleaned {} old records by retention window ({} episodic, {} semantic, {} procedural), purged {} deleted memories, reclaimed {} bytes",
+                report.total(),
+                report.episodic,
+                report.semantic,
+                report.procedural,
+                purged_memories,
+                report.bytes_reclaimed
+            ),
+        }
+    }
 }
 
 // ============================================================================
@@ -496,6 +686,10 @@ pub struct InstructionInfo {
 
     /// ApplyTo pattern from frontmatter
     pub apply_to: Option<String>,
+
+    /// Priority (higher = injected first); list order follows injection order
+    #[serde(default)]
+    pub priority: i32,
 }
 
 /// Output from cortex_instructions tool
@@ -522,6 +716,86 @@ pub struct CortexInstructionsResult {
     pub message: String,
 }
 
+// ============================================================================
+// user_instructions_list / user_instructions_delete - Per-user preferences
+// ============================================================================
+
+/// Document key prefix of a user's stored instructions
+pub fn user_instruction_prefix(user_id: &str) -> String {
+    format!("user_instruction:{}:", user_id)
+}
+
+/// Document key of a stored user instruction
+pub fn user_instruction_key(user_id: &str, key: &str) -> String {
+    format!("{}{}", user_instruction_prefix(user_id), key)
+}
+
+/// Input parameters for user_instructions_list tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct UserInstructionsListParams {
+    /// User/session ID the instructions were saved for (default: "default")
+    #[serde(default = "default_user_id")]
+    pub user_id: String,
+}
+
+fn default_user_id() -> String {
+    "default".to_string()
+}
+
+/// A stored user instruction
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct UserInstructionInfo {
+    /// Instruction key (e.g., "language", "style")
+    pub key: String,
+
+    /// Instruction value/content
+    pub value: String,
+
+    /// Category
+    pub category: String,
+
+    /// Priority (higher = applied first)
+    pub priority: i32,
+
+    /// Last update time (RFC 3339)
+    #[serde(default)]
+    pub updated_at: Option<String>,
+}
+
+/// Output from user_instructions_list tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct UserInstructionsListResult {
+    /// User/session ID
+    pub user_id: String,
+
+    /// Number of instructions
+    pub count: usize,
+
+    /// Stored instructions, ordered by key
+    pub instructions: Vec<UserInstructionInfo>,
+}
+
+/// Input parameters for user_instructions_delete tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct UserInstructionsDeleteParams {
+    /// User/session ID the instruction was saved for (default: "default")
+    #[serde(default = "default_user_id")]
+    pub user_id: String,
+
+    /// Instruction key to delete
+    pub key: String,
+}
+
+/// Output from user_instructions_delete tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct UserInstructionsDeleteResult {
+    /// Whether an instruction was deleted
+    pub deleted: bool,
+
+    /// Message
+    pub message: String,
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -538,6 +812,10 @@ mod tests {
             inject_doubt: true,
             file_path: Some("src/main.rs".to_string()),
             inject_instructions: true,
+            response_language: Some("fr".to_string()),
+            max_instructions_chars: None,
+            user_id: None,
+            timeout_secs: None,
         };
 
         assert_eq!(params.query, "Test query");
@@ -573,8 +851,9 @@ mod tests {
         let json = r#"{}"#;
         let params: CortexCleanupParams = serde_json::from_str(json).unwrap();
 
-        // Default should be 30 days
-        assert_eq!(params.retention_days, 30);
+        // Default defers to the configured per-type retention windows
+        assert!(params.retention_days.is_none());
+        assert!(!params.compact);
     }
 
     #[test]
@@ -589,6 +868,46 @@ mod tests {
         assert!(params.task_type.is_none());
         assert!(params.language.is_none());
         assert!(params.file_path.is_none());
+        assert!(params.response_language.is_none());
+    }
+
+    #[test]
+    fn test_user_instruction_keys() {
+        assert_eq!(user_instruction_prefix("alice"), "user_instruction:alice:");
+        assert_eq!(user_instruction_key("alice", "tone"), "user_instruction:alice:tone");
+
+        let params: UserInstructionsListParams = serde_json::from_str("{}").unwrap();
+        assert_eq!(params.user_id, "default");
+    }
+
+    #[test]
+    fn test_response_language_instruction() {
+        let instruction = response_language_instruction(Some("French")).unwrap();
+        assert!(instruction.contains("Respond only in French"));
+
+        assert!(response_language_instruction(None).is_none());
+        assert!(response_language_instruction(Some("  ")).is_none());
+    }
+
+    #[test]
+    fn test_memory_stats_detail_from_stats() {
+        let stats = serde_json::json!({
+            "semantic": {
+                "total_facts": 12,
+                "dimension": 768,
+                "index_size_bytes": 4096,
+                "embedding_model": "BAAI/bge-base-en-v1.5",
+            },
+            "episodic": { "total_episodes": 3 },
+        });
+
+        let detail = MemoryStatsDetail::from_stats(&stats);
+        assert_eq!(detail.semantic_facts, 12);
+        assert_eq!(detail.episodic_events, 3);
+        assert_eq!(detail.procedural_rules, 0);
+        assert_eq!(detail.semantic_dimension, 768);
+        assert_eq!(detail.semantic_index_size_bytes, 4096);
+        assert_eq!(detail.semantic_embedding_model, "BAAI/bge-base-en-v1.5");
     }
 
     #[test]
@@ -606,6 +925,10 @@ mod tests {
             session_id: None,
             loaded_prompts: vec![],
             instructions_count: 5,
+            instructions_dropped: vec![],
+            instructions_dropped_chars: 0,
+            stopped_reason: StoppedReason::Completed,
+            decision_trace: vec![],
         };
 
         assert_eq!(result.instructions_count, 5);
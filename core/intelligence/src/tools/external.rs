@@ -6,6 +6,8 @@
 //! - external_search: Web search via Tavily
 //! - external_call: Generic tool call to any connected MCP server
 
+use crate::integrations::CodeSnippet;
+pub use crate::config::DocsFallbackMode;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -83,6 +85,69 @@ pub struct ExternalDocsParams {
     /// Source preference: "context7", "mslearn", "auto"
     #[serde(default = "default_source")]
     pub source: String,
+
+    /// Provider order to try (e.g., ["mslearn", "context7"]); overrides `source` and the configured order
+    #[serde(default)]
+    pub providers: Vec<String>,
+
+    /// How to combine providers (default: configured mode)
+    #[serde(default)]
+    pub mode: Option<DocsFallbackMode>,
+
+    /// Maximum characters kept per provider (default: `max_tokens` * 4)
+    #[serde(default)]
+    pub max_chars: Option<usize>,
+}
+
+impl ExternalDocsParams {
+    /// Character budget applied to each provider's content
+    pub fn char_budget(&self) -> usize {
+        self.max_chars
+            .unwrap_or(self.max_tokens as usize * CHARS_PER_TOKEN)
+    }
+}
+
+/// Rough characters-per-token ratio used to turn `max_tokens` into a char budget
+pub const CHARS_PER_TOKEN: usize = 4;
+
+/// Marker appended to truncated documentation
+const TRUNCATION_MARKER: &str = "\n\n[... truncated]";
+
+/// Truncate markdown to at most `max_chars` characters at a readable boundary
+///
+/// Prefers cutting before a heading, then at a paragraph break, then at a line
+/// or word break, as long as that keeps at least half the budget. An open code
+/// fence is closed. Returns `None` when the content already fits.
+pub fn truncate_at_boundary(content: &str, max_chars: usize) -> Option<String> {
+    if content.chars().count() <= max_chars {
+        return None;
+    }
+
+    let limit = content
+        .char_indices()
+        .nth(max_chars)
+        .map(|(i, _)| i)
+        .unwrap_or(content.len());
+    let window = &content[..limit];
+    let min_keep = limit / 2;
+
+    let cut = [
+        window.rfind("\n#"),
+        window.rfind("\n\n"),
+        window.rfind('\n'),
+        window.rfind(char::is_whitespace),
+    ]
+    .into_iter()
+    .flatten()
+    .find(|&i| i >= min_keep)
+    .unwrap_or(limit);
+
+    let mut truncated = content[..cut].trim_end().to_string();
+    if truncated.lines().filter(|l| l.trim_start().starts_with("```")).count() % 2 == 1 {
+        truncated.push_str("\n```");
+    }
+    truncated.push_str(TRUNCATION_MARKER);
+    Some(truncated)
 }
 
 fn default_tokens() -> u32 {
@@ -93,6 +158,20 @@ fn default_source() -> String {
     "auto".to_string()
 }
 
+impl Default for ExternalDocsParams {
+    fn default() -> Self {
+        Self {
+            library: String::new(),
+            topic: None,
+            max_tokens: default_tokens(),
+            source: default_source(),
+            providers: Vec::new(),
+            mode: None,
+            max_chars: None,
+        }
+    }
+}
+
 /// Result from external documentation
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ExternalDocsResult {
@@ -105,14 +184,26 @@ pub struct ExternalDocsResult {
     /// Documentation content
     pub content: String,
 
-    /// Code snippets extracted
-    pub code_snippets: Vec<String>,
+    /// Code snippets extracted, with language and source page
+    pub code_snippets: Vec<CodeSnippet>,
 
     /// Source URL
     pub url: Option<String>,
 
     /// Provider name
     pub provider: String,
+
+    /// Library version the content was taken from, if known
+    #[serde(default)]
+    pub version: Option<String>,
+
+    /// Whether any provider's content was cut to fit the budget
+    #[serde(default)]
+    pub truncated: bool,
+
+    /// Providers queried, in order
+    #[serde(default)]
+    pub providers_tried: Vec<String>,
 }
 
 // =============================================================================
@@ -222,6 +313,38 @@ pub struct ExternalMcpCallResult {
     pub error: Option<String>,
 }
 
+// =============================================================================
+// Batch External MCP Tool Call
+// =============================================================================
+
+/// Parameters for calling several external MCP tools concurrently
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ExternalMcpCallBatchParams {
+    /// Calls to execute (results are returned in the same order)
+    pub calls: Vec<ExternalMcpCallParams>,
+
+    /// Maximum number of calls in flight at once (default: 4)
+    #[serde(default = "default_max_concurrency")]
+    pub max_concurrency: usize,
+}
+
+fn default_max_concurrency() -> usize {
+    4
+}
+
+/// Result from a batch of external MCP calls
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ExternalMcpCallBatchResult {
+    /// Individual results, in the order the calls were given
+    pub results: Vec<ExternalMcpCallResult>,
+
+    /// Number of calls that succeeded
+    pub succeeded: usize,
+
+    /// Number of calls that failed
+    pub failed: usize,
+}
+
 // =============================================================================
 // MCP Status Tool
 // =============================================================================
@@ -443,7 +566,7 @@ pub struct McpInstallParams {
     /// Package name (npm: @org/package, pip: package-name)
     pub package: String,
 
-    /// Package type: "npm", "pip", "binary"
+    /// Package type: "npm" or "pip"
     #[serde(default = "default_npm")]
     pub package_type: String,
 
@@ -628,3 +751,38 @@ pub struct McpListInstalledResult {
     /// Installed servers
     pub servers: Vec<McpServerInfo>,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_truncate_at_boundary() {
+        let content = "# Intro\n\nShort paragraph.\n\n## Usage\n\nThis section is much longer than the budget allows.";
+        assert!(truncate_at_boundary(content, 1000).is_none());
+
+        let truncated = truncate_at_boundary(content, 40).unwrap();
+        assert!(truncated.starts_with("# Intro\n\nShort paragraph."));
+        assert!(!truncated.contains("## Usage"));
+        assert!(truncated.ends_with(TRUNCATION_MARKER));
+    }
+
+    #[test]
+    fn test_truncate_closes_code_fence() {
+        let content = format!("Example:\n\n```rust\n{}\n```", "let x = 1;\n".repeat(50));
+        let truncated = truncate_at_boundary(&content, 100).unwrap();
+
+        let fences = truncated.lines().filter(|l| l.starts_with("```")).count();
+        assert_eq!(fences % 2, 0);
+    }
+
+    #[test]
+    fn test_char_budget_defaults_to_tokens() {
+        let params: ExternalDocsParams = serde_json::from_str(r#"{"library": "tokio", "max_tokens": 100}"#).unwrap();
+        assert_eq!(params.char_budget(), 400);
+
+        let params: ExternalDocsParams =
+            serde_json::from_str(r#"{"library": "tokio", "max_chars": 250}"#).unwrap();
+        assert_eq!(params.char_budget(), 250);
+    }
+}
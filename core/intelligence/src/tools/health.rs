@@ -0,0 +1,137 @@
+//! Health Tool for WhytCard Intelligence
+//!
+//! Reports subsystem status independently of the transport, so stdio clients
+//! get the same view as SSE clients.
+
+use crate::metrics::ToolPayloadStats;
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+// =============================================================================
+// Health Tool
+// =============================================================================
+
+/// Parameters for health (empty - no params needed)
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct HealthParams {}
+
+/// Status of a single subsystem
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SubsystemHealth {
+    /// Subsystem name (database, rag, cortex, data_dir, integrations, mcp_clients)
+    pub name: String,
+
+    /// Whether the subsystem is usable
+    pub healthy: bool,
+
+    /// Whether the server cannot work without it
+    pub critical: bool,
+
+    /// Human-readable detail
+    pub detail: String,
+}
+
+impl SubsystemHealth {
+    /// Healthy subsystem
+    pub fn ok(name: &str, critical: bool, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            healthy: true,
+            critical,
+            detail: detail.into(),
+        }
+    }
+
+    /// Unhealthy subsystem
+    pub fn failed(name: &str, critical: bool, detail: impl Into<String>) -> Self {
+        Self {
+            name: name.to_string(),
+            healthy: false,
+            critical,
+            detail: detail.into(),
+        }
+    }
+}
+
+/// Result of health
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct HealthResult {
+    /// "ok", "degraded" (non-critical failures) or "unhealthy" (critical failures)
+    pub status: String,
+
+    /// Whether every critical subsystem is healthy
+    pub ready: bool,
+
+    /// Per-subsystem status
+    pub subsystems: Vec<SubsystemHealth>,
+
+    /// Serialized request/response sizes per tool, heaviest responses first
+    #[serde(default)]
+    pub tool_payloads: Vec<ToolPayloadStats>,
+
+    /// Clients currently connected over SSE
+    #[serde(default)]
+    pub active_clients: usize,
+
+    /// Maximum concurrent SSE clients (0 = unlimited)
+    #[serde(default)]
+    pub max_clients: usize,
+}
+
+impl HealthResult {
+    /// Summarize subsystem checks into an overall status
+    pub fn from_subsystems(subsystems: Vec<SubsystemHealth>) -> Self {
+        let ready = subsystems.iter().all(|s| s.healthy || !s.critical);
+        let status = if !ready {
+            "unhealthy"
+        } else if subsystems.iter().all(|s| s.healthy) {
+            "ok"
+        } else {
+            "degraded"
+        };
+
+        Self {
+            status: status.to_string(),
+            ready,
+            subsystems,
+            tool_payloads: Vec::new(),
+            active_clients: 0,
+            max_clients: 0,
+        }
+    }
+
+    /// Critical subsystems that failed
+    pub fn critical_failures(&self) -> impl Iterator<Item = &SubsystemHealth> {
+        self.subsystems.iter().filter(|s| s.critical && !s.healthy)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_health_status_summary() {
+        let ok = HealthResult::from_subsystems(vec![
+            SubsystemHealth::ok("database", true, "reachable"),
+            SubsystemHealth::ok("integrations", false, "tavily ready"),
+        ]);
+        assert_eq!(ok.status, "ok");
+        assert!(ok.ready);
+
+        let degraded = HealthResult::from_subsystems(vec![
+            SubsystemHealth::ok("database", true, "reachable"),
+            SubsystemHealth::failed("mcp_clients", false, "1 failed"),
+        ]);
+        assert_eq!(degraded.status, "degraded");
+        assert!(degraded.ready);
+
+        let unhealthy = HealthResult::from_subsystems(vec![
+            SubsystemHealth::failed("database", true, "query failed"),
+        ]);
+        assert_eq!(unhealthy.status, "unhealthy");
+        assert!(!unhealthy.ready);
+        assert_eq!(unhealthy.critical_failures().count(), 1);
+    }
+}
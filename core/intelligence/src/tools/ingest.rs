@@ -0,0 +1,76 @@
+//! Ingest Tool for WhytCard Intelligence
+//!
+//! Stores a text as a memory together with the entities and relations
+//! extracted from it, in one transaction. Each entity records the memory key
+//! as an observation and the memory lists its entities in its metadata, so
+//! unstructured and structured knowledge point at each other.
+
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use super::pipelines::{EntityDef, EntityResult, RelationDef, RelationResult};
+
+/// Prefix of the observation linking an entity to the memory it came from
+pub const SOURCE_OBSERVATION_PREFIX: &str = "Source memory: ";
+
+/// Metadata field of the memory listing its entities
+pub const ENTITIES_METADATA_KEY: &str = "entities";
+
+fn default_true() -> bool {
+    true
+}
+
+/// Parameters for ingest tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct IngestParams {
+    /// Text to store as a memory
+    pub content: String,
+
+    /// Memory key (auto-generated if not provided)
+    #[serde(default)]
+    pub key: Option<String>,
+
+    /// Optional title/summary
+    #[serde(default)]
+    pub title: Option<String>,
+
+    /// Optional tags for categorization
+    #[serde(default)]
+    pub tags: Vec<String>,
+
+    /// Optional metadata as a JSON object
+    #[serde(default)]
+    pub metadata: Option<serde_json::Value>,
+
+    /// Entities extracted from the text; an existing entity with the same
+    /// name and type gets the observations instead
+    #[serde(default)]
+    pub entities: Vec<EntityDef>,
+
+    /// Relations between these entities or entities already in the graph
+    #[serde(default)]
+    pub relations: Vec<RelationDef>,
+
+    /// Whether to index the memory for semantic search (default: true)
+    #[serde(default = "default_true")]
+    pub index: bool,
+}
+
+/// Result from ingest
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct IngestResult {
+    /// Key of the stored memory
+    pub key: String,
+
+    /// Whether the memory was indexed for semantic search
+    pub indexed: bool,
+
+    /// Timestamp when stored
+    pub stored_at: i64,
+
+    /// Entities created or updated
+    pub entities: Vec<EntityResult>,
+
+    /// Relations created (or found already existing)
+    pub relations: Vec<RelationResult>,
+}
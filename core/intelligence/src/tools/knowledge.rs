@@ -22,6 +22,10 @@ pub struct KnowledgeAddEntityParams {
 /// Result from knowledge_add_entity
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct KnowledgeAddEntityResult {
+    /// Record ID (e.g. "entity:abc123"), usable without re-resolving the name
+    #[serde(default)]
+    pub id: String,
+
     /// Entity name
     pub name: String,
 
@@ -56,7 +60,7 @@ pub struct KnowledgeAddObservationResult {
 }
 
 /// Parameters for knowledge_add_relation tool
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct KnowledgeAddRelationParams {
     /// Source entity name
     pub from: String,
@@ -66,6 +70,10 @@ pub struct KnowledgeAddRelationParams {
 
     /// Relation type (e.g., "works_with", "knows", "depends_on")
     pub relation_type: String,
+
+    /// Treat the relation as bidirectional during traversal (e.g., "related_to", "synonym_of")
+    #[serde(default)]
+    pub symmetric: bool,
 }
 
 /// Result from knowledge_add_relation
@@ -82,6 +90,10 @@ pub struct KnowledgeAddRelationResult {
 
     /// Whether this was a new relation
     pub created: bool,
+
+    /// Whether an identical relation (same endpoints and type) already existed
+    #[serde(default)]
+    pub already_existed: bool,
 }
 
 /// Parameters for knowledge_search tool
@@ -93,6 +105,29 @@ pub struct KnowledgeSearchParams {
     /// Maximum entities to return
     #[serde(default = "default_limit")]
     pub limit: usize,
+
+    /// Number of matching entities to skip (for paging)
+    #[serde(default)]
+    pub offset: usize,
+
+    /// Matching mode: "name" (substring on names) or "semantic" (name matches plus embedding similarity)
+    #[serde(default)]
+    pub mode: KnowledgeSearchMode,
+
+    /// Minimum cosine similarity for semantic matches (0.0 - 1.0, default: 0.5)
+    #[serde(default = "default_similarity_threshold")]
+    pub similarity_threshold: f32,
+}
+
+/// Matching mode for knowledge_search
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum KnowledgeSearchMode {
+    /// Substring match on entity names
+    #[default]
+    Name,
+    /// Name matches merged with entities whose name and observations embed close to the query
+    Semantic,
 }
 
 /// Result from knowledge_search
@@ -103,6 +138,14 @@ pub struct KnowledgeSearchResult {
 
     /// Relations between matching entities
     pub relations: Vec<RelationInfo>,
+
+    /// Total number of matching entities
+    #[serde(default)]
+    pub total: usize,
+
+    /// Whether more entities exist after this page
+    #[serde(default)]
+    pub has_more: bool,
 }
 
 /// Entity information
@@ -200,20 +243,24 @@ pub struct KnowledgeDeleteRelationResult {
 }
 
 /// Parameters for knowledge_read_graph tool (read entire graph)
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct KnowledgeReadGraphParams {
-    /// Maximum entities to return (0 = all)
+    /// Maximum entities to return (default: all)
     #[serde(default)]
-    pub limit: usize,
+    pub limit: Option<usize>,
+
+    /// Number of entities to skip (for paging)
+    #[serde(default)]
+    pub offset: usize,
 }
 
 /// Result from knowledge_read_graph
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct KnowledgeReadGraphResult {
-    /// All entities
+    /// Entities in this page, ordered by name
     pub entities: Vec<EntityInfo>,
 
-    /// All relations
+    /// Relations leaving the entities in this page
     pub relations: Vec<RelationInfo>,
 
     /// Total entity count
@@ -221,6 +268,10 @@ pub struct KnowledgeReadGraphResult {
 
     /// Total relation count
     pub total_relations: usize,
+
+    /// Whether more entities exist after this page
+    #[serde(default)]
+    pub has_more: bool,
 }
 
 // ============================================================================
@@ -307,6 +358,10 @@ pub struct KnowledgeGetNeighborsParams {
     /// Filter by relation types (empty = all)
     #[serde(default)]
     pub relation_types: Vec<String>,
+
+    /// Stop traversal after visiting this many entities (default: server config)
+    #[serde(default)]
+    pub max_nodes_visited: Option<usize>,
 }
 
 /// A neighbor with path information
@@ -333,6 +388,10 @@ pub struct KnowledgeGetNeighborsResult {
 
     /// Total neighbors found
     pub total: usize,
+
+    /// Whether traversal stopped early at the node visit cap
+    #[serde(default)]
+    pub truncated: bool,
 }
 
 /// Parameters for knowledge_find_path tool
@@ -347,6 +406,10 @@ pub struct KnowledgeFindPathParams {
     /// Maximum path length (default: 5)
     #[serde(default = "default_max_depth")]
     pub max_depth: usize,
+
+    /// Stop the search after visiting this many entities (default: server config)
+    #[serde(default)]
+    pub max_nodes_visited: Option<usize>,
 }
 
 /// Result from knowledge_find_path
@@ -360,6 +423,10 @@ pub struct KnowledgeFindPathResult {
 
     /// Path length
     pub length: usize,
+
+    /// Whether the search stopped early at the node visit cap
+    #[serde(default)]
+    pub truncated: bool,
 }
 
 // Default helpers
@@ -383,6 +450,44 @@ fn default_max_depth() -> usize {
     5
 }
 
+fn default_similarity_threshold() -> f32 {
+    0.5
+}
+
+impl Default for KnowledgeSearchParams {
+    fn default() -> Self {
+        Self {
+            query: String::new(),
+            limit: default_limit(),
+            offset: 0,
+            mode: KnowledgeSearchMode::default(),
+            similarity_threshold: default_similarity_threshold(),
+        }
+    }
+}
+
+impl Default for KnowledgeGetNeighborsParams {
+    fn default() -> Self {
+        Self {
+            entity_name: String::new(),
+            max_depth: default_depth(),
+            relation_types: Vec::new(),
+            max_nodes_visited: None,
+        }
+    }
+}
+
+impl Default for KnowledgeFindPathParams {
+    fn default() -> Self {
+        Self {
+            from: String::new(),
+            to: String::new(),
+            max_depth: default_max_depth(),
+            max_nodes_visited: None,
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -412,6 +517,7 @@ mod tests {
                 from: from.into(),
                 to: to.into(),
                 relation_type: relation_type.into(),
+                symmetric: false,
             }
         }
     }
@@ -421,6 +527,9 @@ mod tests {
             Self {
                 query: query.into(),
                 limit: super::default_limit(),
+                offset: 0,
+                mode: KnowledgeSearchMode::default(),
+                similarity_threshold: super::default_similarity_threshold(),
             }
         }
 
@@ -428,6 +537,12 @@ mod tests {
             self.limit = limit;
             self
         }
+
+        fn semantic(mut self, threshold: f32) -> Self {
+            self.mode = KnowledgeSearchMode::Semantic;
+            self.similarity_threshold = threshold;
+            self
+        }
     }
 
     #[test]
@@ -456,6 +571,19 @@ mod tests {
 
         assert_eq!(params.query, "software");
         assert_eq!(params.limit, 20);
+        assert_eq!(params.mode, KnowledgeSearchMode::Name);
+    }
+
+    #[test]
+    fn test_search_params_semantic_mode() {
+        let params = KnowledgeSearchParams::new("error handling").semantic(0.7);
+        assert_eq!(params.mode, KnowledgeSearchMode::Semantic);
+        assert_eq!(params.similarity_threshold, 0.7);
+
+        let parsed: KnowledgeSearchParams =
+            serde_json::from_str(r#"{"query": "x", "mode": "semantic"}"#).unwrap();
+        assert_eq!(parsed.mode, KnowledgeSearchMode::Semantic);
+        assert_eq!(parsed.similarity_threshold, 0.5);
     }
 
     #[test]
@@ -55,7 +55,7 @@ pub struct MemorySearchParams {
     #[serde(default = "default_limit")]
     pub limit: usize,
 
-    /// Minimum similarity score (0.0 - 1.0)
+    /// Minimum similarity score (0.0 - 1.0, default: configured `rag.min_relevance`)
     #[serde(default)]
     pub min_score: Option<f32>,
 
@@ -97,6 +97,67 @@ pub struct MemorySearchResult {
 
     /// Query used
     pub query: String,
+
+    /// Semantic search failed and these are full-text matches, scored by
+    /// BM25 rather than similarity
+    #[serde(default)]
+    pub fallback: bool,
+}
+
+/// Parameters for memory_search_explain tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MemorySearchExplainParams {
+    /// Search query (semantic search)
+    pub query: String,
+
+    /// Maximum number of results (default: 10)
+    #[serde(default = "default_limit")]
+    pub limit: usize,
+}
+
+/// A search hit with the numbers behind its rank
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MemorySearchExplainItem {
+    /// Memory key
+    pub key: String,
+
+    /// Position of the chunk within the memory
+    pub chunk_index: usize,
+
+    /// Chunk content
+    pub content: String,
+
+    /// Raw distance between the query and chunk embeddings
+    pub distance: f32,
+
+    /// Similarity derived from the distance (1 - distance)
+    pub vector_score: f32,
+
+    /// Final score used for ranking (the rerank score when reranking is on)
+    pub score: f32,
+
+    /// Chunk metadata as stored in the index
+    pub metadata: Option<serde_json::Value>,
+}
+
+/// Result from memory_search_explain
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MemorySearchExplainResult {
+    /// Query used
+    pub query: String,
+
+    /// Euclidean norm of the query embedding (about 1.0 for normalized models)
+    pub query_norm: f32,
+
+    /// Chunks the vector search examined
+    pub candidates_examined: usize,
+
+    /// HNSW search breadth (ef) used; null when every candidate was scored
+    /// exactly
+    pub ef: Option<usize>,
+
+    /// Hits, best first
+    pub results: Vec<MemorySearchExplainItem>,
 }
 
 /// Parameters for memory_get tool
@@ -132,10 +193,14 @@ pub struct MemoryGetResult {
 }
 
 /// Parameters for memory_delete tool
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct MemoryDeleteParams {
     /// Key of the memory to delete
     pub key: String,
+
+    /// Delete permanently instead of moving to the trash (default: false)
+    #[serde(default)]
+    pub permanent: bool,
 }
 
 /// Result from memory_delete
@@ -146,6 +211,30 @@ pub struct MemoryDeleteResult {
 
     /// Whether deletion was successful
     pub deleted: bool,
+
+    /// Whether the memory was soft-deleted and can still be restored
+    #[serde(default)]
+    pub restorable: bool,
+}
+
+/// Parameters for memory_restore tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MemoryRestoreParams {
+    /// Key of the soft-deleted memory to restore
+    pub key: String,
+}
+
+/// Result from memory_restore
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MemoryRestoreResult {
+    /// Key that was restored
+    pub key: String,
+
+    /// Whether the memory was restored
+    pub restored: bool,
+
+    /// Whether the memory was re-indexed for semantic search
+    pub indexed: bool,
 }
 
 /// Parameters for memory_list tool
@@ -170,7 +259,7 @@ pub struct MemoryListResult {
     /// List of memory summaries
     pub memories: Vec<MemorySummary>,
 
-    /// Total count
+    /// Total count matching the tag filter, across all pages
     pub total: usize,
 
     /// Whether there are more results
@@ -193,6 +282,60 @@ pub struct MemorySummary {
     pub stored_at: i64,
 }
 
+// ============================================================================
+// EXPORT / IMPORT
+// ============================================================================
+
+/// Parameters for memory_export tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MemoryExportParams {
+    /// Destination file (JSONL, one memory per line), relative to the
+    /// `exports` directory of the data dir
+    pub path: String,
+
+    /// Only export memories with any of these tags (empty = all)
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+/// Result from memory_export
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MemoryExportResult {
+    /// File the memories were written to
+    pub path: String,
+
+    /// Number of memories exported
+    pub exported: usize,
+}
+
+/// Parameters for memory_import tool
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MemoryImportParams {
+    /// Source file (JSONL as produced by memory_export), relative to the
+    /// `exports` directory of the data dir
+    pub path: String,
+
+    /// Re-index imported memories for semantic search (default: true)
+    #[serde(default = "default_true")]
+    pub reindex: bool,
+}
+
+/// Result from memory_import
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MemoryImportResult {
+    /// Memories created
+    pub created: usize,
+
+    /// Existing memories updated (matched by key)
+    pub updated: usize,
+
+    /// Memories re-indexed for semantic search
+    pub indexed: usize,
+
+    /// Errors encountered (malformed lines, failed writes or indexing)
+    pub errors: Vec<String>,
+}
+
 // ============================================================================
 // BATCH STORE (from Python v2.0)
 // ============================================================================
@@ -224,15 +367,21 @@ pub struct BatchStoreItem {
 pub struct BatchStoreParams {
     /// Items to store in batch
     pub items: Vec<BatchStoreItem>,
+
+    /// Only index the items for semantic search, without creating memory
+    /// documents (default: false). For searchable-only content; the items
+    /// are indexed even when RAG auto-indexing is off.
+    #[serde(default)]
+    pub index_only: bool,
 }
 
 /// Result from batch_store
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct BatchStoreResult {
-    /// Number of items successfully stored
+    /// Number of items successfully stored (indexed, with `index_only`)
     pub stored: usize,
 
-    /// Keys assigned to stored items
+    /// Keys assigned to stored items (RAG document IDs, with `index_only`)
     pub keys: Vec<String>,
 
     /// Any errors that occurred
@@ -254,9 +403,9 @@ pub struct HybridSearchParams {
     #[serde(default = "default_limit")]
     pub top_k: usize,
 
-    /// Minimum relevance threshold (default: 0.3)
-    #[serde(default = "default_min_relevance")]
-    pub min_relevance: f32,
+    /// Minimum relevance threshold (0.0 - 1.0, default: configured `rag.min_relevance`)
+    #[serde(default)]
+    pub min_relevance: Option<f32>,
 }
 
 /// A semantic search result item
@@ -331,10 +480,86 @@ pub struct HybridSearchResult {
     /// Knowledge graph results
     pub graph: Vec<serde_json::Value>,
 
+    /// All sources fused into one list via reciprocal-rank fusion, best first
+    #[serde(default)]
+    pub combined: Vec<HybridItem>,
+
     /// Summary of results
     pub summary: String,
 }
 
+/// A single entry in the fused hybrid ranking
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct HybridItem {
+    /// Item ID (document ID, episode ID, rule ID or entity name)
+    pub id: String,
+
+    /// Content snippet or description
+    pub content: String,
+
+    /// Source list: "semantic", "episodic", "procedural" or "graph"
+    pub source: String,
+
+    /// Fused score normalized to 0.0 - 1.0 (1.0 = best item)
+    pub score: f32,
+
+    /// Position in the fused ranking (1-based)
+    pub rank: usize,
+
+    /// Score reported by the source itself, if it has one
+    pub raw_score: Option<f32>,
+}
+
+/// Constant from the reciprocal-rank fusion formula `1 / (k + rank)`
+const RRF_K: f32 = 60.0;
+
+/// Fuse per-source ranked lists with reciprocal-rank fusion.
+///
+/// Each list must already be ordered best first. Items are identified by
+/// source and ID, so a document and an entity that happen to share an ID stay
+/// separate entries; the result is sorted by fused score and normalized so
+/// the top item scores 1.0.
+pub fn reciprocal_rank_fusion(lists: Vec<Vec<HybridItem>>) -> Vec<HybridItem> {
+    let mut fused: Vec<HybridItem> = Vec::new();
+    let mut positions: std::collections::HashMap<String, usize> = std::collections::HashMap::new();
+
+    for list in lists {
+        let mut seen = std::collections::HashSet::new();
+        for item in list {
+            let key = format!("{}:{}", item.source, item.id);
+            // Only the best occurrence of an ID counts within a single list
+            if !seen.insert(key.clone()) {
+                continue;
+            }
+            let contribution = 1.0 / (RRF_K + seen.len() as f32);
+            match positions.get(&key) {
+                Some(&pos) => fused[pos].score += contribution,
+                None => {
+                    positions.insert(key, fused.len());
+                    fused.push(HybridItem {
+                        score: contribution,
+                        ..item
+                    });
+                }
+            }
+        }
+    }
+
+    fused.sort_by(|a, b| {
+        b.score
+            .partial_cmp(&a.score)
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    let top = fused.first().map(|item| item.score).unwrap_or(1.0);
+    for (i, item) in fused.iter_mut().enumerate() {
+        item.score /= top;
+        item.rank = i + 1;
+    }
+
+    fused
+}
+
 // ============================================================================
 // TAG MANAGEMENT (from Python v2.0)
 // ============================================================================
@@ -388,6 +613,10 @@ pub struct GetContextParams {
     /// Type of context gathering: "query", "search", or "session"
     #[serde(default = "default_context_type")]
     pub context_type: String,
+
+    /// Minimum relevance threshold (0.0 - 1.0, default: configured `rag.min_relevance`)
+    #[serde(default)]
+    pub min_relevance: Option<f32>,
 }
 
 /// Aggregated context result
@@ -443,10 +672,6 @@ fn default_category() -> String {
     "general".to_string()
 }
 
-fn default_min_relevance() -> f32 {
-    0.3
-}
-
 fn default_context_type() -> String {
     "query".to_string()
 }
@@ -534,6 +759,37 @@ mod tests {
         assert_eq!(params.min_score, Some(0.7));
     }
 
+    fn hybrid_item(id: &str, source: &str) -> HybridItem {
+        HybridItem {
+            id: id.to_string(),
+            content: String::new(),
+            source: source.to_string(),
+            score: 0.0,
+            rank: 0,
+            raw_score: None,
+        }
+    }
+
+    #[test]
+    fn test_reciprocal_rank_fusion() {
+        let fused = reciprocal_rank_fusion(vec![
+            vec![hybrid_item("a", "semantic"), hybrid_item("b", "semantic"), hybrid_item("a", "semantic")],
+            vec![hybrid_item("c", "episodic"), hybrid_item("b", "episodic")],
+        ]);
+
+        // The same ID from two sources is two items
+        let entries: Vec<(&str, &str)> = fused.iter().map(|i| (i.id.as_str(), i.source.as_str())).collect();
+        assert_eq!(entries, vec![("a", "semantic"), ("c", "episodic"), ("b", "semantic"), ("b", "episodic")]);
+        assert_eq!(fused[0].score, 1.0);
+        assert_eq!(fused[0].rank, 1);
+        assert_eq!(fused[0].score, fused[1].score);
+        assert!(fused[2].score < 1.0);
+        assert_eq!(fused[2].score, fused[3].score);
+        assert_eq!(fused[3].rank, 4);
+
+        assert!(reciprocal_rank_fusion(vec![Vec::new()]).is_empty());
+    }
+
     #[test]
     fn test_serialization() {
         let params = MemoryStoreParams::new("content");
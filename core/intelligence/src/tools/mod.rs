@@ -20,13 +20,19 @@
 pub mod pipelines;
 
 // Atomic Tools (internal implementation)
+pub mod capabilities;
 pub mod cortex;
 pub mod external;
+pub mod health;
+pub mod ingest;
 pub mod knowledge;
 pub mod memory;
 
 // Re-export atomic tools for internal use
+pub use capabilities::*;
 pub use cortex::*;
 pub use external::*;
+pub use health::*;
+pub use ingest::*;
 pub use knowledge::*;
 pub use memory::*;
@@ -10,6 +10,7 @@
 //! 4. context7: doc officielle lib/framework
 //! 5. tavily: best practices actuelles
 
+use crate::integrations::CodeSnippet;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
@@ -59,9 +60,9 @@ pub struct AnalyzeParams {
     #[serde(default = "default_max_per_source")]
     pub max_per_source: usize,
 
-    /// Minimum relevance score (0.0-1.0, default: 0.3)
-    #[serde(default = "default_min_score")]
-    pub min_score: f32,
+    /// Minimum relevance score (0.0-1.0, default: configured `rag.min_relevance`)
+    #[serde(default)]
+    pub min_score: Option<f32>,
 
     /// Tags to filter memory search
     #[serde(default)]
@@ -70,6 +71,86 @@ pub struct AnalyzeParams {
     /// File path context for filtering instructions
     #[serde(default)]
     pub file_path: Option<String>,
+
+    /// Collapse near-identical web results, keeping the highest-scored (default: true)
+    #[serde(default = "default_true")]
+    pub dedup_web: bool,
+
+    /// Cosine similarity above which two web results count as duplicates (default: 0.9)
+    #[serde(default = "default_dedup_threshold")]
+    pub dedup_threshold: f32,
+
+    /// Trust placed in each source when computing confidence
+    #[serde(default)]
+    pub source_weights: SourceWeights,
+}
+
+/// Trust placed in each source when computing `AnalyzeResult::confidence`
+///
+/// Each result adds `weight * score^2` of evidence (results without a score
+/// count as 1.0), so a few authoritative, high-score results outweigh many
+/// weak web hits.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize, JsonSchema)]
+#[serde(default)]
+pub struct SourceWeights {
+    /// Memory results (default: 0.7)
+    pub memory: f32,
+    /// Knowledge graph entities (default: 0.7)
+    pub knowledge: f32,
+    /// Official documentation (Context7) (default: 0.8)
+    pub docs: f32,
+    /// Web search results (default: 0.35)
+    pub web: f32,
+    /// Microsoft Learn documentation (default: 0.8)
+    pub microsoft: f32,
+}
+
+impl Default for SourceWeights {
+    fn default() -> Self {
+        Self {
+            memory: 0.7,
+            knowledge: 0.7,
+            docs: 0.8,
+            web: 0.35,
+            microsoft: 0.8,
+        }
+    }
+}
+
+impl SourceWeights {
+    /// Weight of `source`, clamped to 0.0-1.0
+    pub fn weight(&self, source: AnalyzeSource) -> f32 {
+        let weight = match source {
+            AnalyzeSource::Memory => self.memory,
+            AnalyzeSource::Knowledge => self.knowledge,
+            AnalyzeSource::Docs => self.docs,
+            AnalyzeSource::Web => self.web,
+            AnalyzeSource::Microsoft => self.microsoft,
+        };
+        weight.clamp(0.0, 1.0)
+    }
+}
+
+/// Confidence with no results at all
+const CONFIDENCE_FLOOR: f32 = 0.2;
+
+/// Confidence reached as evidence becomes overwhelming
+const CONFIDENCE_CEILING: f32 = 0.95;
+
+/// Analysis confidence from `(source, score)` per result
+///
+/// Results combine as independent evidence (`1 - prod(1 - weight * score^2)`),
+/// then map onto `CONFIDENCE_FLOOR..CONFIDENCE_CEILING`. Scores are clamped
+/// to 0.0-1.0.
+pub fn analyze_confidence(results: &[(AnalyzeSource, f32)], weights: &SourceWeights) -> f32 {
+    let doubt: f32 = results
+        .iter()
+        .map(|&(source, score)| {
+            let score = score.clamp(0.0, 1.0);
+            1.0 - weights.weight(source) * score * score
+        })
+        .product();
+    CONFIDENCE_FLOOR + (CONFIDENCE_CEILING - CONFIDENCE_FLOOR) * (1.0 - doubt)
 }
 
 fn default_sources() -> Vec<AnalyzeSource> {
@@ -93,8 +174,32 @@ fn default_max_per_source() -> usize {
     5
 }
 
-fn default_min_score() -> f32 {
-    0.3
+fn default_dedup_threshold() -> f32 {
+    0.9
+}
+
+/// Greedy similarity dedup: visit items by descending score and keep each one
+/// unless it is at least `threshold` similar to an item already kept.
+///
+/// Returns the indices of the kept items, best score first.
+pub fn dedup_by_similarity(embeddings: &[Vec<f32>], scores: &[f32], threshold: f32) -> Vec<usize> {
+    let mut order: Vec<usize> = (0..embeddings.len().min(scores.len())).collect();
+    order.sort_by(|&a, &b| {
+        scores[b]
+            .partial_cmp(&scores[a])
+            .unwrap_or(std::cmp::Ordering::Equal)
+    });
+
+    let mut kept: Vec<usize> = Vec::new();
+    for i in order {
+        let duplicate = kept.iter().any(|&k| {
+            whytcard_rag::cosine_similarity(&embeddings[i], &embeddings[k]) >= threshold
+        });
+        if !duplicate {
+            kept.push(i);
+        }
+    }
+    kept
 }
 
 /// A thinking step from sequential thinking
@@ -145,11 +250,14 @@ pub struct DocsResult {
     /// Content snippet
     pub content: String,
     /// Code examples extracted
-    pub code_snippets: Vec<String>,
+    pub code_snippets: Vec<CodeSnippet>,
     /// Source URL
     pub url: Option<String>,
     /// Provider (context7, mslearn)
     pub provider: String,
+    /// Library version the content was taken from, if known
+    #[serde(default)]
+    pub version: Option<String>,
 }
 
 /// A web search result
@@ -194,6 +302,10 @@ pub struct AnalyzeResult {
     #[serde(default)]
     pub web_results: Vec<WebResult>,
 
+    /// Web results dropped as near-duplicates
+    #[serde(default)]
+    pub web_duplicates_removed: usize,
+
     /// Aggregated context summary
     pub summary: String,
 
@@ -223,9 +335,12 @@ impl Default for AnalyzeParams {
             library: None,
             topic: None,
             max_per_source: 5,
-            min_score: 0.3,
+            min_score: None,
             tags: Vec::new(),
             file_path: None,
+            dedup_web: true,
+            dedup_threshold: default_dedup_threshold(),
+            source_weights: SourceWeights::default(),
         }
     }
 }
@@ -244,6 +359,45 @@ mod tests {
         assert_eq!(params.think_steps, 5);
         assert_eq!(params.sources.len(), 4);
         assert_eq!(params.max_per_source, 5);
+        assert!(params.dedup_web);
+        assert_eq!(params.dedup_threshold, 0.9);
+    }
+
+    #[test]
+    fn test_dedup_by_similarity() {
+        let embeddings = vec![
+            vec![1.0, 0.0],
+            vec![0.99, 0.1],
+            vec![0.0, 1.0],
+        ];
+        // The rewrite (index 1) outscores the original, so it is kept instead
+        let kept = dedup_by_similarity(&embeddings, &[0.5, 0.8, 0.3], 0.9);
+        assert_eq!(kept, vec![1, 2]);
+
+        // A threshold above any similarity keeps everything
+        let kept = dedup_by_similarity(&embeddings, &[0.5, 0.8, 0.3], 1.1);
+        assert_eq!(kept.len(), 3);
+    }
+
+    #[test]
+    fn test_analyze_confidence_weights_sources() {
+        let weights = SourceWeights::default();
+        assert_eq!(analyze_confidence(&[], &weights), CONFIDENCE_FLOOR);
+
+        let one_doc = analyze_confidence(&[(AnalyzeSource::Docs, 1.0)], &weights);
+        let noisy_web = analyze_confidence(&[(AnalyzeSource::Web, 0.3); 10], &weights);
+        assert!(one_doc >= 0.7, "one authoritative doc: {one_doc}");
+        assert!(noisy_web < 0.5, "ten weak web hits: {noisy_web}");
+
+        let strong_memory = analyze_confidence(&[(AnalyzeSource::Memory, 0.9); 3], &weights);
+        assert!(strong_memory > one_doc && strong_memory <= CONFIDENCE_CEILING);
+
+        // Weights are tunable per call
+        let params: AnalyzeParams =
+            serde_json::from_str(r#"{"query": "q", "source_weights": {"web": 1.0}}"#).unwrap();
+        assert_eq!(params.source_weights.web, 1.0);
+        assert_eq!(params.source_weights.docs, 0.8);
+        assert!(analyze_confidence(&[(AnalyzeSource::Web, 0.3); 10], &params.source_weights) > noisy_web);
     }
 
     #[test]
@@ -266,6 +420,7 @@ mod tests {
             knowledge_results: vec![],
             docs_results: vec![],
             web_results: vec![],
+            web_duplicates_removed: 0,
             summary: "summary".to_string(),
             sources_searched: vec!["memory".to_string()],
             confidence: 0.8,
@@ -43,6 +43,8 @@ pub enum ManageAction {
     InstructionsList,
     /// Reload instructions from workspace
     InstructionsReload,
+    /// Rebuild the vector search index from stored embeddings
+    OptimizeIndex,
 }
 
 /// Server installation parameters
@@ -52,7 +54,7 @@ pub struct InstallConfig {
     pub name: String,
     /// Package name (npm: @org/package, pip: package-name)
     pub package: String,
-    /// Package type: "npm", "pip", "binary"
+    /// Package type: "npm" or "pip"
     #[serde(default = "default_npm")]
     pub package_type: String,
     /// Human-readable description
@@ -168,19 +170,19 @@ pub struct ManageParams {
     #[serde(default)]
     pub filter_tool: Option<String>,
 
-    /// Retention days (for cortex_cleanup)
-    #[serde(default = "default_retention")]
-    pub retention_days: i64,
+    /// Episodic retention days override (for cortex_cleanup; default: CORTEX config)
+    #[serde(default)]
+    pub retention_days: Option<i64>,
+
+    /// Compact the episodic store after cleanup (for cortex_cleanup; default: false)
+    #[serde(default)]
+    pub compact: bool,
 
     /// Instructions config (for instructions action)
     #[serde(default)]
     pub instructions: Option<InstructionsConfig>,
 }
 
-fn default_retention() -> i64 {
-    30
-}
-
 /// Server status info
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct ServerInfo {
@@ -233,10 +235,13 @@ pub struct InstructionInfoItem {
     pub description: Option<String>,
     /// ApplyTo pattern
     pub apply_to: Option<String>,
+    /// Priority (higher = injected first)
+    #[serde(default)]
+    pub priority: i32,
 }
 
 /// Result from the manage pipeline
-#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct ManageResult {
     /// Action that was performed
     pub action: String,
@@ -259,10 +264,18 @@ pub struct ManageResult {
     #[serde(default)]
     pub cortex_stats: Option<CortexStatsInfo>,
 
-    /// Cleanup count (for cortex_cleanup)
+    /// CORTEX records removed by the retention window (for cortex_cleanup)
     #[serde(default)]
     pub cleaned_count: Option<usize>,
 
+    /// Soft-deleted memories purged from the trash (for cortex_cleanup)
+    #[serde(default)]
+    pub purged_count: Option<usize>,
+
+    /// Chunks in the rebuilt vector index (for optimize_index)
+    #[serde(default)]
+    pub indexed_count: Option<usize>,
+
     /// Tool call result (for call_tool)
     #[serde(default)]
     pub tool_result: Option<serde_json::Value>,
@@ -293,7 +306,8 @@ impl Default for ManageParams {
             tool_call: None,
             filter_server: None,
             filter_tool: None,
-            retention_days: 30,
+            retention_days: None,
+            compact: false,
             instructions: None,
         }
     }
@@ -301,6 +315,19 @@ impl Default for ManageParams {
 
 impl ManageParams {}
 
+impl ManageResult {
+    /// Result of `action` with an empty payload
+    pub fn new(action: &str, success: bool, message: impl Into<String>, connected_count: usize) -> Self {
+        Self {
+            action: action.to_string(),
+            success,
+            message: message.into(),
+            connected_count,
+            ..Default::default()
+        }
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -328,6 +355,7 @@ mod tests {
             instruction_content: None,
             connected_count: 3,
             error: None,
+            ..Default::default()
         };
 
         assert!(result.success);
@@ -168,6 +168,9 @@ pub struct RememberResult {
 /// Result of entity creation
 #[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct EntityResult {
+    /// Record ID (e.g. "entity:abc123")
+    #[serde(default)]
+    pub id: String,
     /// Entity name
     pub name: String,
     /// Entity type
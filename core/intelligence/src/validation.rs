@@ -0,0 +1,356 @@
+//! Tool parameter validation
+//!
+//! Two layers give clients field-level errors they can act on:
+//!
+//! - When arguments fail to deserialize, rmcp only reports the serde message.
+//!   [`check_arguments`] re-checks the arguments against the tool's input
+//!   schema to name the offending fields (missing, wrong type, unknown enum
+//!   value).
+//! - Handlers check constraints the schema can't express (`limit` > 0, score
+//!   ranges, free-form actions) with the `require_*` helpers, which return
+//!   [`IntelligenceError::InvalidParams`].
+//!
+//! Both surface as `invalid_params` errors whose data lists the fields.
+//!
+//! A few values are deliberately left unchecked because the edge value has a
+//! documented meaning:
+//!
+//! - `cortex_process.timeout_secs = 0` returns the partial result right away.
+//! - `cortex_process.max_instructions_chars = 0` lifts the instruction budget.
+//! - `retention_days = 0` cleans up everything older than now.
+//! - `analyze.library` may be absent although `docs` is a default source;
+//!   the docs source is then skipped.
+//! - Free-form names (entity and relation types, tags, MCP server and tool
+//!   names) are looked up, not matched against a fixed set.
+
+use crate::error::{IntelligenceError, Result};
+use serde::Serialize;
+use serde_json::{Map, Value};
+
+/// One problem with one parameter
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub(crate) struct ParamIssue {
+    /// Dotted path of the parameter (`filter.tags[1]`)
+    pub field: String,
+
+    /// What is wrong with it
+    pub message: String,
+}
+
+/// Check tool arguments against the tool's JSON schema
+///
+/// Only covers what serde enforces for our parameter types: required
+/// fields, JSON types, enum values and integer minimums. Returns no issues
+/// when the schema accepts the arguments.
+pub(crate) fn check_arguments(schema: &Map<String, Value>, arguments: &Value) -> Vec<ParamIssue> {
+    let mut checker = SchemaChecker {
+        root: schema,
+        issues: Vec::new(),
+    };
+    checker.check(&Value::Object(schema.clone()), arguments, "");
+    checker.issues
+}
+
+/// Error for a parameter that must be positive
+pub(crate) fn require_positive(field: &str, value: usize) -> Result<()> {
+    if value == 0 {
+        return Err(IntelligenceError::invalid_param(field, "must be > 0"));
+    }
+    Ok(())
+}
+
+/// Error for a score that must lie between 0.0 and 1.0
+pub(crate) fn require_unit_range(field: &str, value: f32) -> Result<()> {
+    if !(0.0..=1.0).contains(&value) {
+        return Err(IntelligenceError::invalid_param(
+            field,
+            format!("must be between 0.0 and 1.0, got {value}"),
+        ));
+    }
+    Ok(())
+}
+
+/// Error for a value that must not be negative (day counts, weights)
+pub(crate) fn require_non_negative(field: &str, value: f64) -> Result<()> {
+    if value.is_nan() || value < 0.0 {
+        return Err(IntelligenceError::invalid_param(field, format!("must be >= 0, got {value}")));
+    }
+    Ok(())
+}
+
+/// Error for a free-form value outside its allowed set
+pub(crate) fn require_one_of(field: &str, value: &str, allowed: &[&str]) -> Result<()> {
+    if !allowed.contains(&value) {
+        return Err(IntelligenceError::invalid_param(
+            field,
+            format!("unknown {field} '{value}', expected one of: {}", allowed.join(", ")),
+        ));
+    }
+    Ok(())
+}
+
+struct SchemaChecker<'a> {
+    root: &'a Map<String, Value>,
+    issues: Vec<ParamIssue>,
+}
+
+impl SchemaChecker<'_> {
+    fn check(&mut self, schema: &Value, value: &Value, path: &str) {
+        let Some(schema) = self.resolve(schema) else {
+            return;
+        };
+
+        for key in ["anyOf", "oneOf"] {
+            if let Some(Value::Array(alternatives)) = schema.get(key) {
+                self.check_alternatives(alternatives, value, path);
+                return;
+            }
+        }
+        if let Some(Value::Array(all)) = schema.get("allOf") {
+            for part in all {
+                self.check(part, value, path);
+            }
+        }
+
+        if let Some(expected) = schema.get("type") {
+            if !type_matches(expected, value) {
+                self.issue(path, format!("expected {}, got {}", type_names(expected), json_type(value)));
+                return;
+            }
+        }
+        if let Some(constant) = schema.get("const") {
+            if constant != value {
+                self.issue(path, format!("expected {constant}, got {value}"));
+                return;
+            }
+        }
+        if let Some(Value::Array(allowed)) = schema.get("enum") {
+            if !allowed.contains(value) {
+                self.issue(path, unknown_value(value, allowed));
+                return;
+            }
+        }
+        if let (Some(minimum), Some(number)) = (schema.get("minimum").and_then(Value::as_f64), value.as_f64()) {
+            if number < minimum {
+                self.issue(path, format!("must be >= {minimum}, got {value}"));
+            }
+        }
+
+        match value {
+            Value::Object(fields) => self.check_object(schema, fields, path),
+            Value::Array(items) => {
+                if let Some(item_schema) = schema.get("items") {
+                    for (i, item) in items.iter().enumerate() {
+                        self.check(item_schema, item, &format!("{path}[{i}]"));
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn check_object(&mut self, schema: &Map<String, Value>, fields: &Map<String, Value>, path: &str) {
+        if let Some(Value::Array(required)) = schema.get("required") {
+            for name in required.iter().filter_map(Value::as_str) {
+                if !fields.contains_key(name) {
+                    self.issue(&join(path, name), "is required".to_string());
+                }
+            }
+        }
+        if let Some(Value::Object(properties)) = schema.get("properties") {
+            for (name, field_value) in fields {
+                if let Some(field_schema) = properties.get(name) {
+                    self.check(field_schema, field_value, &join(path, name));
+                }
+            }
+        }
+    }
+
+    /// Accept the value if any alternative does; otherwise report the
+    /// issues of the closest one (enum-like alternatives are merged)
+    fn check_alternatives(&mut self, alternatives: &[Value], value: &Value, path: &str) {
+        let mut best: Option<Vec<ParamIssue>> = None;
+        for alternative in alternatives {
+            let mut nested = SchemaChecker {
+                root: self.root,
+                issues: Vec::new(),
+            };
+            nested.check(alternative, value, path);
+            if nested.issues.is_empty() {
+                return;
+            }
+            if best.as_ref().is_none_or(|b| nested.issues.len() < b.len()) {
+                best = Some(nested.issues);
+            }
+        }
+
+        let constants: Vec<Value> = alternatives
+            .iter()
+            .filter_map(|a| self.resolve(a))
+            .flat_map(|a| match (a.get("const"), a.get("enum")) {
+                (Some(constant), _) => vec![constant.clone()],
+                (None, Some(Value::Array(values))) => values.clone(),
+                _ => vec![],
+            })
+            .collect();
+        if !constants.is_empty() && value.is_string() {
+            self.issue(path, unknown_value(value, &constants));
+        } else {
+            self.issues.extend(best.unwrap_or_default());
+        }
+    }
+
+    /// Follow `$ref` into the root `$defs`/`definitions`
+    fn resolve<'s>(&'s self, schema: &'s Value) -> Option<&'s Map<String, Value>> {
+        let schema = schema.as_object()?;
+        let Some(reference) = schema.get("$ref").and_then(Value::as_str) else {
+            return Some(schema);
+        };
+        let (section, name) = reference.strip_prefix("#/")?.split_once('/')?;
+        self.root.get(section)?.get(name)?.as_object()
+    }
+
+    fn issue(&mut self, path: &str, message: String) {
+        let field = if path.is_empty() { "arguments" } else { path };
+        self.issues.push(ParamIssue {
+            field: field.to_string(),
+            message,
+        });
+    }
+}
+
+fn join(path: &str, name: &str) -> String {
+    if path.is_empty() {
+        name.to_string()
+    } else {
+        format!("{path}.{name}")
+    }
+}
+
+fn type_matches(expected: &Value, value: &Value) -> bool {
+    match expected {
+        Value::String(name) => is_type(name, value),
+        Value::Array(names) => names.iter().filter_map(Value::as_str).any(|name| is_type(name, value)),
+        _ => true,
+    }
+}
+
+fn is_type(name: &str, value: &Value) -> bool {
+    match name {
+        "null" => value.is_null(),
+        "boolean" => value.is_boolean(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "number" => value.is_number(),
+        "string" => value.is_string(),
+        "array" => value.is_array(),
+        "object" => value.is_object(),
+        _ => true,
+    }
+}
+
+fn type_names(expected: &Value) -> String {
+    match expected {
+        Value::Array(names) => names
+            .iter()
+            .filter_map(Value::as_str)
+            .filter(|name| *name != "null")
+            .collect::<Vec<_>>()
+            .join(" or "),
+        other => other.as_str().unwrap_or("any").to_string(),
+    }
+}
+
+fn json_type(value: &Value) -> &'static str {
+    match value {
+        Value::Null => "null",
+        Value::Bool(_) => "boolean",
+        Value::Number(n) if n.is_f64() => "number",
+        Value::Number(_) => "integer",
+        Value::String(_) => "string",
+        Value::Array(_) => "array",
+        Value::Object(_) => "object",
+    }
+}
+
+fn unknown_value(value: &Value, allowed: &[Value]) -> String {
+    let allowed: Vec<String> = allowed
+        .iter()
+        .map(|v| v.as_str().map(str::to_string).unwrap_or_else(|| v.to_string()))
+        .collect();
+    let value = value.as_str().map(str::to_string).unwrap_or_else(|| value.to_string());
+    format!("unknown value '{value}', expected one of: {}", allowed.join(", "))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn schema() -> Map<String, Value> {
+        json!({
+            "type": "object",
+            "required": ["query", "action"],
+            "properties": {
+                "query": { "type": "string" },
+                "limit": { "type": "integer", "format": "uint", "minimum": 0 },
+                "min_score": { "type": ["number", "null"] },
+                "action": { "$ref": "#/$defs/Action" },
+                "tags": { "type": "array", "items": { "type": "string" } }
+            },
+            "$defs": {
+                "Action": {
+                    "oneOf": [
+                        { "type": "string", "const": "add", "description": "Add" },
+                        { "type": "string", "const": "remove", "description": "Remove" }
+                    ]
+                }
+            }
+        })
+        .as_object()
+        .cloned()
+        .unwrap()
+    }
+
+    #[test]
+    fn test_check_arguments_accepts_valid() {
+        let args = json!({ "query": "q", "action": "add", "limit": 3, "min_score": null, "tags": ["a"] });
+        assert!(check_arguments(&schema(), &args).is_empty());
+    }
+
+    #[test]
+    fn test_check_arguments_names_fields() {
+        let args = json!({ "action": "foo", "limit": "ten", "tags": ["a", 2] });
+        let issues = check_arguments(&schema(), &args);
+        let by_field = |field: &str| issues.iter().find(|i| i.field == field).map(|i| i.message.as_str());
+
+        assert_eq!(by_field("query"), Some("is required"));
+        assert_eq!(by_field("limit"), Some("expected integer, got string"));
+        assert_eq!(by_field("action"), Some("unknown value 'foo', expected one of: add, remove"));
+        assert_eq!(by_field("tags[1]"), Some("expected string, got integer"));
+        assert_eq!(issues.len(), 4);
+
+        let negative = check_arguments(&schema(), &json!({ "query": "q", "action": "add", "limit": -1 }));
+        assert_eq!(negative[0].field, "limit");
+        assert!(negative[0].message.starts_with("must be >= 0"));
+    }
+
+    #[test]
+    fn test_require_helpers() {
+        assert!(require_positive("limit", 1).is_ok());
+        let err = require_positive("limit", 0).unwrap_err();
+        assert_eq!(err.to_string(), "Invalid parameter `limit`: must be > 0");
+
+        assert!(require_unit_range("min_score", 0.5).is_ok());
+        assert!(require_unit_range("min_score", 1.5).is_err());
+
+        assert!(require_non_negative("retention_days", 0.0).is_ok());
+        assert!(require_non_negative("retention_days", -1.0).is_err());
+        assert!(require_non_negative("source_weights.web", f64::NAN).is_err());
+
+        let err = require_one_of("action", "foo", &["add", "remove"]).unwrap_err();
+        assert_eq!(
+            err.to_string(),
+            "Invalid parameter `action`: unknown action 'foo', expected one of: add, remove"
+        );
+    }
+}
@@ -2,6 +2,7 @@
 //!
 //! Tests the cognitive engine:
 //! - cortex_process with different task types
+//! - cortex_preview_context prompt inspection
 //! - cortex_feedback for learning
 //! - cortex_stats monitoring
 //! - cortex_cleanup maintenance
@@ -31,6 +32,7 @@ async fn test_cortex_process_basic_query() {
         inject_doubt: true,
         language: None,
         task_type: None,
+        ..Default::default()
     };
 
     let result = ctx.server.call_cortex_process(params).await;
@@ -54,6 +56,7 @@ async fn test_cortex_process_with_task_type_code() {
         inject_doubt: true,
         language: Some("rust".to_string()),
         task_type: Some(TaskType::Code),
+        ..Default::default()
     };
 
     let result = ctx.server.call_cortex_process(params).await;
@@ -77,6 +80,7 @@ async fn test_cortex_process_with_task_type_research() {
         inject_doubt: true,
         language: Some("rust".to_string()),
         task_type: Some(TaskType::Research),
+        ..Default::default()
     };
 
     let result = ctx.server.call_cortex_process(params).await;
@@ -95,6 +99,7 @@ async fn test_cortex_process_with_task_type_fix() {
         inject_doubt: true,
         language: Some("rust".to_string()),
         task_type: Some(TaskType::Fix),
+        ..Default::default()
     };
 
     let result = ctx.server.call_cortex_process(params).await;
@@ -114,6 +119,7 @@ async fn test_cortex_process_with_session() {
         inject_doubt: false,
         language: None,
         task_type: None,
+        ..Default::default()
     };
 
     let result1 = ctx.server.call_cortex_process(params1).await.unwrap();
@@ -128,6 +134,7 @@ async fn test_cortex_process_with_session() {
         inject_doubt: false,
         language: None,
         task_type: None,
+        ..Default::default()
     };
 
     let result2 = ctx.server.call_cortex_process(params2).await;
@@ -146,6 +153,7 @@ async fn test_cortex_process_without_doubt_injection() {
         inject_doubt: false, // Disabled
         language: None,
         task_type: None,
+        ..Default::default()
     };
 
     let result = ctx.server.call_cortex_process(params).await;
@@ -156,6 +164,58 @@ async fn test_cortex_process_without_doubt_injection() {
     assert!(res.loaded_prompts.is_empty() || !res.loaded_prompts.iter().any(|p| p.contains("doubt")));
 }
 
+#[tokio::test]
+async fn test_cortex_process_with_response_language() {
+    let ctx = TestContext::new().await;
+
+    let params = CortexProcessParams {
+        query: "Explique les closures en Rust".to_string(),
+        context: None,
+        session_id: None,
+        auto_learn: false,
+        inject_doubt: false,
+        language: None,
+        task_type: None,
+        response_language: Some("French".to_string()),
+        ..Default::default()
+    };
+
+    let result = ctx.server.call_cortex_process(params).await;
+    assert!(result.is_ok());
+
+    let res = result.unwrap();
+    assert!(res.loaded_prompts.iter().any(|p| p == "response_language:French"));
+}
+
+#[tokio::test]
+async fn test_cortex_preview_context_matches_injection() {
+    let ctx = TestContext::new().await;
+
+    let params = CortexProcessParams {
+        query: "Explique les closures en Rust".to_string(),
+        context: Some("user notes".to_string()),
+        session_id: None,
+        auto_learn: false,
+        inject_doubt: false,
+        language: None,
+        task_type: None,
+        response_language: Some("French".to_string()),
+        ..Default::default()
+    };
+
+    let preview = ctx.server.call_cortex_preview_context(params.clone()).await.unwrap();
+    assert!(preview.prompt_context.contains("French"));
+    assert_eq!(preview.loaded_prompts, vec!["response_language:French".to_string()]);
+
+    let context = preview.context.expect("context should be enriched");
+    assert_eq!(context["system_prompts"], preview.prompt_context.as_str());
+    assert_eq!(context["user_context"], "user notes");
+
+    // Preview and execution load the same prompts
+    let res = ctx.server.call_cortex_process(params).await.unwrap();
+    assert_eq!(res.loaded_prompts, preview.loaded_prompts);
+}
+
 #[tokio::test]
 async fn test_cortex_process_all_task_types() {
     let ctx = TestContext::new().await;
@@ -178,6 +238,7 @@ async fn test_cortex_process_all_task_types() {
             inject_doubt: false,
             language: None,
             task_type: Some(task_type),
+            ..Default::default()
         };
 
         let result = ctx.server.call_cortex_process(params).await;
@@ -202,6 +263,7 @@ async fn test_cortex_feedback_positive() {
         inject_doubt: false,
         language: None,
         task_type: None,
+        ..Default::default()
     };
     ctx.server.call_cortex_process(process_params).await.unwrap();
 
@@ -266,6 +328,7 @@ async fn test_cortex_stats_after_operations() {
         inject_doubt: false,
         language: None,
         task_type: None,
+        ..Default::default()
     }).await.unwrap();
 
     ctx.server.call_cortex_process(CortexProcessParams {
@@ -276,6 +339,7 @@ async fn test_cortex_stats_after_operations() {
         inject_doubt: false,
         language: None,
         task_type: None,
+        ..Default::default()
     }).await.unwrap();
 
     // Check stats
@@ -294,7 +358,8 @@ async fn test_cortex_cleanup_basic() {
     let ctx = TestContext::new().await;
 
     let params = CortexCleanupParams {
-        retention_days: 30,
+        retention_days: Some(30),
+        ..Default::default()
     };
 
     let result = ctx.server.call_cortex_cleanup(params).await;
@@ -318,11 +383,13 @@ async fn test_cortex_cleanup_aggressive() {
         inject_doubt: false,
         language: None,
         task_type: None,
+        ..Default::default()
     }).await.unwrap();
 
     // Cleanup with 0 days retention (clean everything)
     let params = CortexCleanupParams {
-        retention_days: 0,
+        retention_days: Some(0),
+        compact: true,
     };
 
     let result = ctx.server.call_cortex_cleanup(params).await;
@@ -516,6 +583,7 @@ async fn test_cortex_code_workflow_simulation() {
         inject_doubt: true,
         language: Some("rust".to_string()),
         task_type: Some(TaskType::Research),
+        ..Default::default()
     }).await.unwrap();
 
     assert!(research.success);
@@ -529,6 +597,7 @@ async fn test_cortex_code_workflow_simulation() {
         inject_doubt: true,
         language: Some("rust".to_string()),
         task_type: Some(TaskType::Code),
+        ..Default::default()
     }).await.unwrap();
 
     assert!(code.success);
@@ -542,6 +611,7 @@ async fn test_cortex_code_workflow_simulation() {
         inject_doubt: true,
         language: Some("rust".to_string()),
         task_type: Some(TaskType::Review),
+        ..Default::default()
     }).await.unwrap();
 
     assert!(review.success);
@@ -569,6 +639,7 @@ fn main() {
         inject_doubt: true,
         language: Some("rust".to_string()),
         task_type: Some(TaskType::Research),
+        ..Default::default()
     }).await.unwrap();
 
     assert!(analysis.success);
@@ -582,6 +653,7 @@ fn main() {
         inject_doubt: true,
         language: Some("rust".to_string()),
         task_type: Some(TaskType::Fix),
+        ..Default::default()
     }).await.unwrap();
 
     assert!(fix.success);
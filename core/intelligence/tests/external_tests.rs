@@ -5,13 +5,14 @@
 //! - external_docs (Context7, MS Learn)
 //! - external_search (Tavily)
 //! - external_mcp_call
+//! - external_mcp_call_batch
 
 mod common;
 
 use common::TestContext;
 use whytcard_intelligence::tools::{
-    SequentialThinkingParams, ExternalDocsParams, ExternalSearchParams,
-    ExternalMcpCallParams, McpStatusParams,
+    SequentialThinkingParams, DocsFallbackMode, ExternalDocsParams, ExternalSearchParams,
+    ExternalMcpCallBatchParams, ExternalMcpCallParams, McpStatusParams,
 };
 
 // =============================================================================
@@ -119,6 +120,7 @@ async fn test_external_docs_context7() {
         topic: Some("async runtime".to_string()),
         source: "context7".to_string(),
         max_tokens: 2000,
+        ..Default::default()
     };
 
     let result = ctx.server.call_external_docs(params).await;
@@ -145,6 +147,7 @@ async fn test_external_docs_mslearn() {
         topic: Some("upload files".to_string()),
         source: "mslearn".to_string(),
         max_tokens: 3000,
+        ..Default::default()
     };
 
     let result = ctx.server.call_external_docs(params).await;
@@ -170,6 +173,7 @@ async fn test_external_docs_auto_source() {
         topic: Some("hooks".to_string()),
         source: "auto".to_string(),
         max_tokens: 2000,
+        ..Default::default()
     };
 
     let result = ctx.server.call_external_docs(params).await;
@@ -185,6 +189,33 @@ async fn test_external_docs_auto_source() {
     }
 }
 
+#[tokio::test]
+async fn test_external_docs_provider_order() {
+    let ctx = TestContext::new().await;
+
+    // Un provider inconnu est refusé
+    let params = ExternalDocsParams {
+        library: "react".to_string(),
+        providers: vec!["unknown-provider".to_string()],
+        ..Default::default()
+    };
+    assert!(ctx.server.call_external_docs(params).await.is_err());
+
+    // En mode merge, chaque provider est essayé dans l'ordre donné
+    let params = ExternalDocsParams {
+        library: "react".to_string(),
+        topic: None,
+        source: "auto".to_string(),
+        max_tokens: 1000,
+        providers: vec!["mslearn".to_string(), "context7".to_string()],
+        mode: Some(DocsFallbackMode::Merge),
+        ..Default::default()
+    };
+
+    let docs = ctx.server.call_external_docs(params).await.unwrap();
+    assert_eq!(docs.providers_tried, vec!["mslearn".to_string(), "context7".to_string()]);
+}
+
 #[tokio::test]
 async fn test_external_docs_various_libraries() {
     let ctx = TestContext::new().await;
@@ -202,6 +233,7 @@ async fn test_external_docs_various_libraries() {
             topic: Some(topic.to_string()),
             source: "auto".to_string(),
             max_tokens: 1000,
+            ..Default::default()
         };
 
         let result = ctx.server.call_external_docs(params).await;
@@ -460,6 +492,37 @@ async fn test_external_mcp_call_invalid_tool() {
     }
 }
 
+#[tokio::test]
+async fn test_external_mcp_call_batch_preserves_order() {
+    let ctx = TestContext::new().await;
+
+    let params = ExternalMcpCallBatchParams {
+        calls: vec![
+            ExternalMcpCallParams {
+                server: "nonexistent-a".to_string(),
+                tool: "tool-a".to_string(),
+                arguments: None,
+            },
+            ExternalMcpCallParams {
+                server: "nonexistent-b".to_string(),
+                tool: "tool-b".to_string(),
+                arguments: None,
+            },
+        ],
+        max_concurrency: 2,
+    };
+
+    let result = ctx.server.call_external_mcp_call_batch(params).await;
+
+    let response = result.expect("batch call should return per-call results");
+    assert_eq!(response.results.len(), 2);
+    assert_eq!(response.results[0].server, "nonexistent-a");
+    assert_eq!(response.results[1].server, "nonexistent-b");
+    assert_eq!(response.succeeded, 0);
+    assert_eq!(response.failed, 2);
+    assert!(response.results.iter().all(|r| r.error.is_some()));
+}
+
 // =============================================================================
 // MCP STATUS TESTS
 // =============================================================================
@@ -504,6 +567,7 @@ async fn test_workflow_research_with_external_tools() {
         topic: Some("rate limiting".to_string()),
         source: "auto".to_string(),
         max_tokens: 2000,
+        ..Default::default()
     }).await;
 
     match docs_result {
@@ -556,6 +620,7 @@ async fn test_workflow_documentation_lookup() {
             topic: Some(topic.to_string()),
             source: "auto".to_string(),
             max_tokens: 1500,
+            ..Default::default()
         }).await;
 
         match result {
@@ -578,7 +643,7 @@ async fn test_workflow_documentation_lookup() {
 
     // Vérifier le knowledge graph
     let graph = ctx.server.call_knowledge_read_graph(
-        whytcard_intelligence::tools::KnowledgeReadGraphParams { limit: 10 }
+        whytcard_intelligence::tools::KnowledgeReadGraphParams { limit: Some(10), ..Default::default() }
     ).await.unwrap();
 
     println!("Knowledge graph has {} entities after doc lookup", graph.total_entities);
@@ -13,7 +13,7 @@ use whytcard_intelligence::tools::{
     ExportGraphParams, KnowledgeAddEntityParams, KnowledgeAddObservationParams,
     KnowledgeAddRelationParams, KnowledgeDeleteEntityParams, KnowledgeDeleteObservationParams,
     KnowledgeDeleteRelationParams, KnowledgeFindPathParams, KnowledgeGetEntityParams,
-    KnowledgeGetNeighborsParams, KnowledgeReadGraphParams, KnowledgeSearchParams,
+    KnowledgeGetNeighborsParams, KnowledgeReadGraphParams, KnowledgeSearchMode, KnowledgeSearchParams,
 };
 
 // =============================================================================
@@ -97,7 +97,8 @@ async fn test_entity_duplicate_handling() {
         entity_type: "language".to_string(),
         observations: vec!["Interpreted language".to_string()],
     };
-    ctx.server.call_knowledge_add_entity(params.clone()).await.unwrap();
+    let first = ctx.server.call_knowledge_add_entity(params.clone()).await.unwrap();
+    assert!(first.id.starts_with("entity:"));
 
     // Create again - should add observations, not create new
     let result = ctx.server.call_knowledge_add_entity(KnowledgeAddEntityParams {
@@ -108,6 +109,7 @@ async fn test_entity_duplicate_handling() {
 
     assert!(!result.created); // Not created because exists
     assert_eq!(result.observations_added, 1); // But observation added
+    assert_eq!(result.id, first.id); // Same record returned
 
     // Verify total observations
     let entity = ctx.server.call_knowledge_get_entity(KnowledgeGetEntityParams {
@@ -144,6 +146,7 @@ async fn test_relation_create_and_query() {
         from: "TypeScript".to_string(),
         to: "JavaScript".to_string(),
         relation_type: "extends".to_string(),
+        ..Default::default()
     };
 
     let result = ctx.server.call_knowledge_add_relation(rel_params).await;
@@ -170,6 +173,40 @@ async fn test_relation_create_and_query() {
     assert_eq!(js.incoming[0].from, "TypeScript");
 }
 
+#[tokio::test]
+async fn test_relation_duplicate_detected() {
+    let ctx = TestContext::new().await;
+
+    for name in ["Cargo", "Rust"] {
+        ctx.server.call_knowledge_add_entity(KnowledgeAddEntityParams {
+            name: name.to_string(),
+            entity_type: "tool".to_string(),
+            observations: vec![],
+        }).await.unwrap();
+    }
+
+    let rel_params = KnowledgeAddRelationParams {
+        from: "Cargo".to_string(),
+        to: "Rust".to_string(),
+        relation_type: "builds".to_string(),
+        ..Default::default()
+    };
+
+    let first = ctx.server.call_knowledge_add_relation(rel_params.clone()).await.unwrap();
+    assert!(first.created);
+    assert!(!first.already_existed);
+
+    let second = ctx.server.call_knowledge_add_relation(rel_params).await.unwrap();
+    assert!(!second.created);
+    assert!(second.already_existed);
+
+    let cargo = ctx.server.call_knowledge_get_entity(KnowledgeGetEntityParams {
+        name: "Cargo".to_string(),
+        include_relations: true,
+    }).await.unwrap();
+    assert_eq!(cargo.outgoing.len(), 1);
+}
+
 #[tokio::test]
 async fn test_relation_delete() {
     let ctx = TestContext::new().await;
@@ -191,6 +228,7 @@ async fn test_relation_delete() {
         from: "A".to_string(),
         to: "B".to_string(),
         relation_type: "links_to".to_string(),
+        ..Default::default()
     }).await.unwrap();
 
     // Delete relation
@@ -234,12 +272,14 @@ async fn test_relation_cascade_on_entity_delete() {
         from: "A".to_string(),
         to: "B".to_string(),
         relation_type: "connects".to_string(),
+        ..Default::default()
     }).await.unwrap();
 
     ctx.server.call_knowledge_add_relation(KnowledgeAddRelationParams {
         from: "B".to_string(),
         to: "C".to_string(),
         relation_type: "connects".to_string(),
+        ..Default::default()
     }).await.unwrap();
 
     // Delete B (middle node)
@@ -275,6 +315,7 @@ async fn test_knowledge_search_basic() {
     let params = KnowledgeSearchParams {
         query: "Searchable".to_string(),
         limit: 10,
+        ..Default::default()
     };
 
     let result = ctx.server.call_knowledge_search(params).await;
@@ -298,11 +339,42 @@ async fn test_knowledge_search_partial_match() {
     let result = ctx.server.call_knowledge_search(KnowledgeSearchParams {
         query: "Long".to_string(),
         limit: 10,
+        ..Default::default()
     }).await.unwrap();
 
     assert!(result.entities.iter().any(|e| e.name.contains("Long")));
 }
 
+#[tokio::test]
+async fn test_knowledge_search_semantic_mode() {
+    let ctx = TestContext::new().await;
+
+    ctx.server.call_knowledge_add_entity(KnowledgeAddEntityParams {
+        name: "Tokio".to_string(),
+        entity_type: "library".to_string(),
+        observations: vec!["Asynchronous runtime for Rust".to_string()],
+    }).await.unwrap();
+
+    // Name matching alone cannot find it
+    let by_name = ctx.server.call_knowledge_search(KnowledgeSearchParams {
+        query: "async runtime".to_string(),
+        limit: 10,
+        mode: KnowledgeSearchMode::Name,
+        similarity_threshold: 0.3,
+        ..Default::default()
+    }).await.unwrap();
+    assert!(by_name.entities.is_empty());
+
+    let semantic = ctx.server.call_knowledge_search(KnowledgeSearchParams {
+        query: "async runtime".to_string(),
+        limit: 10,
+        mode: KnowledgeSearchMode::Semantic,
+        similarity_threshold: 0.3,
+        ..Default::default()
+    }).await.unwrap();
+    assert!(semantic.entities.iter().any(|e| e.name == "Tokio"));
+}
+
 // =============================================================================
 // GRAPH TRAVERSAL TESTS
 // =============================================================================
@@ -325,10 +397,11 @@ async fn test_read_graph() {
         from: "Node0".to_string(),
         to: "Node1".to_string(),
         relation_type: "next".to_string(),
+        ..Default::default()
     }).await.unwrap();
 
     // Read graph
-    let params = KnowledgeReadGraphParams { limit: 0 };
+    let params = KnowledgeReadGraphParams::default();
     let result = ctx.server.call_knowledge_read_graph(params).await;
 
     assert!(result.is_ok());
@@ -337,6 +410,55 @@ async fn test_read_graph() {
     assert!(graph.total_relations >= 1);
 }
 
+#[tokio::test]
+async fn test_read_graph_and_search_paging() {
+    let ctx = TestContext::new().await;
+
+    for i in 0..5 {
+        ctx.server.call_knowledge_add_entity(KnowledgeAddEntityParams {
+            name: format!("PagedNode{}", i),
+            entity_type: "paging_test".to_string(),
+            observations: vec![],
+        }).await.unwrap();
+    }
+
+    let first = ctx.server.call_knowledge_search(KnowledgeSearchParams {
+        query: "PagedNode".to_string(),
+        limit: 2,
+        ..Default::default()
+    }).await.unwrap();
+    assert_eq!(first.entities.len(), 2);
+    assert_eq!(first.total, 5);
+    assert!(first.has_more);
+
+    let last = ctx.server.call_knowledge_search(KnowledgeSearchParams {
+        query: "PagedNode".to_string(),
+        limit: 2,
+        offset: 4,
+        ..Default::default()
+    }).await.unwrap();
+    assert_eq!(last.entities.len(), 1);
+    assert!(!last.has_more);
+    assert!(first.entities.iter().all(|e| e.name != last.entities[0].name));
+
+    // Page through the whole graph
+    let mut seen = Vec::new();
+    let mut offset = 0;
+    loop {
+        let page = ctx.server.call_knowledge_read_graph(KnowledgeReadGraphParams {
+            limit: Some(2),
+            offset,
+        }).await.unwrap();
+        offset += page.entities.len();
+        seen.extend(page.entities.into_iter().map(|e| e.name));
+        if !page.has_more {
+            assert_eq!(offset, page.total_entities);
+            break;
+        }
+    }
+    assert_eq!(seen.iter().filter(|n| n.starts_with("PagedNode")).count(), 5);
+}
+
 #[tokio::test]
 async fn test_get_neighbors() {
     let ctx = TestContext::new().await;
@@ -359,6 +481,7 @@ async fn test_get_neighbors() {
             from: "Center".to_string(),
             to: name.to_string(),
             relation_type: "connects".to_string(),
+            ..Default::default()
         }).await.unwrap();
     }
 
@@ -367,6 +490,7 @@ async fn test_get_neighbors() {
         entity_name: "Center".to_string(),
         max_depth: 1,
         relation_types: vec![],
+        ..Default::default()
     };
 
     let result = ctx.server.call_knowledge_get_neighbors(params).await;
@@ -375,6 +499,66 @@ async fn test_get_neighbors() {
     let neighbors = result.unwrap();
     assert_eq!(neighbors.total, 4);
     assert!(neighbors.neighbors.iter().all(|n| n.distance == 1));
+
+    // Cap includes the start entity, so only two neighbors fit
+    let capped = ctx.server.call_knowledge_get_neighbors(KnowledgeGetNeighborsParams {
+        entity_name: "Center".to_string(),
+        max_depth: 1,
+        relation_types: vec![],
+        max_nodes_visited: Some(3),
+    }).await.unwrap();
+
+    assert_eq!(capped.total, 2);
+    assert!(capped.truncated);
+    assert!(!neighbors.truncated);
+}
+
+#[tokio::test]
+async fn test_symmetric_relation_traversal() {
+    let ctx = TestContext::new().await;
+
+    for name in ["Car", "Automobile"] {
+        ctx.server.call_knowledge_add_entity(KnowledgeAddEntityParams {
+            name: name.to_string(),
+            entity_type: "term".to_string(),
+            observations: vec![],
+        }).await.unwrap();
+    }
+
+    ctx.server.call_knowledge_add_relation(KnowledgeAddRelationParams {
+        from: "Car".to_string(),
+        to: "Automobile".to_string(),
+        relation_type: "synonym_of".to_string(),
+        symmetric: true,
+    }).await.unwrap();
+
+    // Reverse direction is already covered by the symmetric edge
+    let reverse = ctx.server.call_knowledge_add_relation(KnowledgeAddRelationParams {
+        from: "Automobile".to_string(),
+        to: "Car".to_string(),
+        relation_type: "synonym_of".to_string(),
+        symmetric: true,
+    }).await.unwrap();
+    assert!(reverse.already_existed);
+
+    let neighbors = ctx.server.call_knowledge_get_neighbors(KnowledgeGetNeighborsParams {
+        entity_name: "Automobile".to_string(),
+        max_depth: 1,
+        relation_types: vec![],
+        ..Default::default()
+    }).await.unwrap();
+    assert_eq!(neighbors.total, 1);
+    assert_eq!(neighbors.neighbors[0].path, vec!["synonym_of".to_string()]);
+
+    let path = ctx.server.call_knowledge_find_path(KnowledgeFindPathParams {
+        from: "Automobile".to_string(),
+        to: "Car".to_string(),
+        max_depth: 2,
+        ..Default::default()
+    }).await.unwrap();
+    assert!(path.found);
+    assert_eq!(path.path[0].from, "Automobile");
+    assert_eq!(path.path[0].to, "Car");
 }
 
 #[tokio::test]
@@ -394,12 +578,14 @@ async fn test_find_path() {
         from: "Start".to_string(),
         to: "Middle".to_string(),
         relation_type: "next".to_string(),
+        ..Default::default()
     }).await.unwrap();
 
     ctx.server.call_knowledge_add_relation(KnowledgeAddRelationParams {
         from: "Middle".to_string(),
         to: "End".to_string(),
         relation_type: "next".to_string(),
+        ..Default::default()
     }).await.unwrap();
 
     // Find path
@@ -407,6 +593,7 @@ async fn test_find_path() {
         from: "Start".to_string(),
         to: "End".to_string(),
         max_depth: 5,
+        ..Default::default()
     };
 
     let result = ctx.server.call_knowledge_find_path(params).await;
@@ -438,6 +625,7 @@ async fn test_find_path_no_connection() {
         from: "Island1".to_string(),
         to: "Island2".to_string(),
         max_depth: 10,
+        ..Default::default()
     }).await.unwrap();
 
     assert!(!result.found);
@@ -570,6 +758,7 @@ async fn test_complex_graph_structure() {
             from: from.to_string(),
             to: to.to_string(),
             relation_type: rel.to_string(),
+            ..Default::default()
         }).await.unwrap();
     }
 
@@ -578,6 +767,7 @@ async fn test_complex_graph_structure() {
         entity_name: "MyProject".to_string(),
         max_depth: 2,
         relation_types: vec![],
+        ..Default::default()
     }).await.unwrap();
 
     // Should find all connected entities
@@ -588,6 +778,7 @@ async fn test_complex_graph_structure() {
         entity_name: "MyProject".to_string(),
         max_depth: 1,
         relation_types: vec!["uses".to_string()],
+        ..Default::default()
     }).await.unwrap();
 
     assert_eq!(uses_only.total, 2); // React and PostgreSQL
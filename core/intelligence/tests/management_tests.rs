@@ -17,7 +17,7 @@ use common::TestContext;
 use whytcard_intelligence::tools::{
     McpListServersParams, McpListToolsParams, McpInstallServerParams,
     McpUninstallServerParams, McpConfigureServerParams, McpConnectParams,
-    McpDisconnectParams, ManageTagsParams, ExportGraphParams,
+    McpDisconnectParams, ManageTagsParams, ExportGraphParams, HealthParams,
 };
 
 // =============================================================================
@@ -358,6 +358,7 @@ async fn test_export_graph_json() {
             from: "ExportTest1".to_string(),
             to: "ExportTest2".to_string(),
             relation_type: "related_to".to_string(),
+            ..Default::default()
         }
     ).await.unwrap();
 
@@ -462,6 +463,7 @@ async fn test_export_graph_without_relations() {
             from: "NodeA".to_string(),
             to: "NodeB".to_string(),
             relation_type: "connects".to_string(),
+            ..Default::default()
         }
     ).await.unwrap();
 
@@ -562,3 +564,23 @@ async fn test_tag_based_organization() {
         println!("Tag '{}': {} documents", tag, result.doc_ids.len());
     }
 }
+
+// =============================================================================
+// HEALTH TESTS
+// =============================================================================
+
+#[tokio::test]
+async fn test_health_reports_subsystems() {
+    let ctx = TestContext::new().await;
+
+    let result = ctx.server.call_health(HealthParams {}).await.unwrap();
+
+    for name in ["database", "rag", "cortex", "integrations", "mcp_clients"] {
+        assert!(
+            result.subsystems.iter().any(|s| s.name == name),
+            "missing subsystem {}",
+            name
+        );
+    }
+    assert!(result.subsystems.iter().any(|s| s.name == "database" && s.healthy));
+}
@@ -1,7 +1,8 @@
 //! Memory Tools Integration Tests
 //!
 //! Tests all memory operations:
-//! - memory_store, memory_get, memory_search, memory_delete, memory_list
+//! - memory_store, memory_get, memory_search, memory_delete, memory_restore, memory_list
+//! - memory_export, memory_import
 //! - batch_store, hybrid_search, manage_tags, get_context
 
 mod common;
@@ -9,8 +10,8 @@ mod common;
 use common::{random_key, test_content, TestContext};
 use whytcard_intelligence::tools::{
     BatchStoreParams, GetContextParams, HybridSearchParams, ManageTagsParams,
-    MemoryDeleteParams, MemoryGetParams, MemoryListParams, MemorySearchParams,
-    MemoryStoreParams,
+    MemoryDeleteParams, MemoryExportParams, MemoryGetParams, MemoryImportParams,
+    MemoryListParams, MemoryRestoreParams, MemorySearchParams, MemoryStoreParams,
 };
 
 // =============================================================================
@@ -312,6 +313,7 @@ async fn test_memory_delete_existing() {
     // Delete
     let delete_params = MemoryDeleteParams {
         key: stored.key.clone(),
+        ..Default::default()
     };
     let result = ctx.server.call_memory_delete(delete_params).await;
     assert!(result.is_ok());
@@ -330,6 +332,7 @@ async fn test_memory_delete_nonexistent() {
 
     let delete_params = MemoryDeleteParams {
         key: "nonexistent-key".to_string(),
+        ..Default::default()
     };
 
     let result = ctx.server.call_memory_delete(delete_params).await;
@@ -337,6 +340,64 @@ async fn test_memory_delete_nonexistent() {
     assert!(!result.unwrap().deleted);
 }
 
+#[tokio::test]
+async fn test_memory_restore_after_soft_delete() {
+    let ctx = TestContext::new().await;
+
+    let store_params = MemoryStoreParams {
+        content: "Content to restore".to_string(),
+        title: None,
+        tags: vec![],
+        metadata: None,
+        index: true,
+        key: None,
+    };
+    let stored = ctx.server.call_memory_store(store_params).await.unwrap();
+
+    let delete_params = MemoryDeleteParams {
+        key: stored.key.clone(),
+        ..Default::default()
+    };
+    let deleted = ctx.server.call_memory_delete(delete_params).await.unwrap();
+    assert!(deleted.restorable);
+
+    let restore_params = MemoryRestoreParams {
+        key: stored.key.clone(),
+    };
+    let restored = ctx.server.call_memory_restore(restore_params).await.unwrap();
+    assert!(restored.restored);
+
+    let get_params = MemoryGetParams { key: stored.key };
+    assert!(ctx.server.call_memory_get(get_params).await.is_ok());
+}
+
+#[tokio::test]
+async fn test_memory_delete_permanent_cannot_restore() {
+    let ctx = TestContext::new().await;
+
+    let store_params = MemoryStoreParams {
+        content: "Content gone for good".to_string(),
+        title: None,
+        tags: vec![],
+        metadata: None,
+        index: false,
+        key: None,
+    };
+    let stored = ctx.server.call_memory_store(store_params).await.unwrap();
+
+    let delete_params = MemoryDeleteParams {
+        key: stored.key.clone(),
+        permanent: true,
+    };
+    let deleted = ctx.server.call_memory_delete(delete_params).await.unwrap();
+    assert!(deleted.deleted);
+    assert!(!deleted.restorable);
+
+    let restore_params = MemoryRestoreParams { key: stored.key };
+    let restored = ctx.server.call_memory_restore(restore_params).await.unwrap();
+    assert!(!restored.restored);
+}
+
 // =============================================================================
 // MEMORY_LIST TESTS
 // =============================================================================
@@ -441,6 +502,100 @@ async fn test_memory_list_with_tags_filter() {
     assert_eq!(result.memories.len(), 1);
 }
 
+// =============================================================================
+// MEMORY_EXPORT / MEMORY_IMPORT TESTS
+// =============================================================================
+
+#[tokio::test]
+async fn test_memory_export_jsonl() {
+    let ctx = TestContext::new().await;
+
+    for i in 0..3 {
+        let store_params = MemoryStoreParams {
+            content: format!("Exported item {}", i),
+            title: None,
+            tags: vec!["export".to_string()],
+            metadata: Some(serde_json::json!({"index": i})),
+            index: false,
+            key: None,
+        };
+        ctx.server.call_memory_store(store_params).await.unwrap();
+    }
+
+    let result = ctx.server.call_memory_export(MemoryExportParams {
+        path: "memories.jsonl".to_string(),
+        tags: vec!["export".to_string()],
+    }).await.unwrap();
+    assert_eq!(result.exported, 3);
+
+    let content = std::fs::read_to_string(ctx.path().join("exports").join("memories.jsonl")).unwrap();
+    assert_eq!(content.lines().count(), 3);
+    for line in content.lines() {
+        let record: serde_json::Value = serde_json::from_str(line).unwrap();
+        assert!(record["content"].as_str().unwrap().starts_with("Exported item"));
+        assert_eq!(record["tags"], serde_json::json!(["export"]));
+    }
+}
+
+#[tokio::test]
+async fn test_memory_export_rejects_paths_outside_exports() {
+    let ctx = TestContext::new().await;
+
+    for path in ["../whytcard.db", "/tmp/memories.jsonl", "backup/../../config.toml"] {
+        let result = ctx.server.call_memory_export(MemoryExportParams {
+            path: path.to_string(),
+            tags: vec![],
+        }).await;
+        assert!(result.is_err(), "{} should be rejected", path);
+    }
+    assert!(!ctx.path().join("whytcard.db").exists());
+}
+
+#[tokio::test]
+async fn test_memory_import_roundtrip() {
+    let ctx = TestContext::new().await;
+
+    let path = ctx.path().join("exports").join("import.jsonl");
+    std::fs::write(
+        &path,
+        concat!(
+            r#"{"key":"imported-1","content":"Imported memory about Rust ownership","tags":["import"]}"#, "\n",
+            r#"{"key":"imported-2","content":"Imported memory about async runtimes"}"#, "\n",
+            "{broken\n",
+        ),
+    ).unwrap();
+
+    let result = ctx.server.call_memory_import(MemoryImportParams {
+        path: "import.jsonl".to_string(),
+        reindex: true,
+    }).await.unwrap();
+
+    assert_eq!(result.created, 2);
+    assert_eq!(result.updated, 0);
+    assert_eq!(result.indexed, 2);
+    assert_eq!(result.errors.len(), 1);
+
+    let get_params = MemoryGetParams { key: "imported-1".to_string() };
+    let memory = ctx.server.call_memory_get(get_params).await.unwrap();
+    assert_eq!(memory.tags, vec!["import"]);
+}
+
+#[tokio::test]
+async fn test_memory_import_rejects_paths_outside_exports() {
+    let ctx = TestContext::new().await;
+
+    let outside = ctx.path().join("outside.jsonl");
+    std::fs::write(&outside, r#"{"key":"outside","content":"Not importable"}"#).unwrap();
+
+    for path in ["../outside.jsonl".to_string(), outside.to_string_lossy().to_string()] {
+        let result = ctx.server.call_memory_import(MemoryImportParams {
+            path: path.clone(),
+            reindex: false,
+        }).await;
+        assert!(result.is_err(), "{} should be rejected", path);
+    }
+}
+
 // =============================================================================
 // BATCH_STORE TESTS
 // =============================================================================
@@ -541,6 +696,14 @@ async fn test_hybrid_search_basic() {
         !res.semantic.is_empty() || !res.episodic.is_empty() || !res.procedural.is_empty(),
         "Hybrid search should return at least one result"
     );
+
+    // Fused ranking covers every source, best first
+    let total = res.semantic.len() + res.episodic.len() + res.procedural.len() + res.graph.len();
+    assert!(!res.combined.is_empty());
+    assert!(res.combined.len() <= total);
+    assert_eq!(res.combined[0].rank, 1);
+    assert_eq!(res.combined[0].score, 1.0);
+    assert!(res.combined.windows(2).all(|w| w[0].score >= w[1].score));
 }
 
 // =============================================================================
@@ -197,6 +197,7 @@ async fn stress_test_knowledge_graph_build() {
                 from: format!("Entity_{}", i),
                 to: format!("Entity_{}", i + 1),
                 relation_type: "next".to_string(),
+                ..Default::default()
             }
         ).await.unwrap();
     }
@@ -207,7 +208,7 @@ async fn stress_test_knowledge_graph_build() {
     // Test de lecture du graphe
     let read_start = Instant::now();
     let graph = ctx.server.call_knowledge_read_graph(
-        whytcard_intelligence::tools::KnowledgeReadGraphParams { limit: 0 }
+        whytcard_intelligence::tools::KnowledgeReadGraphParams::default()
     ).await.unwrap();
     let read_elapsed = read_start.elapsed();
 
@@ -238,6 +239,7 @@ async fn stress_test_knowledge_search_performance() {
         let result = ctx.server.call_knowledge_search(KnowledgeSearchParams {
             query: query.to_string(),
             limit: 100,
+            ..Default::default()
         }).await.unwrap();
         let elapsed = start.elapsed();
 
@@ -264,6 +266,7 @@ async fn stress_test_path_finding_deep_graph() {
                     from: format!("ChainNode_{}", i - 1),
                     to: format!("ChainNode_{}", i),
                     relation_type: "next".to_string(),
+                    ..Default::default()
                 }
             ).await.unwrap();
         }
@@ -283,6 +286,7 @@ async fn stress_test_path_finding_deep_graph() {
                 from: format!("ChainNode_{}", from),
                 to: format!("ChainNode_{}", to),
                 max_depth: depth,
+                ..Default::default()
             }
         ).await.unwrap();
         let elapsed = start.elapsed();
@@ -398,6 +402,7 @@ async fn stress_test_cortex_process_sequential() {
                 inject_doubt: false,
                 language: None,
                 task_type: None,
+                ..Default::default()
             }
         ).await.unwrap();
     }
@@ -482,6 +487,7 @@ async fn stress_test_concurrent_mixed_operations() {
                     let _ = server.call_knowledge_search(KnowledgeSearchParams {
                         query: "Concurrent".to_string(),
                         limit: 5,
+                        ..Default::default()
                     }).await;
                 }
             }
@@ -528,7 +534,7 @@ async fn stress_test_create_delete_cycle() {
         // Delete phase
         for key in keys {
             ctx.server.call_memory_delete(
-                whytcard_intelligence::tools::MemoryDeleteParams { key }
+                whytcard_intelligence::tools::MemoryDeleteParams { key, ..Default::default() }
             ).await.unwrap();
         }
     }
@@ -538,6 +544,44 @@ async fn stress_test_create_delete_cycle() {
              cycles, items_per_cycle, elapsed);
 }
 
+#[tokio::test]
+async fn stress_test_create_permanent_delete_cycle() {
+    let ctx = TestContext::new().await;
+    let cycles = 10;
+    let items_per_cycle = 20;
+
+    let start = Instant::now();
+
+    for cycle in 0..cycles {
+        // Create phase, reusing the same keys every cycle
+        let mut keys = vec![];
+        for i in 0..items_per_cycle {
+            let result = ctx.server.call_memory_store(MemoryStoreParams {
+                content: format!("Cycle {} item {}", cycle, i),
+                title: None,
+                tags: vec![],
+                metadata: None,
+                index: false,
+                key: Some(format!("permanent-{}", i)),
+            }).await.unwrap();
+            keys.push(result.key);
+        }
+
+        // Permanent delete phase: nothing is left in the trash
+        for key in keys {
+            let deleted = ctx.server.call_memory_delete(
+                whytcard_intelligence::tools::MemoryDeleteParams { key, permanent: true }
+            ).await.unwrap();
+            assert!(deleted.deleted);
+            assert!(!deleted.restorable);
+        }
+    }
+
+    let elapsed = start.elapsed();
+    println!("{} create/permanent delete cycles ({} items each) in {:?}",
+             cycles, items_per_cycle, elapsed);
+}
+
 // =============================================================================
 // LIMITES DU SYSTÈME
 // =============================================================================
@@ -47,6 +47,7 @@ async fn test_scenario_agent_implements_rust_feature() {
     let existing = ctx.server.call_knowledge_search(KnowledgeSearchParams {
         query: "thiserror error handling".to_string(),
         limit: 5,
+        ..Default::default()
     }).await.unwrap();
 
     // 3. Recherche documentation externe (mocké en test)
@@ -55,6 +56,7 @@ async fn test_scenario_agent_implements_rust_feature() {
         topic: Some("derive macro".to_string()),
         max_tokens: 3000,
         source: "auto".to_string(),
+        ..Default::default()
     }).await;
     // Note: Peut échouer si pas de clé API, c'est OK en test
 
@@ -128,6 +130,7 @@ Use thiserror for derive macros.
         from: "WhytCard-Intelligence".to_string(),
         to: "thiserror".to_string(),
         relation_type: "uses".to_string(),
+        ..Default::default()
     }).await.unwrap();
 
     // =========================================================================
@@ -143,6 +146,7 @@ Use thiserror for derive macros.
         inject_doubt: true,
         language: Some("rust".to_string()),
         task_type: Some(TaskType::Code),
+        ..Default::default()
     }).await.unwrap();
 
     assert!(code_result.success);
@@ -215,6 +219,7 @@ error[E0502]: cannot borrow `data` as mutable because it is also borrowed as imm
         inject_doubt: true,
         language: Some("rust".to_string()),
         task_type: Some(TaskType::Research),
+        ..Default::default()
     }).await.unwrap();
 
     assert!(analysis.success);
@@ -236,6 +241,7 @@ error[E0502]: cannot borrow `data` as mutable because it is also borrowed as imm
         inject_doubt: true,
         language: Some("rust".to_string()),
         task_type: Some(TaskType::Fix),
+        ..Default::default()
     }).await.unwrap();
 
     assert!(fix.success);
@@ -310,6 +316,7 @@ async fn test_scenario_agent_builds_knowledge_base() {
             from: from.to_string(),
             to: to.to_string(),
             relation_type: rel.to_string(),
+            ..Default::default()
         }).await.unwrap();
     }
 
@@ -346,6 +353,7 @@ async fn test_scenario_agent_builds_knowledge_base() {
     let search = ctx.server.call_knowledge_search(KnowledgeSearchParams {
         query: "async".to_string(),
         limit: 10,
+        ..Default::default()
     }).await.unwrap();
 
     // Devrait trouver tokio
@@ -403,6 +411,7 @@ async fn test_scenario_agent_uses_aggregated_context() {
         inject_doubt: false,
         language: Some("rust".to_string()),
         task_type: Some(TaskType::Document),
+        ..Default::default()
     }).await.unwrap();
 
     assert!(answer.success);
@@ -438,6 +447,7 @@ async fn test_scenario_acid_workflow_with_verification() {
     let existing_knowledge = ctx.server.call_knowledge_search(KnowledgeSearchParams {
         query: "logging tracing rust".to_string(),
         limit: 5,
+        ..Default::default()
     }).await.unwrap();
 
     let existing_memory = ctx.server.call_memory_search(MemorySearchParams {
@@ -490,6 +500,7 @@ async fn test_scenario_acid_workflow_with_verification() {
         inject_doubt: true,
         language: Some("rust".to_string()),
         task_type: Some(TaskType::Code),
+        ..Default::default()
     }).await.unwrap();
 
     assert!(code.success);
@@ -1,5 +1,7 @@
 //! Configuration types for the LLM engine
 
+use crate::sampling::{PenaltyConfig, SamplingStrategy};
+
 use serde::{Deserialize, Serialize};
 use std::num::NonZeroU32;
 use std::path::PathBuf;
@@ -62,14 +64,28 @@ pub struct ModelConfig {
     /// Micro-batch size
     pub ubatch_size: u32,
     
-    /// Number of GPU layers to offload
+    /// Number of GPU layers to offload (None = all layers if a GPU backend is
+    /// available, else CPU only)
     pub n_gpu_layers: Option<u32>,
     
+    /// GPU holding the whole model (`SplitMode::None`) or the small tensors
+    /// (`SplitMode::Row`); None = llama.cpp default (device 0)
+    #[serde(default)]
+    pub main_gpu: Option<i32>,
+    
+    /// How the model is spread across several GPUs
+    #[serde(default)]
+    pub split_mode: SplitMode,
+    
     /// Use memory mapping
     pub use_mmap: bool,
     
     /// Lock model in memory
     pub use_mlock: bool,
+    
+    /// Allow `LlmEngine::embed` on this model (pooled embedding contexts)
+    #[serde(default)]
+    pub embeddings: bool,
 }
 
 impl Default for ModelConfig {
@@ -80,8 +96,11 @@ impl Default for ModelConfig {
             batch_size: 512,
             ubatch_size: 256,
             n_gpu_layers: None, // Use engine default
+            main_gpu: None,
+            split_mode: SplitMode::default(),
             use_mmap: true,
             use_mlock: false,
+            embeddings: false,
         }
     }
 }
@@ -106,6 +125,37 @@ impl ModelConfig {
         self.n_gpu_layers = Some(layers);
         self
     }
+    
+    /// Set the main GPU
+    pub fn with_main_gpu(mut self, gpu: i32) -> Self {
+        self.main_gpu = Some(gpu);
+        self
+    }
+    
+    /// Set the multi-GPU split mode
+    pub fn with_split_mode(mut self, mode: SplitMode) -> Self {
+        self.split_mode = mode;
+        self
+    }
+    
+    /// Enable embedding generation
+    pub fn with_embeddings(mut self, enabled: bool) -> Self {
+        self.embeddings = enabled;
+        self
+    }
+}
+
+/// How a model is split across multiple GPUs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SplitMode {
+    /// Whole model on `main_gpu`
+    None,
+    /// Split whole layers across GPUs
+    #[default]
+    Layer,
+    /// Split rows of each tensor across GPUs
+    Row,
 }
 
 /// Generation configuration
@@ -146,6 +196,15 @@ pub struct GenerationConfig {
     
     /// System prompt to prepend
     pub system_prompt: Option<String>,
+    
+    /// GBNF grammar constraining the output (root rule: `root`)
+    #[serde(default)]
+    pub grammar: Option<String>,
+    
+    /// Sampling strategy (e.g. Mirostat); overrides `temperature`, `top_k`,
+    /// `top_p` and `min_p` when set. Penalties apply either way.
+    #[serde(default)]
+    pub strategy: Option<SamplingStrategy>,
 }
 
 impl Default for GenerationConfig {
@@ -163,6 +222,8 @@ impl Default for GenerationConfig {
             seed: None,
             stop_sequences: vec![],
             system_prompt: None,
+            grammar: None,
+            strategy: None,
         }
     }
 }
@@ -225,6 +286,37 @@ impl GenerationConfig {
         self.stop_sequences.push(seq.into());
         self
     }
+    
+    /// Constrain output with a GBNF grammar
+    pub fn with_grammar(mut self, grammar: impl Into<String>) -> Self {
+        self.grammar = Some(grammar.into());
+        self
+    }
+    
+    /// Sample with an explicit strategy (e.g. `SamplingStrategy::mirostat_v2`)
+    pub fn with_strategy(mut self, strategy: SamplingStrategy) -> Self {
+        self.strategy = Some(strategy);
+        self
+    }
+    
+    /// Set repeat, frequency and presence penalties
+    pub fn with_penalties(mut self, penalties: PenaltyConfig) -> Self {
+        self.repeat_penalty = penalties.repeat_penalty;
+        self.repeat_last_n = penalties.repeat_last_n;
+        self.frequency_penalty = penalties.frequency_penalty;
+        self.presence_penalty = penalties.presence_penalty;
+        self
+    }
+    
+    /// Penalties applied before truncation (see [`crate::sampling`] for the chain order)
+    pub fn penalties(&self) -> PenaltyConfig {
+        PenaltyConfig {
+            repeat_penalty: self.repeat_penalty,
+            repeat_last_n: self.repeat_last_n,
+            frequency_penalty: self.frequency_penalty,
+            presence_penalty: self.presence_penalty,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -258,5 +350,12 @@ mod tests {
         
         assert_eq!(config.context_size, NonZeroU32::new(8192));
         assert_eq!(config.n_gpu_layers, Some(32));
+        assert_eq!(config.split_mode, SplitMode::Layer);
+        
+        let config = config.with_main_gpu(1).with_split_mode(SplitMode::None);
+        assert_eq!(config.main_gpu, Some(1));
+        assert_eq!(serde_json::to_value(config.split_mode).unwrap(), "none");
+        assert!(!config.embeddings);
+        assert!(config.with_embeddings(true).embeddings);
     }
 }
@@ -2,19 +2,36 @@
 
 use crate::config::{GenerationConfig, LlmConfig, ModelConfig};
 use crate::error::{LlmError, Result};
+use crate::gpu::GpuInfo;
+use crate::json_schema::{schema_to_grammar, validate_json};
+use crate::lora::{self, ActiveLora, LoraInfo};
 use crate::model::{LoadedModel, ModelManager};
-use crate::session::{ChatSession, MessageRole};
-use crate::streaming::{StopReason, StreamSender, TokenStream};
+use crate::sampling::{grammar_sampler, SamplingStrategy};
+use crate::session::{ChatMessage, ChatSession, MessageRole, PromptTemplate};
+use crate::state::KvState;
+use crate::streaming::{Completion, StopReason, StreamSender, TokenStream, Utf8Buffer};
+use crate::tools::{parse_outcome, tool_choice_schema, tools_system_prompt, GenerationOutcome, ToolSpec};
 
-use llama_cpp_2::context::params::LlamaContextParams;
+use llama_cpp_2::context::params::{LlamaContextParams, LlamaPoolingType};
+use llama_cpp_2::context::LlamaContext;
 use llama_cpp_2::llama_backend::LlamaBackend;
 use llama_cpp_2::llama_batch::LlamaBatch;
 use llama_cpp_2::model::{AddBos, LlamaChatMessage, Special};
+use llama_cpp_2::token::LlamaToken;
 use llama_cpp_2::sampling::LlamaSampler;
 
-use std::sync::Arc;
+use std::num::NonZeroU32;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use tokio_util::sync::CancellationToken;
 use tracing::{debug, info, warn};
 
+/// Output budget for summaries written under `ContextPolicy::SummarizeOldest`
+const SUMMARY_MAX_TOKENS: u32 = 256;
+
+/// Most sequences llama.cpp accepts in one context (`LLAMA_MAX_SEQ`)
+const MAX_SEQUENCES: usize = 64;
+
 /// Callback for streaming tokens
 pub type TokenCallback = Box<dyn FnMut(&str, u32, bool) -> bool + Send>;
 
@@ -29,8 +46,8 @@ pub struct LlmEngine {
     /// Engine configuration
     config: LlmConfig,
     
-    /// Currently active model name
-    active_model: Option<String>,
+    /// Evaluated prompt prefix reused by generation when prompts start with it
+    prefix_state: Mutex<Option<Arc<KvState>>>,
 }
 
 impl LlmEngine {
@@ -45,8 +62,14 @@ impl LlmEngine {
         let backend = LlamaBackend::init()
             .map_err(|e| LlmError::BackendError(format!("Failed to init backend: {}", e)))?;
         
-        let backend = Arc::new(backend);
-        
+        Ok(Self::with_backend(Arc::new(backend), config))
+    }
+    
+    /// Create engine on an already initialized backend
+    ///
+    /// llama.cpp allows a single backend per process, so engines created after
+    /// the first one must share it.
+    pub fn with_backend(backend: Arc<LlamaBackend>, config: LlmConfig) -> Self {
         // Check capabilities
         if backend.supports_gpu_offload() {
             info!("GPU acceleration available");
@@ -54,57 +77,84 @@ impl LlmEngine {
             warn!("GPU acceleration not available, using CPU");
         }
         
+        let default_gpu_layers = if config.use_gpu { config.n_gpu_layers } else { 0 };
         let model_manager = ModelManager::new(Arc::clone(&backend), &config.models_dir)
-            .with_default_gpu_layers(config.n_gpu_layers);
+            .with_default_gpu_layers(default_gpu_layers);
         
-        Ok(Self {
+        Self {
             backend,
             model_manager,
             config,
-            active_model: None,
-        })
+            prefix_state: Mutex::new(None),
+        }
     }
     
     /// Load a model from path
     pub fn load_model(&mut self, path: impl Into<std::path::PathBuf>) -> Result<()> {
         let config = ModelConfig::from_path(path);
-        let model = self.model_manager.load(config)?;
-        self.active_model = Some(model.info.name.clone());
+        self.model_manager.load(config)?;
         Ok(())
     }
     
     /// Load a model with custom config
     pub fn load_model_with_config(&mut self, config: ModelConfig) -> Result<()> {
-        let model = self.model_manager.load(config)?;
-        self.active_model = Some(model.info.name.clone());
+        self.model_manager.load(config)?;
         Ok(())
     }
     
     /// Load a model by name
     pub fn load_model_by_name(&mut self, name: &str) -> Result<()> {
-        let model = self.model_manager.load_by_name(name)?;
-        self.active_model = Some(model.info.name.clone());
+        self.model_manager.load_by_name(name)?;
+        Ok(())
+    }
+    
+    /// Load a model under an explicit name, keeping other models resident
+    pub fn load_model_as(
+        &mut self,
+        name: &str,
+        path: impl Into<std::path::PathBuf>,
+        config: ModelConfig,
+    ) -> Result<()> {
+        self.model_manager.load_model_as(name, path, config)?;
         Ok(())
     }
     
-    /// Unload current model
+    /// Unload the active (most recently loaded) model
     pub fn unload_model(&mut self) -> bool {
-        if let Some(name) = self.active_model.take() {
-            self.model_manager.unload(&name)
-        } else {
-            false
+        match self.model_manager.most_recent() {
+            Some(name) => self.model_manager.unload(&name),
+            None => false,
         }
     }
     
-    /// Get active model
+    /// Unload a model by name
+    pub fn unload_model_by_name(&mut self, name: &str) -> bool {
+        self.model_manager.unload(name)
+    }
+    
+    /// Get active model (the most recently loaded one)
     pub fn active_model(&self) -> Option<Arc<LoadedModel>> {
-        self.active_model.as_ref()
-            .and_then(|name| self.model_manager.get(name))
+        self.model_manager.most_recent()
+            .and_then(|name| self.model_manager.get(&name))
+    }
+    
+    /// Get a loaded model by name
+    pub fn model(&self, name: &str) -> Option<Arc<LoadedModel>> {
+        self.model_manager.get(name)
     }
     
     /// Check if a model is loaded
     pub fn has_model(&self) -> bool {
-        self.active_model.is_some()
+        self.model_manager.most_recent().is_some()
+    }
+    
+    /// Resolve a model by name, or the active model when `name` is `None`
+    fn resolve_model(&self, name: Option<&str>) -> Result<Arc<LoadedModel>> {
+        match name {
+            Some(name) => self.model_manager.get(name)
+                .ok_or_else(|| LlmError::ModelNotFound(name.to_string())),
+            None => self.active_model().ok_or(LlmError::NoModelLoaded),
+        }
     }
     
     /// List available models
@@ -122,6 +172,101 @@ impl LlmEngine {
         self.generate_with_callback(prompt, config, None)
     }
 
+    /// Generate text with a specific loaded model (`None` = active model)
+    pub fn generate_with_model(
+        &self,
+        model: Option<&str>,
+        prompt: &str,
+        config: &GenerationConfig,
+    ) -> Result<String> {
+        Ok(self.generate_on(model, prompt, config, None, None)?.text)
+    }
+
+    /// Generate text until done or until `cancel` is triggered
+    ///
+    /// The token is checked between tokens; on cancellation the text produced
+    /// so far is returned with `StopReason::Cancelled`. Each generation
+    /// decodes into its own context, so a cancelled run leaves the engine (and
+    /// any prefilled state) reusable.
+    pub fn generate_cancellable(
+        &self,
+        prompt: &str,
+        config: &GenerationConfig,
+        cancel: &CancellationToken,
+    ) -> Result<Completion> {
+        self.generate_on(None, prompt, config, None, Some(cancel))
+    }
+
+    /// Generate JSON matching a JSON Schema
+    ///
+    /// The schema is compiled to a GBNF grammar (replacing any grammar in
+    /// `config`), so decoding can only produce conforming JSON; the parsed
+    /// value is validated against the schema before it is returned. Output
+    /// cut off before the JSON closes (e.g. at `max_tokens`) is reported as
+    /// `IncompleteJson` with the partial text.
+    pub fn generate_json(
+        &self,
+        prompt: &str,
+        schema: &serde_json::Value,
+        config: &GenerationConfig,
+    ) -> Result<serde_json::Value> {
+        let grammar = schema_to_grammar(schema)?;
+        let config = config.clone().with_grammar(grammar);
+        
+        let output = self.generate(prompt, &config)?;
+        let value: serde_json::Value = match serde_json::from_str(output.trim()) {
+            Ok(value) => value,
+            Err(e) if e.is_eof() => return Err(LlmError::IncompleteJson(output)),
+            Err(e) => return Err(e.into()),
+        };
+        
+        validate_json(&value, schema)?;
+        Ok(value)
+    }
+    
+    /// Answer a conversation with text or with calls to `tools`
+    ///
+    /// The tools are described in the system message and the reply is
+    /// constrained by a grammar to a JSON object, so it always parses: either
+    /// a text answer or one or more tool calls whose arguments match the
+    /// tool's parameter schema. The prompt is rendered with the template
+    /// `chat` would use for the active model.
+    pub fn generate_with_tools(
+        &self,
+        messages: &[ChatMessage],
+        tools: &[ToolSpec],
+        config: &GenerationConfig,
+    ) -> Result<GenerationOutcome> {
+        let model = self.active_model()
+            .ok_or(LlmError::NoModelLoaded)?;
+        let schema = tool_choice_schema(tools)?;
+        
+        // Tool descriptions extend the system message, or open the conversation
+        let mut messages = messages.to_vec();
+        let system = config.system_prompt.clone().unwrap_or_default();
+        let tools_prompt = tools_system_prompt(tools);
+        match messages.first_mut() {
+            Some(first) if first.role == MessageRole::System => {
+                first.content = format!("{}\n\n{}", first.content, tools_prompt);
+            }
+            _ if system.is_empty() => messages.insert(0, ChatMessage::system(tools_prompt)),
+            _ => messages.insert(0, ChatMessage::system(format!("{}\n\n{}", system, tools_prompt))),
+        }
+        
+        let prompt = Self::render_chat(&model, &messages, None);
+        let mut config = config.clone().with_grammar(schema_to_grammar(&schema)?);
+        config.system_prompt = None; // Already in the rendered prompt
+        
+        let output = self.generate(&prompt, &config)?;
+        let value: serde_json::Value = match serde_json::from_str(output.trim()) {
+            Ok(value) => value,
+            Err(e) if e.is_eof() => return Err(LlmError::IncompleteJson(output)),
+            Err(e) => return Err(e.into()),
+        };
+        
+        parse_outcome(&value, tools)
+    }
+
     /// Generate text with streaming callback
     /// 
     /// The callback receives (token_text, token_id, is_special) and returns
@@ -130,82 +275,97 @@ impl LlmEngine {
         &self,
         prompt: &str,
         config: &GenerationConfig,
-        mut callback: Option<TokenCallback>,
+        callback: Option<TokenCallback>,
     ) -> Result<String> {
-        let model = self.active_model()
-            .ok_or(LlmError::NoModelLoaded)?;
+        Ok(self.generate_on(None, prompt, config, callback, None)?.text)
+    }
+
+    /// Blocking generation on a named model (`None` = active model)
+    fn generate_on(
+        &self,
+        model: Option<&str>,
+        prompt: &str,
+        config: &GenerationConfig,
+        mut callback: Option<TokenCallback>,
+        cancel: Option<&CancellationToken>,
+    ) -> Result<Completion> {
+        let model = self.resolve_model(model)?;
         
         // Create context
-        let ctx_params = self.build_context_params(&model.config);
+        let loras = model.active_loras();
+        let ctx_params = Self::build_context_params(&model.config, &self.config);
         let mut ctx = model.model.new_context(&self.backend, ctx_params)
             .map_err(|e| LlmError::ContextError(e.to_string()))?;
+        lora::attach_all(&loras, &mut ctx)?;
         
         // Build full prompt with system if present
-        let full_prompt = if let Some(system) = &config.system_prompt {
-            format!("{}\n\n{}", system, prompt)
-        } else {
-            prompt.to_string()
-        };
+        let full_prompt = Self::full_prompt(prompt, config);
         
         // Tokenize
-        let tokens = model.model.str_to_token(&full_prompt, AddBos::Always)
-            .map_err(|e| LlmError::TokenizationError(e.to_string()))?;
+        let tokens = Self::tokenize_with(&model, &full_prompt)?;
         
         debug!("Prompt tokens: {}", tokens.len());
         
-        // Process prompt
-        let mut batch = LlamaBatch::new(ctx.n_ctx() as usize, 1);
-        for (i, token) in tokens.iter().enumerate() {
-            let is_last = i == tokens.len() - 1;
-            batch.add(*token, i as i32, &[0], is_last)
-                .map_err(|e| LlmError::GenerationError(e.to_string()))?;
-        }
-        
-        ctx.decode(&mut batch)
-            .map_err(|e| LlmError::GenerationError(e.to_string()))?;
+        // Process prompt, skipping a restored prefix
+        let prefix = self.prefix_state();
+        let start = Self::restore_prefix(&model, &mut ctx, &tokens, prefix.as_deref());
+        let mut batch = Self::decode_prompt(&mut ctx, &tokens, start)?;
         
         // Generate
-        let mut sampler = Self::build_sampler(config);
+        let mut sampler = Self::build_model_sampler(&model, config)?;
+        let mut utf8 = Utf8Buffer::new();
         let mut output = String::new();
         let mut pos = tokens.len();
+        let mut stop_reason = StopReason::MaxTokens;
         
         for _ in 0..config.max_tokens {
+            if cancel.is_some_and(|c| c.is_cancelled()) {
+                debug!("Generation cancelled after {} tokens", pos - tokens.len());
+                // Drop an unfinished codepoint rather than emit replacement text
+                return Ok(Completion { text: output, stop_reason: StopReason::Cancelled });
+            }
+    
             // Sample next token
             let new_token = sampler.sample(&ctx, batch.n_tokens() - 1);
             sampler.accept(new_token);
             
             // Check for end
             if model.model.is_eog_token(new_token) {
+                stop_reason = StopReason::EndOfGeneration;
                 break;
             }
             
-            // Decode token to text
-            let token_str = model.model.token_to_str(new_token, Special::Tokenize)
+            // Decode token bytes; a codepoint may span several tokens
+            let token_bytes = model.model.token_to_bytes(new_token, Special::Tokenize)
                 .map_err(|e| LlmError::GenerationError(e.to_string()))?;
             
-            let token_id = new_token.0 as u32;
-            // Note: checking if token is special is complex with enumflags
-            // For now, we assume non-special for user tokens
-            let is_special = false;
-            
-            // Call streaming callback
-            if let Some(ref mut cb) = callback {
-                if !cb(&token_str, token_id, is_special) {
-                    break; // Callback requested stop
-                }
-            }
-            
-            // Check stop sequences
-            output.push_str(&token_str);
-            if config.stop_sequences.iter().any(|s| output.ends_with(s)) {
-                // Remove stop sequence from output
-                for stop in &config.stop_sequences {
-                    if output.ends_with(stop) {
-                        output.truncate(output.len() - stop.len());
+            if let Some(token_str) = utf8.push(&token_bytes) {
+                let token_id = new_token.0 as u32;
+                // Note: checking if token is special is complex with enumflags
+                // For now, we assume non-special for user tokens
+                let is_special = false;
+                
+                // Call streaming callback
+                if let Some(ref mut cb) = callback {
+                    if !cb(&token_str, token_id, is_special) {
+                        // Callback requested stop
+                        stop_reason = StopReason::Cancelled;
                         break;
                     }
                 }
-                break;
+                
+                // Check stop sequences
+                output.push_str(&token_str);
+                if config.stop_sequences.iter().any(|s| output.ends_with(s)) {
+                    // Remove stop sequence from output
+                    for stop in &config.stop_sequences {
+                        if output.ends_with(stop) {
+                            output.truncate(output.len() - stop.len());
+                            break;
+                        }
+                    }
+                    return Ok(Completion { text: output, stop_reason: StopReason::StopSequence });
+                }
             }
             
             // Prepare next iteration
@@ -219,29 +379,75 @@ impl LlmEngine {
             pos += 1;
         }
         
-        Ok(output)
+        if let Some(rest) = utf8.flush() {
+            output.push_str(&rest);
+        }
+        
+        Ok(Completion { text: output, stop_reason })
     }
 
     /// Generate text with async streaming via channel
     /// 
     /// Spawns generation in a blocking task and sends tokens through a channel.
+    /// Tokens are only emitted once they form complete UTF-8, `Done` carries the
+    /// final token counts, and dropping the returned `TokenStream` stops decoding.
     pub fn generate_stream(
         &self,
         prompt: &str,
         config: &GenerationConfig,
     ) -> Result<TokenStream> {
-        let model = self.active_model()
-            .ok_or(LlmError::NoModelLoaded)?;
+        self.generate_stream_with_model(None, prompt, config)
+    }
+
+    /// Stream generation that stops when `cancel` is triggered
+    ///
+    /// The token is checked between tokens. On cancellation the stream ends
+    /// with `StreamEvent::Cancelled` carrying the partial text instead of
+    /// `Done`; the engine stays usable for further generations.
+    pub fn generate_stream_cancellable(
+        &self,
+        prompt: &str,
+        config: &GenerationConfig,
+        cancel: CancellationToken,
+    ) -> Result<TokenStream> {
+        self.stream_on(None, prompt, config, Some(cancel))
+    }
+
+    /// Stream generation from a specific loaded model (`None` = active model)
+    pub fn generate_stream_with_model(
+        &self,
+        model: Option<&str>,
+        prompt: &str,
+        config: &GenerationConfig,
+    ) -> Result<TokenStream> {
+        self.stream_on(model, prompt, config, None)
+    }
+
+    /// Spawn streaming generation on a named model (`None` = active model)
+    fn stream_on(
+        &self,
+        model: Option<&str>,
+        prompt: &str,
+        config: &GenerationConfig,
+        cancel: Option<CancellationToken>,
+    ) -> Result<TokenStream> {
+        let model = self.resolve_model(model)?;
+        
+        // Reject a bad grammar up front rather than as a stream error
+        if let Some(grammar) = &config.grammar {
+            grammar_sampler(&model.model, grammar)?;
+        }
         
         let (sender, stream) = StreamSender::channel(256);
+        let sender = sender.with_cancel(cancel);
         
         // Clone what we need for the blocking task
         let model = Arc::clone(&model);
         let backend = Arc::clone(&self.backend);
         let config = config.clone();
         let prompt = prompt.to_string();
-        let n_threads = self.config.n_threads;
-        let n_threads_batch = self.config.n_threads_batch;
+        let prefix = self.prefix_state();
+        let engine_config = self.config.clone();
         
         // Spawn blocking task for generation
         tokio::task::spawn_blocking(move || {
@@ -250,8 +456,8 @@ impl LlmEngine {
                 &backend,
                 &prompt,
                 &config,
-                n_threads,
-                n_threads_batch,
+                prefix.as_deref(),
+                &engine_config,
                 sender,
             );
         });
@@ -265,53 +471,49 @@ impl LlmEngine {
         backend: &LlamaBackend,
         prompt: &str,
         config: &GenerationConfig,
-        n_threads: i32,
-        n_threads_batch: i32,
+        prefix: Option<&KvState>,
+        engine_config: &LlmConfig,
         mut sender: StreamSender,
     ) {
         let result = (|| -> Result<(String, StopReason)> {
             // Create context
-            let ctx_params = LlamaContextParams::default()
-                .with_n_ctx(model.config.context_size)
-                .with_n_batch(model.config.batch_size)
-                .with_n_threads(n_threads)
-                .with_n_threads_batch(n_threads_batch);
+            let loras = model.active_loras();
+            let ctx_params = Self::build_context_params(&model.config, engine_config);
             
             let mut ctx = model.model.new_context(backend, ctx_params)
                 .map_err(|e| LlmError::ContextError(e.to_string()))?;
+            lora::attach_all(&loras, &mut ctx)?;
             
             // Build prompt
-            let full_prompt = if let Some(system) = &config.system_prompt {
-                format!("{}\n\n{}", system, prompt)
-            } else {
-                prompt.to_string()
-            };
+            let full_prompt = Self::full_prompt(prompt, config);
             
             // Tokenize
-            let tokens = model.model.str_to_token(&full_prompt, AddBos::Always)
-                .map_err(|e| LlmError::TokenizationError(e.to_string()))?;
+            let tokens = Self::tokenize_with(model, &full_prompt)?;
             
             // Send start (blocking)
             sender.send_start_blocking(tokens.len());
             
-            // Process prompt
-            let mut batch = LlamaBatch::new(ctx.n_ctx() as usize, 1);
-            for (i, token) in tokens.iter().enumerate() {
-                let is_last = i == tokens.len() - 1;
-                batch.add(*token, i as i32, &[0], is_last)
-                    .map_err(|e| LlmError::GenerationError(e.to_string()))?;
-            }
-            
-            ctx.decode(&mut batch)
-                .map_err(|e| LlmError::GenerationError(e.to_string()))?;
+            // Process prompt, skipping a restored prefix
+            let prefill_start = std::time::Instant::now();
+            let start = Self::restore_prefix(model, &mut ctx, &tokens, prefix);
+            let mut batch = Self::decode_prompt(&mut ctx, &tokens, start)?;
+            sender.record_prefill(prefill_start.elapsed());
             
             // Generate
-            let mut sampler = Self::build_sampler(config);
+            let mut sampler = Self::build_model_sampler(model, config)?;
+            let mut utf8 = Utf8Buffer::new();
             let mut output = String::new();
             let mut pos = tokens.len();
             let mut stop_reason = StopReason::MaxTokens;
+            let mut last_token_id = 0;
             
             for _ in 0..config.max_tokens {
+                // Stop decoding as soon as the TokenStream is dropped or cancelled
+                if sender.should_stop() {
+                    stop_reason = StopReason::Cancelled;
+                    break;
+                }
+                
                 // Sample
                 let new_token = sampler.sample(&ctx, batch.n_tokens() - 1);
                 sampler.accept(new_token);
@@ -322,20 +524,26 @@ impl LlmEngine {
                     break;
                 }
                 
-                // Decode token
-                let token_str = model.model.token_to_str(new_token, Special::Tokenize)
+                // Decode token bytes, holding back an unfinished codepoint
+                let token_bytes = model.model.token_to_bytes(new_token, Special::Tokenize)
                     .map_err(|e| LlmError::GenerationError(e.to_string()))?;
                 
                 let token_id = new_token.0 as u32;
+                last_token_id = token_id;
                 
-                // Send token (blocking)
-                if !sender.send_token_blocking(token_str.clone(), token_id, false) {
-                    stop_reason = StopReason::Cancelled;
-                    break;
+                match utf8.push(&token_bytes) {
+                    Some(token_str) => {
+                        // Send token (blocking)
+                        if !sender.send_token_blocking(token_str.clone(), token_id, false) {
+                            stop_reason = StopReason::Cancelled;
+                            break;
+                        }
+                        output.push_str(&token_str);
+                    }
+                    None => sender.record_token(),
                 }
                 
                 // Check stop sequences
-                output.push_str(&token_str);
                 let mut hit_stop = false;
                 for stop in &config.stop_sequences {
                     if output.ends_with(stop) {
@@ -360,11 +568,24 @@ impl LlmEngine {
                 pos += 1;
             }
             
+            // Emit any trailing bytes of a truncated codepoint as replacement text
+            if stop_reason != StopReason::Cancelled {
+                if let Some(rest) = utf8.flush() {
+                    sender.send_buffered_blocking(rest.clone(), last_token_id);
+                    output.push_str(&rest);
+                }
+            }
+            
             Ok((output, stop_reason))
         })();
         
         match result {
+            Ok((output, StopReason::Cancelled)) => {
+                sender.send_metrics_blocking();
+                sender.send_cancelled_blocking(output);
+            }
             Ok((output, stop_reason)) => {
+                sender.send_metrics_blocking();
                 sender.send_done_blocking(output, stop_reason);
             }
             Err(e) => {
@@ -373,13 +594,378 @@ impl LlmEngine {
         }
     }
 
+    /// Evaluate a prompt prefix with the active model and keep its KV cache
+    ///
+    /// Later generations whose tokens start with this prefix restore the cache
+    /// instead of re-evaluating it. Pass the exact leading text of those
+    /// prompts (e.g. the system prompt followed by `"\n\n"`). Returns the
+    /// number of prefix tokens.
+    pub fn prefill(&self, prefix: &str) -> Result<usize> {
+        let model = self.active_model()
+            .ok_or(LlmError::NoModelLoaded)?;
+        let model_hash = model.gguf_hash()?;
+        
+        let loras = model.active_loras();
+        let ctx_params = Self::build_context_params(&model.config, &self.config);
+        let mut ctx = model.model.new_context(&self.backend, ctx_params)
+            .map_err(|e| LlmError::ContextError(e.to_string()))?;
+        lora::attach_all(&loras, &mut ctx)?;
+        
+        let tokens = Self::tokenize_with(&model, prefix)?;
+        Self::decode_prompt(&mut ctx, &tokens, 0)?;
+        
+        let mut data = vec![0u8; ctx.get_state_size()];
+        // SAFETY: `data` is sized from `get_state_size` for this context
+        let written = unsafe { ctx.copy_state_data(data.as_mut_ptr()) };
+        data.truncate(written);
+        
+        let state = KvState {
+            model_hash,
+            n_ctx: ctx.n_ctx(),
+            tokens: tokens.iter().map(|t| t.0).collect(),
+            data,
+        };
+        *self.lock_prefix_state() = Some(Arc::new(state));
+        
+        Ok(tokens.len())
+    }
+    
+    /// Apply a LoRA adapter to the active model, on top of any already applied
+    ///
+    /// Adapters stack in apply order, each at its own `scale` (1.0 = as
+    /// trained), and are attached to every context created afterwards. Fails
+    /// with `LoraMismatch` when the adapter declares a different architecture
+    /// than the model.
+    ///
+    /// Changing adapters invalidates KV state: the prefilled prefix is
+    /// dropped, and files written by `save_state` under another adapter set
+    /// no longer match what the model computes, so prefill again instead of
+    /// loading them.
+    pub fn apply_lora(&self, adapter_path: &Path, scale: f32) -> Result<()> {
+        let model = self.active_model()
+            .ok_or(LlmError::NoModelLoaded)?;
+        if !adapter_path.exists() {
+            return Err(LlmError::ModelNotFound(adapter_path.display().to_string()));
+        }
+        
+        let architecture = lora::gguf_architecture(adapter_path)?;
+        if let (Some(adapter_arch), Some(model_arch)) = (&architecture, &model.info.architecture) {
+            if adapter_arch != model_arch {
+                return Err(LlmError::LoraMismatch(format!(
+                    "{} targets {}, {} is {}",
+                    adapter_path.display(),
+                    adapter_arch,
+                    model.info.name,
+                    model_arch
+                )));
+            }
+        }
+        
+        let adapter = model.model.lora_adapter_init(adapter_path)
+            .map_err(|e| LlmError::ModelLoadError(format!(
+                "failed to load LoRA adapter {}: {}",
+                adapter_path.display(),
+                e
+            )))?;
+        
+        info!("Applied LoRA adapter {} (scale {}) to {}", adapter_path.display(), scale, model.info.name);
+        model.push_lora(ActiveLora::new(
+            LoraInfo {
+                path: adapter_path.to_path_buf(),
+                scale,
+                architecture,
+            },
+            adapter,
+        ));
+        self.clear_state();
+        Ok(())
+    }
+    
+    /// Remove every LoRA adapter from the active model, returning how many were applied
+    ///
+    /// Like `apply_lora`, this drops the prefilled KV state.
+    pub fn clear_loras(&self) -> Result<usize> {
+        let model = self.active_model()
+            .ok_or(LlmError::NoModelLoaded)?;
+        let removed = model.clear_loras();
+        if removed > 0 {
+            info!("Removed {} LoRA adapters from {}", removed, model.info.name);
+            self.clear_state();
+        }
+        Ok(removed)
+    }
+    
+    /// Write the prefilled KV cache to disk (see [`crate::state`] for the format)
+    pub fn save_state(&self, path: &Path) -> Result<()> {
+        if self.active_model().is_none() {
+            return Err(LlmError::NoModelLoaded);
+        }
+        let state = self.prefix_state()
+            .ok_or_else(|| LlmError::InvalidState("nothing to save, call prefill first".into()))?;
+        state.save(path)
+    }
+    
+    /// Load a KV cache saved by `save_state`, returning its prefix token count
+    ///
+    /// Fails with `StateModelMismatch` unless the active model is the exact
+    /// GGUF file the state was saved with.
+    pub fn load_state(&self, path: &Path) -> Result<usize> {
+        let model = self.active_model()
+            .ok_or(LlmError::NoModelLoaded)?;
+        
+        let state = KvState::load(path)?;
+        let model_hash = model.gguf_hash()?;
+        if state.model_hash != model_hash {
+            return Err(LlmError::StateModelMismatch(format!(
+                "state was saved with model {:016x}, {} is {:016x}",
+                state.model_hash, model.info.name, model_hash
+            )));
+        }
+        
+        let n_tokens = state.tokens.len();
+        *self.lock_prefix_state() = Some(Arc::new(state));
+        Ok(n_tokens)
+    }
+    
+    /// Drop the prefilled KV cache
+    pub fn clear_state(&self) {
+        *self.lock_prefix_state() = None;
+    }
+    
+    /// Current prefilled KV cache, if any
+    fn prefix_state(&self) -> Option<Arc<KvState>> {
+        self.lock_prefix_state().clone()
+    }
+    
+    fn lock_prefix_state(&self) -> std::sync::MutexGuard<'_, Option<Arc<KvState>>> {
+        self.prefix_state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+    
+    /// Restore `state` into `ctx` when it belongs to this model and is a
+    /// strict prefix of `tokens`, returning how many tokens are already evaluated
+    fn restore_prefix(
+        model: &LoadedModel,
+        ctx: &mut LlamaContext,
+        tokens: &[LlamaToken],
+        state: Option<&KvState>,
+    ) -> usize {
+        let Some(state) = state else {
+            return 0;
+        };
+        
+        // At least one token must remain to produce logits
+        if state.tokens.len() >= tokens.len()
+            || state.n_ctx != ctx.n_ctx()
+            || !state.tokens.iter().zip(tokens).all(|(a, b)| *a == b.0)
+        {
+            return 0;
+        }
+        if !matches!(model.gguf_hash(), Ok(hash) if hash == state.model_hash) {
+            return 0;
+        }
+        
+        // SAFETY: the blob was captured from a context of the same model and size
+        let read = unsafe { ctx.set_state_data(&state.data) };
+        if read != state.data.len() {
+            warn!("Saved state could not be restored, evaluating full prompt");
+            ctx.clear_kv_cache();
+            return 0;
+        }
+        
+        debug!("Restored {} prefix tokens", state.tokens.len());
+        state.tokens.len()
+    }
+    
+    /// Decode `tokens[start..]` at their positions, returning the batch whose
+    /// last entry carries the logits to sample from
+    fn decode_prompt(
+        ctx: &mut LlamaContext,
+        tokens: &[LlamaToken],
+        start: usize,
+    ) -> Result<LlamaBatch> {
+        let mut batch = LlamaBatch::new(ctx.n_ctx() as usize, 1);
+        for (i, token) in tokens.iter().enumerate().skip(start) {
+            let is_last = i == tokens.len() - 1;
+            batch.add(*token, i as i32, &[0], is_last)
+                .map_err(|e| LlmError::GenerationError(e.to_string()))?;
+        }
+        
+        ctx.decode(&mut batch)
+            .map_err(|e| LlmError::GenerationError(e.to_string()))?;
+        
+        Ok(batch)
+    }
+
+    /// Tokenize text with the active model's vocabulary
+    ///
+    /// Uses the same settings as `generate` (BOS prepended, special tokens
+    /// parsed), so `generate(text, cfg)` feeds exactly these tokens when
+    /// `cfg.system_prompt` is `None`.
+    pub fn tokenize(&self, text: &str) -> Result<Vec<i32>> {
+        let model = self.active_model()
+            .ok_or(LlmError::NoModelLoaded)?;
+        
+        Ok(Self::tokenize_with(&model, text)?
+            .into_iter()
+            .map(|t| t.0)
+            .collect())
+    }
+    
+    /// Convert token IDs back into text
+    pub fn detokenize(&self, tokens: &[i32]) -> Result<String> {
+        let model = self.active_model()
+            .ok_or(LlmError::NoModelLoaded)?;
+        
+        // Bytes are joined before decoding so split codepoints survive
+        let mut utf8 = Utf8Buffer::new();
+        let mut text = String::new();
+        for &token in tokens {
+            let bytes = model.model.token_to_bytes(LlamaToken(token), Special::Tokenize)
+                .map_err(|e| LlmError::TokenizationError(e.to_string()))?;
+            if let Some(chunk) = utf8.push(&bytes) {
+                text.push_str(&chunk);
+            }
+        }
+        if let Some(rest) = utf8.flush() {
+            text.push_str(&rest);
+        }
+        
+        Ok(text)
+    }
+    
+    /// Count the tokens `text` would consume as a prompt
+    pub fn count_tokens(&self, text: &str) -> Result<usize> {
+        let model = self.active_model()
+            .ok_or(LlmError::NoModelLoaded)?;
+        
+        Ok(Self::tokenize_with(&model, text)?.len())
+    }
+    
+    /// Count the prompt tokens `generate(prompt, config)` would feed,
+    /// including the configured system prompt
+    pub fn count_prompt_tokens(&self, prompt: &str, config: &GenerationConfig) -> Result<usize> {
+        self.count_tokens(&Self::full_prompt(prompt, config))
+    }
+
+    /// Prompt actually fed to the model: system prompt (if any) then prompt
+    fn full_prompt(prompt: &str, config: &GenerationConfig) -> String {
+        if let Some(system) = &config.system_prompt {
+            format!("{}\n\n{}", system, prompt)
+        } else {
+            prompt.to_string()
+        }
+    }
+    
+    /// Tokenize exactly as generation does
+    fn tokenize_with(model: &LoadedModel, text: &str) -> Result<Vec<LlamaToken>> {
+        model.model.str_to_token(text, AddBos::Always)
+            .map_err(|e| LlmError::TokenizationError(e.to_string()))
+    }
+
+    /// Embed text with the active model, returning an L2-normalized vector
+    pub fn embed(&self, text: &str) -> Result<Vec<f32>> {
+        let mut embeddings = self.embed_batch(&[text])?;
+        embeddings.pop()
+            .ok_or_else(|| LlmError::GenerationError("No embedding produced".into()))
+    }
+    
+    /// Embed several texts, packing as many as fit into each decode pass
+    ///
+    /// Requires the model to be loaded with `ModelConfig::embeddings` set.
+    /// Vectors are mean-pooled and L2-normalized, in input order. Without an
+    /// explicit `context_size` the context is sized to the longest input
+    /// rather than the training context.
+    pub fn embed_batch(&self, texts: &[&str]) -> Result<Vec<Vec<f32>>> {
+        let model = self.active_model()
+            .ok_or(LlmError::NoModelLoaded)?;
+        
+        if !model.config.embeddings {
+            return Err(LlmError::EmbeddingsUnsupported(model.info.name.clone()));
+        }
+        if texts.is_empty() {
+            return Ok(Vec::new());
+        }
+        
+        let tokenized = texts.iter()
+            .map(|text| Self::tokenize_with(&model, text))
+            .collect::<Result<Vec<_>>>()?;
+        
+        let max_ctx = Self::context_size(&model);
+        let longest = tokenized.iter().map(Vec::len).max().unwrap_or(0);
+        if longest > max_ctx {
+            return Err(LlmError::TokenizationError(format!(
+                "Input of {} tokens exceeds context size {}",
+                longest,
+                max_ctx
+            )));
+        }
+        let n_ctx = match model.config.context_size {
+            Some(_) => max_ctx,
+            None => longest.max(1),
+        };
+        let n_seq_max = texts.len().min(MAX_SEQUENCES);
+        
+        // Pooled embeddings need the whole batch in one ubatch
+        let ctx_params = Self::build_context_params(&model.config, &self.config)
+            .with_n_ctx(NonZeroU32::new(n_ctx as u32))
+            .with_n_batch(n_ctx as u32)
+            .with_n_ubatch(n_ctx as u32)
+            .with_n_seq_max(n_seq_max as u32)
+            .with_embeddings(true)
+            .with_pooling_type(LlamaPoolingType::Mean);
+        let mut ctx = model.model.new_context(&self.backend, ctx_params)
+            .map_err(|e| LlmError::ContextError(e.to_string()))?;
+        
+        let mut embeddings = Vec::with_capacity(texts.len());
+        let mut batch = LlamaBatch::new(n_ctx, n_seq_max as i32);
+        let mut batch_tokens = 0;
+        let mut batch_seqs = 0;
+        
+        for tokens in &tokenized {
+            if batch_tokens + tokens.len() > n_ctx || batch_seqs == n_seq_max {
+                Self::decode_embeddings(&mut ctx, &mut batch, batch_seqs, &mut embeddings)?;
+                batch_tokens = 0;
+                batch_seqs = 0;
+            }
+            batch.add_sequence(tokens, batch_seqs as i32, false)
+                .map_err(|e| LlmError::GenerationError(e.to_string()))?;
+            batch_tokens += tokens.len();
+            batch_seqs += 1;
+        }
+        Self::decode_embeddings(&mut ctx, &mut batch, batch_seqs, &mut embeddings)?;
+        
+        Ok(embeddings)
+    }
+    
+    /// Decode one packed batch and collect a pooled vector per sequence
+    fn decode_embeddings(
+        ctx: &mut llama_cpp_2::context::LlamaContext,
+        batch: &mut LlamaBatch,
+        n_seqs: usize,
+        output: &mut Vec<Vec<f32>>,
+    ) -> Result<()> {
+        ctx.clear_kv_cache();
+        ctx.decode(batch)
+            .map_err(|e| LlmError::GenerationError(e.to_string()))?;
+        
+        for seq in 0..n_seqs {
+            let embedding = ctx.embeddings_seq_ith(seq as i32)
+                .map_err(|e| LlmError::GenerationError(e.to_string()))?;
+            output.push(normalize(embedding));
+        }
+        
+        batch.clear();
+        Ok(())
+    }
+
     /// Chat completion with session
     pub fn chat(&self, session: &mut ChatSession, message: &str, config: &GenerationConfig) -> Result<String> {
         let model = self.active_model()
             .ok_or(LlmError::NoModelLoaded)?;
         
-        // Add user message
+        // Add user message, then make the history fit the context window
         session.add_user_message(message);
+        self.fit_session(&model, session, config)?;
         
         // Build prompt using chat template if available
         let prompt = self.build_chat_prompt(&model, session, config)?;
@@ -406,8 +992,9 @@ impl LlmEngine {
         let model = self.active_model()
             .ok_or(LlmError::NoModelLoaded)?;
         
-        // Add user message
+        // Add user message, then make the history fit the context window
         session.add_user_message(message);
+        self.fit_session(&model, session, config)?;
         
         // Build prompt
         let prompt = self.build_chat_prompt(&model, session, config)?;
@@ -419,6 +1006,53 @@ impl LlmEngine {
         self.generate_stream(&prompt, &temp_config)
     }
 
+    /// Trim or summarize the session history so the rendered prompt plus
+    /// `config.max_tokens` fits the model's context window
+    fn fit_session(
+        &self,
+        model: &LoadedModel,
+        session: &mut ChatSession,
+        config: &GenerationConfig,
+    ) -> Result<()> {
+        let removed = session.fit_context(
+            Self::context_size(model),
+            config.max_tokens as usize,
+            |session| {
+                let prompt = self.build_chat_prompt(model, session, config)?;
+                Ok(Self::tokenize_with(model, &prompt)?.len())
+            },
+            |messages| self.summarize_messages(messages),
+        )?;
+        if removed > 0 {
+            debug!(
+                "Session {}: {} messages trimmed ({:?})",
+                session.id, removed, session.context_policy
+            );
+        }
+        Ok(())
+    }
+    
+    /// Condense chat messages into a short summary with the active model
+    fn summarize_messages(&self, messages: &[ChatMessage]) -> Result<String> {
+        let transcript = messages.iter()
+            .map(|m| format!("{}: {}", m.role.as_str(), m.content))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let prompt = format!(
+            "Summarize the conversation below in a few sentences. Keep facts, decisions and open questions.\n\n{}\n\nSummary:",
+            transcript
+        );
+        let config = GenerationConfig::greedy().with_max_tokens(SUMMARY_MAX_TOKENS);
+        self.generate(&prompt, &config)
+    }
+    
+    /// Context window a model's contexts are created with
+    fn context_size(model: &LoadedModel) -> usize {
+        model.config.context_size
+            .map(|n| n.get() as usize)
+            .unwrap_or(model.info.context_length as usize)
+    }
+
     /// Build chat prompt from session
     fn build_chat_prompt(
         &self,
@@ -426,9 +1060,34 @@ impl LlmEngine {
         session: &ChatSession,
         config: &GenerationConfig,
     ) -> Result<String> {
+        let mut messages = session.get_messages_with_system();
+        if session.system_prompt.is_none() {
+            if let Some(system) = &config.system_prompt {
+                messages.insert(0, ChatMessage::system(system.clone()));
+            }
+        }
+        
+        Ok(Self::render_chat(model, &messages, session.template))
+    }
+    
+    /// Render chat messages for `model`
+    ///
+    /// An explicit template wins, then a format recognized from metadata, then
+    /// the model's embedded chat template, then plain `Role: content` lines.
+    fn render_chat(
+        model: &LoadedModel,
+        messages: &[ChatMessage],
+        template: Option<PromptTemplate>,
+    ) -> String {
+        let explicit = template.is_some();
+        let template = template.unwrap_or(model.info.prompt_template);
+        if explicit || template != PromptTemplate::Raw {
+            return template.render(messages);
+        }
+        
         // Try to use model's chat template
         if let Ok(template) = model.model.chat_template(None) {
-            let messages: Vec<LlamaChatMessage> = session.get_messages_with_system()
+            let chat_messages: Vec<LlamaChatMessage> = messages
                 .iter()
                 .filter_map(|m| {
                     LlamaChatMessage::new(
@@ -438,82 +1097,74 @@ impl LlmEngine {
                 })
                 .collect();
             
-            if !messages.is_empty() {
-                if let Ok(prompt) = model.model.apply_chat_template(&template, &messages, true) {
-                    return Ok(prompt);
+            if !chat_messages.is_empty() {
+                if let Ok(prompt) = model.model.apply_chat_template(&template, &chat_messages, true) {
+                    return prompt;
                 }
             }
         }
         
         // Fallback: simple concatenation
-        let mut prompt = String::new();
-        
-        if let Some(system) = session.system_prompt.as_ref().or(config.system_prompt.as_ref()) {
-            prompt.push_str(&format!("System: {}\n\n", system));
-        }
-        
-        for msg in session.get_messages() {
-            let role = match msg.role {
-                MessageRole::System => "System",
-                MessageRole::User => "User",
-                MessageRole::Assistant => "Assistant",
-            };
-            prompt.push_str(&format!("{}: {}\n", role, msg.content));
-        }
-        
-        prompt.push_str("Assistant:");
-        
-        Ok(prompt)
+        PromptTemplate::Raw.render(messages)
     }
 
     /// Build context parameters
-    fn build_context_params(&self, model_config: &ModelConfig) -> LlamaContextParams {
+    fn build_context_params(model_config: &ModelConfig, engine_config: &LlmConfig) -> LlamaContextParams {
         LlamaContextParams::default()
             .with_n_ctx(model_config.context_size)
             .with_n_batch(model_config.batch_size)
             .with_n_ubatch(model_config.ubatch_size)
-            .with_n_threads(self.config.n_threads)
-            .with_n_threads_batch(self.config.n_threads_batch)
+            .with_n_threads(engine_config.n_threads)
+            .with_n_threads_batch(engine_config.n_threads_batch)
+    }
+
+    /// Build the sampler for a model, masking with the grammar first if set
+    fn build_model_sampler(model: &LoadedModel, config: &GenerationConfig) -> Result<LlamaSampler> {
+        let sampler = Self::build_sampler(config, model.model.n_vocab());
+        match &config.grammar {
+            Some(grammar) => {
+                let grammar = grammar_sampler(&model.model, grammar)?;
+                Ok(LlamaSampler::chain_simple([grammar, sampler]))
+            }
+            None => Ok(sampler),
+        }
     }
 
     /// Build sampler from config
-    fn build_sampler(config: &GenerationConfig) -> LlamaSampler {
+    ///
+    /// Chain order: penalties, then the strategy's truncation, temperature and
+    /// selection samplers (see [`crate::sampling`]). `n_vocab` is only used by
+    /// Mirostat v1.
+    fn build_sampler(config: &GenerationConfig, n_vocab: i32) -> LlamaSampler {
         let seed = config.seed.unwrap_or_else(|| {
             use std::time::{SystemTime, UNIX_EPOCH};
             let duration = SystemTime::now().duration_since(UNIX_EPOCH).unwrap();
             (duration.as_nanos() % u32::MAX as u128) as u32
         });
         
-        if config.temperature <= 0.0 {
-            // Greedy sampling
-            LlamaSampler::greedy()
-        } else {
-            // Build sampler chain
-            let mut samplers = Vec::new();
-            
-            if config.top_k > 0 {
-                samplers.push(LlamaSampler::top_k(config.top_k));
-            }
-            
-            if config.top_p < 1.0 {
-                samplers.push(LlamaSampler::top_p(config.top_p, 1));
-            }
-            
-            if config.min_p > 0.0 {
-                samplers.push(LlamaSampler::min_p(config.min_p, 1));
-            }
-            
-            samplers.push(LlamaSampler::temp(config.temperature));
-            samplers.push(LlamaSampler::dist(seed));
-            
-            LlamaSampler::chain_simple(samplers)
+        let strategy = config
+            .strategy
+            .clone()
+            .unwrap_or_else(|| SamplingStrategy::from_generation(config));
+        
+        let mut samplers = Vec::new();
+        if let Some(penalties) = config.penalties().sampler() {
+            samplers.push(penalties);
         }
+        samplers.extend(strategy.samplers(seed, n_vocab));
+        
+        LlamaSampler::chain_simple(samplers)
     }
 
     /// Get backend capabilities
     pub fn supports_gpu(&self) -> bool {
         self.backend.supports_gpu_offload()
     }
+    
+    /// Detected GPU backend and per-device free VRAM
+    pub fn gpu_info(&self) -> GpuInfo {
+        GpuInfo::detect(&self.backend)
+    }
 
     /// Get config
     pub fn config(&self) -> &LlmConfig {
@@ -521,22 +1172,477 @@ impl LlmEngine {
     }
 }
 
+/// L2-normalize a vector (zero vectors are returned unchanged)
+fn normalize(vector: &[f32]) -> Vec<f32> {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        vector.iter().map(|x| x / norm).collect()
+    } else {
+        vector.to_vec()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     
+    /// Engine on the backend shared by all tests of the crate
+    fn test_engine() -> LlmEngine {
+        LlmEngine::with_backend(crate::model::test_backend(), LlmConfig::default())
+    }
+    
+    /// Path of the GGUF model the ignored tests run against
+    fn test_model_path() -> String {
+        std::env::var("WHYTCARD_TEST_MODEL").expect("WHYTCARD_TEST_MODEL not set")
+    }
+    
+    /// Engine with the test model loaded as the active model
+    fn loaded_engine() -> LlmEngine {
+        let mut engine = test_engine();
+        engine.load_model(test_model_path()).unwrap();
+        engine
+    }
+    
     #[test]
     fn test_generation_config_sampler() {
         let config = GenerationConfig::default();
-        let sampler = LlmEngine::build_sampler(&config);
+        let sampler = LlmEngine::build_sampler(&config, 32_000);
         // Just verify it doesn't panic
         drop(sampler);
+        
+        let config = config.with_strategy(SamplingStrategy::mirostat_v2(5.0, 0.1));
+        drop(LlmEngine::build_sampler(&config, 32_000));
     }
     
     #[test]
     fn test_greedy_sampler() {
         let config = GenerationConfig::greedy();
-        let sampler = LlmEngine::build_sampler(&config);
+        let sampler = LlmEngine::build_sampler(&config, 32_000);
         drop(sampler);
     }
+    
+    #[test]
+    fn test_tokenize_requires_model() {
+        let engine = test_engine();
+        
+        assert!(matches!(engine.tokenize("hello"), Err(LlmError::NoModelLoaded)));
+        assert!(matches!(engine.detokenize(&[1, 2]), Err(LlmError::NoModelLoaded)));
+        assert!(matches!(engine.count_tokens("hello"), Err(LlmError::NoModelLoaded)));
+    }
+    
+    #[test]
+    fn test_gpu_info_matches_backend() {
+        let engine = test_engine();
+        let info = engine.gpu_info();
+        
+        assert_eq!(info.supports_offload, engine.supports_gpu());
+        if !info.supports_offload {
+            assert_eq!(info.backend, crate::gpu::GpuBackend::None);
+            assert!(info.free_vram().is_none());
+        }
+    }
+    
+    #[test]
+    fn test_normalize() {
+        let v = normalize(&[3.0, 4.0]);
+        assert!((v[0] - 0.6).abs() < 1e-6);
+        assert!((v[1] - 0.8).abs() < 1e-6);
+        
+        assert_eq!(normalize(&[0.0, 0.0]), vec![0.0, 0.0]);
+    }
+    
+    #[test]
+    #[ignore = "requires a GGUF model at WHYTCARD_TEST_MODEL"]
+    fn test_multiple_named_models() {
+        let path = test_model_path();
+        let mut engine = test_engine();
+        
+        engine.load_model_as("draft", &path, ModelConfig::default()).unwrap();
+        engine.load_model_as("chat", &path, ModelConfig::default()).unwrap();
+        assert_eq!(engine.list_loaded_models(), vec!["draft".to_string(), "chat".to_string()]);
+        
+        let config = GenerationConfig::greedy().with_max_tokens(4);
+        assert!(engine.generate_with_model(Some("draft"), "Hello", &config).is_ok());
+        assert!(matches!(
+            engine.generate_with_model(Some("missing"), "Hello", &config),
+            Err(LlmError::ModelNotFound(_))
+        ));
+        
+        // Unloading the most recent model falls back to the previous one
+        assert!(engine.unload_model());
+        assert!(engine.model("chat").is_none());
+        assert!(engine.active_model().is_some());
+        assert!(engine.generate("Hello", &config).is_ok());
+    }
+    
+    #[test]
+    #[ignore = "requires a GGUF model at WHYTCARD_TEST_MODEL"]
+    fn test_grammar_sampler() {
+        use crate::json_schema::schema_to_grammar;
+        use serde_json::json;
+        
+        let engine = loaded_engine();
+        let model = engine.active_model().unwrap();
+        
+        let schema = json!({
+            "type": "object",
+            "properties": { "name": { "type": "string" }, "tags": { "type": "array", "items": { "enum": ["a", "b"] } } },
+            "required": ["name"]
+        });
+        let valid = [
+            r#"root ::= [0-9]+"#.to_string(),
+            "root   ::= answer (\".\" | \"!\")?\nanswer ::=\n    \"yes\" | \"no\" |\n    \"maybe\"\n".to_string(),
+            r#"root ::= ("a" [b-c]){2,3} "\"" ."#.to_string(),
+            schema_to_grammar(&schema).unwrap(),
+            schema_to_grammar(&tool_choice_schema(&[ToolSpec::new("get_time", serde_json::Value::Null)]).unwrap()).unwrap(),
+        ];
+        for grammar in &valid {
+            assert!(grammar_sampler(&model.model, grammar).is_ok(), "rejected: {}", grammar);
+        }
+        
+        for grammar in [r#"answer ::= "yes""#, "root ::= digit+", "root ::= \"open", "root ::= (\"a\""] {
+            assert!(matches!(grammar_sampler(&model.model, grammar), Err(LlmError::InvalidGrammar(_))));
+        }
+        
+        // A bad grammar is rejected before streaming starts
+        let config = GenerationConfig::greedy().with_grammar("root ::= digit+");
+        assert!(matches!(engine.generate_stream("Hello", &config), Err(LlmError::InvalidGrammar(_))));
+    }
+    
+    #[test]
+    #[ignore = "requires a GGUF model at WHYTCARD_TEST_MODEL"]
+    fn test_embed_batch() {
+        let path = test_model_path();
+        let mut engine = loaded_engine();
+        assert!(matches!(engine.embed("hello"), Err(LlmError::EmbeddingsUnsupported(_))));
+        engine.unload_model();
+        
+        engine.load_model_with_config(ModelConfig::from_path(&path).with_embeddings(true)).unwrap();
+        let vectors = engine.embed_batch(&["the cat sat", "a cat was sitting", "quarterly tax report"]).unwrap();
+        assert_eq!(vectors.len(), 3);
+        
+        let norm: f32 = vectors[0].iter().map(|x| x * x).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 1e-3);
+        
+        let dot = |a: &[f32], b: &[f32]| a.iter().zip(b).map(|(x, y)| x * y).sum::<f32>();
+        assert!(dot(&vectors[0], &vectors[1]) > dot(&vectors[0], &vectors[2]));
+    }
+    
+    #[test]
+    #[ignore = "requires a GGUF model at WHYTCARD_TEST_MODEL"]
+    fn test_tokenize_roundtrip() {
+        let engine = loaded_engine();
+        
+        let text = "Héllo, wörld! 🦀";
+        let tokens = engine.tokenize(text).unwrap();
+        assert_eq!(engine.count_tokens(text).unwrap(), tokens.len());
+        assert!(engine.detokenize(&tokens).unwrap().contains(text));
+        
+        let config = GenerationConfig::default().with_system_prompt("Be brief.");
+        assert!(engine.count_prompt_tokens(text, &config).unwrap() > tokens.len());
+    }
+    
+    #[test]
+    #[ignore = "requires a GGUF model at WHYTCARD_TEST_MODEL"]
+    fn test_chat_context_overflow_policies() {
+        use crate::session::ContextPolicy;
+        
+        let path = test_model_path();
+        let mut engine = test_engine();
+        engine.load_model_with_config(ModelConfig::from_path(path).with_context_size(256)).unwrap();
+        let config = GenerationConfig::greedy().with_max_tokens(16);
+        
+        let filler = "Tell me about the history of the Rust programming language. ".repeat(4);
+        let mut session = ChatSession::new().with_system_prompt("Be brief.");
+        for _ in 0..6 {
+            engine.chat(&mut session, &filler, &config).unwrap();
+        }
+        assert_eq!(session.system_prompt.as_deref(), Some("Be brief."));
+        assert!(session.message_count() < 12);
+        
+        let mut strict = ChatSession::new().with_context_policy(ContextPolicy::Error);
+        let mut overflowed = false;
+        for _ in 0..6 {
+            match engine.chat(&mut strict, &filler, &config) {
+                Ok(_) => {}
+                Err(LlmError::ContextOverflow { .. }) => {
+                    overflowed = true;
+                    break;
+                }
+                Err(e) => panic!("unexpected error: {}", e),
+            }
+        }
+        assert!(overflowed);
+    }
+    
+    #[test]
+    fn test_save_state_requires_prefill() {
+        let engine = test_engine();
+        let dir = tempfile::tempdir().unwrap();
+        
+        let err = engine.save_state(&dir.path().join("prefix.kv")).unwrap_err();
+        assert!(matches!(err, LlmError::NoModelLoaded));
+        assert!(matches!(
+            engine.load_state(&dir.path().join("prefix.kv")),
+            Err(LlmError::NoModelLoaded)
+        ));
+    }
+    
+    #[test]
+    #[ignore = "requires a GGUF model at WHYTCARD_TEST_MODEL"]
+    fn test_state_roundtrip_and_mismatch() {
+        let path = test_model_path();
+        let dir = tempfile::tempdir().unwrap();
+        let state_path = dir.path().join("prefix.kv");
+        
+        let mut engine = test_engine();
+        engine.load_model(&path).unwrap();
+        
+        let system = "You are a terse assistant.";
+        let n = engine.prefill(&format!("{}\n\n", system)).unwrap();
+        engine.save_state(&state_path).unwrap();
+        engine.clear_state();
+        assert_eq!(engine.load_state(&state_path).unwrap(), n);
+        
+        let config = GenerationConfig::default()
+            .with_max_tokens(8)
+            .with_system_prompt(system);
+        assert!(engine.generate("Say hi.", &config).is_ok());
+        
+        // A state stamped with another model's hash is rejected
+        let mut state = KvState::load(&state_path).unwrap();
+        state.model_hash ^= 1;
+        state.save(&state_path).unwrap();
+        assert!(matches!(
+            engine.load_state(&state_path),
+            Err(LlmError::StateModelMismatch(_))
+        ));
+    }
+    
+    #[test]
+    fn test_lora_requires_model() {
+        let engine = test_engine();
+        assert!(matches!(
+            engine.apply_lora(Path::new("adapter.gguf"), 1.0),
+            Err(LlmError::NoModelLoaded)
+        ));
+        assert!(matches!(engine.clear_loras(), Err(LlmError::NoModelLoaded)));
+    }
+    
+    #[test]
+    #[ignore = "requires a GGUF model at WHYTCARD_TEST_MODEL and an adapter at WHYTCARD_TEST_LORA"]
+    fn test_lora_stack_and_mismatch() {
+        let path = test_model_path();
+        let adapter = std::env::var("WHYTCARD_TEST_LORA").expect("WHYTCARD_TEST_LORA not set");
+        let dir = tempfile::tempdir().unwrap();
+        
+        let mut engine = test_engine();
+        engine.load_model(&path).unwrap();
+        engine.prefill("You are a terse assistant.\n\n").unwrap();
+        
+        engine.apply_lora(Path::new(&adapter), 1.0).unwrap();
+        engine.apply_lora(Path::new(&adapter), 0.5).unwrap();
+        let model = engine.active_model().unwrap();
+        let scales: Vec<f32> = model.loras().iter().map(|l| l.scale).collect();
+        assert_eq!(scales, vec![1.0, 0.5]);
+        // Applying an adapter drops the prefilled state
+        assert!(matches!(
+            engine.save_state(&dir.path().join("prefix.kv")),
+            Err(LlmError::InvalidState(_))
+        ));
+        
+        let config = GenerationConfig::default().with_max_tokens(8);
+        assert!(engine.generate("Say hi.", &config).is_ok());
+        
+        // An adapter declaring another architecture is rejected before loading
+        let mut header = Vec::new();
+        header.extend_from_slice(b"GGUF");
+        header.extend_from_slice(&3u32.to_le_bytes());
+        header.extend_from_slice(&0u64.to_le_bytes());
+        header.extend_from_slice(&1u64.to_le_bytes());
+        let key = "general.architecture";
+        header.extend_from_slice(&(key.len() as u64).to_le_bytes());
+        header.extend_from_slice(key.as_bytes());
+        header.extend_from_slice(&8u32.to_le_bytes());
+        let arch = "not-a-real-arch";
+        header.extend_from_slice(&(arch.len() as u64).to_le_bytes());
+        header.extend_from_slice(arch.as_bytes());
+        let foreign = dir.path().join("foreign.gguf");
+        std::fs::write(&foreign, header).unwrap();
+        assert!(matches!(
+            engine.apply_lora(&foreign, 1.0),
+            Err(LlmError::LoraMismatch(_))
+        ));
+        
+        assert_eq!(engine.clear_loras().unwrap(), 2);
+        assert!(model.loras().is_empty());
+    }
+    
+    #[test]
+    fn test_generate_with_tools_requires_model() {
+        let engine = test_engine();
+        let tools = [ToolSpec::new("get_time", serde_json::Value::Null)];
+        let messages = [ChatMessage::user("What time is it?")];
+        assert!(matches!(
+            engine.generate_with_tools(&messages, &tools, &GenerationConfig::default()),
+            Err(LlmError::NoModelLoaded)
+        ));
+    }
+    
+    #[test]
+    #[ignore = "requires a GGUF model at WHYTCARD_TEST_MODEL"]
+    fn test_generate_with_tools() {
+        let engine = loaded_engine();
+        
+        let tools = [
+            ToolSpec::new(
+                "get_weather",
+                serde_json::json!({
+                    "type": "object",
+                    "properties": { "city": { "type": "string" } },
+                    "required": ["city"]
+                }),
+            )
+            .with_description("Current weather for a city"),
+        ];
+        let messages = [ChatMessage::user("What is the weather in Paris and in Tokyo?")];
+        let config = GenerationConfig::default()
+            .with_max_tokens(128)
+            .with_temperature(0.0);
+        
+        match engine.generate_with_tools(&messages, &tools, &config).unwrap() {
+            GenerationOutcome::ToolCalls(calls) => {
+                assert!(!calls.is_empty());
+                assert!(calls.iter().all(|c| c.name == "get_weather" && c.arguments["city"].is_string()));
+            }
+            GenerationOutcome::Text(text) => assert!(!text.is_empty()),
+        }
+    }
+    
+    #[test]
+    fn test_generate_json_rejects_bad_schema() {
+        let engine = test_engine();
+        let schema = serde_json::json!({ "$ref": "#/definitions/person" });
+        
+        let err = engine.generate_json("hi", &schema, &GenerationConfig::default()).unwrap_err();
+        assert!(matches!(err, LlmError::InvalidSchema(_)));
+    }
+    
+    #[test]
+    #[ignore = "requires a GGUF model at WHYTCARD_TEST_MODEL"]
+    fn test_generate_json_nested() {
+        let engine = loaded_engine();
+        
+        let schema = serde_json::json!({
+            "type": "object",
+            "properties": {
+                "title": { "type": "string" },
+                "author": {
+                    "type": "object",
+                    "properties": { "name": { "type": "string" } },
+                    "required": ["name"]
+                },
+                "chapters": {
+                    "type": "array",
+                    "items": { "type": "integer" },
+                    "minItems": 1,
+                    "maxItems": 3
+                }
+            },
+            "required": ["title", "author", "chapters"]
+        });
+        
+        let config = GenerationConfig::default().with_max_tokens(128);
+        let value = engine.generate_json("Describe a short book as JSON.", &schema, &config).unwrap();
+        assert!(value["author"]["name"].is_string());
+        assert!(value["chapters"].as_array().is_some_and(|c| !c.is_empty()));
+        
+        // Too few tokens to close the object
+        let config = GenerationConfig::default().with_max_tokens(2);
+        assert!(matches!(
+            engine.generate_json("Describe a short book as JSON.", &schema, &config),
+            Err(LlmError::IncompleteJson(_))
+        ));
+    }
+    
+    #[test]
+    #[ignore = "requires a GGUF model at WHYTCARD_TEST_MODEL"]
+    fn test_grammar_forces_digits() {
+        let engine = loaded_engine();
+        
+        let config = GenerationConfig::default()
+            .with_max_tokens(16)
+            .with_grammar("root ::= [0-9]+");
+        let output = engine.generate("Write a short poem about the sea.", &config).unwrap();
+        
+        assert!(!output.is_empty());
+        assert!(output.chars().all(|c| c.is_ascii_digit()), "got {:?}", output);
+    }
+    
+    #[test]
+    #[ignore = "requires a GGUF model at WHYTCARD_TEST_MODEL"]
+    fn test_repeat_penalty_reduces_loops() {
+        use crate::sampling::PenaltyConfig;
+        
+        let engine = loaded_engine();
+        
+        let prompt = "again again again again again again again again";
+        let count = |penalties: PenaltyConfig| {
+            let config = GenerationConfig::greedy()
+                .with_max_tokens(48)
+                .with_penalties(penalties);
+            engine.generate(prompt, &config).unwrap().matches("again").count()
+        };
+        
+        let unpenalized = count(PenaltyConfig::none());
+        let penalized = count(PenaltyConfig {
+            repeat_penalty: 2.0,
+            repeat_last_n: 64,
+            ..PenaltyConfig::none()
+        });
+        assert!(penalized < unpenalized, "{} repeats with penalty vs {} without", penalized, unpenalized);
+    }
+    
+    #[tokio::test]
+    #[ignore = "requires a GGUF model at WHYTCARD_TEST_MODEL"]
+    async fn test_cancel_then_generate_again() {
+        use crate::streaming::StreamEvent;
+        
+        let engine = loaded_engine();
+        
+        let config = GenerationConfig::greedy().with_max_tokens(256);
+        let cancel = CancellationToken::new();
+        let mut stream = engine
+            .generate_stream_cancellable("Count from one to one hundred:", &config, cancel.clone())
+            .unwrap();
+        
+        let mut tokens = 0;
+        let (text, generated) = loop {
+            match stream.next().await.expect("stream ended without a final event") {
+                StreamEvent::Token { .. } => {
+                    tokens += 1;
+                    if tokens == 5 {
+                        cancel.cancel();
+                    }
+                }
+                StreamEvent::Cancelled { text, tokens_generated, .. } => break (text, tokens_generated),
+                StreamEvent::Done { .. } => panic!("generation finished instead of cancelling"),
+                StreamEvent::Error { message } => panic!("generation failed: {}", message),
+                _ => {}
+            }
+        };
+        assert!(generated >= 5 && generated < 256, "generated {}", generated);
+        assert_eq!(text, stream.current_text());
+        
+        // A pre-cancelled token stops the blocking path before the first token
+        let cancelled = CancellationToken::new();
+        cancelled.cancel();
+        let completion = engine.generate_cancellable("Hello", &config, &cancelled).unwrap();
+        assert!(completion.is_cancelled());
+        assert_eq!(completion.text, "");
+        
+        // The engine is still usable after cancellation
+        let output = engine.generate("Hello", &config.clone().with_max_tokens(8)).unwrap();
+        assert!(!output.is_empty());
+    }
 }
@@ -48,6 +48,38 @@ pub enum LlmError {
     #[error("Sampling error: {0}")]
     SamplingError(String),
 
+    /// Model was not loaded with embeddings enabled
+    #[error("Embeddings not enabled for model: {0}")]
+    EmbeddingsUnsupported(String),
+
+    /// GBNF grammar failed to parse
+    #[error("Invalid grammar: {0}")]
+    InvalidGrammar(String),
+
+    /// JSON Schema uses a construct the grammar compiler cannot express
+    #[error("Unsupported JSON schema: {0}")]
+    InvalidSchema(String),
+
+    /// Generated JSON does not conform to the requested schema
+    #[error("JSON does not match schema: {0}")]
+    SchemaValidation(String),
+
+    /// Generation stopped (e.g. at max_tokens) before the JSON was closed
+    #[error("Incomplete JSON output: {0}")]
+    IncompleteJson(String),
+
+    /// Saved state was produced by a different model file
+    #[error("Saved state does not match the loaded model: {0}")]
+    StateModelMismatch(String),
+
+    /// LoRA adapter was trained for a different model architecture
+    #[error("LoRA adapter does not match the model: {0}")]
+    LoraMismatch(String),
+
+    /// Saved state file is corrupt or in an unknown format
+    #[error("Invalid saved state: {0}")]
+    InvalidState(String),
+
     /// IO error
     #[error("IO error: {0}")]
     IoError(#[from] std::io::Error),
@@ -67,4 +99,15 @@ pub enum LlmError {
     /// Model already loaded
     #[error("Model already loaded: {0}")]
     ModelAlreadyLoaded(String),
+
+    /// Prompt plus requested output does not fit the context window
+    #[error("Context overflow: {prompt_tokens} prompt tokens + {max_tokens} max tokens exceed the context size {context_size}")]
+    ContextOverflow {
+        /// Tokens in the rendered prompt
+        prompt_tokens: usize,
+        /// Tokens requested for generation
+        max_tokens: usize,
+        /// Context window of the model
+        context_size: usize,
+    },
 }
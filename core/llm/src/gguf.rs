@@ -0,0 +1,275 @@
+//! GGUF headers - model and adapter metadata read without loading tensors
+
+use crate::error::{LlmError, Result};
+use crate::model::quantization_name;
+use crate::state::{read_u32, read_u64};
+
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, Read};
+use std::path::Path;
+
+/// GGUF file magic
+const GGUF_MAGIC: &[u8; 4] = b"GGUF";
+
+/// GGUF metadata value type for 32-bit unsigned integers
+const GGUF_TYPE_UINT32: u32 = 4;
+
+/// GGUF metadata value type for strings
+const GGUF_TYPE_STRING: u32 = 8;
+
+/// GGUF metadata value type for arrays
+const GGUF_TYPE_ARRAY: u32 = 9;
+
+/// GGUF metadata value type for 64-bit unsigned integers
+const GGUF_TYPE_UINT64: u32 = 10;
+
+/// Upper bound on a GGUF string read from a header
+const MAX_GGUF_STRING: u64 = 64 * 1024 * 1024;
+
+/// Metadata declared in a GGUF header
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct GgufMetadata {
+    /// Architecture (`general.architecture`)
+    pub architecture: Option<String>,
+
+    /// Training context length (`<architecture>.context_length`)
+    pub context_length: Option<u32>,
+
+    /// llama.cpp file type (`general.file_type`)
+    pub file_type: Option<u32>,
+
+    /// Declared parameter count (`general.parameter_count`)
+    pub parameter_count: Option<u64>,
+}
+
+impl GgufMetadata {
+    /// Quantization name of the file type ("unknown" when absent)
+    pub fn quantization(&self) -> &'static str {
+        self.file_type.map(quantization_name).unwrap_or("unknown")
+    }
+}
+
+/// Read the metadata of a GGUF file without loading its tensors
+pub fn read_metadata(path: &Path) -> Result<GgufMetadata> {
+    let mut reader = BufReader::new(File::open(path)?);
+    read_header(&mut reader)
+        .map_err(|e| match e {
+            LlmError::IoError(e) => LlmError::ModelLoadError(format!(
+                "invalid GGUF header in {}: {}",
+                path.display(),
+                e
+            )),
+            e => e,
+        })
+}
+
+fn read_header(reader: &mut impl Read) -> Result<GgufMetadata> {
+    let mut magic = [0u8; 4];
+    reader.read_exact(&mut magic)?;
+    if &magic != GGUF_MAGIC {
+        return Err(LlmError::ModelLoadError("not a GGUF file".into()));
+    }
+
+    let version = read_u32(reader)?;
+    if version < 2 {
+        return Err(LlmError::ModelLoadError(format!("unsupported GGUF version {}", version)));
+    }
+
+    let _tensor_count = read_u64(reader)?;
+    let kv_count = read_u64(reader)?;
+
+    let mut metadata = GgufMetadata::default();
+    // Keyed by architecture, which may be declared after them
+    let mut context_lengths = HashMap::new();
+
+    for _ in 0..kv_count {
+        let key = read_string(reader)?;
+        let value_type = read_u32(reader)?;
+        match key.as_str() {
+            "general.architecture" if value_type == GGUF_TYPE_STRING => {
+                metadata.architecture = Some(read_string(reader)?);
+            }
+            "general.file_type" => {
+                metadata.file_type = read_uint(reader, value_type)?.and_then(|v| u32::try_from(v).ok());
+            }
+            "general.parameter_count" => {
+                metadata.parameter_count = read_uint(reader, value_type)?;
+            }
+            _ => match key.strip_suffix(".context_length") {
+                Some(architecture) => {
+                    if let Some(length) = read_uint(reader, value_type)?.and_then(|v| u32::try_from(v).ok()) {
+                        context_lengths.insert(architecture.to_string(), length);
+                    }
+                }
+                None => skip_value(reader, value_type)?,
+            },
+        }
+    }
+
+    metadata.context_length = metadata
+        .architecture
+        .as_ref()
+        .and_then(|architecture| context_lengths.get(architecture).copied());
+    Ok(metadata)
+}
+
+fn read_string(reader: &mut impl Read) -> Result<String> {
+    let len = read_u64(reader)?;
+    if len > MAX_GGUF_STRING {
+        return Err(LlmError::ModelLoadError(format!("GGUF string too long: {} bytes", len)));
+    }
+    let mut bytes = vec![0u8; len as usize];
+    reader.read_exact(&mut bytes)?;
+    String::from_utf8(bytes)
+        .map_err(|e| LlmError::ModelLoadError(format!("GGUF string is not UTF-8: {}", e)))
+}
+
+/// Read an unsigned integer value, skipping a value of any other type
+fn read_uint(reader: &mut impl Read, value_type: u32) -> Result<Option<u64>> {
+    match value_type {
+        GGUF_TYPE_UINT32 => Ok(Some(read_u32(reader)? as u64)),
+        GGUF_TYPE_UINT64 => Ok(Some(read_u64(reader)?)),
+        other => {
+            skip_value(reader, other)?;
+            Ok(None)
+        }
+    }
+}
+
+fn skip_value(reader: &mut impl Read, value_type: u32) -> Result<()> {
+    let size = match value_type {
+        0 | 1 | 7 => 1,
+        2 | 3 => 2,
+        4..=6 => 4,
+        10..=12 => 8,
+        GGUF_TYPE_STRING => read_u64(reader)?,
+        GGUF_TYPE_ARRAY => {
+            let element_type = read_u32(reader)?;
+            let count = read_u64(reader)?;
+            for _ in 0..count {
+                skip_value(reader, element_type)?;
+            }
+            return Ok(());
+        }
+        other => {
+            return Err(LlmError::ModelLoadError(format!("unknown GGUF value type {}", other)));
+        }
+    };
+    std::io::copy(&mut reader.by_ref().take(size), &mut std::io::sink())?;
+    Ok(())
+}
+
+/// Hand-built GGUF headers for tests
+#[cfg(test)]
+pub(crate) mod fixture {
+    use super::*;
+
+    /// GGUF metadata value type for 32-bit floats
+    const GGUF_TYPE_FLOAT32: u32 = 6;
+
+    /// A GGUF header with no tensors, filled with `push_*` entries
+    pub(crate) struct Header {
+        entries: Vec<u8>,
+        count: u64,
+    }
+
+    impl Header {
+        pub(crate) fn new() -> Self {
+            Self { entries: Vec::new(), count: 0 }
+        }
+
+        fn key(&mut self, key: &str, value_type: u32) {
+            write_string(&mut self.entries, key);
+            self.entries.extend_from_slice(&value_type.to_le_bytes());
+            self.count += 1;
+        }
+
+        pub(crate) fn push_string(mut self, key: &str, value: &str) -> Self {
+            self.key(key, GGUF_TYPE_STRING);
+            write_string(&mut self.entries, value);
+            self
+        }
+
+        pub(crate) fn push_u32(mut self, key: &str, value: u32) -> Self {
+            self.key(key, GGUF_TYPE_UINT32);
+            self.entries.extend_from_slice(&value.to_le_bytes());
+            self
+        }
+
+        pub(crate) fn push_u64(mut self, key: &str, value: u64) -> Self {
+            self.key(key, GGUF_TYPE_UINT64);
+            self.entries.extend_from_slice(&value.to_le_bytes());
+            self
+        }
+
+        pub(crate) fn push_f32(mut self, key: &str, value: f32) -> Self {
+            self.key(key, GGUF_TYPE_FLOAT32);
+            self.entries.extend_from_slice(&value.to_le_bytes());
+            self
+        }
+
+        pub(crate) fn push_strings(mut self, key: &str, values: &[&str]) -> Self {
+            self.key(key, GGUF_TYPE_ARRAY);
+            self.entries.extend_from_slice(&GGUF_TYPE_STRING.to_le_bytes());
+            self.entries.extend_from_slice(&(values.len() as u64).to_le_bytes());
+            for value in values {
+                write_string(&mut self.entries, value);
+            }
+            self
+        }
+
+        pub(crate) fn build(self) -> Vec<u8> {
+            let mut out = Vec::new();
+            out.extend_from_slice(GGUF_MAGIC);
+            out.extend_from_slice(&3u32.to_le_bytes());
+            out.extend_from_slice(&0u64.to_le_bytes());
+            out.extend_from_slice(&self.count.to_le_bytes());
+            out.extend_from_slice(&self.entries);
+            out
+        }
+    }
+
+    fn write_string(out: &mut Vec<u8>, s: &str) {
+        out.extend_from_slice(&(s.len() as u64).to_le_bytes());
+        out.extend_from_slice(s.as_bytes());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::fixture::Header;
+    use super::*;
+
+    #[test]
+    fn test_gguf_metadata() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("tiny.gguf");
+
+        // Context length declared before the architecture it belongs to
+        let header = Header::new()
+            .push_u32("qwen2.context_length", 32768)
+            .push_u32("llama.context_length", 4096)
+            .push_string("general.architecture", "llama")
+            .push_strings("tokenizer.ggml.tokens", &["<s>", "</s>", "hello"])
+            .push_f32("llama.rope.freq_base", 10000.0)
+            .push_u32("general.file_type", 15)
+            .push_u64("general.parameter_count", 1_100_000_000)
+            .build();
+        std::fs::write(&path, header).unwrap();
+
+        let metadata = read_metadata(&path).unwrap();
+        assert_eq!(metadata.architecture.as_deref(), Some("llama"));
+        assert_eq!(metadata.context_length, Some(4096));
+        assert_eq!(metadata.quantization(), "Q4_K_M");
+        assert_eq!(metadata.parameter_count, Some(1_100_000_000));
+
+        std::fs::write(&path, Header::new().build()).unwrap();
+        let metadata = read_metadata(&path).unwrap();
+        assert_eq!(metadata, GgufMetadata::default());
+        assert_eq!(metadata.quantization(), "unknown");
+
+        std::fs::write(&path, b"WCKV not a gguf").unwrap();
+        assert!(matches!(read_metadata(&path), Err(LlmError::ModelLoadError(_))));
+    }
+}
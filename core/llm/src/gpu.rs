@@ -0,0 +1,114 @@
+//! GPU backend detection
+
+use llama_cpp_2::llama_backend::LlamaBackend;
+use serde::{Deserialize, Serialize};
+
+/// GPU backend compiled into llama.cpp
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum GpuBackend {
+    /// NVIDIA CUDA (`cuda` feature)
+    Cuda,
+    /// Apple Metal (`metal` feature)
+    Metal,
+    /// CPU only
+    None,
+}
+
+/// A device llama.cpp can offload to
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GpuDevice {
+    /// Device index (the value for `ModelConfig::main_gpu`)
+    pub index: usize,
+    
+    /// Device name
+    pub name: String,
+    
+    /// Free memory in bytes
+    pub free_vram: u64,
+    
+    /// Total memory in bytes
+    pub total_vram: u64,
+}
+
+/// Detected GPU support
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GpuInfo {
+    /// Compiled-in GPU backend
+    pub backend: GpuBackend,
+    
+    /// Whether llama.cpp can offload layers at all
+    pub supports_offload: bool,
+    
+    /// GPU devices with their memory, empty when none are visible
+    pub devices: Vec<GpuDevice>,
+}
+
+impl GpuInfo {
+    /// Detect the backend and query GPU devices
+    pub fn detect(backend: &LlamaBackend) -> Self {
+        let supports_offload = backend.supports_gpu_offload();
+        
+        let gpu_backend = if !supports_offload {
+            GpuBackend::None
+        } else if cfg!(feature = "cuda") {
+            GpuBackend::Cuda
+        } else if cfg!(feature = "metal") {
+            GpuBackend::Metal
+        } else {
+            GpuBackend::None
+        };
+        
+        let devices = if supports_offload {
+            llama_cpp_2::list_llama_ggml_backend_devices()
+                .into_iter()
+                .filter(|d| d.memory_total > 0 && !d.backend.eq_ignore_ascii_case("cpu"))
+                .map(|d| GpuDevice {
+                    index: d.index,
+                    name: d.name,
+                    free_vram: d.memory_free as u64,
+                    total_vram: d.memory_total as u64,
+                })
+                .collect()
+        } else {
+            Vec::new()
+        };
+        
+        Self {
+            backend: gpu_backend,
+            supports_offload,
+            devices,
+        }
+    }
+    
+    /// Free memory summed over all devices
+    pub fn free_vram(&self) -> Option<u64> {
+        if self.devices.is_empty() {
+            None
+        } else {
+            Some(self.devices.iter().map(|d| d.free_vram).sum())
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    
+    #[test]
+    fn test_free_vram_sums_devices() {
+        let mut info = GpuInfo {
+            backend: GpuBackend::Cuda,
+            supports_offload: true,
+            devices: Vec::new(),
+        };
+        assert_eq!(info.free_vram(), None);
+        
+        info.devices = vec![
+            GpuDevice { index: 0, name: "A".into(), free_vram: 4 << 30, total_vram: 8 << 30 },
+            GpuDevice { index: 1, name: "B".into(), free_vram: 2 << 30, total_vram: 8 << 30 },
+        ];
+        assert_eq!(info.free_vram(), Some(6 << 30));
+        assert_eq!(serde_json::to_value(info.backend).unwrap(), "cuda");
+    }
+}
@@ -0,0 +1,438 @@
+//! JSON Schema support for constrained decoding
+//!
+//! [`schema_to_grammar`] compiles a JSON Schema into a GBNF grammar whose
+//! output is valid JSON of that shape, and [`validate_json`] checks a parsed
+//! value against the same schema.
+//!
+//! Supported keywords: `type` (including type arrays), `properties`,
+//! `required`, `items`, `minItems`, `maxItems`, `enum`, `const`, `anyOf` and
+//! `oneOf`. Objects with `properties` emit them in `serde_json::Map` order and
+//! allow no extra keys. Unsupported keywords that would change the shape (`$ref`,
+//! `allOf`, `patternProperties`) are rejected with [`LlmError::InvalidSchema`].
+
+use crate::error::{LlmError, Result};
+
+use serde_json::Value;
+
+/// Shared rules for JSON primitives
+const PRIMITIVE_RULES: &str = r#"ws ::= [ \t\n]{0,20}
+string ::= "\"" char* "\""
+char ::= [^"\\\x7F\x00-\x1F] | "\\" (["\\/bfnrt] | "u" [0-9a-fA-F]{4})
+integer ::= "-"? ([0-9] | [1-9] [0-9]*)
+number ::= integer ("." [0-9]+)? ([eE] [-+]? [0-9]+)?
+boolean ::= "true" | "false"
+null ::= "null"
+value ::= object | array | string | number | boolean | null
+object ::= "{" ws (string ws ":" ws value ws ("," ws string ws ":" ws value ws)*)? "}"
+array ::= "[" ws (value ws ("," ws value ws)*)? "]"
+"#;
+
+/// Compile a JSON Schema into a GBNF grammar (root rule: `root`)
+pub fn schema_to_grammar(schema: &Value) -> Result<String> {
+    let mut compiler = Compiler::default();
+    let root = compiler.visit(schema, "root")?;
+
+    let mut grammar = format!("root ::= ws {} ws\n", root);
+    for (name, body) in &compiler.rules {
+        grammar.push_str(&format!("{} ::= {}\n", name, body));
+    }
+    grammar.push_str(PRIMITIVE_RULES);
+    Ok(grammar)
+}
+
+/// Check a value against a JSON Schema (same keyword subset as the compiler)
+pub fn validate_json(value: &Value, schema: &Value) -> Result<()> {
+    check(value, schema, "$").map_err(LlmError::SchemaValidation)
+}
+
+#[derive(Default)]
+struct Compiler {
+    rules: Vec<(String, String)>,
+}
+
+impl Compiler {
+    /// Return a GBNF expression for `schema`, defining helper rules as needed
+    fn visit(&mut self, schema: &Value, hint: &str) -> Result<String> {
+        let obj = match schema {
+            Value::Bool(true) => return Ok("value".into()),
+            Value::Object(obj) => obj,
+            _ => return Err(LlmError::InvalidSchema(format!("{}: schema must be an object", hint))),
+        };
+
+        for keyword in ["$ref", "allOf", "patternProperties"] {
+            if obj.contains_key(keyword) {
+                return Err(LlmError::InvalidSchema(format!("{}: '{}' is not supported", hint, keyword)));
+            }
+        }
+
+        if let Some(value) = obj.get("const") {
+            return Ok(literal(value));
+        }
+        if let Some(values) = obj.get("enum") {
+            let values = values.as_array()
+                .filter(|v| !v.is_empty())
+                .ok_or_else(|| LlmError::InvalidSchema(format!("{}: 'enum' must be a non-empty array", hint)))?;
+            let alternatives: Vec<String> = values.iter().map(literal).collect();
+            return Ok(format!("({})", alternatives.join(" | ")));
+        }
+        for keyword in ["anyOf", "oneOf"] {
+            if let Some(options) = obj.get(keyword).and_then(|v| v.as_array()) {
+                let mut alternatives = Vec::new();
+                for (i, option) in options.iter().enumerate() {
+                    alternatives.push(self.visit(option, &format!("{}-{}", hint, i))?);
+                }
+                return Ok(format!("({})", alternatives.join(" | ")));
+            }
+        }
+
+        match obj.get("type") {
+            None if obj.contains_key("properties") => self.visit_type("object", obj, hint),
+            None if obj.contains_key("items") => self.visit_type("array", obj, hint),
+            None => Ok("value".into()),
+            Some(Value::String(ty)) => self.visit_type(ty, obj, hint),
+            Some(Value::Array(types)) => {
+                let mut alternatives = Vec::new();
+                for ty in types {
+                    let ty = ty.as_str()
+                        .ok_or_else(|| LlmError::InvalidSchema(format!("{}: 'type' entries must be strings", hint)))?;
+                    alternatives.push(self.visit_type(ty, obj, hint)?);
+                }
+                Ok(format!("({})", alternatives.join(" | ")))
+            }
+            Some(_) => Err(LlmError::InvalidSchema(format!("{}: 'type' must be a string or array", hint))),
+        }
+    }
+
+    fn visit_type(
+        &mut self,
+        ty: &str,
+        obj: &serde_json::Map<String, Value>,
+        hint: &str,
+    ) -> Result<String> {
+        match ty {
+            "string" | "number" | "integer" | "boolean" | "null" => Ok(ty.to_string()),
+            "object" => self.visit_object(obj, hint),
+            "array" => self.visit_array(obj, hint),
+            other => Err(LlmError::InvalidSchema(format!("{}: unknown type '{}'", hint, other))),
+        }
+    }
+
+    fn visit_object(&mut self, obj: &serde_json::Map<String, Value>, hint: &str) -> Result<String> {
+        let Some(properties) = obj.get("properties").and_then(|p| p.as_object()) else {
+            return Ok("object".into());
+        };
+        let required: Vec<&str> = obj.get("required")
+            .and_then(|r| r.as_array())
+            .map(|r| r.iter().filter_map(|v| v.as_str()).collect())
+            .unwrap_or_default();
+
+        // One `"key" ws ":" ws value` expression per property, in order
+        let mut items = Vec::new();
+        for (key, prop) in properties {
+            let value = self.visit(prop, &format!("{}-{}", hint, rule_name(key)))?;
+            let pair = format!("{} ws \":\" ws {}", literal(&Value::String(key.clone())), value);
+            items.push((pair, required.contains(&key.as_str())));
+        }
+
+        let name = self.define(&format!("{}-obj", hint), String::new());
+        let body = if items.is_empty() {
+            "\"{\" ws \"}\"".to_string()
+        } else {
+            let list = self.comma_list(&name, &items);
+            if items.iter().any(|(_, req)| *req) {
+                format!("\"{{\" ws {} ws \"}}\"", list)
+            } else {
+                format!("\"{{\" ws ({})? ws \"}}\"", list)
+            }
+        };
+        self.set(&name, body);
+        Ok(name)
+    }
+
+    /// Rules for a comma-separated list where optional items may be omitted
+    ///
+    /// `{name}-{i}` matches a non-empty list drawn from `items[i..]`.
+    fn comma_list(&mut self, name: &str, items: &[(String, bool)]) -> String {
+        let tail = |from: usize| -> String {
+            items[from..]
+                .iter()
+                .map(|(pair, req)| {
+                    if *req {
+                        format!(" ws \",\" ws {}", pair)
+                    } else {
+                        format!(" (ws \",\" ws {})?", pair)
+                    }
+                })
+                .collect()
+        };
+
+        let mut next: Option<String> = None;
+        for i in (0..items.len()).rev() {
+            let (pair, req) = &items[i];
+            let starts_here = format!("{}{}", pair, tail(i + 1));
+            let body = match (&next, req) {
+                // An optional item may be skipped if a later item can start the list
+                (Some(later), false) => format!("{} | {}", starts_here, later),
+                _ => starts_here,
+            };
+            next = Some(self.define(&format!("{}-{}", name, i), body));
+        }
+        next.unwrap_or_default()
+    }
+
+    fn visit_array(&mut self, obj: &serde_json::Map<String, Value>, hint: &str) -> Result<String> {
+        let item = match obj.get("items") {
+            Some(items) => self.visit(items, &format!("{}-item", hint))?,
+            None => "value".to_string(),
+        };
+        let min = obj.get("minItems").and_then(|v| v.as_u64()).unwrap_or(0);
+        let max = obj.get("maxItems").and_then(|v| v.as_u64());
+        if max.is_some_and(|max| max < min) {
+            return Err(LlmError::InvalidSchema(format!("{}: maxItems is below minItems", hint)));
+        }
+
+        let more = match max {
+            Some(0) => return Ok(self.define(&format!("{}-arr", hint), "\"[\" ws \"]\"".into())),
+            Some(max) => format!("{{{},{}}}", min.saturating_sub(1), max - 1),
+            None => format!("{{{},}}", min.saturating_sub(1)),
+        };
+        let list = format!("{} (ws \",\" ws {}){}", item, item, more);
+        let body = if min == 0 {
+            format!("\"[\" ws ({})? ws \"]\"", list)
+        } else {
+            format!("\"[\" ws {} ws \"]\"", list)
+        };
+        Ok(self.define(&format!("{}-arr", hint), body))
+    }
+
+    /// Add a rule under a unique name derived from `hint`
+    fn define(&mut self, hint: &str, body: String) -> String {
+        let mut name = hint.to_string();
+        let mut n = 1;
+        while self.rules.iter().any(|(existing, _)| *existing == name) {
+            n += 1;
+            name = format!("{}{}", hint, n);
+        }
+        self.rules.push((name.clone(), body));
+        name
+    }
+
+    fn set(&mut self, name: &str, body: String) {
+        if let Some(rule) = self.rules.iter_mut().find(|(n, _)| n == name) {
+            rule.1 = body;
+        }
+    }
+}
+
+/// GBNF literal matching `value` serialized as compact JSON
+fn literal(value: &Value) -> String {
+    let json = value.to_string();
+    let mut out = String::with_capacity(json.len() + 2);
+    out.push('"');
+    for c in json.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+/// Reduce a property name to GBNF rule-name characters
+fn rule_name(key: &str) -> String {
+    let name: String = key
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '-' })
+        .collect();
+    if name.is_empty() { "prop".into() } else { name }
+}
+
+fn check(value: &Value, schema: &Value, path: &str) -> std::result::Result<(), String> {
+    let obj = match schema {
+        Value::Bool(true) => return Ok(()),
+        Value::Object(obj) => obj,
+        _ => return Err(format!("{}: unsupported schema", path)),
+    };
+
+    if let Some(expected) = obj.get("const") {
+        if value != expected {
+            return Err(format!("{}: expected {}", path, expected));
+        }
+    }
+    if let Some(values) = obj.get("enum").and_then(|v| v.as_array()) {
+        if !values.contains(value) {
+            return Err(format!("{}: {} is not one of the allowed values", path, value));
+        }
+    }
+    for keyword in ["anyOf", "oneOf"] {
+        if let Some(options) = obj.get(keyword).and_then(|v| v.as_array()) {
+            if !options.iter().any(|option| check(value, option, path).is_ok()) {
+                return Err(format!("{}: matches none of '{}'", path, keyword));
+            }
+        }
+    }
+
+    if let Some(ty) = obj.get("type") {
+        let types: Vec<&str> = match ty {
+            Value::String(t) => vec![t.as_str()],
+            Value::Array(ts) => ts.iter().filter_map(|t| t.as_str()).collect(),
+            _ => Vec::new(),
+        };
+        if !types.iter().any(|t| type_matches(value, t)) {
+            return Err(format!("{}: expected {}", path, types.join(" or ")));
+        }
+    }
+
+    if let Value::Object(map) = value {
+        if let Some(required) = obj.get("required").and_then(|r| r.as_array()) {
+            for key in required.iter().filter_map(|k| k.as_str()) {
+                if !map.contains_key(key) {
+                    return Err(format!("{}: missing required property '{}'", path, key));
+                }
+            }
+        }
+        if let Some(properties) = obj.get("properties").and_then(|p| p.as_object()) {
+            for (key, val) in map {
+                match properties.get(key) {
+                    Some(prop) => check(val, prop, &format!("{}.{}", path, key))?,
+                    None => return Err(format!("{}: unexpected property '{}'", path, key)),
+                }
+            }
+        }
+    }
+
+    if let Value::Array(items) = value {
+        if let Some(min) = obj.get("minItems").and_then(|v| v.as_u64()) {
+            if (items.len() as u64) < min {
+                return Err(format!("{}: expected at least {} items", path, min));
+            }
+        }
+        if let Some(max) = obj.get("maxItems").and_then(|v| v.as_u64()) {
+            if items.len() as u64 > max {
+                return Err(format!("{}: expected at most {} items", path, max));
+            }
+        }
+        if let Some(item_schema) = obj.get("items") {
+            for (i, item) in items.iter().enumerate() {
+                check(item, item_schema, &format!("{}[{}]", path, i))?;
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn type_matches(value: &Value, ty: &str) -> bool {
+    match ty {
+        "string" => value.is_string(),
+        "number" => value.is_number(),
+        "integer" => value.is_i64() || value.is_u64(),
+        "boolean" => value.is_boolean(),
+        "null" => value.is_null(),
+        "object" => value.is_object(),
+        "array" => value.is_array(),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    fn person_schema() -> Value {
+        json!({
+            "type": "object",
+            "properties": {
+                "name": { "type": "string" },
+                "age": { "type": "integer" },
+                "address": {
+                    "type": "object",
+                    "properties": {
+                        "city": { "type": "string" },
+                        "zip": { "type": ["string", "null"] }
+                    },
+                    "required": ["city"]
+                },
+                "tags": {
+                    "type": "array",
+                    "items": { "enum": ["admin", "user"] },
+                    "minItems": 1,
+                    "maxItems": 3
+                },
+                "scores": {
+                    "type": "array",
+                    "items": {
+                        "type": "array",
+                        "items": { "type": "number" }
+                    }
+                }
+            },
+            "required": ["name", "address"]
+        })
+    }
+
+    #[test]
+    fn test_nested_schema_compiles_to_valid_grammar() {
+        let grammar = schema_to_grammar(&person_schema()).unwrap();
+
+        assert!(grammar.starts_with("root ::= ws root-obj ws\n"));
+        assert!(grammar.contains("root-address-obj ::="));
+        assert!(grammar.contains("root-scores-item-arr ::="));
+        assert!(grammar.contains(
+            r#"root-tags-arr ::= "[" ws ("\"admin\"" | "\"user\"") (ws "," ws ("\"admin\"" | "\"user\"")){0,2} ws "]""#
+        ));
+    }
+
+    #[test]
+    fn test_optional_leading_properties() {
+        let schema = json!({
+            "type": "object",
+            "properties": {
+                "a": { "type": "boolean" },
+                "b": { "type": "null" }
+            }
+        });
+        let grammar = schema_to_grammar(&schema).unwrap();
+
+        // Either property can open the list, and the object may be empty
+        assert!(grammar.contains(r#"root-obj ::= "{" ws (root-obj-0)? ws "}""#));
+        assert!(grammar.contains(r#"root-obj-0 ::= "\"a\"" ws ":" ws boolean (ws "," ws "\"b\"" ws ":" ws null)? | root-obj-1"#));
+    }
+
+    #[test]
+    fn test_validate_nested_values() {
+        let schema = person_schema();
+
+        let ok = json!({
+            "name": "Ada",
+            "address": { "city": "London", "zip": null },
+            "tags": ["admin"],
+            "scores": [[1.5, 2], []]
+        });
+        assert!(validate_json(&ok, &schema).is_ok());
+
+        let missing = json!({ "name": "Ada", "address": { "zip": "N1" } });
+        let err = validate_json(&missing, &schema).unwrap_err();
+        assert!(matches!(err, LlmError::SchemaValidation(ref m) if m.contains("$.address") && m.contains("city")));
+
+        let bad_item = json!({ "name": "Ada", "address": { "city": "L" }, "scores": [[1, "x"]] });
+        let err = validate_json(&bad_item, &schema).unwrap_err();
+        assert!(matches!(err, LlmError::SchemaValidation(ref m) if m.contains("$.scores[0][1]")));
+
+        let too_many = json!({ "name": "Ada", "address": { "city": "L" }, "tags": ["user", "user", "user", "user"] });
+        assert!(validate_json(&too_many, &schema).is_err());
+    }
+
+    #[test]
+    fn test_unsupported_schema() {
+        let err = schema_to_grammar(&json!({ "$ref": "#/definitions/x" })).unwrap_err();
+        assert!(matches!(err, LlmError::InvalidSchema(_)));
+        assert!(schema_to_grammar(&json!({ "type": "tuple" })).is_err());
+    }
+}
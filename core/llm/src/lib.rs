@@ -7,9 +7,13 @@
 //!
 //! - Model management (load/unload GGUF models)
 //! - Chat sessions with history
-//! - Token streaming
-//! - GPU acceleration (CUDA/Metal)
+//! - Token streaming, cancellable via `CancellationToken`
+//! - GPU acceleration (CUDA/Metal) with CPU fallback
 //! - Sampling strategies
+//! - JSON output constrained by a JSON Schema
+//! - Tool calling with grammar-constrained call JSON
+//! - Saved KV cache state for prompt prefixes
+//! - Stackable LoRA adapters applied at runtime
 //!
 //! # Example
 //!
@@ -26,15 +30,27 @@
 pub mod config;
 pub mod engine;
 pub mod error;
+pub mod gguf;
+pub mod gpu;
+pub mod json_schema;
+pub mod lora;
 pub mod model;
 pub mod session;
 pub mod sampling;
+pub mod state;
 pub mod streaming;
+pub mod tools;
 
-pub use config::{LlmConfig, ModelConfig, GenerationConfig};
+pub use config::{LlmConfig, ModelConfig, GenerationConfig, SplitMode};
 pub use engine::LlmEngine;
 pub use error::{LlmError, Result};
-pub use model::{LoadedModel, ModelInfo, ModelManager};
-pub use session::{ChatSession, ChatMessage, MessageRole};
-pub use sampling::SamplingStrategy;
-pub use streaming::{TokenStream, StreamEvent};
+pub use gguf::GgufMetadata;
+pub use gpu::{GpuBackend, GpuDevice, GpuInfo};
+pub use lora::LoraInfo;
+pub use model::{quantization_name, LoadedModel, ModelInfo, ModelManager};
+pub use session::{ChatSession, ChatMessage, ContextPolicy, MessageRole, PromptTemplate};
+pub use sampling::{PenaltyConfig, SamplingStrategy};
+pub use state::{KvState, STATE_FORMAT_VERSION};
+pub use streaming::{Completion, TokenStream, StreamEvent, StopReason, Utf8Buffer};
+pub use tools::{GenerationOutcome, ParsedToolCall, ToolSpec};
+pub use tokio_util::sync::CancellationToken;
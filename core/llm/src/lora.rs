@@ -0,0 +1,114 @@
+//! LoRA adapters - fine-tuned weight deltas applied on top of a loaded model
+//!
+//! Adapters are loaded once per model and attached to every context created
+//! for it, in the order they were applied, each with its own scale. Changing
+//! the adapter set changes what the KV cache holds, so any prefilled or saved
+//! state must be rebuilt afterwards.
+
+use crate::error::{LlmError, Result};
+use crate::gguf;
+
+use llama_cpp_2::context::LlamaContext;
+use llama_cpp_2::model::LlamaLoraAdapter;
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// An adapter applied to a loaded model
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LoraInfo {
+    /// Adapter GGUF file
+    pub path: PathBuf,
+
+    /// Scale the adapter is applied with (1.0 = as trained)
+    pub scale: f32,
+
+    /// Architecture declared by the adapter (`general.architecture`)
+    pub architecture: Option<String>,
+}
+
+/// A loaded adapter and the scale it is attached with
+pub(crate) struct ActiveLora {
+    pub(crate) info: LoraInfo,
+    adapter: Mutex<AdapterHandle>,
+}
+
+/// Owned llama.cpp adapter
+struct AdapterHandle(LlamaLoraAdapter);
+
+// SAFETY: the adapter is only read by llama.cpp once loaded, and every use
+// from Rust goes through the `Mutex` in `ActiveLora`
+unsafe impl Send for AdapterHandle {}
+
+impl ActiveLora {
+    pub(crate) fn new(info: LoraInfo, adapter: LlamaLoraAdapter) -> Self {
+        Self {
+            info,
+            adapter: Mutex::new(AdapterHandle(adapter)),
+        }
+    }
+
+    /// Attach this adapter to `ctx` at its scale
+    pub(crate) fn attach(&self, ctx: &mut LlamaContext) -> Result<()> {
+        let mut handle = self.adapter.lock().unwrap_or_else(|e| e.into_inner());
+        ctx.lora_adapter_set(&mut handle.0, self.info.scale)
+            .map_err(|e| LlmError::ContextError(format!(
+                "failed to attach LoRA adapter {}: {}",
+                self.info.path.display(),
+                e
+            )))
+    }
+}
+
+/// Attach `adapters` to `ctx` in order
+///
+/// Callers must keep `adapters` alive for as long as `ctx` is used, so
+/// declare them before the context.
+pub(crate) fn attach_all(adapters: &[Arc<ActiveLora>], ctx: &mut LlamaContext) -> Result<()> {
+    for adapter in adapters {
+        adapter.attach(ctx)?;
+    }
+    Ok(())
+}
+
+/// Read `general.architecture` from a GGUF header without loading tensors
+pub fn gguf_architecture(path: &Path) -> Result<Option<String>> {
+    Ok(gguf::read_metadata(path)?.architecture)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::gguf::fixture::Header;
+
+    fn sample_header(architecture: Option<&str>) -> Vec<u8> {
+        let header = Header::new()
+            .push_string("general.type", "adapter")
+            // Skipped
+            .push_f32("adapter.lora.alpha", 16.0);
+        match architecture {
+            Some(arch) => header.push_string("general.architecture", arch).build(),
+            None => header.build(),
+        }
+    }
+
+    #[test]
+    fn test_gguf_architecture() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("adapter.gguf");
+
+        std::fs::write(&path, sample_header(Some("llama"))).unwrap();
+        assert_eq!(gguf_architecture(&path).unwrap().as_deref(), Some("llama"));
+
+        std::fs::write(&path, sample_header(None)).unwrap();
+        assert_eq!(gguf_architecture(&path).unwrap(), None);
+
+        std::fs::write(&path, b"WCKV not a gguf").unwrap();
+        assert!(matches!(gguf_architecture(&path), Err(LlmError::ModelLoadError(_))));
+
+        let truncated = &sample_header(Some("llama"))[..30];
+        std::fs::write(&path, truncated).unwrap();
+        assert!(matches!(gguf_architecture(&path), Err(LlmError::ModelLoadError(_))));
+    }
+}
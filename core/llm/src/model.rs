@@ -1,16 +1,20 @@
 //! Model management - loading, unloading, and info
 
-use crate::config::ModelConfig;
+use crate::config::{ModelConfig, SplitMode};
 use crate::error::{LlmError, Result};
+use crate::gguf::{self, GgufMetadata};
+use crate::lora::{ActiveLora, LoraInfo};
+use crate::session::PromptTemplate;
+use crate::state;
 
 use llama_cpp_2::llama_backend::LlamaBackend;
-use llama_cpp_2::model::params::LlamaModelParams;
+use llama_cpp_2::model::params::{LlamaModelParams, LlamaSplitMode};
 use llama_cpp_2::model::LlamaModel;
 
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 use std::path::{Path, PathBuf};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex, OnceLock, RwLock};
 use tracing::{debug, info, warn};
 
 /// Information about a loaded model
@@ -37,11 +41,84 @@ pub struct ModelInfo {
     /// Number of parameters
     pub n_params: u64,
     
+    /// Parameter count declared in the GGUF header (`general.parameter_count`),
+    /// else counted from the tensors
+    #[serde(default)]
+    pub param_count: Option<u64>,
+    
+    /// Quantization type from `general.file_type` (e.g. "Q4_K_M", "unknown")
+    #[serde(default)]
+    pub quantization: String,
+    
     /// Model size in bytes
     pub size_bytes: u64,
     
     /// Whether model supports chat template
     pub has_chat_template: bool,
+    
+    /// Jinja chat template source (`tokenizer.chat_template`)
+    #[serde(default)]
+    pub chat_template: Option<String>,
+    
+    /// Prompt format detected from metadata (`Raw` when unknown)
+    #[serde(default)]
+    pub prompt_template: PromptTemplate,
+    
+    /// Layers actually offloaded to GPU (0 = CPU only)
+    #[serde(default)]
+    pub gpu_layers: u32,
+}
+
+impl ModelInfo {
+    /// Check that `max_tokens` fits the model's training context window
+    pub fn validate_max_tokens(&self, max_tokens: u32) -> Result<()> {
+        if self.context_length > 0 && max_tokens > self.context_length {
+            return Err(LlmError::ConfigError(format!(
+                "max_tokens {} exceeds the context length {} of {}",
+                max_tokens, self.context_length, self.name
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Name of a GGUF `general.file_type` (llama.cpp `llama_ftype`) value
+pub fn quantization_name(file_type: u32) -> &'static str {
+    match file_type {
+        0 => "F32",
+        1 => "F16",
+        2 => "Q4_0",
+        3 => "Q4_1",
+        7 => "Q8_0",
+        8 => "Q5_0",
+        9 => "Q5_1",
+        10 => "Q2_K",
+        11 => "Q3_K_S",
+        12 => "Q3_K_M",
+        13 => "Q3_K_L",
+        14 => "Q4_K_S",
+        15 => "Q4_K_M",
+        16 => "Q5_K_S",
+        17 => "Q5_K_M",
+        18 => "Q6_K",
+        19 => "IQ2_XXS",
+        20 => "IQ2_XS",
+        21 => "Q2_K_S",
+        22 => "IQ3_XS",
+        23 => "IQ3_XXS",
+        24 => "IQ1_S",
+        25 => "IQ4_NL",
+        26 => "IQ3_S",
+        27 => "IQ3_M",
+        28 => "IQ2_S",
+        29 => "IQ2_M",
+        30 => "IQ4_XS",
+        31 => "IQ1_M",
+        32 => "BF16",
+        36 => "TQ1_0",
+        37 => "TQ2_0",
+        _ => "unknown",
+    }
 }
 
 /// A loaded model with its backend reference
@@ -54,6 +131,12 @@ pub struct LoadedModel {
     
     /// Model information
     pub info: ModelInfo,
+    
+    /// Hash of the GGUF file, computed on first use
+    gguf_hash: OnceLock<u64>,
+    
+    /// LoRA adapters attached to every new context, in apply order
+    loras: Mutex<Vec<Arc<ActiveLora>>>,
 }
 
 impl LoadedModel {
@@ -67,25 +150,76 @@ impl LoadedModel {
         &self.model
     }
     
+    /// Hash of the GGUF file (read once, then cached)
+    pub fn gguf_hash(&self) -> Result<u64> {
+        if let Some(hash) = self.gguf_hash.get() {
+            return Ok(*hash);
+        }
+        let hash = state::gguf_hash(&self.info.path)?;
+        Ok(*self.gguf_hash.get_or_init(|| hash))
+    }
+    
     /// Check if model has a chat template
     pub fn has_chat_template(&self) -> bool {
         self.model.chat_template(None).is_ok()
     }
     
-    /// Get the chat template if available (returns None - templates are opaque)
-    /// Use apply_chat_template directly with the model instead
+    /// Get the chat template source from the GGUF metadata, if any
     pub fn chat_template(&self) -> Option<String> {
-        // LlamaChatTemplate is opaque - we can't extract the source
-        // Just indicate if a template exists
-        if self.has_chat_template() {
-            Some("ChatML".to_string()) // Placeholder name
-        } else {
-            None
-        }
+        self.info.chat_template.clone()
+    }
+    
+    /// LoRA adapters currently applied, in apply order
+    pub fn loras(&self) -> Vec<LoraInfo> {
+        self.lock_loras().iter().map(|l| l.info.clone()).collect()
+    }
+    
+    /// Snapshot of the applied adapters, for attaching to a new context
+    pub(crate) fn active_loras(&self) -> Vec<Arc<ActiveLora>> {
+        self.lock_loras().clone()
+    }
+    
+    pub(crate) fn push_lora(&self, lora: ActiveLora) {
+        self.lock_loras().push(Arc::new(lora));
+    }
+    
+    /// Remove all adapters, returning how many were applied
+    pub(crate) fn clear_loras(&self) -> usize {
+        std::mem::take(&mut *self.lock_loras()).len()
+    }
+    
+    fn lock_loras(&self) -> std::sync::MutexGuard<'_, Vec<Arc<ActiveLora>>> {
+        self.loras.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+/// Resident models, keyed by name
+#[derive(Default)]
+struct LoadedModels {
+    /// Loaded models by name
+    by_name: HashMap<String, Arc<LoadedModel>>,
+    
+    /// Names in load order, most recent last
+    order: Vec<String>,
+}
+
+impl LoadedModels {
+    fn insert(&mut self, name: String, model: Arc<LoadedModel>) {
+        self.order.retain(|n| n != &name);
+        self.order.push(name.clone());
+        self.by_name.insert(name, model);
+    }
+    
+    fn remove(&mut self, name: &str) -> Option<Arc<LoadedModel>> {
+        self.order.retain(|n| n != name);
+        self.by_name.remove(name)
     }
 }
 
 /// Manager for loading and caching models
+///
+/// Several models can be resident at once (e.g. a draft and a chat model),
+/// each addressed by name.
 pub struct ModelManager {
     /// Reference to the llama.cpp backend
     backend: Arc<LlamaBackend>,
@@ -93,8 +227,8 @@ pub struct ModelManager {
     /// Models directory
     models_dir: PathBuf,
     
-    /// Currently loaded models by name
-    loaded_models: HashMap<String, Arc<LoadedModel>>,
+    /// Currently loaded models
+    loaded_models: RwLock<LoadedModels>,
     
     /// Default GPU layers to offload
     default_gpu_layers: u32,
@@ -106,7 +240,7 @@ impl ModelManager {
         Self {
             backend,
             models_dir: models_dir.into(),
-            loaded_models: HashMap::new(),
+            loaded_models: RwLock::new(LoadedModels::default()),
             default_gpu_layers: 1000,
         }
     }
@@ -146,64 +280,136 @@ impl ModelManager {
         Ok(())
     }
     
-    /// Load a model from path
-    pub fn load(&mut self, config: ModelConfig) -> Result<Arc<LoadedModel>> {
-        let path = if config.path.is_absolute() {
-            config.path.clone()
-        } else {
-            self.models_dir.join(&config.path)
-        };
-        
-        if !path.exists() {
-            return Err(LlmError::ModelNotFound(path.display().to_string()));
-        }
-        
+    /// Load a model from path, named after its file stem
+    pub fn load(&self, config: ModelConfig) -> Result<Arc<LoadedModel>> {
+        let path = self.resolve_path(&config.path);
         let model_name = path.file_stem()
             .and_then(|s| s.to_str())
             .unwrap_or("unknown")
             .to_string();
         
+        self.load_named(&model_name, path, config)
+    }
+    
+    /// Load a model under an explicit name
+    ///
+    /// `path` overrides `config.path`. If `name` is already loaded from the
+    /// same file the resident model is returned unchanged; loading another
+    /// file under that name fails until the resident model is unloaded.
+    pub fn load_model_as(
+        &self,
+        name: &str,
+        path: impl Into<PathBuf>,
+        config: ModelConfig,
+    ) -> Result<Arc<LoadedModel>> {
+        let path = self.resolve_path(&path.into());
+        self.load_named(name, path, config)
+    }
+    
+    /// Resolve a model path against the models directory
+    fn resolve_path(&self, path: &Path) -> PathBuf {
+        if path.is_absolute() {
+            path.to_path_buf()
+        } else {
+            self.models_dir.join(path)
+        }
+    }
+    
+    /// Load `path` and register it as `name`
+    fn load_named(&self, name: &str, path: PathBuf, mut config: ModelConfig) -> Result<Arc<LoadedModel>> {
+        if !path.exists() {
+            return Err(LlmError::ModelNotFound(path.display().to_string()));
+        }
+        
         // Check if already loaded
-        if let Some(model) = self.loaded_models.get(&model_name) {
-            debug!("Model {} already loaded", model_name);
-            return Ok(Arc::clone(model));
+        if let Some(model) = self.get(name) {
+            if !same_file(&model.config.path, &path) {
+                return Err(LlmError::ConfigError(format!(
+                    "model '{}' is already loaded from {}; unload it before loading {}",
+                    name,
+                    model.config.path.display(),
+                    path.display()
+                )));
+            }
+            debug!("Model {} already loaded", name);
+            self.write_models().insert(name.to_string(), Arc::clone(&model));
+            return Ok(model);
         }
         
-        info!("Loading model: {}", path.display());
+        info!("Loading model: {} as {}", path.display(), name);
         
-        // Configure model parameters
-        let gpu_layers = config.n_gpu_layers.unwrap_or(self.default_gpu_layers);
-        let model_params = LlamaModelParams::default()
-            .with_n_gpu_layers(gpu_layers);
+        // Read the header first so a non-GGUF file fails with a clear error
+        let header = gguf::read_metadata(&path)?;
         
-        // Load model
-        let model = LlamaModel::load_from_file(&self.backend, &path, &model_params)
-            .map_err(|e| LlmError::ModelLoadError(e.to_string()))?;
+        // Configure GPU offload
+        let mut gpu_layers = resolve_gpu_layers(
+            config.n_gpu_layers,
+            self.default_gpu_layers,
+            self.backend.supports_gpu_offload(),
+        );
+        
+        // Load model, falling back to CPU if GPU allocation fails
+        let model = match Self::load_with_gpu_layers(&self.backend, &path, &config, gpu_layers) {
+            Ok(model) => model,
+            Err(e) if gpu_layers > 0 => {
+                warn!("Loading {} on GPU failed ({}), falling back to CPU", path.display(), e);
+                gpu_layers = 0;
+                Self::load_with_gpu_layers(&self.backend, &path, &config, 0)?
+            }
+            Err(e) => return Err(e),
+        };
         
         // Extract model info
-        let info = Self::extract_info(&model, &path);
+        let info = Self::extract_info(&model, &path, gpu_layers, &header);
+        config.path = path;
         
         info!(
-            "Loaded model: {} ({} params, {} ctx)",
-            info.name, info.n_params, info.context_length
+            "Loaded model: {} ({} params, {}, {} ctx, {} layers on GPU)",
+            info.name, info.n_params, info.quantization, info.context_length, info.gpu_layers
         );
         
         let loaded = Arc::new(LoadedModel {
             model,
             config,
             info,
+            gguf_hash: OnceLock::new(),
+            loras: Mutex::new(Vec::new()),
         });
         
-        self.loaded_models.insert(model_name.clone(), Arc::clone(&loaded));
+        self.write_models().insert(name.to_string(), Arc::clone(&loaded));
         
         Ok(loaded)
     }
     
+    /// Load a GGUF file with `gpu_layers` layers offloaded
+    fn load_with_gpu_layers(
+        backend: &LlamaBackend,
+        path: &Path,
+        config: &ModelConfig,
+        gpu_layers: u32,
+    ) -> Result<LlamaModel> {
+        let mut model_params = LlamaModelParams::default()
+            .with_n_gpu_layers(gpu_layers);
+        if gpu_layers > 0 {
+            model_params = model_params.with_split_mode(match config.split_mode {
+                SplitMode::None => LlamaSplitMode::None,
+                SplitMode::Layer => LlamaSplitMode::Layer,
+                SplitMode::Row => LlamaSplitMode::Row,
+            });
+            if let Some(main_gpu) = config.main_gpu {
+                model_params = model_params.with_main_gpu(main_gpu);
+            }
+        }
+        
+        LlamaModel::load_from_file(backend, path, &model_params)
+            .map_err(|e| LlmError::ModelLoadError(e.to_string()))
+    }
+    
     /// Load a model by name (looks in models directory)
-    pub fn load_by_name(&mut self, name: &str) -> Result<Arc<LoadedModel>> {
+    pub fn load_by_name(&self, name: &str) -> Result<Arc<LoadedModel>> {
         // Check if already loaded
-        if let Some(model) = self.loaded_models.get(name) {
-            return Ok(Arc::clone(model));
+        if let Some(model) = self.get(name) {
+            return Ok(model);
         }
         
         // Search for model file
@@ -221,8 +427,8 @@ impl ModelManager {
     }
     
     /// Unload a model by name
-    pub fn unload(&mut self, name: &str) -> bool {
-        if self.loaded_models.remove(name).is_some() {
+    pub fn unload(&self, name: &str) -> bool {
+        if self.write_models().remove(name).is_some() {
             info!("Unloaded model: {}", name);
             true
         } else {
@@ -233,16 +439,30 @@ impl ModelManager {
     
     /// Get a loaded model by name
     pub fn get(&self, name: &str) -> Option<Arc<LoadedModel>> {
-        self.loaded_models.get(name).cloned()
+        self.read_models().by_name.get(name).cloned()
     }
     
-    /// List loaded models
+    /// Name of the most recently loaded model still resident
+    pub fn most_recent(&self) -> Option<String> {
+        self.read_models().order.last().cloned()
+    }
+    
+    /// List loaded models, oldest first
     pub fn list_loaded(&self) -> Vec<String> {
-        self.loaded_models.keys().cloned().collect()
+        self.read_models().order.clone()
+    }
+    
+    fn read_models(&self) -> std::sync::RwLockReadGuard<'_, LoadedModels> {
+        self.loaded_models.read().unwrap_or_else(|e| e.into_inner())
     }
     
-    /// Extract model information
-    fn extract_info(model: &LlamaModel, path: &Path) -> ModelInfo {
+    fn write_models(&self) -> std::sync::RwLockWriteGuard<'_, LoadedModels> {
+        self.loaded_models.write().unwrap_or_else(|e| e.into_inner())
+    }
+    
+    /// Extract model information, with architecture, context length,
+    /// quantization and parameter count taken from the GGUF header
+    fn extract_info(model: &LlamaModel, path: &Path, gpu_layers: u32, header: &GgufMetadata) -> ModelInfo {
         let name = model.meta_val_str("general.name")
             .ok()
             .or_else(|| {
@@ -252,9 +472,29 @@ impl ModelManager {
             })
             .unwrap_or_else(|| "unknown".to_string());
         
-        let architecture = model.meta_val_str("general.architecture").ok();
+        let architecture = header.architecture.clone();
+        
+        // llama.cpp offloads at most every block plus the output layer
+        let block_count = architecture.as_deref()
+            .and_then(|arch| model.meta_val_str(&format!("{}.block_count", arch)).ok())
+            .and_then(|v| v.parse::<u32>().ok());
+        let gpu_layers = match block_count {
+            Some(blocks) => gpu_layers.min(blocks + 1),
+            None => gpu_layers,
+        };
         
         let has_chat_template = model.chat_template(None).is_ok();
+        let chat_template = model.meta_val_str("tokenizer.chat_template").ok();
+        let prompt_template = PromptTemplate::detect(
+            chat_template.as_deref(),
+            architecture.as_deref(),
+            Some(&name),
+        );
+        
+        let quantization = header.quantization().to_string();
+        let n_params = model.n_params();
+        let param_count = header.parameter_count
+            .or((n_params > 0).then_some(n_params));
         
         ModelInfo {
             path: path.to_path_buf(),
@@ -262,14 +502,48 @@ impl ModelManager {
             architecture,
             vocab_size: model.n_vocab(),
             embedding_dim: model.n_embd(),
-            context_length: model.n_ctx_train(),
-            n_params: model.n_params(),
+            context_length: header.context_length.unwrap_or_else(|| model.n_ctx_train()),
+            n_params,
+            param_count,
+            quantization,
             size_bytes: model.size(),
             has_chat_template,
+            chat_template,
+            prompt_template,
+            gpu_layers,
         }
     }
 }
 
+/// Layers to offload: the explicit request, else the engine default; always
+/// 0 when no GPU backend is available
+pub(crate) fn resolve_gpu_layers(requested: Option<u32>, default: u32, gpu_available: bool) -> u32 {
+    if !gpu_available {
+        return 0;
+    }
+    requested.unwrap_or(default)
+}
+
+/// Whether two paths name the same file, compared as given when either
+/// cannot be resolved
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// Backend shared by the crate's tests
+///
+/// Tests run on parallel threads and llama.cpp refuses a second backend.
+#[cfg(test)]
+pub(crate) fn test_backend() -> Arc<LlamaBackend> {
+    static BACKEND: OnceLock<Arc<LlamaBackend>> = OnceLock::new();
+    Arc::clone(BACKEND.get_or_init(|| {
+        Arc::new(LlamaBackend::init().expect("Failed to init llama.cpp backend"))
+    }))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -284,8 +558,13 @@ mod tests {
             embedding_dim: 4096,
             context_length: 4096,
             n_params: 7_000_000_000,
+            param_count: Some(7_000_000_000),
+            quantization: "Q4_K_M".to_string(),
             size_bytes: 4_000_000_000,
             has_chat_template: true,
+            chat_template: Some("{{ bos_token }}".to_string()),
+            prompt_template: PromptTemplate::Llama3,
+            gpu_layers: 33,
         };
         
         let json = serde_json::to_string(&info).unwrap();
@@ -293,5 +572,66 @@ mod tests {
         
         assert_eq!(parsed.name, "test-model");
         assert_eq!(parsed.vocab_size, 32000);
+        assert_eq!(parsed.prompt_template, PromptTemplate::Llama3);
+        assert_eq!(parsed.gpu_layers, 33);
+        assert_eq!(parsed.quantization, "Q4_K_M");
+        assert_eq!(parsed.chat_template.as_deref(), Some("{{ bos_token }}"));
+        
+        assert!(parsed.validate_max_tokens(4096).is_ok());
+        assert!(matches!(parsed.validate_max_tokens(4097), Err(LlmError::ConfigError(_))));
+    }
+    
+    #[test]
+    fn test_quantization_name() {
+        assert_eq!(quantization_name(1), "F16");
+        assert_eq!(quantization_name(15), "Q4_K_M");
+        assert_eq!(quantization_name(18), "Q6_K");
+        assert_eq!(quantization_name(4), "unknown");
+        assert_eq!(quantization_name(999), "unknown");
+    }
+    
+    #[test]
+    #[ignore = "requires a GGUF model at WHYTCARD_TEST_MODEL"]
+    fn test_loaded_model_info() {
+        let path = std::env::var("WHYTCARD_TEST_MODEL").expect("WHYTCARD_TEST_MODEL not set");
+        let manager = ModelManager::new(test_backend(), ".");
+        
+        let model = manager.load(ModelConfig::from_path(&path).with_gpu_layers(0)).unwrap();
+        let info = model.info();
+        assert!(info.context_length > 0);
+        assert!(info.architecture.is_some());
+        assert_ne!(info.quantization, "unknown");
+        assert!(info.param_count.is_some_and(|n| n > 0));
+        assert_eq!(info.chat_template.is_some(), info.has_chat_template);
+    }
+    
+    #[test]
+    #[ignore = "requires a GGUF model at WHYTCARD_TEST_MODEL"]
+    fn test_load_named_rejects_other_path() {
+        let path = std::env::var("WHYTCARD_TEST_MODEL").expect("WHYTCARD_TEST_MODEL not set");
+        let manager = ModelManager::new(test_backend(), ".");
+        let config = ModelConfig::from_path(&path).with_gpu_layers(0);
+        
+        let model = manager.load_model_as("main", &path, config.clone()).unwrap();
+        let again = manager.load_model_as("main", &path, config.clone()).unwrap();
+        assert!(Arc::ptr_eq(&model, &again));
+        
+        // Rejected before anything is read, so the other file need not be a model
+        let dir = tempfile::tempdir().unwrap();
+        let other = dir.path().join("other.gguf");
+        std::fs::write(&other, b"GGUF").unwrap();
+        let err = manager.load_model_as("main", &other, config).err().unwrap();
+        assert!(matches!(err, LlmError::ConfigError(_)), "{err}");
+    }
+    
+    #[test]
+    fn test_resolve_gpu_layers() {
+        // No GPU backend: CPU regardless of the request
+        assert_eq!(resolve_gpu_layers(Some(32), 1000, false), 0);
+        assert_eq!(resolve_gpu_layers(None, 1000, false), 0);
+        
+        assert_eq!(resolve_gpu_layers(None, 1000, true), 1000);
+        assert_eq!(resolve_gpu_layers(Some(12), 1000, true), 12);
+        assert_eq!(resolve_gpu_layers(Some(0), 1000, true), 0);
     }
 }
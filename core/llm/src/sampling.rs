@@ -1,5 +1,23 @@
 //! Sampling strategies for token generation
+//!
+//! # Sampler chain order
+//!
+//! Samplers run in this order, and the order changes the output:
+//!
+//! 1. Grammar (if any) - masks tokens the grammar doesn't allow
+//! 2. Penalties - repeat, frequency and presence penalties over the last
+//!    `repeat_last_n` tokens, applied to the raw logits
+//! 3. Truncation - top-k, then top-p, then min-p (skipped for Mirostat)
+//! 4. Temperature
+//! 5. Selection - random draw, greedy pick, or Mirostat
+//!
+//! Penalising before truncation means a repeated token can fall out of the
+//! top-k/top-p set entirely instead of only losing probability within it.
 
+use crate::error::{LlmError, Result};
+
+use llama_cpp_2::model::LlamaModel;
+use llama_cpp_2::sampling::LlamaSampler;
 use serde::{Deserialize, Serialize};
 
 /// Sampling strategy configuration
@@ -40,6 +58,9 @@ pub enum SamplingStrategy {
     },
     
     /// Mirostat sampling (dynamic entropy control)
+    ///
+    /// Replaces truncation and temperature: the sampler targets a surprise
+    /// of `tau`, adjusting its cutoff at learning rate `eta`.
     Mirostat {
         tau: f32,
         eta: f32,
@@ -47,6 +68,9 @@ pub enum SamplingStrategy {
     },
 }
 
+/// Candidates considered by Mirostat v1 when estimating the Zipf exponent
+const MIROSTAT_M: i32 = 100;
+
 impl Default for SamplingStrategy {
     fn default() -> Self {
         Self::Combined {
@@ -97,6 +121,69 @@ impl SamplingStrategy {
             version: 2,
         }
     }
+    
+    /// Strategy described by the temperature and truncation fields of a config
+    pub fn from_generation(config: &crate::config::GenerationConfig) -> Self {
+        if config.temperature <= 0.0 {
+            Self::Greedy
+        } else {
+            Self::Combined {
+                temperature: config.temperature,
+                top_k: config.top_k,
+                top_p: config.top_p,
+                min_p: config.min_p,
+            }
+        }
+    }
+    
+    /// Truncation, temperature and selection samplers, in chain order
+    ///
+    /// `n_vocab` is only used by Mirostat v1.
+    pub fn samplers(&self, seed: u32, n_vocab: i32) -> Vec<LlamaSampler> {
+        let mut samplers = Vec::new();
+        match *self {
+            Self::Greedy => samplers.push(LlamaSampler::greedy()),
+            Self::Temperature { temperature } => {
+                samplers.push(LlamaSampler::temp(temperature));
+                samplers.push(LlamaSampler::dist(seed));
+            }
+            Self::TopK { k, temperature } => {
+                samplers.push(LlamaSampler::top_k(k));
+                samplers.push(LlamaSampler::temp(temperature));
+                samplers.push(LlamaSampler::dist(seed));
+            }
+            Self::TopP { p, temperature } => {
+                samplers.push(LlamaSampler::top_p(p, 1));
+                samplers.push(LlamaSampler::temp(temperature));
+                samplers.push(LlamaSampler::dist(seed));
+            }
+            Self::MinP { p, temperature } => {
+                samplers.push(LlamaSampler::min_p(p, 1));
+                samplers.push(LlamaSampler::temp(temperature));
+                samplers.push(LlamaSampler::dist(seed));
+            }
+            Self::Combined { temperature, top_k, top_p, min_p } => {
+                if top_k > 0 {
+                    samplers.push(LlamaSampler::top_k(top_k));
+                }
+                if top_p < 1.0 {
+                    samplers.push(LlamaSampler::top_p(top_p, 1));
+                }
+                if min_p > 0.0 {
+                    samplers.push(LlamaSampler::min_p(min_p, 1));
+                }
+                samplers.push(LlamaSampler::temp(temperature));
+                samplers.push(LlamaSampler::dist(seed));
+            }
+            Self::Mirostat { tau, eta, version: 1 } => {
+                samplers.push(LlamaSampler::mirostat(n_vocab, seed, tau, eta, MIROSTAT_M));
+            }
+            Self::Mirostat { tau, eta, .. } => {
+                samplers.push(LlamaSampler::mirostat_v2(seed, tau, eta));
+            }
+        }
+        samplers
+    }
 }
 
 /// Penalty configuration for repetition control
@@ -156,6 +243,33 @@ impl PenaltyConfig {
             presence_penalty: 0.5,
         }
     }
+    
+    /// Check whether any penalty would change the logits
+    pub fn is_enabled(&self) -> bool {
+        self.repeat_last_n != 0
+            && (self.repeat_penalty != 1.0 || self.frequency_penalty != 0.0 || self.presence_penalty != 0.0)
+    }
+    
+    /// Penalties sampler (`None` when every penalty is neutral)
+    pub fn sampler(&self) -> Option<LlamaSampler> {
+        self.is_enabled().then(|| {
+            LlamaSampler::penalties(
+                self.repeat_last_n,
+                self.repeat_penalty,
+                self.frequency_penalty,
+                self.presence_penalty,
+            )
+        })
+    }
+}
+
+/// Build a grammar sampler that masks tokens not allowed by a GBNF grammar
+///
+/// llama.cpp parses the grammar against the model's vocabulary; a grammar it
+/// rejects is reported as [`LlmError::InvalidGrammar`].
+pub fn grammar_sampler(model: &LlamaModel, grammar: &str) -> Result<LlamaSampler> {
+    LlamaSampler::grammar(model, grammar, "root")
+        .map_err(|e| LlmError::InvalidGrammar(format!("{:?}", e)))
 }
 
 #[cfg(test)]
@@ -183,4 +297,35 @@ mod tests {
         let strong = PenaltyConfig::strong();
         assert!((strong.repeat_penalty - 1.2).abs() < f32::EPSILON);
     }
+    
+    #[test]
+    fn test_penalties_enabled() {
+        assert!(!PenaltyConfig::none().is_enabled());
+        assert!(PenaltyConfig::none().sampler().is_none());
+        assert!(PenaltyConfig::default().is_enabled());
+        
+        // A zero window disables every penalty
+        let windowless = PenaltyConfig { repeat_last_n: 0, ..PenaltyConfig::strong() };
+        assert!(!windowless.is_enabled());
+        
+        let presence_only = PenaltyConfig { presence_penalty: 0.3, ..PenaltyConfig::none() };
+        assert!(!presence_only.is_enabled());
+        assert!(PenaltyConfig { repeat_last_n: 64, ..presence_only }.is_enabled());
+    }
+    
+    #[test]
+    fn test_strategy_samplers() {
+        let config = crate::config::GenerationConfig::greedy();
+        assert!(matches!(SamplingStrategy::from_generation(&config), SamplingStrategy::Greedy));
+        
+        let config = crate::config::GenerationConfig::default();
+        assert!(matches!(SamplingStrategy::from_generation(&config), SamplingStrategy::Combined { top_k: 40, .. }));
+        
+        // top_k + top_p + min_p + temp + dist
+        assert_eq!(SamplingStrategy::default().samplers(42, 32_000).len(), 5);
+        // Mirostat replaces truncation and temperature
+        assert_eq!(SamplingStrategy::mirostat_v2(5.0, 0.1).samplers(42, 32_000).len(), 1);
+        let v1 = SamplingStrategy::Mirostat { tau: 5.0, eta: 0.1, version: 1 };
+        assert_eq!(v1.samplers(42, 32_000).len(), 1);
+    }
 }
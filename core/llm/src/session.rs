@@ -30,6 +30,150 @@ impl MessageRole {
     }
 }
 
+/// Prompt format used to delimit chat turns for a model family
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PromptTemplate {
+    /// Llama 3 header tokens (`<|start_header_id|>` ... `<|eot_id|>`)
+    Llama3,
+    /// ChatML (`<|im_start|>` ... `<|im_end|>`), used by Qwen and others
+    #[serde(rename = "chatml")]
+    ChatML,
+    /// Mistral `[INST]` ... `[/INST]`
+    Mistral,
+    /// Gemma `<start_of_turn>` ... `<end_of_turn>`
+    Gemma,
+    /// Plain `Role: content` lines
+    #[default]
+    Raw,
+}
+
+impl PromptTemplate {
+    /// Guess the template from GGUF metadata
+    ///
+    /// The embedded `tokenizer.chat_template` wins when present, then the
+    /// architecture and model name. Unknown models fall back to `Raw`.
+    pub fn detect(
+        chat_template: Option<&str>,
+        architecture: Option<&str>,
+        name: Option<&str>,
+    ) -> Self {
+        if let Some(template) = chat_template {
+            if template.contains("<|start_header_id|>") {
+                return Self::Llama3;
+            }
+            if template.contains("<|im_start|>") {
+                return Self::ChatML;
+            }
+            if template.contains("<start_of_turn>") {
+                return Self::Gemma;
+            }
+            if template.contains("[INST]") {
+                return Self::Mistral;
+            }
+        }
+        
+        let architecture = architecture.unwrap_or_default().to_lowercase();
+        let name = name.unwrap_or_default().to_lowercase();
+        if architecture.starts_with("gemma") || name.contains("gemma") {
+            Self::Gemma
+        } else if architecture.starts_with("qwen") || name.contains("qwen") {
+            Self::ChatML
+        } else if name.contains("mistral") || name.contains("mixtral") {
+            Self::Mistral
+        } else if name.contains("llama-3") || name.contains("llama3") {
+            Self::Llama3
+        } else {
+            Self::Raw
+        }
+    }
+    
+    /// Render messages into a prompt, ending with the assistant's turn opener
+    ///
+    /// BOS is not emitted; tokenization adds it.
+    pub fn render(&self, messages: &[ChatMessage]) -> String {
+        let mut prompt = String::new();
+        
+        match self {
+            Self::Llama3 => {
+                for m in messages {
+                    prompt.push_str(&format!(
+                        "<|start_header_id|>{}<|end_header_id|>\n\n{}<|eot_id|>",
+                        m.role.as_str(),
+                        m.content
+                    ));
+                }
+                prompt.push_str("<|start_header_id|>assistant<|end_header_id|>\n\n");
+            }
+            Self::ChatML => {
+                for m in messages {
+                    prompt.push_str(&format!("<|im_start|>{}\n{}<|im_end|>\n", m.role.as_str(), m.content));
+                }
+                prompt.push_str("<|im_start|>assistant\n");
+            }
+            Self::Mistral => {
+                // No system role: it is folded into the first user turn
+                let mut system = Self::system_text(messages);
+                for m in messages {
+                    match m.role {
+                        MessageRole::System => {}
+                        MessageRole::User => match system.take() {
+                            Some(sys) => prompt.push_str(&format!("[INST] {}\n\n{} [/INST]", sys, m.content)),
+                            None => prompt.push_str(&format!("[INST] {} [/INST]", m.content)),
+                        },
+                        MessageRole::Assistant => prompt.push_str(&format!("{}</s>", m.content)),
+                    }
+                }
+            }
+            Self::Gemma => {
+                // No system role: it is folded into the first user turn
+                let mut system = Self::system_text(messages);
+                for m in messages {
+                    match m.role {
+                        MessageRole::System => {}
+                        MessageRole::User => {
+                            let content = match system.take() {
+                                Some(sys) => format!("{}\n\n{}", sys, m.content),
+                                None => m.content.clone(),
+                            };
+                            prompt.push_str(&format!("<start_of_turn>user\n{}<end_of_turn>\n", content));
+                        }
+                        MessageRole::Assistant => {
+                            prompt.push_str(&format!("<start_of_turn>model\n{}<end_of_turn>\n", m.content));
+                        }
+                    }
+                }
+                prompt.push_str("<start_of_turn>model\n");
+            }
+            Self::Raw => {
+                for m in messages {
+                    match m.role {
+                        MessageRole::System => prompt.push_str(&format!("System: {}\n\n", m.content)),
+                        MessageRole::User => prompt.push_str(&format!("User: {}\n", m.content)),
+                        MessageRole::Assistant => prompt.push_str(&format!("Assistant: {}\n", m.content)),
+                    }
+                }
+                prompt.push_str("Assistant:");
+            }
+        }
+        
+        prompt
+    }
+    
+    /// All system messages joined, for formats without a system role
+    fn system_text(messages: &[ChatMessage]) -> Option<String> {
+        let parts: Vec<&str> = messages.iter()
+            .filter(|m| m.role == MessageRole::System)
+            .map(|m| m.content.as_str())
+            .collect();
+        if parts.is_empty() {
+            None
+        } else {
+            Some(parts.join("\n\n"))
+        }
+    }
+}
+
 /// A single message in a conversation
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatMessage {
@@ -80,6 +224,21 @@ impl ChatMessage {
     }
 }
 
+/// What a session does when its history no longer fits the context window
+///
+/// System messages are pinned under every policy, as is the latest message.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ContextPolicy {
+    /// Drop the oldest messages until the prompt fits
+    #[default]
+    TruncateOldest,
+    /// Fold the oldest messages into a running summary
+    SummarizeOldest,
+    /// Fail with `LlmError::ContextOverflow`
+    Error,
+}
+
 /// A chat session with history management
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatSession {
@@ -107,6 +266,18 @@ pub struct ChatSession {
     /// Model used for this session
     pub model_name: Option<String>,
     
+    /// Prompt format (`None` = detect from the model)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub template: Option<PromptTemplate>,
+    
+    /// Handling of history that outgrows the context window
+    #[serde(default)]
+    pub context_policy: ContextPolicy,
+    
+    /// Summary of messages folded away by `ContextPolicy::SummarizeOldest`
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    
     /// Custom metadata
     #[serde(default)]
     pub metadata: HashMap<String, serde_json::Value>,
@@ -130,6 +301,9 @@ impl ChatSession {
             system_prompt: None,
             max_context_tokens: 4096,
             model_name: None,
+            template: None,
+            context_policy: ContextPolicy::default(),
+            summary: None,
             metadata: HashMap::new(),
         }
     }
@@ -166,6 +340,26 @@ impl ChatSession {
         self
     }
     
+    /// Set the prompt format, overriding detection from the model
+    pub fn with_template(mut self, template: PromptTemplate) -> Self {
+        self.template = Some(template);
+        self
+    }
+    
+    /// Set the context overflow policy
+    pub fn with_context_policy(mut self, policy: ContextPolicy) -> Self {
+        self.context_policy = policy;
+        self
+    }
+    
+    /// Render the conversation (system prompt included) with the session's
+    /// template, or `Raw` when none is set
+    pub fn render(&self) -> String {
+        self.template
+            .unwrap_or_default()
+            .render(&self.get_messages_with_system())
+    }
+    
     /// Add a message to the session
     pub fn add_message(&mut self, message: ChatMessage) {
         self.messages.push(message);
@@ -187,13 +381,16 @@ impl ChatSession {
         &self.messages
     }
     
-    /// Get messages with system prompt prepended
+    /// Get messages with system prompt (and summary, if any) prepended
     pub fn get_messages_with_system(&self) -> Vec<ChatMessage> {
         let mut messages = Vec::new();
         
         if let Some(system) = &self.system_prompt {
             messages.push(ChatMessage::system(system.clone()));
         }
+        if let Some(summary) = &self.summary {
+            messages.push(Self::summary_message(summary));
+        }
         
         messages.extend(self.messages.iter().cloned());
         messages
@@ -208,6 +405,7 @@ impl ChatSession {
     /// Clear all messages
     pub fn clear(&mut self) {
         self.messages.clear();
+        self.summary = None;
         self.updated_at = Utc::now();
     }
     
@@ -273,6 +471,85 @@ impl ChatSession {
             self.updated_at = Utc::now();
         }
     }
+    
+    /// Shrink the history until the prompt plus `max_tokens` fits in
+    /// `context_size`, following the session's [`ContextPolicy`]
+    ///
+    /// `count_tokens` measures the rendered prompt of the session;
+    /// `summarize` condenses messages for `SummarizeOldest` (the previous
+    /// summary, if any, comes first as a system message). A failed summary
+    /// falls back to dropping the messages. Returns the number of messages
+    /// removed from the history.
+    pub fn fit_context(
+        &mut self,
+        context_size: usize,
+        max_tokens: usize,
+        mut count_tokens: impl FnMut(&ChatSession) -> Result<usize>,
+        mut summarize: impl FnMut(&[ChatMessage]) -> Result<String>,
+    ) -> Result<usize> {
+        let mut removed = 0;
+        loop {
+            let prompt_tokens = count_tokens(self)?;
+            if prompt_tokens + max_tokens <= context_size {
+                return Ok(removed);
+            }
+            
+            let droppable = self.droppable_indices();
+            if self.context_policy == ContextPolicy::Error || droppable.is_empty() {
+                return Err(LlmError::ContextOverflow {
+                    prompt_tokens,
+                    max_tokens,
+                    context_size,
+                });
+            }
+            
+            // Fold the older half at once so each summary covers real ground
+            let take = match self.context_policy {
+                ContextPolicy::SummarizeOldest => droppable.len().div_ceil(2),
+                _ => 1,
+            };
+            let folded = self.remove_messages(&droppable[..take]);
+            removed += folded.len();
+            
+            if self.context_policy == ContextPolicy::SummarizeOldest {
+                let mut input = Vec::with_capacity(folded.len() + 1);
+                if let Some(summary) = &self.summary {
+                    input.push(Self::summary_message(summary));
+                }
+                input.extend(folded);
+                match summarize(&input) {
+                    Ok(summary) => self.summary = Some(summary.trim().to_string()),
+                    Err(e) => tracing::warn!("Summarizing chat history failed, dropping instead: {}", e),
+                }
+            }
+            self.updated_at = Utc::now();
+        }
+    }
+    
+    /// Indices of messages that may be trimmed, oldest first: everything
+    /// except system messages and the latest message
+    fn droppable_indices(&self) -> Vec<usize> {
+        let last = self.messages.len().saturating_sub(1);
+        self.messages.iter()
+            .enumerate()
+            .filter(|(i, m)| *i < last && m.role != MessageRole::System)
+            .map(|(i, _)| i)
+            .collect()
+    }
+    
+    /// Remove messages at ascending `indices`, returning them in order
+    fn remove_messages(&mut self, indices: &[usize]) -> Vec<ChatMessage> {
+        let mut removed = Vec::with_capacity(indices.len());
+        for &i in indices.iter().rev() {
+            removed.push(self.messages.remove(i));
+        }
+        removed.reverse();
+        removed
+    }
+    
+    fn summary_message(summary: &str) -> ChatMessage {
+        ChatMessage::system(format!("Summary of the earlier conversation:\n{}", summary))
+    }
 }
 
 /// Manager for multiple chat sessions
@@ -403,6 +680,121 @@ mod tests {
         assert_eq!(messages[0].role, MessageRole::System);
     }
     
+    /// One token per word of every rendered message
+    fn word_count(session: &ChatSession) -> Result<usize> {
+        Ok(session.get_messages_with_system()
+            .iter()
+            .map(|m| m.content.split_whitespace().count())
+            .sum())
+    }
+    
+    fn long_session(policy: ContextPolicy) -> ChatSession {
+        let mut session = ChatSession::new()
+            .with_system_prompt("be brief")
+            .with_context_policy(policy);
+        session.add_message(ChatMessage::system("pinned rule here"));
+        for i in 0..6 {
+            session.add_user_message(format!("question {} a b c", i));
+            session.add_assistant_message(format!("answer {} a b c", i));
+        }
+        session.add_user_message("final question");
+        session
+    }
+    
+    #[test]
+    fn test_fit_context_truncates_oldest() {
+        let mut session = long_session(ContextPolicy::TruncateOldest);
+        let removed = session.fit_context(30, 10, word_count, |_| unreachable!()).unwrap();
+        
+        assert!(removed > 0);
+        assert!(word_count(&session).unwrap() + 10 <= 30);
+        // System messages and the latest message survive
+        assert_eq!(session.messages[0].content, "pinned rule here");
+        assert_eq!(session.last_message().unwrap().content, "final question");
+        assert_eq!(session.messages[1].content, "question 5 a b c");
+        
+        // Nothing left to drop
+        let err = session.fit_context(5, 10, word_count, |_| unreachable!()).unwrap_err();
+        assert!(matches!(err, LlmError::ContextOverflow { max_tokens: 10, .. }));
+        assert_eq!(session.message_count(), 2);
+    }
+    
+    #[test]
+    fn test_fit_context_summarizes_oldest() {
+        let mut session = long_session(ContextPolicy::SummarizeOldest);
+        let mut calls = Vec::new();
+        session.fit_context(30, 10, word_count, |messages| {
+            calls.push(messages.len());
+            Ok(format!("{} messages", messages.len()))
+        }).unwrap();
+        
+        assert!(calls.len() >= 2);
+        assert!(word_count(&session).unwrap() + 10 <= 30);
+        let summary = session.summary.clone().unwrap();
+        assert!(session.get_messages_with_system()[1].content.contains(&summary));
+        assert_eq!(session.last_message().unwrap().content, "final question");
+        
+        session.clear();
+        assert!(session.summary.is_none());
+    }
+    
+    #[test]
+    fn test_fit_context_error_policy() {
+        let mut session = long_session(ContextPolicy::Error);
+        let before = session.message_count();
+        
+        assert!(matches!(
+            session.fit_context(30, 10, word_count, |_| unreachable!()),
+            Err(LlmError::ContextOverflow { .. })
+        ));
+        assert_eq!(session.message_count(), before);
+        assert_eq!(session.fit_context(1000, 10, word_count, |_| unreachable!()).unwrap(), 0);
+    }
+    
+    #[test]
+    fn test_prompt_templates() {
+        let session = |template| {
+            let mut session = ChatSession::new()
+                .with_system_prompt("Be brief.")
+                .with_template(template);
+            session.add_user_message("Hi");
+            session
+        };
+        
+        assert_eq!(
+            session(PromptTemplate::ChatML).render(),
+            "<|im_start|>system\nBe brief.<|im_end|>\n<|im_start|>user\nHi<|im_end|>\n<|im_start|>assistant\n"
+        );
+        assert_eq!(
+            session(PromptTemplate::Llama3).render(),
+            "<|start_header_id|>system<|end_header_id|>\n\nBe brief.<|eot_id|>\
+             <|start_header_id|>user<|end_header_id|>\n\nHi<|eot_id|>\
+             <|start_header_id|>assistant<|end_header_id|>\n\n"
+        );
+        assert_eq!(session(PromptTemplate::Mistral).render(), "[INST] Be brief.\n\nHi [/INST]");
+        assert_eq!(
+            session(PromptTemplate::Gemma).render(),
+            "<start_of_turn>user\nBe brief.\n\nHi<end_of_turn>\n<start_of_turn>model\n"
+        );
+        assert_eq!(session(PromptTemplate::Raw).render(), "System: Be brief.\n\nUser: Hi\nAssistant:");
+        
+        // No template set renders as Raw
+        let mut plain = ChatSession::new();
+        plain.add_user_message("Hi");
+        assert_eq!(plain.render(), "User: Hi\nAssistant:");
+    }
+    
+    #[test]
+    fn test_prompt_template_detection() {
+        let llama3 = "{% for m in messages %}<|start_header_id|>{{ m.role }}<|end_header_id|>{% endfor %}";
+        assert_eq!(PromptTemplate::detect(Some(llama3), Some("llama"), None), PromptTemplate::Llama3);
+        assert_eq!(PromptTemplate::detect(Some("<|im_start|>"), None, None), PromptTemplate::ChatML);
+        assert_eq!(PromptTemplate::detect(None, Some("gemma2"), None), PromptTemplate::Gemma);
+        assert_eq!(PromptTemplate::detect(None, Some("llama"), Some("Mistral 7B Instruct")), PromptTemplate::Mistral);
+        assert_eq!(PromptTemplate::detect(None, Some("llama"), Some("TinyStories")), PromptTemplate::Raw);
+        assert_eq!(PromptTemplate::detect(None, None, None), PromptTemplate::Raw);
+    }
+    
     #[test]
     fn test_session_manager() {
         let mut manager = SessionManager::new();
@@ -0,0 +1,217 @@
+//! Saved KV cache state - persist an evaluated prompt prefix across restarts
+//!
+//! # On-disk format (version 1)
+//!
+//! All integers are little-endian.
+//!
+//! | Field         | Size          | Notes                                  |
+//! |---------------|---------------|----------------------------------------|
+//! | magic         | 4 bytes       | `b"WCKV"`                              |
+//! | version       | u32           | [`STATE_FORMAT_VERSION`]               |
+//! | model hash    | u64           | FNV-1a 64 of the GGUF file             |
+//! | context size  | u32           | `n_ctx` of the context that was saved  |
+//! | token count   | u32           | number of prefix tokens                |
+//! | tokens        | i32 × count   | prefix token IDs, in order             |
+//! | state length  | u64           | size of the llama.cpp state blob       |
+//! | state         | bytes         | `llama_state_get_data` output          |
+//!
+//! Readers reject any other magic or version. A format change must bump
+//! [`STATE_FORMAT_VERSION`] and keep reading older versions where possible.
+
+use crate::error::{LlmError, Result};
+
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// File magic for saved state
+pub const STATE_MAGIC: &[u8; 4] = b"WCKV";
+
+/// Current on-disk format version
+pub const STATE_FORMAT_VERSION: u32 = 1;
+
+/// Upper bound on a state blob read from disk (guards against corrupt lengths)
+const MAX_STATE_BYTES: u64 = 64 * 1024 * 1024 * 1024;
+
+/// An evaluated prompt prefix and the llama.cpp context state that holds it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct KvState {
+    /// Hash of the GGUF file the state was produced with
+    pub model_hash: u64,
+
+    /// Context size the state was captured from
+    pub n_ctx: u32,
+
+    /// Prefix tokens already evaluated into the KV cache
+    pub tokens: Vec<i32>,
+
+    /// Raw llama.cpp context state
+    pub data: Vec<u8>,
+}
+
+impl KvState {
+    /// Write the state in the current format
+    pub fn write_to(&self, writer: &mut impl Write) -> Result<()> {
+        writer.write_all(STATE_MAGIC)?;
+        writer.write_all(&STATE_FORMAT_VERSION.to_le_bytes())?;
+        writer.write_all(&self.model_hash.to_le_bytes())?;
+        writer.write_all(&self.n_ctx.to_le_bytes())?;
+        writer.write_all(&(self.tokens.len() as u32).to_le_bytes())?;
+        for token in &self.tokens {
+            writer.write_all(&token.to_le_bytes())?;
+        }
+        writer.write_all(&(self.data.len() as u64).to_le_bytes())?;
+        writer.write_all(&self.data)?;
+        Ok(())
+    }
+
+    /// Read a state, rejecting unknown magic or versions
+    pub fn read_from(reader: &mut impl Read) -> Result<Self> {
+        let mut magic = [0u8; 4];
+        reader.read_exact(&mut magic)?;
+        if &magic != STATE_MAGIC {
+            return Err(LlmError::InvalidState("not a saved state file".into()));
+        }
+
+        let version = read_u32(reader)?;
+        if version != STATE_FORMAT_VERSION {
+            return Err(LlmError::InvalidState(format!(
+                "unsupported format version {} (expected {})",
+                version, STATE_FORMAT_VERSION
+            )));
+        }
+
+        let model_hash = read_u64(reader)?;
+        let n_ctx = read_u32(reader)?;
+        let n_tokens = read_u32(reader)? as usize;
+        if n_tokens > n_ctx as usize {
+            return Err(LlmError::InvalidState(format!(
+                "{} tokens exceed context size {}",
+                n_tokens, n_ctx
+            )));
+        }
+
+        let mut tokens = Vec::with_capacity(n_tokens);
+        for _ in 0..n_tokens {
+            tokens.push(read_u32(reader)? as i32);
+        }
+
+        let len = read_u64(reader)?;
+        if len > MAX_STATE_BYTES {
+            return Err(LlmError::InvalidState(format!("state blob too large: {} bytes", len)));
+        }
+        let mut data = vec![0u8; len as usize];
+        reader.read_exact(&mut data)?;
+
+        Ok(Self {
+            model_hash,
+            n_ctx,
+            tokens,
+            data,
+        })
+    }
+
+    /// Save to a file
+    pub fn save(&self, path: &Path) -> Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        self.write_to(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Load from a file
+    pub fn load(path: &Path) -> Result<Self> {
+        let mut reader = BufReader::new(File::open(path)?);
+        Self::read_from(&mut reader)
+    }
+}
+
+/// FNV-1a 64 hash of a file's contents
+///
+/// Stable across Rust versions and platforms, unlike `DefaultHasher`.
+pub fn gguf_hash(path: &Path) -> Result<u64> {
+    let mut reader = BufReader::new(File::open(path)?);
+    let mut hash = FNV_OFFSET;
+    let mut buf = [0u8; 64 * 1024];
+    loop {
+        let n = reader.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hash = fnv1a(hash, &buf[..n]);
+    }
+    Ok(hash)
+}
+
+const FNV_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+fn fnv1a(mut hash: u64, bytes: &[u8]) -> u64 {
+    for &b in bytes {
+        hash ^= b as u64;
+        hash = hash.wrapping_mul(FNV_PRIME);
+    }
+    hash
+}
+
+pub(crate) fn read_u32(reader: &mut impl Read) -> Result<u32> {
+    let mut buf = [0u8; 4];
+    reader.read_exact(&mut buf)?;
+    Ok(u32::from_le_bytes(buf))
+}
+
+pub(crate) fn read_u64(reader: &mut impl Read) -> Result<u64> {
+    let mut buf = [0u8; 8];
+    reader.read_exact(&mut buf)?;
+    Ok(u64::from_le_bytes(buf))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample() -> KvState {
+        KvState {
+            model_hash: 0xdead_beef,
+            n_ctx: 4096,
+            tokens: vec![1, 15043, -1, 29991],
+            data: vec![0, 1, 2, 3, 255],
+        }
+    }
+
+    #[test]
+    fn test_state_roundtrip() {
+        let dir = tempfile::tempdir().unwrap();
+        let path = dir.path().join("prefix.kv");
+
+        sample().save(&path).unwrap();
+        assert_eq!(KvState::load(&path).unwrap(), sample());
+    }
+
+    #[test]
+    fn test_state_rejects_unknown_version() {
+        let mut bytes = Vec::new();
+        sample().write_to(&mut bytes).unwrap();
+        bytes[4..8].copy_from_slice(&(STATE_FORMAT_VERSION + 1).to_le_bytes());
+
+        let err = KvState::read_from(&mut bytes.as_slice()).unwrap_err();
+        assert!(matches!(err, LlmError::InvalidState(_)));
+
+        let err = KvState::read_from(&mut &b"GGUF...."[..]).unwrap_err();
+        assert!(matches!(err, LlmError::InvalidState(_)));
+    }
+
+    #[test]
+    fn test_gguf_hash_stable() {
+        let dir = tempfile::tempdir().unwrap();
+        let a = dir.path().join("a.gguf");
+        let b = dir.path().join("b.gguf");
+        std::fs::write(&a, b"GGUF model bytes").unwrap();
+        std::fs::write(&b, b"GGUF other bytes").unwrap();
+
+        assert_eq!(gguf_hash(&a).unwrap(), gguf_hash(&a).unwrap());
+        assert_ne!(gguf_hash(&a).unwrap(), gguf_hash(&b).unwrap());
+        // Empty input hashes to the FNV offset basis
+        assert_eq!(fnv1a(FNV_OFFSET, b""), FNV_OFFSET);
+    }
+}
@@ -4,6 +4,7 @@ use crate::error::{LlmError, Result};
 
 use serde::{Deserialize, Serialize};
 use tokio::sync::mpsc;
+use tokio_util::sync::CancellationToken;
 
 /// Events emitted during token streaming
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,6 +24,9 @@ pub enum StreamEvent {
         token_id: u32,
         /// Is this a special token
         is_special: bool,
+        /// Milliseconds since the stream started
+        #[serde(default)]
+        elapsed_ms: u64,
     },
     
     /// Generation progress update
@@ -33,6 +37,18 @@ pub enum StreamEvent {
         tokens_per_second: f32,
     },
     
+    /// Timing breakdown, sent once right before `Done` or `Cancelled`
+    Metrics {
+        /// Prompt tokens
+        prompt_tokens: usize,
+        /// Tokens generated
+        generated_tokens: usize,
+        /// Prompt evaluation (prefill) time in milliseconds
+        prefill_ms: u64,
+        /// Sampling and decoding time after prefill, in milliseconds
+        decode_ms: u64,
+    },
+    
     /// Generation completed
     Done {
         /// Full generated text
@@ -47,6 +63,18 @@ pub enum StreamEvent {
         stop_reason: StopReason,
     },
     
+    /// Generation cancelled before completion
+    Cancelled {
+        /// Text generated before cancellation
+        text: String,
+        /// Tokens generated before cancellation
+        tokens_generated: usize,
+        /// Prompt tokens
+        prompt_tokens: usize,
+        /// Time until cancellation in milliseconds
+        duration_ms: u64,
+    },
+    
     /// Error during generation
     Error {
         /// Error message
@@ -54,6 +82,32 @@ pub enum StreamEvent {
     },
 }
 
+impl StreamEvent {
+    /// Prompt tokens evaluated per second during prefill (`Metrics` only)
+    pub fn prefill_tokens_per_second(&self) -> Option<f32> {
+        match self {
+            Self::Metrics { prompt_tokens, prefill_ms, .. } => Some(per_second(*prompt_tokens, *prefill_ms)),
+            _ => None,
+        }
+    }
+    
+    /// Tokens generated per second after prefill (`Metrics` only)
+    pub fn decode_tokens_per_second(&self) -> Option<f32> {
+        match self {
+            Self::Metrics { generated_tokens, decode_ms, .. } => Some(per_second(*generated_tokens, *decode_ms)),
+            _ => None,
+        }
+    }
+}
+
+fn per_second(tokens: usize, ms: u64) -> f32 {
+    if ms == 0 {
+        0.0
+    } else {
+        tokens as f32 * 1000.0 / ms as f32
+    }
+}
+
 /// Reason for stopping generation
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
 #[serde(rename_all = "snake_case")]
@@ -70,6 +124,23 @@ pub enum StopReason {
     Error,
 }
 
+/// Text of a blocking generation and why it stopped
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Completion {
+    /// Generated text, partial when cancelled
+    pub text: String,
+    
+    /// Why generation stopped
+    pub stop_reason: StopReason,
+}
+
+impl Completion {
+    /// Whether generation was cancelled before it finished
+    pub fn is_cancelled(&self) -> bool {
+        self.stop_reason == StopReason::Cancelled
+    }
+}
+
 /// Token stream receiver
 pub struct TokenStream {
     receiver: mpsc::Receiver<StreamEvent>,
@@ -101,7 +172,10 @@ impl TokenStream {
         }
         
         // Mark done
-        if matches!(event, StreamEvent::Done { .. } | StreamEvent::Error { .. }) {
+        if matches!(
+            event,
+            StreamEvent::Done { .. } | StreamEvent::Cancelled { .. } | StreamEvent::Error { .. }
+        ) {
             self.is_done = true;
         }
         
@@ -109,10 +183,12 @@ impl TokenStream {
     }
     
     /// Collect all tokens into a string (blocks until done)
+    ///
+    /// A cancelled stream yields the partial text.
     pub async fn collect(mut self) -> Result<String> {
         while let Some(event) = self.next().await {
             match event {
-                StreamEvent::Done { text, .. } => return Ok(text),
+                StreamEvent::Done { text, .. } | StreamEvent::Cancelled { text, .. } => return Ok(text),
                 StreamEvent::Error { message } => return Err(LlmError::GenerationError(message)),
                 _ => continue,
             }
@@ -139,6 +215,10 @@ pub struct StreamSender {
     tokens_generated: usize,
     prompt_tokens: usize,
     start_time: std::time::Instant,
+    /// Prefill duration and when it ended, once recorded
+    prefill: Option<(std::time::Duration, std::time::Instant)>,
+    /// Token that stops generation besides dropping the stream
+    cancel: Option<CancellationToken>,
 }
 
 impl StreamSender {
@@ -151,6 +231,8 @@ impl StreamSender {
             tokens_generated: 0,
             prompt_tokens: 0,
             start_time: std::time::Instant::now(),
+            prefill: None,
+            cancel: None,
         };
         
         let stream = TokenStream::new(receiver);
@@ -176,6 +258,7 @@ impl StreamSender {
                 text,
                 token_id,
                 is_special,
+                elapsed_ms: self.elapsed_ms(),
             })
             .await
             .map_err(|_| LlmError::ChannelError("Failed to send token".into()))
@@ -218,6 +301,14 @@ impl StreamSender {
             .map_err(|_| LlmError::ChannelError("Failed to send done".into()))
     }
     
+    /// Send the timing breakdown
+    pub async fn send_metrics(&self) -> Result<()> {
+        self.sender
+            .send(self.metrics())
+            .await
+            .map_err(|_| LlmError::ChannelError("Failed to send metrics".into()))
+    }
+    
     /// Send error event
     pub async fn send_error(&self, message: String) -> Result<()> {
         self.sender
@@ -241,6 +332,7 @@ impl StreamSender {
             text,
             token_id,
             is_special,
+            elapsed_ms: self.elapsed_ms(),
         }).is_ok()
     }
     
@@ -255,15 +347,139 @@ impl StreamSender {
         });
     }
     
+    /// Send cancelled event with the partial text (blocking)
+    pub fn send_cancelled_blocking(&self, text: String) {
+        let _ = self.sender.blocking_send(StreamEvent::Cancelled {
+            text,
+            tokens_generated: self.tokens_generated,
+            prompt_tokens: self.prompt_tokens,
+            duration_ms: self.start_time.elapsed().as_millis() as u64,
+        });
+    }
+    
+    /// Send the timing breakdown (blocking)
+    pub fn send_metrics_blocking(&self) {
+        let _ = self.sender.blocking_send(self.metrics());
+    }
+    
     /// Send error (blocking)
     pub fn send_error_blocking(&self, message: String) {
         let _ = self.sender.blocking_send(StreamEvent::Error { message });
     }
     
+    /// Send text completed from already-counted tokens (blocking)
+    pub fn send_buffered_blocking(&self, text: String, token_id: u32) -> bool {
+        self.sender.blocking_send(StreamEvent::Token {
+            text,
+            token_id,
+            is_special: false,
+            elapsed_ms: self.elapsed_ms(),
+        }).is_ok()
+    }
+    
+    /// Count a decoded token whose bytes are still buffered (no event sent)
+    pub fn record_token(&mut self) {
+        self.tokens_generated += 1;
+    }
+    
+    /// Check whether the receiving `TokenStream` has been dropped
+    pub fn is_closed(&self) -> bool {
+        self.sender.is_closed()
+    }
+    
+    /// Also stop generation when `cancel` is triggered
+    pub fn with_cancel(mut self, cancel: Option<CancellationToken>) -> Self {
+        self.cancel = cancel;
+        self
+    }
+    
+    /// Whether generation should stop: the stream was dropped or cancelled
+    pub fn should_stop(&self) -> bool {
+        self.is_closed() || self.cancel.as_ref().is_some_and(|c| c.is_cancelled())
+    }
+    
     /// Get tokens generated so far
     pub fn tokens_generated(&self) -> usize {
         self.tokens_generated
     }
+    
+    /// Record that prompt evaluation took `duration` and has just finished
+    pub fn record_prefill(&mut self, duration: std::time::Duration) {
+        self.prefill = Some((duration, std::time::Instant::now()));
+    }
+    
+    fn elapsed_ms(&self) -> u64 {
+        self.start_time.elapsed().as_millis() as u64
+    }
+    
+    /// Timing so far; decode time runs from the end of prefill (or the start
+    /// of the stream when no prefill was recorded)
+    fn metrics(&self) -> StreamEvent {
+        let (prefill, decode_start) = self.prefill.unwrap_or((std::time::Duration::ZERO, self.start_time));
+        StreamEvent::Metrics {
+            prompt_tokens: self.prompt_tokens,
+            generated_tokens: self.tokens_generated,
+            prefill_ms: prefill.as_millis() as u64,
+            decode_ms: decode_start.elapsed().as_millis() as u64,
+        }
+    }
+}
+
+/// Reassembles token bytes into valid UTF-8 text
+///
+/// A single multi-byte codepoint can be split across several tokens, so the
+/// bytes of an incomplete trailing sequence are held back until the rest
+/// arrives instead of being emitted as invalid text.
+#[derive(Debug, Default)]
+pub struct Utf8Buffer {
+    pending: Vec<u8>,
+}
+
+impl Utf8Buffer {
+    /// Create an empty buffer
+    pub fn new() -> Self {
+        Self::default()
+    }
+    
+    /// Append token bytes and return any text that is now complete
+    pub fn push(&mut self, bytes: &[u8]) -> Option<String> {
+        self.pending.extend_from_slice(bytes);
+        
+        let valid_up_to = match std::str::from_utf8(&self.pending) {
+            Ok(_) => self.pending.len(),
+            // Bytes that can never become valid are replaced rather than held forever
+            Err(e) if e.error_len().is_some() => {
+                let text = String::from_utf8_lossy(&self.pending).into_owned();
+                self.pending.clear();
+                return Some(text);
+            }
+            Err(e) => e.valid_up_to(),
+        };
+        
+        if valid_up_to == 0 {
+            return None;
+        }
+        
+        let rest = self.pending.split_off(valid_up_to);
+        let complete = std::mem::replace(&mut self.pending, rest);
+        // Safe: validated above
+        String::from_utf8(complete).ok()
+    }
+    
+    /// Drain whatever is left, replacing an unfinished sequence
+    pub fn flush(&mut self) -> Option<String> {
+        if self.pending.is_empty() {
+            return None;
+        }
+        let text = String::from_utf8_lossy(&self.pending).into_owned();
+        self.pending.clear();
+        Some(text)
+    }
+    
+    /// Check if bytes are waiting for the rest of a codepoint
+    pub fn has_pending(&self) -> bool {
+        !self.pending.is_empty()
+    }
 }
 
 #[cfg(test)]
@@ -314,16 +530,127 @@ mod tests {
         assert_eq!(result, "Test");
     }
     
+    #[test]
+    fn test_utf8_buffer_split_codepoint() {
+        // "é" is 0xC3 0xA9, "🦀" is 0xF0 0x9F 0xA6 0x80
+        let mut buffer = Utf8Buffer::new();
+        
+        assert_eq!(buffer.push(b"caf\xC3"), Some("caf".to_string()));
+        assert!(buffer.has_pending());
+        assert_eq!(buffer.push(b"\xA9 "), Some("\u{e9} ".to_string()));
+        
+        assert_eq!(buffer.push(b"\xF0\x9F"), None);
+        assert_eq!(buffer.push(b"\xA6"), None);
+        assert_eq!(buffer.push(b"\x80!"), Some("\u{1F980}!".to_string()));
+        assert!(!buffer.has_pending());
+        assert_eq!(buffer.flush(), None);
+    }
+    
+    #[test]
+    fn test_utf8_buffer_flush_incomplete() {
+        let mut buffer = Utf8Buffer::new();
+        assert_eq!(buffer.push(b"\xE2\x82"), None);
+        assert_eq!(buffer.flush(), Some("\u{FFFD}".to_string()));
+        assert!(!buffer.has_pending());
+    }
+    
+    #[tokio::test]
+    async fn test_stream_cancelled_keeps_partial_text() {
+        let (mut sender, mut stream) = StreamSender::channel(10);
+        
+        tokio::task::spawn_blocking(move || {
+            sender.send_start_blocking(3);
+            sender.send_token_blocking("Once".into(), 1, false);
+            sender.send_token_blocking(" upon".into(), 2, false);
+            sender.send_cancelled_blocking("Once upon".into());
+        })
+        .await
+        .unwrap();
+        
+        assert!(matches!(stream.next().await, Some(StreamEvent::Start { .. })));
+        assert!(matches!(stream.next().await, Some(StreamEvent::Token { .. })));
+        assert!(matches!(stream.next().await, Some(StreamEvent::Token { .. })));
+        match stream.next().await {
+            Some(StreamEvent::Cancelled { text, tokens_generated, prompt_tokens, .. }) => {
+                assert_eq!(text, "Once upon");
+                assert_eq!(tokens_generated, 2);
+                assert_eq!(prompt_tokens, 3);
+            }
+            other => panic!("expected Cancelled, got {:?}", other),
+        }
+        assert!(stream.is_done());
+        assert!(stream.next().await.is_none());
+        assert_eq!(stream.current_text(), "Once upon");
+    }
+    
+    #[tokio::test]
+    async fn test_sender_detects_dropped_stream() {
+        let (sender, stream) = StreamSender::channel(4);
+        assert!(!sender.is_closed());
+        drop(stream);
+        assert!(sender.is_closed());
+    }
+    
     #[test]
     fn test_event_serialization() {
         let event = StreamEvent::Token {
             text: "hello".into(),
             token_id: 42,
             is_special: false,
+            elapsed_ms: 7,
         };
         
         let json = serde_json::to_string(&event).unwrap();
         assert!(json.contains("token"));
         assert!(json.contains("hello"));
+        assert!(json.contains("\"elapsed_ms\":7"));
+        
+        let metrics = StreamEvent::Metrics {
+            prompt_tokens: 100,
+            generated_tokens: 50,
+            prefill_ms: 200,
+            decode_ms: 2000,
+        };
+        assert_eq!(metrics.prefill_tokens_per_second(), Some(500.0));
+        assert_eq!(metrics.decode_tokens_per_second(), Some(25.0));
+        assert_eq!(event.decode_tokens_per_second(), None);
+    }
+    
+    #[tokio::test]
+    async fn test_metrics_before_done() {
+        let (mut sender, mut stream) = StreamSender::channel(10);
+        
+        tokio::task::spawn_blocking(move || {
+            sender.send_start_blocking(4);
+            sender.record_prefill(std::time::Duration::from_millis(30));
+            sender.send_token_blocking("Hi".into(), 1, false);
+            std::thread::sleep(std::time::Duration::from_millis(5));
+            sender.send_token_blocking("!".into(), 2, false);
+            sender.send_metrics_blocking();
+            sender.send_done_blocking("Hi!".into(), StopReason::EndOfGeneration);
+        })
+        .await
+        .unwrap();
+        
+        assert!(matches!(stream.next().await, Some(StreamEvent::Start { .. })));
+        let mut elapsed = Vec::new();
+        while let Some(StreamEvent::Token { elapsed_ms, .. }) = stream.next().await {
+            elapsed.push(elapsed_ms);
+            if elapsed.len() == 2 {
+                break;
+            }
+        }
+        assert!(elapsed[1] >= elapsed[0] + 5);
+        
+        match stream.next().await {
+            Some(StreamEvent::Metrics { prompt_tokens, generated_tokens, prefill_ms, decode_ms }) => {
+                assert_eq!(prompt_tokens, 4);
+                assert_eq!(generated_tokens, 2);
+                assert_eq!(prefill_ms, 30);
+                assert!(decode_ms >= 5);
+            }
+            other => panic!("expected Metrics, got {:?}", other),
+        }
+        assert!(matches!(stream.next().await, Some(StreamEvent::Done { .. })));
     }
 }
@@ -0,0 +1,258 @@
+//! Tool calling - let a model answer with text or with calls to declared tools
+//!
+//! The model is shown the tools and constrained (via [`tool_choice_schema`])
+//! to reply with one JSON object, either
+//!
+//! ```json
+//! {"type": "text", "content": "..."}
+//! ```
+//!
+//! or one or more calls, each matching its tool's parameter schema:
+//!
+//! ```json
+//! {"type": "tool_calls", "calls": [{"name": "get_weather", "arguments": {"city": "Paris"}}]}
+//! ```
+
+use crate::error::{LlmError, Result};
+use crate::json_schema::validate_json;
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+
+/// A tool the model may call
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ToolSpec {
+    /// Tool name, as the model must emit it
+    pub name: String,
+
+    /// What the tool does, shown to the model
+    #[serde(default)]
+    pub description: String,
+
+    /// JSON Schema of the call arguments (same subset as [`crate::json_schema`])
+    pub parameters: Value,
+}
+
+impl ToolSpec {
+    /// Create a tool with an arguments schema
+    pub fn new(name: impl Into<String>, parameters: Value) -> Self {
+        Self {
+            name: name.into(),
+            description: String::new(),
+            parameters,
+        }
+    }
+
+    /// Set the description shown to the model
+    pub fn with_description(mut self, description: impl Into<String>) -> Self {
+        self.description = description.into();
+        self
+    }
+}
+
+/// A tool call parsed from model output
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParsedToolCall {
+    /// Call ID, unique within one outcome (`call_0`, `call_1`, ...)
+    pub id: String,
+
+    /// Name of the called tool
+    pub name: String,
+
+    /// Arguments, valid against the tool's parameter schema
+    pub arguments: Value,
+}
+
+/// What the model chose to do
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "value", rename_all = "snake_case")]
+pub enum GenerationOutcome {
+    /// A plain text answer
+    Text(String),
+    /// One or more tool calls, in the order the model emitted them
+    ToolCalls(Vec<ParsedToolCall>),
+}
+
+/// Schema every tool-enabled reply must match
+///
+/// Fails with `ConfigError` when `tools` is empty or names a tool twice.
+pub fn tool_choice_schema(tools: &[ToolSpec]) -> Result<Value> {
+    if tools.is_empty() {
+        return Err(LlmError::ConfigError("at least one tool is required".into()));
+    }
+    let mut names = HashSet::new();
+    for tool in tools {
+        if tool.name.is_empty() {
+            return Err(LlmError::ConfigError("tool names must not be empty".into()));
+        }
+        if !names.insert(tool.name.as_str()) {
+            return Err(LlmError::ConfigError(format!("duplicate tool name: {}", tool.name)));
+        }
+    }
+
+    let calls: Vec<Value> = tools
+        .iter()
+        .map(|tool| {
+            json!({
+                "type": "object",
+                "properties": {
+                    "name": { "const": tool.name },
+                    "arguments": parameters_schema(tool),
+                },
+                "required": ["name", "arguments"]
+            })
+        })
+        .collect();
+
+    Ok(json!({
+        "anyOf": [
+            {
+                "type": "object",
+                "properties": {
+                    "type": { "const": "text" },
+                    "content": { "type": "string" }
+                },
+                "required": ["type", "content"]
+            },
+            {
+                "type": "object",
+                "properties": {
+                    "type": { "const": "tool_calls" },
+                    "calls": {
+                        "type": "array",
+                        "items": { "anyOf": calls },
+                        "minItems": 1
+                    }
+                },
+                "required": ["type", "calls"]
+            }
+        ]
+    }))
+}
+
+/// System prompt section describing the tools and the reply format
+pub fn tools_system_prompt(tools: &[ToolSpec]) -> String {
+    let mut prompt = String::from("You can call the following tools:\n");
+    for tool in tools {
+        prompt.push_str(&format!("\n- {}", tool.name));
+        if !tool.description.is_empty() {
+            prompt.push_str(&format!(": {}", tool.description));
+        }
+        prompt.push_str(&format!("\n  arguments schema: {}", parameters_schema(tool)));
+    }
+    prompt.push_str(
+        "\n\nReply with a single JSON object. To answer directly, use \
+         {\"type\": \"text\", \"content\": \"<answer>\"}. To call tools, use \
+         {\"type\": \"tool_calls\", \"calls\": [{\"name\": \"<tool>\", \"arguments\": {...}}]}, \
+         listing every call needed for this turn.",
+    );
+    prompt
+}
+
+/// Turn a reply matching [`tool_choice_schema`] into an outcome
+pub fn parse_outcome(value: &Value, tools: &[ToolSpec]) -> Result<GenerationOutcome> {
+    validate_json(value, &tool_choice_schema(tools)?)?;
+
+    if value["type"] == "text" {
+        let content = value["content"].as_str().unwrap_or_default();
+        return Ok(GenerationOutcome::Text(content.to_string()));
+    }
+
+    let calls = value["calls"]
+        .as_array()
+        .map(|calls| {
+            calls
+                .iter()
+                .enumerate()
+                .map(|(i, call)| ParsedToolCall {
+                    id: format!("call_{}", i),
+                    name: call["name"].as_str().unwrap_or_default().to_string(),
+                    arguments: call["arguments"].clone(),
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    Ok(GenerationOutcome::ToolCalls(calls))
+}
+
+/// A tool's arguments schema, defaulting to any object
+fn parameters_schema(tool: &ToolSpec) -> Value {
+    if tool.parameters.is_null() {
+        json!({ "type": "object" })
+    } else {
+        tool.parameters.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::json_schema::schema_to_grammar;
+
+    fn tools() -> Vec<ToolSpec> {
+        vec![
+            ToolSpec::new(
+                "get_weather",
+                json!({
+                    "type": "object",
+                    "properties": { "city": { "type": "string" } },
+                    "required": ["city"]
+                }),
+            )
+            .with_description("Current weather for a city"),
+            ToolSpec::new("get_time", Value::Null),
+        ]
+    }
+
+    #[test]
+    fn test_tool_schema_compiles_to_valid_grammar() {
+        let schema = tool_choice_schema(&tools()).unwrap();
+        let grammar = schema_to_grammar(&schema).unwrap();
+        assert!(grammar.contains(r#""\"get_weather\"""#));
+
+        let prompt = tools_system_prompt(&tools());
+        assert!(prompt.contains("- get_weather: Current weather for a city"));
+        assert!(prompt.contains("- get_time\n  arguments schema: {\"type\":\"object\"}"));
+    }
+
+    #[test]
+    fn test_tool_schema_rejects_bad_tool_lists() {
+        assert!(matches!(tool_choice_schema(&[]), Err(LlmError::ConfigError(_))));
+
+        let duplicated = vec![tools()[0].clone(), tools()[0].clone()];
+        assert!(matches!(tool_choice_schema(&duplicated), Err(LlmError::ConfigError(_))));
+    }
+
+    #[test]
+    fn test_parse_outcome() {
+        let text = json!({ "type": "text", "content": "It is sunny." });
+        assert_eq!(
+            parse_outcome(&text, &tools()).unwrap(),
+            GenerationOutcome::Text("It is sunny.".into())
+        );
+
+        let calls = json!({
+            "type": "tool_calls",
+            "calls": [
+                { "name": "get_weather", "arguments": { "city": "Paris" } },
+                { "name": "get_time", "arguments": {} }
+            ]
+        });
+        let GenerationOutcome::ToolCalls(calls) = parse_outcome(&calls, &tools()).unwrap() else {
+            panic!("expected tool calls");
+        };
+        assert_eq!(calls.len(), 2);
+        assert_eq!(calls[0].id, "call_0");
+        assert_eq!(calls[0].name, "get_weather");
+        assert_eq!(calls[0].arguments["city"], "Paris");
+        assert_eq!(calls[1].id, "call_1");
+        assert_eq!(calls[1].name, "get_time");
+
+        // Unknown tools and arguments off-schema are rejected
+        let unknown = json!({ "type": "tool_calls", "calls": [{ "name": "rm", "arguments": {} }] });
+        assert!(matches!(parse_outcome(&unknown, &tools()), Err(LlmError::SchemaValidation(_))));
+        let bad_args = json!({ "type": "tool_calls", "calls": [{ "name": "get_weather", "arguments": {} }] });
+        assert!(parse_outcome(&bad_args, &tools()).is_err());
+    }
+}
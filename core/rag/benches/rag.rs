@@ -0,0 +1,67 @@
+//! Benchmarks for embedding and vector search.
+//!
+//! Run with `cargo bench` from `core/rag`. The first run downloads the
+//! embedding model.
+
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion};
+use tokio::runtime::Runtime;
+use whytcard_rag::{Document, Embedder, RagEngineBuilder};
+
+/// Small fixture corpus: one paragraph per document.
+const CORPUS: &str = include_str!("fixtures/corpus.md");
+
+const QUERIES: &[&str] = &[
+    "how does async scheduling work",
+    "graph traversal shortest path",
+    "vector similarity for sentences",
+];
+
+fn paragraphs() -> Vec<String> {
+    CORPUS
+        .split("\n\n")
+        .map(str::trim)
+        .filter(|p| !p.is_empty())
+        .map(String::from)
+        .collect()
+}
+
+fn bench_embed_batch(c: &mut Criterion) {
+    let mut embedder = Embedder::new().expect("embedder");
+    let texts = paragraphs();
+
+    let mut group = c.benchmark_group("embedder");
+    for size in [1, 4, texts.len()] {
+        let batch: Vec<String> = texts.iter().take(size).cloned().collect();
+        group.bench_with_input(BenchmarkId::new("embed_texts", size), &batch, |b, batch| {
+            b.iter(|| embedder.embed_texts(batch.clone()).expect("embed"))
+        });
+    }
+    group.finish();
+}
+
+fn bench_search(c: &mut Criterion) {
+    let rt = Runtime::new().expect("runtime");
+
+    let mut engine = rt.block_on(async {
+        let mut engine = RagEngineBuilder::new()
+            .db_path(":memory:")
+            .min_chunk_size(10)
+            .build()
+            .await
+            .expect("engine");
+        let docs: Vec<Document> = paragraphs().into_iter().map(Document::new).collect();
+        engine.index_many(&docs).await.expect("index");
+        engine
+    });
+
+    let mut group = c.benchmark_group("rag_engine");
+    for query in QUERIES {
+        group.bench_with_input(BenchmarkId::new("search", query), query, |b, query| {
+            b.iter(|| rt.block_on(engine.search(query, Some(5))).expect("search"))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, bench_embed_batch, bench_search);
+criterion_main!(benches);
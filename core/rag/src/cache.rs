@@ -0,0 +1,551 @@
+//! Embedding cache.
+//!
+//! Re-indexing a corpus mostly re-embeds text that has not changed. The
+//! cache keys embeddings on `(model id, sha256(text))`, so identical chunks
+//! skip the embedding backend and switching models never returns vectors of
+//! the old one. Entries live in an in-memory LRU; with a path configured they
+//! are also appended to a JSON lines file and reloaded on startup.
+//!
+//! The file is compacted to the LRU's entries, least recently used first,
+//! when it grows past twice the capacity and when the cache is dropped, so
+//! it stays bounded and a restart keeps the entries that were in use.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::config::EmbeddingCacheConfig;
+use crate::embedder::{EmbeddingBackend, TokenCounter};
+use crate::error::{RagError, Result};
+
+/// Hit/miss counters of an [`EmbeddingCache`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct EmbeddingCacheStats {
+    /// Lookups answered from the cache
+    pub hits: u64,
+    /// Lookups that went to the embedding backend
+    pub misses: u64,
+    /// Embeddings currently held in memory
+    pub entries: usize,
+    /// Maximum number of embeddings held in memory
+    pub capacity: usize,
+}
+
+/// Cache key: model id and SHA-256 of the text.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+struct CacheKey {
+    model: String,
+    hash: [u8; 32],
+}
+
+impl CacheKey {
+    fn new(model: &str, text: &str) -> Self {
+        Self {
+            model: model.to_string(),
+            hash: Sha256::digest(text.as_bytes()).into(),
+        }
+    }
+}
+
+/// One line of the cache file.
+#[derive(Serialize, Deserialize)]
+struct CacheRecord {
+    model: String,
+    hash: String,
+    embedding: Vec<f32>,
+}
+
+impl CacheRecord {
+    fn new(key: &CacheKey, embedding: &[f32]) -> Self {
+        Self {
+            model: key.model.clone(),
+            hash: to_hex(&key.hash),
+            embedding: embedding.to_vec(),
+        }
+    }
+
+    fn into_entry(self) -> Option<(CacheKey, Vec<f32>)> {
+        let hash = from_hex(&self.hash)?;
+        Some((CacheKey { model: self.model, hash }, self.embedding))
+    }
+}
+
+/// Least recently used map of embeddings.
+///
+/// `order` maps a use counter to its key, so the oldest entry is the first one.
+#[derive(Default)]
+struct Lru {
+    entries: HashMap<CacheKey, (Vec<f32>, u64)>,
+    order: BTreeMap<u64, CacheKey>,
+    tick: u64,
+}
+
+impl Lru {
+    fn get(&mut self, key: &CacheKey) -> Option<Vec<f32>> {
+        self.tick += 1;
+        let (embedding, used) = self.entries.get_mut(key)?;
+        self.order.remove(used);
+        *used = self.tick;
+        self.order.insert(self.tick, key.clone());
+        Some(embedding.clone())
+    }
+
+    fn insert(&mut self, key: CacheKey, embedding: Vec<f32>, capacity: usize) {
+        if capacity == 0 {
+            return;
+        }
+        self.tick += 1;
+        if let Some((_, used)) = self.entries.get(&key) {
+            self.order.remove(used);
+        } else if self.entries.len() >= capacity {
+            if let Some((_, oldest)) = self.order.pop_first() {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.order.insert(self.tick, key.clone());
+        self.entries.insert(key, (embedding, self.tick));
+    }
+
+    fn clear(&mut self) {
+        self.entries.clear();
+        self.order.clear();
+    }
+}
+
+/// Append handle on the cache file.
+struct CacheFile {
+    writer: BufWriter<File>,
+    /// Records in the file, including superseded and evicted ones
+    records: usize,
+}
+
+impl CacheFile {
+    fn append(path: &Path, records: usize) -> Result<Self> {
+        let file = OpenOptions::new().create(true).append(true).open(path)?;
+        Ok(Self {
+            writer: BufWriter::new(file),
+            records,
+        })
+    }
+}
+
+/// Embeddings keyed on `(model id, sha256(text))`.
+///
+/// Shared by the engine (for stats and clearing) and the [`CachedBackend`]
+/// that fills it.
+pub(crate) struct EmbeddingCache {
+    lru: Mutex<Lru>,
+    capacity: usize,
+    file: Option<Mutex<CacheFile>>,
+    path: Option<PathBuf>,
+    /// Whether the LRU order changed since the file was last compacted
+    touched: AtomicBool,
+    hits: AtomicU64,
+    misses: AtomicU64,
+}
+
+impl EmbeddingCache {
+    /// Open a cache, loading the file backend when configured.
+    ///
+    /// The file is an append log; it is rewritten on open when it holds
+    /// entries the in-memory capacity no longer keeps. Unreadable lines (e.g.
+    /// a write cut short by a crash) are skipped.
+    pub(crate) fn open(config: &EmbeddingCacheConfig) -> Result<Self> {
+        let mut lru = Lru::default();
+        let path = config.path.as_ref().map(PathBuf::from);
+
+        let file = match &path {
+            Some(path) => {
+                let mut records = load_records(path, &mut lru, config.capacity)?;
+                if records > lru.entries.len() {
+                    write_records(path, &lru)?;
+                    records = lru.entries.len();
+                }
+                Some(Mutex::new(CacheFile::append(path, records)?))
+            }
+            None => None,
+        };
+
+        Ok(Self {
+            lru: Mutex::new(lru),
+            capacity: config.capacity,
+            file,
+            path,
+            touched: AtomicBool::new(false),
+            hits: AtomicU64::new(0),
+            misses: AtomicU64::new(0),
+        })
+    }
+
+    fn get(&self, key: &CacheKey) -> Result<Option<Vec<f32>>> {
+        let embedding = self.lock_lru()?.get(key);
+        let counter = if embedding.is_some() {
+            self.touched.store(true, Ordering::Relaxed);
+            &self.hits
+        } else {
+            &self.misses
+        };
+        counter.fetch_add(1, Ordering::Relaxed);
+        Ok(embedding)
+    }
+
+    fn insert(&self, entries: Vec<(CacheKey, Vec<f32>)>) -> Result<()> {
+        let (Some(file), Some(path)) = (&self.file, &self.path) else {
+            let mut lru = self.lock_lru()?;
+            for (key, embedding) in entries {
+                lru.insert(key, embedding, self.capacity);
+            }
+            return Ok(());
+        };
+
+        let mut file = self.lock_file(file)?;
+        for (key, embedding) in &entries {
+            serde_json::to_writer(&mut file.writer, &CacheRecord::new(key, embedding))?;
+            file.writer.write_all(b"\n")?;
+        }
+        file.writer.flush()?;
+        file.records += entries.len();
+
+        let mut lru = self.lock_lru()?;
+        for (key, embedding) in entries {
+            lru.insert(key, embedding, self.capacity);
+        }
+        self.touched.store(true, Ordering::Relaxed);
+
+        // Keep the file bounded: drop superseded and evicted records
+        if file.records > self.capacity.saturating_mul(2) {
+            write_records(path, &lru)?;
+            *file = CacheFile::append(path, lru.entries.len())?;
+            self.touched.store(false, Ordering::Relaxed);
+        }
+        Ok(())
+    }
+
+    /// Current counters.
+    pub(crate) fn stats(&self) -> Result<EmbeddingCacheStats> {
+        Ok(EmbeddingCacheStats {
+            hits: self.hits.load(Ordering::Relaxed),
+            misses: self.misses.load(Ordering::Relaxed),
+            entries: self.lock_lru()?.entries.len(),
+            capacity: self.capacity,
+        })
+    }
+
+    /// Drop every entry, in memory and on disk, and reset the counters.
+    pub(crate) fn clear(&self) -> Result<()> {
+        if let (Some(file), Some(path)) = (&self.file, &self.path) {
+            let mut file = self.lock_file(file)?;
+            file.writer.flush()?;
+            File::create(path)?;
+            *file = CacheFile::append(path, 0)?;
+        }
+
+        self.lock_lru()?.clear();
+        self.touched.store(false, Ordering::Relaxed);
+        self.hits.store(0, Ordering::Relaxed);
+        self.misses.store(0, Ordering::Relaxed);
+        Ok(())
+    }
+
+    fn lock_lru(&self) -> Result<std::sync::MutexGuard<'_, Lru>> {
+        self.lru.lock()
+            .map_err(|_| RagError::Embedding("Failed to lock embedding cache".to_string()))
+    }
+
+    fn lock_file<'a>(&self, file: &'a Mutex<CacheFile>) -> Result<std::sync::MutexGuard<'a, CacheFile>> {
+        file.lock()
+            .map_err(|_| RagError::Embedding("Failed to lock embedding cache file".to_string()))
+    }
+}
+
+impl Drop for EmbeddingCache {
+    /// Save the LRU order, so entries hit since the last compaction are the
+    /// last evicted after a restart.
+    fn drop(&mut self) {
+        let (Some(path), Ok(lru)) = (&self.path, self.lru.get_mut()) else {
+            return;
+        };
+        if !*self.touched.get_mut() {
+            return;
+        }
+        if let Err(e) = write_records(path, lru) {
+            tracing::warn!("Failed to save embedding cache {}: {e}", path.display());
+        }
+    }
+}
+
+/// Load the cache file into `lru`, returning the number of records read.
+fn load_records(path: &Path, lru: &mut Lru, capacity: usize) -> Result<usize> {
+    let file = match File::open(path) {
+        Ok(file) => file,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e.into()),
+    };
+
+    let mut records = 0;
+    for line in BufReader::new(file).lines() {
+        let line = line?;
+        let Some((key, embedding)) = serde_json::from_str::<CacheRecord>(&line)
+            .ok()
+            .and_then(CacheRecord::into_entry)
+        else {
+            tracing::warn!("Skipping unreadable embedding cache entry in {}", path.display());
+            continue;
+        };
+        records += 1;
+        lru.insert(key, embedding, capacity);
+    }
+    Ok(records)
+}
+
+/// Rewrite the cache file with the entries of `lru`, oldest first.
+fn write_records(path: &Path, lru: &Lru) -> Result<()> {
+    let mut file = BufWriter::new(File::create(path)?);
+    for key in lru.order.values() {
+        if let Some((embedding, _)) = lru.entries.get(key) {
+            serde_json::to_writer(&mut file, &CacheRecord::new(key, embedding))?;
+            file.write_all(b"\n")?;
+        }
+    }
+    file.flush()?;
+    Ok(())
+}
+
+/// Embedding backend answering from an [`EmbeddingCache`] before calling
+/// the wrapped backend.
+///
+/// Queries are cached apart from documents, since a backend may embed them
+/// differently.
+pub(crate) struct CachedBackend {
+    inner: Arc<Mutex<Box<dyn EmbeddingBackend>>>,
+    cache: Arc<EmbeddingCache>,
+    model: String,
+    query_model: String,
+}
+
+impl CachedBackend {
+    pub(crate) fn new(
+        inner: Arc<Mutex<Box<dyn EmbeddingBackend>>>,
+        cache: Arc<EmbeddingCache>,
+        model: &str,
+    ) -> Self {
+        Self {
+            inner,
+            cache,
+            model: model.to_string(),
+            query_model: format!("{model}#query"),
+        }
+    }
+
+    fn lock_inner(&self) -> Result<std::sync::MutexGuard<'_, Box<dyn EmbeddingBackend>>> {
+        self.inner.lock()
+            .map_err(|_| RagError::Embedding("Failed to lock embedder".to_string()))
+    }
+}
+
+impl EmbeddingBackend for CachedBackend {
+    fn dimension(&self) -> usize {
+        self.lock_inner().map(|inner| inner.dimension()).unwrap_or_default()
+    }
+
+    fn token_counter(&self) -> Option<TokenCounter> {
+        self.lock_inner().ok()?.token_counter()
+    }
+
+    fn embed_texts(&mut self, texts: Vec<String>) -> Result<Vec<Vec<f32>>> {
+        let keys: Vec<CacheKey> = texts.iter().map(|text| CacheKey::new(&self.model, text)).collect();
+        let mut embeddings = keys
+            .iter()
+            .map(|key| self.cache.get(key))
+            .collect::<Result<Vec<_>>>()?;
+
+        // Embed each missing text once, even when it repeats in the batch
+        let mut missing: HashMap<&CacheKey, usize> = HashMap::new();
+        let mut to_embed = Vec::new();
+        for (i, key) in keys.iter().enumerate() {
+            if embeddings[i].is_none() && !missing.contains_key(key) {
+                missing.insert(key, to_embed.len());
+                to_embed.push(i);
+            }
+        }
+        if to_embed.is_empty() {
+            return Ok(embeddings.into_iter().flatten().collect());
+        }
+
+        let fresh = self
+            .lock_inner()?
+            .embed_texts(to_embed.iter().map(|&i| texts[i].clone()).collect())?;
+        if fresh.len() != to_embed.len() {
+            return Err(RagError::Embedding(format!(
+                "Backend returned {} embeddings for {} texts",
+                fresh.len(),
+                to_embed.len()
+            )));
+        }
+
+        for (i, slot) in embeddings.iter_mut().enumerate() {
+            if slot.is_none() {
+                *slot = Some(fresh[missing[&keys[i]]].clone());
+            }
+        }
+        self.cache.insert(
+            to_embed
+                .iter()
+                .map(|&i| keys[i].clone())
+                .zip(fresh)
+                .collect(),
+        )?;
+        Ok(embeddings.into_iter().flatten().collect())
+    }
+
+    fn embed_query(&mut self, query: &str) -> Result<Vec<f32>> {
+        let key = CacheKey::new(&self.query_model, query);
+        if let Some(embedding) = self.cache.get(&key)? {
+            return Ok(embedding);
+        }
+
+        let embedding = self.lock_inner()?.embed_query(query)?;
+        self.cache.insert(vec![(key, embedding.clone())])?;
+        Ok(embedding)
+    }
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn from_hex(hex: &str) -> Option<[u8; 32]> {
+    if hex.len() != 64 || !hex.is_ascii() {
+        return None;
+    }
+    let mut bytes = [0u8; 32];
+    for (i, byte) in bytes.iter_mut().enumerate() {
+        *byte = u8::from_str_radix(&hex[2 * i..2 * i + 2], 16).ok()?;
+    }
+    Some(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Counts the texts it is asked to embed.
+    struct CountingBackend(Arc<AtomicU64>);
+
+    impl EmbeddingBackend for CountingBackend {
+        fn dimension(&self) -> usize {
+            1
+        }
+
+        fn embed_texts(&mut self, texts: Vec<String>) -> Result<Vec<Vec<f32>>> {
+            self.0.fetch_add(texts.len() as u64, Ordering::Relaxed);
+            Ok(texts.iter().map(|t| vec![t.len() as f32]).collect())
+        }
+    }
+
+    fn cached(config: &EmbeddingCacheConfig, model: &str) -> (CachedBackend, Arc<EmbeddingCache>, Arc<AtomicU64>) {
+        let calls = Arc::new(AtomicU64::new(0));
+        let inner: Box<dyn EmbeddingBackend> = Box::new(CountingBackend(Arc::clone(&calls)));
+        let cache = Arc::new(EmbeddingCache::open(config).unwrap());
+        let backend = CachedBackend::new(Arc::new(Mutex::new(inner)), Arc::clone(&cache), model);
+        (backend, cache, calls)
+    }
+
+    fn texts(texts: &[&str]) -> Vec<String> {
+        texts.iter().map(|t| t.to_string()).collect()
+    }
+
+    #[test]
+    fn test_cache_hits_skip_backend() {
+        let (mut backend, cache, calls) = cached(&EmbeddingCacheConfig::default(), "m");
+
+        let first = backend.embed_texts(texts(&["a", "bb", "a"])).unwrap();
+        assert_eq!(first, vec![vec![1.0], vec![2.0], vec![1.0]]);
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+
+        let second = backend.embed_texts(texts(&["bb", "ccc"])).unwrap();
+        assert_eq!(second, vec![vec![2.0], vec![3.0]]);
+        assert_eq!(calls.load(Ordering::Relaxed), 3);
+
+        let stats = cache.stats().unwrap();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (1, 4, 3));
+
+        cache.clear().unwrap();
+        assert_eq!(cache.stats().unwrap(), EmbeddingCacheStats { capacity: stats.capacity, ..Default::default() });
+    }
+
+    #[test]
+    fn test_cache_keys_on_model_and_evicts_lru() {
+        let config = EmbeddingCacheConfig { capacity: 2, path: None };
+        let (mut backend, cache, calls) = cached(&config, "m1");
+        backend.embed_texts(texts(&["a", "b"])).unwrap();
+        backend.embed_texts(texts(&["a"])).unwrap();
+        backend.embed_texts(texts(&["c"])).unwrap();
+        assert_eq!(calls.load(Ordering::Relaxed), 3);
+
+        // "b" was least recently used
+        backend.embed_texts(texts(&["a", "b"])).unwrap();
+        assert_eq!(calls.load(Ordering::Relaxed), 4);
+
+        let mut other = CachedBackend::new(Arc::clone(&backend.inner), Arc::clone(&cache), "m2");
+        other.embed_texts(texts(&["a"])).unwrap();
+        assert_eq!(calls.load(Ordering::Relaxed), 5);
+    }
+
+    #[test]
+    fn test_cache_file_survives_reopen() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("embeddings.jsonl");
+        let config = EmbeddingCacheConfig {
+            capacity: 10,
+            path: Some(path.to_string_lossy().to_string()),
+        };
+
+        let (mut backend, _, _) = cached(&config, "m");
+        backend.embed_texts(texts(&["a", "bb"])).unwrap();
+        drop(backend);
+
+        let (mut backend, cache, calls) = cached(&config, "m");
+        assert_eq!(backend.embed_texts(texts(&["bb"])).unwrap(), vec![vec![2.0]]);
+        assert_eq!(calls.load(Ordering::Relaxed), 0);
+        assert_eq!(cache.stats().unwrap().entries, 2);
+
+        cache.clear().unwrap();
+        let (_, reopened, _) = cached(&config, "m");
+        assert_eq!(reopened.stats().unwrap().entries, 0);
+    }
+
+    #[test]
+    fn test_cache_file_stays_bounded_and_keeps_recency() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let path = temp_dir.path().join("embeddings.jsonl");
+        let config = EmbeddingCacheConfig {
+            capacity: 2,
+            path: Some(path.to_string_lossy().to_string()),
+        };
+        let lines = || std::fs::read_to_string(&path).unwrap().lines().count();
+
+        let (mut backend, _, _) = cached(&config, "m");
+        for text in ["a", "bb", "ccc", "dddd", "eeeee"] {
+            backend.embed_texts(texts(&[text])).unwrap();
+            assert!(lines() <= 4, "{} lines", lines());
+        }
+        // "dddd" becomes the most recently used
+        backend.embed_texts(texts(&["dddd"])).unwrap();
+        drop(backend);
+
+        let (mut backend, _, calls) = cached(&config, "m");
+        backend.embed_texts(texts(&["f"])).unwrap();
+        // "eeeee" was evicted for "f", "dddd" kept
+        backend.embed_texts(texts(&["dddd"])).unwrap();
+        assert_eq!(calls.load(Ordering::Relaxed), 1);
+        backend.embed_texts(texts(&["eeeee"])).unwrap();
+        assert_eq!(calls.load(Ordering::Relaxed), 2);
+    }
+}
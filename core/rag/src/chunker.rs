@@ -4,11 +4,38 @@
 //! - Sentence boundaries
 //! - Paragraph boundaries
 //! - Code block boundaries
+//! - Markdown sections (headings and fenced code blocks)
 //! - UTF-8 character boundaries (safe for multi-byte characters)
 
-use crate::config::ChunkingConfig;
+use crate::config::{ChunkingConfig, SizeUnit};
+use crate::embedder::TokenCounter;
 use crate::error::Result;
-use crate::types::{Chunk, Document};
+use crate::types::{estimate_tokens, Chunk, Document};
+use serde_json::Value;
+
+/// Metadata key holding the heading path of a Markdown chunk
+pub const HEADING_PATH_KEY: &str = "heading_path";
+
+/// Metadata key holding the symbol (function, type...) a code chunk defines
+pub const SYMBOL_KEY: &str = "symbol";
+
+/// Metadata key holding the (1-based) PDF page a chunk starts on
+pub const PAGE_KEY: &str = "page";
+
+/// Document metadata key holding the byte offset where each PDF page starts
+pub const PAGE_OFFSETS_KEY: &str = "page_offsets";
+
+/// Metadata keys describing a chunk itself rather than its document
+pub(crate) const CHUNK_FIELDS: [&str; 3] = [HEADING_PATH_KEY, SYMBOL_KEY, PAGE_KEY];
+
+/// Separator between headings in a heading path (`"Install > Linux"`)
+const HEADING_PATH_SEPARATOR: &str = " > ";
+
+/// Raw chunk text with its byte range in the source
+type Span = (String, usize, usize);
+
+/// Metadata field attached to a raw chunk, as (key, value)
+type Field = (&'static str, String);
 
 /// Find a valid UTF-8 character boundary at or before the given byte index.
 /// This ensures we never slice in the middle of a multi-byte character.
@@ -25,21 +52,37 @@ fn find_char_boundary(s: &str, byte_index: usize) -> usize {
 }
 
 /// Chunking strategy.
-#[derive(Debug, Clone, Copy, Default)]
+#[derive(Debug, Clone, Default)]
 pub enum ChunkingStrategy {
     /// Split on sentences/paragraphs
     #[default]
     Semantic,
     /// Fixed size chunks
     FixedSize,
-    /// Split on code boundaries (functions, classes)
-    Code,
+    /// Split source code into whole items (functions, types, impl blocks),
+    /// recording each item's name under [`SYMBOL_KEY`].
+    ///
+    /// Brace languages are split by brace balance, Python by indentation.
+    /// Containers larger than `chunk_size` (impl blocks, classes, modules)
+    /// are split into their members (`Type::method`); other items are kept
+    /// whole. Falls back to [`ChunkingStrategy::Semantic`] when braces don't
+    /// balance.
+    Code {
+        /// Source language (`"rust"`, `"python"`...), guessed when unset
+        language: Option<String>,
+    },
+    /// Split Markdown on headings, keeping fenced code blocks intact.
+    ///
+    /// Each chunk records its heading path under [`HEADING_PATH_KEY`].
+    /// Sections larger than `chunk_size` are split at paragraph boundaries.
+    Markdown,
 }
 
 /// Text chunker.
 pub struct Chunker {
     config: ChunkingConfig,
     strategy: ChunkingStrategy,
+    token_counter: Option<TokenCounter>,
 }
 
 impl Chunker {
@@ -48,6 +91,7 @@ impl Chunker {
         Self {
             config: ChunkingConfig::default(),
             strategy: ChunkingStrategy::default(),
+            token_counter: None,
         }
     }
 
@@ -56,6 +100,7 @@ impl Chunker {
         Self {
             config,
             strategy: ChunkingStrategy::default(),
+            token_counter: None,
         }
     }
 
@@ -65,6 +110,19 @@ impl Chunker {
         self
     }
 
+    /// Get the chunking strategy.
+    pub fn strategy(&self) -> &ChunkingStrategy {
+        &self.strategy
+    }
+
+    /// Count tokens with `counter` when sizes are in [`SizeUnit::Tokens`].
+    ///
+    /// Without one, tokens are estimated at four bytes each.
+    pub fn with_token_counter(mut self, counter: TokenCounter) -> Self {
+        self.token_counter = Some(counter);
+        self
+    }
+
     /// Chunk a document into smaller pieces.
     pub fn chunk(&self, document: &Document) -> Result<Vec<Chunk>> {
         let text = &document.content;
@@ -73,20 +131,32 @@ impl Chunker {
             return Ok(vec![]);
         }
 
-        let chunks = match self.strategy {
-            ChunkingStrategy::Semantic => self.chunk_semantic(text),
-            ChunkingStrategy::FixedSize => self.chunk_fixed(text),
-            ChunkingStrategy::Code => self.chunk_code(text),
+        let chunks: Vec<(Span, Option<Field>)> = match &self.strategy {
+            ChunkingStrategy::Semantic => without_fields(self.chunk_semantic(text)),
+            ChunkingStrategy::FixedSize => match self.config.size_unit {
+                SizeUnit::Chars => without_fields(self.chunk_fixed(text)),
+                SizeUnit::Tokens => without_fields(self.chunk_fixed_tokens(text)),
+            },
+            ChunkingStrategy::Code { language } => self.chunk_code(text, language.as_deref()),
+            ChunkingStrategy::Markdown => self.chunk_markdown(text),
         };
 
-        // Convert raw chunks to Chunk structs
+        // Convert raw chunks to Chunk structs. Markdown sections and named
+        // code items stand on their own, so they are kept even when short.
+        let markdown = matches!(self.strategy, ChunkingStrategy::Markdown);
         let result: Vec<Chunk> = chunks
             .into_iter()
             .enumerate()
-            .filter(|(_, (text, _, _))| text.len() >= self.config.min_chunk_size)
-            .map(|(index, (text, start, end))| {
+            .filter(|(_, ((text, _, _), field))| {
+                markdown || field.is_some() || self.size(text) >= self.config.min_chunk_size
+            })
+            .map(|(index, ((text, start, end), field))| {
                 let mut chunk = Chunk::new(&document.id, index, text, start, end);
-                chunk.metadata = document.metadata.clone();
+                chunk.metadata = match field {
+                    Some((key, value)) => with_field(document.metadata.clone(), key, value),
+                    None => document.metadata.clone(),
+                };
+                chunk.metadata = with_page(chunk.metadata, start);
                 chunk
             })
             .collect();
@@ -110,9 +180,7 @@ impl Chunker {
             }
 
             // If adding this paragraph exceeds chunk size, save current and start new
-            if !current_chunk.is_empty()
-                && current_chunk.len() + paragraph.len() > self.config.chunk_size
-            {
+            if !current_chunk.is_empty() && self.exceeds(&current_chunk, "\n\n", paragraph) {
                 chunks.push((
                     current_chunk.clone(),
                     current_start,
@@ -120,10 +188,7 @@ impl Chunker {
                 ));
 
                 // Start new chunk with overlap (safe UTF-8 boundary)
-                let target_overlap = current_chunk
-                    .len()
-                    .saturating_sub(self.config.chunk_overlap);
-                let overlap_start = find_char_boundary(&current_chunk, target_overlap);
+                let overlap_start = self.overlap_start(&current_chunk);
                 let overlap_text = &current_chunk[overlap_start..];
                 current_start = byte_pos.saturating_sub(overlap_text.len());
                 current_chunk = overlap_text.to_string();
@@ -138,7 +203,7 @@ impl Chunker {
             byte_pos += paragraph.len() + 2; // +2 for \n\n (bytes)
 
             // If single paragraph is too large, split by sentences
-            if current_chunk.len() > self.config.chunk_size {
+            if self.size(&current_chunk) > self.config.chunk_size {
                 let sentence_chunks = self.split_by_sentences(&current_chunk, current_start);
                 chunks.extend(sentence_chunks);
                 current_chunk.clear();
@@ -167,9 +232,7 @@ impl Chunker {
 
         // Simple sentence splitting on . ! ?
         for sentence in split_sentences(text) {
-            if !current_chunk.is_empty()
-                && current_chunk.len() + sentence.len() > self.config.chunk_size
-            {
+            if !current_chunk.is_empty() && self.exceeds(&current_chunk, " ", &sentence) {
                 chunks.push((
                     current_chunk.clone(),
                     current_start,
@@ -177,10 +240,7 @@ impl Chunker {
                 ));
 
                 // Overlap (safe UTF-8 boundary)
-                let target_overlap = current_chunk
-                    .len()
-                    .saturating_sub(self.config.chunk_overlap);
-                let overlap_start = find_char_boundary(&current_chunk, target_overlap);
+                let overlap_start = self.overlap_start(&current_chunk);
                 let overlap_text = &current_chunk[overlap_start..];
                 current_start = base_offset + byte_offset.saturating_sub(overlap_text.len());
                 current_chunk = overlap_text.to_string();
@@ -226,75 +286,836 @@ impl Chunker {
         chunks
     }
 
-    /// Code-aware chunking (split on function/class boundaries).
-    fn chunk_code(&self, text: &str) -> Vec<(String, usize, usize)> {
+    /// Fixed size chunking in tokens: the longest runs of characters that
+    /// fit `chunk_size` tokens.
+    fn chunk_fixed_tokens(&self, text: &str) -> Vec<Span> {
+        let bounds: Vec<usize> = text.char_indices().map(|(i, _)| i).chain([text.len()]).collect();
+        let last = bounds.len() - 1;
         let mut chunks = Vec::new();
-        let mut current_chunk = String::new();
-        let mut current_start = 0;
-        let mut char_pos = 0;
-
-        // Split on common code boundaries
-        let code_boundaries = [
-            "\nfn ",
-            "\npub fn ",
-            "\nasync fn ",
-            "\npub async fn ",
-            "\nimpl ",
-            "\nstruct ",
-            "\nenum ",
-            "\ntrait ",
-            "\nclass ",
-            "\ndef ",
-            "\nasync def ",
-            "\nfunction ",
-            "\nconst ",
-            "\nexport ",
-        ];
-
-        let lines: Vec<&str> = text.lines().collect();
-
-        for line in lines.iter() {
-            let is_boundary = code_boundaries
-                .iter()
-                .any(|b| line.starts_with(b.trim_start()));
-
-            if is_boundary && !current_chunk.is_empty() {
-                // Save current chunk
-                chunks.push((
-                    current_chunk.clone(),
-                    current_start,
-                    current_start + current_chunk.len(),
-                ));
-                current_chunk.clear();
-                current_start = char_pos;
+        let mut start = 0;
+
+        while start < last {
+            let from = bounds[start];
+            let fit = bounds[start + 1..]
+                .partition_point(|&to| self.size(&text[from..to]) <= self.config.chunk_size);
+            // At least one character, even if it alone is too large
+            let end = start + fit.max(1);
+            let to = bounds[end];
+            chunks.push((text[from..to].to_string(), from, to));
+            if end == last {
+                break;
             }
 
-            current_chunk.push_str(line);
-            current_chunk.push('\n');
-            char_pos += line.len() + 1;
+            let overlap = from + self.overlap_start(&text[from..to]);
+            start = bounds.partition_point(|&b| b < overlap).clamp(start + 1, end);
+        }
 
-            // If chunk is too big, force split
-            if current_chunk.len() > self.config.chunk_size * 2 {
-                chunks.push((
-                    current_chunk.clone(),
-                    current_start,
-                    current_start + current_chunk.len(),
-                ));
-                current_chunk.clear();
-                current_start = char_pos;
+        chunks
+    }
+
+    /// Size of `text` in the configured unit.
+    fn size(&self, text: &str) -> usize {
+        match (self.config.size_unit, &self.token_counter) {
+            (SizeUnit::Chars, _) => text.len(),
+            (SizeUnit::Tokens, Some(counter)) => counter(text),
+            (SizeUnit::Tokens, None) => estimate_tokens(text),
+        }
+    }
+
+    /// Whether `current` followed by `separator` and `next` is over `chunk_size`.
+    fn exceeds(&self, current: &str, separator: &str, next: &str) -> bool {
+        match self.config.size_unit {
+            // Separators are not counted, as before token sizing existed
+            SizeUnit::Chars => current.len() + next.len() > self.config.chunk_size,
+            SizeUnit::Tokens => self.size(&format!("{current}{separator}{next}")) > self.config.chunk_size,
+        }
+    }
+
+    /// Byte index in `chunk` where the overlap carried into the next chunk
+    /// starts: the last `chunk_overlap` units, on a character boundary.
+    fn overlap_start(&self, chunk: &str) -> usize {
+        match self.config.size_unit {
+            SizeUnit::Chars => find_char_boundary(chunk, chunk.len().saturating_sub(self.config.chunk_overlap)),
+            SizeUnit::Tokens => {
+                if self.config.chunk_overlap == 0 {
+                    return chunk.len();
+                }
+                // The longest suffix that fits, found by bisection (the
+                // token count shrinks as the suffix does)
+                let bounds: Vec<usize> = chunk.char_indices().map(|(i, _)| i).collect();
+                let first = bounds.partition_point(|&b| self.size(&chunk[b..]) > self.config.chunk_overlap);
+                let start = bounds.get(first).copied().unwrap_or(chunk.len());
+                chunk.len() - chunk[start..].trim_start().len()
             }
         }
+    }
 
-        if !current_chunk.is_empty() {
-            chunks.push((
-                current_chunk.clone(),
-                current_start,
-                current_start + current_chunk.len(),
-            ));
+    /// Code chunking: one chunk per item, tagged with its symbol.
+    fn chunk_code(&self, text: &str, language: Option<&str>) -> Vec<(Span, Option<Field>)> {
+        let python = match language {
+            Some(language) => matches!(language.to_ascii_lowercase().as_str(), "python" | "py"),
+            None => looks_like_python(text),
+        };
+
+        let items = if python {
+            Some(indented_items(text, 0, text.len()))
+        } else {
+            braced_items(text, 0, text.len())
+        };
+        let Some(items) = items else {
+            return without_fields(self.chunk_semantic(text));
+        };
+
+        let mut chunks = Vec::new();
+        self.push_code_items(text, items, None, python, &mut chunks);
+        chunks
+    }
+
+    /// Turn code items into chunks, splitting large containers into members.
+    fn push_code_items(
+        &self,
+        text: &str,
+        items: Vec<CodeItem>,
+        parent: Option<&str>,
+        python: bool,
+        chunks: &mut Vec<(Span, Option<Field>)>,
+    ) {
+        let separator = if python { "." } else { "::" };
+
+        for item in items {
+            let symbol = match (parent, item.symbol) {
+                (Some(parent), Some(name)) => Some(format!("{parent}{separator}{name}")),
+                (Some(parent), None) => Some(parent.to_string()),
+                (None, name) => name,
+            };
+            let field = symbol.clone().map(|s| (SYMBOL_KEY, s));
+            let oversized = self.size(&text[item.start..item.end]) > self.config.chunk_size;
+
+            let members = match item.body {
+                Some((start, end)) if oversized => {
+                    let members = if python {
+                        Some(indented_items(text, start, end))
+                    } else {
+                        braced_items(text, start, end)
+                    };
+                    members
+                        .filter(|m| m.iter().any(|i| i.symbol.is_some()))
+                        .map(|m| (start, m))
+                }
+                _ => None,
+            };
+
+            match members {
+                Some((body_start, members)) => {
+                    // The container's header (unless it is just the opening
+                    // line), then each member
+                    if text[item.start..body_start].trim().lines().count() > 1 {
+                        chunks.extend(trimmed_span(text, item.start, body_start).map(|s| (s, field.clone())));
+                    }
+                    self.push_code_items(text, members, symbol.as_deref(), python, chunks);
+                }
+                // Code between items has no unit to preserve
+                None if oversized && symbol.is_none() => {
+                    chunks.extend(without_fields(self.pack_lines(text, item.start, item.end)));
+                }
+                None => chunks.extend(trimmed_span(text, item.start, item.end).map(|s| (s, field))),
+            }
+        }
+    }
+
+    /// Pack whole lines of `text[start..end]` into chunks of at most `chunk_size`.
+    fn pack_lines(&self, text: &str, start: usize, end: usize) -> Vec<Span> {
+        let mut spans = Vec::new();
+        let mut chunk_start = start;
+        let mut pos = start;
+
+        for line in text[start..end].split_inclusive('\n') {
+            if pos > chunk_start && self.size(&text[chunk_start..pos + line.len()]) > self.config.chunk_size {
+                spans.extend(trimmed_span(text, chunk_start, pos));
+                chunk_start = pos;
+            }
+            pos += line.len();
+        }
+
+        spans.extend(trimmed_span(text, chunk_start, end));
+        spans
+    }
+
+    /// Markdown chunking: one chunk per heading section, tagged with its
+    /// heading path. Oversized sections are split into paragraphs (fenced
+    /// code blocks count as one paragraph and are never split).
+    fn chunk_markdown(&self, text: &str) -> Vec<(Span, Option<Field>)> {
+        let mut chunks = Vec::new();
+
+        for section in markdown_sections(text) {
+            let field = (!section.path.is_empty())
+                .then(|| (HEADING_PATH_KEY, section.path.join(HEADING_PATH_SEPARATOR)));
+
+            if self.size(&text[section.start..section.end]) <= self.config.chunk_size {
+                if let Some(span) = trimmed_span(text, section.start, section.end) {
+                    chunks.push((span, field));
+                }
+                continue;
+            }
+
+            for span in self.pack_blocks(text, &section.blocks) {
+                chunks.push((span, field.clone()));
+            }
         }
 
         chunks
     }
+
+    /// Greedily pack Markdown blocks into chunks of at most `chunk_size`.
+    ///
+    /// A block that is too large on its own becomes its own chunk if it is a
+    /// code block, and is split by sentences otherwise.
+    fn pack_blocks(&self, text: &str, blocks: &[MarkdownBlock]) -> Vec<Span> {
+        let mut spans = Vec::new();
+        let mut current: Option<(usize, usize)> = None;
+
+        for block in blocks {
+            if let Some((start, _)) = current {
+                if self.size(&text[start..block.end]) > self.config.chunk_size {
+                    spans.extend(current.take().and_then(|(s, e)| trimmed_span(text, s, e)));
+                }
+            }
+
+            if self.size(&text[block.start..block.end]) > self.config.chunk_size {
+                spans.extend(current.take().and_then(|(s, e)| trimmed_span(text, s, e)));
+                if block.code {
+                    spans.extend(trimmed_span(text, block.start, block.end));
+                } else {
+                    spans.extend(self.split_by_sentences(&text[block.start..block.end], block.start));
+                }
+                continue;
+            }
+
+            current = match current {
+                Some((start, _)) => Some((start, block.end)),
+                None => Some((block.start, block.end)),
+            };
+        }
+
+        spans.extend(current.and_then(|(s, e)| trimmed_span(text, s, e)));
+        spans
+    }
+}
+
+/// An item of source code, or a run of code between items
+struct CodeItem {
+    /// Name of the item, None for code between items
+    symbol: Option<String>,
+    start: usize,
+    end: usize,
+    /// Byte range of the members of a container (impl block, class, module)
+    body: Option<(usize, usize)>,
+}
+
+/// An item whose end has not been found yet
+struct OpenItem {
+    symbol: String,
+    container: bool,
+    start: usize,
+    /// Whether a brace was opened since the item started
+    opened: bool,
+    body_start: Option<usize>,
+}
+
+/// Split brace-delimited code in `text[start..end]` into items.
+///
+/// An item starts at a declaration at brace depth 0 (with the comments and
+/// attributes right above it) and ends when its braces close, or at a `;`
+/// if it has no body. Returns None when braces don't balance.
+fn braced_items(text: &str, start: usize, end: usize) -> Option<Vec<CodeItem>> {
+    let mut items = Vec::new();
+    let mut scanner = BraceScanner::default();
+    let mut loose: Option<usize> = None;
+    let mut leading: Option<usize> = None;
+    let mut current: Option<OpenItem> = None;
+    let mut pos = start;
+
+    for line in text[start..end].split_inclusive('\n') {
+        let line_start = pos;
+        pos += line.len();
+
+        let at_top = scanner.depth == 0 && !scanner.in_literal();
+        if at_top && current.as_ref().is_none_or(|item| !item.opened) {
+            let trimmed = line.trim();
+            let declared = declaration(trimmed);
+
+            // A declaration without body or `;` (`const x = 1` in JS) ends
+            // at the next declaration
+            if declared.is_some() {
+                if let Some(item) = current.take() {
+                    items.push(item.close(line_start, None));
+                }
+            }
+
+            if current.is_none() {
+                if trimmed.is_empty() {
+                    // Comments followed by a blank line don't document the next item
+                    if let Some(s) = leading.take() {
+                        loose.get_or_insert(s);
+                    }
+                } else if is_decoration(trimmed) {
+                    leading.get_or_insert(line_start);
+                } else if let Some((symbol, container)) = declared {
+                    let item_start = leading.take().unwrap_or(line_start);
+                    if let Some(s) = loose.take() {
+                        items.push(CodeItem {
+                            symbol: None,
+                            start: s,
+                            end: item_start,
+                            body: None,
+                        });
+                    }
+                    current = Some(OpenItem {
+                        symbol,
+                        container,
+                        start: item_start,
+                        opened: false,
+                        body_start: None,
+                    });
+                } else {
+                    let s = leading.take().unwrap_or(line_start);
+                    loose.get_or_insert(s);
+                }
+            }
+        }
+
+        let (last, opened) = scanner.scan(line);
+        if scanner.depth < 0 {
+            return None;
+        }
+
+        if let Some(item) = current.as_mut() {
+            item.opened |= opened;
+            if scanner.depth > 0 && item.body_start.is_none() {
+                item.body_start = Some(pos);
+            }
+            let closed = scanner.depth == 0
+                && !scanner.in_literal()
+                && (item.opened || last == Some(';'));
+            if closed {
+                let body = item.body_start.map(|body_start| (body_start, line_start.max(body_start)));
+                if let Some(item) = current.take() {
+                    items.push(item.close(pos, body));
+                }
+            }
+        }
+    }
+
+    if scanner.depth != 0 || scanner.in_literal() {
+        return None;
+    }
+    if let Some(item) = current.take() {
+        items.push(item.close(end, None));
+    }
+    if let Some(s) = loose.or(leading) {
+        items.push(CodeItem {
+            symbol: None,
+            start: s,
+            end,
+            body: None,
+        });
+    }
+
+    Some(items)
+}
+
+/// Split indentation-delimited code (Python) in `text[start..end]` into items.
+///
+/// An item is a declaration at the region's base indentation (with the
+/// comments and decorators right above it) followed by its indented lines.
+fn indented_items(text: &str, start: usize, end: usize) -> Vec<CodeItem> {
+    let region = &text[start..end];
+    let base = region
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .map(indentation)
+        .min()
+        .unwrap_or(0);
+
+    let mut items = Vec::new();
+    let mut loose: Option<usize> = None;
+    let mut leading: Option<usize> = None;
+    let mut current: Option<(OpenItem, usize)> = None;
+    let mut pos = start;
+
+    for line in region.split_inclusive('\n') {
+        let line_start = pos;
+        pos += line.len();
+        let trimmed = line.trim();
+
+        if let Some((item, item_end)) = current.as_mut() {
+            // Blank lines, indented lines and closing brackets of a
+            // multi-line signature belong to the item
+            if trimmed.is_empty() {
+                continue;
+            }
+            if indentation(line) > base || trimmed.starts_with([')', ']', '}']) {
+                if indentation(line) > base && item.body_start.is_none() {
+                    item.body_start = Some(line_start);
+                }
+                *item_end = pos;
+                continue;
+            }
+            if let Some((item, item_end)) = current.take() {
+                let body = item.body_start.map(|body_start| (body_start, item_end));
+                items.push(item.close(item_end, body));
+            }
+        }
+
+        if trimmed.is_empty() {
+            if let Some(s) = leading.take() {
+                loose.get_or_insert(s);
+            }
+        } else if trimmed.starts_with('@') || trimmed.starts_with('#') {
+            leading.get_or_insert(line_start);
+        } else if let Some((symbol, container)) = declaration(trimmed) {
+            let item_start = leading.take().unwrap_or(line_start);
+            if let Some(s) = loose.take() {
+                items.push(CodeItem {
+                    symbol: None,
+                    start: s,
+                    end: item_start,
+                    body: None,
+                });
+            }
+            let item = OpenItem {
+                symbol,
+                container,
+                start: item_start,
+                opened: false,
+                body_start: None,
+            };
+            current = Some((item, pos));
+        } else {
+            let s = leading.take().unwrap_or(line_start);
+            loose.get_or_insert(s);
+        }
+    }
+
+    if let Some((item, item_end)) = current.take() {
+        let body = item.body_start.map(|body_start| (body_start, item_end));
+        items.push(item.close(item_end, body));
+    }
+    if let Some(s) = loose.or(leading) {
+        items.push(CodeItem {
+            symbol: None,
+            start: s,
+            end,
+            body: None,
+        });
+    }
+
+    items
+}
+
+impl OpenItem {
+    fn close(self, end: usize, body: Option<(usize, usize)>) -> CodeItem {
+        CodeItem {
+            symbol: Some(self.symbol),
+            start: self.start,
+            end,
+            body: body.filter(|_| self.container),
+        }
+    }
+}
+
+/// Tracks brace depth across lines, skipping strings and comments
+#[derive(Default)]
+struct BraceScanner {
+    depth: i32,
+    block_comment: bool,
+    quote: Option<char>,
+}
+
+impl BraceScanner {
+    /// Whether the scan stopped inside a string or block comment
+    fn in_literal(&self) -> bool {
+        self.block_comment || self.quote.is_some()
+    }
+
+    /// Scan one line, returning its last code character and whether it
+    /// opened a brace
+    fn scan(&mut self, line: &str) -> (Option<char>, bool) {
+        let mut chars = line.chars().peekable();
+        let mut last = None;
+        let mut opened = false;
+
+        while let Some(c) = chars.next() {
+            if self.block_comment {
+                if c == '*' && chars.next_if_eq(&'/').is_some() {
+                    self.block_comment = false;
+                }
+                continue;
+            }
+            if let Some(quote) = self.quote {
+                if c == '\\' {
+                    chars.next();
+                } else if c == quote {
+                    self.quote = None;
+                    last = Some(c);
+                }
+                continue;
+            }
+
+            match c {
+                '/' if chars.next_if_eq(&'/').is_some() => break,
+                '/' if chars.next_if_eq(&'*').is_some() => {
+                    self.block_comment = true;
+                    continue;
+                }
+                '"' | '`' => self.quote = Some(c),
+                // Char literals ('{', '\n'); a lone quote is a lifetime
+                '\'' => {
+                    let mut ahead = chars.clone();
+                    match (ahead.next(), ahead.next()) {
+                        (Some('\\'), _) => {
+                            chars.next();
+                            chars.next();
+                            for n in chars.by_ref() {
+                                if n == '\'' {
+                                    break;
+                                }
+                            }
+                        }
+                        (Some(_), Some('\'')) => {
+                            chars.next();
+                            chars.next();
+                        }
+                        _ => {}
+                    }
+                }
+                '{' => {
+                    self.depth += 1;
+                    opened = true;
+                }
+                '}' => self.depth -= 1,
+                _ => {}
+            }
+            if !c.is_whitespace() {
+                last = Some(c);
+            }
+        }
+
+        (last, opened)
+    }
+}
+
+/// Attribute, decorator or comment line, attached to the item below it
+fn is_decoration(line: &str) -> bool {
+    ["#[", "#!", "//", "/*", "*", "@"].iter().any(|p| line.starts_with(p))
+}
+
+/// Parse a declaration line, returning the declared name and whether the
+/// item contains members (impl block, class, module)
+fn declaration(line: &str) -> Option<(String, bool)> {
+    const MODIFIERS: &[&str] = &[
+        "pub", "export", "default", "async", "unsafe", "extern", "public", "private", "protected",
+        "internal", "abstract", "final", "override", "virtual", "inline", "sealed", "open", "declare",
+    ];
+
+    let mut rest = line;
+    loop {
+        let (word, after) = split_word(rest);
+        let after = after.trim_start();
+        let (next, _) = split_word(after);
+        let container = match word {
+            // `pub(crate)`, `extern "C"`
+            _ if MODIFIERS.contains(&word) => {
+                rest = skip_group(after).trim_start();
+                continue;
+            }
+            // `const fn`, `static async function`
+            "const" | "static" if is_keyword(next) || MODIFIERS.contains(&next) => {
+                rest = after;
+                continue;
+            }
+            "impl" => {
+                let target = skip_generics(after);
+                let end = target.find(" where").or_else(|| target.find('{')).unwrap_or(target.len());
+                let target = target[..end].trim();
+                return (!target.is_empty()).then(|| (target.to_string(), true));
+            }
+            "trait" | "mod" | "class" | "interface" | "namespace" | "object" => true,
+            "fn" | "struct" | "enum" | "union" | "type" | "macro_rules!" | "function" | "func" | "def"
+            | "const" | "static" => false,
+            _ => return None,
+        };
+
+        // `function* gen`, Go receivers `func (s *Server) Start`
+        let after = after.trim_start_matches('*').trim_start();
+        let after = if word == "func" { skip_group(after).trim_start() } else { after };
+        let name: String = after
+            .chars()
+            .take_while(|c| c.is_alphanumeric() || *c == '_' || *c == '$')
+            .collect();
+        return (!name.is_empty()).then_some((name, container));
+    }
+}
+
+fn is_keyword(word: &str) -> bool {
+    matches!(word, "fn" | "function" | "class" | "def")
+}
+
+/// Split off the leading word (letters, digits, `_`, `!`)
+fn split_word(text: &str) -> (&str, &str) {
+    let end = text
+        .find(|c: char| !(c.is_alphanumeric() || c == '_' || c == '!'))
+        .unwrap_or(text.len());
+    text.split_at(end)
+}
+
+/// Skip a leading parenthesized group or string (`(crate)`, `"C"`)
+fn skip_group(text: &str) -> &str {
+    let close = match text.chars().next() {
+        Some('(') => ')',
+        Some('"') => '"',
+        _ => return text,
+    };
+    match text[1..].find(close) {
+        Some(i) => &text[i + 2..],
+        None => text,
+    }
+}
+
+/// Skip leading generic parameters (`<T: Display>`)
+fn skip_generics(text: &str) -> &str {
+    if !text.starts_with('<') {
+        return text;
+    }
+    let mut depth = 0;
+    for (i, c) in text.char_indices() {
+        match c {
+            '<' => depth += 1,
+            '>' => {
+                depth -= 1;
+                if depth == 0 {
+                    return text[i + 1..].trim_start();
+                }
+            }
+            _ => {}
+        }
+    }
+    text
+}
+
+fn indentation(line: &str) -> usize {
+    line.len() - line.trim_start().len()
+}
+
+/// Whether unannotated code looks like Python (`def f():` at the top level)
+fn looks_like_python(text: &str) -> bool {
+    text.lines().any(|line| {
+        let line = line.trim_end();
+        indentation(line) == 0
+            && line.ends_with(':')
+            && ["def ", "async def ", "class "].iter().any(|p| line.starts_with(p))
+    })
+}
+
+/// A Markdown section: a heading (if any) and the content up to the next one
+struct MarkdownSection {
+    /// Titles of the enclosing headings, outermost first
+    path: Vec<String>,
+    start: usize,
+    end: usize,
+    /// Paragraphs and code blocks of the section, in order
+    blocks: Vec<MarkdownBlock>,
+}
+
+/// A paragraph or fenced code block, as a byte range
+struct MarkdownBlock {
+    start: usize,
+    end: usize,
+    code: bool,
+}
+
+/// Split Markdown into heading sections and their blocks.
+///
+/// Headings inside fenced code blocks are ignored. Sections holding nothing
+/// but their heading are dropped: their title is in the path of the
+/// sections below.
+fn markdown_sections(text: &str) -> Vec<MarkdownSection> {
+    let mut sections = Vec::new();
+    let mut headings: Vec<(usize, String)> = Vec::new();
+    let mut current = MarkdownSection {
+        path: Vec::new(),
+        start: 0,
+        end: 0,
+        blocks: Vec::new(),
+    };
+    let mut has_body = false;
+    let mut block: Option<MarkdownBlock> = None;
+    let mut fence: Option<(char, usize)> = None;
+    let mut pos = 0;
+
+    for line in text.split_inclusive('\n') {
+        let line_start = pos;
+        pos += line.len();
+
+        if let Some((marker, len)) = fence {
+            // Inside a code block: only the closing fence matters
+            if closes_fence(line, marker, len) {
+                fence = None;
+                if let Some(b) = block.as_mut() {
+                    b.end = pos;
+                }
+                current.blocks.extend(block.take());
+            } else if let Some(b) = block.as_mut() {
+                b.end = pos;
+            }
+            continue;
+        }
+
+        if let Some((level, title)) = heading(line) {
+            current.blocks.extend(block.take());
+            current.end = line_start;
+            if has_body {
+                sections.push(current);
+            }
+
+            headings.retain(|(l, _)| *l < level);
+            headings.push((level, title));
+            current = MarkdownSection {
+                path: headings.iter().map(|(_, t)| t.clone()).collect(),
+                start: line_start,
+                end: pos,
+                blocks: vec![MarkdownBlock {
+                    start: line_start,
+                    end: pos,
+                    code: false,
+                }],
+            };
+            has_body = false;
+            continue;
+        }
+
+        if line.trim().is_empty() {
+            current.blocks.extend(block.take());
+            continue;
+        }
+
+        has_body = true;
+        if let Some(opening) = opens_fence(line) {
+            current.blocks.extend(block.take());
+            fence = Some(opening);
+            block = Some(MarkdownBlock {
+                start: line_start,
+                end: pos,
+                code: true,
+            });
+            continue;
+        }
+
+        block
+            .get_or_insert(MarkdownBlock {
+                start: line_start,
+                end: pos,
+                code: false,
+            })
+            .end = pos;
+    }
+
+    // An unclosed fence runs to the end of the document
+    current.blocks.extend(block.take());
+    current.end = text.len();
+    if has_body {
+        sections.push(current);
+    }
+
+    sections
+}
+
+/// Parse an ATX heading (`## Title`), returning its level and title
+fn heading(line: &str) -> Option<(usize, String)> {
+    let trimmed = strip_indent(line)?;
+    let level = trimmed.chars().take_while(|&c| c == '#').count();
+    if !(1..=6).contains(&level) {
+        return None;
+    }
+
+    let rest = &trimmed[level..];
+    if !rest.is_empty() && !rest.starts_with([' ', '\t', '\n', '\r']) {
+        return None;
+    }
+
+    // Optional closing sequence: `## Title ##`
+    let title = rest.trim().trim_end_matches('#').trim_end();
+    Some((level, title.to_string()))
+}
+
+/// Parse an opening code fence (```` ``` ```` or `~~~`), returning its marker and length
+fn opens_fence(line: &str) -> Option<(char, usize)> {
+    let trimmed = strip_indent(line)?;
+    let marker = trimmed.chars().next().filter(|c| *c == '`' || *c == '~')?;
+    let len = trimmed.chars().take_while(|&c| c == marker).count();
+    (len >= 3).then_some((marker, len))
+}
+
+/// Whether `line` closes a fence opened with `len` `marker` characters
+fn closes_fence(line: &str, marker: char, len: usize) -> bool {
+    let Some(trimmed) = strip_indent(line) else {
+        return false;
+    };
+    let run = trimmed.chars().take_while(|&c| c == marker).count();
+    run >= len && trimmed[run..].trim().is_empty()
+}
+
+/// Strip up to three leading spaces; more makes an indented code line
+fn strip_indent(line: &str) -> Option<&str> {
+    let indent = line.len() - line.trim_start_matches(' ').len();
+    (indent <= 3).then(|| &line[indent..])
+}
+
+/// Text of `text[start..end]` without surrounding whitespace, or None if blank
+fn trimmed_span(text: &str, start: usize, end: usize) -> Option<Span> {
+    let slice = &text[start..end];
+    let trimmed = slice.trim();
+    if trimmed.is_empty() {
+        return None;
+    }
+    let offset = start + (slice.len() - slice.trim_start().len());
+    Some((trimmed.to_string(), offset, offset + trimmed.len()))
+}
+
+fn without_fields(spans: Vec<Span>) -> Vec<(Span, Option<Field>)> {
+    spans.into_iter().map(|span| (span, None)).collect()
+}
+
+/// Add `key` to object metadata, creating it if missing
+fn with_field(metadata: Option<Value>, key: &str, value: String) -> Option<Value> {
+    match metadata {
+        Some(Value::Object(mut fields)) => {
+            fields.insert(key.to_string(), Value::String(value));
+            Some(Value::Object(fields))
+        }
+        None => Some(serde_json::json!({ key: value })),
+        // Non-object metadata has nowhere to put the field
+        other => other,
+    }
+}
+
+/// Replace a PDF's page offsets with the page a chunk starting at `start`
+/// is on. Other metadata is returned unchanged.
+fn with_page(metadata: Option<Value>, start: usize) -> Option<Value> {
+    let mut fields = match metadata {
+        Some(Value::Object(fields)) if fields.contains_key(PAGE_OFFSETS_KEY) => fields,
+        other => return other,
+    };
+    if let Some(Value::Array(offsets)) = fields.remove(PAGE_OFFSETS_KEY) {
+        let page = offsets
+            .iter()
+            .filter_map(Value::as_u64)
+            .take_while(|&offset| offset as usize <= start)
+            .count()
+            .max(1);
+        fields.insert(PAGE_KEY.to_string(), page.into());
+    }
+    Some(Value::Object(fields))
 }
 
 impl Default for Chunker {
@@ -351,6 +1172,7 @@ mod tests {
             chunk_size: 100,
             chunk_overlap: 10,
             min_chunk_size: 5,
+            ..Default::default()
         });
         let doc = make_doc("Hello world. This is a test.");
         let chunks = chunker.chunk(&doc).unwrap();
@@ -365,6 +1187,7 @@ mod tests {
             chunk_size: 50,
             chunk_overlap: 10,
             min_chunk_size: 10,
+            ..Default::default()
         });
 
         let content = "This is paragraph one with some content.\n\n\
@@ -389,6 +1212,7 @@ mod tests {
             chunk_size: 20,
             chunk_overlap: 5,
             min_chunk_size: 5,
+            ..Default::default()
         })
         .with_strategy(ChunkingStrategy::FixedSize);
 
@@ -404,8 +1228,9 @@ mod tests {
             chunk_size: 100,
             chunk_overlap: 10,
             min_chunk_size: 10,
+            ..Default::default()
         })
-        .with_strategy(ChunkingStrategy::Code);
+        .with_strategy(ChunkingStrategy::Code { language: None });
 
         let code = r#"
 fn hello() {
@@ -428,12 +1253,227 @@ fn main() {
         assert!(chunks.len() >= 1);
     }
 
+    fn symbols(chunks: &[Chunk]) -> Vec<Option<&str>> {
+        chunks
+            .iter()
+            .map(|c| c.metadata.as_ref().and_then(|m| m[SYMBOL_KEY].as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_code_chunking_keeps_items_whole() {
+        let chunker = Chunker::with_config(ChunkingConfig {
+            chunk_size: 150,
+            chunk_overlap: 0,
+            min_chunk_size: 20,
+            ..Default::default()
+        })
+        .with_strategy(ChunkingStrategy::Code {
+            language: Some("rust".to_string()),
+        });
+
+        let code = r#"use std::fs;
+
+/// Server settings
+#[derive(Debug)]
+pub struct Config {
+    pub port: u16,
+}
+
+impl Config {
+    /// Load from a file
+    pub fn load(path: &str) -> Config {
+        let text = fs::read_to_string(path).unwrap_or_default();
+        Config { port: text.trim().parse().unwrap_or(80) }
+    }
+
+    pub fn url(&self) -> String {
+        format!("http://localhost:{}/{}", self.port, '}')
+    }
+}
+
+fn main() {
+    let config = Config::load("port.txt");
+    println!("{}", config.url());
+}
+"#;
+        let chunks = chunker.chunk(&make_doc(code)).unwrap();
+
+        // The impl block is too large and is split into its methods
+        assert_eq!(
+            symbols(&chunks),
+            vec![Some("Config"), Some("Config::load"), Some("Config::url"), Some("main")]
+        );
+        assert!(chunks[0].text.starts_with("/// Server settings"));
+        assert!(chunks[1].text.starts_with("/// Load from a file"));
+        assert!(chunks[1].text.ends_with("unwrap_or(80) }\n    }"));
+        assert!(chunks[2].text.ends_with("'}')\n    }"));
+        for chunk in &chunks {
+            assert_eq!(&code[chunk.start_char..chunk.end_char], chunk.text);
+        }
+    }
+
+    #[test]
+    fn test_code_chunking_python() {
+        let chunker = Chunker::with_config(ChunkingConfig {
+            chunk_size: 500,
+            chunk_overlap: 0,
+            min_chunk_size: 20,
+            ..Default::default()
+        })
+        .with_strategy(ChunkingStrategy::Code { language: None });
+
+        let code = "import os\n\n\
+                    @dataclass\nclass Job:\n    name: str\n\n    def run(self):\n        return os.system(self.name)\n\n\n\
+                    def main():\n    Job(\"ls\").run()\n";
+        let chunks = chunker.chunk(&make_doc(code)).unwrap();
+
+        assert_eq!(symbols(&chunks), vec![Some("Job"), Some("main")]);
+        assert!(chunks[0].text.starts_with("@dataclass"));
+        assert!(chunks[0].text.ends_with("os.system(self.name)"));
+    }
+
+    #[test]
+    fn test_code_chunking_falls_back_when_unbalanced() {
+        let config = ChunkingConfig {
+            chunk_size: 100,
+            chunk_overlap: 10,
+            min_chunk_size: 10,
+            ..Default::default()
+        };
+        let doc = make_doc("fn broken() {\n    let x = 1;\n\nfn other() {\n    x\n}\n");
+
+        let code = Chunker::with_config(config.clone())
+            .with_strategy(ChunkingStrategy::Code { language: None })
+            .chunk(&doc)
+            .unwrap();
+        let semantic = Chunker::with_config(config).chunk(&doc).unwrap();
+
+        let texts = |chunks: &[Chunk]| chunks.iter().map(|c| c.text.clone()).collect::<Vec<_>>();
+        assert_eq!(texts(&code), texts(&semantic));
+        assert!(symbols(&code).iter().all(Option::is_none));
+    }
+
+    #[test]
+    fn test_markdown_chunking_follows_headings() {
+        let chunker = Chunker::with_config(ChunkingConfig {
+            chunk_size: 200,
+            chunk_overlap: 0,
+            min_chunk_size: 50,
+            ..Default::default()
+        })
+        .with_strategy(ChunkingStrategy::Markdown);
+
+        let content = "Intro text.\n\n\
+                       # Install\n\n\
+                       ## Linux\n\n\
+                       Run the installer.\n\n\
+                       ```sh\n# not a heading\n\ncurl -sSf https://example.com | sh\n```\n\n\
+                       ## macOS ##\n\n\
+                       Use brew.\n";
+        let doc = Document::new(content).with_metadata(serde_json::json!({"source": "README.md"}));
+        let chunks = chunker.chunk(&doc).unwrap();
+
+        let paths: Vec<Option<&str>> = chunks
+            .iter()
+            .map(|c| c.metadata.as_ref().and_then(|m| m[HEADING_PATH_KEY].as_str()))
+            .collect();
+        assert_eq!(paths, vec![None, Some("Install > Linux"), Some("Install > macOS")]);
+
+        // Short sections are kept, the code block stays with its section
+        assert_eq!(chunks[0].text, "Intro text.");
+        assert!(chunks[1].text.starts_with("## Linux"));
+        assert!(chunks[1].text.ends_with("```"));
+        assert_eq!(chunks[2].metadata.as_ref().unwrap()["source"], "README.md");
+        assert_eq!(&content[chunks[2].start_char..chunks[2].end_char], chunks[2].text);
+    }
+
+    #[test]
+    fn test_markdown_chunking_splits_large_sections() {
+        let chunker = Chunker::with_config(ChunkingConfig {
+            chunk_size: 60,
+            chunk_overlap: 0,
+            min_chunk_size: 10,
+            ..Default::default()
+        })
+        .with_strategy(ChunkingStrategy::Markdown);
+
+        let code = "```rust\nfn main() {\n\n    println!(\"a fenced block longer than the limit\");\n}\n```";
+        let content = format!(
+            "# Usage\n\nFirst paragraph of usage.\n\nSecond paragraph of usage.\n\n{code}\n\nLast words."
+        );
+        let chunks = chunker.chunk(&Document::new(content)).unwrap();
+
+        assert!(chunks.len() >= 3);
+        for chunk in &chunks {
+            assert_eq!(chunk.metadata.as_ref().unwrap()[HEADING_PATH_KEY], "Usage");
+        }
+        assert!(chunks.iter().any(|c| c.text == code), "code block kept whole");
+        assert!(chunks
+            .iter()
+            .filter(|c| c.text != code)
+            .all(|c| c.text.len() <= 60));
+    }
+
+    #[test]
+    fn test_chunk_sizes_in_tokens() {
+        let words: TokenCounter = std::sync::Arc::new(|text: &str| text.split_whitespace().count());
+        let config = ChunkingConfig {
+            chunk_size: 5,
+            chunk_overlap: 2,
+            min_chunk_size: 1,
+            size_unit: SizeUnit::Tokens,
+        };
+        let text = "one two three. four five six. seven eight nine. ten eleven twelve.";
+
+        let chunker = Chunker::with_config(config.clone()).with_token_counter(words.clone());
+        let chunks = chunker.chunk(&Document::new(text)).unwrap();
+        assert!(chunks.len() >= 3);
+        assert!(chunks.iter().all(|c| words(&c.text) <= 5), "{chunks:?}");
+        // Overlap is counted in tokens too
+        assert!(chunks[1].text.starts_with("two three."), "{}", chunks[1].text);
+
+        let fixed = Chunker::with_config(config)
+            .with_strategy(ChunkingStrategy::FixedSize)
+            .with_token_counter(words.clone());
+        let chunks = fixed.chunk(&Document::new(text)).unwrap();
+        assert!(chunks.iter().all(|c| words(&c.text) <= 5), "{chunks:?}");
+        assert!(chunks.last().unwrap().text.ends_with("twelve."));
+        for chunk in &chunks {
+            assert_eq!(&text[chunk.start_char..chunk.end_char], chunk.text);
+        }
+    }
+
+    #[test]
+    fn test_chunk_page_from_offsets() {
+        let chunker = Chunker::with_config(ChunkingConfig {
+            chunk_size: 30,
+            chunk_overlap: 0,
+            min_chunk_size: 5,
+            ..Default::default()
+        });
+        let content = "First page text here.\n\nSecond page text here.";
+        let doc = Document::new(content)
+            .with_metadata_field("source", "book.pdf")
+            .with_metadata_field(PAGE_OFFSETS_KEY, vec![0, 23]);
+
+        let chunks = chunker.chunk(&doc).unwrap();
+        assert_eq!(chunks.len(), 2);
+        for (chunk, page) in chunks.iter().zip([1, 2]) {
+            let metadata = chunk.metadata.as_ref().unwrap();
+            assert_eq!(metadata[PAGE_KEY], page);
+            assert_eq!(metadata["source"], "book.pdf");
+            assert!(metadata.get(PAGE_OFFSETS_KEY).is_none());
+        }
+    }
+
     #[test]
     fn test_chunk_metadata_inheritance() {
         let chunker = Chunker::with_config(ChunkingConfig {
             chunk_size: 500,
             chunk_overlap: 50,
             min_chunk_size: 10,
+            ..Default::default()
         });
         let doc = Document::new("Hello world content here with enough text to pass minimum size")
             .with_metadata(serde_json::json!({"key": "value"}));
@@ -462,6 +1502,7 @@ fn main() {
             chunk_size: 50,
             chunk_overlap: 0,
             min_chunk_size: 5,
+            ..Default::default()
         });
 
         let doc = make_doc("First chunk content.\n\nSecond chunk content.");
@@ -480,6 +1521,7 @@ fn main() {
             chunk_size: 30,
             chunk_overlap: 10,
             min_chunk_size: 5,
+            ..Default::default()
         });
 
         // Text with French accents (é = 2 bytes in UTF-8)
@@ -2,6 +2,9 @@
 
 use serde::{Deserialize, Serialize};
 
+use crate::embedder::{EmbeddingBackend, EmbeddingBackendHandle};
+use crate::reranker::{Reranker, RerankerHandle};
+
 /// RAG engine configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RagConfig {
@@ -11,10 +14,43 @@ pub struct RagConfig {
     pub table_name: String,
     /// Embedding model name
     pub embedding_model: EmbeddingModel,
+    /// Custom embedding backend used instead of fastembed (not serialized)
+    ///
+    /// Its dimension must match `embedding_model.dimensions()`, which sizes
+    /// the vector index; use `EmbeddingModel::Custom` to describe it.
+    #[serde(skip)]
+    pub embedding_backend: Option<EmbeddingBackendHandle>,
+    /// Whether embeddings are unit length, allowing dot-product search
+    ///
+    /// `None` assumes fastembed models are (they normalize their output) and
+    /// custom backends are not.
+    #[serde(default)]
+    pub normalized_embeddings: Option<bool>,
+    /// Custom reranker used instead of the cross-encoder (not serialized)
+    ///
+    /// Only used when `search.rerank_top_n` is set.
+    #[serde(skip)]
+    pub reranker: Option<RerankerHandle>,
     /// Chunking configuration
     pub chunking: ChunkingConfig,
     /// Search configuration
     pub search: SearchConfig,
+    /// Batch indexing configuration
+    #[serde(default)]
+    pub indexing: IndexingConfig,
+    /// Embedding cache, off when `None`
+    #[serde(default)]
+    pub embedding_cache: Option<EmbeddingCacheConfig>,
+    /// Skip indexing chunks whose cosine similarity to an already indexed
+    /// chunk exceeds this (e.g. 0.98), off when `None`
+    ///
+    /// Only chunks of this engine's table within `search.filter` count, so
+    /// separate collections never suppress each other.
+    #[serde(default)]
+    pub dedup_threshold: Option<f32>,
+    /// HNSW vector index parameters
+    #[serde(default)]
+    pub hnsw: HnswConfig,
 }
 
 impl Default for RagConfig {
@@ -23,8 +59,15 @@ impl Default for RagConfig {
             db_path: "data/vectors".to_string(),
             table_name: "chunks".to_string(),
             embedding_model: EmbeddingModel::default(),
+            embedding_backend: None,
+            normalized_embeddings: None,
+            reranker: None,
             chunking: ChunkingConfig::default(),
             search: SearchConfig::default(),
+            indexing: IndexingConfig::default(),
+            embedding_cache: None,
+            dedup_threshold: None,
+            hnsw: HnswConfig::default(),
         }
     }
 }
@@ -47,6 +90,36 @@ impl RagConfig {
         self.embedding_model = model;
         self
     }
+
+    /// Create config with a custom embedding backend.
+    pub fn with_embedding_backend(mut self, backend: Box<dyn EmbeddingBackend>) -> Self {
+        self.embedding_backend = Some(EmbeddingBackendHandle::new(backend));
+        self
+    }
+
+    /// Whether stored embeddings are unit length (see `normalized_embeddings`).
+    pub fn embeddings_normalized(&self) -> bool {
+        self.normalized_embeddings
+            .unwrap_or(self.embedding_backend.is_none())
+    }
+
+    /// Create config with a custom reranker.
+    pub fn with_reranker(mut self, reranker: Box<dyn Reranker>) -> Self {
+        self.reranker = Some(RerankerHandle::new(reranker));
+        self
+    }
+
+    /// Create config with an embedding cache.
+    pub fn with_embedding_cache(mut self, cache: EmbeddingCacheConfig) -> Self {
+        self.embedding_cache = Some(cache);
+        self
+    }
+
+    /// Create config skipping near-duplicate chunks on index.
+    pub fn with_dedup_threshold(mut self, threshold: f32) -> Self {
+        self.dedup_threshold = Some(threshold);
+        self
+    }
 }
 
 /// Embedding model selection.
@@ -58,6 +131,13 @@ pub enum EmbeddingModel {
     BgeSmallEnV15,
     /// BGE-base-en-v1.5 (768 dimensions, high quality)
     BgeBaseEnV15,
+    /// Any other fastembed model, by its model code, with its embedding dimension
+    Custom {
+        /// fastembed model code (e.g. "nomic-ai/nomic-embed-text-v1.5")
+        name: String,
+        /// Embedding dimension produced by the model
+        dimension: usize,
+    },
 }
 
 impl Default for EmbeddingModel {
@@ -73,15 +153,25 @@ impl EmbeddingModel {
             Self::AllMiniLmL6V2 => 384,
             Self::BgeSmallEnV15 => 384,
             Self::BgeBaseEnV15 => 768,
+            Self::Custom { dimension, .. } => *dimension,
         }
     }
 
     /// Get fastembed model name.
-    pub fn fastembed_name(&self) -> &'static str {
+    pub fn fastembed_name(&self) -> &str {
         match self {
             Self::AllMiniLmL6V2 => "sentence-transformers/all-MiniLM-L6-v2",
             Self::BgeSmallEnV15 => "BAAI/bge-small-en-v1.5",
             Self::BgeBaseEnV15 => "BAAI/bge-base-en-v1.5",
+            Self::Custom { name, .. } => name,
+        }
+    }
+
+    /// Create a custom model selection.
+    pub fn custom(name: impl Into<String>, dimension: usize) -> Self {
+        Self::Custom {
+            name: name.into(),
+            dimension,
         }
     }
 }
@@ -89,12 +179,15 @@ impl EmbeddingModel {
 /// Chunking configuration.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChunkingConfig {
-    /// Target chunk size in characters
+    /// Target chunk size, in `size_unit`
     pub chunk_size: usize,
-    /// Overlap between chunks in characters
+    /// Overlap between chunks, in `size_unit`
     pub chunk_overlap: usize,
-    /// Minimum chunk size (smaller chunks are merged)
+    /// Minimum chunk size (smaller chunks are merged), in `size_unit`
     pub min_chunk_size: usize,
+    /// Unit of the sizes above
+    #[serde(default)]
+    pub size_unit: SizeUnit,
 }
 
 impl Default for ChunkingConfig {
@@ -103,6 +196,83 @@ impl Default for ChunkingConfig {
             chunk_size: 512,
             chunk_overlap: 50,
             min_chunk_size: 100,
+            size_unit: SizeUnit::default(),
+        }
+    }
+}
+
+/// Unit chunk sizes are measured in.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SizeUnit {
+    /// UTF-8 bytes (characters, for ASCII text)
+    #[default]
+    Chars,
+    /// Tokens of the embedding model's tokenizer, so chunks fit the
+    /// model's input limit instead of being truncated by the embedder
+    Tokens,
+}
+
+/// Batch indexing configuration, used by `RagEngine::index_batch`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct IndexingConfig {
+    /// Chunks embedded per call to the embedding backend
+    pub batch_size: usize,
+    /// Embedding batches in flight at once
+    ///
+    /// A backend embeds one batch at a time; further batches wait for it
+    /// while finished documents are written to the database.
+    pub concurrency: usize,
+}
+
+impl Default for IndexingConfig {
+    fn default() -> Self {
+        Self {
+            batch_size: 64,
+            concurrency: 2,
+        }
+    }
+}
+
+/// HNSW vector index parameters.
+///
+/// Both trade indexing speed and memory for recall. They are fixed when the
+/// index is created: after changing them on an existing database, call
+/// `RagEngine::redefine_index` (a warning is logged until then).
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct HnswConfig {
+    /// Links per node (8 - 16 for most models, up to 48 for very high recall)
+    pub m: usize,
+    /// Candidate list size while building the graph
+    pub ef_construction: usize,
+}
+
+impl Default for HnswConfig {
+    fn default() -> Self {
+        Self {
+            m: 12,
+            ef_construction: 150,
+        }
+    }
+}
+
+/// Embedding cache configuration.
+///
+/// Embeddings are keyed on the model and a SHA-256 of the text, so
+/// re-indexing unchanged chunks skips the embedding backend.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmbeddingCacheConfig {
+    /// Maximum number of embeddings kept in memory (least recently used go first)
+    pub capacity: usize,
+    /// File persisting the cache across restarts (JSON lines), memory only when `None`
+    #[serde(default)]
+    pub path: Option<String>,
+}
+
+impl Default for EmbeddingCacheConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 10_000,
+            path: None,
         }
     }
 }
@@ -116,6 +286,94 @@ pub struct SearchConfig {
     pub max_limit: usize,
     /// Minimum similarity score (0.0 - 1.0)
     pub min_score: f32,
+    /// Document metadata filter applied to every search
+    ///
+    /// Keys are metadata fields, values a literal to match or an object of
+    /// operators (`$eq`, `$ne`, `$in`, `$nin`, `$contains`, `$gt`, `$gte`,
+    /// `$lt`, `$lte`, `$exists`); `$and`/`$or` combine filters. See
+    /// `whytcard_database::filter` for the full syntax.
+    #[serde(default)]
+    pub filter: Option<serde_json::Value>,
+    /// Rerank this many vector candidates, then keep the requested limit
+    ///
+    /// `None` disables reranking. Capped at `max_limit`.
+    #[serde(default)]
+    pub rerank_top_n: Option<usize>,
+    /// Cross-encoder used for reranking (unless a custom reranker is set)
+    #[serde(default)]
+    pub reranker_model: RerankerModel,
+    /// Weight of the vector score in `RagEngine::search_hybrid` (0.0 - 1.0)
+    ///
+    /// Hybrid scores are `alpha * vector + (1 - alpha) * lexical`, each
+    /// normalized so the best candidate of its path scores 1.0: 1.0 is pure
+    /// vector search, 0.0 pure BM25 keyword search.
+    #[serde(default = "default_hybrid_alpha")]
+    pub hybrid_alpha: f32,
+    /// How `RagEngine::search_documents` scores a document from its chunks
+    #[serde(default)]
+    pub document_aggregation: DocumentAggregation,
+    /// Diversify results with Maximal Marginal Relevance (0.0 - 1.0)
+    ///
+    /// Each pick maximizes `lambda * relevance - (1 - lambda) * similarity`
+    /// to the results already picked: 1.0 keeps plain relevance order, lower
+    /// values drop near-duplicates. `None` disables it.
+    #[serde(default)]
+    pub mmr_lambda: Option<f32>,
+    /// Similarity used to rank vector results
+    ///
+    /// The index is built for cosine; dot product is only served when
+    /// embeddings are normalized (see `RagConfig::normalized_embeddings`).
+    #[serde(default)]
+    pub metric: SimilarityMetric,
+    /// Metadata keys returned with each result, `None` for all of them
+    ///
+    /// Other keys (bulky provenance, internal fields) are dropped from
+    /// result chunks; filters still see the full metadata.
+    #[serde(default)]
+    pub metadata_fields: Option<Vec<String>>,
+    /// Walk the HNSW graph with this candidate list size instead of scoring
+    /// every chunk, `None` for exact search
+    ///
+    /// Only applies to cosine ranking (the index metric). Larger values
+    /// raise recall and latency; 40 - 100 usually finds over 95% of the
+    /// exact neighbours. Values below the result limit are raised to it.
+    #[serde(default)]
+    pub ef_search: Option<usize>,
+}
+
+fn default_hybrid_alpha() -> f32 {
+    0.5
+}
+
+/// Score of a document in document-level search, from its chunk hits.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DocumentAggregation {
+    /// Score of the best matching chunk
+    #[default]
+    Max,
+    /// Mean score of the matching chunks, favoring consistently relevant
+    /// documents over ones with a single strong passage
+    Mean,
+}
+
+/// Similarity ranking vector search results.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SimilarityMetric {
+    /// Cosine similarity (the index metric)
+    #[default]
+    Cosine,
+    /// Dot product, for normalized embeddings
+    Dot,
+}
+
+/// Cross-encoder reranking model selection.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum RerankerModel {
+    /// BAAI/bge-reranker-base (multilingual, quality)
+    #[default]
+    BgeRerankerBase,
+    /// jinaai/jina-reranker-v1-turbo-en (English, fast)
+    JinaRerankerV1TurboEn,
 }
 
 impl Default for SearchConfig {
@@ -124,6 +382,15 @@ impl Default for SearchConfig {
             default_limit: 5,
             max_limit: 50,
             min_score: 0.0,
+            filter: None,
+            rerank_top_n: None,
+            reranker_model: RerankerModel::default(),
+            hybrid_alpha: default_hybrid_alpha(),
+            document_aggregation: DocumentAggregation::default(),
+            mmr_lambda: None,
+            metric: SimilarityMetric::default(),
+            metadata_fields: None,
+            ef_search: None,
         }
     }
 }
@@ -158,4 +425,16 @@ mod tests {
         assert_eq!(EmbeddingModel::BgeSmallEnV15.dimensions(), 384);
         assert_eq!(EmbeddingModel::BgeBaseEnV15.dimensions(), 768);
     }
+
+    #[test]
+    fn test_custom_embedding_model() {
+        let model = EmbeddingModel::custom("nomic-ai/nomic-embed-text-v1.5", 768);
+        assert_eq!(model.dimensions(), 768);
+        assert_eq!(model.fastembed_name(), "nomic-ai/nomic-embed-text-v1.5");
+
+        let config = RagConfig::default().with_embedding_model(model);
+        let json = serde_json::to_string(&config).unwrap();
+        let parsed: RagConfig = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.embedding_model.dimensions(), 768);
+    }
 }
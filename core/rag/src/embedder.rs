@@ -1,17 +1,104 @@
 //! Embedding generation with fastembed.
 //!
-//! Wraps fastembed for local embedding generation.
+//! Wraps fastembed for local embedding generation. Other backends (e.g. a
+//! remote embedding service) implement [`EmbeddingBackend`].
+
+use std::sync::{Arc, Mutex};
 
 use fastembed::{EmbeddingModel as FastEmbedModel, InitOptions, TextEmbedding};
 
 use crate::config::EmbeddingModel;
 use crate::error::{RagError, Result};
-use crate::types::Chunk;
+use crate::types::{estimate_tokens, Chunk};
+
+/// Counts the tokens of a text, the way an embedding model's tokenizer does.
+pub type TokenCounter = Arc<dyn Fn(&str) -> usize + Send + Sync>;
+
+/// A source of text embeddings.
+///
+/// Implement this to plug in an embedding provider the crate does not know
+/// about, then pass it to [`crate::RagConfig::with_embedding_backend`]. Calls
+/// run on a blocking thread, so implementations may block (e.g. on HTTP).
+pub trait EmbeddingBackend: Send {
+    /// Dimension of every vector this backend returns.
+    fn dimension(&self) -> usize;
+
+    /// Embed texts, returning one vector per text in the same order.
+    fn embed_texts(&mut self, texts: Vec<String>) -> Result<Vec<Vec<f32>>>;
+
+    /// Token counter of the model's tokenizer, used to size chunks in
+    /// [`crate::SizeUnit::Tokens`]. None (the default) when the tokenizer
+    /// is not available locally.
+    fn token_counter(&self) -> Option<TokenCounter> {
+        None
+    }
+
+    /// Embed a single query.
+    fn embed_query(&mut self, query: &str) -> Result<Vec<f32>> {
+        self.embed_texts(vec![query.to_string()])?
+            .into_iter()
+            .next()
+            .ok_or_else(|| RagError::Embedding("No embedding generated".to_string()))
+    }
+
+    /// Embed chunks and return them with their embeddings.
+    fn embed_chunks(&mut self, chunks: &[Chunk]) -> Result<Vec<(Chunk, Vec<f32>)>> {
+        if chunks.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let texts: Vec<String> = chunks.iter().map(|c| c.text.clone()).collect();
+        let embeddings = self.embed_texts(texts)?;
+        if embeddings.len() != chunks.len() {
+            return Err(RagError::Embedding(format!(
+                "Backend returned {} embeddings for {} chunks",
+                embeddings.len(),
+                chunks.len()
+            )));
+        }
+
+        Ok(chunks.iter().cloned().zip(embeddings).collect())
+    }
+}
+
+/// Shared handle to an embedding backend.
+///
+/// Cheap to clone; all clones use the same backend.
+#[derive(Clone)]
+pub struct EmbeddingBackendHandle(pub(crate) Arc<Mutex<Box<dyn EmbeddingBackend>>>);
+
+impl EmbeddingBackendHandle {
+    /// Wrap a backend.
+    pub fn new(backend: Box<dyn EmbeddingBackend>) -> Self {
+        Self(Arc::new(Mutex::new(backend)))
+    }
+
+    /// Dimension reported by the backend.
+    pub fn dimension(&self) -> Result<usize> {
+        let backend = self.0.lock()
+            .map_err(|_| RagError::Embedding("Failed to lock embedder".to_string()))?;
+        Ok(backend.dimension())
+    }
+}
+
+impl std::fmt::Debug for EmbeddingBackendHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let mut debug = f.debug_struct("EmbeddingBackendHandle");
+        if let Ok(dimension) = self.dimension() {
+            debug.field("dimension", &dimension);
+        }
+        debug.finish_non_exhaustive()
+    }
+}
+
+/// Splits a text into the model's tokens.
+type TokenizeFn = Arc<dyn Fn(&str) -> Result<Vec<String>> + Send + Sync>;
 
 /// Text embedder.
 pub struct Embedder {
     model: TextEmbedding,
     model_type: EmbeddingModel,
+    tokenize: TokenizeFn,
 }
 
 impl Embedder {
@@ -23,10 +110,11 @@ impl Embedder {
     /// Create embedder with specific model.
     pub fn with_model(model_type: EmbeddingModel) -> Result<Self> {
         // Map our config enum to fastembed's enum
-        let fast_model = match model_type {
+        let fast_model = match &model_type {
             EmbeddingModel::AllMiniLmL6V2 => FastEmbedModel::AllMiniLML6V2,
             EmbeddingModel::BgeSmallEnV15 => FastEmbedModel::BGESmallENV15,
             EmbeddingModel::BgeBaseEnV15 => FastEmbedModel::BGEBaseENV15,
+            EmbeddingModel::Custom { name, dimension } => resolve_custom(name, *dimension)?,
         };
 
         let options = InitOptions::new(fast_model).with_show_download_progress(true);
@@ -35,7 +123,31 @@ impl Embedder {
             RagError::Embedding(format!("Failed to initialize embedding model: {e}"))
         })?;
 
-        Ok(Self { model, model_type })
+        let tokenize = untruncated_tokenizer(&model)?;
+        Ok(Self {
+            model,
+            model_type,
+            tokenize,
+        })
+    }
+
+    /// Split `text` into the model's tokens, special tokens included.
+    ///
+    /// The text is not truncated, so a result longer than the model's input
+    /// limit (512 tokens for the default models) means the embedding would
+    /// only cover its start.
+    pub fn tokenize(&self, text: &str) -> Result<Vec<String>> {
+        (self.tokenize)(text)
+    }
+
+    /// Number of tokens the model sees for `text`, before truncation.
+    ///
+    /// Falls back to an estimate of four bytes per token if the tokenizer
+    /// fails.
+    pub fn count_tokens(&self, text: &str) -> usize {
+        self.tokenize(text)
+            .map(|tokens| tokens.len())
+            .unwrap_or_else(|_| estimate_tokens(text))
     }
 
     /// Get the model type.
@@ -94,6 +206,63 @@ impl Embedder {
     }
 }
 
+impl EmbeddingBackend for Embedder {
+    fn dimension(&self) -> usize {
+        Embedder::dimension(self)
+    }
+
+    fn embed_texts(&mut self, texts: Vec<String>) -> Result<Vec<Vec<f32>>> {
+        Embedder::embed_texts(self, texts)
+    }
+
+    fn embed_query(&mut self, query: &str) -> Result<Vec<f32>> {
+        Embedder::embed_query(self, query)
+    }
+
+    fn token_counter(&self) -> Option<TokenCounter> {
+        let tokenize = Arc::clone(&self.tokenize);
+        Some(Arc::new(move |text: &str| {
+            tokenize(text)
+                .map(|tokens| tokens.len())
+                .unwrap_or_else(|_| estimate_tokens(text))
+        }))
+    }
+}
+
+/// The model's tokenizer without the truncation and padding fastembed
+/// configures for embedding, so texts over the limit are counted in full.
+fn untruncated_tokenizer(model: &TextEmbedding) -> Result<TokenizeFn> {
+    let mut tokenizer = model.tokenizer.clone();
+    tokenizer.with_padding(None);
+    tokenizer
+        .with_truncation(None)
+        .map_err(|e| RagError::Embedding(format!("Failed to configure tokenizer: {e}")))?;
+
+    Ok(Arc::new(move |text: &str| {
+        tokenizer
+            .encode(text, true)
+            .map(|encoding| encoding.get_tokens().to_vec())
+            .map_err(|e| RagError::Embedding(format!("Tokenization failed: {e}")))
+    }))
+}
+
+/// Find a fastembed model by its model code and check its dimension.
+fn resolve_custom(name: &str, dimension: usize) -> Result<FastEmbedModel> {
+    let info = TextEmbedding::list_supported_models()
+        .into_iter()
+        .find(|info| info.model_code == name)
+        .ok_or_else(|| RagError::Embedding(format!("Unknown fastembed model: {name}")))?;
+
+    if info.dim != dimension {
+        return Err(RagError::Embedding(format!(
+            "Model {name} produces {} dimensions, configured {dimension}",
+            info.dim
+        )));
+    }
+
+    Ok(info.model)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -123,6 +292,20 @@ mod tests {
         assert_eq!(embeddings[1].len(), 384);
     }
 
+    #[test]
+    fn test_count_tokens() {
+        let embedder = Embedder::new().unwrap();
+
+        let tokens = embedder.tokenize("Hello world").unwrap();
+        assert_eq!(tokens, vec!["[CLS]", "hello", "world", "[SEP]"]);
+        assert_eq!(embedder.count_tokens("Hello world"), 4);
+
+        // Counted past the model's 512-token input limit
+        let long = "word ".repeat(1000);
+        assert_eq!(embedder.count_tokens(&long), 1002);
+        assert_eq!(embedder.token_counter().unwrap()(&long), 1002);
+    }
+
     #[test]
     fn test_dimension() {
         assert_eq!(EmbeddingModel::AllMiniLmL6V2.dimensions(), 384);
@@ -130,6 +313,13 @@ mod tests {
         assert_eq!(EmbeddingModel::BgeBaseEnV15.dimensions(), 768);
     }
 
+    #[test]
+    fn test_resolve_custom_model() {
+        assert!(resolve_custom("nomic-ai/nomic-embed-text-v1.5", 768).is_ok());
+        assert!(resolve_custom("nomic-ai/nomic-embed-text-v1.5", 384).is_err());
+        assert!(resolve_custom("not-a/real-model", 384).is_err());
+    }
+
     #[test]
     fn test_empty_texts() {
         // This doesn't require model since we short-circuit
@@ -4,51 +4,166 @@
 //! Uses spawn_blocking for CPU-intensive embedding operations to avoid
 //! blocking the async runtime.
 
+use crate::cache::{CachedBackend, EmbeddingCache, EmbeddingCacheStats};
 use crate::chunker::{Chunker, ChunkingStrategy};
-use crate::config::RagConfig;
-use crate::embedder::Embedder;
+use crate::config::{
+    DocumentAggregation, EmbeddingCacheConfig, EmbeddingModel, HnswConfig, RagConfig, SimilarityMetric,
+    SizeUnit,
+};
+use crate::embedder::{Embedder, EmbeddingBackend};
 use crate::error::{RagError, Result};
+use crate::reranker::{CrossEncoderReranker, Reranker};
 use crate::store::VectorStore;
-use crate::types::{Document, SearchResult};
+use crate::types::{
+    cosine_similarity, Chunk, Document, DocumentResult, IndexReport, RagStats, SearchExplain, SearchResult,
+    UpsertDelta,
+};
+use futures::StreamExt;
+use std::collections::HashMap;
 use std::sync::{Arc, Mutex};
 
+/// Embedder shared with blocking tasks.
+type SharedEmbedder = Arc<Mutex<Box<dyn EmbeddingBackend>>>;
+
+/// Reranker shared with blocking tasks.
+type SharedReranker = Arc<Mutex<Box<dyn Reranker>>>;
+
+/// Candidates fetched per path in hybrid search, as a multiple of the limit.
+const HYBRID_POOL_FACTOR: usize = 3;
+
+/// Chunk hits fetched in document search, as a multiple of the documents wanted.
+const DOCUMENT_POOL_FACTOR: usize = 5;
+
+/// Candidates fetched for MMR selection, as a multiple of the limit.
+const MMR_POOL_FACTOR: usize = 4;
+
 /// Main RAG engine combining all components.
+///
+/// Besides its own index, an engine can hold named collections (see
+/// [`RagEngine::add_collection`]), each with its own embedding model and
+/// vector table.
 pub struct RagEngine {
     chunker: Chunker,
-    embedder: Arc<Mutex<Embedder>>,
+    embedder: SharedEmbedder,
+    cache: Option<Arc<EmbeddingCache>>,
+    reranker: Option<SharedReranker>,
     store: VectorStore,
     config: RagConfig,
+    collections: HashMap<String, RagEngine>,
 }
 
 impl RagEngine {
     /// Create a new RAG engine with the given config.
     pub async fn new(config: RagConfig) -> Result<Self> {
-        let chunker = Chunker::with_config(config.chunking.clone());
-        let embedder = Embedder::with_model(config.embedding_model.clone())?;
+        let (embedder, cache) = Self::open_embedder(&config)?;
+        let chunker = Self::open_chunker(&config, &embedder, ChunkingStrategy::default())?;
+        let reranker = Self::open_reranker(&config)?;
         let store = VectorStore::open(config.clone()).await?;
 
         Ok(Self {
             chunker,
-            embedder: Arc::new(Mutex::new(embedder)),
+            embedder,
+            cache,
+            reranker,
             store,
             config,
+            collections: HashMap::new(),
         })
     }
 
     /// Create engine with custom chunking strategy.
     pub async fn with_strategy(config: RagConfig, strategy: ChunkingStrategy) -> Result<Self> {
-        let chunker = Chunker::with_config(config.chunking.clone()).with_strategy(strategy);
-        let embedder = Embedder::with_model(config.embedding_model.clone())?;
+        let (embedder, cache) = Self::open_embedder(&config)?;
+        let chunker = Self::open_chunker(&config, &embedder, strategy)?;
+        let reranker = Self::open_reranker(&config)?;
         let store = VectorStore::open(config.clone()).await?;
 
         Ok(Self {
             chunker,
-            embedder: Arc::new(Mutex::new(embedder)),
+            embedder,
+            cache,
+            reranker,
             store,
             config,
+            collections: HashMap::new(),
         })
     }
 
+    /// Create the chunker, sizing chunks with the embedder's tokenizer when
+    /// sizes are in tokens.
+    fn open_chunker(config: &RagConfig, embedder: &SharedEmbedder, strategy: ChunkingStrategy) -> Result<Chunker> {
+        let chunker = Chunker::with_config(config.chunking.clone()).with_strategy(strategy);
+        if config.chunking.size_unit != SizeUnit::Tokens {
+            return Ok(chunker);
+        }
+
+        let counter = embedder
+            .lock()
+            .map_err(|_| RagError::Embedding("Failed to lock embedder".to_string()))?
+            .token_counter()
+            .ok_or_else(|| {
+                RagError::Config(
+                    "Chunk sizes are in tokens but the embedding backend has no tokenizer \
+                     (EmbeddingBackend::token_counter)"
+                        .to_string(),
+                )
+            })?;
+        Ok(chunker.with_token_counter(counter))
+    }
+
+    /// Open the embedding backend, behind the embedding cache when enabled.
+    fn open_embedder(config: &RagConfig) -> Result<(SharedEmbedder, Option<Arc<EmbeddingCache>>)> {
+        let backend = Self::open_backend(config)?;
+        let Some(cache_config) = &config.embedding_cache else {
+            return Ok((backend, None));
+        };
+
+        let cache = Arc::new(EmbeddingCache::open(cache_config)?);
+        let cached: Box<dyn EmbeddingBackend> = Box::new(CachedBackend::new(
+            backend,
+            Arc::clone(&cache),
+            config.embedding_model.fastembed_name(),
+        ));
+        Ok((Arc::new(Mutex::new(cached)), Some(cache)))
+    }
+
+    /// Use the configured backend, or fastembed for `embedding_model`.
+    ///
+    /// A custom backend must report the dimension the vector index is built
+    /// with (`embedding_model.dimensions()`).
+    fn open_backend(config: &RagConfig) -> Result<SharedEmbedder> {
+        let Some(backend) = &config.embedding_backend else {
+            let embedder: Box<dyn EmbeddingBackend> =
+                Box::new(Embedder::with_model(config.embedding_model.clone())?);
+            return Ok(Arc::new(Mutex::new(embedder)));
+        };
+
+        let dimension = backend.dimension()?;
+        let expected = config.embedding_model.dimensions();
+        if dimension != expected {
+            return Err(RagError::Config(format!(
+                "Embedding backend produces {dimension} dimensions but the vector index expects {expected}; \
+                 describe the backend with EmbeddingModel::Custom"
+            )));
+        }
+
+        Ok(Arc::clone(&backend.0))
+    }
+
+    /// Use the configured reranker, or a cross-encoder, when reranking is on.
+    fn open_reranker(config: &RagConfig) -> Result<Option<SharedReranker>> {
+        if config.search.rerank_top_n.is_none() {
+            return Ok(None);
+        }
+        if let Some(reranker) = &config.reranker {
+            return Ok(Some(Arc::clone(&reranker.0)));
+        }
+
+        let reranker: Box<dyn Reranker> =
+            Box::new(CrossEncoderReranker::with_model(config.search.reranker_model)?);
+        Ok(Some(Arc::new(Mutex::new(reranker))))
+    }
+
     /// Get the configuration.
     pub fn config(&self) -> &RagConfig {
         &self.config
@@ -57,33 +172,196 @@ impl RagEngine {
     /// Index a document.
     ///
     /// Chunks the document, generates embeddings, and stores in vector DB.
-    /// Uses spawn_blocking for CPU-intensive embedding to avoid blocking async runtime.
+    /// Append-only: indexing the same document id twice keeps both sets of
+    /// chunks (unless `dedup_threshold` is set). Use [`RagEngine::upsert`]
+    /// to replace an edited document.
+    ///
+    /// Returns the number of chunks inserted.
     pub async fn index(&mut self, document: &Document) -> Result<usize> {
+        Ok(self.index_with_report(document).await?.indexed)
+    }
+
+    /// Like [`Self::index`], also reporting the chunks skipped as
+    /// near-duplicates when `dedup_threshold` is set.
+    pub async fn index_with_report(&mut self, document: &Document) -> Result<IndexReport> {
+        let chunks_with_embeddings = self.embed_document(document).await?;
+        let (chunks_with_embeddings, deduplicated) = self.deduplicate(chunks_with_embeddings).await?;
+        if chunks_with_embeddings.is_empty() {
+            return Ok(IndexReport {
+                indexed: 0,
+                deduplicated,
+            });
+        }
+
+        let indexed = chunks_with_embeddings.len();
+
+        // Store in vector DB
+        self.store.insert(chunks_with_embeddings).await?;
+
+        Ok(IndexReport { indexed, deduplicated })
+    }
+
+    /// Drop chunks whose cosine similarity to an indexed chunk, or to an
+    /// earlier chunk of `chunks`, exceeds `dedup_threshold`.
+    ///
+    /// Costs one nearest-neighbour query per chunk. Returns the kept chunks
+    /// and how many were dropped.
+    async fn deduplicate(
+        &self,
+        chunks: Vec<(Chunk, Vec<f32>)>,
+    ) -> Result<(Vec<(Chunk, Vec<f32>)>, usize)> {
+        let Some(threshold) = self.config.dedup_threshold else {
+            return Ok((chunks, 0));
+        };
+
+        let mut kept: Vec<(Chunk, Vec<f32>)> = Vec::with_capacity(chunks.len());
+        let mut skipped = 0;
+        for (chunk, embedding) in chunks {
+            let mut duplicate = kept
+                .iter()
+                .any(|(_, other)| cosine_similarity(other, &embedding) > threshold);
+            if !duplicate {
+                let nearest = self
+                    .store
+                    .search_ranked(embedding.clone(), Some(1), None, SimilarityMetric::Cosine)
+                    .await?;
+                duplicate = nearest.first().is_some_and(|hit| hit.score > threshold);
+            }
+
+            if duplicate {
+                skipped += 1;
+            } else {
+                kept.push((chunk, embedding));
+            }
+        }
+
+        if skipped > 0 {
+            tracing::debug!(skipped, threshold, "Skipped near-duplicate chunks");
+        }
+        Ok((kept, skipped))
+    }
+
+    /// Index a document, replacing the chunks of any previous version.
+    ///
+    /// The old chunks are deleted and the new ones inserted in a single
+    /// transaction, so searches never see a mix of both versions.
+    pub async fn upsert(&self, document: &Document) -> Result<UpsertDelta> {
+        let chunks_with_embeddings = self.embed_document(document).await?;
+        let (removed, added) = self
+            .store
+            .replace(&document.id, document.metadata.clone(), chunks_with_embeddings)
+            .await?;
+
+        Ok(UpsertDelta { added, removed })
+    }
+
+    /// Index many documents, embedding their chunks in batches.
+    ///
+    /// The chunks of all documents are embedded `indexing.batch_size` at a
+    /// time, with up to `indexing.concurrency` batches in flight, and each
+    /// document's chunks are inserted in one statement as soon as they are
+    /// all embedded. Like [`Self::index`], this only appends.
+    ///
+    /// Returns one result per document, in order: its chunk count, or why it
+    /// failed. A failing document does not stop the others, though an
+    /// embedding failure fails every document with chunks in that batch.
+    pub async fn index_batch(&self, documents: &[Document]) -> Vec<Result<usize>> {
+        let indexing = &self.config.indexing;
+        let mut results: Vec<Option<Result<usize>>> = documents.iter().map(|_| None).collect();
+        let mut remaining = vec![0; documents.len()];
+        let mut embedded: Vec<Vec<(Chunk, Vec<f32>)>> = documents.iter().map(|_| Vec::new()).collect();
+
+        // Chunk every document, remembering which document each chunk is from
+        let mut chunks = Vec::new();
+        for (owner, document) in documents.iter().enumerate() {
+            match self.chunker.chunk(document) {
+                Ok(doc_chunks) if doc_chunks.is_empty() => results[owner] = Some(Ok(0)),
+                Ok(doc_chunks) => {
+                    remaining[owner] = doc_chunks.len();
+                    chunks.extend(doc_chunks.into_iter().map(|chunk| (owner, chunk)));
+                }
+                Err(e) => results[owner] = Some(Err(e)),
+            }
+        }
+
+        let mut batches = Vec::new();
+        let mut chunks = chunks.into_iter().peekable();
+        while chunks.peek().is_some() {
+            let batch: Vec<(usize, Chunk)> = chunks.by_ref().take(indexing.batch_size.max(1)).collect();
+            batches.push(batch);
+        }
+
+        let mut embeddings = futures::stream::iter(batches)
+            .map(|batch| async move {
+                let (owners, chunks): (Vec<usize>, Vec<Chunk>) = batch.into_iter().unzip();
+                (owners, self.embed_chunks(chunks).await)
+            })
+            .buffered(indexing.concurrency.max(1));
+
+        while let Some((owners, outcome)) = embeddings.next().await {
+            let pairs = match outcome {
+                Ok(pairs) => pairs,
+                Err(e) => {
+                    for owner in owners {
+                        if results[owner].is_none() {
+                            results[owner] = Some(Err(batch_error(&e)));
+                            embedded[owner].clear();
+                        }
+                    }
+                    continue;
+                }
+            };
+
+            for (owner, pair) in owners.into_iter().zip(pairs) {
+                if results[owner].is_some() {
+                    continue; // An earlier batch of this document failed
+                }
+                embedded[owner].push(pair);
+                remaining[owner] -= 1;
+                if remaining[owner] == 0 {
+                    let doc_chunks = std::mem::take(&mut embedded[owner]);
+                    results[owner] = Some(self.insert_deduplicated(doc_chunks).await);
+                }
+            }
+        }
+
+        results.into_iter().map(|result| result.unwrap_or(Ok(0))).collect()
+    }
+
+    /// Insert one document's chunks, minus near-duplicates, returning how
+    /// many were inserted.
+    async fn insert_deduplicated(&self, chunks: Vec<(Chunk, Vec<f32>)>) -> Result<usize> {
+        let (chunks, _) = self.deduplicate(chunks).await?;
+        let count = chunks.len();
+        self.store.insert(chunks).await?;
+        Ok(count)
+    }
+
+    /// Chunk a document and embed its chunks.
+    async fn embed_document(&self, document: &Document) -> Result<Vec<(Chunk, Vec<f32>)>> {
         // Chunk the document (fast, doesn't need spawn_blocking)
         let chunks = self.chunker.chunk(document)?;
+        self.embed_chunks(chunks).await
+    }
 
+    /// Embed chunks, returning them with their embeddings.
+    ///
+    /// Uses spawn_blocking for CPU-intensive embedding to avoid blocking async runtime.
+    async fn embed_chunks(&self, chunks: Vec<Chunk>) -> Result<Vec<(Chunk, Vec<f32>)>> {
         if chunks.is_empty() {
-            return Ok(0);
+            return Ok(vec![]);
         }
 
         // Generate embeddings in blocking task
         let embedder = Arc::clone(&self.embedder);
-        let chunks_clone = chunks.clone();
-        
-        let chunks_with_embeddings = tokio::task::spawn_blocking(move || {
+
+        tokio::task::spawn_blocking(move || {
             let mut embedder = embedder.lock()
                 .map_err(|_| RagError::Embedding("Failed to lock embedder".to_string()))?;
-            embedder.embed_chunks(&chunks_clone)
+            embedder.embed_chunks(&chunks)
         })
         .await
-        .map_err(|e| RagError::Embedding(format!("Embedding task failed: {e}")))??;
-
-        let count = chunks_with_embeddings.len();
-
-        // Store in vector DB
-        self.store.insert(chunks_with_embeddings).await?;
-
-        Ok(count)
+        .map_err(|e| RagError::Embedding(format!("Embedding task failed: {e}")))?
     }
 
     /// Index multiple documents.
@@ -98,20 +376,314 @@ impl RagEngine {
     }
 
     /// Search for relevant chunks.
+    ///
+    /// With `SearchConfig::rerank_top_n` set, that many vector candidates are
+    /// reranked and the best `limit` returned; `SearchResult::score` is then
+    /// the rerank score and `vector_score` the original similarity.
     /// Uses spawn_blocking for CPU-intensive embedding to avoid blocking async runtime.
     pub async fn search(&mut self, query: &str, limit: Option<usize>) -> Result<Vec<SearchResult>> {
+        self.retrieve(query, limit, None).await
+    }
+
+    /// Search for relevant chunks whose document metadata matches `filter`.
+    ///
+    /// The filter is pushed down into the vector query (so `limit` counts
+    /// matching chunks) and combined with `SearchConfig::filter` if set.
+    /// Example: `{"project": "whytcard", "lang": {"$in": ["rust", "go"]}}`.
+    pub async fn search_filtered(
+        &mut self,
+        query: &str,
+        limit: Option<usize>,
+        filter: &serde_json::Value,
+    ) -> Result<Vec<SearchResult>> {
+        self.retrieve(query, limit, Some(filter)).await
+    }
+
+    /// Search ranked by `metric` instead of `SearchConfig::metric`.
+    ///
+    /// The cosine index serves dot-product ranking only over normalized
+    /// embeddings (`RagConfig::normalized_embeddings`); otherwise this fails
+    /// with [`RagError::Config`].
+    pub async fn search_with_metric(
+        &self,
+        query: &str,
+        limit: Option<usize>,
+        metric: SimilarityMetric,
+    ) -> Result<Vec<SearchResult>> {
+        let query_embedding = self.embed_query(query).await?;
+        self.retrieve_embedded(query, query_embedding, limit, None, metric)
+            .await
+    }
+
+    /// Hybrid keyword + vector search.
+    ///
+    /// Runs a BM25 keyword search next to the vector search and ranks the
+    /// union by `hybrid_alpha * vector + (1 - hybrid_alpha) * lexical`, each
+    /// score normalized so the best hit of its path scores 1.0. Finds exact
+    /// tokens (error codes, API names) that embeddings miss. With reranking
+    /// enabled, the fused candidates are reranked.
+    ///
+    /// `filter` restricts both paths like in [`Self::search_filtered`].
+    pub async fn search_hybrid(
+        &self,
+        query: &str,
+        limit: Option<usize>,
+        filter: Option<&serde_json::Value>,
+    ) -> Result<Vec<SearchResult>> {
+        let search = &self.config.search;
+        let limit = limit.unwrap_or(search.default_limit).min(search.max_limit);
+        let alpha = search.hybrid_alpha.clamp(0.0, 1.0);
+        let top_n = match &self.reranker {
+            Some(_) => search.rerank_top_n.unwrap_or(limit).max(limit),
+            None => limit,
+        };
+        let pool = (top_n * HYBRID_POOL_FACTOR).min(search.max_limit).max(top_n);
+
+        // Skip a path that carries no weight
+        let vector = if alpha > 0.0 {
+            let query_embedding = self.embed_query(query).await?;
+            self.store.search_filtered(query_embedding, Some(pool), filter).await?
+        } else {
+            Vec::new()
+        };
+        let lexical = if alpha < 1.0 {
+            self.store.search_text(query, pool, filter).await?
+        } else {
+            Vec::new()
+        };
+
+        let mut candidates = fuse(vector, lexical, alpha);
+        candidates.truncate(top_n);
+        match &self.reranker {
+            Some(reranker) => Self::rerank(Arc::clone(reranker), query, candidates, limit).await,
+            None => Ok(candidates),
+        }
+    }
+
+    /// Search for relevant documents rather than chunks.
+    ///
+    /// Retrieves chunk hits like [`Self::search`], groups them by document
+    /// and scores each document per `SearchConfig::document_aggregation`,
+    /// so several near-identical chunks of one document take a single
+    /// slot. Returns the best `top_k` documents, each with its best chunk.
+    pub async fn search_documents(&self, query: &str, top_k: usize) -> Result<Vec<DocumentResult>> {
+        let pool = (top_k * DOCUMENT_POOL_FACTOR)
+            .min(self.config.search.max_limit)
+            .max(top_k);
+        let hits = self.retrieve(query, Some(pool), None).await?;
+
+        let mut documents = group_by_document(hits, self.config.search.document_aggregation);
+        documents.truncate(top_k);
+        Ok(documents)
+    }
+
+    /// Search like [`Self::search`] and report how the results were found.
+    ///
+    /// For debugging retrieval: next to the results (whose `distance`,
+    /// `vector_score` and chunk metadata are kept), returns the query
+    /// embedding norm and the number of chunks the vector search examined.
+    pub async fn search_explain(&self, query: &str, limit: Option<usize>) -> Result<SearchExplain> {
+        let query_embedding = self.embed_query(query).await?;
+        let query_norm = query_embedding.iter().map(|x| x * x).sum::<f32>().sqrt();
+        let candidates = self.store.count_candidates(None).await?;
+        let metric = self.config.search.metric;
+        let results = self
+            .retrieve_embedded(query, query_embedding, limit, None, metric)
+            .await?;
+        let ef = self
+            .config
+            .search
+            .ef_search
+            .filter(|_| metric == SimilarityMetric::Cosine)
+            .map(|ef| ef.max(limit.unwrap_or(self.config.search.default_limit)));
+
+        Ok(SearchExplain {
+            results,
+            query_norm,
+            candidates,
+            ef,
+        })
+    }
+
+    /// Vector search, followed by reranking when enabled.
+    async fn retrieve(
+        &self,
+        query: &str,
+        limit: Option<usize>,
+        filter: Option<&serde_json::Value>,
+    ) -> Result<Vec<SearchResult>> {
+        let query_embedding = self.embed_query(query).await?;
+        self.retrieve_embedded(query, query_embedding, limit, filter, self.config.search.metric)
+            .await
+    }
+
+    /// [`Self::retrieve`] with the query already embedded, ranked by `metric`.
+    async fn retrieve_embedded(
+        &self,
+        query: &str,
+        query_embedding: Vec<f32>,
+        limit: Option<usize>,
+        filter: Option<&serde_json::Value>,
+        metric: SimilarityMetric,
+    ) -> Result<Vec<SearchResult>> {
+        if let Some(lambda) = self.config.search.mmr_lambda {
+            return self
+                .retrieve_diverse(query, query_embedding, limit, filter, metric, lambda)
+                .await;
+        }
+
+        let Some(reranker) = &self.reranker else {
+            return self.store.search_ranked(query_embedding, limit, filter, metric).await;
+        };
+
+        let limit = limit
+            .unwrap_or(self.config.search.default_limit)
+            .min(self.config.search.max_limit);
+        let top_n = self.config.search.rerank_top_n.unwrap_or(limit).max(limit);
+        let candidates = self
+            .store
+            .search_ranked(query_embedding, Some(top_n), filter, metric)
+            .await?;
+
+        Self::rerank(Arc::clone(reranker), query, candidates, limit).await
+    }
+
+    /// Fetch a larger candidate pool, then pick `limit` diverse results by MMR.
+    ///
+    /// Relevance is the result score (the rerank score when reranking is on);
+    /// similarity between results uses the stored chunk embeddings.
+    async fn retrieve_diverse(
+        &self,
+        query: &str,
+        query_embedding: Vec<f32>,
+        limit: Option<usize>,
+        filter: Option<&serde_json::Value>,
+        metric: SimilarityMetric,
+        lambda: f32,
+    ) -> Result<Vec<SearchResult>> {
+        let limit = limit
+            .unwrap_or(self.config.search.default_limit)
+            .min(self.config.search.max_limit);
+        let pool = (limit * MMR_POOL_FACTOR).max(self.config.search.rerank_top_n.unwrap_or(0));
+        let (mut candidates, embeddings): (Vec<SearchResult>, Vec<Vec<f32>>) = self
+            .store
+            .search_with_embeddings(query_embedding, Some(pool), filter, metric)
+            .await?
+            .into_iter()
+            .unzip();
+
+        if let Some(reranker) = &self.reranker {
+            let scores = Self::rerank_scores(Arc::clone(reranker), query, &candidates).await?;
+            for (result, score) in candidates.iter_mut().zip(scores) {
+                result.score = score;
+            }
+        }
+
+        Ok(mmr_select(candidates, &embeddings, lambda.clamp(0.0, 1.0), limit))
+    }
+
+    /// Rescore candidates with the reranker and keep the best `limit`.
+    async fn rerank(
+        reranker: SharedReranker,
+        query: &str,
+        mut candidates: Vec<SearchResult>,
+        limit: usize,
+    ) -> Result<Vec<SearchResult>> {
+        let scores = Self::rerank_scores(reranker, query, &candidates).await?;
+        for (result, score) in candidates.iter_mut().zip(scores) {
+            result.score = score;
+        }
+        candidates.sort_by(|a, b| b.score.total_cmp(&a.score));
+        candidates.truncate(limit);
+        Ok(candidates)
+    }
+
+    /// Reranker scores of candidates, in candidate order.
+    async fn rerank_scores(
+        reranker: SharedReranker,
+        query: &str,
+        candidates: &[SearchResult],
+    ) -> Result<Vec<f32>> {
+        if candidates.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let query_owned = query.to_string();
+        let texts: Vec<String> = candidates.iter().map(|r| r.chunk.text.clone()).collect();
+
+        let scores = tokio::task::spawn_blocking(move || {
+            let mut reranker = reranker.lock()
+                .map_err(|_| RagError::Embedding("Failed to lock reranker".to_string()))?;
+            reranker.rerank(&query_owned, &texts)
+        })
+        .await
+        .map_err(|e| RagError::Embedding(format!("Reranking task failed: {e}")))??;
+
+        if scores.len() != candidates.len() {
+            return Err(RagError::Embedding(format!(
+                "Reranker returned {} scores for {} candidates",
+                scores.len(),
+                candidates.len()
+            )));
+        }
+        Ok(scores)
+    }
+
+    /// Embed a query with the engine's model.
+    /// Uses spawn_blocking for CPU-intensive embedding to avoid blocking async runtime.
+    pub async fn embed_query(&self, query: &str) -> Result<Vec<f32>> {
         let embedder = Arc::clone(&self.embedder);
         let query_owned = query.to_string();
-        
-        let query_embedding = tokio::task::spawn_blocking(move || {
+
+        tokio::task::spawn_blocking(move || {
             let mut embedder = embedder.lock()
                 .map_err(|_| RagError::Embedding("Failed to lock embedder".to_string()))?;
             embedder.embed_query(&query_owned)
         })
         .await
-        .map_err(|e| RagError::Embedding(format!("Embedding task failed: {e}")))??;
-        
-        self.store.search(query_embedding, limit).await
+        .map_err(|e| RagError::Embedding(format!("Embedding task failed: {e}")))?
+    }
+
+    /// Embed multiple texts with the engine's model.
+    /// Uses spawn_blocking for CPU-intensive embedding to avoid blocking async runtime.
+    pub async fn embed_texts(&self, texts: Vec<String>) -> Result<Vec<Vec<f32>>> {
+        let embedder = Arc::clone(&self.embedder);
+
+        tokio::task::spawn_blocking(move || {
+            let mut embedder = embedder.lock()
+                .map_err(|_| RagError::Embedding("Failed to lock embedder".to_string()))?;
+            embedder.embed_texts(texts)
+        })
+        .await
+        .map_err(|e| RagError::Embedding(format!("Embedding task failed: {e}")))?
+    }
+
+    /// Count the tokens of `text` with the embedding model's tokenizer,
+    /// e.g. to pack chunks into the model's input limit or a prompt budget.
+    ///
+    /// `None` when the embedding backend has no tokenizer.
+    pub fn count_tokens(&self, text: &str) -> Result<Option<usize>> {
+        let counter = self
+            .embedder
+            .lock()
+            .map_err(|_| RagError::Embedding("Failed to lock embedder".to_string()))?
+            .token_counter();
+        Ok(counter.map(|count| count(text)))
+    }
+
+    /// Hit/miss counters of the embedding cache, `None` when it is disabled.
+    pub fn cache_stats(&self) -> Result<Option<EmbeddingCacheStats>> {
+        self.cache.as_ref().map(|cache| cache.stats()).transpose()
+    }
+
+    /// Empty the embedding cache (memory and file) and reset its counters.
+    ///
+    /// Does nothing when the cache is disabled.
+    pub fn clear_cache(&self) -> Result<()> {
+        match &self.cache {
+            Some(cache) => cache.clear(),
+            None => Ok(()),
+        }
     }
 
     /// Search and return only the text content.
@@ -149,22 +721,162 @@ impl RagEngine {
         self.store.delete_by_document(document_id).await
     }
 
+    /// Delete every document whose metadata matches `filter`, with its chunks.
+    ///
+    /// Uses the same filter syntax as `search_filtered`, e.g.
+    /// `{"project": "old"}`. Returns the number of chunks removed, so
+    /// re-running the same filter returns 0.
+    pub async fn delete_by_filter(&self, filter: &serde_json::Value) -> Result<usize> {
+        self.store.delete_by_filter(filter).await
+    }
+
+    /// Rebuild the vector index to restore search recall after heavy churn.
+    ///
+    /// Reuses the stored embeddings, so nothing is re-embedded. Returns the
+    /// number of chunks in the rebuilt index.
+    pub async fn optimize_index(&self) -> Result<usize> {
+        self.store.rebuild_index().await
+    }
+
+    /// Recreate the vector index with the configured `RagConfig::hnsw`.
+    ///
+    /// The index keeps the `m`/`ef_construction` it was created with; call
+    /// this after changing them on an existing database. A full reindex
+    /// of the stored embeddings, so nothing is re-embedded. Returns the
+    /// number of chunks in the new index.
+    pub async fn redefine_index(&self) -> Result<usize> {
+        self.store.redefine_index().await
+    }
+
     /// Get number of indexed chunks.
     pub async fn count(&self) -> Result<usize> {
         self.store.count().await
     }
 
-    /// Reindex a document (delete old chunks, index new).
+    /// Number of chunks indexed for a document (0 if it is not indexed).
+    pub async fn count_chunks_for(&self, document_id: &str) -> Result<usize> {
+        self.store.count_chunks_for(document_id).await
+    }
+
+    /// Size of the index: document and chunk counts (count queries, no
+    /// rows are loaded), embedding model and disk usage.
+    pub async fn stats(&self) -> Result<RagStats> {
+        Ok(RagStats {
+            documents: self.store.count_documents().await?,
+            chunks: self.store.count().await?,
+            dimension: self.config.embedding_model.dimensions(),
+            model: self.config.embedding_model.fastembed_name().to_string(),
+            disk_bytes: self.store.disk_usage()?,
+        })
+    }
+
+    /// Reindex a document (replace old chunks with new ones).
+    ///
+    /// Returns the number of chunks indexed; see [`RagEngine::upsert`] for
+    /// the full delta.
     pub async fn reindex(&mut self, document: &Document) -> Result<usize> {
-        self.store.delete_by_document(&document.id).await?;
-        self.index(document).await
+        Ok(self.upsert(document).await?.added)
+    }
+
+    /// Add a named collection embedding with `model`.
+    ///
+    /// A collection is an index of its own: its chunks live in a separate
+    /// vector table sized for `model`, next to this engine's (`db_path`
+    /// suffixed with `.{name}`, or in memory for an in-memory engine). It
+    /// shares this engine's chunking, search and cache settings.
+    pub async fn add_collection(&mut self, name: &str, model: EmbeddingModel) -> Result<()> {
+        let config = self.collection_config(name, model)?;
+        self.open_collection(name, config).await
+    }
+
+    /// Add a named collection embedding with a custom `backend`.
+    ///
+    /// `model` describes the backend, usually an `EmbeddingModel::Custom`
+    /// of the backend's dimension.
+    pub async fn add_collection_with_backend(
+        &mut self,
+        name: &str,
+        model: EmbeddingModel,
+        backend: Box<dyn EmbeddingBackend>,
+    ) -> Result<()> {
+        let config = self.collection_config(name, model)?.with_embedding_backend(backend);
+        self.open_collection(name, config).await
+    }
+
+    /// Names of the collections, sorted.
+    pub fn collections(&self) -> Vec<&str> {
+        let mut names: Vec<&str> = self.collections.keys().map(String::as_str).collect();
+        names.sort_unstable();
+        names
+    }
+
+    /// Get a collection by name.
+    pub fn collection(&self, name: &str) -> Result<&RagEngine> {
+        self.collections.get(name).ok_or_else(|| unknown_collection(name))
     }
+
+    /// Get a collection by name, mutably.
+    pub fn collection_mut(&mut self, name: &str) -> Result<&mut RagEngine> {
+        self.collections.get_mut(name).ok_or_else(|| unknown_collection(name))
+    }
+
+    /// Index a document into a collection.
+    pub async fn index_into(&mut self, collection: &str, document: &Document) -> Result<usize> {
+        self.collection_mut(collection)?.index(document).await
+    }
+
+    /// Search a collection, embedding the query with its model.
+    pub async fn search_in(
+        &mut self,
+        collection: &str,
+        query: &str,
+        limit: Option<usize>,
+    ) -> Result<Vec<SearchResult>> {
+        self.collection_mut(collection)?.search(query, limit).await
+    }
+
+    /// Config of a new collection: this engine's, with `model` and the
+    /// collection's own storage and cache file.
+    fn collection_config(&self, name: &str, model: EmbeddingModel) -> Result<RagConfig> {
+        let valid = !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_' || c == '-');
+        if !valid {
+            return Err(RagError::Config(format!(
+                "Invalid collection name '{name}': use letters, digits, '_' or '-'"
+            )));
+        }
+        if self.collections.contains_key(name) {
+            return Err(RagError::Config(format!("Collection '{name}' already exists")));
+        }
+
+        let mut config = self.config.clone();
+        config.embedding_model = model;
+        config.embedding_backend = None;
+        config.normalized_embeddings = None;
+        if !config.db_path.is_empty() && config.db_path != ":memory:" {
+            config.db_path = format!("{}.{name}", config.db_path);
+        }
+        if let Some(cache) = &mut config.embedding_cache {
+            cache.path = cache.path.take().map(|path| format!("{path}.{name}"));
+        }
+        Ok(config)
+    }
+
+    async fn open_collection(&mut self, name: &str, config: RagConfig) -> Result<()> {
+        let engine = Self::with_strategy(config, self.chunker.strategy().clone()).await?;
+        self.collections.insert(name.to_string(), engine);
+        Ok(())
+    }
+}
+
+fn unknown_collection(name: &str) -> RagError {
+    RagError::Config(format!("Unknown collection '{name}'"))
 }
 
 /// Builder for RagEngine with fluent API.
 pub struct RagEngineBuilder {
     config: RagConfig,
     strategy: ChunkingStrategy,
+    collections: Vec<(String, EmbeddingModel, Option<Box<dyn EmbeddingBackend>>)>,
 }
 
 impl RagEngineBuilder {
@@ -173,6 +885,7 @@ impl RagEngineBuilder {
         Self {
             config: RagConfig::default(),
             strategy: ChunkingStrategy::default(),
+            collections: Vec::new(),
         }
     }
 
@@ -199,6 +912,19 @@ impl RagEngineBuilder {
         self.embedding_model(model)
     }
 
+    /// Use a custom embedding backend instead of fastembed.
+    ///
+    /// `build` fails unless its dimension matches the embedding model's.
+    pub fn embedding_backend(mut self, backend: Box<dyn EmbeddingBackend>) -> Self {
+        self.config = self.config.with_embedding_backend(backend);
+        self
+    }
+
+    /// Alias for `embedding_backend` (e.g. a [`crate::RemoteEmbedder`]).
+    pub fn embedder(self, backend: Box<dyn EmbeddingBackend>) -> Self {
+        self.embedding_backend(backend)
+    }
+
     /// Set the chunking configuration.
     pub fn chunking_config(mut self, config: crate::config::ChunkingConfig) -> Self {
         self.config.chunking = config;
@@ -229,6 +955,12 @@ impl RagEngineBuilder {
         self
     }
 
+    /// Measure chunk sizes in characters or embedding model tokens.
+    pub fn size_unit(mut self, unit: SizeUnit) -> Self {
+        self.config.chunking.size_unit = unit;
+        self
+    }
+
     /// Set chunking strategy.
     pub fn chunking_strategy(mut self, strategy: ChunkingStrategy) -> Self {
         self.strategy = strategy;
@@ -247,40 +979,286 @@ impl RagEngineBuilder {
         self
     }
 
-    /// Build the engine.
-    pub async fn build(self) -> Result<RagEngine> {
-        RagEngine::with_strategy(self.config, self.strategy).await
+    /// Rerank this many vector candidates before returning the search limit.
+    pub fn rerank_top_n(mut self, top_n: usize) -> Self {
+        self.config.search.rerank_top_n = Some(top_n);
+        self
     }
-}
 
-impl Default for RagEngineBuilder {
-    fn default() -> Self {
-        Self::new()
+    /// Use a custom reranker instead of the cross-encoder.
+    ///
+    /// Only takes effect together with `rerank_top_n`.
+    pub fn reranker(mut self, reranker: Box<dyn Reranker>) -> Self {
+        self.config = self.config.with_reranker(reranker);
+        self
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+    /// Set the vector weight of hybrid search (1.0 pure vector, 0.0 pure keyword).
+    pub fn hybrid_alpha(mut self, alpha: f32) -> Self {
+        self.config.search.hybrid_alpha = alpha;
+        self
+    }
 
-    #[test]
-    fn test_builder_config() {
-        let builder = RagEngineBuilder::new()
-            .db_path("/tmp/test.lance")
-            .table_name("test")
-            .chunk_size(256)
-            .chunk_overlap(32);
+    /// Set how many chunks `index_batch` embeds per call.
+    pub fn embed_batch_size(mut self, size: usize) -> Self {
+        self.config.indexing.batch_size = size;
+        self
+    }
 
-        assert_eq!(builder.config.db_path, "/tmp/test.lance");
-        assert_eq!(builder.config.table_name, "test");
-        assert_eq!(builder.config.chunking.chunk_size, 256);
-        assert_eq!(builder.config.chunking.chunk_overlap, 32);
+    /// Set how many embedding batches `index_batch` keeps in flight.
+    pub fn index_concurrency(mut self, concurrency: usize) -> Self {
+        self.config.indexing.concurrency = concurrency;
+        self
     }
 
-    #[tokio::test]
-    async fn test_engine_creation() {
-        let temp_dir = tempfile::TempDir::new().unwrap();
-        let db_path = temp_dir.path().join("test.lance").to_string_lossy().to_string();
+    /// Set how document search scores a document from its chunks.
+    pub fn document_aggregation(mut self, aggregation: DocumentAggregation) -> Self {
+        self.config.search.document_aggregation = aggregation;
+        self
+    }
+
+    /// Cache embeddings by model and text hash.
+    pub fn embedding_cache(mut self, cache: EmbeddingCacheConfig) -> Self {
+        self.config.embedding_cache = Some(cache);
+        self
+    }
+
+    /// Return only these metadata keys with search results.
+    pub fn metadata_fields(mut self, fields: Vec<String>) -> Self {
+        self.config.search.metadata_fields = Some(fields);
+        self
+    }
+
+    /// Skip indexing chunks more similar than `threshold` to indexed ones.
+    pub fn dedup_threshold(mut self, threshold: f32) -> Self {
+        self.config.dedup_threshold = Some(threshold);
+        self
+    }
+
+    /// Diversify search results with MMR (1.0 = plain relevance order).
+    pub fn mmr_lambda(mut self, lambda: f32) -> Self {
+        self.config.search.mmr_lambda = Some(lambda);
+        self
+    }
+
+    /// Set the similarity ranking vector results.
+    pub fn similarity_metric(mut self, metric: SimilarityMetric) -> Self {
+        self.config.search.metric = metric;
+        self
+    }
+
+    /// Set the HNSW index links per node and build candidate list size.
+    pub fn hnsw(mut self, m: usize, ef_construction: usize) -> Self {
+        self.config.hnsw = HnswConfig { m, ef_construction };
+        self
+    }
+
+    /// Search the HNSW graph with this breadth instead of exactly.
+    pub fn ef_search(mut self, ef: usize) -> Self {
+        self.config.search.ef_search = Some(ef);
+        self
+    }
+
+    /// Declare whether embeddings are unit length (enables dot-product search).
+    pub fn normalized_embeddings(mut self, normalized: bool) -> Self {
+        self.config.normalized_embeddings = Some(normalized);
+        self
+    }
+
+    /// Set a document metadata filter applied to every search.
+    pub fn search_filter(mut self, filter: serde_json::Value) -> Self {
+        self.config.search.filter = Some(filter);
+        self
+    }
+
+    /// Add a named collection embedding with `model` (see [`RagEngine::add_collection`]).
+    pub fn collection(mut self, name: impl Into<String>, model: EmbeddingModel) -> Self {
+        self.collections.push((name.into(), model, None));
+        self
+    }
+
+    /// Add a named collection embedding with a custom backend.
+    pub fn collection_with_backend(
+        mut self,
+        name: impl Into<String>,
+        model: EmbeddingModel,
+        backend: Box<dyn EmbeddingBackend>,
+    ) -> Self {
+        self.collections.push((name.into(), model, Some(backend)));
+        self
+    }
+
+    /// Build the engine.
+    pub async fn build(self) -> Result<RagEngine> {
+        let mut engine = RagEngine::with_strategy(self.config, self.strategy).await?;
+        for (name, model, backend) in self.collections {
+            match backend {
+                Some(backend) => engine.add_collection_with_backend(&name, model, backend).await?,
+                None => engine.add_collection(&name, model).await?,
+            }
+        }
+        Ok(engine)
+    }
+}
+
+impl Default for RagEngineBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Greedily pick `limit` results by Maximal Marginal Relevance.
+///
+/// Each step takes the candidate maximizing `lambda * score - (1 - lambda) *
+/// max similarity to the picked results`; ties keep candidate order, so
+/// lambda 1.0 returns the candidates by descending score.
+fn mmr_select(
+    candidates: Vec<SearchResult>,
+    embeddings: &[Vec<f32>],
+    lambda: f32,
+    limit: usize,
+) -> Vec<SearchResult> {
+    let mut remaining: Vec<usize> = (0..candidates.len()).collect();
+    let mut picked: Vec<usize> = Vec::with_capacity(limit.min(candidates.len()));
+    // Highest similarity of each candidate to any picked result
+    let mut redundancy = vec![0.0f32; candidates.len()];
+
+    while picked.len() < limit {
+        let mmr = |i: usize| lambda * candidates[i].score - (1.0 - lambda) * redundancy[i];
+        let Some((position, &best)) = remaining
+            .iter()
+            .enumerate()
+            .reduce(|a, b| if mmr(*b.1) > mmr(*a.1) { b } else { a })
+        else {
+            break;
+        };
+        remaining.remove(position);
+        picked.push(best);
+
+        for &i in &remaining {
+            redundancy[i] = redundancy[i].max(cosine_similarity(&embeddings[i], &embeddings[best]));
+        }
+    }
+
+    let mut candidates: Vec<Option<SearchResult>> = candidates.into_iter().map(Some).collect();
+    picked
+        .into_iter()
+        .filter_map(|i| candidates[i].take())
+        .collect()
+}
+
+/// Merge vector and keyword hits per chunk and rank them by weighted score.
+///
+/// Chunks missing from a path score 0 on it; chunks scoring 0 overall are
+/// dropped, so alpha 1.0 / 0.0 return only vector / keyword hits.
+fn fuse(vector: Vec<SearchResult>, lexical: Vec<(Chunk, f32)>, alpha: f32) -> Vec<SearchResult> {
+    let max_vector = vector.iter().map(|r| r.vector_score).fold(0.0, f32::max);
+    let max_lexical = lexical.iter().map(|(_, score)| *score).fold(0.0, f32::max);
+    let normalize = |score: f32, max: f32| if max > 0.0 { score.max(0.0) / max } else { 0.0 };
+
+    let mut merged: HashMap<(String, usize), SearchResult> = HashMap::new();
+    for result in vector {
+        merged.insert((result.chunk.document_id.clone(), result.chunk.index), result);
+    }
+    for (chunk, score) in lexical {
+        let key = (chunk.document_id.clone(), chunk.index);
+        // A keyword-only hit has no vector score; report maximal cosine distance
+        let result = merged
+            .entry(key)
+            .or_insert_with(|| SearchResult::new(chunk, 0.0, 1.0));
+        result.lexical_score = normalize(score, max_lexical);
+    }
+
+    let mut results: Vec<SearchResult> = merged
+        .into_values()
+        .map(|mut result| {
+            result.score = alpha * normalize(result.vector_score, max_vector)
+                + (1.0 - alpha) * result.lexical_score;
+            result
+        })
+        .filter(|result| result.score > 0.0)
+        .collect();
+    results.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.chunk.document_id.cmp(&b.chunk.document_id))
+            .then_with(|| a.chunk.index.cmp(&b.chunk.index))
+    });
+    results
+}
+
+/// Copy of a batch's embedding error for one of its documents.
+fn batch_error(error: &RagError) -> RagError {
+    match error {
+        RagError::Embedding(message) => RagError::Embedding(message.clone()),
+        other => RagError::Embedding(other.to_string()),
+    }
+}
+
+/// Group chunk hits by document and rank the documents by aggregated score.
+fn group_by_document(hits: Vec<SearchResult>, aggregation: DocumentAggregation) -> Vec<DocumentResult> {
+    // Hits arrive best first, so the first hit of a document is its best chunk
+    let mut documents: Vec<DocumentResult> = Vec::new();
+    let mut positions: HashMap<String, usize> = HashMap::new();
+    for hit in hits {
+        match positions.get(&hit.chunk.document_id) {
+            Some(&i) => {
+                let document = &mut documents[i];
+                document.max_score = document.max_score.max(hit.score);
+                document.mean_score += hit.score;
+                document.chunk_count += 1;
+            }
+            None => {
+                positions.insert(hit.chunk.document_id.clone(), documents.len());
+                documents.push(DocumentResult {
+                    document_id: hit.chunk.document_id.clone(),
+                    score: 0.0,
+                    max_score: hit.score,
+                    mean_score: hit.score,
+                    chunk_count: 1,
+                    best_chunk: hit.chunk,
+                });
+            }
+        }
+    }
+
+    for document in &mut documents {
+        document.mean_score /= document.chunk_count as f32;
+        document.score = match aggregation {
+            DocumentAggregation::Max => document.max_score,
+            DocumentAggregation::Mean => document.mean_score,
+        };
+    }
+    documents.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.document_id.cmp(&b.document_id))
+    });
+    documents
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_builder_config() {
+        let builder = RagEngineBuilder::new()
+            .db_path("/tmp/test.lance")
+            .table_name("test")
+            .chunk_size(256)
+            .chunk_overlap(32);
+
+        assert_eq!(builder.config.db_path, "/tmp/test.lance");
+        assert_eq!(builder.config.table_name, "test");
+        assert_eq!(builder.config.chunking.chunk_size, 256);
+        assert_eq!(builder.config.chunking.chunk_overlap, 32);
+    }
+
+    #[tokio::test]
+    async fn test_engine_creation() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.lance").to_string_lossy().to_string();
 
         let engine = RagEngineBuilder::new()
             .db_path(db_path)
@@ -313,6 +1291,567 @@ mod tests {
         assert!(!results.is_empty());
     }
 
+    #[tokio::test]
+    async fn test_embed_query_and_texts() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("test.lance").to_string_lossy().to_string();
+
+        let engine = RagEngineBuilder::new()
+            .db_path(db_path)
+            .build()
+            .await
+            .unwrap();
+
+        let query = engine.embed_query("async runtime").await.unwrap();
+        assert_eq!(query.len(), engine.config().embedding_model.dimensions());
+
+        let texts = engine
+            .embed_texts(vec!["Tokio runtime".to_string(), "Cooking recipes".to_string()])
+            .await
+            .unwrap();
+        assert_eq!(texts.len(), 2);
+        assert!(
+            crate::cosine_similarity(&query, &texts[0]) > crate::cosine_similarity(&query, &texts[1])
+        );
+    }
+
+    /// Deterministic bag-of-letters embeddings, standing in for a remote service.
+    struct LetterBackend;
+
+    impl EmbeddingBackend for LetterBackend {
+        fn dimension(&self) -> usize {
+            26
+        }
+
+        fn embed_texts(&mut self, texts: Vec<String>) -> Result<Vec<Vec<f32>>> {
+            Ok(texts
+                .iter()
+                .map(|text| {
+                    let mut v = vec![0.0; 26];
+                    for c in text.to_ascii_lowercase().bytes().filter(u8::is_ascii_lowercase) {
+                        v[(c - b'a') as usize] += 1.0;
+                    }
+                    v
+                })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_custom_embedding_backend() {
+        let mismatched = RagEngineBuilder::new()
+            .db_path(":memory:")
+            .embedding_backend(Box::new(LetterBackend))
+            .build()
+            .await;
+        assert!(matches!(mismatched, Err(RagError::Config(_))));
+
+        let mut engine = RagEngineBuilder::new()
+            .db_path(":memory:")
+            .embedding_model(crate::config::EmbeddingModel::custom("letters", 26))
+            .embedding_backend(Box::new(LetterBackend))
+            .min_chunk_size(10)
+            .build()
+            .await
+            .unwrap();
+
+        let doc = Document::new("zzzz zzzz zzzz zzzz zzzz");
+        assert!(engine.index(&doc).await.unwrap() > 0);
+        let results = engine.search("zz", Some(1)).await.unwrap();
+        assert_eq!(results[0].chunk.document_id, doc.id);
+        assert_eq!(engine.embed_query("abc").await.unwrap().len(), 26);
+    }
+
+    /// Two-dimensional embeddings: share of vowels and of consonants.
+    struct VowelBackend;
+
+    impl EmbeddingBackend for VowelBackend {
+        fn dimension(&self) -> usize {
+            2
+        }
+
+        fn embed_texts(&mut self, texts: Vec<String>) -> Result<Vec<Vec<f32>>> {
+            Ok(texts
+                .iter()
+                .map(|text| {
+                    let letters = text.to_ascii_lowercase().bytes().filter(u8::is_ascii_lowercase).collect::<Vec<_>>();
+                    let vowels = letters.iter().filter(|c| b"aeiouy".contains(c)).count() as f32;
+                    vec![vowels + 0.1, letters.len() as f32 - vowels + 0.1]
+                })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_collections_with_their_own_model() {
+        let temp_dir = tempfile::TempDir::new().unwrap();
+        let db_path = temp_dir.path().join("vectors").to_string_lossy().to_string();
+
+        let mut engine = RagEngineBuilder::new()
+            .db_path(&db_path)
+            .embedding_model(crate::config::EmbeddingModel::custom("letters", 26))
+            .embedding_backend(Box::new(LetterBackend))
+            .min_chunk_size(10)
+            .collection_with_backend("vowels", EmbeddingModel::custom("vowels", 2), Box::new(VowelBackend))
+            .build()
+            .await
+            .unwrap();
+        assert_eq!(engine.collections(), vec!["vowels"]);
+
+        let doc = Document::new("aaaa eeee iiii oooo uuuu");
+        assert!(engine.index_into("vowels", &doc).await.unwrap() > 0);
+        assert_eq!(engine.count().await.unwrap(), 0);
+
+        let results = engine.search_in("vowels", "aeiou", Some(1)).await.unwrap();
+        assert_eq!(results[0].chunk.document_id, doc.id);
+        let collection = engine.collection("vowels").unwrap();
+        assert_eq!(collection.stats().await.unwrap().dimension, 2);
+        assert_eq!(collection.config().db_path, format!("{db_path}.vowels"));
+        assert_eq!(engine.embed_query("abc").await.unwrap().len(), 26);
+
+        assert!(matches!(engine.search_in("missing", "aeiou", None).await, Err(RagError::Config(_))));
+        let duplicate = engine
+            .add_collection_with_backend("vowels", EmbeddingModel::custom("vowels", 2), Box::new(VowelBackend))
+            .await;
+        assert!(matches!(duplicate, Err(RagError::Config(_))));
+        let invalid = engine.add_collection("bad name", EmbeddingModel::default()).await;
+        assert!(matches!(invalid, Err(RagError::Config(_))));
+    }
+
+    #[test]
+    fn test_mmr_select() {
+        let candidates: Vec<SearchResult> = [0.9, 0.89, 0.5]
+            .iter()
+            .enumerate()
+            .map(|(i, &score)| SearchResult::new(Chunk::new(format!("doc{i}"), 0, "text", 0, 4), score, 1.0 - score))
+            .collect();
+        let embeddings = vec![vec![1.0, 0.0], vec![1.0, 0.0], vec![0.0, 1.0]];
+        let ids = |results: Vec<SearchResult>| -> Vec<String> {
+            results.into_iter().map(|r| r.chunk.document_id).collect()
+        };
+
+        let plain = mmr_select(candidates.clone(), &embeddings, 1.0, 3);
+        assert_eq!(ids(plain), ["doc0", "doc1", "doc2"]);
+
+        // The duplicate of the best result loses to a less relevant distinct one
+        let diverse = mmr_select(candidates, &embeddings, 0.5, 2);
+        assert_eq!(ids(diverse), ["doc0", "doc2"]);
+
+        assert!(mmr_select(vec![], &[], 0.5, 5).is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_search_mmr() {
+        let engine_with = |lambda: f32| {
+            RagEngineBuilder::new()
+                .db_path(":memory:")
+                .embedding_model(crate::config::EmbeddingModel::custom("letters", 26))
+                .embedding_backend(Box::new(LetterBackend))
+                .min_chunk_size(5)
+                .mmr_lambda(lambda)
+                .build()
+        };
+
+        let mut engine = engine_with(0.3).await.unwrap();
+        assert!(engine.search("apple", Some(2)).await.unwrap().is_empty());
+
+        let docs = [
+            Document::new("apple apple pie"),
+            Document::new("apple apple pies"),
+            Document::new("apple tart"),
+        ];
+        engine.index_many(&docs).await.unwrap();
+        let diverse = engine.search("apple", Some(2)).await.unwrap();
+        assert_eq!(diverse[0].chunk.document_id, docs[0].id);
+        assert_eq!(diverse[1].chunk.document_id, docs[2].id);
+
+        let mut plain = engine_with(1.0).await.unwrap();
+        plain.index_many(&docs).await.unwrap();
+        let results = plain.search("apple", Some(2)).await.unwrap();
+        assert_eq!(results[0].chunk.document_id, docs[0].id);
+        assert_eq!(results[1].chunk.document_id, docs[1].id);
+    }
+
+    #[tokio::test]
+    async fn test_embedding_cache() {
+        let mut engine = RagEngineBuilder::new()
+            .db_path(":memory:")
+            .embedding_model(crate::config::EmbeddingModel::custom("letters", 26))
+            .embedding_backend(Box::new(LetterBackend))
+            .embedding_cache(EmbeddingCacheConfig::default())
+            .min_chunk_size(5)
+            .build()
+            .await
+            .unwrap();
+
+        let doc = Document::new("cached chunk text");
+        engine.index(&doc).await.unwrap();
+        engine.index(&doc).await.unwrap();
+        let stats = engine.cache_stats().unwrap().unwrap();
+        assert_eq!((stats.hits, stats.misses), (1, 1));
+
+        engine.search("chunk", Some(1)).await.unwrap();
+        engine.search("chunk", Some(1)).await.unwrap();
+        let stats = engine.cache_stats().unwrap().unwrap();
+        assert_eq!((stats.hits, stats.misses, stats.entries), (2, 2, 2));
+
+        engine.clear_cache().unwrap();
+        assert_eq!(engine.cache_stats().unwrap().unwrap().entries, 0);
+    }
+
+    #[tokio::test]
+    async fn test_remote_embedder_dimension_checked_at_build() {
+        let remote = || crate::RemoteEmbedder::new("http://127.0.0.1:9/v1/embeddings", "text-embedding-3-small", 1536).unwrap();
+
+        let mismatched = RagEngineBuilder::new()
+            .db_path(":memory:")
+            .embedder(Box::new(remote()))
+            .build()
+            .await;
+        assert!(matches!(mismatched, Err(RagError::Config(_))));
+
+        let engine = RagEngineBuilder::new()
+            .db_path(":memory:")
+            .embedding_model(crate::config::EmbeddingModel::custom("text-embedding-3-small", 1536))
+            .embedder(Box::new(remote()))
+            .build()
+            .await;
+        assert!(engine.is_ok());
+    }
+
+    #[tokio::test]
+    async fn test_token_sized_chunks_use_backend_tokenizer() {
+        /// Letter embeddings, one token per word
+        struct WordTokens;
+
+        impl EmbeddingBackend for WordTokens {
+            fn dimension(&self) -> usize {
+                26
+            }
+
+            fn embed_texts(&mut self, texts: Vec<String>) -> Result<Vec<Vec<f32>>> {
+                LetterBackend.embed_texts(texts)
+            }
+
+            fn token_counter(&self) -> Option<crate::TokenCounter> {
+                Some(Arc::new(|text: &str| text.split_whitespace().count()))
+            }
+        }
+
+        let builder = |backend: Box<dyn EmbeddingBackend>| {
+            RagEngineBuilder::new()
+                .db_path(":memory:")
+                .embedding_model(crate::config::EmbeddingModel::custom("letters", 26))
+                .embedding_backend(backend)
+                .size_unit(SizeUnit::Tokens)
+                .chunk_size(5)
+                .chunk_overlap(0)
+                .min_chunk_size(1)
+        };
+
+        let without_tokenizer = builder(Box::new(LetterBackend)).build().await;
+        assert!(matches!(without_tokenizer, Err(RagError::Config(_))));
+
+        let mut engine = builder(Box::new(WordTokens)).build().await.unwrap();
+        let doc = Document::new("one two three. four five six. seven eight nine. ten eleven twelve.");
+        assert_eq!(engine.index(&doc).await.unwrap(), 4);
+        assert_eq!(engine.count_tokens("ten eleven twelve").unwrap(), Some(3));
+    }
+
+    #[tokio::test]
+    async fn test_index_skips_near_duplicates() {
+        let mut engine = RagEngineBuilder::new()
+            .db_path(":memory:")
+            .embedding_model(crate::config::EmbeddingModel::custom("letters", 26))
+            .embedding_backend(Box::new(LetterBackend))
+            .chunk_size(40)
+            .chunk_overlap(0)
+            .min_chunk_size(5)
+            .dedup_threshold(0.99)
+            .build()
+            .await
+            .unwrap();
+
+        let license = "Licensed under the MIT license.";
+        let first = engine
+            .index_with_report(&Document::new(format!("{license}\n\nRust ownership prevents data races.")))
+            .await
+            .unwrap();
+        assert_eq!(first, IndexReport { indexed: 2, deduplicated: 0 });
+
+        let second = engine
+            .index_with_report(&Document::new(format!("{license}\n\nCoffee brewing needs hot water.")))
+            .await
+            .unwrap();
+        assert_eq!(second, IndexReport { indexed: 1, deduplicated: 1 });
+
+        // Batch indexing skips them as well
+        let results = engine
+            .index_batch(&[Document::new(format!("Tea leaves steep in warm water.\n\n{license}"))])
+            .await;
+        assert_eq!(results[0].as_ref().unwrap(), &1);
+        assert_eq!(engine.count().await.unwrap(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_metadata_fields_projection() {
+        let mut engine = RagEngineBuilder::new()
+            .db_path(":memory:")
+            .embedding_model(crate::config::EmbeddingModel::custom("letters", 26))
+            .embedding_backend(Box::new(LetterBackend))
+            .min_chunk_size(5)
+            .metadata_fields(vec!["source".to_string()])
+            .build()
+            .await
+            .unwrap();
+
+        let doc = Document::new("Rust ownership prevents data races.")
+            .with_metadata(serde_json::json!({
+                "source": "book.md",
+                "provenance": { "crawler": "v2", "fetched": "2026-01-01" }
+            }));
+        engine.index(&doc).await.unwrap();
+
+        let expected = Some(serde_json::json!({ "source": "book.md" }));
+        let hits = engine.search("ownership", Some(1)).await.unwrap();
+        assert_eq!(hits[0].chunk.metadata, expected);
+        let hybrid = engine.search_hybrid("ownership", Some(1), None).await.unwrap();
+        assert_eq!(hybrid[0].chunk.metadata, expected);
+
+        // Filters still see the dropped fields
+        let filtered = engine
+            .search_filtered("ownership", Some(1), &serde_json::json!({ "provenance.crawler": "v2" }))
+            .await
+            .unwrap();
+        assert_eq!(filtered.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_stats_and_chunk_counts() {
+        let mut engine = RagEngineBuilder::new()
+            .db_path(":memory:")
+            .embedding_model(crate::config::EmbeddingModel::custom("letters", 26))
+            .embedding_backend(Box::new(LetterBackend))
+            .chunk_size(40)
+            .chunk_overlap(0)
+            .min_chunk_size(5)
+            .build()
+            .await
+            .unwrap();
+
+        let doc = Document::new("Rust ownership prevents data races.\n\nBorrowing allows shared references.")
+            .with_id("rust");
+        engine.index(&doc).await.unwrap();
+        engine.index(&Document::new("Coffee brewing needs hot water.")).await.unwrap();
+
+        assert_eq!(engine.count_chunks_for("rust").await.unwrap(), 2);
+        assert_eq!(engine.count_chunks_for("missing").await.unwrap(), 0);
+
+        let stats = engine.stats().await.unwrap();
+        assert_eq!(
+            stats,
+            RagStats {
+                documents: 2,
+                chunks: 3,
+                dimension: 26,
+                model: "letters".to_string(),
+                disk_bytes: None,
+            }
+        );
+    }
+
+    #[tokio::test]
+    async fn test_search_with_metric() {
+        let letters = |normalized: bool| {
+            RagEngineBuilder::new()
+                .db_path(":memory:")
+                .embedding_model(crate::config::EmbeddingModel::custom("letters", 26))
+                .embedding_backend(Box::new(LetterBackend))
+                .normalized_embeddings(normalized)
+                .min_chunk_size(5)
+                .build()
+        };
+        let doc = Document::new("zzzz zzzz zzzz");
+
+        // Letter counts are not unit length: the cosine index can't rank by dot
+        let mut engine = letters(false).await.unwrap();
+        engine.index(&doc).await.unwrap();
+        let result = engine.search_with_metric("zz", Some(1), SimilarityMetric::Dot).await;
+        assert!(matches!(result, Err(RagError::Config(_))));
+        let results = engine.search_with_metric("zz", Some(1), SimilarityMetric::Cosine).await.unwrap();
+        assert_eq!(results[0].chunk.document_id, doc.id);
+
+        let mut engine = letters(true).await.unwrap();
+        engine.index(&doc).await.unwrap();
+        let results = engine.search_with_metric("zz", Some(1), SimilarityMetric::Dot).await.unwrap();
+        assert_eq!(results[0].chunk.document_id, doc.id);
+        // The score is the raw dot product: 2 * 12 letter z
+        assert!((results[0].score - 24.0).abs() < 1e-3);
+    }
+
+    #[tokio::test]
+    async fn test_search_filtered() {
+        let mut engine = RagEngineBuilder::new()
+            .db_path(":memory:")
+            .min_chunk_size(10)
+            .build()
+            .await
+            .unwrap();
+
+        let rust = Document::new("Rust ownership rules prevent data races at compile time.")
+            .with_metadata_field("project", "compiler");
+        let web = Document::new("Rust web servers handle many concurrent requests with async.")
+            .with_metadata_field("project", "web");
+        engine.index_many(&[rust.clone(), web.clone()]).await.unwrap();
+
+        let filter = serde_json::json!({ "project": "web" });
+        let results = engine.search_filtered("Rust", Some(5), &filter).await.unwrap();
+        assert!(!results.is_empty());
+        assert!(results.iter().all(|r| r.chunk.document_id == web.id));
+    }
+
+    /// Prefers chunks mentioning a keyword, whatever the vector score.
+    struct KeywordReranker(&'static str);
+
+    impl Reranker for KeywordReranker {
+        fn rerank(&mut self, _query: &str, documents: &[String]) -> Result<Vec<f32>> {
+            Ok(documents
+                .iter()
+                .map(|d| if d.contains(self.0) { 0.9 } else { 0.1 })
+                .collect())
+        }
+    }
+
+    #[tokio::test]
+    async fn test_search_reranks_top_candidates() {
+        let mut engine = RagEngineBuilder::new()
+            .db_path(":memory:")
+            .embedding_model(crate::config::EmbeddingModel::custom("letters", 26))
+            .embedding_backend(Box::new(LetterBackend))
+            .reranker(Box::new(KeywordReranker("bamboo")))
+            .rerank_top_n(3)
+            .min_chunk_size(10)
+            .build()
+            .await
+            .unwrap();
+
+        for text in [
+            "zzzz zzzz zzzz zzzz zzzz",
+            "zzzz zzzz zzzz bamboo",
+            "aaaa bbbb cccc dddd eeee",
+        ] {
+            engine.index(&Document::new(text)).await.unwrap();
+        }
+
+        let results = engine.search("zzzz", Some(1)).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].chunk.text.contains("bamboo"));
+        assert_eq!(results[0].score, 0.9);
+        assert!(results[0].vector_score < 1.0);
+
+        // Without rerank_top_n the reranker is not used
+        let mut plain = RagEngineBuilder::new()
+            .db_path(":memory:")
+            .embedding_model(crate::config::EmbeddingModel::custom("letters", 26))
+            .embedding_backend(Box::new(LetterBackend))
+            .reranker(Box::new(KeywordReranker("bamboo")))
+            .min_chunk_size(10)
+            .build()
+            .await
+            .unwrap();
+        plain.index(&Document::new("zzzz zzzz zzzz zzzz zzzz")).await.unwrap();
+        let results = plain.search("zzzz", Some(1)).await.unwrap();
+        assert_eq!(results[0].score, results[0].vector_score);
+    }
+
+    #[tokio::test]
+    async fn test_search_hybrid_finds_rare_tokens() {
+        let build = |alpha: f32| {
+            RagEngineBuilder::new()
+                .db_path(":memory:")
+                .embedding_model(crate::config::EmbeddingModel::custom("letters", 26))
+                .embedding_backend(Box::new(LetterBackend))
+                .hybrid_alpha(alpha)
+                .min_chunk_size(10)
+                .build()
+        };
+        let mut engine = build(0.5).await.unwrap();
+
+        // Letter embeddings of "E0425" are all 'e': the first document wins
+        // on vectors, only the second contains the token
+        let vowels = Document::new("eeee eeee eeee eeee eeee");
+        let error = Document::new("compiler error E0425 in main");
+        engine.index_many(&[vowels.clone(), error.clone()]).await.unwrap();
+
+        let vector = engine.search("E0425", Some(1)).await.unwrap();
+        assert_eq!(vector[0].chunk.document_id, vowels.id);
+
+        let hybrid = engine.search_hybrid("E0425", Some(1), None).await.unwrap();
+        assert_eq!(hybrid[0].chunk.document_id, error.id);
+        assert_eq!(hybrid[0].lexical_score, 1.0);
+        assert!(hybrid[0].vector_score < 1.0);
+
+        // The keyword index follows deletes
+        engine.delete_document(&error.id).await.unwrap();
+        let hybrid = engine.search_hybrid("E0425", Some(2), None).await.unwrap();
+        assert_eq!(hybrid.len(), 1);
+        assert_eq!(hybrid[0].chunk.document_id, vowels.id);
+        assert_eq!(hybrid[0].lexical_score, 0.0);
+
+        // Both paths honour the metadata filter
+        let tagged = Document::new("linker error E0425 again")
+            .with_metadata(serde_json::json!({ "crate": "core" }));
+        engine.index(&tagged).await.unwrap();
+        let filter = serde_json::json!({ "crate": "core" });
+        let hybrid = engine.search_hybrid("eeee", Some(5), Some(&filter)).await.unwrap();
+        assert_eq!(hybrid.len(), 1);
+        assert_eq!(hybrid[0].chunk.document_id, tagged.id);
+
+        // alpha 1.0 is pure vector search, 0.0 pure keyword search
+        let mut pure_vector = build(1.0).await.unwrap();
+        pure_vector.index_many(&[vowels.clone(), error.clone()]).await.unwrap();
+        let results = pure_vector.search_hybrid("E0425", Some(1), None).await.unwrap();
+        assert_eq!(results[0].chunk.document_id, vowels.id);
+
+        let mut pure_keyword = build(0.0).await.unwrap();
+        pure_keyword.index_many(&[vowels.clone(), error.clone()]).await.unwrap();
+        let results = pure_keyword.search_hybrid("E0425", Some(5), None).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].chunk.document_id, error.id);
+    }
+
+    #[tokio::test]
+    async fn test_code_chunks_retrieved_by_symbol() {
+        let mut engine = RagEngineBuilder::new()
+            .db_path(":memory:")
+            .embedding_model(crate::config::EmbeddingModel::custom("letters", 26))
+            .embedding_backend(Box::new(LetterBackend))
+            .chunking_strategy(ChunkingStrategy::Code {
+                language: Some("rust".to_string()),
+            })
+            .hybrid_alpha(0.0)
+            .chunk_size(120)
+            .build()
+            .await
+            .unwrap();
+
+        let checksum = "/// CRC-32 of `data`\n\
+                        pub fn checksum_crc32(data: &[u8]) -> u32 {\n    \
+                            let mut crc = !0u32;\n    \
+                            for byte in data {\n        \
+                                crc ^= u32::from(*byte);\n    \
+                            }\n    \
+                            !crc\n\
+                        }";
+        let source = format!("use std::io;\n\nfn main() {{\n    println!(\"hello\");\n}}\n\n{checksum}\n");
+        engine.index(&Document::new(source)).await.unwrap();
+
+        let results = engine.search_hybrid("checksum_crc32", Some(1), None).await.unwrap();
+        assert_eq!(results[0].chunk.text, checksum);
+        let metadata = results[0].chunk.metadata.as_ref().unwrap();
+        assert_eq!(metadata[crate::chunker::SYMBOL_KEY], "checksum_crc32");
+    }
+
     #[tokio::test]
     async fn test_delete_document() {
         let temp_dir = tempfile::TempDir::new().unwrap();
@@ -338,4 +1877,175 @@ mod tests {
         let count_after = engine.count().await.unwrap();
         assert_eq!(count_after, 0);
     }
+
+    #[tokio::test]
+    async fn test_upsert_replaces_chunks() {
+        let mut engine = RagEngineBuilder::new()
+            .db_path(":memory:")
+            .embedding_model(crate::config::EmbeddingModel::custom("letters", 26))
+            .embedding_backend(Box::new(LetterBackend))
+            .chunk_size(40)
+            .chunk_overlap(0)
+            .min_chunk_size(5)
+            .build()
+            .await
+            .unwrap();
+
+        let mut doc = Document::new("First draft of the notes.\n\nA second paragraph here.");
+        let first = engine.upsert(&doc).await.unwrap();
+        assert_eq!(first, UpsertDelta { added: 2, removed: 0 });
+
+        // index is append-only, upsert replaces
+        engine.index(&doc).await.unwrap();
+        assert_eq!(engine.count().await.unwrap(), 4);
+
+        doc.content = "Edited notes, now a single chunk.".to_string();
+        let edited = engine.upsert(&doc).await.unwrap();
+        assert_eq!(edited, UpsertDelta { added: 1, removed: 4 });
+        assert_eq!(engine.count().await.unwrap(), 1);
+
+        let results = engine.search("notes", Some(5)).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert!(results[0].chunk.text.starts_with("Edited"));
+    }
+
+    /// Letter embeddings that fail on any text mentioning "boom".
+    struct FlakyBackend;
+
+    impl EmbeddingBackend for FlakyBackend {
+        fn dimension(&self) -> usize {
+            26
+        }
+
+        fn embed_texts(&mut self, texts: Vec<String>) -> Result<Vec<Vec<f32>>> {
+            if texts.iter().any(|t| t.contains("boom")) {
+                return Err(RagError::Embedding("backend exploded".to_string()));
+            }
+            LetterBackend.embed_texts(texts)
+        }
+    }
+
+    #[tokio::test]
+    async fn test_index_batch_isolates_failures() {
+        let engine = RagEngineBuilder::new()
+            .db_path(":memory:")
+            .embedding_model(crate::config::EmbeddingModel::custom("letters", 26))
+            .embedding_backend(Box::new(FlakyBackend))
+            .chunk_size(20)
+            .chunk_overlap(0)
+            .min_chunk_size(5)
+            .embed_batch_size(1)
+            .index_concurrency(3)
+            .build()
+            .await
+            .unwrap();
+
+        let documents = [
+            Document::new("First paragraph.\n\nSecond paragraph."),
+            Document::new("Fine start.\n\nthen boom."),
+            Document::new(""),
+            Document::new("Last document."),
+        ];
+        let results = engine.index_batch(&documents).await;
+
+        assert_eq!(results.len(), 4);
+        assert_eq!(*results[0].as_ref().unwrap(), 2);
+        assert!(matches!(&results[1], Err(RagError::Embedding(msg)) if msg == "backend exploded"));
+        assert_eq!(*results[2].as_ref().unwrap(), 0);
+        assert_eq!(*results[3].as_ref().unwrap(), 1);
+
+        // Nothing of the failed document was stored
+        assert_eq!(engine.count().await.unwrap(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_search_documents_aggregates_chunks() {
+        let strong = Document::new("zzzz zzzz.\n\nzzzz abcd.\n\nabcd efgh.");
+        let steady = Document::new("zzzz zzzy.");
+        let other = Document::new("abcd efgh.");
+
+        for (aggregation, expected) in [
+            (DocumentAggregation::Max, [&strong, &steady]),
+            (DocumentAggregation::Mean, [&steady, &strong]),
+        ] {
+            let mut engine = RagEngineBuilder::new()
+                .db_path(":memory:")
+                .embedding_model(crate::config::EmbeddingModel::custom("letters", 26))
+                .embedding_backend(Box::new(LetterBackend))
+                .chunk_size(12)
+                .chunk_overlap(0)
+                .min_chunk_size(5)
+                .document_aggregation(aggregation)
+                .build()
+                .await
+                .unwrap();
+            engine.index_many(&[strong.clone(), steady.clone(), other.clone()]).await.unwrap();
+
+            let documents = engine.search_documents("zz", 2).await.unwrap();
+            let ids: Vec<&str> = documents.iter().map(|d| d.document_id.as_str()).collect();
+            assert_eq!(ids, vec![expected[0].id.as_str(), expected[1].id.as_str()], "{aggregation:?}");
+
+            let strong_hit = documents.iter().find(|d| d.document_id == strong.id).unwrap();
+            assert_eq!(strong_hit.chunk_count, 3);
+            assert!((strong_hit.max_score - 1.0).abs() < 1e-5);
+            assert!(strong_hit.mean_score < strong_hit.max_score);
+            assert_eq!(strong_hit.best_chunk.text, "zzzz zzzz.");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hnsw_search() {
+        let mut engine = RagEngineBuilder::new()
+            .db_path(":memory:")
+            .embedding_model(crate::config::EmbeddingModel::custom("letters", 26))
+            .embedding_backend(Box::new(LetterBackend))
+            .min_chunk_size(5)
+            .hnsw(8, 64)
+            .ef_search(16)
+            .build()
+            .await
+            .unwrap();
+
+        for text in ["notes on tests", "a quiz about jazz", "something else entirely"] {
+            engine.index(&Document::new(text)).await.unwrap();
+        }
+
+        let explain = engine.search_explain("notes", Some(2)).await.unwrap();
+        assert_eq!(explain.ef, Some(16));
+        assert_eq!(explain.results[0].chunk.text, "notes on tests");
+        assert_eq!(engine.redefine_index().await.unwrap(), 3);
+        assert_eq!(engine.search("quiz jazz", Some(1)).await.unwrap()[0].chunk.text, "a quiz about jazz");
+    }
+
+    #[tokio::test]
+    async fn test_search_explain() {
+        let mut engine = RagEngineBuilder::new()
+            .db_path(":memory:")
+            .embedding_model(crate::config::EmbeddingModel::custom("letters", 26))
+            .embedding_backend(Box::new(LetterBackend))
+            .min_chunk_size(5)
+            .build()
+            .await
+            .unwrap();
+
+        for text in ["notes on tests", "a quiz about jazz", "something else entirely"] {
+            engine.index(&Document::new(text)).await.unwrap();
+        }
+
+        let explain = engine.search_explain("notes", Some(2)).await.unwrap();
+        assert_eq!(explain.candidates, 3);
+        assert_eq!(explain.ef, None);
+        // One of each of n, o, t, e, s
+        assert!((explain.query_norm - 5f32.sqrt()).abs() < 1e-5);
+
+        assert_eq!(explain.results.len(), 2);
+        assert_eq!(explain.results[0].chunk.text, "notes on tests");
+        for result in &explain.results {
+            assert!((result.score - (1.0 - result.distance)).abs() < 1e-5);
+        }
+
+        let plain = engine.search("notes", Some(2)).await.unwrap();
+        let texts = |results: &[SearchResult]| results.iter().map(|r| r.chunk.text.clone()).collect::<Vec<_>>();
+        assert_eq!(texts(&explain.results), texts(&plain));
+    }
 }
@@ -17,6 +17,10 @@ pub enum RagError {
     #[error("IO error: {0}")]
     Io(#[from] std::io::Error),
 
+    /// Text could not be extracted from a file
+    #[error("Extraction error: {0}")]
+    Extraction(String),
+
     /// Chunking error
     #[error("Chunking error: {0}")]
     Chunking(String),
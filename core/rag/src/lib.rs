@@ -1,10 +1,12 @@
 //! WhytCard RAG Module
 //!
 //! Provides Retrieval-Augmented Generation capabilities:
-//! - Document chunking
-//! - Text embeddings via fastembed
+//! - Document loading (text, Markdown, HTML, PDF) and chunking
+//! - Text embeddings via fastembed, a remote endpoint ([`RemoteEmbedder`]) or any
+//!   [`EmbeddingBackend`], with an optional cache
 //! - Vector storage via SurrealDB (unified whytcard-database)
-//! - Semantic search
+//! - Semantic search, optionally reranked by a cross-encoder ([`Reranker`])
+//! - Hybrid BM25 keyword + vector search
 //!
 //! # Architecture
 //!
@@ -40,18 +42,31 @@
 //! }
 //! ```
 
+mod cache;
 mod chunker;
 mod config;
 mod embedder;
 mod engine;
 mod error;
+mod loader;
+mod remote;
+mod reranker;
 mod store;
 mod types;
 
-pub use chunker::{Chunker, ChunkingStrategy};
-pub use config::{ChunkingConfig, EmbeddingModel, RagConfig, SearchConfig};
-pub use embedder::Embedder;
+pub use cache::EmbeddingCacheStats;
+pub use chunker::{Chunker, ChunkingStrategy, HEADING_PATH_KEY, PAGE_KEY, PAGE_OFFSETS_KEY, SYMBOL_KEY};
+pub use config::{
+    ChunkingConfig, DocumentAggregation, EmbeddingCacheConfig, EmbeddingModel, HnswConfig, IndexingConfig, RagConfig,
+    RerankerModel, SearchConfig, SimilarityMetric, SizeUnit,
+};
+pub use embedder::{Embedder, EmbeddingBackend, EmbeddingBackendHandle, TokenCounter};
 pub use engine::{RagEngine, RagEngineBuilder};
 pub use error::{RagError, Result};
+pub use remote::RemoteEmbedder;
+pub use reranker::{CrossEncoderReranker, Reranker, RerankerHandle};
 pub use store::VectorStore;
-pub use types::{Chunk, Document, SearchResult};
+pub use types::{
+    cosine_similarity, Chunk, Document, DocumentResult, IndexReport, RagStats, SearchExplain, SearchResult,
+    UpsertDelta,
+};
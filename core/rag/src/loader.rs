@@ -0,0 +1,294 @@
+//! Loading documents from files.
+//!
+//! [`Document::from_path`] picks a reader from the file extension:
+//!
+//! | Extension          | MIME type         | Text                             |
+//! |--------------------|-------------------|----------------------------------|
+//! | `.txt`             | `text/plain`      | as is                            |
+//! | `.md`, `.markdown` | `text/markdown`   | as is                            |
+//! | `.html`, `.htm`    | `text/html`       | tags, scripts and styles removed |
+//! | `.pdf`             | `application/pdf` | per page (`pdf` feature)         |
+//!
+//! PDF pages are joined with blank lines and their start offsets stored
+//! under [`PAGE_OFFSETS_KEY`], from which the chunker derives each chunk's
+//! [`crate::PAGE_KEY`].
+
+use std::path::Path;
+
+use crate::chunker::PAGE_OFFSETS_KEY;
+use crate::error::{RagError, Result};
+use crate::types::Document;
+
+/// Separator between PDF pages in the document content
+const PAGE_SEPARATOR: &str = "\n\n";
+
+/// Elements whose content is not readable text
+const SKIPPED_ELEMENTS: [&str; 6] = ["script", "style", "noscript", "template", "svg", "title"];
+
+/// Elements that start a new paragraph
+const BLOCK_ELEMENTS: [&str; 27] = [
+    "address", "article", "aside", "blockquote", "dd", "div", "dl", "dt", "figcaption", "figure",
+    "footer", "h1", "h2", "h3", "h4", "h5", "h6", "header", "hr", "li", "main", "nav", "ol", "p",
+    "pre", "section", "table",
+];
+
+/// Elements that start a new line
+const LINE_ELEMENTS: [&str; 4] = ["br", "tr", "td", "th"];
+
+pub(crate) fn load(path: &Path) -> Result<Document> {
+    let extension = path
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(str::to_ascii_lowercase)
+        .unwrap_or_default();
+
+    let (document, mime_type) = match extension.as_str() {
+        "txt" => (Document::new(std::fs::read_to_string(path)?), "text/plain"),
+        "md" | "markdown" => (Document::new(std::fs::read_to_string(path)?), "text/markdown"),
+        "html" | "htm" => {
+            let (title, text) = html_to_text(&std::fs::read_to_string(path)?);
+            let document = Document::new(text);
+            let document = match title {
+                Some(title) => document.with_title(title),
+                None => document,
+            };
+            (document, "text/html")
+        }
+        "pdf" => {
+            let pages = pdf_pages(path)?;
+            let mut offsets = Vec::with_capacity(pages.len());
+            let mut content = String::new();
+            for page in &pages {
+                if !offsets.is_empty() {
+                    content.push_str(PAGE_SEPARATOR);
+                }
+                offsets.push(content.len());
+                content.push_str(page.trim());
+            }
+            let document = Document::new(content)
+                .with_metadata_field("page_count", pages.len())
+                .with_metadata_field(PAGE_OFFSETS_KEY, offsets);
+            (document, "application/pdf")
+        }
+        _ => {
+            return Err(RagError::Extraction(format!(
+                "Unsupported file type: {} (expected .txt, .md, .html or .pdf)",
+                path.display()
+            )));
+        }
+    };
+
+    let filename = path
+        .file_name()
+        .map(|n| n.to_string_lossy().to_string())
+        .unwrap_or_default();
+    Ok(document
+        .with_path(path.to_string_lossy())
+        .with_mime_type(mime_type)
+        .with_metadata_field("source", path.to_string_lossy())
+        .with_metadata_field("filename", filename)
+        .with_metadata_field("mime_type", mime_type))
+}
+
+#[cfg(feature = "pdf")]
+fn pdf_pages(path: &Path) -> Result<Vec<String>> {
+    pdf_extract::extract_text_by_pages(path).map_err(|e| {
+        RagError::Extraction(format!("Failed to extract text from {}: {e}", path.display()))
+    })
+}
+
+#[cfg(not(feature = "pdf"))]
+fn pdf_pages(path: &Path) -> Result<Vec<String>> {
+    Err(RagError::Extraction(format!(
+        "Cannot read {}: PDF support requires the `pdf` feature",
+        path.display()
+    )))
+}
+
+/// Readable text of an HTML page, with its `<title>` if any.
+///
+/// Block elements become paragraphs separated by blank lines; other
+/// whitespace collapses to single spaces.
+fn html_to_text(html: &str) -> (Option<String>, String) {
+    let mut title = None;
+    let mut text = String::with_capacity(html.len() / 2);
+    let mut rest = html;
+
+    while let Some(open) = rest.find('<') {
+        push_text(&mut text, &rest[..open]);
+        rest = &rest[open..];
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        let Some(close) = rest.find('>') else {
+            rest = "";
+            break;
+        };
+        let tag = &rest[1..close];
+        rest = &rest[close + 1..];
+
+        let closing = tag.starts_with('/');
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default()
+            .to_ascii_lowercase();
+
+        if !closing && SKIPPED_ELEMENTS.contains(&name.as_str()) && !tag.ends_with('/') {
+            let end = find_ignore_case(rest, &format!("</{name}")).unwrap_or(rest.len());
+            if name == "title" {
+                let value = tidy(&decode_entities(&rest[..end]));
+                title = (!value.is_empty()).then_some(value);
+            }
+            rest = &rest[end..];
+            rest = rest.find('>').map_or("", |close| &rest[close + 1..]);
+        } else if BLOCK_ELEMENTS.contains(&name.as_str()) {
+            text.push_str("\n\n");
+        } else if LINE_ELEMENTS.contains(&name.as_str()) {
+            text.push('\n');
+        }
+    }
+    push_text(&mut text, rest);
+
+    (title, tidy(&decode_entities(&text)))
+}
+
+/// Append source text, turning its line breaks into spaces
+fn push_text(text: &mut String, source: &str) {
+    text.extend(source.chars().map(|c| if c.is_whitespace() { ' ' } else { c }));
+}
+
+/// Collapse spaces within lines and runs of blank lines
+fn tidy(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut blank = false;
+    for line in text.lines() {
+        let line = line.split_whitespace().collect::<Vec<_>>().join(" ");
+        if line.is_empty() {
+            blank = true;
+            continue;
+        }
+        if !result.is_empty() {
+            result.push_str(if blank { "\n\n" } else { "\n" });
+        }
+        result.push_str(&line);
+        blank = false;
+    }
+    result
+}
+
+/// Decode named and numeric character references; unknown ones are kept
+fn decode_entities(text: &str) -> String {
+    let mut result = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        result.push_str(&rest[..amp]);
+        let after = &rest[amp + 1..];
+        let decoded = after
+            .find(';')
+            .filter(|&end| end <= 10)
+            .and_then(|end| entity(&after[..end]).map(|c| (c, end)));
+        match decoded {
+            Some((c, end)) => {
+                result.push(c);
+                rest = &after[end + 1..];
+            }
+            None => {
+                result.push('&');
+                rest = after;
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+fn entity(name: &str) -> Option<char> {
+    match name {
+        "amp" => Some('&'),
+        "lt" => Some('<'),
+        "gt" => Some('>'),
+        "quot" => Some('"'),
+        "apos" => Some('\''),
+        "nbsp" => Some(' '),
+        _ => {
+            let number = name.strip_prefix('#')?;
+            let code = match number.strip_prefix(['x', 'X']) {
+                Some(hex) => u32::from_str_radix(hex, 16).ok()?,
+                None => number.parse().ok()?,
+            };
+            char::from_u32(code)
+        }
+    }
+}
+
+fn find_ignore_case(haystack: &str, needle: &str) -> Option<usize> {
+    haystack
+        .as_bytes()
+        .windows(needle.len())
+        .position(|window| window.eq_ignore_ascii_case(needle.as_bytes()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html_to_text() {
+        let html = r#"<!DOCTYPE html>
+<html><head><title>Rust &amp; Co</title>
+<style>p { color: red; }</style><script>let x = "<p>";</script></head>
+<body>
+  <!-- navigation -->
+  <h1>Ownership</h1>
+  <p>Each value has
+     an <b>owner</b>.</p>
+  <p>Values are dropped&nbsp;when the owner goes out of scope &#8212; see &lt;drop&gt;.<br>Next line</p>
+</body></html>"#;
+
+        let (title, text) = html_to_text(html);
+        assert_eq!(title.as_deref(), Some("Rust & Co"));
+        assert_eq!(
+            text,
+            "Ownership\n\nEach value has an owner.\n\n\
+             Values are dropped when the owner goes out of scope \u{2014} see <drop>.\nNext line"
+        );
+    }
+
+    #[test]
+    fn test_decode_entities_keeps_unknown() {
+        assert_eq!(decode_entities("a &copy; b & c &#x41;"), "a &copy; b & c A");
+    }
+
+    #[test]
+    fn test_load_by_extension() {
+        let temp = tempfile::tempdir().unwrap();
+
+        let text_path = temp.path().join("notes.txt");
+        std::fs::write(&text_path, "Plain notes.").unwrap();
+        let document = Document::from_path(&text_path).unwrap();
+        assert_eq!(document.content, "Plain notes.");
+        assert_eq!(document.mime_type.as_deref(), Some("text/plain"));
+        let metadata = document.metadata.unwrap();
+        assert_eq!(metadata["filename"], "notes.txt");
+        assert_eq!(metadata["mime_type"], "text/plain");
+
+        let html_path = temp.path().join("page.HTML");
+        std::fs::write(&html_path, "<p>Hello</p><p>World</p>").unwrap();
+        let document = Document::from_path(&html_path).unwrap();
+        assert_eq!(document.content, "Hello\n\nWorld");
+        assert_eq!(document.title.as_deref(), Some("page.HTML"));
+        assert_eq!(document.metadata.unwrap()["mime_type"], "text/html");
+
+        let other = temp.path().join("data.bin");
+        std::fs::write(&other, [0u8, 1]).unwrap();
+        assert!(matches!(Document::from_path(&other), Err(RagError::Extraction(_))));
+        assert!(matches!(
+            Document::from_path(&temp.path().join("missing.md")),
+            Err(RagError::Io(_))
+        ));
+    }
+}
@@ -0,0 +1,233 @@
+//! Embeddings from a remote, OpenAI-compatible endpoint.
+//!
+//! [`RemoteEmbedder`] posts `{"model", "input"}` to an embeddings URL and
+//! reads `data[].embedding` back, the format served by OpenAI, Azure OpenAI,
+//! Ollama, vLLM, LM Studio and most hosted embedding services.
+
+use std::future::Future;
+use std::time::Duration;
+
+use serde::{Deserialize, Serialize};
+
+use crate::embedder::EmbeddingBackend;
+use crate::error::{RagError, Result};
+
+/// Timeout of one embeddings request.
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(60);
+
+/// Texts sent per request by default.
+const DEFAULT_BATCH_SIZE: usize = 256;
+
+/// Embedding backend calling an OpenAI-compatible embeddings endpoint.
+///
+/// Pass it to [`crate::RagEngineBuilder::embedder`] together with an
+/// `EmbeddingModel::Custom` of the same dimension.
+pub struct RemoteEmbedder {
+    client: reqwest::Client,
+    url: String,
+    model: String,
+    api_key: Option<String>,
+    dimension: usize,
+    batch_size: usize,
+}
+
+#[derive(Serialize)]
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+    embedding: Vec<f32>,
+    #[serde(default)]
+    index: usize,
+}
+
+impl RemoteEmbedder {
+    /// Embed with `model` at `url` (e.g. `https://api.openai.com/v1/embeddings`).
+    ///
+    /// `dimension` is the size of the vectors the model returns; responses
+    /// of another size are rejected.
+    pub fn new(url: impl Into<String>, model: impl Into<String>, dimension: usize) -> Result<Self> {
+        let client = reqwest::Client::builder()
+            .timeout(REQUEST_TIMEOUT)
+            .build()
+            .map_err(|e| RagError::Config(format!("Failed to create HTTP client: {e}")))?;
+
+        Ok(Self {
+            client,
+            url: url.into(),
+            model: model.into(),
+            api_key: None,
+            dimension,
+            batch_size: DEFAULT_BATCH_SIZE,
+        })
+    }
+
+    /// Send `api_key` as a bearer token.
+    pub fn with_api_key(mut self, api_key: impl Into<String>) -> Self {
+        self.api_key = Some(api_key.into());
+        self
+    }
+
+    /// Set how many texts go in one request.
+    pub fn with_batch_size(mut self, batch_size: usize) -> Self {
+        self.batch_size = batch_size.max(1);
+        self
+    }
+
+    async fn request(&self, texts: &[String]) -> Result<Vec<Vec<f32>>> {
+        let mut request = self.client.post(&self.url).json(&EmbeddingRequest {
+            model: &self.model,
+            input: texts,
+        });
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+
+        let response = request
+            .send()
+            .await
+            .map_err(|e| RagError::Embedding(format!("Embedding request failed: {e}")))?;
+        let status = response.status();
+        if !status.is_success() {
+            let body = response.text().await.unwrap_or_default();
+            return Err(RagError::Embedding(format!("Embedding endpoint returned {status}: {body}")));
+        }
+
+        let mut response: EmbeddingResponse = response
+            .json()
+            .await
+            .map_err(|e| RagError::Embedding(format!("Invalid embedding response: {e}")))?;
+        if response.data.len() != texts.len() {
+            return Err(RagError::Embedding(format!(
+                "Endpoint returned {} embeddings for {} texts",
+                response.data.len(),
+                texts.len()
+            )));
+        }
+
+        response.data.sort_by_key(|d| d.index);
+        response
+            .data
+            .into_iter()
+            .map(|d| {
+                if d.embedding.len() != self.dimension {
+                    return Err(RagError::Embedding(format!(
+                        "Endpoint returned {} dimensions, expected {}",
+                        d.embedding.len(),
+                        self.dimension
+                    )));
+                }
+                Ok(d.embedding)
+            })
+            .collect()
+    }
+}
+
+impl EmbeddingBackend for RemoteEmbedder {
+    fn dimension(&self) -> usize {
+        self.dimension
+    }
+
+    fn embed_texts(&mut self, texts: Vec<String>) -> Result<Vec<Vec<f32>>> {
+        let mut embeddings = Vec::with_capacity(texts.len());
+        for batch in texts.chunks(self.batch_size) {
+            embeddings.extend(block_on(self.request(batch))??);
+        }
+        Ok(embeddings)
+    }
+}
+
+/// Wait for `future` on the blocking thread backends are called on.
+///
+/// Uses the engine's runtime when there is one, else a throwaway runtime.
+/// Must not be called from async code.
+fn block_on<F: Future>(future: F) -> Result<F::Output> {
+    if let Ok(handle) = tokio::runtime::Handle::try_current() {
+        return Ok(handle.block_on(future));
+    }
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()?;
+    Ok(runtime.block_on(future))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+    /// Answer one HTTP request with `body`, returning the request received.
+    async fn serve_once(listener: tokio::net::TcpListener, status: &str, body: &str) -> String {
+        let (mut stream, _) = listener.accept().await.unwrap();
+        let mut request = Vec::new();
+        let mut buf = [0u8; 4096];
+        loop {
+            let n = stream.read(&mut buf).await.unwrap();
+            request.extend_from_slice(&buf[..n]);
+            let text = String::from_utf8_lossy(&request);
+            if let Some(end) = text.find("\r\n\r\n") {
+                let length = text[..end]
+                    .lines()
+                    .find_map(|l| l.to_ascii_lowercase().strip_prefix("content-length:").map(|v| v.trim().to_string()))
+                    .and_then(|v| v.parse::<usize>().ok())
+                    .unwrap_or(0);
+                if request.len() >= end + 4 + length {
+                    break;
+                }
+            }
+        }
+
+        let response = format!(
+            "HTTP/1.1 {status}\r\ncontent-type: application/json\r\ncontent-length: {}\r\nconnection: close\r\n\r\n{body}",
+            body.len()
+        );
+        stream.write_all(response.as_bytes()).await.unwrap();
+        String::from_utf8_lossy(&request).to_string()
+    }
+
+    #[tokio::test]
+    async fn test_remote_embedder() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/v1/embeddings", listener.local_addr().unwrap());
+        // Out of order, as some servers answer
+        let body = r#"{"data": [{"index": 1, "embedding": [0.0, 1.0]}, {"index": 0, "embedding": [1.0, 0.0]}]}"#;
+        let server = tokio::spawn(serve_once(listener, "200 OK", body));
+
+        let mut embedder = RemoteEmbedder::new(url, "text-embedding-3-small", 2)
+            .unwrap()
+            .with_api_key("secret");
+        let embeddings = tokio::task::spawn_blocking(move || {
+            embedder.embed_texts(vec!["first".to_string(), "second".to_string()])
+        })
+        .await
+        .unwrap()
+        .unwrap();
+        assert_eq!(embeddings, vec![vec![1.0, 0.0], vec![0.0, 1.0]]);
+
+        let request = server.await.unwrap();
+        assert!(request.to_ascii_lowercase().contains("authorization: bearer secret"));
+        assert!(request.contains(r#""input":["first","second"]"#));
+    }
+
+    #[tokio::test]
+    async fn test_remote_embedder_rejects_wrong_dimension() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}/embeddings", listener.local_addr().unwrap());
+        let server = tokio::spawn(serve_once(listener, "200 OK", r#"{"data": [{"embedding": [1.0]}]}"#));
+
+        let mut embedder = RemoteEmbedder::new(url, "model", 2).unwrap();
+        let result = tokio::task::spawn_blocking(move || embedder.embed_texts(vec!["text".to_string()]))
+            .await
+            .unwrap();
+        assert!(matches!(result, Err(RagError::Embedding(_))));
+        server.await.unwrap();
+    }
+}
@@ -0,0 +1,125 @@
+//! Reranking of search candidates.
+//!
+//! Vector search ranks chunks by embedding similarity, which is fast but
+//! noisy. A reranker scores each (query, chunk) pair jointly and reorders
+//! the top candidates; see `SearchConfig::rerank_top_n`.
+
+use std::sync::{Arc, Mutex};
+
+use fastembed::{RerankInitOptions, RerankerModel as FastRerankModel, TextRerank};
+
+use crate::config::RerankerModel;
+use crate::error::{RagError, Result};
+
+/// Scores documents against a query.
+///
+/// Implement this to plug in another reranking model, then pass it to
+/// [`crate::RagConfig::with_reranker`]. Calls run on a blocking thread.
+pub trait Reranker: Send {
+    /// Score `documents` against `query`, one score per document in the
+    /// same order. Higher is more relevant; scores should fall in 0.0 - 1.0
+    /// like vector similarity scores.
+    fn rerank(&mut self, query: &str, documents: &[String]) -> Result<Vec<f32>>;
+}
+
+/// Shared handle to a reranker.
+///
+/// Cheap to clone; all clones use the same reranker.
+#[derive(Clone)]
+pub struct RerankerHandle(pub(crate) Arc<Mutex<Box<dyn Reranker>>>);
+
+impl RerankerHandle {
+    /// Wrap a reranker.
+    pub fn new(reranker: Box<dyn Reranker>) -> Self {
+        Self(Arc::new(Mutex::new(reranker)))
+    }
+}
+
+impl std::fmt::Debug for RerankerHandle {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("RerankerHandle").finish_non_exhaustive()
+    }
+}
+
+/// Cross-encoder reranker running a fastembed reranking model locally.
+pub struct CrossEncoderReranker {
+    model: TextRerank,
+}
+
+impl CrossEncoderReranker {
+    /// Create a reranker with the default model (BGE reranker base).
+    pub fn new() -> Result<Self> {
+        Self::with_model(RerankerModel::default())
+    }
+
+    /// Create a reranker with a specific model.
+    pub fn with_model(model: RerankerModel) -> Result<Self> {
+        let fast_model = match model {
+            RerankerModel::BgeRerankerBase => FastRerankModel::BGERerankerBase,
+            RerankerModel::JinaRerankerV1TurboEn => FastRerankModel::JINARerankerV1TurboEn,
+        };
+
+        let options = RerankInitOptions::new(fast_model).with_show_download_progress(true);
+        let model = TextRerank::try_new(options).map_err(|e| {
+            RagError::Embedding(format!("Failed to initialize reranker model: {e}"))
+        })?;
+
+        Ok(Self { model })
+    }
+}
+
+impl Reranker for CrossEncoderReranker {
+    fn rerank(&mut self, query: &str, documents: &[String]) -> Result<Vec<f32>> {
+        if documents.is_empty() {
+            return Ok(vec![]);
+        }
+
+        let docs: Vec<&str> = documents.iter().map(String::as_str).collect();
+        let results = self
+            .model
+            .rerank(query, docs, false, None)
+            .map_err(|e| RagError::Embedding(format!("Reranking failed: {e}")))?;
+
+        // Results come back sorted by score; put them back in input order.
+        // Cross-encoders output logits, squashed to 0.0 - 1.0 here.
+        let mut scores = vec![0.0; documents.len()];
+        for result in results {
+            if let Some(score) = scores.get_mut(result.index) {
+                *score = sigmoid(result.score);
+            }
+        }
+        Ok(scores)
+    }
+}
+
+fn sigmoid(x: f32) -> f32 {
+    1.0 / (1.0 + (-x).exp())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sigmoid() {
+        assert!((sigmoid(0.0) - 0.5).abs() < 1e-6);
+        assert!(sigmoid(8.0) > 0.99);
+        assert!(sigmoid(-8.0) < 0.01);
+    }
+
+    #[test]
+    fn test_cross_encoder_reranker() {
+        let mut reranker = CrossEncoderReranker::new().unwrap();
+        let documents = vec![
+            "Pandas eat bamboo for most of the day.".to_string(),
+            "The giant panda is a bear native to China.".to_string(),
+            "Rust has no garbage collector.".to_string(),
+        ];
+
+        let scores = reranker.rerank("what is a panda?", &documents).unwrap();
+        assert_eq!(scores.len(), 3);
+        assert!(scores.iter().all(|s| (0.0..=1.0).contains(s)));
+        assert!(scores[1] > scores[2]);
+        assert!(reranker.rerank("anything", &[]).unwrap().is_empty());
+    }
+}
@@ -3,12 +3,13 @@
 //! Provides vector storage and semantic search using the unified
 //! whytcard-database module with SurrealDB's HNSW index.
 
-use crate::config::RagConfig;
+use crate::chunker::CHUNK_FIELDS;
+use crate::config::{RagConfig, SimilarityMetric};
 use crate::error::{RagError, Result};
 use crate::types::{Chunk, SearchResult};
 use whytcard_database::{
     Config as DbConfig, CreateChunk as DbCreateChunk, Database, DatabaseError,
-    DistanceMetric, StorageMode, VectorConfig,
+    DistanceMetric, RecordId, SearchMetric, StorageMode, VectorConfig, VectorSearchResult,
 };
 
 /// Vector store backed by SurrealDB.
@@ -35,6 +36,9 @@ impl VectorStore {
             vector_config: VectorConfig {
                 dimension: config.embedding_model.dimensions(),
                 distance: DistanceMetric::Cosine,
+                normalized: config.embeddings_normalized(),
+                m: config.hnsw.m,
+                ef_construction: config.hnsw.ef_construction,
             },
         };
 
@@ -45,129 +49,264 @@ impl VectorStore {
         Ok(Self { db, config })
     }
 
-    /// Insert chunks with their embeddings.
-    pub async fn insert(&mut self, chunks_with_embeddings: Vec<(Chunk, Vec<f32>)>) -> Result<()> {
+    /// Insert chunks with their embeddings, in one statement.
+    pub async fn insert(&self, chunks_with_embeddings: Vec<(Chunk, Vec<f32>)>) -> Result<()> {
         if chunks_with_embeddings.is_empty() {
             return Ok(());
         }
 
-        // First, ensure document exists for each unique document_id
-        let mut doc_ids: std::collections::HashSet<String> = std::collections::HashSet::new();
+        // First, ensure document exists for each unique document_id,
+        // carrying the metadata its chunks inherited (used by search filters)
+        let mut doc_ids: std::collections::HashMap<String, RecordId> = std::collections::HashMap::new();
         for (chunk, _) in &chunks_with_embeddings {
-            doc_ids.insert(chunk.document_id.clone());
+            if !doc_ids.contains_key(&chunk.document_id) {
+                let metadata = document_metadata(chunk.metadata.clone());
+                let doc_id = self.ensure_document(&chunk.document_id, metadata).await?;
+                doc_ids.insert(chunk.document_id.clone(), doc_id);
+            }
+        }
+
+        // Now create chunks
+        let chunks = chunks_with_embeddings
+            .into_iter()
+            .map(|(chunk, embedding)| db_chunk(doc_ids[&chunk.document_id].clone(), &chunk, embedding))
+            .collect();
+        self.db.create_chunks(chunks).await.map_err(db_err)?;
+
+        Ok(())
+    }
+
+    /// Replace every chunk of a document, in one transaction.
+    ///
+    /// Returns (chunks removed, chunks added).
+    pub async fn replace(
+        &self,
+        document_id: &str,
+        metadata: Option<serde_json::Value>,
+        chunks_with_embeddings: Vec<(Chunk, Vec<f32>)>,
+    ) -> Result<(usize, usize)> {
+        let existing = self.db.get_document_by_key(document_id).await.map_err(db_err)?;
+        if existing.is_none() && chunks_with_embeddings.is_empty() {
+            return Ok((0, 0));
         }
+        let doc_id = self.ensure_document(document_id, metadata).await?;
 
-        // Create documents if they don't exist
-        for doc_id in doc_ids {
-            if self.db.get_document_by_key(&doc_id).await.map_err(db_err)?.is_none() {
+        let chunks = chunks_with_embeddings
+            .into_iter()
+            .map(|(chunk, embedding)| db_chunk(doc_id.clone(), &chunk, embedding))
+            .collect();
+        self.db.replace_chunks(&doc_id, chunks).await.map_err(db_err)
+    }
+
+    /// Record ID of the document with `key`, creating a placeholder if missing.
+    ///
+    /// An existing document takes `metadata`, so filters match the metadata
+    /// of the version indexed last.
+    async fn ensure_document(&self, key: &str, metadata: Option<serde_json::Value>) -> Result<RecordId> {
+        let doc = match self.db.get_document_by_key(key).await.map_err(db_err)? {
+            Some(doc) if doc.metadata == metadata => doc,
+            Some(doc) => {
+                let id = doc
+                    .id
+                    .as_ref()
+                    .map(|id| id.key().to_string())
+                    .ok_or_else(|| RagError::VectorStore("Document has no ID".into()))?;
+                let update = whytcard_database::CreateDocument {
+                    key: doc.key,
+                    content: doc.content,
+                    title: doc.title,
+                    tags: doc.tags,
+                    metadata,
+                };
+                self.db.replace_document(&id, update).await.map_err(db_err)?
+            }
+            None => {
                 // Create a placeholder document
                 let doc_input = whytcard_database::CreateDocument {
-                    key: Some(doc_id.clone()),
+                    key: Some(key.to_string()),
                     content: String::new(), // Will be filled by the actual document
                     title: None,
                     tags: vec![],
-                    metadata: None,
+                    metadata,
                 };
-                self.db.create_document(doc_input).await.map_err(db_err)?;
+                self.db.create_document(doc_input).await.map_err(db_err)?
             }
-        }
-
-        // Now create chunks
-        for (chunk, embedding) in chunks_with_embeddings {
-            // Get the document record ID
-            let doc = self.db.get_document_by_key(&chunk.document_id).await.map_err(db_err)?
-                .ok_or_else(|| RagError::VectorStore(format!("Document not found: {}", chunk.document_id)))?;
-
-            let doc_id = doc.id.ok_or_else(|| RagError::VectorStore("Document has no ID".into()))?;
-
-            let db_chunk = DbCreateChunk::new(
-                doc_id,
-                chunk.text.clone(),
-                embedding,
-                chunk.index as i32,
-            )
-            .with_metadata(serde_json::json!({
-                "start_char": chunk.start_char,
-                "end_char": chunk.end_char,
-                "token_count": chunk.token_count,
-                "original_id": chunk.id,
-            }));
-
-            self.db.create_chunk(db_chunk).await.map_err(db_err)?;
-        }
+        };
 
-        Ok(())
+        doc.id.ok_or_else(|| RagError::VectorStore("Document has no ID".into()))
     }
 
     /// Search for similar chunks using vector similarity.
+    ///
+    /// Applies the configured `search.filter`, if any.
     pub async fn search(
         &self,
         query_embedding: Vec<f32>,
         limit: Option<usize>,
+    ) -> Result<Vec<SearchResult>> {
+        self.search_filtered(query_embedding, limit, None).await
+    }
+
+    /// Search for similar chunks whose document metadata matches `filter`.
+    ///
+    /// The filter is combined with the configured `search.filter` and
+    /// evaluated by SurrealDB inside the vector query. Ranks by
+    /// `search.metric`.
+    pub async fn search_filtered(
+        &self,
+        query_embedding: Vec<f32>,
+        limit: Option<usize>,
+        filter: Option<&serde_json::Value>,
+    ) -> Result<Vec<SearchResult>> {
+        self.search_ranked(query_embedding, limit, filter, self.config.search.metric)
+            .await
+    }
+
+    /// Like `search_filtered`, ranked by `metric`.
+    ///
+    /// Fails with a configuration error when the index cannot serve the
+    /// metric (dot product over unnormalized embeddings).
+    pub async fn search_ranked(
+        &self,
+        query_embedding: Vec<f32>,
+        limit: Option<usize>,
+        filter: Option<&serde_json::Value>,
+        metric: SimilarityMetric,
     ) -> Result<Vec<SearchResult>> {
         let limit = limit
             .unwrap_or(self.config.search.default_limit)
             .min(self.config.search.max_limit);
+        let filter = self.scoped_filter(filter);
 
         let db_results = self
             .db
-            .search_vectors(&query_embedding, limit, None)
+            .search_vectors_with_metric(
+                &query_embedding,
+                limit,
+                filter.as_ref(),
+                db_metric(metric),
+                self.config.search.ef_search,
+            )
             .await
-            .map_err(db_err)?;
+            .map_err(filter_err)?;
+
+        Ok(db_results
+            .into_iter()
+            .filter_map(|r| self.search_result(r))
+            .collect())
+    }
 
-        let min_score = self.config.search.min_score;
+    /// Like `search_ranked`, pairing each result with its chunk embedding.
+    pub async fn search_with_embeddings(
+        &self,
+        query_embedding: Vec<f32>,
+        limit: Option<usize>,
+        filter: Option<&serde_json::Value>,
+        metric: SimilarityMetric,
+    ) -> Result<Vec<(SearchResult, Vec<f32>)>> {
+        let limit = limit
+            .unwrap_or(self.config.search.default_limit)
+            .min(self.config.search.max_limit);
+        let filter = self.scoped_filter(filter);
+
+        let db_results = self
+            .db
+            .search_vectors_with_embeddings(
+                &query_embedding,
+                limit,
+                filter.as_ref(),
+                db_metric(metric),
+                self.config.search.ef_search,
+            )
+            .await
+            .map_err(filter_err)?;
 
-        let results: Vec<SearchResult> = db_results
+        Ok(db_results
             .into_iter()
-            .filter_map(|r| {
-                // Convert distance to similarity score (cosine distance: 0 = identical)
-                let score = 1.0 - r.distance;
-                if score < min_score {
-                    return None;
-                }
-
-                // Extract metadata
-                let metadata = r.metadata.clone();
-                let start_char = metadata
-                    .as_ref()
-                    .and_then(|m| m.get("start_char"))
-                    .and_then(|v| v.as_u64())
-                    .unwrap_or(0) as usize;
-                let end_char = metadata
-                    .as_ref()
-                    .and_then(|m| m.get("end_char"))
-                    .and_then(|v| v.as_u64())
-                    .unwrap_or(0) as usize;
-                let original_id = metadata
-                    .as_ref()
-                    .and_then(|m| m.get("original_id"))
-                    .and_then(|v| v.as_str())
-                    .unwrap_or("")
-                    .to_string();
-
-                // Get document_id from RecordId
-                let document_id = r.document_id.key().to_string();
-
-                let chunk = Chunk {
-                    id: original_id,
-                    document_id,
-                    index: r.chunk_index as usize,
-                    text: r.content,
-                    start_char,
-                    end_char,
-                    token_count: (end_char - start_char) / 4, // Rough estimate
-                    metadata,
-                };
+            .filter_map(|mut r| {
+                let embedding = r.embedding.take().unwrap_or_default();
+                self.search_result(r).map(|result| (result, embedding))
+            })
+            .collect())
+    }
 
-                Some(SearchResult {
-                    chunk,
-                    score,
-                    distance: r.distance,
-                })
+    /// Convert a vector hit, dropping it below `search.min_score`.
+    fn search_result(&self, r: VectorSearchResult) -> Option<SearchResult> {
+        // Convert distance to similarity score (cosine distance: 0 = identical,
+        // dot product distance: 1 - dot)
+        let score = 1.0 - r.distance;
+        if score < self.config.search.min_score {
+            return None;
+        }
+
+        let mut chunk = stored_chunk(&r.document_id, r.document_key, r.chunk_index, r.content, r.metadata);
+        chunk.metadata = self.project_metadata(chunk.metadata);
+        Some(SearchResult::new(chunk, score, r.distance))
+    }
+
+    /// Number of chunks a vector search with `filter` examines.
+    ///
+    /// Applies `search.filter` like `search_filtered`.
+    pub async fn count_candidates(&self, filter: Option<&serde_json::Value>) -> Result<usize> {
+        let filter = self.scoped_filter(filter);
+        self.db
+            .count_chunks_filtered(filter.as_ref())
+            .await
+            .map_err(filter_err)
+    }
+
+    /// Keyword search over chunk text with the BM25 index.
+    ///
+    /// Returns chunks with their raw BM25 score, best first. Applies
+    /// `search.filter` and `filter` like `search_filtered`, but not
+    /// `min_score`, which is a similarity threshold.
+    pub async fn search_text(
+        &self,
+        query: &str,
+        limit: usize,
+        filter: Option<&serde_json::Value>,
+    ) -> Result<Vec<(Chunk, f32)>> {
+        let filter = self.scoped_filter(filter);
+
+        let db_results = self
+            .db
+            .search_chunks_text(query, limit, filter.as_ref())
+            .await
+            .map_err(filter_err)?;
+
+        Ok(db_results
+            .into_iter()
+            .map(|r| {
+                let mut chunk = stored_chunk(&r.document_id, r.document_key, r.chunk_index, r.content, r.metadata);
+                chunk.metadata = self.project_metadata(chunk.metadata);
+                (chunk, r.score)
             })
-            .collect();
+            .collect())
+    }
+
+    /// Keep only the `search.metadata_fields` of result metadata.
+    fn project_metadata(&self, metadata: Option<serde_json::Value>) -> Option<serde_json::Value> {
+        let Some(keep) = &self.config.search.metadata_fields else {
+            return metadata;
+        };
+        match metadata {
+            Some(serde_json::Value::Object(mut fields)) => {
+                fields.retain(|key, _| keep.contains(key));
+                (!fields.is_empty()).then_some(serde_json::Value::Object(fields))
+            }
+            // Non-object metadata has no fields to keep
+            _ => None,
+        }
+    }
 
-        Ok(results)
+    /// Combine the configured filter with a per-query one.
+    fn scoped_filter(&self, filter: Option<&serde_json::Value>) -> Option<serde_json::Value> {
+        match (self.config.search.filter.as_ref(), filter) {
+            (Some(configured), Some(filter)) => {
+                Some(serde_json::json!({ "$and": [configured, filter] }))
+            }
+            (configured, filter) => configured.or(filter).cloned(),
+        }
     }
 
     /// Delete all chunks for a document.
@@ -184,6 +323,51 @@ impl VectorStore {
         Ok(())
     }
 
+    /// Delete every document whose metadata matches `filter`, with its chunks.
+    ///
+    /// Returns the number of chunks removed (0 when nothing matches).
+    pub async fn delete_by_filter(&self, filter: &serde_json::Value) -> Result<usize> {
+        self.db.delete_documents_by_filter(filter).await.map_err(filter_err)
+    }
+
+    /// Number of chunks indexed for the document with key `document_id`
+    /// (0 when there is no such document).
+    pub async fn count_chunks_for(&self, document_id: &str) -> Result<usize> {
+        let Some(doc_record_id) = self
+            .db
+            .get_document_by_key(document_id)
+            .await
+            .map_err(db_err)?
+            .and_then(|doc| doc.id)
+        else {
+            return Ok(0);
+        };
+        self.db.count_chunks_by_document(&doc_record_id).await.map_err(db_err)
+    }
+
+    /// Number of indexed documents.
+    pub async fn count_documents(&self) -> Result<usize> {
+        self.db.count_documents(None).await.map_err(db_err)
+    }
+
+    /// Bytes used by the database files, `None` for an in-memory store.
+    pub fn disk_usage(&self) -> Result<Option<u64>> {
+        if self.config.db_path.is_empty() || self.config.db_path == ":memory:" {
+            return Ok(None);
+        }
+        Ok(Some(disk_usage(std::path::Path::new(&self.config.db_path))?))
+    }
+
+    /// Rebuild the vector index from the stored embeddings.
+    pub async fn rebuild_index(&self) -> Result<usize> {
+        self.db.rebuild_vector_index().await.map_err(db_err)
+    }
+
+    /// Recreate the vector index with the configured HNSW parameters.
+    pub async fn redefine_index(&self) -> Result<usize> {
+        self.db.redefine_vector_index().await.map_err(db_err)
+    }
+
     /// Count total indexed chunks.
     pub async fn count(&self) -> Result<usize> {
         self.db.count_chunks().await.map_err(db_err)
@@ -195,11 +379,123 @@ impl VectorStore {
     }
 }
 
+/// Total size of the files under `path` (0 if it does not exist).
+fn disk_usage(path: &std::path::Path) -> std::io::Result<u64> {
+    let metadata = match std::fs::metadata(path) {
+        Ok(metadata) => metadata,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(0),
+        Err(e) => return Err(e),
+    };
+    if !metadata.is_dir() {
+        return Ok(metadata.len());
+    }
+
+    let mut total = 0;
+    for entry in std::fs::read_dir(path)? {
+        total += disk_usage(&entry?.path())?;
+    }
+    Ok(total)
+}
+
+/// Database input for a chunk.
+///
+/// The stored metadata is the chunk's (its document's metadata merged with
+/// chunk-level fields such as `heading_path`) plus the chunk's position.
+/// On a name clash the position wins, then chunk-level fields, then the
+/// document's. Non-object document metadata stays on the document only.
+fn db_chunk(doc_id: RecordId, chunk: &Chunk, embedding: Vec<f32>) -> DbCreateChunk {
+    let mut chunk_metadata = match &chunk.metadata {
+        Some(serde_json::Value::Object(fields)) => fields.clone(),
+        _ => serde_json::Map::new(),
+    };
+    for (key, value) in [
+        ("start_char", serde_json::json!(chunk.start_char)),
+        ("end_char", serde_json::json!(chunk.end_char)),
+        ("token_count", serde_json::json!(chunk.token_count)),
+        ("original_id", serde_json::json!(chunk.id)),
+    ] {
+        chunk_metadata.insert(key.to_string(), value);
+    }
+
+    DbCreateChunk::new(doc_id, chunk.text.clone(), embedding, chunk.index as i32)
+        .with_metadata(serde_json::Value::Object(chunk_metadata))
+}
+
+/// Rebuild a chunk from its stored row.
+///
+/// The chunk's `document_id` is the document key the engine indexed it
+/// under, falling back to the record ID for documents without one.
+fn stored_chunk(
+    document_id: &RecordId,
+    document_key: Option<String>,
+    chunk_index: i32,
+    content: String,
+    metadata: Option<serde_json::Value>,
+) -> Chunk {
+    let position = |key: &str| {
+        metadata
+            .as_ref()
+            .and_then(|m| m.get(key))
+            .and_then(|v| v.as_u64())
+            .unwrap_or(0) as usize
+    };
+    let start_char = position("start_char");
+    let end_char = position("end_char");
+    let original_id = metadata
+        .as_ref()
+        .and_then(|m| m.get("original_id"))
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string();
+
+    Chunk {
+        id: original_id,
+        document_id: document_key.unwrap_or_else(|| document_id.key().to_string()),
+        index: chunk_index as usize,
+        text: content,
+        start_char,
+        end_char,
+        token_count: end_char.saturating_sub(start_char) / 4, // Rough estimate
+        metadata,
+    }
+}
+
+/// Document metadata from a chunk's, without the fields describing the
+/// chunk itself (such as its heading path or symbol).
+fn document_metadata(metadata: Option<serde_json::Value>) -> Option<serde_json::Value> {
+    match metadata {
+        Some(serde_json::Value::Object(mut fields)) => {
+            for key in CHUNK_FIELDS {
+                fields.remove(key);
+            }
+            (!fields.is_empty()).then_some(serde_json::Value::Object(fields))
+        }
+        other => other,
+    }
+}
+
 /// Convert DatabaseError to RagError.
 fn db_err(e: DatabaseError) -> RagError {
     RagError::VectorStore(e.to_string())
 }
 
+/// Convert DatabaseError to RagError, reporting bad filters and metrics as
+/// configuration errors.
+fn filter_err(e: DatabaseError) -> RagError {
+    match e {
+        DatabaseError::InvalidFilter(msg) => RagError::Config(format!("Invalid metadata filter: {msg}")),
+        DatabaseError::UnsupportedMetric(msg) => RagError::Config(format!("Unsupported similarity metric: {msg}")),
+        e => db_err(e),
+    }
+}
+
+fn db_metric(metric: SimilarityMetric) -> SearchMetric {
+    match metric {
+        SimilarityMetric::Cosine => SearchMetric::Cosine,
+        SimilarityMetric::Dot => SearchMetric::Dot,
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -228,7 +524,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_insert_and_count() {
-        let mut store = create_test_store().await;
+        let store = create_test_store().await;
 
         let chunk = Chunk::new("doc1", 0, "Test content".to_string(), 0, 12);
         let embedding = vec![0.1_f32; 384]; // Match AllMiniLmL6V2 dimension
@@ -241,7 +537,7 @@ mod tests {
 
     #[tokio::test]
     async fn test_insert_and_search() {
-        let mut store = create_test_store().await;
+        let store = create_test_store().await;
 
         // Insert test chunk
         let chunk = Chunk::new("doc1", 0, "Hello world test".to_string(), 0, 16);
@@ -256,6 +552,112 @@ mod tests {
         assert_eq!(results[0].chunk.text, "Hello world test");
     }
 
+    #[tokio::test]
+    async fn test_search_filtered_by_document_metadata() {
+        let store = create_test_store().await;
+        let embedding = vec![0.5_f32; 384];
+
+        let mut chunks = Vec::new();
+        for (doc, project) in [("doc1", "alpha"), ("doc2", "beta"), ("doc3", "gamma")] {
+            let mut chunk = Chunk::new(doc, 0, format!("Content of {doc}"), 0, 14);
+            chunk.metadata = Some(serde_json::json!({ "project": project }));
+            chunks.push((chunk, embedding.clone()));
+        }
+        store.insert(chunks).await.unwrap();
+
+        let filter = serde_json::json!({ "project": "beta" });
+        let results = store.search_filtered(embedding.clone(), Some(10), Some(&filter)).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].chunk.document_id, "doc2");
+
+        let filter = serde_json::json!({ "project": { "$in": ["alpha", "gamma"] } });
+        let results = store.search_filtered(embedding.clone(), Some(10), Some(&filter)).await.unwrap();
+        let mut docs: Vec<String> = results.into_iter().map(|r| r.chunk.document_id).collect();
+        docs.sort();
+        assert_eq!(docs, vec!["doc1", "doc3"]);
+
+        // A configured filter scopes every search and combines with explicit ones
+        store.config.search.filter = Some(serde_json::json!({ "project": { "$ne": "alpha" } }));
+        assert_eq!(store.search(embedding.clone(), Some(10)).await.unwrap().len(), 2);
+        let results = store.search_filtered(embedding.clone(), Some(10), Some(&filter)).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].chunk.document_id, "doc3");
+
+        let invalid = serde_json::json!({ "project": { "$in": "alpha" } });
+        let result = store.search_filtered(embedding, Some(10), Some(&invalid)).await;
+        assert!(matches!(result, Err(RagError::Config(_))));
+    }
+
+    use crate::chunker::HEADING_PATH_KEY;
+
+    #[tokio::test]
+    async fn test_heading_path_stored_on_chunk() {
+        let store = create_test_store().await;
+        let embedding = vec![0.5_f32; 384];
+
+        let mut chunk = Chunk::new("guide", 0, "## Linux\n\nRun it.".to_string(), 0, 18);
+        chunk.metadata = Some(serde_json::json!({ "source": "guide.md", HEADING_PATH_KEY: "Install > Linux" }));
+        store.insert(vec![(chunk, embedding.clone())]).await.unwrap();
+
+        let results = store.search(embedding, Some(1)).await.unwrap();
+        let metadata = results[0].chunk.metadata.as_ref().unwrap();
+        assert_eq!(metadata[HEADING_PATH_KEY], "Install > Linux");
+
+        // The heading path describes the chunk, not its document
+        let doc = store.database().get_document_by_key("guide").await.unwrap().unwrap();
+        assert_eq!(doc.metadata, Some(serde_json::json!({ "source": "guide.md" })));
+    }
+
+    #[tokio::test]
+    async fn test_document_metadata_on_chunks() {
+        let store = create_test_store().await;
+        let embedding = vec![0.5_f32; 384];
+
+        let versioned = |source: &str| {
+            let mut chunk = Chunk::new("notes", 0, "Some notes".to_string(), 3, 13);
+            chunk.metadata = Some(serde_json::json!({ "source": source, "start_char": 99 }));
+            vec![(chunk, embedding.clone())]
+        };
+        store.insert(versioned("draft.md")).await.unwrap();
+
+        let results = store.search(embedding.clone(), Some(1)).await.unwrap();
+        let metadata = results[0].chunk.metadata.as_ref().unwrap();
+        assert_eq!(metadata["source"], "draft.md");
+        // The chunk's own position takes precedence over document fields
+        assert_eq!(metadata["start_char"], 3);
+
+        // Re-indexing with new metadata updates what filters see
+        store.replace("notes", Some(serde_json::json!({ "source": "final.md" })), versioned("final.md"))
+            .await
+            .unwrap();
+        let filter = serde_json::json!({ "source": "final.md" });
+        let results = store.search_filtered(embedding, Some(5), Some(&filter)).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].chunk.metadata.as_ref().unwrap()["source"], "final.md");
+    }
+
+    #[tokio::test]
+    async fn test_delete_by_filter() {
+        let store = create_test_store().await;
+        let embedding = vec![0.5_f32; 384];
+
+        let mut chunks = Vec::new();
+        for (doc, project, index) in [("doc1", "old", 0), ("doc1", "old", 1), ("doc2", "new", 0)] {
+            let mut chunk = Chunk::new(doc, index, format!("Chunk {index} of {doc}"), 0, 14);
+            chunk.metadata = Some(serde_json::json!({ "project": project }));
+            chunks.push((chunk, embedding.clone()));
+        }
+        store.insert(chunks).await.unwrap();
+
+        let filter = serde_json::json!({ "project": "old" });
+        assert_eq!(store.delete_by_filter(&filter).await.unwrap(), 2);
+        assert_eq!(store.count().await.unwrap(), 1);
+        assert!(store.database().get_document_by_key("doc1").await.unwrap().is_none());
+        assert!(store.database().get_document_by_key("doc2").await.unwrap().is_some());
+
+        assert_eq!(store.delete_by_filter(&filter).await.unwrap(), 0);
+    }
+
     #[tokio::test]
     async fn test_delete_by_document() {
         let mut store = create_test_store().await;
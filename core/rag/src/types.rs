@@ -35,23 +35,28 @@ impl Document {
         }
     }
 
-    /// Create a document from a file path.
-    pub fn from_path(path: impl Into<String>, content: impl Into<String>) -> Self {
-        let path_str = path.into();
-        let title = std::path::Path::new(&path_str)
-            .file_name()
-            .and_then(|n| n.to_str())
-            .map(String::from);
+    /// Read a document from a `.txt`, `.md`, `.html` or `.pdf` file.
+    ///
+    /// The format comes from the extension (`.markdown` and `.htm` work
+    /// too). HTML tags, scripts and styles are stripped, and `source`,
+    /// `filename` and `mime_type` are recorded in metadata. PDFs need the
+    /// `pdf` feature; their page offsets are kept under
+    /// [`crate::PAGE_OFFSETS_KEY`] so chunks get a [`crate::PAGE_KEY`].
+    pub fn from_path(path: &std::path::Path) -> crate::Result<Self> {
+        crate::loader::load(path)
+    }
 
-        Self {
-            id: uuid::Uuid::new_v4().to_string(),
-            path: Some(path_str),
-            title,
-            content: content.into(),
-            mime_type: None,
-            metadata: None,
-            created_at: chrono::Utc::now().timestamp(),
+    /// Set the file path, and the title to the file name if not set.
+    pub fn with_path(mut self, path: impl Into<String>) -> Self {
+        let path = path.into();
+        if self.title.is_none() {
+            self.title = std::path::Path::new(&path)
+                .file_name()
+                .and_then(|n| n.to_str())
+                .map(String::from);
         }
+        self.path = Some(path);
+        self
     }
 
     /// Set a custom document ID.
@@ -117,7 +122,9 @@ pub struct Chunk {
     pub end_char: usize,
     /// Token count estimate
     pub token_count: usize,
-    /// Optional metadata inherited from document
+    /// Metadata inherited from the document, plus chunk-level fields
+    /// (`heading_path`, `symbol`) which win over document fields of the
+    /// same name
     pub metadata: Option<serde_json::Value>,
 }
 
@@ -152,7 +159,16 @@ pub struct SearchResult {
     /// The matching chunk
     pub chunk: Chunk,
     /// Similarity score (0.0 - 1.0, higher is better)
+    ///
+    /// The rerank score when reranking is enabled, the combined score for
+    /// hybrid search, otherwise `vector_score`.
     pub score: f32,
+    /// Vector similarity score, before any reranking
+    ///
+    /// 0.0 for hybrid results found by keyword search only.
+    pub vector_score: f32,
+    /// Normalized BM25 keyword score (0.0 - 1.0), set by hybrid search only
+    pub lexical_score: f32,
     /// Distance from query vector
     pub distance: f32,
 }
@@ -163,17 +179,100 @@ impl SearchResult {
         Self {
             chunk,
             score,
+            vector_score: score,
+            lexical_score: 0.0,
             distance,
         }
     }
 }
 
+/// A document ranked by [`crate::RagEngine::search_documents`].
+#[derive(Debug, Clone)]
+pub struct DocumentResult {
+    /// Document ID
+    pub document_id: String,
+    /// Score per `SearchConfig::document_aggregation` (0.0 - 1.0)
+    pub score: f32,
+    /// Score of the best matching chunk
+    pub max_score: f32,
+    /// Mean score of the matching chunks
+    pub mean_score: f32,
+    /// Number of the document's chunks among the hits
+    pub chunk_count: usize,
+    /// Best matching chunk, as an excerpt of the document
+    pub best_chunk: Chunk,
+}
+
+/// Results of [`crate::RagEngine::search_explain`] with retrieval details.
+#[derive(Debug, Clone)]
+pub struct SearchExplain {
+    /// The results `search` would return
+    pub results: Vec<SearchResult>,
+    /// Euclidean norm of the query embedding
+    pub query_norm: f32,
+    /// Chunks the vector search examined
+    pub candidates: usize,
+    /// HNSW search breadth used (`SearchConfig::ef_search`), `None` when
+    /// the search scored every candidate exactly
+    pub ef: Option<usize>,
+}
+
+/// Chunks changed by [`crate::RagEngine::upsert`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct UpsertDelta {
+    /// Chunks indexed for the new version
+    pub added: usize,
+    /// Chunks of the previous version that were removed
+    pub removed: usize,
+}
+
+/// Chunks handled by [`crate::RagEngine::index_with_report`].
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct IndexReport {
+    /// Chunks inserted
+    pub indexed: usize,
+    /// Chunks skipped as near-duplicates (see `RagConfig::dedup_threshold`)
+    pub deduplicated: usize,
+}
+
+/// Size of an index, from [`crate::RagEngine::stats`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RagStats {
+    /// Indexed documents
+    pub documents: usize,
+    /// Indexed chunks
+    pub chunks: usize,
+    /// Embedding dimension
+    pub dimension: usize,
+    /// Embedding model name
+    pub model: String,
+    /// Approximate bytes used on disk, `None` for an in-memory index
+    pub disk_bytes: Option<u64>,
+}
+
 /// Estimate token count for text (rough approximation).
-fn estimate_tokens(text: &str) -> usize {
+pub(crate) fn estimate_tokens(text: &str) -> usize {
     // Rough estimate: ~4 characters per token for English text
     text.len() / 4
 }
 
+/// Cosine similarity between two embeddings (0.0 if either is empty or zero).
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> f32 {
+    if a.is_empty() || a.len() != b.len() {
+        return 0.0;
+    }
+
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return 0.0;
+    }
+
+    dot / (norm_a * norm_b)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -187,8 +286,8 @@ mod tests {
     }
 
     #[test]
-    fn test_document_from_path() {
-        let doc = Document::from_path("/path/to/file.md", "Content here");
+    fn test_document_with_path() {
+        let doc = Document::new("Content here").with_path("/path/to/file.md");
         assert_eq!(doc.path, Some("/path/to/file.md".to_string()));
         assert_eq!(doc.title, Some("file.md".to_string()));
     }
@@ -219,4 +318,12 @@ mod tests {
         assert_eq!(estimate_tokens("test"), 1);
         assert_eq!(estimate_tokens("hello world"), 2); // 11 chars / 4
     }
+
+    #[test]
+    fn test_cosine_similarity() {
+        assert!((cosine_similarity(&[1.0, 0.0], &[1.0, 0.0]) - 1.0).abs() < 1e-6);
+        assert!(cosine_similarity(&[1.0, 0.0], &[0.0, 1.0]).abs() < 1e-6);
+        assert_eq!(cosine_similarity(&[], &[]), 0.0);
+        assert_eq!(cosine_similarity(&[1.0], &[1.0, 2.0]), 0.0);
+    }
 }